@@ -422,6 +422,41 @@ impl<I: FixedWidthUnsignedInteger, const N: usize> Num<I, N> {
     }
 }
 
+/// The sine of each of the first 65 of 256 steps around a circle, with 14 bits
+/// of fractional precision. The rest of the circle is mirrored from these.
+static QUARTER_SINE_TABLE: [i16; 65] = [
+    0, 402, 804, 1205, 1606, 2006, 2404, 2801, 3196, 3590, 3981, 4370, 4756, 5139, 5520, 5897,
+    6270, 6639, 7005, 7366, 7723, 8076, 8423, 8765, 9102, 9434, 9760, 10080, 10394, 10702, 11003,
+    11297, 11585, 11866, 12140, 12406, 12665, 12916, 13160, 13395, 13623, 13842, 14053, 14256,
+    14449, 14635, 14811, 14978, 15137, 15286, 15426, 15557, 15679, 15791, 15893, 15986, 16069,
+    16143, 16207, 16261, 16305, 16340, 16364, 16379, 16384,
+];
+
+/// Looks up the sine of an angle given in 1/65536ths of a revolution, linearly
+/// interpolating between entries of the table. The result has 14 bits of
+/// fractional precision.
+fn sine_lookup(angle: u32) -> i32 {
+    fn table_entry(step: u32) -> i32 {
+        let step = step & 0xff;
+        let index = (step & 0x3f) as usize;
+
+        match step >> 6 {
+            0 => QUARTER_SINE_TABLE[index].into(),
+            1 => QUARTER_SINE_TABLE[64 - index].into(),
+            2 => -i32::from(QUARTER_SINE_TABLE[index]),
+            _ => -i32::from(QUARTER_SINE_TABLE[64 - index]),
+        }
+    }
+
+    let step = (angle >> 8) & 0xff;
+    let between = (angle & 0xff) as i32;
+
+    let start = table_entry(step);
+    let end = table_entry(step + 1);
+
+    start + (((end - start) * between) >> 8)
+}
+
 impl<const N: usize> Num<i32, N> {
     #[must_use]
     /// Returns the square root of a number, it is calculated a digit at a time.
@@ -454,6 +489,108 @@ impl<const N: usize> Num<i32, N> {
         }
         Self(c << (N / 2))
     }
+
+    /// Calculates the angle of the point (x, y) from the positive x axis,
+    /// where `self` is y, as a number of revolutions in the range [0, 1) to
+    /// match [`sin`](Num::sin) and [`cos`](Num::cos). Angles increase
+    /// towards the positive y axis, which is down the screen on the GBA.
+    ///
+    /// This uses a polynomial approximation which is accurate to about
+    /// 1/1600 of a revolution, plus the precision lost dividing `y` and `x`,
+    /// which is about 1/(2<sup>N</sup>) of a revolution. The angle of (0, 0)
+    /// is 0.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let y: Num<i32, 8> = num!(1.);
+    /// assert_eq!(y.atan2(num!(0.)), num!(0.25));
+    /// let y: Num<i32, 8> = num!(0.);
+    /// assert_eq!(y.atan2(num!(-1.)), num!(0.5));
+    /// ```
+    #[must_use]
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+        let zero = Self::new(0);
+        if x == zero && y == zero {
+            return zero;
+        }
+
+        let quarter = Self::new(1) / 4;
+        let half = Self::new(1) / 2;
+
+        // atan(a) for a in [0, 1], in revolutions
+        let octant = |a: Self| a / 8 + a * (Self::new(1) - a) * Self::new(89) / 2048;
+
+        // divided in 64 bits so that large coordinates don't overflow
+        let ratio = |smaller: Self, larger: Self| {
+            Self::from_raw(((i64::from(smaller.0) << N) / i64::from(larger.0)) as i32)
+        };
+
+        let (x_size, y_size) = (x.abs(), y.abs());
+        let angle = if y_size <= x_size {
+            octant(ratio(y_size, x_size))
+        } else {
+            quarter - octant(ratio(x_size, y_size))
+        };
+
+        let angle = match (x >= zero, y >= zero) {
+            (true, true) => angle,
+            (false, true) => half - angle,
+            (false, false) => half + angle,
+            (true, false) => Self::new(1) - angle,
+        };
+
+        angle.rem_euclid(Self::new(1))
+    }
+
+    /// Converts the fractional part of the number, which is an angle in
+    /// revolutions, to 1/65536ths of a revolution.
+    fn angle_to_u16_steps(self) -> u32 {
+        let fraction = (self.0 & ((1 << N) - 1)) as u32;
+        if N >= 16 {
+            fraction >> (N - 16)
+        } else {
+            fraction << (16 - N)
+        }
+    }
+
+    /// Converts a result of the sine table to this precision.
+    fn from_sine_table(value: i32) -> Self {
+        if N >= 14 {
+            Self(value << (N - 14))
+        } else {
+            Self(value >> (14 - N))
+        }
+    }
+
+    /// Calculates the sine of a number with domain of [0, 1] using a lookup
+    /// table stored in ROM, which is faster than [`sin`](Num::sin) and
+    /// accurate to about 1/4096.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 8> = num!(0.25); // pi / 2 radians
+    /// assert_eq!(n.fast_sin(), num!(1.));
+    /// let n: Num<i32, 8> = num!(-0.25); // -pi / 2 radians
+    /// assert_eq!(n.fast_sin(), num!(-1.));
+    /// ```
+    #[must_use]
+    pub fn fast_sin(self) -> Self {
+        Self::from_sine_table(sine_lookup(self.angle_to_u16_steps()))
+    }
+
+    /// Calculates the cosine of a number with domain of [0, 1] using a lookup
+    /// table stored in ROM, which is faster than [`cos`](Num::cos) and
+    /// accurate to about 1/4096.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let n: Num<i32, 8> = num!(0.); // 0 radians
+    /// assert_eq!(n.fast_cos(), num!(1.));
+    /// let n: Num<i32, 8> = num!(0.5); // pi radians
+    /// assert_eq!(n.fast_cos(), num!(-1.));
+    /// ```
+    #[must_use]
+    pub fn fast_cos(self) -> Self {
+        Self::from_sine_table(sine_lookup(self.angle_to_u16_steps() + 0x4000))
+    }
 }
 
 impl<I: FixedWidthSignedInteger, const N: usize> Num<I, N> {
@@ -862,6 +999,30 @@ impl<T: Number> Rect<T> {
             && point.y <= self.position.y + self.size.y
     }
 
+    /// Returns the point in the rectangle which is closest to the point given,
+    /// which is the point itself if the rectangle contains it.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let r = Rect::new(Vector2D::new(1,1), Vector2D::new(3,3));
+    /// assert_eq!(r.clamp_point(Vector2D::new(2,2)), Vector2D::new(2,2));
+    /// assert_eq!(r.clamp_point(Vector2D::new(0,10)), Vector2D::new(1,4));
+    /// ```
+    pub fn clamp_point(&self, point: Vector2D<T>) -> Vector2D<T> {
+        point.clamp(self.position, self.position + self.size)
+    }
+
+    /// Returns true if the other rectangle is entirely inside this one, with
+    /// the boundary counting as inside like in [`contains_point`](Rect::contains_point).
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let r = Rect::new(Vector2D::new(1,1), Vector2D::new(3,3));
+    /// assert!(r.contains_rect(Rect::new(Vector2D::new(2,2), Vector2D::new(2,2))));
+    /// assert!(!r.contains_rect(Rect::new(Vector2D::new(2,2), Vector2D::new(3,3))));
+    /// ```
+    pub fn contains_rect(&self, other: Rect<T>) -> bool {
+        self.contains_point(other.position) && self.contains_point(other.position + other.size)
+    }
+
     /// Returns true if the other rectangle touches or overlaps the first.
     /// ```
     /// # use agb_fixnum::*;
@@ -1011,6 +1172,60 @@ impl<T: Number> Vector2D<T> {
         }
     }
 
+    /// Calculates the dot product of two vectors
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let v1 = Vector2D::new(2, 3);
+    /// let v2 = Vector2D::new(4, 5);
+    ///
+    /// assert_eq!(v1.dot(v2), 2 * 4 + 3 * 5);
+    /// ```
+    pub fn dot(self, other: Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    #[must_use]
+    /// Returns the smaller of each coordinate of the two vectors
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let v1 = Vector2D::new(2, 5);
+    /// let v2 = Vector2D::new(4, 3);
+    /// assert_eq!(v1.min(v2), Vector2D::new(2, 3));
+    /// ```
+    pub fn min(self, other: Self) -> Self {
+        Self {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+        }
+    }
+
+    #[must_use]
+    /// Returns the larger of each coordinate of the two vectors
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let v1 = Vector2D::new(2, 5);
+    /// let v2 = Vector2D::new(4, 3);
+    /// assert_eq!(v1.max(v2), Vector2D::new(4, 5));
+    /// ```
+    pub fn max(self, other: Self) -> Self {
+        Self {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+        }
+    }
+
+    #[must_use]
+    /// Restricts each coordinate to be between the corresponding coordinates
+    /// of `min` and `max`
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let v = Vector2D::new(-2, 12);
+    /// assert_eq!(v.clamp(Vector2D::new(0, 0), Vector2D::new(10, 10)), Vector2D::new(0, 10));
+    /// ```
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+
     #[must_use]
     /// Swaps the x and y coordinate
     /// ```
@@ -1065,6 +1280,58 @@ mod tests {
         assert_eq!(format!("{}", d), "-0.25");
     }
 
+    #[test]
+    fn fast_trig_matches_the_standard_library() {
+        extern crate std;
+
+        for i in -1024..=1024 {
+            let n: Num<i32, 16> = Num::new(i) / 256;
+            let angle = i as f64 / 256. * 2. * core::f64::consts::PI;
+
+            let sin = n.fast_sin().to_raw() as f64 / (1 << 16) as f64;
+            let cos = n.fast_cos().to_raw() as f64 / (1 << 16) as f64;
+
+            assert!((sin - std::primitive::f64::sin(angle)).abs() < 1. / 4096.);
+            assert!((cos - std::primitive::f64::cos(angle)).abs() < 1. / 4096.);
+        }
+
+        let between: Num<i32, 16> = Num::new(1) / 300;
+        let expected = std::primitive::f64::sin(2. * core::f64::consts::PI / 300.);
+        let sin = between.fast_sin().to_raw() as f64 / (1 << 16) as f64;
+        assert!((sin - expected).abs() < 1. / 4096.);
+    }
+
+    #[test]
+    fn atan2_matches_the_standard_library() {
+        extern crate std;
+
+        for y in -20..=20 {
+            for x in -20..=20 {
+                if x == 0 && y == 0 {
+                    continue;
+                }
+
+                let angle: Num<i32, 16> = Num::<i32, 16>::new(y).atan2(Num::new(x));
+
+                let expected =
+                    std::primitive::f64::atan2(y as f64, x as f64) / (2. * core::f64::consts::PI);
+                let expected = expected.rem_euclid(1.);
+                let actual = angle.to_raw() as f64 / (1 << 16) as f64;
+
+                let difference = (actual - expected).abs();
+                let difference = difference.min(1. - difference);
+                assert!(
+                    difference < 1. / 1600.,
+                    "atan2({}, {}) was {} but should be {}",
+                    y,
+                    x,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+
     #[test]
     fn sqrt() {
         for x in 1..1024 {
@@ -0,0 +1,232 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Compression {
+    Lz77,
+    RunLength,
+}
+
+impl Compression {
+    pub(crate) fn compress(self, data: &[u8]) -> Vec<u8> {
+        let mut output = match self {
+            Compression::Lz77 => lz77_compress(data),
+            Compression::RunLength => rle_compress(data),
+        };
+
+        // the BIOS requires the compressed data to be a multiple of 4 bytes long
+        while output.len() % 4 != 0 {
+            output.push(0);
+        }
+
+        output
+    }
+}
+
+fn header(compression_type: u8, data: &[u8]) -> Vec<u8> {
+    assert!(
+        data.len() < 1 << 24,
+        "Data too large to compress for the GBA BIOS"
+    );
+
+    let header = (compression_type as u32) << 4 | (data.len() as u32) << 8;
+    header.to_le_bytes().to_vec()
+}
+
+const LZ77_MIN_LENGTH: usize = 3;
+const LZ77_MAX_LENGTH: usize = 18;
+const LZ77_MAX_DISTANCE: usize = 4096;
+// The VRAM safe variant of the decompressor writes 16 bits at a time, so it
+// can't refer back to the byte immediately before the one being written.
+const LZ77_MIN_DISTANCE: usize = 2;
+
+fn lz77_compress(data: &[u8]) -> Vec<u8> {
+    let mut output = header(1, data);
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let flag_position = output.len();
+        output.push(0);
+
+        for block in 0..8 {
+            if pos >= data.len() {
+                break;
+            }
+
+            let (length, distance) = lz77_longest_match(data, pos);
+
+            if length >= LZ77_MIN_LENGTH {
+                output[flag_position] |= 0x80 >> block;
+
+                let length_bits = (length - LZ77_MIN_LENGTH) as u8;
+                let distance_bits = distance - 1;
+                output.push(length_bits << 4 | (distance_bits >> 8) as u8);
+                output.push((distance_bits & 0xFF) as u8);
+
+                pos += length;
+            } else {
+                output.push(data[pos]);
+                pos += 1;
+            }
+        }
+    }
+
+    output
+}
+
+fn lz77_longest_match(data: &[u8], pos: usize) -> (usize, usize) {
+    let mut best = (0, 0);
+
+    let max_length = LZ77_MAX_LENGTH.min(data.len() - pos);
+    for distance in LZ77_MIN_DISTANCE..=LZ77_MAX_DISTANCE.min(pos) {
+        let length = (0..max_length)
+            .take_while(|&i| data[pos + i] == data[pos + i - distance])
+            .count();
+
+        if length > best.0 {
+            best = (length, distance);
+
+            if length == max_length {
+                break;
+            }
+        }
+    }
+
+    best
+}
+
+const RLE_MIN_RUN: usize = 3;
+const RLE_MAX_RUN: usize = 130;
+const RLE_MAX_LITERALS: usize = 128;
+
+fn rle_compress(data: &[u8]) -> Vec<u8> {
+    let mut output = header(3, data);
+
+    let mut literals_start = 0;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let run_length = data[pos..]
+            .iter()
+            .take(RLE_MAX_RUN)
+            .take_while(|&&x| x == data[pos])
+            .count();
+
+        if run_length >= RLE_MIN_RUN {
+            rle_push_literals(&mut output, &data[literals_start..pos]);
+
+            output.push(0x80 | (run_length - RLE_MIN_RUN) as u8);
+            output.push(data[pos]);
+
+            pos += run_length;
+            literals_start = pos;
+        } else {
+            pos += 1;
+        }
+    }
+
+    rle_push_literals(&mut output, &data[literals_start..]);
+
+    output
+}
+
+fn rle_push_literals(output: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(RLE_MAX_LITERALS) {
+        output.push((chunk.len() - 1) as u8);
+        output.extend_from_slice(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompressed_length(data: &[u8]) -> usize {
+        (u32::from_le_bytes([data[0], data[1], data[2], data[3]]) >> 8) as usize
+    }
+
+    fn lz77_decompress(data: &[u8]) -> Vec<u8> {
+        assert_eq!(data[0], 0x10);
+        let length = decompressed_length(data);
+
+        let mut output = Vec::new();
+        let mut pos = 4;
+
+        while output.len() < length {
+            let flags = data[pos];
+            pos += 1;
+
+            for block in 0..8 {
+                if output.len() >= length {
+                    break;
+                }
+
+                if flags & (0x80 >> block) != 0 {
+                    let length = (data[pos] >> 4) as usize + LZ77_MIN_LENGTH;
+                    let distance = ((data[pos] as usize & 0xF) << 8 | data[pos + 1] as usize) + 1;
+                    assert!(distance >= LZ77_MIN_DISTANCE);
+                    pos += 2;
+
+                    for _ in 0..length {
+                        output.push(output[output.len() - distance]);
+                    }
+                } else {
+                    output.push(data[pos]);
+                    pos += 1;
+                }
+            }
+        }
+
+        output
+    }
+
+    fn rle_decompress(data: &[u8]) -> Vec<u8> {
+        assert_eq!(data[0], 0x30);
+        let length = decompressed_length(data);
+
+        let mut output = Vec::new();
+        let mut pos = 4;
+
+        while output.len() < length {
+            let flag = data[pos] as usize;
+            pos += 1;
+
+            if flag & 0x80 != 0 {
+                let run_length = (flag & 0x7F) + RLE_MIN_RUN;
+                output.extend(std::iter::repeat_n(data[pos], run_length));
+                pos += 1;
+            } else {
+                let literal_length = flag + 1;
+                output.extend_from_slice(&data[pos..pos + literal_length]);
+                pos += literal_length;
+            }
+        }
+
+        output
+    }
+
+    fn test_data() -> Vec<u8> {
+        let mut data = vec![0; 200];
+        data.extend((0..=255).cycle().take(300));
+        data.extend([1, 2, 3, 1, 2, 3, 1, 2, 3, 7, 7, 8, 8, 8, 9]);
+        data.extend(vec![5; 20]);
+        data
+    }
+
+    #[test]
+    fn lz77_round_trips() {
+        let data = test_data();
+        let compressed = Compression::Lz77.compress(&data);
+
+        assert_eq!(compressed.len() % 4, 0);
+        assert!(compressed.len() < data.len());
+        assert_eq!(lz77_decompress(&compressed), data);
+    }
+
+    #[test]
+    fn rle_round_trips() {
+        let data = test_data();
+        let compressed = Compression::RunLength.compress(&data);
+
+        assert_eq!(compressed.len() % 4, 0);
+        assert!(compressed.len() < data.len());
+        assert_eq!(rle_decompress(&compressed), data);
+    }
+}
@@ -2,7 +2,7 @@ use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
 
-use crate::{Colour, Colours, TileSize};
+use crate::{compression::Compression, Colour, Colours, TileSize};
 
 pub(crate) fn parse(filename: &str) -> Box<dyn Config> {
     let config_toml =
@@ -30,6 +30,8 @@ pub(crate) trait Image {
     fn filename(&self) -> String;
     fn tile_size(&self) -> TileSize;
     fn colours(&self) -> Colours;
+    fn compression(&self) -> Option<Compression>;
+    fn tile_properties(&self) -> HashMap<usize, u16>;
 }
 
 #[derive(Deserialize)]
@@ -77,6 +79,8 @@ pub struct ImageV1 {
     transparent_colour: Option<String>,
     tile_size: TileSizeV1,
     colours: Option<u32>,
+    compression: Option<CompressionV1>,
+    tile_properties: Option<HashMap<String, u16>>,
 }
 
 impl Image for ImageV1 {
@@ -95,6 +99,23 @@ impl Image for ImageV1 {
             _ => panic!("colours must either not be set or 16 or 256"),
         }
     }
+
+    fn compression(&self) -> Option<Compression> {
+        self.compression.map(Into::into)
+    }
+
+    fn tile_properties(&self) -> HashMap<usize, u16> {
+        self.tile_properties
+            .iter()
+            .flatten()
+            .map(|(tile_id, &property)| {
+                let tile_id = tile_id
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Expected tile id, got {}", tile_id));
+                (tile_id, property)
+            })
+            .collect()
+    }
 }
 
 impl ImageV1 {
@@ -124,3 +145,20 @@ impl From<TileSizeV1> for TileSize {
         }
     }
 }
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum CompressionV1 {
+    #[serde(rename = "lz77")]
+    Lz77,
+    #[serde(rename = "rle")]
+    RunLength,
+}
+
+impl From<CompressionV1> for Compression {
+    fn from(item: CompressionV1) -> Self {
+        match item {
+            CompressionV1::Lz77 => Compression::Lz77,
+            CompressionV1::RunLength => Compression::RunLength,
+        }
+    }
+}
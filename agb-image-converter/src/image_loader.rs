@@ -38,4 +38,102 @@ impl Image {
     pub fn colour(&self, x: usize, y: usize) -> Colour {
         self.colour_data[x + y * self.width]
     }
+
+    /// Copies part of the image, with anything outside of this image being
+    /// transparent.
+    pub fn sub_image(&self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        let mut colour_data = Vec::with_capacity(width * height);
+
+        for j in y..y + height {
+            for i in x..x + width {
+                colour_data.push(if i < self.width && j < self.height {
+                    self.colour(i, j)
+                } else {
+                    Colour::from_rgb(0, 0, 0, 0)
+                });
+            }
+        }
+
+        Image {
+            width,
+            height,
+            colour_data,
+        }
+    }
+
+    /// The smallest rectangle containing all the non transparent pixels as
+    /// (x, y, width, height), or all zeros if every pixel is transparent.
+    pub fn non_transparent_bounds(&self) -> (usize, usize, usize, usize) {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.colour(x, y).is_transparent() {
+                    continue;
+                }
+
+                bounds = Some(match bounds {
+                    None => (x, y, x, y),
+                    Some((min_x, min_y, max_x, max_y)) => {
+                        (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                    }
+                });
+            }
+        }
+
+        match bounds {
+            Some((min_x, min_y, max_x, max_y)) => {
+                (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
+            }
+            None => (0, 0, 0, 0),
+        }
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.colour_data
+            .iter()
+            .all(|colour| colour.is_transparent())
+    }
+
+    /// A 1 bit per pixel mask of the non transparent pixels. Each row starts
+    /// on a new 32 bit word with the leftmost pixel in the least significant
+    /// bit, and any bits past the width of the image are zero.
+    pub fn collision_mask(&self) -> Vec<u32> {
+        let words_per_row = self.width.div_ceil(32);
+        let mut mask = vec![0; words_per_row * self.height];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.colour(x, y).is_transparent() {
+                    mask[y * words_per_row + x / 32] |= 1 << (x % 32);
+                }
+            }
+        }
+
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collision_mask_rows_are_word_aligned() {
+        let transparent = Colour::from_rgb(0, 0, 0, 0);
+        let solid = Colour::from_rgb(255, 255, 255, 255);
+
+        let mut colour_data = vec![transparent; 40 * 2];
+        colour_data[0] = solid;
+        colour_data[33] = solid;
+        colour_data[40 + 39] = solid;
+
+        let image = Image {
+            width: 40,
+            height: 2,
+            colour_data,
+        };
+
+        assert_eq!(image.collision_mask(), vec![1, 1 << 1, 0, 1 << 7]);
+    }
 }
@@ -1,7 +1,7 @@
 use palette16::{Palette16OptimisationResults, Palette16Optimiser};
 use palette256::Palette256;
 use proc_macro::TokenStream;
-use proc_macro2::Literal;
+use proc_macro2::{Literal, TokenTree};
 use syn::parse::Parser;
 use syn::{parse_macro_input, punctuated::Punctuated, LitStr};
 use syn::{Expr, ExprLit, Lit};
@@ -14,9 +14,11 @@ use quote::{format_ident, quote, ToTokens};
 
 mod aseprite;
 mod colour;
+mod compression;
 mod config;
 mod font_loader;
 mod image_loader;
+mod metasprite;
 mod palette16;
 mod palette256;
 mod rust_generator;
@@ -151,18 +153,37 @@ impl ToTokens for ByteString<'_> {
     }
 }
 
-#[proc_macro]
-pub fn include_aseprite_inner(input: TokenStream) -> TokenStream {
-    let parser = Punctuated::<LitStr, syn::Token![,]>::parse_separated_nonempty;
-    let parsed = match parser.parse(input) {
-        Ok(e) => e,
-        Err(e) => return e.to_compile_error().into(),
-    };
+struct AsepriteFiles {
+    crate_path: proc_macro2::TokenStream,
+    filenames: Vec<PathBuf>,
+    images: Vec<Image>,
+    durations: Vec<u16>,
+    tags: Vec<(Vec<asefile::Tag>, usize)>,
+}
 
-    let transparent_colour = Colour::from_rgb(255, 0, 255, 0);
+/// Splits off the path to agb, which agb's include macros pass as `$crate;`
+/// before the rest of the input.
+fn split_crate_path(input: TokenStream) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let mut tokens = proc_macro2::TokenStream::from(input).into_iter();
+    let crate_path = tokens
+        .by_ref()
+        .take_while(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == ';'))
+        .collect();
+
+    (crate_path, tokens.collect())
+}
+
+fn load_aseprite_files(
+    input: TokenStream,
+    require_sprite_sizes: bool,
+) -> syn::Result<AsepriteFiles> {
+    let (crate_path, input) = split_crate_path(input);
+
+    let parser = Punctuated::<LitStr, syn::Token![,]>::parse_separated_nonempty;
+    let parsed = parser.parse2(input)?;
 
-    let mut optimiser = palette16::Palette16Optimiser::new(Some(transparent_colour));
     let mut images = Vec::new();
+    let mut durations = Vec::new();
     let mut tags = Vec::new();
 
     let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
@@ -179,25 +200,135 @@ pub fn include_aseprite_inner(input: TokenStream) -> TokenStream {
         tags.push((tag, images.len()));
 
         for frame in frames {
-            let width = frame.width();
-            let height = frame.height();
+            let width = frame.image.width();
+            let height = frame.image.height();
             assert!(
-                valid_sprite_size(width, height),
+                !require_sprite_sizes || valid_sprite_size(width, height),
                 "File {} contains sprites with size {}x{} which cannot be represented on the GameBoy Advance",
                 filename.display(),
                 width,
                 height
             );
 
-            let image = Image::load_from_dyn_image(frame);
-            add_to_optimiser(&mut optimiser, &image, 8, Some(transparent_colour));
-            images.push(image);
+            images.push(Image::load_from_dyn_image(frame.image));
+            durations.push(frame.duration_ms.min(u16::MAX as u32) as u16);
         }
     }
 
+    Ok(AsepriteFiles {
+        crate_path,
+        filenames,
+        images,
+        durations,
+        tags,
+    })
+}
+
+fn sprites_code<'a>(
+    images: &'a [Image],
+    durations: &'a [u16],
+    bytes_per_pixel_pair: usize,
+    tile_data: &'a [u8],
+    collision_masks: bool,
+    constructor: impl Fn(usize, ByteString, proc_macro2::TokenStream) -> proc_macro2::TokenStream + 'a,
+) -> impl Iterator<Item = proc_macro2::TokenStream> + 'a {
+    let mut pre = 0;
+    images
+        .iter()
+        .zip(durations.iter())
+        .enumerate()
+        .map(move |(i, (f, duration))| {
+            let start: usize = pre;
+            let end: usize = pre + (f.width / 8) * (f.height / 8) * 32 * bytes_per_pixel_pair;
+            let data = ByteString(&tile_data[start..end]);
+            pre = end;
+            let width = f.width;
+            let height = f.height;
+            let size = quote! { Size::from_width_height(#width, #height) };
+
+            let (x, y, w, h) = f.non_transparent_bounds();
+            let (x, y, w, h) = (x as u8, y as u8, w as u8, h as u8);
+
+            let sprite = constructor(i, data, size);
+            let sprite = quote! {
+                #sprite.with_duration(#duration).with_hit_box(#x, #y, #w, #h)
+            };
+
+            if collision_masks {
+                let mask = f.collision_mask();
+                let (width, height) = (width as u16, height as u16);
+                quote! {
+                    unsafe {
+                        #sprite.with_collision_mask(&CollisionMask::new(#width, #height, &[#(#mask),*]))
+                    }
+                }
+            } else {
+                quote! { unsafe { #sprite } }
+            }
+        })
+}
+
+impl AsepriteFiles {
+    /// The path to agb's `align_bytes` macro.
+    fn align_bytes(&self) -> proc_macro2::TokenStream {
+        let crate_path = &self.crate_path;
+        quote!(#crate_path::align_bytes)
+    }
+
+    fn tags(&self) -> impl Iterator<Item = proc_macro2::TokenStream> + '_ {
+        self.tags.iter().flat_map(|(tag, num_images)| {
+            tag.iter().map(move |tag| {
+                let start = tag.from_frame() as usize + num_images;
+                let end = tag.to_frame() as usize + num_images;
+                let direction = tag.animation_direction() as usize;
+
+                let name = tag.name();
+                assert!(start <= end, "Tag {} has start > end", name);
+
+                quote! {
+                    (#name, Tag::new(SPRITES, #start, #end, #direction))
+                }
+            })
+        })
+    }
+
+    fn include_paths(&self) -> impl Iterator<Item = proc_macro2::TokenStream> + '_ {
+        self.filenames.iter().map(|s| {
+            let s = s.as_os_str().to_string_lossy();
+            quote! {
+                const _: &[u8] = include_bytes!(#s);
+            }
+        })
+    }
+}
+
+#[proc_macro]
+pub fn include_aseprite_inner(input: TokenStream) -> TokenStream {
+    include_aseprite(input, false)
+}
+
+#[proc_macro]
+pub fn include_aseprite_with_masks_inner(input: TokenStream) -> TokenStream {
+    include_aseprite(input, true)
+}
+
+fn include_aseprite(input: TokenStream, collision_masks: bool) -> TokenStream {
+    let files = match load_aseprite_files(input, true) {
+        Ok(files) => files,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let transparent_colour = Colour::from_rgb(255, 0, 255, 0);
+
+    let mut optimiser = palette16::Palette16Optimiser::new(Some(transparent_colour));
+    for image in &files.images {
+        add_to_optimiser(&mut optimiser, image, 8, Some(transparent_colour));
+    }
+
     let optimised_results = optimiser.optimise_palettes();
 
-    let (palette_data, tile_data, assignments) = palette_tile_data(&optimised_results, &images);
+    let (palette_data, tile_data, assignments) =
+        palette_tile_data(&optimised_results, &files.images);
 
     let palette_data = palette_data.iter().map(|colours| {
         quote! {
@@ -207,49 +338,212 @@ pub fn include_aseprite_inner(input: TokenStream) -> TokenStream {
         }
     });
 
-    let mut pre = 0;
-    let sprites = images
+    let align_bytes = files.align_bytes();
+    let sprites = sprites_code(
+        &files.images,
+        &files.durations,
+        1,
+        &tile_data,
+        collision_masks,
+        |i, data, size| {
+            let assignment = assignments[i];
+            quote! {
+                Sprite::new(
+                    &PALETTES[#assignment],
+                    #align_bytes!(u16, #data),
+                    #size
+                )
+            }
+        },
+    );
+
+    let tags = files.tags();
+    let include_paths = files.include_paths();
+
+    let module = quote! {
+        #(#include_paths)*
+
+
+        const PALETTES: &[Palette16] = &[
+            #(#palette_data),*
+        ];
+
+        pub const SPRITES: &[Sprite] = &[
+            #(#sprites),*
+        ];
+
+        const TAGS: &TagMap = &TagMap::new(
+            &[
+                #(#tags),*
+            ]
+        );
+
+    };
+
+    TokenStream::from(module)
+}
+
+#[proc_macro]
+pub fn include_aseprite_256_inner(input: TokenStream) -> TokenStream {
+    let files = match load_aseprite_files(input, true) {
+        Ok(files) => files,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let (palette_data, tile_data) = palette_256_tile_data(&files.images);
+    let align_bytes = files.align_bytes();
+
+    let sprites = sprites_code(
+        &files.images,
+        &files.durations,
+        2,
+        &tile_data,
+        false,
+        |_, data, size| {
+            quote! {
+                Sprite::new_256(
+                    #align_bytes!(u16, #data),
+                    #size
+                )
+            }
+        },
+    );
+
+    let tags = files.tags();
+    let include_paths = files.include_paths();
+
+    let module = quote! {
+        #(#include_paths)*
+
+
+        const PALETTE: &[u16] = &[
+            #(#palette_data),*
+        ];
+
+        pub const SPRITES: &[Sprite] = &[
+            #(#sprites),*
+        ];
+
+        const TAGS: &TagMap = &TagMap::new(
+            &[
+                #(#tags),*
+            ]
+        );
+
+    };
+
+    TokenStream::from(module)
+}
+
+#[proc_macro]
+pub fn include_metasprites_inner(input: TokenStream) -> TokenStream {
+    let files = match load_aseprite_files(input, false) {
+        Ok(files) => files,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let transparent_colour = Colour::from_rgb(255, 0, 255, 0);
+
+    let mut part_images = Vec::new();
+    let mut part_durations = Vec::new();
+    let mut frames = Vec::new();
+
+    for (image, &duration) in files.images.iter().zip(files.durations.iter()) {
+        let mut parts = Vec::new();
+
+        for part in metasprite::split_into_parts(image.width, image.height) {
+            let part_image = image.sub_image(part.x, part.y, part.width, part.height);
+            if part_image.is_transparent() {
+                continue;
+            }
+
+            parts.push((part_images.len(), part.x, part.y));
+            part_images.push(part_image);
+            part_durations.push(duration);
+        }
+
+        frames.push((parts, image.width, image.height, duration));
+    }
+
+    let mut optimiser = palette16::Palette16Optimiser::new(Some(transparent_colour));
+    for image in &part_images {
+        add_to_optimiser(&mut optimiser, image, 8, Some(transparent_colour));
+    }
+
+    let optimised_results = optimiser.optimise_palettes();
+
+    let (palette_data, tile_data, assignments) =
+        palette_tile_data(&optimised_results, &part_images);
+
+    let palette_data = palette_data.iter().map(|colours| {
+        quote! {
+            Palette16::new([
+                #(#colours),*
+            ])
+        }
+    });
+
+    let align_bytes = files.align_bytes();
+    let sprites = sprites_code(
+        &part_images,
+        &part_durations,
+        1,
+        &tile_data,
+        false,
+        |i, data, size| {
+            let assignment = assignments[i];
+            quote! {
+                Sprite::new(
+                    &PALETTES[#assignment],
+                    #align_bytes!(u16, #data),
+                    #size
+                )
+            }
+        },
+    );
+
+    let part_lists = frames.iter().enumerate().map(|(i, (parts, _, _, _))| {
+        let name = format_ident!("PARTS_{}", i);
+        let parts = parts.iter().map(|&(sprite, x, y)| {
+            let x = x as i32;
+            let y = y as i32;
+            quote! { MetaSpritePart::new(&SPRITES[#sprite], #x, #y) }
+        });
+
+        quote! {
+            const #name: &[MetaSpritePart] = &[
+                #(#parts),*
+            ];
+        }
+    });
+
+    let metasprites = frames
         .iter()
-        .zip(assignments.iter())
-        .map(|(f, assignment)| {
-            let start: usize = pre;
-            let end: usize = pre + (f.width / 8) * (f.height / 8) * 32;
-            let data = ByteString(&tile_data[start..end]);
-            pre = end;
-            let width = f.width;
-            let height = f.height;
+        .enumerate()
+        .map(|(i, (_, width, height, duration))| {
+            let name = format_ident!("PARTS_{}", i);
+            let width = *width as u16;
+            let height = *height as u16;
             quote! {
-                unsafe {
-                        Sprite::new(
-                        &PALETTES[#assignment],
-                        align_bytes!(u16, #data),
-                        Size::from_width_height(#width, #height)
-                    )
-                }
+                MetaSpriteData::new(#name, #width, #height).with_duration(#duration)
             }
         });
 
-    let tags = tags.iter().flat_map(|(tag, num_images)| {
+    let tags = files.tags.iter().flat_map(|(tag, num_images)| {
         tag.iter().map(move |tag| {
             let start = tag.from_frame() as usize + num_images;
             let end = tag.to_frame() as usize + num_images;
-            let direction = tag.animation_direction() as usize;
 
             let name = tag.name();
             assert!(start <= end, "Tag {} has start > end", name);
 
             quote! {
-                (#name, Tag::new(SPRITES, #start, #end, #direction))
+                (#name, #start, #end)
             }
         })
     });
 
-    let include_paths = filenames.iter().map(|s| {
-        let s = s.as_os_str().to_string_lossy();
-        quote! {
-            const _: &[u8] = include_bytes!(#s);
-        }
-    });
+    let include_paths = files.include_paths();
 
     let module = quote! {
         #(#include_paths)*
@@ -259,16 +553,19 @@ pub fn include_aseprite_inner(input: TokenStream) -> TokenStream {
             #(#palette_data),*
         ];
 
-        pub const SPRITES: &[Sprite] = &[
+        const SPRITES: &[Sprite] = &[
             #(#sprites),*
         ];
 
-        const TAGS: &TagMap = &TagMap::new(
-            &[
-                #(#tags),*
-            ]
-        );
+        #(#part_lists)*
+
+        const METASPRITES: &[MetaSpriteData] = &[
+            #(#metasprites),*
+        ];
 
+        const TAGS: &[(&str, usize, usize)] = &[
+            #(#tags),*
+        ];
     };
 
     TokenStream::from(module)
@@ -290,7 +587,7 @@ fn convert_image(
         optimisation_results,
         &image,
         &image_filename.to_string_lossy(),
-        settings.tile_size(),
+        settings,
         crate_prefix.to_owned(),
         assignment_offset,
     )
@@ -358,6 +655,61 @@ fn palette_tile_data(
     (palette_data, tile_data, assignments)
 }
 
+/// Creates a single 256 colour palette shared by all the images, where the
+/// first colour is transparent, along with the 8bpp tile data for the images.
+fn palette_256_tile_data(images: &[Image]) -> (Vec<u16>, Vec<u8>) {
+    let mut colours: Vec<Colour> = Vec::new();
+
+    for image in images {
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let colour = image.colour(x, y);
+                if !colour.is_transparent() && !colours.contains(&colour) {
+                    colours.push(colour);
+                }
+            }
+        }
+    }
+
+    assert!(
+        colours.len() < 256,
+        "Sprites can use at most 255 colours as well as transparency, but {} colours were used",
+        colours.len()
+    );
+
+    let palette_data = iter::once(0)
+        .chain(colours.iter().map(|colour| colour.to_rgb15()))
+        .chain(iter::repeat(0))
+        .take(256)
+        .collect();
+
+    let mut tile_data = Vec::new();
+
+    for image in images {
+        let tiles_x = image.width / 8;
+        let tiles_y = image.height / 8;
+
+        for y in 0..tiles_y {
+            for x in 0..tiles_x {
+                for j in 0..8 {
+                    for i in 0..8 {
+                        let colour = image.colour(x * 8 + i, y * 8 + j);
+                        let index = if colour.is_transparent() {
+                            0
+                        } else {
+                            colours.iter().position(|&c| c == colour).unwrap() + 1
+                        };
+
+                        tile_data.push(index as u8);
+                    }
+                }
+            }
+        }
+    }
+
+    (palette_data, tile_data)
+}
+
 fn collapse_to_4bpp(tile_data: &[u8]) -> Vec<u8> {
     tile_data
         .chunks(2)
@@ -435,6 +787,36 @@ fn flatten_group(expr: &Expr) -> &Expr {
     }
 }
 
+#[proc_macro]
+pub fn include_bitmap_inner(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::LitStr);
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
+    let path = Path::new(&root).join(&*input.value());
+
+    let image = Image::load_from_file(&path);
+    assert!(
+        image.width <= u16::MAX as usize && image.height <= u16::MAX as usize,
+        "Image {} is too large to include as a bitmap",
+        path.display()
+    );
+
+    let width = image.width as u16;
+    let height = image.height as u16;
+    let colours = (0..image.height)
+        .flat_map(|y| (0..image.width).map(move |x| (x, y)))
+        .map(|(x, y)| image.colour(x, y).to_rgb15());
+
+    let include_path = path.to_string_lossy();
+
+    quote!({
+        let _ = include_bytes!(#include_path);
+
+        BitmapImage::new(#width, #height, &[#(#colours),*])
+    })
+    .into()
+}
+
 #[proc_macro]
 pub fn include_font(input: TokenStream) -> TokenStream {
     let parser = Punctuated::<Expr, syn::Token![,]>::parse_separated_nonempty;
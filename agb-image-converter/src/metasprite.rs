@@ -0,0 +1,112 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Part {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Splits a length into lengths which hardware sprites can have, largest
+/// first. The final length may go past the end if it isn't a multiple of 8.
+fn split_length(mut length: usize) -> Vec<usize> {
+    let mut lengths = Vec::new();
+
+    while length > 0 {
+        let part = [64, 32, 16, 8]
+            .iter()
+            .copied()
+            .find(|&part| part <= length)
+            .unwrap_or(8);
+
+        lengths.push(part);
+        length = length.saturating_sub(part);
+    }
+
+    lengths
+}
+
+/// Covers an image of the given size with parts which are valid hardware
+/// sprite sizes.
+pub(crate) fn split_into_parts(width: usize, height: usize) -> Vec<Part> {
+    let mut parts = Vec::new();
+
+    let mut y = 0;
+    for part_height in split_length(height) {
+        let mut x = 0;
+        for part_width in split_length(width) {
+            // 64x8, 64x16, 8x64 and 16x64 aren't valid sizes, so these are
+            // split into two halves instead
+            let (count_x, count_y) = match (part_width, part_height) {
+                (64, 8 | 16) => (2, 1),
+                (8 | 16, 64) => (1, 2),
+                _ => (1, 1),
+            };
+
+            for j in 0..count_y {
+                for i in 0..count_x {
+                    let width = part_width / count_x;
+                    let height = part_height / count_y;
+
+                    parts.push(Part {
+                        x: x + i * width,
+                        y: y + j * height,
+                        width,
+                        height,
+                    });
+                }
+            }
+
+            x += part_width;
+        }
+
+        y += part_height;
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::valid_sprite_size;
+
+    #[test]
+    fn parts_cover_the_image_with_valid_sizes() {
+        for (width, height) in [(8, 8), (96, 48), (72, 64), (64, 8), (24, 80), (20, 12)] {
+            let parts = split_into_parts(width, height);
+
+            let padded_width = width.next_multiple_of(8);
+            let padded_height = height.next_multiple_of(8);
+            let mut covered = vec![0; padded_width * padded_height];
+
+            for part in &parts {
+                assert!(
+                    valid_sprite_size(part.width as u32, part.height as u32),
+                    "{:?} is not a valid size",
+                    part
+                );
+
+                for y in part.y..part.y + part.height {
+                    for x in part.x..part.x + part.width {
+                        covered[x + y * padded_width] += 1;
+                    }
+                }
+            }
+
+            assert!(covered.iter().all(|&count| count == 1));
+        }
+    }
+
+    #[test]
+    fn square_images_use_a_single_part() {
+        assert_eq!(
+            split_into_parts(32, 32),
+            [Part {
+                x: 0,
+                y: 0,
+                width: 32,
+                height: 32
+            }]
+        );
+    }
+}
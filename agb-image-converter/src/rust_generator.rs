@@ -1,5 +1,6 @@
+use crate::config;
 use crate::palette16::Palette16OptimisationResults;
-use crate::{add_image_256_to_tile_data, add_image_to_tile_data, collapse_to_4bpp, TileSize};
+use crate::{add_image_256_to_tile_data, add_image_to_tile_data, collapse_to_4bpp};
 use crate::{image_loader::Image, ByteString};
 
 use proc_macro2::TokenStream;
@@ -39,10 +40,12 @@ pub(crate) fn generate_code(
     results: &Palette16OptimisationResults,
     image: &Image,
     image_filename: &str,
-    tile_size: TileSize,
+    settings: &dyn config::Image,
     crate_prefix: String,
     assignment_offset: Option<usize>,
 ) -> TokenStream {
+    let tile_size = settings.tile_size();
+    let compression = settings.compression();
     let crate_prefix = format_ident!("{}", crate_prefix);
     let output_variable_name = format_ident!("{}", output_variable_name);
 
@@ -72,11 +75,47 @@ pub(crate) fn generate_code(
         (tile_data, vec![])
     };
 
+    let num_tiles = image.width * image.height / (8 * 8);
+    let tile_properties = settings.tile_properties();
+    if let Some(tile_id) = tile_properties
+        .keys()
+        .find(|&&tile_id| tile_id >= num_tiles)
+    {
+        panic!(
+            "Tile property given for tile {} but the image only has {} tiles",
+            tile_id, num_tiles
+        );
+    }
+
+    let tile_properties = if tile_properties.is_empty() {
+        vec![]
+    } else {
+        (0..num_tiles)
+            .map(|tile_id| tile_properties.get(&tile_id).copied().unwrap_or(0))
+            .collect()
+    };
+
+    let (tile_data, tile_data_type) = if let Some(compression) = compression {
+        (
+            compression.compress(&tile_data),
+            format_ident!("CompressedTileData"),
+        )
+    } else {
+        (tile_data, format_ident!("TileData"))
+    };
+
     let data = ByteString(&tile_data);
 
+    // compressed data is read by the BIOS a word at a time, so needs a stricter alignment
+    let alignment = if compression.is_some() {
+        quote!(u32)
+    } else {
+        quote!(u16)
+    };
+
     quote! {
         #[allow(non_upper_case_globals)]
-        pub const #output_variable_name: #crate_prefix::display::tile_data::TileData = {
+        pub const #output_variable_name: #crate_prefix::display::tile_data::#tile_data_type = {
             const _: &[u8] = include_bytes!(#image_filename);
 
             const TILE_DATA: &[u8] = {
@@ -85,7 +124,7 @@ pub(crate) fn generate_code(
                     pub bytes: Bytes,
                 }
 
-                const ALIGNED: &AlignedAs<u16, [u8]> = &AlignedAs {
+                const ALIGNED: &AlignedAs<#alignment, [u8]> = &AlignedAs {
                     _align: [],
                     bytes: *#data,
                 };
@@ -97,7 +136,12 @@ pub(crate) fn generate_code(
                 #(#assignments),*
             ];
 
-            #crate_prefix::display::tile_data::TileData::new(TILE_DATA, PALETTE_ASSIGNMENT)
+            const TILE_PROPERTIES: &[u16] = &[
+                #(#tile_properties),*
+            ];
+
+            #crate_prefix::display::tile_data::#tile_data_type::new(TILE_DATA, PALETTE_ASSIGNMENT)
+                .with_tile_properties(TILE_PROPERTIES)
         };
     }
 }
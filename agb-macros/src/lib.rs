@@ -3,7 +3,12 @@ use proc_macro::TokenStream;
 
 use proc_macro2::Span;
 use quote::{quote, ToTokens};
-use syn::{FnArg, Ident, ItemFn, Pat, ReturnType, Token, Type, Visibility};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{
+    FnArg, Ident, Item, ItemFn, Lit, LitByteStr, LitInt, MetaNameValue, Pat, ReturnType, Token,
+    Type, Visibility,
+};
 
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -54,10 +59,9 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
         _ => panic!("Expected first argument to #[agb::entry] to not be self"),
     };
 
-    assert!(
-        args.to_string() == "",
-        "Must pass no args to #[agb::entry] macro"
-    );
+    let args = parse_entry_args(args);
+    let rom_header = rom_header(&args);
+    let memory_layout = memory_layout(&args);
 
     let fn_name = hashed_ident(&f);
 
@@ -76,6 +80,12 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
     );
 
     quote!(
+        #[link_section = ".agb_rom_header"]
+        #[used]
+        static __AGB_ROM_HEADER: [u8; 188] = #rom_header;
+
+        #memory_layout
+
         #[cfg(not(test))]
         #[export_name = "main"]
         #(#attrs)*
@@ -101,6 +111,268 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
     .into()
 }
 
+/// The arguments to `#[agb::entry]`.
+#[derive(Default)]
+struct EntryArgs {
+    title: String,
+    game_code: String,
+    maker_code: String,
+    version: u8,
+    stack: Option<String>,
+    stack_size: Option<usize>,
+    iwram_heap_size: Option<usize>,
+    ewram_heap_size: Option<usize>,
+}
+
+/// Parses the `title = "..."` style arguments to `#[agb::entry]`.
+fn parse_entry_args(args: TokenStream) -> EntryArgs {
+    let args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated
+        .parse(args)
+        .expect("#[agb::entry] takes arguments like #[agb::entry(title = \"MY GAME\")]");
+
+    let mut entry_args = EntryArgs::default();
+
+    for arg in args {
+        let name = arg
+            .path
+            .get_ident()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+
+        let size = |value: &LitInt| {
+            let size: usize = value.base10_parse().unwrap_or_else(|_| {
+                panic!("the {} in #[agb::entry] must be a number of bytes", name)
+            });
+            assert!(
+                size > 0 && size.is_multiple_of(8),
+                "the {} in #[agb::entry] must be a non zero multiple of 8 bytes",
+                name
+            );
+            Some(size)
+        };
+
+        match (name.as_str(), &arg.lit) {
+            ("title", Lit::Str(value)) => entry_args.title = value.value(),
+            ("game_code", Lit::Str(value)) => entry_args.game_code = value.value(),
+            ("maker_code", Lit::Str(value)) => entry_args.maker_code = value.value(),
+            ("version", Lit::Int(value)) => {
+                entry_args.version = value
+                    .base10_parse()
+                    .expect("the version in #[agb::entry] must be between 0 and 255");
+            }
+            ("stack", Lit::Str(value)) => entry_args.stack = Some(value.value()),
+            ("stack_size", Lit::Int(value)) => entry_args.stack_size = size(value),
+            ("iwram_heap_size", Lit::Int(value)) => entry_args.iwram_heap_size = size(value),
+            ("ewram_heap_size", Lit::Int(value)) => entry_args.ewram_heap_size = size(value),
+            _ => panic!(
+                "unknown argument to #[agb::entry], expected title, game_code, maker_code and stack as strings or version, stack_size, iwram_heap_size and ewram_heap_size as numbers"
+            ),
+        }
+    }
+
+    entry_args
+}
+
+/// A call which builds the rom header from the arguments to `#[agb::entry]`.
+fn rom_header(args: &EntryArgs) -> proc_macro2::TokenStream {
+    check_header_field("title", &args.title, 0..=12);
+    check_header_field("game_code", &args.game_code, [0, 4]);
+    check_header_field("maker_code", &args.maker_code, [0, 2]);
+
+    let title = LitByteStr::new(args.title.as_bytes(), Span::call_site());
+    let game_code = LitByteStr::new(args.game_code.as_bytes(), Span::call_site());
+    let maker_code = LitByteStr::new(args.maker_code.as_bytes(), Span::call_site());
+    let version = args.version;
+
+    quote!(agb::rom_header(#title, #game_code, #maker_code, #version))
+}
+
+/// Reserves the space for the stack and heaps given a size in the arguments
+/// to `#[agb::entry]`, in the sections the linker script puts at the end of
+/// IWRAM and EWRAM.
+fn memory_layout(args: &EntryArgs) -> proc_macro2::TokenStream {
+    const MINIMUM_STACK_SIZE: usize = 1024;
+
+    let stack_section = match args.stack.as_deref() {
+        None | Some("iwram") => ".agb_stack_iwram",
+        Some("ewram") => ".agb_stack_ewram",
+        Some(other) => panic!(
+            "the stack in #[agb::entry] must be \"iwram\" or \"ewram\", not {:?}",
+            other
+        ),
+    };
+
+    let stack_size = match (args.stack_size, &args.stack) {
+        (Some(size), _) => {
+            assert!(
+                size >= MINIMUM_STACK_SIZE,
+                "the stack_size in #[agb::entry] must be at least {} bytes",
+                MINIMUM_STACK_SIZE
+            );
+            Some(size)
+        }
+        (None, Some(_)) => panic!("a stack_size is needed to choose where the stack goes"),
+        (None, None) => None,
+    };
+
+    let reservations = [
+        (stack_section, stack_size),
+        (".agb_heap_iwram", args.iwram_heap_size),
+        (".agb_heap_ewram", args.ewram_heap_size),
+    ];
+
+    reservations
+        .iter()
+        .filter_map(|&(section, size)| Some((section, size?)))
+        .map(|(section, size)| {
+            quote!(
+                #[link_section = #section]
+                #[used]
+                static mut __AGB_RESERVATION: agb::mem::Reservation<#size> =
+                    agb::mem::Reservation([0; #size]);
+            )
+        })
+        .map(|reservation| quote!(const _: () = { #reservation };))
+        .collect()
+}
+
+fn check_header_field(name: &str, value: &str, allowed_lengths: impl IntoIterator<Item = usize>) {
+    assert!(
+        value
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == ' '),
+        "the {} in #[agb::entry] can only contain capital letters, digits and spaces",
+        name
+    );
+
+    let allowed_lengths: Vec<_> = allowed_lengths.into_iter().collect();
+    assert!(
+        allowed_lengths.contains(&value.len()),
+        "the {} in #[agb::entry] must be {:?} characters long",
+        name,
+        allowed_lengths
+    );
+}
+
+#[proc_macro_attribute]
+pub fn iwram(args: TokenStream, input: TokenStream) -> TokenStream {
+    place_in_section(args, input, "iwram", true)
+}
+
+#[proc_macro_attribute]
+pub fn ewram(args: TokenStream, input: TokenStream) -> TokenStream {
+    place_in_section(args, input, "ewram", false)
+}
+
+#[proc_macro_attribute]
+pub fn overlay(args: TokenStream, input: TokenStream) -> TokenStream {
+    let index: syn::LitInt = syn::parse(args)
+        .expect("#[agb::overlay] must be given the overlay number, for example #[agb::overlay(0)]");
+    let index: usize = index
+        .base10_parse()
+        .expect("#[agb::overlay] must be given the overlay number");
+    assert!(index < 4, "there are only 4 overlays, numbered 0 to 3");
+
+    // nothing stops an item being used while a different overlay is loaded,
+    // so using anything in an overlay has to be unsafe
+    let item = input.clone();
+    match syn::parse_macro_input!(item as Item) {
+        Item::Fn(f) if f.sig.unsafety.is_some() => {}
+        Item::Static(s) if s.mutability.is_some() => {}
+        _ => panic!("#[agb::overlay] must be applied to an unsafe fn or a static mut"),
+    }
+
+    place_in_section(
+        TokenStream::new(),
+        input,
+        &format!("overlay{}", index),
+        false,
+    )
+}
+
+#[proc_macro_attribute]
+pub fn rom(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = input.clone();
+    match syn::parse_macro_input!(item as Item) {
+        Item::Static(s) if s.mutability.is_none() => {}
+        _ => panic!("#[agb::rom] must be applied to a static which isn't mutable"),
+    }
+
+    place_in_section(args, input, "rom", false)
+}
+
+fn place_in_section(
+    args: TokenStream,
+    input: TokenStream,
+    section: &str,
+    functions_in_arm: bool,
+) -> TokenStream {
+    assert!(
+        args.is_empty(),
+        "Must pass no args to #[agb::{}] macro",
+        section
+    );
+
+    let item: Item = syn::parse(input).unwrap_or_else(|_| {
+        panic!(
+            "#[agb::{}] must be applied to a function or a static",
+            section
+        )
+    });
+
+    let (ident, is_function) = match &item {
+        Item::Fn(f) => (&f.sig.ident, true),
+        Item::Static(s) => (&s.ident, false),
+        _ => panic!(
+            "#[agb::{}] must be applied to a function or a static",
+            section
+        ),
+    };
+
+    let link_section = format!(".{}.{}", section, ident);
+    let instruction_set = (is_function && functions_in_arm)
+        .then(|| quote!(#[cfg_attr(not(doc), instruction_set(arm::a32))]));
+
+    quote!(
+        #[link_section = #link_section]
+        #instruction_set
+        #item
+    )
+    .into()
+}
+
+#[proc_macro_attribute]
+pub fn arm(args: TokenStream, input: TokenStream) -> TokenStream {
+    set_instruction_set(args, input, "arm", quote!(arm::a32))
+}
+
+#[proc_macro_attribute]
+pub fn thumb(args: TokenStream, input: TokenStream) -> TokenStream {
+    set_instruction_set(args, input, "thumb", quote!(arm::t32))
+}
+
+fn set_instruction_set(
+    args: TokenStream,
+    input: TokenStream,
+    name: &str,
+    instruction_set: proc_macro2::TokenStream,
+) -> TokenStream {
+    assert!(
+        args.is_empty(),
+        "Must pass no args to #[agb::{}] macro",
+        name
+    );
+
+    let f: ItemFn = syn::parse(input)
+        .unwrap_or_else(|_| panic!("#[agb::{}] must be applied to a function", name));
+
+    quote!(
+        #[cfg_attr(not(doc), instruction_set(#instruction_set))]
+        #f
+    )
+    .into()
+}
+
 #[proc_macro]
 pub fn num(input: TokenStream) -> TokenStream {
     let f = syn::parse_macro_input!(input as syn::LitFloat);
@@ -0,0 +1,133 @@
+//! 4-bit IMA ADPCM encoding, which the mixer decodes as it plays.
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+const INDEX_TABLE: [i32; 8] = [-1, -1, -1, -1, 2, 4, 6, 8];
+
+/// The state of the decoder before decoding a sample
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct State {
+    pub predictor: i16,
+    pub step_index: u8,
+}
+
+impl State {
+    fn decode(&mut self, nibble: u8) -> i16 {
+        let step = STEP_TABLE[usize::from(self.step_index)];
+
+        let mut difference = step >> 3;
+        if nibble & 1 != 0 {
+            difference += step >> 2;
+        }
+        if nibble & 2 != 0 {
+            difference += step >> 1;
+        }
+        if nibble & 4 != 0 {
+            difference += step;
+        }
+        if nibble & 8 != 0 {
+            difference = -difference;
+        }
+
+        self.predictor = (i32::from(self.predictor) + difference)
+            .clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16;
+        self.step_index =
+            (i32::from(self.step_index) + INDEX_TABLE[usize::from(nibble & 7)]).clamp(0, 88) as u8;
+
+        self.predictor
+    }
+
+    fn encode(&mut self, sample: i16) -> u8 {
+        let step = STEP_TABLE[usize::from(self.step_index)];
+        let difference = i32::from(sample) - i32::from(self.predictor);
+
+        let mut nibble = if difference < 0 { 8 } else { 0 };
+        let mut remaining = difference.abs();
+        if remaining >= step {
+            nibble |= 4;
+            remaining -= step;
+        }
+        if remaining >= step >> 1 {
+            nibble |= 2;
+            remaining -= step >> 1;
+        }
+        if remaining >= step >> 2 {
+            nibble |= 1;
+        }
+
+        // keep track of exactly what the decoder will produce so errors don't build up
+        self.decode(nibble);
+        nibble
+    }
+}
+
+/// Encodes 16 bit samples as 4 bit ADPCM, with two samples per byte and the first in the low
+/// nibble. Also returns the decoder's state at `loop_start` so that the mixer can jump back to
+/// it.
+pub(crate) fn encode(samples: &[i16], loop_start: usize) -> (Vec<u8>, State) {
+    let mut state = State::default();
+    let mut loop_state = state;
+    let mut data = vec![0; samples.len().div_ceil(2)];
+
+    for (i, &sample) in samples.iter().enumerate() {
+        if i == loop_start {
+            loop_state = state;
+        }
+
+        data[i / 2] |= state.encode(sample) << ((i % 2) * 4);
+    }
+
+    (data, loop_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[u8], samples: usize) -> Vec<i16> {
+        let mut state = State::default();
+        (0..samples)
+            .map(|i| state.decode((data[i / 2] >> ((i % 2) * 4)) & 0xf))
+            .collect()
+    }
+
+    #[test]
+    fn encoded_samples_decode_to_about_the_same_thing() {
+        let samples: Vec<i16> = (0..1000)
+            .map(|i| ((i as f64 / 20.0).sin() * 20000.0) as i16)
+            .collect();
+
+        let (data, _) = encode(&samples, 0);
+        assert_eq!(data.len(), 500);
+
+        let decoded = decode(&data, samples.len());
+        // the decoder needs a few samples to get up to the right step size
+        for (expected, actual) in samples.iter().zip(&decoded).skip(50) {
+            assert!(
+                (i32::from(*expected) - i32::from(*actual)).abs() < 1500,
+                "{} != {}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn loop_state_is_the_state_before_the_loop_start() {
+        let samples: Vec<i16> = (0..100).map(|i| (i * 300) as i16).collect();
+        let (data, loop_state) = encode(&samples, 51);
+
+        let mut state = State::default();
+        for i in 0..51 {
+            state.decode((data[i / 2] >> ((i % 2) * 4)) & 0xf);
+        }
+
+        assert_eq!(state, loop_state);
+    }
+}
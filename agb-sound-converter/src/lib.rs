@@ -3,9 +3,16 @@
 use proc_macro::TokenStream;
 use proc_macro2::Literal;
 use quote::{quote, ToTokens};
+use std::convert::TryInto;
 use std::path::Path;
+use syn::parse::{Parse, ParseStream};
 use syn::parse_macro_input;
 
+mod adpcm;
+mod midi;
+mod resample;
+mod tracker;
+
 use quote::TokenStreamExt;
 struct ByteString<'a>(&'a [u8]);
 impl ToTokens for ByteString<'_> {
@@ -14,21 +21,188 @@ impl ToTokens for ByteString<'_> {
     }
 }
 
+struct WavInput {
+    filename: syn::LitStr,
+    loop_start: Option<usize>,
+    loop_end: Option<usize>,
+    sample_rate: Option<u32>,
+    adpcm: bool,
+}
+
+impl Parse for WavInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let filename = input.parse()?;
+        let mut loop_start = None;
+        let mut loop_end = None;
+        let mut sample_rate = None;
+        let mut adpcm = false;
+
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            let name: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+
+            match name.to_string().as_str() {
+                "loop_start" => loop_start = Some(input.parse::<syn::LitInt>()?.base10_parse()?),
+                "loop_end" => loop_end = Some(input.parse::<syn::LitInt>()?.base10_parse()?),
+                "sample_rate" => sample_rate = Some(input.parse::<syn::LitInt>()?.base10_parse()?),
+                "adpcm" => adpcm = input.parse::<syn::LitBool>()?.value,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        name,
+                        "expected `loop_start`, `loop_end`, `sample_rate` or `adpcm`",
+                    ))
+                }
+            }
+        }
+
+        Ok(Self {
+            filename,
+            loop_start,
+            loop_end,
+            sample_rate,
+            adpcm,
+        })
+    }
+}
+
 #[proc_macro]
 pub fn include_wav(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as syn::LitStr);
+    let input = parse_macro_input!(input as WavInput);
 
-    let filename = input.value();
+    let filename = input.filename.value();
 
     let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
     let path = Path::new(&root).join(&*filename);
 
     let include_path = path.to_string_lossy();
 
-    let wav_reader = hound::WavReader::open(&path)
+    let file_contents =
+        std::fs::read(&path).unwrap_or_else(|_| panic!("Failed to load file {}", include_path));
+
+    let wav_reader = hound::WavReader::new(file_contents.as_slice())
         .unwrap_or_else(|_| panic!("Failed to load file {}", include_path));
 
-    let samples: Vec<u8> = samples_from_reader(wav_reader).collect();
+    let spec = wav_reader.spec();
+    assert!(
+        spec.channels == 1 || spec.channels == 2,
+        "{} must be mono or stereo",
+        include_path
+    );
+
+    let channels = usize::from(spec.channels);
+    let frames = wav_reader.duration() as usize;
+
+    // loop points given to the macro take precedence over the ones in the file
+    let file_loop = loop_points(&file_contents);
+    let loop_points = match (input.loop_start, input.loop_end) {
+        (None, None) => file_loop,
+        (start, end) => Some((
+            start
+                .or_else(|| file_loop.map(|(start, _)| start))
+                .unwrap_or(0),
+            end.or_else(|| file_loop.map(|(_, end)| end))
+                .unwrap_or(frames),
+        )),
+    };
+
+    if let Some((start, end)) = loop_points {
+        assert!(
+            start < end && start < frames,
+            "loop start {} must be before the loop end {} and within the {} frames of {}",
+            start,
+            end,
+            frames,
+            include_path
+        );
+    }
+
+    assert!(
+        !(input.adpcm && channels == 2),
+        "{} must be mono to be compressed with ADPCM",
+        include_path
+    );
+
+    let sample_rate = input.sample_rate.unwrap_or(spec.sample_rate);
+    let (samples, loop_points) = if sample_rate == spec.sample_rate && !input.adpcm {
+        (
+            Samples::Bytes(samples_from_reader(wav_reader).collect()),
+            loop_points,
+        )
+    } else {
+        let samples = float_samples_from_reader(wav_reader);
+        if sample_rate == spec.sample_rate {
+            (Samples::Float(samples), loop_points)
+        } else {
+            let scale = |frame: usize| {
+                (frame as u64 * u64::from(sample_rate) / u64::from(spec.sample_rate)) as usize
+            };
+
+            (
+                Samples::Float(resample::resample(
+                    &samples,
+                    channels,
+                    spec.sample_rate,
+                    sample_rate,
+                )),
+                loop_points.map(|(start, end)| (scale(start), scale(end).max(scale(start) + 1))),
+            )
+        }
+    };
+
+    let quality_warning = if resample::loses_quality(spec.sample_rate, sample_rate) {
+        let note = format!(
+            "{} is being resampled from {}Hz to {}Hz, which will remove a lot of its high frequencies",
+            include_path, spec.sample_rate, sample_rate
+        );
+
+        quote! {
+            #[deprecated(note = #note)]
+            const QUALITY_WARNING: () = ();
+            let _ = QUALITY_WARNING;
+        }
+    } else {
+        quote!()
+    };
+
+    let mut samples = match samples {
+        Samples::Bytes(samples) => samples,
+        Samples::Float(samples) if input.adpcm => {
+            return include_adpcm(
+                &samples,
+                sample_rate,
+                loop_points,
+                &include_path,
+                quality_warning,
+            )
+        }
+        Samples::Float(samples) => samples
+            .into_iter()
+            .map(|sample| {
+                (sample * f64::from(i8::MAX))
+                    .round()
+                    .clamp(f64::from(i8::MIN), f64::from(i8::MAX)) as i8 as u8
+            })
+            .collect(),
+    };
+
+    let loop_start = match loop_points {
+        Some((start, end)) => {
+            // Everything after the loop is never played, so there is no need to store it
+            samples.truncate((end * channels).min(samples.len()));
+            let start = start * channels;
+
+            quote!(Some(#start))
+        }
+        None => quote!(None),
+    };
+
+    let is_stereo = spec.channels == 2;
+    let length = samples.len();
     let samples = ByteString(&samples);
 
     let result = quote! {
@@ -37,14 +211,137 @@ pub fn include_wav(input: TokenStream) -> TokenStream {
             struct AlignmentWrapper<const N: usize>([u8; N]);
 
             const _: &[u8] = include_bytes!(#include_path);
+            const DATA: &AlignmentWrapper<#length> = &AlignmentWrapper(*#samples);
 
-            &AlignmentWrapper(*#samples).0
+            #quality_warning
+
+            SoundData::new(&DATA.0, #sample_rate, #is_stereo, #loop_start)
         }
     };
 
     TokenStream::from(result)
 }
 
+enum Samples {
+    Bytes(Vec<u8>),
+    Float(Vec<f64>),
+}
+
+fn include_adpcm(
+    samples: &[f64],
+    sample_rate: u32,
+    loop_points: Option<(usize, usize)>,
+    include_path: &str,
+    quality_warning: proc_macro2::TokenStream,
+) -> TokenStream {
+    let mut samples: Vec<i16> = samples
+        .iter()
+        .map(|sample| {
+            (sample * f64::from(i16::MAX))
+                .round()
+                .clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+        })
+        .collect();
+
+    if let Some((_, end)) = loop_points {
+        samples.truncate(end);
+    }
+
+    let loop_frame = loop_points.map_or(usize::MAX, |(start, _)| start);
+    let (data, loop_state) = adpcm::encode(&samples, loop_frame);
+
+    let loop_start = match loop_points {
+        Some((start, _)) => quote!(Some(#start)),
+        None => quote!(None),
+    };
+    let adpcm::State {
+        predictor,
+        step_index,
+    } = loop_state;
+
+    let sample_count = samples.len();
+    let length = data.len();
+    let data = ByteString(&data);
+
+    let result = quote! {
+        {
+            #[repr(align(4))]
+            struct AlignmentWrapper<const N: usize>([u8; N]);
+
+            const _: &[u8] = include_bytes!(#include_path);
+            const DATA: &AlignmentWrapper<#length> = &AlignmentWrapper(*#data);
+
+            #quality_warning
+
+            SoundData::new_adpcm(&DATA.0, #sample_rate, #sample_count, #loop_start, #predictor, #step_index)
+        }
+    };
+
+    TokenStream::from(result)
+}
+
+#[proc_macro]
+pub fn include_tracker(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::LitStr);
+
+    let filename = input.value();
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
+    let path = Path::new(&root).join(&*filename);
+
+    let include_path = path.to_string_lossy();
+
+    let file_contents =
+        std::fs::read(&path).unwrap_or_else(|_| panic!("Failed to load file {}", include_path));
+
+    let module = tracker::parse(&file_contents)
+        .unwrap_or_else(|err| panic!("Failed to load file {}: {}", include_path, err));
+
+    TokenStream::from(tracker::to_tokens(&module, &include_path))
+}
+
+#[proc_macro]
+pub fn include_midi(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::LitStr);
+
+    let filename = input.value();
+
+    let root = std::env::var("CARGO_MANIFEST_DIR").expect("Failed to get cargo manifest dir");
+    let path = Path::new(&root).join(&*filename);
+
+    let include_path = path.to_string_lossy();
+
+    let file_contents =
+        std::fs::read(&path).unwrap_or_else(|_| panic!("Failed to load file {}", include_path));
+
+    let events = midi::parse(&file_contents)
+        .unwrap_or_else(|err| panic!("Failed to load file {}: {}", include_path, err));
+
+    TokenStream::from(midi::to_tokens(&events, &include_path))
+}
+
+/// Reads every sample as a value between -1 and 1
+fn float_samples_from_reader<R>(reader: hound::WavReader<R>) -> Vec<f64>
+where
+    R: std::io::Read,
+{
+    let spec = reader.spec();
+
+    match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .map(|sample| f64::from(sample.unwrap()))
+            .collect(),
+        hound::SampleFormat::Int => {
+            let scale = f64::from(1u32 << (spec.bits_per_sample - 1));
+            reader
+                .into_samples::<i32>()
+                .map(|sample| f64::from(sample.unwrap()) / scale)
+                .collect()
+        }
+    }
+}
+
 fn samples_from_reader<'a, R>(reader: hound::WavReader<R>) -> Box<dyn Iterator<Item = u8> + 'a>
 where
     R: std::io::Read + 'a,
@@ -65,3 +362,96 @@ where
         ),
     }
 }
+
+/// Finds the first loop in the `smpl` chunk of a wav file, as the start frame
+/// and the frame after the end of the loop.
+fn loop_points(wav: &[u8]) -> Option<(usize, usize)> {
+    let read_u32 = |offset: usize| -> Option<usize> {
+        let bytes = wav.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    };
+
+    // skip the RIFF header
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let chunk_id = &wav[offset..offset + 4];
+        let chunk_size = read_u32(offset + 4)?;
+        let chunk_start = offset + 8;
+
+        if chunk_id == b"smpl" {
+            let loop_count = read_u32(chunk_start + 28)?;
+            if loop_count == 0 {
+                return None;
+            }
+
+            let first_loop = chunk_start + 36;
+            let start = read_u32(first_loop + 8)?;
+            let end = read_u32(first_loop + 12)?;
+
+            return Some((start, end + 1));
+        }
+
+        // chunks are padded to an even length
+        offset = chunk_start + chunk_size + (chunk_size & 1);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend((data.len() as u32).to_le_bytes());
+        chunk.extend(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    #[test]
+    fn loop_points_come_from_the_smpl_chunk() {
+        let mut smpl = vec![0; 36];
+        smpl[28..32].copy_from_slice(&1u32.to_le_bytes());
+        for value in [0u32, 0, 100, 199, 0, 0] {
+            smpl.extend(value.to_le_bytes());
+        }
+
+        let mut wav = b"RIFF\0\0\0\0WAVE".to_vec();
+        wav.extend(chunk(b"fmt ", &[0; 16]));
+        wav.extend(chunk(b"junk", &[1, 2, 3]));
+        wav.extend(chunk(b"smpl", &smpl));
+
+        assert_eq!(loop_points(&wav), Some((100, 200)));
+    }
+
+    #[test]
+    fn wavs_without_a_smpl_chunk_have_no_loop() {
+        let mut wav = b"RIFF\0\0\0\0WAVE".to_vec();
+        wav.extend(chunk(b"fmt ", &[0; 16]));
+        wav.extend(chunk(b"data", &[0; 8]));
+
+        assert_eq!(loop_points(&wav), None);
+    }
+
+    #[test]
+    fn loop_points_can_be_given_as_arguments() {
+        let input: WavInput =
+            syn::parse_str(r#""sfx/hum.wav", loop_start = 10, loop_end = 20,"#).unwrap();
+
+        assert_eq!(input.filename.value(), "sfx/hum.wav");
+        assert_eq!(input.loop_start, Some(10));
+        assert_eq!(input.loop_end, Some(20));
+        assert_eq!(input.sample_rate, None);
+
+        let input: WavInput =
+            syn::parse_str(r#""sfx/hum.wav", sample_rate = 18157, adpcm = true"#).unwrap();
+        assert_eq!(input.sample_rate, Some(18157));
+        assert!(input.adpcm);
+
+        assert!(syn::parse_str::<WavInput>(r#""sfx/hum.wav", loop = 10"#).is_err());
+    }
+}
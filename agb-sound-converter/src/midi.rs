@@ -0,0 +1,476 @@
+//! Converts standard MIDI files into a stream of events for the DMG channels.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use std::convert::TryInto;
+
+/// Frames per second of the GBA's display.
+const FRAMES_PER_SECOND: f64 = 16_777_216.0 / 280_896.0;
+const DEFAULT_MICROSECONDS_PER_QUARTER: u32 = 500_000;
+const DRUM_CHANNEL: u8 = 9;
+
+/// Sets the hardware envelope of a channel, with the value divided by 16 giving the step time.
+const RELEASE_TIME_CONTROLLER: u8 = 72;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Channel {
+    Square1,
+    Square2,
+    Noise,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Event {
+    Wait(u16),
+    NoteOn {
+        channel: Channel,
+        frequency: u16,
+        volume: u8,
+    },
+    NoteOff(Channel),
+    Duty(Channel, u8),
+    Envelope(Channel, u8),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum MidiEvent {
+    Tempo(u32),
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        key: u8,
+    },
+    Program {
+        channel: u8,
+        program: u8,
+    },
+    Controller {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    EndOfTrack,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + length)
+            .ok_or_else(|| "unexpected end of file".to_string())?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn variable_length(&mut self) -> Result<u32, String> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | u32::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err("variable length value is too long".to_string())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+}
+
+fn parse_track(data: &[u8], events: &mut Vec<(u32, MidiEvent)>) -> Result<(), String> {
+    let mut reader = Reader { data, offset: 0 };
+    let mut tick = 0;
+    let mut running_status = None;
+
+    while !reader.is_empty() {
+        tick += reader.variable_length()?;
+
+        let byte = reader.u8()?;
+        let (status, first_data_byte) = if byte < 0x80 {
+            let status = running_status.ok_or_else(|| "data byte without a status".to_string())?;
+            (status, Some(byte))
+        } else {
+            (byte, None)
+        };
+
+        let channel = status & 0x0f;
+        let event = match status {
+            0x80..=0xef => {
+                running_status = Some(status);
+
+                let first = match first_data_byte {
+                    Some(byte) => byte,
+                    None => reader.u8()?,
+                };
+
+                match status & 0xf0 {
+                    0x80 => {
+                        reader.u8()?;
+                        Some(MidiEvent::NoteOff {
+                            channel,
+                            key: first,
+                        })
+                    }
+                    0x90 => match reader.u8()? {
+                        0 => Some(MidiEvent::NoteOff {
+                            channel,
+                            key: first,
+                        }),
+                        velocity => Some(MidiEvent::NoteOn {
+                            channel,
+                            key: first,
+                            velocity,
+                        }),
+                    },
+                    0xb0 => Some(MidiEvent::Controller {
+                        channel,
+                        controller: first,
+                        value: reader.u8()?,
+                    }),
+                    0xc0 => Some(MidiEvent::Program {
+                        channel,
+                        program: first,
+                    }),
+                    0xd0 => None,
+                    _ => {
+                        // aftertouch and pitch bends
+                        reader.u8()?;
+                        None
+                    }
+                }
+            }
+            0xf0 | 0xf7 => {
+                let length = reader.variable_length()?;
+                reader.bytes(length as usize)?;
+                None
+            }
+            0xff => {
+                let kind = reader.u8()?;
+                let length = reader.variable_length()?;
+                let data = reader.bytes(length as usize)?;
+
+                match kind {
+                    0x51 if length == 3 => Some(MidiEvent::Tempo(
+                        u32::from(data[0]) << 16 | u32::from(data[1]) << 8 | u32::from(data[2]),
+                    )),
+                    0x2f => Some(MidiEvent::EndOfTrack),
+                    _ => None,
+                }
+            }
+            _ => return Err(format!("unknown status byte {:#x}", status)),
+        };
+
+        if let Some(event) = event {
+            events.push((tick, event));
+        }
+    }
+
+    Ok(())
+}
+
+/// The value for the frequency register of a square channel to play the given key.
+fn square_frequency(key: u8) -> u16 {
+    let frequency = 440.0 * 2f64.powf((f64::from(key) - 69.0) / 12.0);
+    (2048.0 - 131_072.0 / frequency).round().clamp(0.0, 2047.0) as u16
+}
+
+/// The value for the noise channel's frequency register, with the clock shift in bits 4 to 7 and
+/// a divider of 0, so higher keys give higher pitched noise.
+fn noise_frequency(key: u8) -> u16 {
+    let shift = 13 - (key / 9).min(13);
+    u16::from(shift) << 4
+}
+
+pub(crate) fn parse(data: &[u8]) -> Result<Vec<Event>, String> {
+    let mut reader = Reader { data, offset: 0 };
+
+    if reader.bytes(4)? != b"MThd" {
+        return Err("not a MIDI file".to_string());
+    }
+
+    let header_length = reader.u32()? as usize;
+    let header = reader.bytes(header_length)?;
+    if header.len() < 6 {
+        return Err("header is too short".to_string());
+    }
+
+    let track_count = u16::from_be_bytes([header[2], header[3]]);
+    let division = u16::from_be_bytes([header[4], header[5]]);
+    if division & 0x8000 != 0 {
+        return Err("SMPTE timing isn't supported".to_string());
+    }
+
+    let mut events = vec![];
+    for _ in 0..track_count {
+        let chunk_id = reader.bytes(4)?;
+        let length = reader.u32()? as usize;
+        let chunk = reader.bytes(length)?;
+
+        if chunk_id == b"MTrk" {
+            parse_track(chunk, &mut events)?;
+        }
+    }
+
+    // a stable sort keeps the order of events within each track, and earlier tracks first
+    events.sort_by_key(|&(tick, _)| tick);
+
+    let mut channels: Vec<(u8, Channel)> = vec![(DRUM_CHANNEL, Channel::Noise)];
+    let mut playing_keys = [None; 3];
+
+    let mut output = vec![];
+    let mut microseconds_per_quarter = DEFAULT_MICROSECONDS_PER_QUARTER;
+    let mut last_tick = 0;
+    let mut time = 0.0;
+    let mut last_frame = 0;
+
+    for (tick, event) in events {
+        time += f64::from(tick - last_tick) * f64::from(microseconds_per_quarter)
+            / f64::from(division)
+            / 1_000_000.0;
+        last_tick = tick;
+
+        let frame = (time * FRAMES_PER_SECOND).round() as u32;
+        let mut wait = frame - last_frame;
+        while wait > 0 {
+            let frames = wait.min(u32::from(u16::MAX));
+            output.push(Event::Wait(frames as u16));
+            wait -= frames;
+        }
+        last_frame = frame;
+
+        let channel_for = |channels: &mut Vec<(u8, Channel)>, midi_channel: u8, allocate: bool| {
+            if let Some(&(_, channel)) = channels.iter().find(|(c, _)| *c == midi_channel) {
+                return Some(channel);
+            }
+
+            // melodic channels get the square channels in the order they first play a note
+            let next = match channels.len() {
+                1 => Channel::Square1,
+                2 => Channel::Square2,
+                _ => return None,
+            };
+
+            if allocate {
+                channels.push((midi_channel, next));
+                Some(next)
+            } else {
+                None
+            }
+        };
+
+        match event {
+            MidiEvent::Tempo(tempo) => microseconds_per_quarter = tempo,
+            MidiEvent::NoteOn {
+                channel,
+                key,
+                velocity,
+            } => {
+                if let Some(channel) = channel_for(&mut channels, channel, true) {
+                    let frequency = match channel {
+                        Channel::Noise => noise_frequency(key),
+                        _ => square_frequency(key),
+                    };
+
+                    playing_keys[channel as usize] = Some(key);
+                    output.push(Event::NoteOn {
+                        channel,
+                        frequency,
+                        volume: (velocity >> 3).max(1),
+                    });
+                }
+            }
+            MidiEvent::NoteOff { channel, key } => {
+                if let Some(channel) = channel_for(&mut channels, channel, false) {
+                    // each channel only plays one note at a time, so only the latest can stop it
+                    if playing_keys[channel as usize] == Some(key) {
+                        playing_keys[channel as usize] = None;
+                        output.push(Event::NoteOff(channel));
+                    }
+                }
+            }
+            MidiEvent::Program { channel, program } => {
+                if let Some(channel) = channel_for(&mut channels, channel, true) {
+                    if channel != Channel::Noise {
+                        output.push(Event::Duty(channel, program % 4));
+                    }
+                }
+            }
+            MidiEvent::Controller {
+                channel,
+                controller: RELEASE_TIME_CONTROLLER,
+                value,
+            } => {
+                if let Some(channel) = channel_for(&mut channels, channel, true) {
+                    output.push(Event::Envelope(channel, value >> 4));
+                }
+            }
+            MidiEvent::Controller { .. } | MidiEvent::EndOfTrack => {}
+        }
+    }
+
+    Ok(output)
+}
+
+impl ToTokens for Channel {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Channel::Square1 => quote!(PsgChannel::Square1),
+            Channel::Square2 => quote!(PsgChannel::Square2),
+            Channel::Noise => quote!(PsgChannel::Noise),
+        });
+    }
+}
+
+impl ToTokens for Event {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match *self {
+            Event::Wait(frames) => quote!(SequenceEvent::Wait(#frames)),
+            Event::NoteOn {
+                channel,
+                frequency,
+                volume,
+            } => quote!(SequenceEvent::NoteOn(#channel, #frequency, #volume)),
+            Event::NoteOff(channel) => quote!(SequenceEvent::NoteOff(#channel)),
+            Event::Duty(channel, duty) => {
+                let duty = match duty {
+                    0 => quote!(OneEighth),
+                    1 => quote!(OneQuarter),
+                    2 => quote!(Half),
+                    _ => quote!(ThreeQuarters),
+                };
+
+                quote!(SequenceEvent::Duty(#channel, DutyCycle::#duty))
+            }
+            Event::Envelope(channel, step_time) => {
+                quote!(SequenceEvent::Envelope(#channel, #step_time))
+            }
+        });
+    }
+}
+
+/// Generates the expression for the `Sequence`, which expects `Sequence`, `SequenceEvent`,
+/// `PsgChannel` and `DutyCycle` to be in scope.
+pub(crate) fn to_tokens(events: &[Event], include_path: &str) -> TokenStream {
+    quote! {
+        {
+            const _: &[u8] = include_bytes!(#include_path);
+
+            Sequence::new(&[#(#events),*])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn midi(division: u16, tracks: &[&[u8]]) -> Vec<u8> {
+        let mut data = b"MThd".to_vec();
+        data.extend(6u32.to_be_bytes());
+        data.extend(1u16.to_be_bytes());
+        data.extend((tracks.len() as u16).to_be_bytes());
+        data.extend(division.to_be_bytes());
+
+        for track in tracks {
+            data.extend(b"MTrk");
+            data.extend((track.len() as u32).to_be_bytes());
+            data.extend(*track);
+        }
+
+        data
+    }
+
+    #[test]
+    fn notes_become_timed_events() {
+        // 120bpm so each quarter note is half a second
+        let track: &[u8] = &[
+            0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, // tempo
+            0x00, 0xc0, 0x02, // program 2 for a half duty cycle
+            0x00, 0x90, 69, 127, // A-4 on
+            0x60, 69, 0, // off after a quarter note, with running status
+            0x00, 0xff, 0x2f, 0x00,
+        ];
+
+        let events = parse(&midi(96, &[track])).unwrap();
+
+        assert_eq!(
+            events,
+            [
+                Event::Duty(Channel::Square1, 2),
+                Event::NoteOn {
+                    channel: Channel::Square1,
+                    frequency: 1750,
+                    volume: 15
+                },
+                Event::Wait(30),
+                Event::NoteOff(Channel::Square1),
+            ]
+        );
+    }
+
+    #[test]
+    fn drums_play_on_the_noise_channel() {
+        let melody: &[u8] = &[0x00, 0x91, 60, 64, 0x10, 0x81, 60, 0];
+        let drums: &[u8] = &[0x00, 0x99, 38, 100, 0x00, 0xb9, 72, 0x30];
+
+        let events = parse(&midi(96, &[melody, drums])).unwrap();
+
+        assert_eq!(
+            events[0],
+            Event::NoteOn {
+                channel: Channel::Square1,
+                frequency: square_frequency(60),
+                volume: 8
+            }
+        );
+        assert_eq!(
+            events[1],
+            Event::NoteOn {
+                channel: Channel::Noise,
+                frequency: noise_frequency(38),
+                volume: 12
+            }
+        );
+        assert_eq!(events[2], Event::Envelope(Channel::Noise, 3));
+    }
+
+    #[test]
+    fn only_the_latest_note_is_stopped() {
+        let track: &[u8] = &[
+            0x00, 0x90, 60, 64, // C-4
+            0x00, 0x90, 64, 64, // E-4 replaces it
+            0x10, 0x80, 60, 0, // stopping C-4 does nothing
+            0x10, 0x80, 64, 0,
+        ];
+
+        let events = parse(&midi(96, &[track])).unwrap();
+
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, Event::NoteOff(_)))
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Sample rate conversion for wavs which aren't at the rate the mixer is running at.
+
+use std::f64::consts::PI;
+
+/// The number of zero crossings of the sinc filter on each side of a sample
+const FILTER_ZERO_CROSSINGS: f64 = 16.0;
+
+/// Resamples interleaved samples between -1 and 1 from one sample rate to another, using a
+/// windowed sinc filter. When reducing the sample rate, the filter also removes frequencies
+/// which are too high to be represented at the new rate rather than letting them alias.
+pub(crate) fn resample(samples: &[f64], channels: usize, from: u32, to: u32) -> Vec<f64> {
+    let frames = samples.len() / channels;
+    let ratio = f64::from(to) / f64::from(from);
+    let output_frames = (frames as f64 * ratio).round() as usize;
+
+    // as a fraction of the input's nyquist frequency
+    let cutoff = ratio.min(1.0);
+    let half_width = FILTER_ZERO_CROSSINGS / cutoff;
+
+    let mut output = Vec::with_capacity(output_frames * channels);
+    for frame in 0..output_frames {
+        let position = frame as f64 / ratio;
+
+        let first = (position - half_width).ceil().max(0.0) as usize;
+        let last = ((position + half_width).floor() as usize).min(frames - 1);
+
+        for channel in 0..channels {
+            let mut total = 0.0;
+            for input_frame in first..=last {
+                let offset = position - input_frame as f64;
+                total += samples[input_frame * channels + channel]
+                    * cutoff
+                    * sinc(cutoff * offset)
+                    * hann(offset / half_width);
+            }
+
+            output.push(total);
+        }
+    }
+
+    output
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn hann(x: f64) -> f64 {
+    if x.abs() >= 1.0 {
+        0.0
+    } else {
+        0.5 * (1.0 + (PI * x).cos())
+    }
+}
+
+/// Whether resampling from one rate to another will noticeably reduce the quality of a sound,
+/// because too much of the audible range will be filtered out.
+pub(crate) fn loses_quality(from: u32, to: u32) -> bool {
+    u64::from(to) * 2 < u64::from(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f64, rate: u32, frames: usize) -> Vec<f64> {
+        (0..frames)
+            .map(|i| (2.0 * PI * frequency * i as f64 / f64::from(rate)).sin() * 0.5)
+            .collect()
+    }
+
+    fn amplitude(samples: &[f64]) -> f64 {
+        // ignore the edges, where the filter doesn't have enough input
+        let middle = &samples[samples.len() / 4..samples.len() * 3 / 4];
+        middle.iter().fold(0.0, |max, sample| sample.abs().max(max))
+    }
+
+    #[test]
+    fn resampling_keeps_the_length_in_time() {
+        let samples = vec![0.0; 44100];
+        assert_eq!(resample(&samples, 1, 44100, 18157).len(), 18157);
+
+        let stereo = vec![0.0; 2 * 10512];
+        assert_eq!(resample(&stereo, 2, 10512, 32768).len(), 2 * 32768);
+    }
+
+    #[test]
+    fn low_frequencies_survive_resampling() {
+        let samples = sine(440.0, 44100, 4410);
+        let resampled = resample(&samples, 1, 44100, 18157);
+
+        let expected = sine(440.0, 18157, resampled.len());
+        for (actual, expected) in resampled.iter().zip(&expected).skip(100).take(1500) {
+            assert!(
+                (actual - expected).abs() < 0.02,
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn frequencies_above_the_new_nyquist_frequency_are_removed() {
+        let samples = sine(15000.0, 44100, 4410);
+        let resampled = resample(&samples, 1, 44100, 18157);
+
+        assert!(amplitude(&samples) > 0.45);
+        assert!(amplitude(&resampled) < 0.05);
+    }
+
+    #[test]
+    fn large_reductions_lose_quality() {
+        assert!(loses_quality(44100, 10512));
+        assert!(!loses_quality(22050, 18157));
+        assert!(!loses_quality(10512, 32768));
+    }
+}
@@ -0,0 +1,864 @@
+//! Converts MOD, XM, S3M and IT modules into the representation used by `agb::sound::tracker`.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+
+use crate::ByteString;
+
+mod it;
+mod s3m;
+
+const NO_NOTE: i16 = i16::MIN;
+
+/// 64ths of a semitone, which is what the runtime measures pitches in
+const PITCH_PER_SEMITONE: f64 = 64.0;
+
+/// MOD period 428 is treated as C-4 and plays back samples at this rate (on a PAL Amiga)
+const MOD_C4_PERIOD: f64 = 428.0;
+const MOD_C4_RATE: f64 = 8287.0;
+
+const XM_C4_RATE: f64 = 8363.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Effect {
+    None,
+    Stop,
+    Arpeggio(u8, u8),
+    Panning(i8),
+    Volume(u8),
+    VolumeSlide(i8),
+    FineVolumeSlide(i8),
+    NoteCut(u8),
+    Portamento(i16),
+    FinePortamento(i16),
+    TonePortamento(u16),
+    SampleOffset(u16),
+    SetSpeed(u8),
+    SetTempo(u8),
+    SetGlobalVolume(u8),
+    GlobalVolumeSlide(i8),
+    PositionJump(u8),
+    PatternBreak(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Slot {
+    pub pitch: i16,
+    /// One more than the index of the sample, or 0 for none
+    pub sample: u16,
+    pub volume_effect: Effect,
+    pub effect: Effect,
+}
+
+impl Default for Slot {
+    fn default() -> Self {
+        Self {
+            pitch: NO_NOTE,
+            sample: 0,
+            volume_effect: Effect::None,
+            effect: Effect::None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Sample {
+    /// Signed 8 bit samples
+    pub data: Vec<u8>,
+    pub loop_start: Option<usize>,
+    pub volume: u8,
+    /// The frequency the sample plays at for C-4
+    pub rate: u32,
+}
+
+#[derive(Debug)]
+pub(crate) struct Module {
+    pub channels: usize,
+    pub samples: Vec<Sample>,
+    /// Each pattern is stored row by row, with one slot per channel
+    pub patterns: Vec<Vec<Slot>>,
+    pub orders: Vec<u8>,
+    pub speed: u8,
+    pub tempo: u8,
+    pub restart_position: usize,
+    /// Descriptions of the effects and features used by the module which can't be played
+    pub unsupported: BTreeSet<String>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, offset: usize, length: usize) -> Result<&'a [u8], String> {
+        self.data
+            .get(offset..offset + length)
+            .ok_or_else(|| format!("unexpected end of file at offset {}", offset))
+    }
+
+    fn u8(&self, offset: usize) -> Result<u8, String> {
+        Ok(self.bytes(offset, 1)?[0])
+    }
+
+    fn u16_be(&self, offset: usize) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(
+            self.bytes(offset, 2)?.try_into().unwrap(),
+        ))
+    }
+
+    fn u16_le(&self, offset: usize) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(
+            self.bytes(offset, 2)?.try_into().unwrap(),
+        ))
+    }
+
+    fn u32_le(&self, offset: usize) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(
+            self.bytes(offset, 4)?.try_into().unwrap(),
+        ))
+    }
+}
+
+/// Parses a MOD, XM, S3M or IT module, depending on its contents
+pub(crate) fn parse(data: &[u8]) -> Result<Module, String> {
+    if data.starts_with(b"Extended Module: ") {
+        parse_xm(data)
+    } else if data.starts_with(b"IMPM") {
+        it::parse(data)
+    } else if data.get(0x2c..0x30) == Some(b"SCRM") {
+        s3m::parse(data)
+    } else {
+        parse_mod(data)
+    }
+}
+
+fn mod_channels(tag: &[u8]) -> Option<usize> {
+    match tag {
+        b"M.K." | b"M!K!" | b"FLT4" | b"4CHN" => Some(4),
+        b"OCTA" | b"CD81" | b"FLT8" => Some(8),
+        [n, b'C', b'H', b'N'] if n.is_ascii_digit() => Some(usize::from(n - b'0')),
+        [a, b, b'C', b'H'] if a.is_ascii_digit() && b.is_ascii_digit() => {
+            Some(usize::from(a - b'0') * 10 + usize::from(b - b'0'))
+        }
+        _ => None,
+    }
+}
+
+fn parse_mod(data: &[u8]) -> Result<Module, String> {
+    let reader = Reader { data };
+
+    let tag = reader.bytes(1080, 4)?;
+    let channels = mod_channels(tag).ok_or("not a MOD, XM, S3M or IT file")?;
+    let mut unsupported = BTreeSet::new();
+
+    let song_length = usize::from(reader.u8(950)?);
+    let restart_position = usize::from(reader.u8(951)?);
+    let all_orders = reader.bytes(952, 128)?;
+    let pattern_count = usize::from(*all_orders.iter().max().unwrap()) + 1;
+
+    let mut offset = 1084;
+    let mut patterns = Vec::with_capacity(pattern_count);
+    for _ in 0..pattern_count {
+        let mut pattern = Vec::with_capacity(64 * channels);
+        for _ in 0..64 * channels {
+            pattern.push(mod_slot(reader.bytes(offset, 4)?, &mut unsupported));
+            offset += 4;
+        }
+
+        patterns.push(pattern);
+    }
+
+    let mut samples = Vec::with_capacity(31);
+    for i in 0..31 {
+        let header = 20 + i * 30;
+
+        let length = usize::from(reader.u16_be(header + 22)?) * 2;
+        let finetune = ((reader.u8(header + 24)? << 4) as i8) >> 4;
+        let volume = reader.u8(header + 25)?.min(64);
+        let loop_start = usize::from(reader.u16_be(header + 26)?) * 2;
+        let loop_length = usize::from(reader.u16_be(header + 28)?) * 2;
+
+        // some files are truncated in the last sample
+        let length = length.min(data.len().saturating_sub(offset));
+        let mut sample_data = reader.bytes(offset, length)?.to_vec();
+        offset += length;
+
+        let loop_start = if loop_length > 2 && loop_start < length {
+            sample_data.truncate(loop_start + loop_length);
+            Some(loop_start)
+        } else {
+            None
+        };
+
+        samples.push(Sample {
+            data: sample_data,
+            loop_start,
+            volume,
+            rate: (MOD_C4_RATE * 2f64.powf(f64::from(finetune) / 96.0)).round() as u32,
+        });
+    }
+
+    Ok(Module {
+        channels,
+        samples,
+        patterns,
+        orders: all_orders[..song_length.min(128)].to_vec(),
+        speed: 6,
+        tempo: 125,
+        restart_position: if restart_position < song_length {
+            restart_position
+        } else {
+            0
+        },
+        unsupported,
+    })
+}
+
+/// Converts a change in period at around C-4 to a change in pitch
+fn mod_pitch_units(amount: u8) -> i16 {
+    (i32::from(amount) * 83 / 32) as i16
+}
+
+fn mod_slot(bytes: &[u8], unsupported: &mut BTreeSet<String>) -> Slot {
+    let period = (u16::from(bytes[0] & 0x0f) << 8) | u16::from(bytes[1]);
+    let sample = u16::from((bytes[0] & 0xf0) | (bytes[2] >> 4));
+    let command = bytes[2] & 0x0f;
+    let param = bytes[3];
+
+    let pitch = if period == 0 {
+        NO_NOTE
+    } else {
+        let semitones = 12.0 * (MOD_C4_PERIOD / f64::from(period)).log2();
+        (semitones * PITCH_PER_SEMITONE).round() as i16
+    };
+
+    let (volume_effect, effect) = match command {
+        // MOD files don't have a volume column, so combined effects use it for the volume slide
+        0x5 => (volume_slide(param), Effect::TonePortamento(0)),
+        0x6 => (volume_slide(param), Effect::None),
+        0x1 => (Effect::None, Effect::Portamento(mod_pitch_units(param))),
+        0x2 => (Effect::None, Effect::Portamento(-mod_pitch_units(param))),
+        0x3 => (
+            Effect::None,
+            Effect::TonePortamento(mod_pitch_units(param) as u16),
+        ),
+        0xe if param >> 4 == 0x1 => (
+            Effect::None,
+            Effect::FinePortamento(mod_pitch_units(param & 0xf)),
+        ),
+        0xe if param >> 4 == 0x2 => (
+            Effect::None,
+            Effect::FinePortamento(-mod_pitch_units(param & 0xf)),
+        ),
+        _ => (Effect::None, common_effect(command, param, unsupported)),
+    };
+
+    Slot {
+        pitch,
+        sample,
+        volume_effect,
+        effect,
+    }
+}
+
+fn volume_slide(param: u8) -> Effect {
+    let up = param >> 4;
+    let down = param & 0xf;
+
+    if up > 0 {
+        Effect::VolumeSlide(up as i8)
+    } else {
+        Effect::VolumeSlide(-(down as i8))
+    }
+}
+
+/// Effects which mean the same thing in both MOD and XM files. Any others are added to
+/// `unsupported`.
+fn common_effect(command: u8, param: u8, unsupported: &mut BTreeSet<String>) -> Effect {
+    match command {
+        0x0 if param != 0 => Effect::Arpeggio(param >> 4, param & 0xf),
+        0x8 => Effect::Panning(param.wrapping_sub(128) as i8),
+        0x9 => Effect::SampleOffset(u16::from(param) * 256),
+        0xa if param != 0 => volume_slide(param),
+        0xb => Effect::PositionJump(param),
+        0xc => Effect::Volume(param.min(64)),
+        0xd => Effect::PatternBreak((param >> 4) * 10 + (param & 0xf)),
+        0xe => match param >> 4 {
+            // the Amiga's filter doesn't affect anything else
+            0x0 => Effect::None,
+            0xa => Effect::FineVolumeSlide((param & 0xf) as i8),
+            0xb => Effect::FineVolumeSlide(-((param & 0xf) as i8)),
+            0xc => Effect::NoteCut(param & 0xf),
+            _ => {
+                unsupported.insert(format!("effect E{:X}x", param >> 4));
+                Effect::None
+            }
+        },
+        0xf if param == 0 => Effect::None,
+        0xf if param < 32 => Effect::SetSpeed(param),
+        0xf => Effect::SetTempo(param),
+        0x0 | 0xa => Effect::None,
+        _ => {
+            unsupported.insert(format!("effect {}", xm_effect_name(command)));
+            Effect::None
+        }
+    }
+}
+
+/// The character used for an effect in MOD and XM files
+fn xm_effect_name(command: u8) -> char {
+    char::from_digit(u32::from(command), 36)
+        .unwrap_or('?')
+        .to_ascii_uppercase()
+}
+
+struct XmInstrument {
+    /// The index of the first sample of this instrument in the module's samples
+    first_sample: usize,
+    sample_count: usize,
+    keymap: [u8; 96],
+}
+
+impl XmInstrument {
+    /// The sample this instrument uses for the given note, as stored in a slot
+    fn sample_for(&self, note: u8) -> u16 {
+        if self.sample_count == 0 {
+            return 0;
+        }
+
+        let index = usize::from(note.saturating_sub(1)).min(95);
+        let sample = usize::from(self.keymap[index]).min(self.sample_count - 1);
+        (self.first_sample + sample + 1) as u16
+    }
+}
+
+fn parse_xm(data: &[u8]) -> Result<Module, String> {
+    let reader = Reader { data };
+
+    let header_size = reader.u32_le(60)? as usize;
+    let song_length = usize::from(reader.u16_le(64)?);
+    let restart_position = usize::from(reader.u16_le(66)?);
+    let channels = usize::from(reader.u16_le(68)?);
+    let pattern_count = usize::from(reader.u16_le(70)?);
+    let instrument_count = usize::from(reader.u16_le(72)?);
+    let speed = reader.u16_le(76)?;
+    let tempo = reader.u16_le(78)?;
+    let orders = reader.bytes(80, song_length.min(256))?.to_vec();
+    let mut unsupported = BTreeSet::new();
+
+    // the patterns refer to instruments, so they are decoded after the instruments are read
+    let mut offset = 60 + header_size;
+    let mut raw_patterns = Vec::with_capacity(pattern_count);
+    for _ in 0..pattern_count {
+        let pattern_header_size = reader.u32_le(offset)? as usize;
+        let rows = usize::from(reader.u16_le(offset + 5)?);
+        let packed_size = usize::from(reader.u16_le(offset + 7)?);
+        offset += pattern_header_size;
+
+        raw_patterns.push((rows, reader.bytes(offset, packed_size)?));
+        offset += packed_size;
+    }
+
+    let mut samples = Vec::new();
+    let mut instruments = Vec::with_capacity(instrument_count);
+    for _ in 0..instrument_count {
+        let instrument_size = reader.u32_le(offset)? as usize;
+        let sample_count = usize::from(reader.u16_le(offset + 27)?);
+
+        let mut instrument = XmInstrument {
+            first_sample: samples.len(),
+            sample_count,
+            keymap: [0; 96],
+        };
+
+        if sample_count == 0 {
+            offset += instrument_size;
+            instruments.push(instrument);
+            continue;
+        }
+
+        let sample_header_size = reader.u32_le(offset + 29)? as usize;
+        if reader.u8(offset + 233)? & 1 != 0 {
+            unsupported.insert("instrument envelopes".to_string());
+        }
+
+        instrument
+            .keymap
+            .copy_from_slice(reader.bytes(offset + 33, 96)?);
+        offset += instrument_size;
+
+        let mut headers = Vec::with_capacity(sample_count);
+        for _ in 0..sample_count {
+            headers.push(offset);
+            offset += sample_header_size;
+        }
+
+        for header in headers {
+            let length = reader.u32_le(header)? as usize;
+            let loop_start = reader.u32_le(header + 4)? as usize;
+            let loop_length = reader.u32_le(header + 8)? as usize;
+            let volume = reader.u8(header + 12)?.min(64);
+            let finetune = reader.u8(header + 13)? as i8;
+            let sample_type = reader.u8(header + 14)?;
+            let relative_note = reader.u8(header + 16)? as i8;
+
+            let is_16_bit = sample_type & 0x10 != 0;
+            let loops = sample_type & 0x3 != 0;
+
+            let raw = reader.bytes(offset, length)?;
+            offset += length;
+
+            let (mut sample_data, bytes_per_sample) = if is_16_bit {
+                (decode_delta_16(raw), 2)
+            } else {
+                (decode_delta_8(raw), 1)
+            };
+
+            let loop_start = loop_start / bytes_per_sample;
+            let loop_length = loop_length / bytes_per_sample;
+            let loop_start = if loops && loop_length > 0 && loop_start < sample_data.len() {
+                sample_data.truncate(loop_start + loop_length);
+                Some(loop_start)
+            } else {
+                None
+            };
+
+            let semitones = f64::from(relative_note) + f64::from(finetune) / 128.0;
+            samples.push(Sample {
+                data: sample_data,
+                loop_start,
+                volume,
+                rate: (XM_C4_RATE * 2f64.powf(semitones / 12.0)).round() as u32,
+            });
+        }
+
+        instruments.push(instrument);
+    }
+
+    let patterns = raw_patterns
+        .into_iter()
+        .map(|(rows, packed)| xm_pattern(rows, packed, channels, &instruments, &mut unsupported))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Module {
+        channels,
+        samples,
+        patterns,
+        orders,
+        speed: speed.clamp(1, 31) as u8,
+        tempo: tempo.clamp(32, 255) as u8,
+        restart_position: if restart_position < song_length {
+            restart_position
+        } else {
+            0
+        },
+        unsupported,
+    })
+}
+
+fn decode_delta_8(raw: &[u8]) -> Vec<u8> {
+    let mut current = 0u8;
+    raw.iter()
+        .map(|delta| {
+            current = current.wrapping_add(*delta);
+            current
+        })
+        .collect()
+}
+
+fn decode_delta_16(raw: &[u8]) -> Vec<u8> {
+    let mut current = 0u16;
+    raw.chunks_exact(2)
+        .map(|delta| {
+            current = current.wrapping_add(u16::from_le_bytes([delta[0], delta[1]]));
+            (current >> 8) as u8
+        })
+        .collect()
+}
+
+fn xm_pattern(
+    rows: usize,
+    packed: &[u8],
+    channels: usize,
+    instruments: &[XmInstrument],
+    unsupported: &mut BTreeSet<String>,
+) -> Result<Vec<Slot>, String> {
+    let mut slots = vec![Slot::default(); rows * channels];
+    if packed.is_empty() {
+        return Ok(slots);
+    }
+
+    let reader = Reader { data: packed };
+    let mut offset = 0;
+    let mut last_instrument = vec![0u8; channels];
+
+    for (i, slot) in slots.iter_mut().enumerate() {
+        let mut fields = [0u8; 5];
+
+        let first = reader.u8(offset)?;
+        if first & 0x80 != 0 {
+            offset += 1;
+            for (bit, field) in fields.iter_mut().enumerate() {
+                if first & (1 << bit) != 0 {
+                    *field = reader.u8(offset)?;
+                    offset += 1;
+                }
+            }
+        } else {
+            fields.copy_from_slice(reader.bytes(offset, 5)?);
+            offset += 5;
+        }
+
+        let [note, instrument, volume, command, param] = fields;
+        let channel = i % channels;
+
+        if instrument != 0 {
+            last_instrument[channel] = instrument;
+        }
+
+        let sample_note = if (1..=96).contains(&note) { note } else { 49 };
+        let sample = match instruments.get(usize::from(last_instrument[channel]).wrapping_sub(1)) {
+            Some(instrument_data) if instrument != 0 || (1..=96).contains(&note) => {
+                instrument_data.sample_for(sample_note)
+            }
+            _ => 0,
+        };
+
+        let pitch = if (1..=96).contains(&note) {
+            (i16::from(note) - 49) * PITCH_PER_SEMITONE as i16
+        } else {
+            NO_NOTE
+        };
+
+        let effect = match command {
+            0x1 => Effect::Portamento(i16::from(param) * 4),
+            0x2 => Effect::Portamento(-i16::from(param) * 4),
+            0x3 => Effect::TonePortamento(u16::from(param) * 4),
+            0x5 => volume_slide(param),
+            0x6 => volume_slide(param),
+            0xe if param >> 4 == 0x1 => Effect::FinePortamento(i16::from(param & 0xf) * 4),
+            0xe if param >> 4 == 0x2 => Effect::FinePortamento(-i16::from(param & 0xf) * 4),
+            0x10 => Effect::SetGlobalVolume(param.min(64)),
+            0x11 if param != 0 => {
+                if let Effect::VolumeSlide(amount) = volume_slide(param) {
+                    Effect::GlobalVolumeSlide(amount)
+                } else {
+                    Effect::None
+                }
+            }
+            0x14 => Effect::NoteCut(param),
+            _ => common_effect(command, param, unsupported),
+        };
+
+        let volume_effect = match volume >> 4 {
+            0x1..=0x5 => Effect::Volume((volume - 0x10).min(64)),
+            0x6 => Effect::VolumeSlide(-((volume & 0xf) as i8)),
+            0x7 => Effect::VolumeSlide((volume & 0xf) as i8),
+            0x8 => Effect::FineVolumeSlide(-((volume & 0xf) as i8)),
+            0x9 => Effect::FineVolumeSlide((volume & 0xf) as i8),
+            0xc => Effect::Panning(((volume & 0xf) * 16).wrapping_sub(128) as i8),
+            0xf => Effect::TonePortamento(u16::from(volume & 0xf) * 64),
+            _ if note == 97 => Effect::Stop,
+            0x0 => Effect::None,
+            _ => {
+                unsupported.insert(format!("volume column effect {:X}x", volume >> 4));
+                Effect::None
+            }
+        };
+
+        // tone portamento with a volume slide needs both columns, so the volume slide is
+        // dropped if the volume column is already in use
+        let (volume_effect, effect) = match (command, volume_effect) {
+            (0x5, Effect::None) => (Effect::TonePortamento(0), effect),
+            (0x5, _) => (volume_effect, Effect::TonePortamento(0)),
+            _ => (volume_effect, effect),
+        };
+
+        *slot = Slot {
+            pitch,
+            sample,
+            volume_effect,
+            effect,
+        };
+    }
+
+    Ok(slots)
+}
+
+impl ToTokens for Effect {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let effect = match *self {
+            Effect::None => quote!(None),
+            Effect::Stop => quote!(Stop),
+            Effect::Arpeggio(first, second) => quote!(Arpeggio(#first, #second)),
+            Effect::Panning(panning) => quote!(Panning(#panning)),
+            Effect::Volume(volume) => quote!(Volume(#volume)),
+            Effect::VolumeSlide(amount) => quote!(VolumeSlide(#amount)),
+            Effect::FineVolumeSlide(amount) => quote!(FineVolumeSlide(#amount)),
+            Effect::NoteCut(tick) => quote!(NoteCut(#tick)),
+            Effect::Portamento(amount) => quote!(Portamento(#amount)),
+            Effect::FinePortamento(amount) => quote!(FinePortamento(#amount)),
+            Effect::TonePortamento(speed) => quote!(TonePortamento(#speed)),
+            Effect::SampleOffset(offset) => quote!(SampleOffset(#offset)),
+            Effect::SetSpeed(speed) => quote!(SetSpeed(#speed)),
+            Effect::SetTempo(tempo) => quote!(SetTempo(#tempo)),
+            Effect::SetGlobalVolume(volume) => quote!(SetGlobalVolume(#volume)),
+            Effect::GlobalVolumeSlide(amount) => quote!(GlobalVolumeSlide(#amount)),
+            Effect::PositionJump(position) => quote!(PositionJump(#position)),
+            Effect::PatternBreak(row) => quote!(PatternBreak(#row)),
+        };
+
+        tokens.extend(quote!(PatternEffect::#effect));
+    }
+}
+
+impl ToTokens for Slot {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let Slot {
+            pitch,
+            sample,
+            volume_effect,
+            effect,
+        } = self;
+
+        tokens.extend(quote!(PatternSlot::new(#pitch, #sample, #volume_effect, #effect)));
+    }
+}
+
+/// Generates the expression for the `Track`, which expects `Track`, `TrackSample`, `Pattern`,
+/// `PatternSlot`, `PatternEffect` and `SoundData` to be in scope.
+pub(crate) fn to_tokens(module: &Module, include_path: &str) -> TokenStream {
+    let sample_data_names: Vec<_> = (0..module.samples.len())
+        .map(|i| format_ident!("SAMPLE_DATA_{}", i))
+        .collect();
+
+    let sample_data = module
+        .samples
+        .iter()
+        .zip(&sample_data_names)
+        .map(|(sample, name)| {
+            let length = sample.data.len();
+            let data = ByteString(&sample.data);
+            quote!(const #name: &AlignmentWrapper<#length> = &AlignmentWrapper(*#data);)
+        });
+
+    let samples = module
+        .samples
+        .iter()
+        .zip(&sample_data_names)
+        .map(|(sample, name)| {
+            let Sample {
+                loop_start,
+                volume,
+                rate,
+                ..
+            } = sample;
+
+            let loop_start = match loop_start {
+                Some(start) => quote!(Some(#start)),
+                None => quote!(None),
+            };
+
+            quote!(TrackSample::new(SoundData::new(&#name.0, #rate, false, #loop_start), #volume, #rate))
+        });
+
+    let mut start = 0u32;
+    let patterns = module.patterns.iter().map(|pattern| {
+        let rows = (pattern.len() / module.channels) as u16;
+        let pattern_start = start;
+        start += pattern.len() as u32;
+
+        quote!(Pattern::new(#rows, #pattern_start))
+    });
+    let pattern_data = module.patterns.iter().flatten();
+
+    let orders = &module.orders;
+    let channels = module.channels;
+    let speed = module.speed;
+    let tempo = module.tempo;
+    let restart_position = module.restart_position;
+
+    let unsupported_warning = if module.unsupported.is_empty() {
+        quote!()
+    } else {
+        let unsupported: Vec<_> = module.unsupported.iter().map(String::as_str).collect();
+        let note = format!(
+            "{} uses features which aren't supported and will be ignored: {}",
+            include_path,
+            unsupported.join(", ")
+        );
+
+        quote! {
+            #[deprecated(note = #note)]
+            const UNSUPPORTED_WARNING: () = ();
+            let _ = UNSUPPORTED_WARNING;
+        }
+    };
+
+    quote! {
+        {
+            #[repr(align(4))]
+            struct AlignmentWrapper<const N: usize>([u8; N]);
+
+            const _: &[u8] = include_bytes!(#include_path);
+
+            #(#sample_data)*
+
+            const SAMPLES: &[TrackSample] = &[#(#samples),*];
+            const PATTERN_DATA: &[PatternSlot] = &[#(#pattern_data),*];
+            const PATTERNS: &[Pattern] = &[#(#patterns),*];
+            const ORDERS: &[u8] = &[#(#orders),*];
+
+            #unsupported_warning
+
+            Track::new(SAMPLES, PATTERN_DATA, PATTERNS, ORDERS, #channels, #speed, #tempo, #restart_position)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_mod() -> Vec<u8> {
+        let mut data = vec![0; 1084];
+
+        // sample 1 is 4 bytes long, with a volume of 48 and a loop over the last 2 words
+        data[20 + 22..20 + 24].copy_from_slice(&2u16.to_be_bytes());
+        data[20 + 25] = 48;
+        data[20 + 28..20 + 30].copy_from_slice(&2u16.to_be_bytes());
+
+        data[950] = 2;
+        data[952] = 0;
+        data[953] = 0;
+        data[1080..1084].copy_from_slice(b"M.K.");
+
+        let mut pattern = vec![0; 64 * 4 * 4];
+        // C-4 with sample 1 and set volume 32
+        pattern[0..4].copy_from_slice(&[0x01, 0xac, 0x1c, 0x20]);
+        // an octave higher in channel 2 with set speed 3
+        pattern[4..8].copy_from_slice(&[0x00, 0xd6, 0x0f, 0x03]);
+        // pattern break to row 12 on the next row
+        pattern[16..20].copy_from_slice(&[0x00, 0x00, 0x0d, 0x12]);
+        data.extend(pattern);
+
+        data.extend([1, 2, 3, 4]);
+        data
+    }
+
+    #[test]
+    fn parses_mod_files() {
+        let module = parse(&test_mod()).unwrap();
+
+        assert_eq!(module.channels, 4);
+        assert_eq!(module.orders, [0, 0]);
+        assert_eq!(module.patterns.len(), 1);
+        assert_eq!(module.patterns[0].len(), 64 * 4);
+        assert_eq!(module.samples.len(), 31);
+
+        let sample = &module.samples[0];
+        assert_eq!(sample.data, [1, 2, 3, 4]);
+        assert_eq!(sample.volume, 48);
+        assert_eq!(sample.loop_start, Some(0));
+        assert_eq!(sample.rate, 8287);
+
+        assert_eq!(
+            module.patterns[0][0],
+            Slot {
+                pitch: 0,
+                sample: 1,
+                volume_effect: Effect::None,
+                effect: Effect::Volume(32),
+            }
+        );
+        assert_eq!(module.patterns[0][1].pitch, 768);
+        assert_eq!(module.patterns[0][1].effect, Effect::SetSpeed(3));
+        assert_eq!(module.patterns[0][4].pitch, NO_NOTE);
+        assert_eq!(module.patterns[0][4].effect, Effect::PatternBreak(12));
+    }
+
+    fn test_xm() -> Vec<u8> {
+        let mut data = b"Extended Module: ".to_vec();
+        data.resize(60, 0);
+
+        let mut header = vec![0; 276];
+        header[0..4].copy_from_slice(&276u32.to_le_bytes());
+        header[4..6].copy_from_slice(&1u16.to_le_bytes()); // song length
+        header[8..10].copy_from_slice(&2u16.to_le_bytes()); // channels
+        header[10..12].copy_from_slice(&1u16.to_le_bytes()); // patterns
+        header[12..14].copy_from_slice(&1u16.to_le_bytes()); // instruments
+        header[16..18].copy_from_slice(&4u16.to_le_bytes()); // speed
+        header[18..20].copy_from_slice(&150u16.to_le_bytes()); // tempo
+        data.extend(header);
+
+        let packed = [
+            // C-5, instrument 1, volume 0x30
+            0x87, 61, 1, 0x30, //
+            // empty
+            0x80, //
+            // key off
+            0x81, 97, //
+            // portamento up by 2
+            0x98, 0x01, 0x02,
+        ];
+        data.extend(9u32.to_le_bytes());
+        data.push(0);
+        data.extend(2u16.to_le_bytes());
+        data.extend((packed.len() as u16).to_le_bytes());
+        data.extend(packed);
+
+        let mut instrument = vec![0; 263];
+        instrument[0..4].copy_from_slice(&263u32.to_le_bytes());
+        instrument[27..29].copy_from_slice(&1u16.to_le_bytes());
+        instrument[29..33].copy_from_slice(&40u32.to_le_bytes());
+        data.extend(instrument);
+
+        let mut sample = vec![0; 40];
+        sample[0..4].copy_from_slice(&4u32.to_le_bytes());
+        sample[4..8].copy_from_slice(&2u32.to_le_bytes());
+        sample[8..12].copy_from_slice(&2u32.to_le_bytes());
+        sample[12] = 64;
+        sample[14] = 1;
+        sample[16] = 12;
+        data.extend(sample);
+        data.extend([1, 1, 1, 0xfd]);
+
+        data
+    }
+
+    #[test]
+    fn parses_xm_files() {
+        let module = parse(&test_xm()).unwrap();
+
+        assert_eq!(module.channels, 2);
+        assert_eq!(module.speed, 4);
+        assert_eq!(module.tempo, 150);
+        assert_eq!(module.orders, [0]);
+
+        let sample = &module.samples[0];
+        assert_eq!(sample.data, [1, 2, 3, 0]);
+        assert_eq!(sample.loop_start, Some(2));
+        assert_eq!(sample.rate, 16726);
+
+        let pattern = &module.patterns[0];
+        assert_eq!(
+            pattern[0],
+            Slot {
+                pitch: 768,
+                sample: 1,
+                volume_effect: Effect::Volume(32),
+                effect: Effect::None,
+            }
+        );
+        assert_eq!(pattern[1], Slot::default());
+        assert_eq!(pattern[2].volume_effect, Effect::Stop);
+        assert_eq!(pattern[3].effect, Effect::Portamento(8));
+    }
+
+    #[test]
+    fn unknown_files_are_rejected() {
+        assert!(parse(&[0; 2000]).is_err());
+    }
+}
@@ -0,0 +1,474 @@
+//! Parses the practical subset of Impulse Tracker modules which the tracker can play.
+
+use std::collections::BTreeSet;
+
+use super::s3m::{convert_effect, slot_effects, EffectMemory, Format, Slides};
+use super::{Effect, Module, Reader, Sample, Slot, NO_NOTE, PITCH_PER_SEMITONE};
+
+/// IT notes are numbered from C-0, and samples are tuned for C-5
+const C5: i16 = 60;
+
+/// The tone portamento speeds which can be used in the volume column
+const VOLUME_COLUMN_PORTAMENTO: [u8; 10] = [0, 1, 4, 8, 16, 32, 64, 96, 128, 255];
+
+struct Instrument {
+    /// The note and sample, which is one more than its index, played for each note
+    keymap: [(u8, u8); 120],
+}
+
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    note: Option<u8>,
+    instrument: Option<u8>,
+    volume: Option<u8>,
+    command: Option<(u8, u8)>,
+}
+
+pub(super) fn parse(data: &[u8]) -> Result<Module, String> {
+    let reader = Reader { data };
+
+    let order_count = usize::from(reader.u16_le(0x20)?);
+    let instrument_count = usize::from(reader.u16_le(0x22)?);
+    let sample_count = usize::from(reader.u16_le(0x24)?);
+    let pattern_count = usize::from(reader.u16_le(0x26)?);
+    let compatible_version = reader.u16_le(0x2a)?;
+    let flags = reader.u16_le(0x2c)?;
+    let speed = reader.u8(0x32)?;
+    let tempo = reader.u8(0x33)?;
+
+    let uses_instruments = flags & 4 != 0;
+    let slides = if flags & 8 != 0 {
+        Slides::Linear
+    } else {
+        Slides::Amiga
+    };
+
+    // 254 marks a gap between parts of the song and 255 the end
+    let orders = reader
+        .bytes(0xc0, order_count)?
+        .iter()
+        .copied()
+        .take_while(|&order| order != 255)
+        .filter(|&order| order != 254)
+        .collect();
+
+    let pointers = 0xc0 + order_count;
+    let pointer = |index: usize| -> Result<usize, String> {
+        Ok(reader.u32_le(pointers + index * 4)? as usize)
+    };
+
+    let mut unsupported = BTreeSet::new();
+
+    let instruments = if uses_instruments {
+        (0..instrument_count)
+            .map(|i| parse_instrument(&reader, pointer(i)?, compatible_version, &mut unsupported))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        vec![]
+    };
+
+    let samples = (0..sample_count)
+        .map(|i| parse_sample(&reader, pointer(instrument_count + i)?, &mut unsupported))
+        .collect::<Result<_, _>>()?;
+
+    let raw_patterns = (0..pattern_count)
+        .map(|i| parse_pattern(&reader, pointer(instrument_count + sample_count + i)?))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // IT modules always have 64 channels, so only the ones which are used are kept
+    let channels = raw_patterns
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|(channel, _)| channel + 1)
+        .max()
+        .unwrap_or(1);
+
+    let patterns = raw_patterns
+        .iter()
+        .map(|rows| {
+            convert_pattern(
+                rows,
+                channels,
+                uses_instruments.then_some(&instruments[..]),
+                slides,
+                &mut unsupported,
+            )
+        })
+        .collect();
+
+    Ok(Module {
+        channels,
+        samples,
+        patterns,
+        orders,
+        speed: speed.clamp(1, 31),
+        tempo: tempo.max(32),
+        restart_position: 0,
+        unsupported,
+    })
+}
+
+fn parse_instrument(
+    reader: &Reader,
+    offset: usize,
+    compatible_version: u16,
+    unsupported: &mut BTreeSet<String>,
+) -> Result<Instrument, String> {
+    let mut keymap = [(0, 0); 120];
+    for (i, entry) in keymap.iter_mut().enumerate() {
+        *entry = (
+            reader.u8(offset + 0x40 + i * 2)?,
+            reader.u8(offset + 0x41 + i * 2)?,
+        );
+    }
+
+    // instruments from before Impulse Tracker 2 have their envelopes elsewhere
+    if compatible_version >= 0x200 && reader.u8(offset + 0x130)? & 1 != 0 {
+        unsupported.insert("instrument envelopes".to_string());
+    }
+
+    Ok(Instrument { keymap })
+}
+
+fn parse_sample(
+    reader: &Reader,
+    offset: usize,
+    unsupported: &mut BTreeSet<String>,
+) -> Result<Sample, String> {
+    let global_volume = u16::from(reader.u8(offset + 0x11)?.min(64));
+    let flags = reader.u8(offset + 0x12)?;
+    let volume = u16::from(reader.u8(offset + 0x13)?.min(64));
+    let conversion = reader.u8(offset + 0x2e)?;
+    let length = reader.u32_le(offset + 0x30)? as usize;
+    let loop_start = reader.u32_le(offset + 0x34)? as usize;
+    let loop_end = reader.u32_le(offset + 0x38)? as usize;
+    let rate = reader.u32_le(offset + 0x3c)?;
+    let data_offset = reader.u32_le(offset + 0x48)? as usize;
+
+    let mut sample = Sample {
+        data: vec![],
+        loop_start: None,
+        volume: (volume * global_volume / 64) as u8,
+        rate,
+    };
+
+    if flags & 1 == 0 {
+        return Ok(sample);
+    }
+
+    if flags & 8 != 0 {
+        return Err("compressed IT samples aren't supported".to_string());
+    }
+    if flags & 4 != 0 {
+        // the left channel is stored first, which is all that is used
+        unsupported.insert("stereo samples".to_string());
+    }
+    if flags & 0x20 != 0 {
+        unsupported.insert("sustain loops".to_string());
+    }
+    if flags & 0x40 != 0 {
+        // these are played as normal loops
+        unsupported.insert("ping-pong loops".to_string());
+    }
+
+    let signed = conversion & 1 != 0;
+    sample.data = if flags & 2 != 0 {
+        reader
+            .bytes(data_offset, length * 2)?
+            .chunks_exact(2)
+            .map(|sample| {
+                let sample = u16::from_le_bytes([sample[0], sample[1]]);
+                let sample = if signed { sample } else { sample ^ 0x8000 };
+                (sample >> 8) as u8
+            })
+            .collect()
+    } else {
+        reader
+            .bytes(data_offset, length)?
+            .iter()
+            .map(|&sample| if signed { sample } else { sample ^ 0x80 })
+            .collect()
+    };
+
+    if flags & 0x10 != 0 && loop_start < loop_end && loop_start < sample.data.len() {
+        sample.data.truncate(loop_end);
+        sample.loop_start = Some(loop_start);
+    }
+
+    Ok(sample)
+}
+
+/// Unpacks a pattern into its rows, each of which has the entries for the channels used in it
+fn parse_pattern(reader: &Reader, offset: usize) -> Result<Vec<Vec<(usize, Entry)>>, String> {
+    if offset == 0 {
+        return Ok(vec![vec![]; 64]);
+    }
+
+    let row_count = usize::from(reader.u16_le(offset + 2)?);
+
+    let mut masks = [0u8; 64];
+    let mut last = [Entry::default(); 64];
+
+    let mut offset = offset + 8;
+    let mut rows = Vec::with_capacity(row_count);
+    for _ in 0..row_count {
+        let mut row = vec![];
+
+        loop {
+            let channel_variable = reader.u8(offset)?;
+            offset += 1;
+            if channel_variable == 0 {
+                break;
+            }
+
+            let channel = usize::from((channel_variable - 1) & 63);
+            if channel_variable & 0x80 != 0 {
+                masks[channel] = reader.u8(offset)?;
+                offset += 1;
+            }
+
+            let mask = masks[channel];
+            let previous = &mut last[channel];
+            let mut entry = Entry::default();
+
+            if mask & 1 != 0 {
+                previous.note = Some(reader.u8(offset)?);
+                offset += 1;
+            }
+            if mask & 2 != 0 {
+                previous.instrument = Some(reader.u8(offset)?);
+                offset += 1;
+            }
+            if mask & 4 != 0 {
+                previous.volume = Some(reader.u8(offset)?);
+                offset += 1;
+            }
+            if mask & 8 != 0 {
+                previous.command = Some((reader.u8(offset)?, reader.u8(offset + 1)?));
+                offset += 2;
+            }
+
+            // the higher bits of the mask repeat the last value in the channel
+            if mask & 0x11 != 0 {
+                entry.note = previous.note;
+            }
+            if mask & 0x22 != 0 {
+                entry.instrument = previous.instrument;
+            }
+            if mask & 0x44 != 0 {
+                entry.volume = previous.volume;
+            }
+            if mask & 0x88 != 0 {
+                entry.command = previous.command;
+            }
+
+            row.push((channel, entry));
+        }
+
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+fn convert_pattern(
+    rows: &[Vec<(usize, Entry)>],
+    channels: usize,
+    instruments: Option<&[Instrument]>,
+    slides: Slides,
+    unsupported: &mut BTreeSet<String>,
+) -> Vec<Slot> {
+    let mut slots = vec![Slot::default(); rows.len() * channels];
+    let mut memory = vec![EffectMemory::default(); channels];
+    let mut last_instrument = vec![0u8; channels];
+
+    for (row_index, row) in rows.iter().enumerate() {
+        for &(channel, entry) in row {
+            if let Some(instrument) = entry.instrument.filter(|&instrument| instrument != 0) {
+                last_instrument[channel] = instrument;
+            }
+
+            let note = entry.note.filter(|&note| note < 120);
+
+            let (note, sample) = match instruments {
+                Some(instruments) => {
+                    let instrument =
+                        instruments.get(usize::from(last_instrument[channel]).wrapping_sub(1));
+
+                    match (instrument, note) {
+                        (Some(instrument), Some(note)) => {
+                            let (note, sample) = instrument.keymap[usize::from(note)];
+                            (Some(note), sample)
+                        }
+                        // an instrument on its own resets the volume of the sample it would play
+                        (Some(instrument), None) if entry.instrument.is_some() => {
+                            (None, instrument.keymap[C5 as usize].1)
+                        }
+                        _ => (note, 0),
+                    }
+                }
+                None => (note, entry.instrument.unwrap_or(0)),
+            };
+
+            let pitch = match note {
+                Some(note) => (i16::from(note) - C5) * PITCH_PER_SEMITONE as i16,
+                None => NO_NOTE,
+            };
+
+            let volume_effect = match (entry.note, entry.volume) {
+                // note cut, note off and note fade all stop the note
+                (Some(120..), _) => Effect::Stop,
+                (_, Some(volume)) => volume_column(volume, slides, unsupported),
+                _ => Effect::None,
+            };
+
+            let effect = match entry.command {
+                Some((command, param)) => convert_effect(
+                    // commands are stored as 1 for A, 2 for B and so on
+                    command.wrapping_add(b'A' - 1),
+                    param,
+                    Format::ImpulseTracker { slides },
+                    &mut memory[channel],
+                    unsupported,
+                ),
+                None => (Effect::None, None),
+            };
+            let (volume_effect, effect) = slot_effects(volume_effect, effect);
+
+            slots[row_index * channels + channel] = Slot {
+                pitch,
+                sample: u16::from(sample),
+                volume_effect,
+                effect,
+            };
+        }
+    }
+
+    slots
+}
+
+fn volume_column(volume: u8, slides: Slides, unsupported: &mut BTreeSet<String>) -> Effect {
+    let amount = |start: u8| (volume - start) as i8;
+    let pitch = |start: u8| {
+        let units = i16::from(volume - start) * 4;
+        match slides {
+            Slides::Amiga => super::mod_pitch_units(units as u8),
+            Slides::Linear => units * 4,
+        }
+    };
+
+    match volume {
+        0..=64 => Effect::Volume(volume),
+        65..=74 => Effect::FineVolumeSlide(amount(65)),
+        75..=84 => Effect::FineVolumeSlide(-amount(75)),
+        85..=94 => Effect::VolumeSlide(amount(85)),
+        95..=104 => Effect::VolumeSlide(-amount(95)),
+        105..=114 => Effect::Portamento(-pitch(105)),
+        115..=124 => Effect::Portamento(pitch(115)),
+        128..=192 => {
+            Effect::Panning((i16::from(volume - 128) * 4).min(255).wrapping_sub(128) as i8)
+        }
+        193..=202 => {
+            let speed = VOLUME_COLUMN_PORTAMENTO[usize::from(volume - 193)];
+            match slides {
+                Slides::Amiga => Effect::TonePortamento(super::mod_pitch_units(speed) as u16),
+                Slides::Linear => Effect::TonePortamento(u16::from(speed) * 4),
+            }
+        }
+        _ => {
+            unsupported.insert("volume column vibrato".to_string());
+            Effect::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_it() -> Vec<u8> {
+        let mut data = vec![0; 0xc0];
+        data[0..4].copy_from_slice(b"IMPM");
+        data[0x20..0x22].copy_from_slice(&2u16.to_le_bytes()); // orders
+        data[0x24..0x26].copy_from_slice(&1u16.to_le_bytes()); // samples
+        data[0x26..0x28].copy_from_slice(&1u16.to_le_bytes()); // patterns
+        data[0x2a..0x2c].copy_from_slice(&0x214u16.to_le_bytes());
+        data[0x2c..0x2e].copy_from_slice(&8u16.to_le_bytes()); // linear slides
+        data[0x32] = 5;
+        data[0x33] = 128;
+
+        data.extend([0, 255]); // orders
+        data.extend(0xd0u32.to_le_bytes()); // sample
+        data.extend(0x120u32.to_le_bytes()); // pattern
+        data.resize(0xd0, 0);
+
+        let mut sample = vec![0; 0x50];
+        sample[0..4].copy_from_slice(b"IMPS");
+        sample[0x11] = 64;
+        sample[0x12] = 1 | 0x10;
+        sample[0x13] = 32;
+        sample[0x2e] = 1; // signed
+        sample[0x30..0x34].copy_from_slice(&4u32.to_le_bytes());
+        sample[0x34..0x38].copy_from_slice(&2u32.to_le_bytes());
+        sample[0x38..0x3c].copy_from_slice(&4u32.to_le_bytes());
+        sample[0x3c..0x40].copy_from_slice(&22050u32.to_le_bytes());
+        sample[0x48..0x4c].copy_from_slice(&0x160u32.to_le_bytes());
+        data.extend(sample);
+        data.resize(0x120, 0);
+
+        let rows = [
+            // C-5 with sample 1 and volume 48 in channel 2, and F02 in channel 1
+            vec![0x82, 0x07, 60, 1, 48, 0x81, 0x08, 6, 0x02, 0],
+            // the same note again in channel 2, and vibrato in channel 1
+            vec![0x82, 0x70, 0x81, 0x08, 8, 0x11, 0],
+            // note cut in channel 2
+            vec![0x82, 0x01, 254, 0],
+        ];
+        let packed: Vec<u8> = rows.concat();
+        data.extend((packed.len() as u16).to_le_bytes());
+        data.extend(3u16.to_le_bytes());
+        data.extend([0; 4]);
+        data.extend(packed);
+        data.resize(0x160, 0);
+
+        data.extend([1, 2, 3, 4]);
+        data
+    }
+
+    #[test]
+    fn parses_it_files() {
+        let module = super::super::parse(&test_it()).unwrap();
+
+        assert_eq!(module.channels, 2);
+        assert_eq!(module.orders, [0]);
+        assert_eq!(module.speed, 5);
+        assert_eq!(module.tempo, 128);
+
+        let sample = &module.samples[0];
+        assert_eq!(sample.data, [1, 2, 3, 4]);
+        assert_eq!(sample.loop_start, Some(2));
+        assert_eq!(sample.volume, 32);
+        assert_eq!(sample.rate, 22050);
+
+        let pattern = &module.patterns[0];
+        assert_eq!(pattern[0].effect, Effect::Portamento(8));
+        assert_eq!(
+            pattern[1],
+            Slot {
+                pitch: 0,
+                sample: 1,
+                volume_effect: Effect::Volume(48),
+                effect: Effect::None,
+            }
+        );
+        assert_eq!(pattern[3].pitch, 0);
+        assert_eq!(pattern[3].volume_effect, Effect::Volume(48));
+        assert_eq!(pattern[5].volume_effect, Effect::Stop);
+
+        assert_eq!(
+            module.unsupported.iter().collect::<Vec<_>>(),
+            ["effect H (vibrato)"]
+        );
+    }
+}
@@ -0,0 +1,495 @@
+//! Parses Scream Tracker 3 modules, and converts the effects which Impulse Tracker shares with
+//! them.
+
+use std::collections::BTreeSet;
+
+use super::{mod_pitch_units, Effect, Module, Reader, Sample, Slot, NO_NOTE, PITCH_PER_SEMITONE};
+
+/// The rows in every S3M pattern
+const ROWS: usize = 64;
+
+/// How the pitch slide effects are measured
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Slides {
+    /// Amiga periods, as in MOD files
+    Amiga,
+    /// 16ths of a semitone, as in XM files
+    Linear,
+}
+
+impl Slides {
+    fn pitch_units(self, amount: u8) -> i16 {
+        match self {
+            Slides::Amiga => mod_pitch_units(amount),
+            Slides::Linear => i16::from(amount) * 4,
+        }
+    }
+}
+
+/// Most effects given a parameter of 0 repeat the last parameter they were given on the
+/// channel, so this keeps track of them for each effect letter.
+#[derive(Clone, Default)]
+pub(super) struct EffectMemory {
+    last: [u8; 27],
+}
+
+impl EffectMemory {
+    fn recall(&mut self, command: u8, param: u8) -> u8 {
+        // the pitch slides share their memory, and so do the volume slides
+        let slot = match command {
+            b'F' => b'E',
+            b'K' | b'L' => b'D',
+            _ => command,
+        } - b'A';
+        let slot = usize::from(slot);
+
+        if param == 0 {
+            self.last[slot]
+        } else {
+            self.last[slot] = param;
+            param
+        }
+    }
+}
+
+/// The format an effect came from, as the S3M and IT effects differ in a few places
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Format {
+    ScreamTracker,
+    ImpulseTracker { slides: Slides },
+}
+
+impl Format {
+    fn slides(self) -> Slides {
+        match self {
+            Format::ScreamTracker => Slides::Amiga,
+            Format::ImpulseTracker { slides } => slides,
+        }
+    }
+}
+
+fn volume_slide(param: u8) -> Effect {
+    let up = param >> 4;
+    let down = param & 0xf;
+
+    match (up, down) {
+        (0xf, 0) => Effect::VolumeSlide(15),
+        (0, 0xf) => Effect::VolumeSlide(-15),
+        (up, 0xf) => Effect::FineVolumeSlide(up as i8),
+        (0xf, down) => Effect::FineVolumeSlide(-(down as i8)),
+        (up, 0) => Effect::VolumeSlide(up as i8),
+        (_, down) => Effect::VolumeSlide(-(down as i8)),
+    }
+}
+
+fn pitch_slide(param: u8, slides: Slides, up: bool) -> Effect {
+    let direction = if up { 1 } else { -1 };
+
+    match param >> 4 {
+        0xf => Effect::FinePortamento(slides.pitch_units(param & 0xf) * direction),
+        0xe => Effect::FinePortamento(slides.pitch_units(param & 0xf) / 4 * direction),
+        _ => Effect::Portamento(slides.pitch_units(param) * direction),
+    }
+}
+
+/// Converts an effect, where `command` is the letter used for the effect in the tracker. Returns
+/// the effect, and a volume slide which also needs to be done if there is one.
+pub(super) fn convert_effect(
+    command: u8,
+    param: u8,
+    format: Format,
+    memory: &mut EffectMemory,
+    unsupported: &mut BTreeSet<String>,
+) -> (Effect, Option<Effect>) {
+    if !command.is_ascii_uppercase() {
+        return (Effect::None, None);
+    }
+
+    let slides = format.slides();
+    let named = |name: &str| Err(format!("{} ({})", char::from(command), name));
+
+    let effect = match command {
+        b'A' if param != 0 => Ok(Effect::SetSpeed(param)),
+        b'A' => Ok(Effect::None),
+        b'B' => Ok(Effect::PositionJump(param)),
+        b'C' if format == Format::ScreamTracker => {
+            Ok(Effect::PatternBreak((param >> 4) * 10 + (param & 0xf)))
+        }
+        b'C' => Ok(Effect::PatternBreak(param)),
+        b'D' => Ok(volume_slide(memory.recall(command, param))),
+        b'E' => Ok(pitch_slide(memory.recall(command, param), slides, false)),
+        b'F' => Ok(pitch_slide(memory.recall(command, param), slides, true)),
+        b'G' => Ok(Effect::TonePortamento(slides.pitch_units(param) as u16)),
+        b'J' => {
+            let param = memory.recall(command, param);
+            Ok(Effect::Arpeggio(param >> 4, param & 0xf))
+        }
+        b'K' => {
+            // the volume slide can still be done without the vibrato
+            unsupported.insert("effect K (vibrato)".to_string());
+            Ok(volume_slide(memory.recall(command, param)))
+        }
+        b'L' => {
+            return (
+                Effect::TonePortamento(0),
+                Some(volume_slide(memory.recall(command, param))),
+            )
+        }
+        b'O' => Ok(Effect::SampleOffset(
+            u16::from(memory.recall(command, param)) * 256,
+        )),
+        b'S' => match param >> 4 {
+            0x8 => Ok(Effect::Panning((i16::from(param & 0xf) * 17 - 128) as i8)),
+            0xc => Ok(Effect::NoteCut(param & 0xf)),
+            _ => Err(format!("S{:X}x", param >> 4)),
+        },
+        b'T' if param >= 0x20 => Ok(Effect::SetTempo(param)),
+        b'T' => named("tempo slide"),
+        b'V' if format == Format::ScreamTracker => Ok(Effect::SetGlobalVolume(param.min(64))),
+        b'V' => Ok(Effect::SetGlobalVolume(param.min(128) / 2)),
+        b'W' if format != Format::ScreamTracker => {
+            match volume_slide(memory.recall(command, param)) {
+                Effect::VolumeSlide(amount) => Ok(Effect::GlobalVolumeSlide(amount)),
+                _ => named("fine global volume slide"),
+            }
+        }
+        b'X' if format == Format::ScreamTracker && param > 0x80 => named("surround"),
+        b'X' if format == Format::ScreamTracker => {
+            Ok(Effect::Panning((i16::from(param) * 2 - 128).min(127) as i8))
+        }
+        b'X' => Ok(Effect::Panning((i16::from(param) - 128) as i8)),
+        b'H' | b'U' => named("vibrato"),
+        b'I' => named("tremor"),
+        b'M' | b'N' => named("channel volume"),
+        b'P' => named("panning slide"),
+        b'Q' => named("retrigger"),
+        b'R' => named("tremolo"),
+        b'Y' => named("panbrello"),
+        b'Z' => named("MIDI macro"),
+        _ => Err(char::from(command).to_string()),
+    };
+
+    match effect {
+        Ok(effect) => (effect, None),
+        Err(name) => {
+            unsupported.insert(format!("effect {}", name));
+            (Effect::None, None)
+        }
+    }
+}
+
+/// Combines an effect with the volume column, dropping the extra volume slide if the volume
+/// column is already in use.
+pub(super) fn slot_effects(
+    volume_effect: Effect,
+    (effect, extra_volume_effect): (Effect, Option<Effect>),
+) -> (Effect, Effect) {
+    match (volume_effect, extra_volume_effect) {
+        (Effect::None, Some(extra)) => (extra, effect),
+        _ => (volume_effect, effect),
+    }
+}
+
+pub(super) fn parse(data: &[u8]) -> Result<Module, String> {
+    let reader = Reader { data };
+
+    let order_count = usize::from(reader.u16_le(0x20)?);
+    let sample_count = usize::from(reader.u16_le(0x22)?);
+    let pattern_count = usize::from(reader.u16_le(0x24)?);
+    let signed_samples = reader.u16_le(0x2a)? == 1;
+    let speed = reader.u8(0x31)?;
+    let tempo = reader.u8(0x32)?;
+
+    // only the enabled channels are kept
+    let mut channel_map = [None; 32];
+    let mut channels = 0;
+    for (i, &setting) in reader.bytes(0x40, 32)?.iter().enumerate() {
+        if setting < 16 {
+            channel_map[i] = Some(channels);
+            channels += 1;
+        }
+    }
+
+    if channels == 0 {
+        return Err("the module has no enabled channels".to_string());
+    }
+
+    // 254 marks a gap between parts of the song and 255 the end
+    let orders = reader
+        .bytes(0x60, order_count)?
+        .iter()
+        .copied()
+        .take_while(|&order| order != 255)
+        .filter(|&order| order != 254)
+        .collect();
+
+    let pointers = 0x60 + order_count;
+    let parapointer = |index: usize| -> Result<usize, String> {
+        Ok(usize::from(reader.u16_le(pointers + index * 2)?) * 16)
+    };
+
+    let mut unsupported = BTreeSet::new();
+
+    let samples = (0..sample_count)
+        .map(|i| parse_sample(&reader, parapointer(i)?, signed_samples, &mut unsupported))
+        .collect::<Result<_, _>>()?;
+
+    let patterns = (0..pattern_count)
+        .map(|i| {
+            let offset = parapointer(sample_count + i)?;
+            parse_pattern(&reader, offset, channels, &channel_map, &mut unsupported)
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Module {
+        channels,
+        samples,
+        patterns,
+        orders,
+        speed: speed.clamp(1, 31),
+        tempo: tempo.max(32),
+        restart_position: 0,
+        unsupported,
+    })
+}
+
+fn parse_sample(
+    reader: &Reader,
+    offset: usize,
+    signed: bool,
+    unsupported: &mut BTreeSet<String>,
+) -> Result<Sample, String> {
+    let empty = Sample {
+        data: vec![],
+        loop_start: None,
+        volume: 0,
+        rate: 8363,
+    };
+
+    // adlib instruments and empty slots have no sample data
+    if reader.u8(offset)? != 1 {
+        return Ok(empty);
+    }
+
+    let data_offset = ((usize::from(reader.u8(offset + 0x0d)?) << 16)
+        | usize::from(reader.u16_le(offset + 0x0e)?))
+        * 16;
+    let length = reader.u32_le(offset + 0x10)? as usize;
+    let loop_start = reader.u32_le(offset + 0x14)? as usize;
+    let loop_end = reader.u32_le(offset + 0x18)? as usize;
+    let volume = reader.u8(offset + 0x1c)?.min(64);
+    let packing = reader.u8(offset + 0x1e)?;
+    let flags = reader.u8(offset + 0x1f)?;
+    let rate = reader.u32_le(offset + 0x20)?;
+
+    if packing != 0 {
+        return Err("packed S3M samples aren't supported".to_string());
+    }
+
+    if flags & 2 != 0 {
+        // the left channel is stored first, which is all that is used
+        unsupported.insert("stereo samples".to_string());
+    }
+
+    let mut data = if flags & 4 != 0 {
+        reader
+            .bytes(data_offset, length * 2)?
+            .chunks_exact(2)
+            .map(|sample| {
+                let sample = u16::from_le_bytes([sample[0], sample[1]]);
+                let sample = if signed { sample } else { sample ^ 0x8000 };
+                (sample >> 8) as u8
+            })
+            .collect()
+    } else {
+        reader
+            .bytes(data_offset, length)?
+            .iter()
+            .map(|&sample| if signed { sample } else { sample ^ 0x80 })
+            .collect::<Vec<_>>()
+    };
+
+    let loop_start = if flags & 1 != 0 && loop_start < loop_end && loop_start < data.len() {
+        data.truncate(loop_end);
+        Some(loop_start)
+    } else {
+        None
+    };
+
+    Ok(Sample {
+        data,
+        loop_start,
+        volume,
+        rate,
+    })
+}
+
+fn parse_pattern(
+    reader: &Reader,
+    offset: usize,
+    channels: usize,
+    channel_map: &[Option<usize>; 32],
+    unsupported: &mut BTreeSet<String>,
+) -> Result<Vec<Slot>, String> {
+    let mut slots = vec![Slot::default(); ROWS * channels];
+    if offset == 0 {
+        return Ok(slots);
+    }
+
+    let mut memory = vec![EffectMemory::default(); channels];
+
+    // skip the packed length
+    let mut offset = offset + 2;
+    let mut row = 0;
+    while row < ROWS {
+        let what = reader.u8(offset)?;
+        offset += 1;
+
+        if what == 0 {
+            row += 1;
+            continue;
+        }
+
+        let (mut note, mut instrument, mut volume, mut command, mut param) = (255, 0, 255, 0, 0);
+        if what & 0x20 != 0 {
+            note = reader.u8(offset)?;
+            instrument = reader.u8(offset + 1)?;
+            offset += 2;
+        }
+        if what & 0x40 != 0 {
+            volume = reader.u8(offset)?;
+            offset += 1;
+        }
+        if what & 0x80 != 0 {
+            command = reader.u8(offset)?;
+            param = reader.u8(offset + 1)?;
+            offset += 2;
+        }
+
+        let Some(channel) = channel_map[usize::from(what & 0x1f)] else {
+            continue;
+        };
+
+        let pitch = match note {
+            254 | 255 => NO_NOTE,
+            note => {
+                let semitones = i16::from(note >> 4) * 12 + i16::from(note & 0xf) - 48;
+                semitones * PITCH_PER_SEMITONE as i16
+            }
+        };
+
+        let volume_effect = match (note, volume) {
+            (254, _) => Effect::Stop,
+            (_, 0..=64) => Effect::Volume(volume),
+            _ => Effect::None,
+        };
+
+        let effect = convert_effect(
+            // commands are stored as 1 for A, 2 for B and so on
+            command.wrapping_add(b'A' - 1),
+            param,
+            Format::ScreamTracker,
+            &mut memory[channel],
+            unsupported,
+        );
+        let (volume_effect, effect) = slot_effects(volume_effect, effect);
+
+        slots[row * channels + channel] = Slot {
+            pitch,
+            sample: u16::from(instrument),
+            volume_effect,
+            effect,
+        };
+    }
+
+    Ok(slots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_s3m() -> Vec<u8> {
+        let mut data = vec![0; 0x60];
+        data[0x1c] = 0x1a;
+        data[0x1d] = 16;
+        data[0x20..0x22].copy_from_slice(&2u16.to_le_bytes()); // orders
+        data[0x22..0x24].copy_from_slice(&1u16.to_le_bytes()); // samples
+        data[0x24..0x26].copy_from_slice(&1u16.to_le_bytes()); // patterns
+        data[0x2a..0x2c].copy_from_slice(&2u16.to_le_bytes()); // unsigned samples
+        data[0x2c..0x30].copy_from_slice(b"SCRM");
+        data[0x31] = 3;
+        data[0x32] = 140;
+
+        // channels 0 and 2 are enabled, the rest are not
+        data[0x40..0x60].fill(255);
+        data[0x40] = 0;
+        data[0x42] = 8;
+
+        data.extend([0, 255]); // orders
+        data.extend(7u16.to_le_bytes()); // sample at 0x70
+        data.extend(10u16.to_le_bytes()); // pattern at 0xa0
+        data.resize(0x70, 0);
+
+        let mut sample = vec![0; 0x50];
+        sample[0] = 1;
+        sample[0x0e..0x10].copy_from_slice(&16u16.to_le_bytes()); // data at 0x100
+        sample[0x10..0x14].copy_from_slice(&4u32.to_le_bytes());
+        sample[0x14..0x18].copy_from_slice(&1u32.to_le_bytes());
+        sample[0x18..0x1c].copy_from_slice(&3u32.to_le_bytes());
+        sample[0x1c] = 40;
+        sample[0x1f] = 1;
+        sample[0x20..0x24].copy_from_slice(&16726u32.to_le_bytes());
+        sample[0x4c..0x50].copy_from_slice(b"SCRS");
+        data.extend(sample);
+        data.resize(0xa0, 0);
+
+        let mut pattern = vec![0, 0];
+        // C-5 with sample 1, volume 32 and a fine volume slide up by 3 in channel 2
+        pattern.extend([0xe2, 0x50, 1, 32, 4, 0x3f]);
+        // vibrato in channel 0
+        pattern.extend([0x80, 8, 0x44]);
+        pattern.push(0);
+        // volume slide memory in channel 2 on the next row
+        pattern.extend([0x82, 4, 0x00, 0]);
+        pattern.extend([0; 62]);
+        data.extend(pattern);
+        data.resize(0x100, 0);
+
+        data.extend([0x80, 0x90, 0x70, 0xff]);
+        data
+    }
+
+    #[test]
+    fn parses_s3m_files() {
+        let module = super::super::parse(&test_s3m()).unwrap();
+
+        assert_eq!(module.channels, 2);
+        assert_eq!(module.orders, [0]);
+        assert_eq!(module.speed, 3);
+        assert_eq!(module.tempo, 140);
+
+        let sample = &module.samples[0];
+        assert_eq!(sample.data, [0, 0x10, 0xf0]);
+        assert_eq!(sample.loop_start, Some(1));
+        assert_eq!(sample.volume, 40);
+        assert_eq!(sample.rate, 16726);
+
+        let pattern = &module.patterns[0];
+        assert_eq!(
+            pattern[1],
+            Slot {
+                pitch: 768,
+                sample: 1,
+                volume_effect: Effect::Volume(32),
+                effect: Effect::FineVolumeSlide(3),
+            }
+        );
+        assert_eq!(pattern[3].effect, Effect::FineVolumeSlide(3));
+
+        assert_eq!(
+            module.unsupported.iter().collect::<Vec<_>>(),
+            ["effect H (vibrato)"]
+        );
+    }
+}
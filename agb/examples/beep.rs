@@ -23,6 +23,13 @@ fn main(gba: agb::Gba) -> ! {
         sound::dmg::DutyCycle::Half,
     );
 
+    let wave = gba.sound.wave();
+    wave.set_waveform(&[
+        0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0xfe, 0xdc, 0xba, 0x98, 0x76, 0x54, 0x32,
+        0x10,
+    ]);
+    wave.play_sound(1800, Some(0), sound::dmg::WaveVolume::Full);
+
     gba.sound.noise().play_sound(
         Some(0),
         &sound::dmg::EnvelopeSettings::default(),
@@ -0,0 +1,22 @@
+#![no_std]
+#![no_main]
+
+use agb::sound::sequencer::{Sequence, Sequencer};
+use agb::{include_midi, Gba};
+
+static TUNE: Sequence = include_midi!("examples/sfx/test_tune.mid");
+
+#[agb::entry]
+fn main(gba: Gba) -> ! {
+    let vblank_provider = agb::interrupt::VBlank::get();
+
+    gba.sound.enable();
+
+    let mut sequencer = Sequencer::new(&TUNE);
+    sequencer.should_loop();
+
+    loop {
+        sequencer.update(&gba.sound);
+        vblank_provider.wait_for_vblank();
+    }
+}
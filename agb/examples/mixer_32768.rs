@@ -7,14 +7,14 @@ use agb::{
         Font, Priority,
     },
     include_font, include_wav,
-    sound::mixer::{Frequency, SoundChannel},
+    sound::mixer::{Frequency, SoundChannel, SoundData},
     Gba,
 };
 
 use core::fmt::Write;
 
 // Music - "Crazy glue" by Josh Woodward, free download at http://joshwoodward.com
-const CRAZY_GLUE: &[u8] = include_wav!("examples/JoshWoodward-CrazyGlue.wav");
+const CRAZY_GLUE: SoundData = include_wav!("examples/JoshWoodward-CrazyGlue.wav");
 
 const FONT: Font = include_font!("examples/font/yoster.ttf", 12);
 
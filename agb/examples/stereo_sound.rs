@@ -7,14 +7,14 @@ use agb::{
         Font, Priority,
     },
     include_font, include_wav,
-    sound::mixer::{Frequency, SoundChannel},
+    sound::mixer::{Frequency, SoundChannel, SoundData},
     Gba,
 };
 
 use core::fmt::Write;
 
 // Music - "Let it in" by Josh Woodward, free download at http://joshwoodward.com
-const LET_IT_IN: &[u8] = include_wav!("examples/JoshWoodward-LetItIn.wav");
+const LET_IT_IN: SoundData = include_wav!("examples/JoshWoodward-LetItIn.wav");
 
 const FONT: Font = include_font!("examples/font/yoster.ttf", 12);
 
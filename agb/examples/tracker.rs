@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+use agb::input::{Button, ButtonController};
+use agb::sound::mixer::Frequency;
+use agb::sound::tracker::{Track, Tracker};
+use agb::{include_tracker, Gba};
+
+static TUNE: Track = include_tracker!("examples/sfx/test_tune.mod");
+
+#[agb::entry]
+fn main(mut gba: Gba) -> ! {
+    let mut input = ButtonController::new();
+    let vblank_provider = agb::interrupt::VBlank::get();
+
+    let mut mixer = gba.mixer.mixer(Frequency::Hz18157);
+    mixer.enable();
+
+    let mut tracker = Tracker::new(&TUNE);
+    let mut paused = false;
+
+    loop {
+        input.update();
+
+        if input.is_just_pressed(Button::A) {
+            paused = !paused;
+            if paused {
+                tracker.stop(&mut mixer);
+            }
+        }
+
+        if !paused {
+            tracker.step(&mut mixer);
+        }
+
+        mixer.frame();
+        vblank_provider.wait_for_vblank();
+        mixer.after_vblank();
+    }
+}
@@ -13,7 +13,22 @@ use crate::interrupt::free;
 use bare_metal::{CriticalSection, Mutex};
 
 use super::bump_allocator::{BumpAllocator, StartEnd};
-use super::SendNonNull;
+use super::{AllocatorStats, SendNonNull};
+
+/// Written just after every heap allocation in debug builds and checked when
+/// the allocation is freed, to catch writes past the end of it.
+const CANARY: u32 = 0xca0a_21e5;
+
+/// The layout to allocate from the heap for `layout`, which has room for the
+/// canary in debug builds.
+fn heap_layout(layout: Layout) -> Layout {
+    if cfg!(debug_assertions) {
+        Layout::from_size_align(layout.size() + core::mem::size_of::<u32>(), layout.align())
+            .expect("too large allocation")
+    } else {
+        layout
+    }
+}
 
 struct Block {
     size: usize,
@@ -76,6 +91,40 @@ impl BlockAllocator {
         })
     }
 
+    /// Adds up the free space in the free list and the space which has never
+    /// been allocated.
+    pub fn stats(&self) -> AllocatorStats {
+        free(|key| {
+            let state = self.state.borrow(key).borrow();
+            let (start, current, end) = self.inner_allocator.bounds(key);
+
+            let never_allocated = end.saturating_sub(current);
+            let mut free_bytes = never_allocated;
+            let mut largest_free_block = never_allocated;
+
+            let mut block = state.first_free_block;
+            while let Some(current) = block {
+                let current = unsafe { current.as_ref() };
+                free_bytes += current.size;
+                largest_free_block = largest_free_block.max(current.size);
+                block = current.next;
+            }
+
+            let total_bytes = end.saturating_sub(start);
+            AllocatorStats {
+                total_bytes,
+                used_bytes: total_bytes - free_bytes,
+                free_bytes,
+                largest_free_block,
+            }
+        })
+    }
+
+    /// The end of the memory which has ever been allocated.
+    pub fn high_water_mark(&self) -> usize {
+        free(|key| self.inner_allocator.bounds(key).1)
+    }
+
     /// Requests a brand new block from the inner bump allocator
     fn new_block(&self, layout: Layout, cs: CriticalSection) -> Option<NonNull<u8>> {
         let overall_layout = Block::either_layout(layout);
@@ -204,22 +253,55 @@ impl BlockAllocator {
     }
 }
 
+/// Allocating through the `GlobalAlloc` and `Allocator` traits is only done
+/// for the heaps, so these are where the canaries are added. The video memory
+/// allocators use the methods directly, so they don't get canaries which would
+/// take up video memory.
+impl BlockAllocator {
+    unsafe fn heap_alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let ptr = self.alloc(heap_layout(layout))?;
+
+        if cfg!(debug_assertions) {
+            ptr.as_ptr()
+                .add(layout.size())
+                .cast::<u32>()
+                .write_unaligned(CANARY);
+        }
+
+        Some(ptr)
+    }
+
+    unsafe fn heap_dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if cfg!(debug_assertions) {
+            let canary = ptr.add(layout.size()).cast::<u32>().read_unaligned();
+            assert!(
+                canary == CANARY,
+                "heap corruption: something wrote past the end of the {} byte allocation at {:p}",
+                layout.size(),
+                ptr
+            );
+        }
+
+        self.dealloc(ptr, heap_layout(layout));
+    }
+}
+
 unsafe impl GlobalAlloc for BlockAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        match self.alloc(layout) {
+        match self.heap_alloc(layout) {
             None => core::ptr::null_mut(),
             Some(p) => p.as_ptr(),
         }
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        self.dealloc(ptr, layout);
+        self.heap_dealloc(ptr, layout);
     }
 }
 
 unsafe impl Allocator for BlockAllocator {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, core::alloc::AllocError> {
-        match unsafe { self.alloc(layout) } {
+        match unsafe { self.heap_alloc(layout) } {
             None => Err(core::alloc::AllocError),
             Some(p) => Ok(unsafe {
                 NonNull::new_unchecked(core::ptr::slice_from_raw_parts_mut(
@@ -231,6 +313,26 @@ unsafe impl Allocator for BlockAllocator {
     }
 
     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
-        self.dealloc(ptr.as_ptr(), layout);
+        self.heap_dealloc(ptr.as_ptr(), layout);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ExternalAllocator;
+
+    #[test_case]
+    fn heap_allocations_have_canaries_in_debug_builds(_gba: &mut crate::Gba) {
+        let layout = Layout::from_size_align(5, 1).unwrap();
+        let allocation = ExternalAllocator.allocate(layout).unwrap();
+        let ptr = allocation.cast::<u8>();
+
+        if cfg!(debug_assertions) {
+            let canary = unsafe { ptr.as_ptr().add(5).cast::<u32>().read_unaligned() };
+            assert_eq!(canary, CANARY);
+        }
+
+        unsafe { ExternalAllocator.deallocate(ptr, layout) };
     }
 }
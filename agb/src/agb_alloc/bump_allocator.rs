@@ -51,6 +51,20 @@ impl BumpAllocator {
 
         NonNull::new(resulting_ptr as *mut _)
     }
+    /// The start of the region, the first byte which hasn't been allocated, and
+    /// the end of the region.
+    pub fn bounds(&self, cs: CriticalSection) -> (usize, usize, usize) {
+        let start_end = self.start_end.borrow(cs);
+        let start = (start_end.start)();
+        let current = self
+            .current_ptr
+            .borrow(cs)
+            .borrow()
+            .map_or(start, |c| c.as_ptr() as usize);
+
+        (start, current, (start_end.end)())
+    }
+
     pub fn alloc_safe(&self, layout: Layout) -> Option<NonNull<u8>> {
         free(|key| self.alloc_critical(layout, key))
     }
@@ -34,15 +34,83 @@ impl<T> DerefMut for SendNonNull<T> {
 
 const EWRAM_END: usize = 0x0204_0000;
 const IWRAM_END: usize = 0x0300_8000;
+/// Where the BIOS keeps its stacks and variables.
+const BIOS_RESERVED_START: usize = 0x0300_7F00;
 
 #[global_allocator]
 static GLOBAL_ALLOC: BlockAllocator = unsafe {
     BlockAllocator::new(StartEnd {
-        start: data_end,
-        end: || EWRAM_END,
+        start: ewram_heap_start,
+        end: ewram_heap_end,
     })
 };
 
+/// The start of the heap, which is after the statics and the stack if it was
+/// given space in EWRAM.
+fn ewram_heap_start() -> usize {
+    linker_address!(__ewram_heap_start)
+}
+
+/// The end of the heap reserved with `#[agb::entry]`, or the end of EWRAM if
+/// it wasn't given a size.
+fn ewram_heap_end() -> usize {
+    let (start, end) = (
+        linker_address!(__ewram_heap_start),
+        linker_address!(__ewram_heap_end),
+    );
+
+    if start == end {
+        EWRAM_END
+    } else {
+        end
+    }
+}
+
+/// The start of the heap, which is after the statics and the stack if it was
+/// given space in IWRAM.
+fn iwram_heap_start() -> usize {
+    linker_address!(__iwram_heap_start)
+}
+
+/// The end of the heap reserved with `#[agb::entry]`. Otherwise it shares the
+/// rest of IWRAM with the stack, unless the stack was given its own space.
+fn iwram_heap_end() -> usize {
+    let (start, end) = (
+        linker_address!(__iwram_heap_start),
+        linker_address!(__iwram_heap_end),
+    );
+
+    if start != end {
+        end
+    } else if crate::mem::has_reserved_stack() {
+        BIOS_RESERVED_START
+    } else {
+        IWRAM_END
+    }
+}
+
+/// How much of the memory managed by an allocator is in use, from
+/// [`ExternalAllocator::stats`] or [`InternalAllocator::stats`].
+///
+/// Allocations are rounded up to a multiple of 8 bytes and at least 8 bytes,
+/// so the used bytes will be a bit more than the total size of everything
+/// allocated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AllocatorStats {
+    /// The size of the memory the allocator manages, which is the part of the
+    /// working ram which isn't used by statics.
+    pub total_bytes: usize,
+    /// The number of bytes which are currently allocated.
+    pub used_bytes: usize,
+    /// The number of bytes which aren't allocated.
+    pub free_bytes: usize,
+    /// The size of the largest single allocation which would succeed. If this
+    /// is a lot smaller than [`free_bytes`](AllocatorStats::free_bytes) the
+    /// memory is fragmented.
+    pub largest_free_block: usize,
+}
+
 macro_rules! impl_zst_allocator {
     ($name_of_struct: ty, $name_of_static: ident) => {
         unsafe impl Allocator for $name_of_struct {
@@ -83,6 +151,15 @@ pub struct ExternalAllocator;
 
 impl_zst_allocator!(ExternalAllocator, GLOBAL_ALLOC);
 
+impl ExternalAllocator {
+    /// How much of the external working ram is in use. This is the allocator
+    /// used by `Box`, `Vec` and the other `alloc` types by default.
+    #[must_use]
+    pub fn stats() -> AllocatorStats {
+        GLOBAL_ALLOC.stats()
+    }
+}
+
 /// This is the allocator for the Internal Working Ram. This implements the
 /// allocator trait, so is meant to be used in specifying where certain
 /// structures should be allocated.
@@ -108,13 +185,28 @@ pub struct InternalAllocator;
 
 impl_zst_allocator!(InternalAllocator, __IWRAM_ALLOC);
 
+impl InternalAllocator {
+    /// How much of the internal working ram available to the allocator is in
+    /// use.
+    #[must_use]
+    pub fn stats() -> AllocatorStats {
+        __IWRAM_ALLOC.stats()
+    }
+}
+
 static __IWRAM_ALLOC: BlockAllocator = unsafe {
     BlockAllocator::new(StartEnd {
-        start: iwram_data_end,
-        end: || IWRAM_END,
+        start: iwram_heap_start,
+        end: iwram_heap_end,
     })
 };
 
+/// The end of the part of iwram which the allocator has ever used. Unless it
+/// was reserved elsewhere, the stack is above this.
+pub(crate) fn iwram_heap_high_water_mark() -> usize {
+    __IWRAM_ALLOC.high_water_mark()
+}
+
 #[cfg(any(test, feature = "testing"))]
 pub(crate) unsafe fn number_of_blocks() -> u32 {
     GLOBAL_ALLOC.number_of_blocks()
@@ -129,7 +221,7 @@ fn alloc_error(layout: Layout) -> ! {
     );
 }
 
-fn iwram_data_end() -> usize {
+pub(crate) fn iwram_data_end() -> usize {
     extern "C" {
         static __iwram_end: usize;
     }
@@ -139,14 +231,14 @@ fn iwram_data_end() -> usize {
     (unsafe { &__iwram_end }) as *const _ as usize
 }
 
-fn data_end() -> usize {
+pub(crate) fn data_end() -> usize {
     extern "C" {
-        static __ewram_data_end: usize;
+        static __ewram_end: usize;
     }
 
     // TODO: This seems completely wrong, but without the &, rust generates
     // a double dereference :/. Maybe a bug in nightly?
-    (unsafe { &__ewram_data_end }) as *const _ as usize
+    (unsafe { &__ewram_end }) as *const _ as usize
 }
 
 #[cfg(test)]
@@ -256,4 +348,23 @@ mod test {
             p
         );
     }
+
+    #[test_case]
+    fn stats_track_allocations(_gba: &mut crate::Gba) {
+        let before = ExternalAllocator::stats();
+        assert_eq!(before.used_bytes + before.free_bytes, before.total_bytes);
+        assert!(before.largest_free_block <= before.free_bytes);
+
+        let allocation = Box::new([0u8; 1024]);
+        let during = ExternalAllocator::stats();
+        assert!(
+            during.used_bytes >= before.used_bytes + 1024,
+            "used {} bytes before and {} after",
+            before.used_bytes,
+            during.used_bytes
+        );
+
+        drop(allocation);
+        assert_eq!(ExternalAllocator::stats().used_bytes, before.used_bytes);
+    }
 }
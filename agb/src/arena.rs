@@ -0,0 +1,193 @@
+#![deny(missing_docs)]
+//! A fast allocator for temporary data which only lasts for a frame.
+//!
+//! A [`FrameArena`] reserves a block of IWRAM and hands out parts of it in
+//! order, which is much faster than the normal allocator and can never
+//! fragment it. Nothing is freed individually, instead the whole arena is
+//! [reset](FrameArena::reset) once per frame. This suits temporary buffers
+//! such as sort keys, decompression scratch space and particle lists.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! #![feature(allocator_api)]
+//! # extern crate alloc;
+//! use agb::arena::FrameArena;
+//! use alloc::vec::Vec;
+//!
+//! # fn foo() {
+//! let vblank = agb::interrupt::VBlank::get();
+//! let mut arena = FrameArena::new(4096);
+//!
+//! loop {
+//!     let mut sort_keys = Vec::with_capacity_in(64, &arena);
+//!     sort_keys.push(5u32);
+//!     sort_keys.sort_unstable();
+//!     drop(sort_keys);
+//!
+//!     arena.reset();
+//!     vblank.wait_for_vblank();
+//! }
+//! # }
+//! ```
+//!
+//! Anything allocated must be dropped before the arena can be reset, which the
+//! borrow checker makes sure of.
+
+use alloc::alloc::{Allocator, Layout};
+use core::alloc::AllocError;
+use core::cell::Cell;
+use core::ptr::NonNull;
+
+use crate::InternalAllocator;
+
+/// The alignment of the block, which is the largest alignment which is
+/// useful on the GBA
+const ARENA_ALIGN: usize = 8;
+
+/// A block of IWRAM which is allocated from in order and freed all at once.
+///
+/// Use it as an allocator by passing `&arena` to functions like
+/// [`Vec::new_in`](alloc::vec::Vec::new_in), or put single values in it with
+/// [`alloc`](FrameArena::alloc).
+pub struct FrameArena {
+    memory: NonNull<u8>,
+    capacity: usize,
+    used: Cell<usize>,
+    peak: Cell<usize>,
+}
+
+impl FrameArena {
+    /// Reserves `capacity` bytes of IWRAM for the arena.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't enough free IWRAM.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        let memory = InternalAllocator
+            .allocate(Self::layout(capacity))
+            .expect("not enough iwram for the frame arena");
+
+        Self {
+            memory: memory.cast(),
+            capacity,
+            used: Cell::new(0),
+            peak: Cell::new(0),
+        }
+    }
+
+    fn layout(capacity: usize) -> Layout {
+        Layout::from_size_align(capacity, ARENA_ALIGN).expect("frame arena is too large")
+    }
+
+    /// Frees everything in the arena, ready for the next frame.
+    pub fn reset(&mut self) {
+        self.used.set(0);
+    }
+
+    /// Moves the value into the arena. Its destructor won't be run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena is full.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T>(&self, value: T) -> &mut T {
+        let memory = self
+            .allocate(Layout::new::<T>())
+            .expect("frame arena is full")
+            .cast::<T>();
+
+        unsafe {
+            memory.as_ptr().write(value);
+            &mut *memory.as_ptr()
+        }
+    }
+
+    /// The number of bytes which can be allocated in each frame.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of bytes allocated since the last reset, including padding
+    /// for alignment.
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// The most bytes which have been in use at once, which helps with
+    /// choosing the arena's capacity.
+    #[must_use]
+    pub fn peak(&self) -> usize {
+        self.peak.get()
+    }
+}
+
+unsafe impl Allocator for &FrameArena {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let base = self.memory.as_ptr() as usize;
+        let start = (base + self.used.get() + layout.align() - 1) & !(layout.align() - 1);
+        let end = start.checked_add(layout.size()).ok_or(AllocError)?;
+
+        if end > base + self.capacity {
+            return Err(AllocError);
+        }
+
+        self.used.set(end - base);
+        self.peak.set(self.peak.get().max(end - base));
+
+        let memory = core::ptr::slice_from_raw_parts_mut(start as *mut u8, layout.size());
+        NonNull::new(memory).ok_or(AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // the most recent allocation can be given back, which makes growing a
+        // vector that was allocated last cheaper
+        let base = self.memory.as_ptr() as usize;
+        if ptr.as_ptr() as usize + layout.size() == base + self.used.get() {
+            self.used.set(ptr.as_ptr() as usize - base);
+        }
+    }
+}
+
+impl Drop for FrameArena {
+    fn drop(&mut self) {
+        unsafe { InternalAllocator.deallocate(self.memory, Self::layout(self.capacity)) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test_case]
+    fn arena_allocates_in_iwram_until_reset(_gba: &mut crate::Gba) {
+        let mut arena = FrameArena::new(64);
+
+        {
+            let value = arena.alloc(5u8);
+            *value += 1;
+            assert_eq!(*value, 6);
+
+            let mut words = Vec::with_capacity_in(4, &arena);
+            words.extend([1u32, 2, 3, 4]);
+
+            let address = words.as_ptr() as usize;
+            assert!((0x0300_0000..0x0300_8000).contains(&address));
+            assert_eq!(address % 4, 0);
+            assert_eq!(arena.used(), 4 + 16);
+
+            assert!((&arena).allocate(Layout::new::<[u8; 64]>()).is_err());
+        }
+
+        arena.reset();
+        assert_eq!(arena.used(), 0);
+        assert_eq!(arena.peak(), 20);
+
+        let bytes = Vec::<u8, _>::with_capacity_in(64, &arena);
+        assert_eq!(bytes.capacity(), 64);
+    }
+}
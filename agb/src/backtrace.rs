@@ -0,0 +1,215 @@
+//! Turns the chain of functions which led to a panic into a short code, which
+//! players can copy off the [crash screen](crate::panic_screen) and send to
+//! the developer.
+//!
+//! There are no frame pointers or unwinding tables to follow, so the stack is
+//! scanned for words which are return addresses, meaning they point just past
+//! a `bl` (or a `bx` in ARM code) in the game's code. Stale return addresses
+//! left on the stack by functions which have already returned can also be
+//! picked up, so the result is a best guess rather than an exact backtrace.
+//!
+//! The code starts with a build id taken from the game's code, so reports from
+//! a different build of the game can be spotted, followed by each return
+//! address. It can be turned back into function names and lines by running
+//! `cargo run -- crash <code> --elf <path to the game's elf file>` in agb's
+//! `tools` directory.
+
+use core::arch::asm;
+use core::fmt;
+
+/// The most return addresses which are put in the code.
+const MAX_FRAMES: usize = 10;
+
+/// Where the BIOS starts the interrupt stack.
+const INTERRUPT_STACK_TOP: usize = 0x0300_7FA0;
+
+const IWRAM_START: usize = 0x0300_0000;
+
+/// Crockford's base 32, which leaves out the letters which are easily mistaken
+/// for digits.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The number of characters the build id takes up at the start of the code.
+const BUILD_ID_CHARACTERS: usize = 4;
+/// How far apart the words of code which go into the build id are.
+const BUILD_ID_STRIDE: usize = 256;
+/// The start of the rom header is changed by `gbafix`, so isn't used for the
+/// build id.
+const BUILD_ID_SKIP: usize = 0x100;
+
+const REGION_TEXT: u32 = 0;
+const REGION_IWRAM: u32 = 1;
+
+/// The build id and return addresses found on the stack when the panic
+/// happened.
+pub(crate) struct Backtrace {
+    build_id: u32,
+    frames: [u32; MAX_FRAMES],
+    len: usize,
+}
+
+impl Backtrace {
+    /// Scans the stack above the caller for return addresses.
+    #[inline(never)]
+    pub(crate) fn capture() -> Self {
+        let sp: usize;
+        unsafe { asm!("mov {}, sp", out(reg) sp, options(nomem, nostack)) };
+
+        let stack_top = crate::mem::stack_top();
+        let top = if sp < stack_top {
+            stack_top
+        } else {
+            INTERRUPT_STACK_TOP
+        };
+
+        let mut backtrace = Backtrace {
+            build_id: build_id(),
+            frames: [0; MAX_FRAMES],
+            len: 0,
+        };
+
+        for address in (sp..top).step_by(4) {
+            if backtrace.len == MAX_FRAMES {
+                break;
+            }
+
+            let word = unsafe { (address as *const u32).read_volatile() };
+            if let Some(frame) = encode_return_address(word as usize) {
+                backtrace.frames[backtrace.len] = frame;
+                backtrace.len += 1;
+            }
+        }
+
+        backtrace
+    }
+
+    fn write_characters(&self, mut character: impl FnMut(u8)) {
+        for i in (0..BUILD_ID_CHARACTERS).rev() {
+            character(ALPHABET[(self.build_id >> (i * 5)) as usize & 31]);
+        }
+
+        // each character holds 4 bits of the frame, lowest first, along with
+        // whether any more characters of the same frame follow
+        for &frame in &self.frames[..self.len] {
+            let mut frame = frame;
+            loop {
+                let more = frame >= 16;
+                character(ALPHABET[(frame & 15) as usize | (usize::from(more) << 4)]);
+                frame >>= 4;
+
+                if !more {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Writes the code in groups of 4 characters.
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut written = 0;
+        let mut result = Ok(());
+
+        self.write_characters(|c| {
+            if written > 0 && written % 4 == 0 {
+                result = result.and_then(|_| f.write_str("-"));
+            }
+            result = result.and_then(|_| fmt::Write::write_char(f, char::from(c)));
+            written += 1;
+        });
+
+        result
+    }
+}
+
+/// A hash of words spread through the game's code, which is almost always
+/// different between builds but is quick to calculate.
+fn build_id() -> u32 {
+    let start = linker_address!(__text_start);
+    let end = linker_address!(__text_end);
+
+    let mut hash = fnv1a(FNV_OFFSET_BASIS, (end - start) as u32);
+    for address in (start + BUILD_ID_SKIP..end).step_by(BUILD_ID_STRIDE) {
+        hash = fnv1a(hash, unsafe { (address as *const u32).read_volatile() });
+    }
+
+    (hash ^ (hash >> 20)) & 0xF_FFFF
+}
+
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+
+fn fnv1a(hash: u32, word: u32) -> u32 {
+    word.to_le_bytes().iter().fold(hash, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// If `address` is just after a call in the game's code, returns it as an
+/// offset into the code's region with the region in the lowest bit.
+fn encode_return_address(address: usize) -> Option<u32> {
+    let text = linker_address!(__text_start)..linker_address!(__text_end);
+    let iwram = IWRAM_START..linker_address!(__iwram_data_end);
+
+    let (region, start) = if text.contains(&address) && text.contains(&(address - 4)) {
+        (REGION_TEXT, text.start)
+    } else if iwram.contains(&address) && iwram.contains(&(address - 4)) {
+        (REGION_IWRAM, iwram.start)
+    } else {
+        return None;
+    };
+
+    if !follows_call(address) {
+        return None;
+    }
+
+    Some(((address - start) as u32) << 1 | region)
+}
+
+/// Whether `address` is where a call returns to. Thumb return addresses have
+/// their lowest bit set.
+fn follows_call(address: usize) -> bool {
+    if address & 1 == 1 {
+        let instruction =
+            |offset| unsafe { ((address - 1 - offset) as *const u16).read_volatile() };
+
+        // the two halves of a thumb `bl`
+        instruction(4) & 0xF800 == 0xF000 && instruction(2) & 0xF800 == 0xF800
+    } else if address & 3 == 0 {
+        let instruction = unsafe { ((address - 4) as *const u32).read_volatile() };
+
+        // `bl` or `bx`, which is used after `mov lr, pc` to call through a register
+        instruction & 0x0F00_0000 == 0x0B00_0000 || instruction & 0x0FFF_FFF0 == 0x012F_FF10
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    #[test_case]
+    fn codes_are_grouped_base_32(_gba: &mut crate::Gba) {
+        let mut backtrace = Backtrace {
+            build_id: 0xA_BCDE,
+            frames: [0; MAX_FRAMES],
+            len: 2,
+        };
+        backtrace.frames[0] = 0x12_3456;
+        backtrace.frames[1] = 7;
+
+        assert_eq!(backtrace.to_string(), "NF6Y-PNMK-J17");
+    }
+
+    #[test_case]
+    fn the_callers_of_capture_are_found(_gba: &mut crate::Gba) {
+        let backtrace = Backtrace::capture();
+
+        assert!(backtrace.len > 0);
+        assert_eq!(backtrace.build_id, build_id());
+        assert!(backtrace.build_id <= 0xF_FFFF);
+    }
+}
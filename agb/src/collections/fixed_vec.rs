@@ -0,0 +1,307 @@
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+/// A list which can hold up to `N` items, stored inline without allocating.
+///
+/// It dereferences to a slice, so it has all of the slice methods such as
+/// [`iter`](slice::iter) and [`sort`](slice::sort_unstable).
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::collections::FixedVec;
+///
+/// # fn foo() {
+/// let mut enemies: FixedVec<u8, 16> = FixedVec::new();
+/// enemies.push(3).unwrap();
+/// enemies.push(5).unwrap();
+///
+/// enemies.retain(|&health| health > 4);
+/// assert_eq!(&enemies[..], &[5]);
+/// # }
+/// ```
+pub struct FixedVec<T, const N: usize> {
+    items: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> FixedVec<T, N> {
+    /// Creates an empty list.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            // an array of uninitialised values doesn't need initialising
+            items: unsafe { MaybeUninit::uninit().assume_init() },
+            len: 0,
+        }
+    }
+
+    /// The maximum number of items the list can hold.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of items in the list.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the list has no items.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the list can't hold any more items.
+    #[must_use]
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Adds an item to the end of the list, or gives it back if the list is
+    /// full.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
+        }
+
+        self.items[self.len].write(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the last item from the list.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.len -= 1;
+        Some(unsafe { self.items[self.len].assume_init_read() })
+    }
+
+    /// Inserts an item at `index`, moving everything after it along by one, or
+    /// gives it back if the list is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn insert(&mut self, index: usize, item: T) -> Result<(), T> {
+        assert!(index <= self.len, "index out of bounds");
+        if self.is_full() {
+            return Err(item);
+        }
+
+        unsafe {
+            let place = self.items.as_mut_ptr().add(index);
+            ptr::copy(place, place.add(1), self.len - index);
+            (*place).write(item);
+        }
+
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the item at `index`, moving everything after it back by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "index out of bounds");
+
+        unsafe {
+            let place = self.items.as_mut_ptr().add(index);
+            let item = (*place).assume_init_read();
+            ptr::copy(place.add(1), place, self.len - index - 1);
+
+            self.len -= 1;
+            item
+        }
+    }
+
+    /// Removes the item at `index`, replacing it with the last item. This is
+    /// faster than [`remove`](FixedVec::remove) but changes the order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "index out of bounds");
+
+        let last = self.len - 1;
+        self.swap(index, last);
+        self.pop().unwrap()
+    }
+
+    /// Removes every item after the first `len`.
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+
+    /// Removes every item.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Keeps only the items for which `keep` returns true, in the same order.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        let len = self.len;
+        let mut kept = 0;
+
+        // if `keep` panics the items are leaked rather than dropped twice
+        self.len = 0;
+
+        for index in 0..len {
+            let item = unsafe { self.items[index].assume_init_read() };
+
+            if keep(&item) {
+                self.items[kept].write(item);
+                kept += 1;
+            }
+        }
+
+        self.len = kept;
+    }
+
+    /// The items as a slice.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { &*(ptr::slice_from_raw_parts(self.items.as_ptr().cast(), self.len)) }
+    }
+
+    /// The items as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { &mut *(ptr::slice_from_raw_parts_mut(self.items.as_mut_ptr().cast(), self.len)) }
+    }
+}
+
+impl<T, const N: usize> Default for FixedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for FixedVec<T, N> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+impl<T, const N: usize> Deref for FixedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for FixedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for FixedVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new();
+        for item in self.iter() {
+            let _ = clone.push(item.clone());
+        }
+        clone
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for FixedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for FixedVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for FixedVec<T, N> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a FixedVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut FixedVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = core::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use super::*;
+
+    #[test_case]
+    fn fixed_vec_behaves_like_a_vec(_gba: &mut crate::Gba) {
+        let mut list: FixedVec<i32, 4> = FixedVec::new();
+        assert!(list.is_empty());
+
+        list.push(1).unwrap();
+        list.push(3).unwrap();
+        list.insert(1, 2).unwrap();
+        list.push(4).unwrap();
+        assert_eq!(list.push(5), Err(5));
+        assert_eq!(&list[..], &[1, 2, 3, 4]);
+
+        assert_eq!(list.remove(0), 1);
+        assert_eq!(list.swap_remove(0), 2);
+        assert_eq!(&list[..], &[4, 3]);
+
+        list.retain(|&x| x != 4);
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), None);
+    }
+
+    #[test_case]
+    fn fixed_vec_drops_its_items(_gba: &mut crate::Gba) {
+        struct Counted<'a>(&'a Cell<u32>);
+
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        {
+            let mut list: FixedVec<Counted, 4> = FixedVec::new();
+            for _ in 0..4 {
+                assert!(list.push(Counted(&drops)).is_ok());
+            }
+
+            list.retain(|_| false);
+            assert_eq!(drops.get(), 4);
+
+            for _ in 0..3 {
+                assert!(list.push(Counted(&drops)).is_ok());
+            }
+        }
+
+        assert_eq!(drops.get(), 7);
+    }
+}
@@ -0,0 +1,18 @@
+#![deny(missing_docs)]
+//! Collections with a fixed capacity, which are stored inline rather than
+//! allocating, so they can be used in statics and interrupt handlers and their
+//! size is known up front.
+//!
+//! * [`FixedVec`] is like a [`Vec`](alloc::vec::Vec) which can hold up to `N`
+//!   items.
+//! * [`RingBuffer`] is a first in, first out queue which can hold up to `N`
+//!   items, for example for buffering input or messages between an interrupt
+//!   and the game.
+//!
+//! For a hash map, see [`HashMap`](crate::hash_map::HashMap).
+
+mod fixed_vec;
+mod ring_buffer;
+
+pub use fixed_vec::FixedVec;
+pub use ring_buffer::RingBuffer;
@@ -0,0 +1,222 @@
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::{Index, IndexMut};
+
+/// A first in, first out queue which can hold up to `N` items, stored inline
+/// without allocating.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::collections::RingBuffer;
+///
+/// # fn foo() {
+/// // remember the last 8 button presses for detecting combos
+/// let mut presses: RingBuffer<u16, 8> = RingBuffer::new();
+///
+/// presses.push_overwriting(1);
+/// presses.push_overwriting(2);
+///
+/// assert_eq!(presses.pop(), Some(1));
+/// # }
+/// ```
+pub struct RingBuffer<T, const N: usize> {
+    items: [MaybeUninit<T>; N],
+    start: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    /// Creates an empty queue.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            // an array of uninitialised values doesn't need initialising
+            items: unsafe { MaybeUninit::uninit().assume_init() },
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// The maximum number of items the queue can hold.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of items in the queue.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the queue has no items.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the queue can't hold any more items.
+    #[must_use]
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn slot(&self, index: usize) -> usize {
+        (self.start + index) % N
+    }
+
+    /// Adds an item to the back of the queue, or gives it back if the queue is
+    /// full.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
+        }
+
+        let slot = self.slot(self.len);
+        self.items[slot].write(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Adds an item to the back of the queue, removing and returning the item
+    /// at the front if the queue is full.
+    pub fn push_overwriting(&mut self, item: T) -> Option<T> {
+        let removed = if self.is_full() { self.pop() } else { None };
+
+        if self.push(item).is_err() {
+            // only possible when the capacity is 0
+            return None;
+        }
+
+        removed
+    }
+
+    /// Removes the item at the front of the queue.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let item = unsafe { self.items[self.start].assume_init_read() };
+        self.start = self.slot(1);
+        self.len -= 1;
+        Some(item)
+    }
+
+    /// The item at the front of the queue, which is the next to be popped.
+    #[must_use]
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// The item at the back of the queue, which was the last to be pushed.
+    #[must_use]
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|last| self.get(last))
+    }
+
+    /// The item `index` places from the front of the queue.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        (index < self.len).then(|| unsafe { self.items[self.slot(index)].assume_init_ref() })
+    }
+
+    /// The item `index` places from the front of the queue, mutably.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+
+        let slot = self.slot(index);
+        Some(unsafe { self.items[slot].assume_init_mut() })
+    }
+
+    /// Removes every item.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Iterates over the items from front to back.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator + '_ {
+        (0..self.len).map(move |index| &self[index])
+    }
+}
+
+impl<T, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for RingBuffer<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Index<usize> for RingBuffer<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for RingBuffer<T, N> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for RingBuffer<T, N> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new();
+        for item in self.iter() {
+            let _ = clone.push(item.clone());
+        }
+        clone
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for RingBuffer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn ring_buffer_wraps_around(_gba: &mut crate::Gba) {
+        let mut queue: RingBuffer<u32, 3> = RingBuffer::new();
+        assert_eq!(queue.pop(), None);
+
+        for i in 0..3 {
+            queue.push(i).unwrap();
+        }
+        assert_eq!(queue.push(3), Err(3));
+
+        assert_eq!(queue.pop(), Some(0));
+        queue.push(3).unwrap();
+        assert_eq!(queue.front(), Some(&1));
+        assert_eq!(queue.back(), Some(&3));
+
+        assert_eq!(queue.push_overwriting(4), Some(1));
+        assert!(queue.iter().copied().eq([2, 3, 4]));
+
+        assert!(queue.iter().rev().copied().eq([4, 3, 2]));
+        assert_eq!(queue[1], 3);
+
+        *queue.get_mut(0).unwrap() = 5;
+        queue[1] = 6;
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.pop(), Some(6));
+
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.back(), None);
+    }
+}
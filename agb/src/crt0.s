@@ -1,13 +1,14 @@
 
     .arm
     .global __start
-    .section .crt0
+    .section .crt0, "ax"
     .align
 __start:
     b .Initialise
 
-    @ Filled in by gbafix
-    .fill 188, 1, 0
+    @ The linker script puts the rom header from #[agb::entry] here
+
+    .section .crt0.boot, "ax"
 
     @ multiboot launch point
 b .Initialise_mb
@@ -45,7 +46,8 @@ b .Initialise_mb
                                                @ see: https://mgba-emu.github.io/gbatek/#swi-0bh-gbands7nds9dsi7dsi9---cpuset
 
 .CommonInit:
-    @ set the waitstate control register to the normal value used in manufactured cartridges
+    @ set the waitstate control register to the normal value used in manufactured cartridges,
+    @ which is agb::waitstate::WaitStates::STANDARD
     ldr r0, =0x04000204 @ address for waitstate control register
     ldr r1, =0x4317     @ WS0/ROM=3,1 clks; SRAM=8 clks; WS2/EEPROM: 8,8 clks; prefetch enabled
     strh r1, [r0]
@@ -56,6 +58,21 @@ b .Initialise_mb
     ldr r2, =__iwram_rom_length_halfwords
     swi 0x000B0000
 
+    @ use the stack reserved with #[agb::entry] if there is one, otherwise keep the one the BIOS set up
+    ldr r0, =__stack_bottom
+    ldr r1, =__stack_top
+    cmp r0, r1
+    movne sp, r1
+    ldreq r0, =__iwram_heap_end
+
+    @ fill the unused memory below the stack with a pattern, so agb::mem::report can find how deep the stack has been
+    mov r1, sp
+    ldr r2, =0x57ac57ac
+1:
+    cmp r0, r1
+    strlo r2, [r0], #4
+    blo 1b
+
     @ enable interrupts
     ldr r0, =0x04000208
     ldr r1, =1
@@ -0,0 +1,443 @@
+#![deny(missing_docs)]
+//! Drawing points, lines and rectangles on top of the game, for seeing things
+//! which are normally invisible such as hit boxes, paths and sensor rays.
+//!
+//! A [`DebugDraw`] draws with a pool of objects of its own, so nothing about
+//! how the game draws itself needs to change. Everything drawn stays on
+//! screen until [`clear`](DebugDraw::clear) is called, which is usually done
+//! at the start of each frame. In release builds nothing is drawn, so the
+//! calls can be left in the game.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::debug::{DebugColour, DebugDraw};
+//! use agb::fixnum::Rect;
+//!
+//! # fn foo(gba: &mut agb::Gba) {
+//! let vblank = agb::interrupt::VBlank::get();
+//! let objects = gba.display.object.get();
+//! let mut debug = DebugDraw::new(&objects, 32);
+//!
+//! loop {
+//!     debug.clear();
+//!
+//!     let player = Rect::new((100, 60).into(), (16, 24).into());
+//!     debug.draw_rect(player, DebugColour::Green);
+//!     debug.draw_line((108, 72).into(), (140, 90).into(), DebugColour::Red);
+//!
+//!     vblank.wait_for_vblank();
+//!     objects.commit();
+//! }
+//! # }
+//! ```
+//!
+//! Each object can draw a dot or a straight line of up to 32 pixels, so
+//! rectangle outlines use a few objects each. Lines which aren't horizontal
+//! or vertical are drawn dotted to save objects. Once the pool is used up,
+//! anything else drawn that frame is left out.
+//!
+//! This module also has [`debug_assert`](crate::debug_assert),
+//! [`debug_assert_eq`](crate::debug_assert_eq) and
+//! [`debug_assert_ne`](crate::debug_assert_ne), which work like the standard
+//! library's versions but also write the failure to the mGBA log at the error
+//! level before showing it on the screen. Like the standard versions, they
+//! aren't checked in release builds, so they cost nothing in the finished
+//! game.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::display::object::{
+    AlignedAs, DynamicSprite, Object, ObjectController, PaletteVram, Size, SpriteBorrow,
+};
+use crate::display::palette16::Palette16;
+use crate::display::Priority;
+use crate::fixnum::{Rect, Vector2D};
+use crate::shapes::Line;
+
+/// The colours that can be drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum DebugColour {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+    White,
+}
+
+const COLOURS: usize = 5;
+
+const PALETTE: Palette16 = Palette16::new([
+    0x0000, 0x001f, 0x03e0, 0x7c00, 0x03ff, 0x7fff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+]);
+
+/// Lines which aren't horizontal or vertical have a dot every this many
+/// pixels.
+const DOT_SPACING: usize = 3;
+
+/// The pieces that shapes are drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Piece {
+    Dot,
+    Horizontal8,
+    Horizontal32,
+    Vertical8,
+    Vertical32,
+}
+
+const PIECES: usize = 5;
+
+impl Piece {
+    fn size(self) -> Size {
+        match self {
+            Piece::Dot | Piece::Horizontal8 | Piece::Vertical8 => Size::S8x8,
+            Piece::Horizontal32 => Size::S32x8,
+            Piece::Vertical32 => Size::S8x32,
+        }
+    }
+
+    /// The number of pixels of line the piece draws.
+    fn length(self) -> i32 {
+        match self {
+            Piece::Dot => 1,
+            Piece::Horizontal8 | Piece::Vertical8 => 8,
+            Piece::Horizontal32 | Piece::Vertical32 => 32,
+        }
+    }
+
+    /// Creates the sprite for this piece, which is a line along the top or
+    /// left edge of the sprite.
+    fn sprite(self, colour: DebugColour, palette: PaletteVram) -> SpriteBorrow {
+        let mut data = AlignedAs::<u16, [u8; 128]> {
+            _align: [],
+            bytes: [0; 128],
+        };
+
+        let size = self.size();
+        let (width, height) = size.to_width_height();
+        let colour_index = colour as u8 + 1;
+
+        for y in 0..height {
+            for x in 0..width {
+                let drawn = match self {
+                    Piece::Dot => x == 0 && y == 0,
+                    Piece::Horizontal8 | Piece::Horizontal32 => y == 0,
+                    Piece::Vertical8 | Piece::Vertical32 => x == 0,
+                };
+
+                if drawn {
+                    // tiles are stored one after the other, and each byte of a
+                    // tile is two pixels with the left one in the low nibble
+                    let tile = (y / 8) * (width / 8) + x / 8;
+                    let byte = tile * 32 + (y % 8) * 4 + (x % 8) / 2;
+                    data.bytes[byte] |= colour_index << ((x % 2) * 4);
+                }
+            }
+        }
+
+        let length = (width / 8) * (height / 8) * 32;
+        DynamicSprite::new(&data.bytes[..length], size).to_vram(palette)
+    }
+}
+
+/// Draws shapes on top of the game using a pool of objects. See the
+/// [module level documentation](self) for more.
+pub struct DebugDraw<'a> {
+    controller: &'a ObjectController,
+    palette: Option<PaletteVram>,
+    sprites: [Option<SpriteBorrow>; PIECES * COLOURS],
+    objects: Vec<Object<'a>>,
+    max_objects: usize,
+    used: usize,
+}
+
+impl<'a> DebugDraw<'a> {
+    /// Creates a debug drawer which uses at most `max_objects` objects at a
+    /// time. Objects and sprite memory are only used once something is drawn.
+    #[must_use]
+    pub fn new(controller: &'a ObjectController, max_objects: usize) -> Self {
+        Self {
+            controller,
+            palette: None,
+            sprites: Default::default(),
+            objects: Vec::new(),
+            max_objects,
+            used: 0,
+        }
+    }
+
+    /// Removes everything which has been drawn.
+    pub fn clear(&mut self) {
+        for object in &mut self.objects[..self.used] {
+            object.hide();
+        }
+
+        self.used = 0;
+    }
+
+    /// Draws a single pixel.
+    pub fn draw_point(&mut self, point: Vector2D<i32>, colour: DebugColour) {
+        self.place(Piece::Dot, point, colour);
+    }
+
+    /// Draws a line from `start` to `end`, including both ends.
+    pub fn draw_line(&mut self, start: Vector2D<i32>, end: Vector2D<i32>, colour: DebugColour) {
+        if start.y == end.y {
+            self.draw_span(start.min(end), (end.x - start.x).abs() + 1, true, colour);
+        } else if start.x == end.x {
+            self.draw_span(start.min(end), (end.y - start.y).abs() + 1, false, colour);
+        } else {
+            let line = Line::new(start, end);
+            let ends_on_a_dot = (line.len() - 1) % DOT_SPACING == 0;
+
+            for point in line.step_by(DOT_SPACING) {
+                self.draw_point(point, colour);
+            }
+
+            if !ends_on_a_dot {
+                self.draw_point(end, colour);
+            }
+        }
+    }
+
+    /// Draws the outline of `rect`.
+    pub fn draw_rect(&mut self, rect: Rect<i32>, colour: DebugColour) {
+        if rect.size.x <= 0 || rect.size.y <= 0 {
+            return;
+        }
+
+        let bottom_right = rect.position + rect.size - (1, 1).into();
+
+        self.draw_span(rect.position, rect.size.x, true, colour);
+        self.draw_span(
+            (rect.position.x, bottom_right.y).into(),
+            rect.size.x,
+            true,
+            colour,
+        );
+        self.draw_span(rect.position, rect.size.y, false, colour);
+        self.draw_span(
+            (bottom_right.x, rect.position.y).into(),
+            rect.size.y,
+            false,
+            colour,
+        );
+    }
+
+    /// Draws a horizontal or vertical line of the given length from `start`,
+    /// using the longest pieces which fit. The last piece overlaps the one
+    /// before so that the line ends in the right place.
+    fn draw_span(
+        &mut self,
+        start: Vector2D<i32>,
+        length: i32,
+        horizontal: bool,
+        colour: DebugColour,
+    ) {
+        let piece = match (length, horizontal) {
+            (32.., true) => Piece::Horizontal32,
+            (32.., false) => Piece::Vertical32,
+            (8.., true) => Piece::Horizontal8,
+            (8.., false) => Piece::Vertical8,
+            _ => Piece::Dot,
+        };
+
+        let direction: Vector2D<i32> = if horizontal { (1, 0) } else { (0, 1) }.into();
+        let pieces = (length + piece.length() - 1) / piece.length();
+
+        for i in 0..pieces {
+            let offset = (i * piece.length()).min(length - piece.length());
+            self.place(piece, start + direction * offset, colour);
+        }
+    }
+
+    fn place(&mut self, piece: Piece, position: Vector2D<i32>, colour: DebugColour) {
+        if !cfg!(debug_assertions) || self.used >= self.max_objects {
+            return;
+        }
+
+        let sprite = self.sprite(piece, colour);
+
+        if self.used == self.objects.len() {
+            let Some(mut object) = self.controller.try_get_object(sprite) else {
+                // the game is using all the objects
+                self.max_objects = self.used;
+                return;
+            };
+
+            object.set_priority(Priority::P0).set_z(i32::MIN);
+            self.objects.push(object);
+        } else {
+            self.objects[self.used].set_sprite(sprite);
+        }
+
+        self.objects[self.used].set_position(position).show();
+        self.used += 1;
+    }
+
+    fn sprite(&mut self, piece: Piece, colour: DebugColour) -> SpriteBorrow {
+        let palette = self
+            .palette
+            .get_or_insert_with(|| PaletteVram::new(&PALETTE).expect("no space for the palette"))
+            .clone();
+
+        self.sprites[piece as usize * COLOURS + colour as usize]
+            .get_or_insert_with(|| piece.sprite(colour, palette))
+            .clone()
+    }
+}
+
+/// Asserts that the condition is true in debug builds, logging the condition
+/// and where it is to the mGBA log before panicking if it isn't. A message can
+/// be given in the same way as [`panic`].
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # fn foo(health: i32) {
+/// agb::debug_assert!(health >= 0);
+/// agb::debug_assert!(health <= 100, "health is {}", health);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! debug_assert {
+    ($condition: expr $(,)?) => {
+        $crate::debug_assert!($condition, "assertion failed: {}", stringify!($condition))
+    };
+    ($condition: expr, $($message: tt)+) => {
+        if cfg!(debug_assertions) && !$condition {
+            $crate::debug::assertion_failed(format_args!($($message)+));
+        }
+    };
+}
+
+/// Asserts that two values are equal in debug builds, logging both of them and
+/// where the assertion is to the mGBA log before panicking if they aren't.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # fn foo(tiles: &[u16]) {
+/// agb::debug_assert_eq!(tiles.len(), 30 * 20);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! debug_assert_eq {
+    ($left: expr, $right: expr $(,)?) => {
+        $crate::debug_assert_eq!($left, $right, "")
+    };
+    ($left: expr, $right: expr, $($message: tt)+) => {
+        if cfg!(debug_assertions) {
+            match (&$left, &$right) {
+                (left, right) => {
+                    if !(*left == *right) {
+                        $crate::debug::assertion_failed(format_args!(
+                            "assertion failed: `{} == {}` {}\n  left: {:?}\n right: {:?}",
+                            stringify!($left),
+                            stringify!($right),
+                            format_args!($($message)+),
+                            left,
+                            right
+                        ));
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Asserts that two values are not equal in debug builds, logging the value
+/// and where the assertion is to the mGBA log before panicking if they are.
+#[macro_export]
+macro_rules! debug_assert_ne {
+    ($left: expr, $right: expr $(,)?) => {
+        $crate::debug_assert_ne!($left, $right, "")
+    };
+    ($left: expr, $right: expr, $($message: tt)+) => {
+        if cfg!(debug_assertions) {
+            match (&$left, &$right) {
+                (left, right) => {
+                    if *left == *right {
+                        $crate::debug::assertion_failed(format_args!(
+                            "assertion failed: `{} != {}` {}\n both: {:?}",
+                            stringify!($left),
+                            stringify!($right),
+                            format_args!($($message)+),
+                            left
+                        ));
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Used by the debug assertion macros to report a failure.
+#[doc(hidden)]
+#[track_caller]
+#[cold]
+pub fn assertion_failed(message: fmt::Arguments) -> ! {
+    let location = core::panic::Location::caller();
+    crate::eprintln!("{} at {}", message, location);
+
+    panic!("{}", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn passing_debug_assertions_do_nothing(_gba: &mut crate::Gba) {
+        let mut evaluated = 0;
+        let mut count = || {
+            evaluated += 1;
+            evaluated
+        };
+
+        crate::debug_assert!(count() == 1);
+        crate::debug_assert!(true, "with a message {}", 5);
+        crate::debug_assert_eq!(2 + 2, 4);
+        crate::debug_assert_ne!(count(), 0, "count was {}", evaluated);
+
+        let expected = if cfg!(debug_assertions) { 2 } else { 0 };
+        assert_eq!(evaluated, expected);
+    }
+
+    #[test_case]
+    fn shapes_use_objects_from_the_pool(gba: &mut crate::Gba) {
+        if !cfg!(debug_assertions) {
+            // nothing is drawn in release builds
+            return;
+        }
+
+        let objects = gba.display.object.get();
+        let mut debug = DebugDraw::new(&objects, 12);
+
+        debug.draw_point((3, 4).into(), DebugColour::White);
+        assert_eq!(debug.used, 1);
+
+        // 40 pixels takes a 32 pixel piece and another overlapping it
+        debug.draw_line((10, 10).into(), (49, 10).into(), DebugColour::Red);
+        assert_eq!(debug.used, 3);
+
+        // each side of a 16x16 rectangle takes two 8 pixel pieces
+        debug.draw_rect(
+            Rect::new((60, 60).into(), (16, 16).into()),
+            DebugColour::Green,
+        );
+        assert_eq!(debug.used, 11);
+
+        debug.draw_line((0, 0).into(), (20, 30).into(), DebugColour::Blue);
+        assert_eq!(debug.used, 12);
+        objects.commit();
+
+        debug.clear();
+        assert_eq!(debug.used, 0);
+        debug.draw_point((5, 5).into(), DebugColour::Yellow);
+        assert_eq!(debug.objects.len(), 12);
+        objects.commit();
+    }
+}
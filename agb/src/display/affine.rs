@@ -164,6 +164,59 @@ impl AffineMatrix {
         (-self.x, -self.y).into()
     }
 
+    #[must_use]
+    /// Transforms a point the same way as the hardware, which is from a pixel
+    /// on the screen to the pixel of the background or object displayed there.
+    /// This is useful for hit testing, for example to find which tile of a
+    /// rotated background the player is standing on.
+    ///
+    /// For objects, both points are relative to the centre of the object.
+    /// ```rust,no_run
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::fixnum::{Num, Vector2D};
+    /// use agb::display::affine::AffineMatrix;
+    ///
+    /// # fn foo() {
+    /// let matrix = AffineMatrix::from_translation((10, 0).into());
+    /// let point: Vector2D<Num<i32, 8>> = (15, 5).into();
+    /// assert_eq!(matrix.transform_point(point), (5, 5).into());
+    /// # }
+    /// ```
+    pub fn transform_point(&self, point: Vector2D<Num<i32, 8>>) -> Vector2D<Num<i32, 8>> {
+        (
+            self.a * point.x + self.b * point.y + self.x,
+            self.c * point.x + self.d * point.y + self.y,
+        )
+            .into()
+    }
+
+    #[must_use]
+    /// The matrix which undoes this one, so it transforms a pixel of the
+    /// background or object to where it is displayed on the screen. Returns
+    /// `None` if there isn't one, which happens when something is scaled to
+    /// nothing in some direction.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.a * self.d - self.b * self.c;
+        if determinant == 0.into() {
+            return None;
+        }
+
+        let a = self.d / determinant;
+        let b = -self.b / determinant;
+        let c = -self.c / determinant;
+        let d = self.a / determinant;
+
+        Some(AffineMatrix {
+            a,
+            b,
+            c,
+            d,
+            x: -(a * self.x + b * self.y),
+            y: -(c * self.x + d * self.y),
+        })
+    }
+
     /// Attempts to convert the matrix to one which can be used in affine
     /// backgrounds.
     pub fn try_to_background(&self) -> Result<AffineMatrixBackground, OverflowError> {
@@ -343,6 +396,17 @@ impl TryFrom<AffineMatrix> for AffineMatrixObject {
 }
 
 impl AffineMatrixObject {
+    /// The raw values of a, b, c and d in the form they are stored in object
+    /// attribute memory.
+    pub(crate) fn components(self) -> [u16; 4] {
+        [
+            self.a.to_raw() as u16,
+            self.b.to_raw() as u16,
+            self.c.to_raw() as u16,
+            self.d.to_raw() as u16,
+        ]
+    }
+
     #[must_use]
     /// Converts to the affine matrix that is usable in performing efficient
     /// calculations.
@@ -408,4 +472,23 @@ mod tests {
         assert_eq!(e.position(), position);
         assert_eq!(d * d, AffineMatrix::identity());
     }
+
+    #[test_case]
+    fn inverse_undoes_the_transformation(_: &mut crate::Gba) {
+        let matrix = AffineMatrix::from_translation((-8, -8).into())
+            * AffineMatrix::from_scale((2, 2).into())
+            * AffineMatrix::from_rotation::<8>(num!(0.25))
+            * AffineMatrix::from_translation((100, 50).into());
+
+        let inverse = matrix.inverse().unwrap();
+        assert_eq!(matrix * inverse, AffineMatrix::identity());
+
+        let point: Vector2D<Num<i32, 8>> = (20, 30).into();
+        assert_eq!(
+            inverse.transform_point(matrix.transform_point(point)),
+            point
+        );
+
+        assert_eq!(AffineMatrix::from_scale((0, 1).into()).inverse(), None);
+    }
 }
@@ -1,4 +1,6 @@
+use crate::fixnum::Vector2D;
 use crate::memory_mapped::MemoryMapped2DArray;
+use crate::shapes::Line;
 
 use super::{
     set_graphics_mode, set_graphics_settings, DisplayMode, GraphicsSettings, HEIGHT, WIDTH,
@@ -9,6 +11,64 @@ use core::convert::TryInto;
 const BITMAP_MODE_3: MemoryMapped2DArray<u16, { WIDTH as usize }, { HEIGHT as usize }> =
     unsafe { MemoryMapped2DArray::new(0x600_0000) };
 
+/// Includes an image as 15 bit colours for drawing with [Bitmap3::blit].
+/// Returns a [BitmapImage].
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::{display::bitmap3::BitmapImage, include_bitmap};
+/// const LOGO: BitmapImage = include_bitmap!("gfx/test_logo.png");
+/// ```
+#[macro_export]
+macro_rules! include_bitmap {
+    ($image_path: literal) => {{
+        use $crate::display::bitmap3::BitmapImage;
+        $crate::include_bitmap_inner!($image_path)
+    }};
+}
+
+/// An image made of 15 bit colours stored row by row, created by
+/// [include_bitmap](crate::include_bitmap).
+pub struct BitmapImage {
+    width: u16,
+    height: u16,
+    data: &'static [u16],
+}
+
+impl BitmapImage {
+    #[doc(hidden)]
+    /// Creates an image from its colours, used internally by
+    /// [include_bitmap](crate::include_bitmap).
+    #[must_use]
+    pub const fn new(width: u16, height: u16, data: &'static [u16]) -> Self {
+        assert!(data.len() == width as usize * height as usize);
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// The width of the image in pixels.
+    #[must_use]
+    pub const fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The height of the image in pixels.
+    #[must_use]
+    pub const fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The colour of the pixel at (x, y).
+    #[must_use]
+    pub fn colour(&self, x: u16, y: u16) -> u16 {
+        self.data[x as usize + y as usize * self.width as usize]
+    }
+}
+
 #[non_exhaustive]
 pub struct Bitmap3 {}
 
@@ -26,4 +86,130 @@ impl Bitmap3 {
         let y = y.try_into().unwrap();
         BITMAP_MODE_3.set(x, y, colour);
     }
+
+    /// Draws a pixel at (x, y) with the given colour. Unlike
+    /// [Bitmap3::draw_point], pixels outside of the screen are ignored.
+    pub fn draw_pixel(&mut self, x: i32, y: i32, colour: u16) {
+        if (0..WIDTH).contains(&x) && (0..HEIGHT).contains(&y) {
+            BITMAP_MODE_3.set(x as usize, y as usize, colour);
+        }
+    }
+
+    /// The colour of the pixel at (x, y), or [None] if it is outside of the
+    /// screen.
+    #[must_use]
+    pub fn pixel(&self, x: i32, y: i32) -> Option<u16> {
+        ((0..WIDTH).contains(&x) && (0..HEIGHT).contains(&y))
+            .then(|| BITMAP_MODE_3.get(x as usize, y as usize))
+    }
+
+    /// Fills the whole screen with a single colour.
+    pub fn clear(&mut self, colour: u16) {
+        self.fill_rect(0, 0, WIDTH, HEIGHT, colour);
+    }
+
+    /// Draws a line from (x0, y0) to (x1, y1) inclusive using Bresenham's line
+    /// algorithm. Any part of the line outside of the screen is ignored.
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, colour: u16) {
+        self.draw_points(Line::new((x0, y0).into(), (x1, y1).into()), colour);
+    }
+
+    /// Draws every point in `points`, such as the shapes in
+    /// [`shapes`](crate::shapes). Any points outside of the screen are ignored.
+    pub fn draw_points(&mut self, points: impl IntoIterator<Item = Vector2D<i32>>, colour: u16) {
+        for point in points {
+            self.draw_pixel(point.x, point.y, colour);
+        }
+    }
+
+    /// Draws the outline of the rectangle with its top left at (x, y). Any
+    /// part of the rectangle outside of the screen is ignored.
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: i32, height: i32, colour: u16) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        let (right, bottom) = (x + width - 1, y + height - 1);
+
+        self.draw_line(x, y, right, y, colour);
+        self.draw_line(x, bottom, right, bottom, colour);
+        self.draw_line(x, y, x, bottom, colour);
+        self.draw_line(right, y, right, bottom, colour);
+    }
+
+    /// Fills the rectangle with its top left at (x, y). Any part of the
+    /// rectangle outside of the screen is ignored.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, colour: u16) {
+        let (left, right) = (x.max(0), (x + width).min(WIDTH));
+        let (top, bottom) = (y.max(0), (y + height).min(HEIGHT));
+
+        for y in top..bottom {
+            for x in left..right {
+                BITMAP_MODE_3.set(x as usize, y as usize, colour);
+            }
+        }
+    }
+
+    /// Copies the image onto the screen with its top left at (x, y). Any part
+    /// of the image outside of the screen is ignored.
+    pub fn blit(&mut self, x: i32, y: i32, image: &BitmapImage) {
+        let (left, right) = (x.max(0), (x + i32::from(image.width)).min(WIDTH));
+        let (top, bottom) = (y.max(0), (y + i32::from(image.height)).min(HEIGHT));
+
+        for screen_y in top..bottom {
+            for screen_x in left..right {
+                let colour = image.colour((screen_x - x) as u16, (screen_y - y) as u16);
+                BITMAP_MODE_3.set(screen_x as usize, screen_y as usize, colour);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn lines_include_both_ends_and_clip(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.clear(0);
+
+        bitmap.draw_line(-5, 3, 4, 3, 0x1f);
+        assert_eq!(bitmap.pixel(0, 3), Some(0x1f));
+        assert_eq!(bitmap.pixel(4, 3), Some(0x1f));
+        assert_eq!(bitmap.pixel(5, 3), Some(0));
+
+        bitmap.draw_line(10, 10, 13, 16, 0x3e0);
+        assert_eq!(bitmap.pixel(10, 10), Some(0x3e0));
+        assert_eq!(bitmap.pixel(13, 16), Some(0x3e0));
+        let drawn = (10..=16)
+            .filter(|&y| (10..=13).any(|x| bitmap.pixel(x, y) == Some(0x3e0)))
+            .count();
+        assert_eq!(drawn, 7);
+
+        assert_eq!(bitmap.pixel(WIDTH, 0), None);
+    }
+
+    #[test_case]
+    fn rects_and_images_are_clipped_to_the_screen(gba: &mut crate::Gba) {
+        const TILES: BitmapImage = crate::include_bitmap!("examples/water_tiles.png");
+
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.clear(0);
+
+        bitmap.fill_rect(WIDTH - 2, HEIGHT - 2, 10, 10, 0x7fff);
+        assert_eq!(bitmap.pixel(WIDTH - 1, HEIGHT - 1), Some(0x7fff));
+        assert_eq!(bitmap.pixel(WIDTH - 3, HEIGHT - 1), Some(0));
+
+        bitmap.draw_rect(20, 20, 5, 4, 0x1f);
+        assert_eq!(bitmap.pixel(24, 23), Some(0x1f));
+        assert_eq!(bitmap.pixel(22, 21), Some(0));
+
+        bitmap.blit(-1, -2, &TILES);
+        assert_eq!(bitmap.pixel(0, 0), Some(TILES.colour(1, 2)));
+        assert_eq!(
+            bitmap.pixel(i32::from(TILES.width()) - 2, 5),
+            Some(TILES.colour(TILES.width() - 1, 7))
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use crate::fixnum::Vector2D;
+use crate::interrupt::VBlank;
 use crate::memory_mapped::{MemoryMapped1DArray, MemoryMapped2DArray};
 
 use super::{
@@ -38,19 +40,29 @@ impl Bitmap4 {
     /// whose colour is specified in the background palette. Panics if (x, y) is
     /// out of the bounds of the screen.
     pub fn draw_point_page(&mut self, x: i32, y: i32, colour: u8, page: Page) {
-        let addr = match page {
-            Page::Front => BITMAP_PAGE_FRONT_MODE_4,
-            Page::Back => BITMAP_PAGE_BACK_MODE_4,
-        };
+        assert!(
+            (0..WIDTH).contains(&x) && (0..HEIGHT).contains(&y),
+            "point ({}, {}) is outside of the screen",
+            x,
+            y
+        );
+
+        let addr = Self::page_memory(page);
 
         let x_in_screen = (x / 2) as usize;
         let y_in_screen = y as usize;
 
+        // video ram can only be written 16 bits at a time, so we need to keep the
+        // other pixel sharing this half word
         let c = addr.get(x_in_screen, y_in_screen);
         if x & 0b1 != 0 {
-            addr.set(x_in_screen, y_in_screen, c | u16::from(colour) << 8);
+            addr.set(
+                x_in_screen,
+                y_in_screen,
+                (c & 0x00ff) | u16::from(colour) << 8,
+            );
         } else {
-            addr.set(x_in_screen, y_in_screen, c | u16::from(colour));
+            addr.set(x_in_screen, y_in_screen, (c & 0xff00) | u16::from(colour));
         }
     }
 
@@ -58,16 +70,106 @@ impl Bitmap4 {
     /// index whose colour is specified in the background palette. Panics if (x,
     /// y) is out of the bounds of the screen.
     pub fn draw_point(&mut self, x: i32, y: i32, colour: u8) {
+        self.draw_point_page(x, y, colour, Self::back_page());
+    }
+
+    /// Draws every point in `points` on the non-current page, such as the
+    /// shapes in [`shapes`](crate::shapes). Any points outside of the screen
+    /// are ignored.
+    pub fn draw_points(&mut self, points: impl IntoIterator<Item = Vector2D<i32>>, colour: u8) {
+        let page = Self::back_page();
+        for point in points {
+            if (0..WIDTH).contains(&point.x) && (0..HEIGHT).contains(&point.y) {
+                self.draw_point_page(point.x, point.y, colour, page);
+            }
+        }
+    }
+
+    /// Copies a rectangle of colour indices onto the non-current page with its
+    /// top left corner at (x, y). `data` is stored row by row and each row is
+    /// `width` pixels long. Nothing is drawn if `data` is empty. Panics if any
+    /// part of the rectangle is out of the bounds of the screen, or if `width`
+    /// is 0 and there is data to draw.
+    pub fn blit(&mut self, x: i32, y: i32, width: usize, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        assert_ne!(width, 0, "a rectangle with data can't be 0 pixels wide");
+        assert_eq!(
+            data.len() % width,
+            0,
+            "data must contain a whole number of rows"
+        );
+
+        let page = Self::back_page();
+        let addr = Self::page_memory(page);
+
+        for (row_index, row) in data.chunks_exact(width).enumerate() {
+            let y = y + row_index as i32;
+
+            let mut row = row;
+            let mut x = x;
+
+            if x & 0b1 != 0 {
+                self.draw_point_page(x, y, row[0], page);
+                row = &row[1..];
+                x += 1;
+            }
+
+            let (pairs, remainder) = row.as_chunks::<2>();
+            for (pair_index, &[left, right]) in pairs.iter().enumerate() {
+                let x = x + 2 * pair_index as i32;
+                assert!(
+                    (0..WIDTH - 1).contains(&x) && (0..HEIGHT).contains(&y),
+                    "point ({}, {}) is outside of the screen",
+                    x,
+                    y
+                );
+
+                addr.set(
+                    (x / 2) as usize,
+                    y as usize,
+                    u16::from(left) | u16::from(right) << 8,
+                );
+            }
+
+            if let [last] = remainder {
+                self.draw_point_page(x + row.len() as i32 - 1, y, *last, page);
+            }
+        }
+    }
+
+    /// Fills the non-current page with a single colour index.
+    pub fn clear(&mut self, colour: u8) {
+        let addr = Self::page_memory(Self::back_page());
+        let value = u16::from(colour) | u16::from(colour) << 8;
+
+        for y in 0..HEIGHT as usize {
+            for x in 0..(WIDTH / 2) as usize {
+                addr.set(x, y, value);
+            }
+        }
+    }
+
+    fn back_page() -> Page {
         let display = DISPLAY_CONTROL.get();
 
         // get other page
-        let page = if display & GraphicsSettings::PAGE_SELECT.bits() != 0 {
+        if display & GraphicsSettings::PAGE_SELECT.bits() != 0 {
             Page::Front
         } else {
             Page::Back
-        };
+        }
+    }
 
-        self.draw_point_page(x, y, colour, page);
+    fn page_memory(
+        page: Page,
+    ) -> MemoryMapped2DArray<u16, { (WIDTH / 2) as usize }, { HEIGHT as usize }> {
+        match page {
+            Page::Front => BITMAP_PAGE_FRONT_MODE_4,
+            Page::Back => BITMAP_PAGE_BACK_MODE_4,
+        }
     }
 
     /// Sets the colour of colour index in the background palette.
@@ -82,4 +184,31 @@ impl Bitmap4 {
         let swapped = display ^ GraphicsSettings::PAGE_SELECT.bits();
         DISPLAY_CONTROL.set(swapped);
     }
+
+    /// Waits for the next vblank and then flips the page, so the page you have
+    /// been drawing to is shown without tearing.
+    pub fn flip(&mut self, vblank: &VBlank) {
+        vblank.wait_for_vblank();
+        self.flip_page();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn blit_copies_rows_and_skips_empty_data(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap4();
+        let page = Bitmap4::page_memory(Bitmap4::back_page());
+        page.set(0, 0, 0);
+        page.set(0, 1, 0);
+
+        bitmap.blit(0, 0, 0, &[]);
+        assert_eq!(page.get(0, 0), 0);
+
+        bitmap.blit(1, 0, 1, &[1, 2]);
+        assert_eq!(page.get(0, 0), 0x0100);
+        assert_eq!(page.get(0, 1), 0x0200);
+    }
 }
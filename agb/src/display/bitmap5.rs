@@ -0,0 +1,160 @@
+use crate::fixnum::Num;
+use crate::interrupt::VBlank;
+use crate::memory_mapped::{MemoryMapped, MemoryMapped2DArray};
+
+use super::affine::{AffineMatrix, AffineMatrixBackground};
+use super::bitmap4::Page;
+use super::{
+    set_graphics_mode, set_graphics_settings, DisplayMode, GraphicsSettings, DISPLAY_CONTROL,
+    HEIGHT, WIDTH,
+};
+
+/// Width of a mode 5 page in pixels
+pub const BITMAP5_WIDTH: i32 = 160;
+/// Height of a mode 5 page in pixels
+pub const BITMAP5_HEIGHT: i32 = 128;
+
+const BITMAP_PAGE_FRONT_MODE_5: MemoryMapped2DArray<
+    u16,
+    { BITMAP5_WIDTH as usize },
+    { BITMAP5_HEIGHT as usize },
+> = unsafe { MemoryMapped2DArray::new(0x600_0000) };
+const BITMAP_PAGE_BACK_MODE_5: MemoryMapped2DArray<
+    u16,
+    { BITMAP5_WIDTH as usize },
+    { BITMAP5_HEIGHT as usize },
+> = unsafe { MemoryMapped2DArray::new(0x600_A000) };
+
+const BG2_AFFINE_MATRIX: MemoryMapped<AffineMatrixBackground> =
+    unsafe { MemoryMapped::new(0x0400_0020) };
+
+#[non_exhaustive]
+pub struct Bitmap5 {}
+
+impl Bitmap5 {
+    pub(crate) unsafe fn new() -> Self {
+        set_graphics_mode(DisplayMode::Bitmap5);
+        set_graphics_settings(GraphicsSettings::LAYER_BG2);
+
+        let mut bitmap = Bitmap5 {};
+        bitmap.set_transform(AffineMatrixBackground::default());
+        bitmap
+    }
+
+    /// Draws point on specified page at (x, y) coordinates with colour. Panics
+    /// if (x, y) is out of the bounds of the 160x128 page.
+    pub fn draw_point_page(&mut self, x: i32, y: i32, colour: u16, page: Page) {
+        assert!(
+            (0..BITMAP5_WIDTH).contains(&x) && (0..BITMAP5_HEIGHT).contains(&y),
+            "point ({}, {}) is outside of the page",
+            x,
+            y
+        );
+
+        Self::page_memory(page).set(x as usize, y as usize, colour);
+    }
+
+    /// Draws point on the non-current page at (x, y) coordinates with colour.
+    /// Panics if (x, y) is out of the bounds of the 160x128 page.
+    pub fn draw_point(&mut self, x: i32, y: i32, colour: u16) {
+        self.draw_point_page(x, y, colour, Self::back_page());
+    }
+
+    /// Fills the non-current page with a single colour.
+    pub fn clear(&mut self, colour: u16) {
+        let addr = Self::page_memory(Self::back_page());
+
+        for y in 0..BITMAP5_HEIGHT as usize {
+            for x in 0..BITMAP5_WIDTH as usize {
+                addr.set(x, y, colour);
+            }
+        }
+    }
+
+    /// Sets the transformation of the page onto the screen. By default the
+    /// page is displayed unscaled in the top left of the screen.
+    pub fn set_transform(&mut self, transform: impl Into<AffineMatrixBackground>) {
+        BG2_AFFINE_MATRIX.set(transform.into());
+    }
+
+    /// Stretches the 160x128 page to fill the whole screen, or returns it to
+    /// being displayed unscaled in the top left of the screen.
+    pub fn set_stretch_to_screen(&mut self, stretch: bool) {
+        let transform = if stretch {
+            AffineMatrix::from_scale(
+                (
+                    Num::new(BITMAP5_WIDTH) / WIDTH,
+                    Num::new(BITMAP5_HEIGHT) / HEIGHT,
+                )
+                    .into(),
+            )
+        } else {
+            AffineMatrix::identity()
+        };
+
+        self.set_transform(transform.to_background_wrapping());
+    }
+
+    fn back_page() -> Page {
+        let display = DISPLAY_CONTROL.get();
+
+        // get other page
+        if display & GraphicsSettings::PAGE_SELECT.bits() != 0 {
+            Page::Front
+        } else {
+            Page::Back
+        }
+    }
+
+    fn page_memory(
+        page: Page,
+    ) -> MemoryMapped2DArray<u16, { BITMAP5_WIDTH as usize }, { BITMAP5_HEIGHT as usize }> {
+        match page {
+            Page::Front => BITMAP_PAGE_FRONT_MODE_5,
+            Page::Back => BITMAP_PAGE_BACK_MODE_5,
+        }
+    }
+
+    /// Flips page, changing the Gameboy advance to draw the contents of the
+    /// other page
+    pub fn flip_page(&mut self) {
+        let display = DISPLAY_CONTROL.get();
+        let swapped = display ^ GraphicsSettings::PAGE_SELECT.bits();
+        DISPLAY_CONTROL.set(swapped);
+    }
+
+    /// Waits for the next vblank and then flips the page, so the page you have
+    /// been drawing to is shown without tearing.
+    pub fn flip(&mut self, vblank: &VBlank) {
+        vblank.wait_for_vblank();
+        self.flip_page();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn points_are_drawn_to_the_back_page(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap5();
+
+        let back = Bitmap5::back_page();
+        bitmap.clear(0);
+        bitmap.draw_point(BITMAP5_WIDTH - 1, BITMAP5_HEIGHT - 1, 0x7fff);
+
+        assert_eq!(
+            Bitmap5::page_memory(back).get(BITMAP5_WIDTH as usize - 1, BITMAP5_HEIGHT as usize - 1),
+            0x7fff
+        );
+
+        bitmap.flip_page();
+        assert!(!matches!(
+            (Bitmap5::back_page(), back),
+            (Page::Front, Page::Front) | (Page::Back, Page::Back)
+        ));
+
+        bitmap.set_stretch_to_screen(true);
+        bitmap.flip_page();
+    }
+}
@@ -15,7 +15,7 @@
 //! ```
 //! where `gba` is a mutable [Gba][crate::Gba] struct.
 
-use crate::{fixnum::Num, memory_mapped::set_bits};
+use crate::{fixnum::Num, interrupt::VBlank, memory_mapped::set_bits};
 
 use super::tiled::BackgroundID;
 
@@ -31,13 +31,13 @@ pub enum Layer {
 /// The different blend modes available on the GBA
 #[derive(Clone, Copy, Debug)]
 pub enum BlendMode {
-    // No blending
+    /// No blending
     Off = 0,
-    // Additive blending, use the [Blend::set_blend_weight] function to use this
+    /// Additive blending, use the [Blend::set_blend_weight] function to use this
     Normal = 0b01,
-    // Brighten, use the [Blend::set_fade] to use this
+    /// Brighten, use the [Blend::set_fade] to use this
     FadeToWhite = 0b10,
-    // Darken, use the [Blend::set_fade] to use this
+    /// Darken, use the [Blend::set_fade] to use this
     FadeToBlack = 0b11,
 }
 
@@ -82,7 +82,8 @@ impl BlendLayer<'_> {
         self
     }
 
-    /// Set the weight for the blend on this layer.
+    /// Set the weight for the blend on this layer. Weights above 1 are treated
+    /// as 1.
     pub fn set_blend_weight(&mut self, value: Num<u8, 4>) -> &mut Self {
         self.blend.set_blend_weight(self.layer, value);
 
@@ -90,6 +91,12 @@ impl BlendLayer<'_> {
     }
 }
 
+/// The hardware treats weights above 1 as 1, but only has room for 5 bits, so
+/// larger values need clamping rather than being truncated.
+fn clamped_weight(value: Num<u8, 4>) -> u16 {
+    u16::from(value.to_raw().min(1 << 4))
+}
+
 const BLEND_CONTROL: *mut u16 = 0x0400_0050 as *mut _;
 const BLEND_ALPHAS: *mut u16 = 0x0400_0052 as *mut _;
 
@@ -172,11 +179,12 @@ impl Blend {
         self
     }
 
-    /// Set the weight for the blend on a particular layer.
+    /// Set the weight for the blend on a particular layer. Weights above 1 are
+    /// treated as 1.
     pub fn set_blend_weight(&mut self, layer: Layer, value: Num<u8, 4>) -> &mut Self {
         self.blend_weights = set_bits(
             self.blend_weights,
-            value.to_raw() as u16,
+            clamped_weight(value),
             5,
             (layer as usize) * 8,
         );
@@ -184,9 +192,9 @@ impl Blend {
         self
     }
 
-    /// Set the fade of brighten or darken
+    /// Set the fade of brighten or darken. Fades above 1 are treated as 1.
     pub fn set_fade(&mut self, value: Num<u8, 4>) -> &mut Self {
-        self.fade_weight = value.to_raw() as u16;
+        self.fade_weight = clamped_weight(value);
 
         self
     }
@@ -198,6 +206,61 @@ impl Blend {
         self
     }
 
+    /// Starts fading every layer to black over the given number of frames,
+    /// using the brightness control. The fade is stepped by calling
+    /// [Fade::update] once per frame, or can be run to completion with
+    /// [Fade::wait].
+    ///
+    /// ```rust,no_run
+    /// # #![no_main]
+    /// # #![no_std]
+    /// # fn blend(mut gba: agb::Gba) {
+    /// let mut blend = gba.display.blend.get();
+    /// let vblank = agb::interrupt::VBlank::get();
+    ///
+    /// blend.fade_to_black(30).wait(&vblank);
+    /// # }
+    /// ```
+    pub fn fade_to_black(&mut self, frames: u16) -> Fade<'_> {
+        self.fade(BlendMode::FadeToBlack, 0, 16, frames)
+    }
+
+    /// Starts fading every layer to white over the given number of frames. See
+    /// [Blend::fade_to_black].
+    pub fn fade_to_white(&mut self, frames: u16) -> Fade<'_> {
+        self.fade(BlendMode::FadeToWhite, 0, 16, frames)
+    }
+
+    /// Starts fading every layer back from black over the given number of
+    /// frames, undoing [Blend::fade_to_black].
+    pub fn fade_from_black(&mut self, frames: u16) -> Fade<'_> {
+        self.fade(BlendMode::FadeToBlack, 16, 0, frames)
+    }
+
+    /// Starts fading every layer back from white over the given number of
+    /// frames, undoing [Blend::fade_to_white].
+    pub fn fade_from_white(&mut self, frames: u16) -> Fade<'_> {
+        self.fade(BlendMode::FadeToWhite, 16, 0, frames)
+    }
+
+    fn fade(&mut self, mode: BlendMode, from: u8, to: u8, frames: u16) -> Fade<'_> {
+        for background in 0..4 {
+            self.set_background_enable(Layer::Top, BackgroundID(background), true);
+        }
+
+        self.set_object_enable(Layer::Top, true)
+            .set_backdrop_enable(Layer::Top, true)
+            .set_blend_mode(mode);
+
+        Fade {
+            blend: self,
+            from,
+            to,
+            frames,
+            frame: 0,
+        }
+    }
+
     /// Commits the current state, should be called near after a call to wait
     /// for next vblank.
     pub fn commit(&self) {
@@ -209,8 +272,113 @@ impl Blend {
     }
 }
 
+/// A brightness fade in progress, created by functions such as
+/// [Blend::fade_to_black].
+pub struct Fade<'blend> {
+    blend: &'blend mut Blend,
+    from: u8,
+    to: u8,
+    frames: u16,
+    frame: u16,
+}
+
+impl Fade<'_> {
+    /// Sets and commits the brightness for the current frame of the fade, then
+    /// advances to the next frame. Returns whether the fade has finished. This
+    /// should be called once per frame shortly after waiting for vblank.
+    pub fn update(&mut self) -> bool {
+        let progress = self.frame.min(self.frames);
+        let from = i32::from(self.from);
+        let to = i32::from(self.to);
+
+        let fade = if self.frames == 0 {
+            to
+        } else {
+            from + (to - from) * i32::from(progress) / i32::from(self.frames)
+        };
+
+        self.blend.set_fade(Num::from_raw(fade as u8)).commit();
+
+        let finished = self.is_finished();
+        self.frame = self.frame.saturating_add(1);
+        finished
+    }
+
+    /// Whether the fade has reached its final brightness.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.frames
+    }
+
+    /// Runs the fade to completion, waiting for vblank before each step.
+    pub fn wait(mut self, vblank: &VBlank) {
+        loop {
+            vblank.wait_for_vblank();
+            if self.update() {
+                break;
+            }
+        }
+    }
+}
+
 impl Drop for Blend {
     fn drop(&mut self) {
         self.reset().commit();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixnum::num;
+
+    #[test_case]
+    fn blend_registers_are_written_on_commit(gba: &mut crate::Gba) {
+        let mut blend = gba.display.blend.get();
+
+        blend
+            .set_blend_mode(BlendMode::Normal)
+            .set_background_enable(Layer::Top, BackgroundID(1), true)
+            .set_object_enable(Layer::Top, true)
+            .set_backdrop_enable(Layer::Bottom, true)
+            .set_blend_weight(Layer::Top, num!(0.5))
+            .set_blend_weight(Layer::Bottom, num!(2.))
+            .set_fade(num!(15.));
+        blend.commit();
+
+        unsafe {
+            assert_eq!(
+                BLEND_CONTROL.read_volatile(),
+                1 << 1 | 1 << 4 | 0b01 << 6 | 1 << 13
+            );
+        }
+
+        // the weights registers are write only
+        assert_eq!(blend.blend_weights, 8 | 16 << 8);
+        assert_eq!(blend.fade_weight, 16);
+    }
+
+    #[test_case]
+    fn fades_step_the_brightness_of_every_layer(gba: &mut crate::Gba) {
+        let mut blend = gba.display.blend.get();
+
+        let mut fade = blend.fade_to_white(2);
+        let mut finished = [false; 3];
+        let mut fades = [0; 3];
+        for (finished, value) in finished.iter_mut().zip(fades.iter_mut()) {
+            *finished = fade.update();
+            *value = fade.blend.fade_weight;
+        }
+
+        assert_eq!(finished, [false, false, true]);
+        assert_eq!(fades, [0, 8, 16]);
+        assert_eq!(
+            unsafe { BLEND_CONTROL.read_volatile() },
+            0b11_1111 | 0b10 << 6
+        );
+
+        let mut fade = blend.fade_from_white(0);
+        assert!(fade.update());
+        assert_eq!(blend.fade_weight, 0);
+    }
+}
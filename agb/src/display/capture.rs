@@ -0,0 +1,215 @@
+use alloc::vec::Vec;
+
+use super::{
+    bitmap3::Bitmap3, control::BACKDROP_COLOUR, palette16::fade_colour, tiled::RegularMap, HEIGHT,
+    WIDTH,
+};
+use crate::fixnum::Num;
+use crate::memory_mapped::MemoryMapped;
+
+/// A copy of what the regular backgrounds are displaying, composited in
+/// software into a buffer on the heap.
+///
+/// This lets a pause screen show a darkened or blurred version of the game
+/// without keeping the whole scene alive. Objects, affine backgrounds and
+/// special effects such as blending and windows are not included.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::display::{capture::ScreenCapture, tiled::RegularMap};
+/// # fn foo(gba: &mut agb::Gba, map: &RegularMap) {
+/// let mut capture = ScreenCapture::capture(&[map]);
+/// capture.fade(0, agb::fixnum::num!(0.5));
+///
+/// let mut bitmap = gba.display.video.bitmap3();
+/// capture.draw(&mut bitmap);
+/// # }
+/// ```
+pub struct ScreenCapture {
+    pixels: Vec<u16>,
+}
+
+impl ScreenCapture {
+    /// Captures what the given maps are currently displaying, along with the
+    /// backdrop colour. Hidden maps are skipped, and the rest are layered by
+    /// their priority in the same way as the hardware does. This reads the
+    /// tiles as they were last committed, so commit any changes first.
+    #[must_use]
+    pub fn capture(maps: &[&RegularMap]) -> Self {
+        let mut maps: Vec<&RegularMap> =
+            maps.iter().copied().filter(|map| map.is_shown()).collect();
+        maps.sort_by_key(|map| {
+            let (priority, background_id) = map.display_priority();
+            (priority as u8, background_id)
+        });
+
+        let backdrop = BACKDROP_COLOUR.get();
+
+        let mut pixels = Vec::with_capacity((WIDTH * HEIGHT) as usize);
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let colour = maps
+                    .iter()
+                    .find_map(|map| map.screen_colour(x, y))
+                    .unwrap_or(backdrop);
+                pixels.push(colour);
+            }
+        }
+
+        Self { pixels }
+    }
+
+    /// The captured colour at (x, y), or [None] if it is outside of the screen.
+    #[must_use]
+    pub fn pixel(&self, x: i32, y: i32) -> Option<u16> {
+        ((0..WIDTH).contains(&x) && (0..HEIGHT).contains(&y))
+            .then(|| self.pixels[(x + y * WIDTH) as usize])
+    }
+
+    /// Every captured colour, row by row.
+    #[must_use]
+    pub fn pixels(&self) -> &[u16] {
+        &self.pixels
+    }
+
+    /// Linearly interpolates every captured colour towards `target_colour`, in
+    /// the same way as [fade](super::palette16::fade). Use `0` to darken the
+    /// image.
+    pub fn fade(&mut self, target_colour: u16, amount: Num<i32, 8>) {
+        let amount = amount.clamp(0.into(), 1.into());
+
+        for colour in &mut self.pixels {
+            *colour = fade_colour(*colour, target_colour, amount);
+        }
+    }
+
+    /// Blurs the image by averaging each pixel with its horizontal and
+    /// vertical neighbours.
+    pub fn blur(&mut self) {
+        let original = self.pixels.clone();
+        let at = |x: i32, y: i32| {
+            let x = x.clamp(0, WIDTH - 1);
+            let y = y.clamp(0, HEIGHT - 1);
+            original[(x + y * WIDTH) as usize]
+        };
+
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let neighbours = [
+                    at(x, y),
+                    at(x - 1, y),
+                    at(x + 1, y),
+                    at(x, y - 1),
+                    at(x, y + 1),
+                ];
+
+                let mut colour = 0;
+                for shift in [0, 5, 10] {
+                    let total: u16 = neighbours.iter().map(|c| (c >> shift) & 0x1f).sum();
+                    colour |= (total / 5) << shift;
+                }
+
+                self.pixels[(x + y * WIDTH) as usize] = colour;
+            }
+        }
+    }
+
+    /// Draws the captured image to the whole screen in mode 3.
+    pub fn draw(&self, bitmap: &mut Bitmap3) {
+        for (i, &colour) in self.pixels.iter().enumerate() {
+            let i = i as i32;
+            bitmap.draw_pixel(i % WIDTH, i / WIDTH, colour);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{
+        tiled::{RegularBackgroundSize, TileSetting, TiledMap},
+        Priority,
+    };
+
+    #[test_case]
+    fn capture_layers_maps_over_the_backdrop(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+        vram.set_background_palette_raw(&[0x1111, 0x2222, 0x3333]);
+
+        let mut back = gfx.background(Priority::P1, RegularBackgroundSize::Background32x32);
+        let mut front = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+
+        let back_tile = vram.new_dynamic_tile().fill_with(1);
+        let front_tile = vram.new_dynamic_tile().fill_with(2);
+
+        for x in 0..2u16 {
+            back.set_tile(
+                &mut vram,
+                (x, 0u16).into(),
+                &back_tile.tile_set(),
+                TileSetting::from_raw(back_tile.tile_index()),
+            );
+        }
+        front.set_tile(
+            &mut vram,
+            (1u16, 0u16).into(),
+            &front_tile.tile_set(),
+            TileSetting::from_raw(front_tile.tile_index()),
+        );
+
+        back.commit(&mut vram);
+        front.commit(&mut vram);
+        back.show();
+        front.show();
+
+        let capture = ScreenCapture::capture(&[&back, &front]);
+
+        assert_eq!(capture.pixel(0, 0), Some(0x2222));
+        assert_eq!(capture.pixel(8, 0), Some(0x3333));
+        assert_eq!(capture.pixel(16, 0), Some(0x1111));
+        assert_eq!(capture.pixel(WIDTH, 0), None);
+
+        back.clear(&mut vram);
+        front.clear(&mut vram);
+        vram.remove_dynamic_tile(back_tile);
+        vram.remove_dynamic_tile(front_tile);
+        vram.gc();
+    }
+
+    #[test_case]
+    fn capture_reads_eight_bit_tiles_from_any_character_block(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+        vram.set_background_palette_raw(&[0x1111, 0x2222, 0x3333]);
+
+        let mut map = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+        let tile = vram.new_dynamic_tile().fill_with(0);
+
+        map.set_tile(
+            &mut vram,
+            (0u16, 0u16).into(),
+            &tile.tile_set(),
+            TileSetting::from_raw(tile.tile_index()),
+        );
+        map.commit(&mut vram);
+        map.show();
+
+        // use 256 colours and character block 1
+        let (_, background_id) = map.display_priority();
+        let control: MemoryMapped<u16> =
+            unsafe { MemoryMapped::new(0x0400_0008 + 2 * usize::from(background_id)) };
+        control.set(control.get() | (1 << 7) | (1 << 2));
+
+        let tile_data = (0x0600_4000 + usize::from(tile.tile_index()) * 64) as *mut u16;
+        unsafe { tile_data.write_volatile(0x0002) };
+
+        let capture = ScreenCapture::capture(&[&map]);
+        assert_eq!(capture.pixel(0, 0), Some(0x3333));
+        assert_eq!(capture.pixel(1, 0), Some(0x1111));
+
+        unsafe { tile_data.write_volatile(0) };
+        map.clear(&mut vram);
+        vram.remove_dynamic_tile(tile);
+        vram.gc();
+    }
+}
@@ -0,0 +1,240 @@
+#![deny(missing_docs)]
+//! Gathering everything which needs copying to video memory during a frame, so
+//! it can all be copied with DMA in one go once vblank starts.
+//!
+//! Normally the objects, maps and palettes are each committed straight after
+//! waiting for vblank, which means doing all the work of deciding what has
+//! changed during vblank as well. With a [`CommitQueue`], that work happens
+//! before waiting for vblank, and only the copies themselves are left for
+//! [`flush`](CommitQueue::flush) to do.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::display::commit_queue::CommitQueue;
+//! use agb::display::tiled::{RegularBackgroundSize, TiledMap};
+//! use agb::display::Priority;
+//!
+//! # fn foo(gba: &mut agb::Gba) {
+//! let object = gba.display.object.get();
+//! let (gfx, mut vram) = gba.display.video.tiled0();
+//! let mut map = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+//!
+//! let vblank = agb::interrupt::VBlank::get();
+//! let mut queue = CommitQueue::new();
+//!
+//! loop {
+//!     // ... update the game ...
+//!
+//!     object.commit_queued(&mut queue);
+//!     map.commit_queued(&mut vram, &mut queue);
+//!     queue.background_palette_colours(0, &[0x7fff]);
+//!
+//!     vblank.wait_for_vblank();
+//!     queue.flush();
+//! }
+//! # }
+//! ```
+//!
+//! The data is copied into the queue when it is added, so the originals can
+//! change straight away.
+
+use alloc::vec::Vec;
+
+use crate::display::palette16::{PALETTE_BACKGROUND, PALETTE_OBJECT};
+use crate::dma::{self, Transfer};
+
+/// The number of cycles in vblank, which lasts for 68 lines of 1232 cycles
+/// each.
+pub const VBLANK_CYCLES: usize = 68 * 1232;
+
+/// Roughly the cycles spent starting each transfer.
+const TRANSFER_OVERHEAD_CYCLES: usize = 50;
+/// Reading from the queue in EWRAM takes 3 cycles for each halfword, and
+/// writing to video memory takes 1 more.
+const CYCLES_PER_BYTE: usize = 2;
+
+struct QueuedCopy {
+    /// Where the data starts in the queue, in words.
+    offset: usize,
+    dest: usize,
+    bytes: usize,
+}
+
+/// Copies to video memory, palette ram and display registers which are
+/// waiting to be made during vblank.
+#[derive(Default)]
+pub struct CommitQueue {
+    data: Vec<u32>,
+    copies: Vec<QueuedCopy>,
+}
+
+impl CommitQueue {
+    /// Creates an empty queue.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues copying `src` to `dest`.
+    ///
+    /// # Safety
+    ///
+    /// `dest` must be valid for writes of `src.len()` values when the queue is
+    /// flushed.
+    pub(crate) unsafe fn copy<T: Transfer>(&mut self, src: &[T], dest: *mut T) {
+        self.copy_bytes(
+            src.as_ptr().cast(),
+            dest as usize,
+            core::mem::size_of_val(src),
+        );
+    }
+
+    /// Queues copying `value` to `dest`, for registers which are more than one
+    /// halfword long.
+    ///
+    /// # Safety
+    ///
+    /// `dest` must be valid for writes of `V` when the queue is flushed, and
+    /// `V` must be a whole number of halfwords long.
+    pub(crate) unsafe fn copy_value<V: Copy>(&mut self, value: &V, dest: *mut V) {
+        assert_eq!(core::mem::size_of::<V>() % 2, 0);
+
+        self.copy_bytes(
+            (value as *const V).cast(),
+            dest as usize,
+            core::mem::size_of::<V>(),
+        );
+    }
+
+    unsafe fn copy_bytes(&mut self, src: *const u8, dest: usize, bytes: usize) {
+        if bytes == 0 {
+            return;
+        }
+
+        let offset = self.data.len();
+        self.data.resize(offset + bytes.div_ceil(4), 0);
+
+        core::ptr::copy_nonoverlapping(src, self.data[offset..].as_mut_ptr().cast(), bytes);
+
+        self.copies.push(QueuedCopy {
+            offset,
+            dest,
+            bytes,
+        });
+    }
+
+    /// Queues setting colours in the background palette, starting from
+    /// `first_colour`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the colours go past the end of the 256 colour palette.
+    pub fn background_palette_colours(&mut self, first_colour: usize, colours: &[u16]) {
+        assert!(
+            first_colour + colours.len() <= 256,
+            "the palette only has 256 colours"
+        );
+
+        unsafe { self.copy(colours, (PALETTE_BACKGROUND as *mut u16).add(first_colour)) };
+    }
+
+    /// Queues setting colours in the object palette, starting from
+    /// `first_colour`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the colours go past the end of the 256 colour palette.
+    pub fn object_palette_colours(&mut self, first_colour: usize, colours: &[u16]) {
+        assert!(
+            first_colour + colours.len() <= 256,
+            "the palette only has 256 colours"
+        );
+
+        unsafe { self.copy(colours, (PALETTE_OBJECT as *mut u16).add(first_colour)) };
+    }
+
+    /// The number of copies waiting to be made.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.copies.len()
+    }
+
+    /// Whether there is nothing to copy.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.copies.is_empty()
+    }
+
+    /// The total number of bytes waiting to be copied.
+    #[must_use]
+    pub fn bytes(&self) -> usize {
+        self.copies.iter().map(|copy| copy.bytes).sum()
+    }
+
+    /// A rough estimate of how many cycles [`flush`](CommitQueue::flush) will
+    /// take, to compare with [`VBLANK_CYCLES`].
+    #[must_use]
+    pub fn estimated_cycles(&self) -> usize {
+        self.bytes() * CYCLES_PER_BYTE + self.len() * TRANSFER_OVERHEAD_CYCLES
+    }
+
+    /// Makes all of the queued copies with DMA and empties the queue. This
+    /// should be called as soon as possible after vblank starts.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if the copies would take longer than vblank
+    /// lasts, as the end of them would be visible on the screen.
+    pub fn flush(&mut self) {
+        debug_assert!(
+            self.estimated_cycles() <= VBLANK_CYCLES,
+            "{} bytes were queued, which takes about {} cycles to copy but vblank only lasts for {}",
+            self.bytes(),
+            self.estimated_cycles(),
+            VBLANK_CYCLES
+        );
+
+        for copy in &self.copies {
+            let src = self.data[copy.offset..].as_ptr().cast::<u16>();
+
+            unsafe { dma::copy_to(src, copy.dest as *mut u16, copy.bytes / 2) };
+        }
+
+        self.clear();
+    }
+
+    /// Throws away everything in the queue without copying it.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.copies.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn commit_queue_copies_when_flushed(_gba: &mut crate::Gba) {
+        let mut destination = [0u16; 5];
+        let mut register = 0u32;
+
+        let mut queue = CommitQueue::new();
+        unsafe {
+            queue.copy(&[1u16, 2, 3], destination.as_mut_ptr());
+            queue.copy(&[4u16, 5], destination.as_mut_ptr().add(3));
+            queue.copy_value(&0x1234_5678u32, &mut register);
+        }
+
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.bytes(), 6 + 4 + 4);
+        assert_eq!(destination, [0; 5], "nothing is copied until flushed");
+
+        queue.flush();
+
+        assert_eq!(destination, [1, 2, 3, 4, 5]);
+        assert_eq!(register, 0x1234_5678);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::memory_mapped::MemoryMapped;
+
+use super::{
+    bitmap3::Bitmap3,
+    bitmap4::Bitmap4,
+    bitmap5::Bitmap5,
+    object::{multiplexer_active, object_controller_active},
+    palette16::PALETTE_BACKGROUND,
+    tiled::{Tiled0, Tiled1, Tiled2},
+    GraphicsSettings, DISPLAY_CONTROL,
+};
+
+const GREEN_SWAP: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0002) };
+pub(crate) const BACKDROP_COLOUR: MemoryMapped<u16> =
+    unsafe { MemoryMapped::new(PALETTE_BACKGROUND) };
+
+/// How the tiles of an object larger than 8x8 are found in object tile memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectTileMapping {
+    /// The tiles of an object follow one after another in memory. This is
+    /// what the [ObjectController](super::object::ObjectController) uses.
+    OneDimensional,
+    /// Object tile memory is treated as a 32x32 grid of tiles, and each row
+    /// of an object starts 32 tiles after the previous one.
+    TwoDimensional,
+}
+
+/// Settings from the display control register which aren't owned by a
+/// background or by the [ObjectController](super::object::ObjectController).
+///
+/// This is implemented by each of the video modes, so these can be changed
+/// without writing to the register directly. Obtaining a new video mode turns
+/// forced blank off again.
+pub trait DisplayControl {
+    /// Forces the screen to display white. Video memory, palette memory and
+    /// object attribute memory can be accessed at any time while the screen
+    /// is blanked, which makes it useful for loading a large amount of
+    /// graphics at once.
+    fn set_forced_blank(&mut self, blank: bool) {
+        set_setting(GraphicsSettings::SCREEN_BLANK, blank);
+    }
+
+    /// Whether the screen is currently forced blank.
+    fn forced_blank(&self) -> bool {
+        get_setting(GraphicsSettings::SCREEN_BLANK)
+    }
+
+    /// Sets how object tiles are laid out in memory. Panics when trying to
+    /// use two dimensional mapping while an
+    /// [ObjectController](super::object::ObjectController) exists, as it
+    /// places sprites assuming one dimensional mapping.
+    fn set_object_tile_mapping(&mut self, mapping: ObjectTileMapping) {
+        assert!(
+            mapping == ObjectTileMapping::OneDimensional || !object_controller_active(),
+            "the object controller requires one dimensional object tile mapping"
+        );
+
+        set_setting(
+            GraphicsSettings::SPRITE1_D,
+            mapping == ObjectTileMapping::OneDimensional,
+        );
+    }
+
+    /// The current object tile mapping.
+    fn object_tile_mapping(&self) -> ObjectTileMapping {
+        if get_setting(GraphicsSettings::SPRITE1_D) {
+            ObjectTileMapping::OneDimensional
+        } else {
+            ObjectTileMapping::TwoDimensional
+        }
+    }
+
+    /// Allows object attribute memory to be written during hblank, at the
+    /// cost of fewer objects being drawable on each scanline. Panics when
+    /// trying to disable this while a
+    /// [Multiplexer](super::object::Multiplexer) exists, as it rewrites
+    /// objects during hblank.
+    fn set_hblank_object_access(&mut self, enabled: bool) {
+        assert!(
+            enabled || !multiplexer_active(),
+            "the multiplexer requires object attribute memory access during hblank"
+        );
+
+        set_setting(GraphicsSettings::OAM_HBLANK, enabled);
+    }
+
+    /// Whether object attribute memory can be written during hblank.
+    fn hblank_object_access(&self) -> bool {
+        get_setting(GraphicsSettings::OAM_HBLANK)
+    }
+
+    /// Sets the colour shown wherever no background or object is drawn. This
+    /// is the first entry of the background palette, so it is shared with
+    /// anything else which writes that entry.
+    fn set_backdrop_colour(&mut self, colour: u16) {
+        BACKDROP_COLOUR.set(colour);
+    }
+
+    /// The colour shown wherever no background or object is drawn.
+    fn backdrop_colour(&self) -> u16 {
+        BACKDROP_COLOUR.get()
+    }
+
+    /// Swaps the green component of each horizontal pair of pixels. Combined
+    /// with a background that alternates between two colours, this gives a
+    /// cheap blur or transparency effect. This register is undocumented and
+    /// only affects real hardware and accurate emulators.
+    fn set_green_swap(&mut self, enabled: bool) {
+        GREEN_SWAP.set(enabled.into());
+    }
+
+    /// Whether green swap is enabled.
+    fn green_swap(&self) -> bool {
+        GREEN_SWAP.get() & 1 != 0
+    }
+}
+
+fn set_setting(setting: GraphicsSettings, enabled: bool) {
+    let current = DISPLAY_CONTROL.get();
+    let new = if enabled {
+        current | setting.bits()
+    } else {
+        current & !setting.bits()
+    };
+
+    DISPLAY_CONTROL.set(new);
+}
+
+fn get_setting(setting: GraphicsSettings) -> bool {
+    DISPLAY_CONTROL.get() & setting.bits() != 0
+}
+
+impl DisplayControl for Bitmap3 {}
+impl DisplayControl for Bitmap4 {}
+impl DisplayControl for Bitmap5 {}
+impl DisplayControl for Tiled0 {}
+impl DisplayControl for Tiled1 {}
+impl DisplayControl for Tiled2 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn display_control_settings_survive_mode_changes(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.set_object_tile_mapping(ObjectTileMapping::TwoDimensional);
+        bitmap.set_hblank_object_access(true);
+        bitmap.set_forced_blank(true);
+        assert!(bitmap.forced_blank());
+
+        let (mut tiled, _) = gba.display.video.tiled0();
+
+        assert!(!tiled.forced_blank());
+        assert_eq!(
+            tiled.object_tile_mapping(),
+            ObjectTileMapping::TwoDimensional
+        );
+        assert!(tiled.hblank_object_access());
+
+        tiled.set_object_tile_mapping(ObjectTileMapping::OneDimensional);
+        tiled.set_hblank_object_access(false);
+        assert!(!tiled.hblank_object_access());
+    }
+
+    #[test_case]
+    fn backdrop_colour_and_green_swap_can_be_set(gba: &mut crate::Gba) {
+        let (mut tiled, _) = gba.display.video.tiled0();
+
+        tiled.set_backdrop_colour(0x1234);
+        assert_eq!(tiled.backdrop_colour(), 0x1234);
+
+        tiled.set_green_swap(true);
+        assert!(tiled.green_swap());
+        tiled.set_green_swap(false);
+        assert!(!tiled.green_swap());
+    }
+}
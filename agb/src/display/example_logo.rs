@@ -24,7 +24,10 @@ pub fn display_logo(map: &mut RegularMap, vram: &mut VRamManager) {
 
 #[cfg(test)]
 mod tests {
-    use crate::display::{tiled::RegularBackgroundSize, Priority};
+    use crate::display::{
+        tiled::{CompressedTileSet, RegularBackgroundSize},
+        Priority,
+    };
 
     use super::*;
 
@@ -41,4 +44,42 @@ mod tests {
         map.clear(&mut vram);
         vram.gc();
     }
+
+    #[test_case]
+    fn logo_display_compressed(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+
+        let mut map = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+
+        vram.set_background_palettes(agb_logo::PALETTES);
+
+        let compressed =
+            CompressedTileSet::new(agb_logo::test_logo_compressed.data, TileFormat::FourBpp)
+                .with_properties(&[0, 3]);
+        let background_tilemap = vram.add_tileset_compressed(&compressed);
+
+        for y in 0..20 {
+            for x in 0..30 {
+                let tile_id = y * 30 + x;
+
+                let palette_entry =
+                    agb_logo::test_logo_compressed.palette_assignments[tile_id as usize];
+                let tile_setting = TileSetting::new(tile_id, false, false, palette_entry);
+
+                map.set_tile(&mut vram, (x, y).into(), &background_tilemap, tile_setting);
+            }
+        }
+
+        assert_eq!(map.tile_property_at(&vram, (0u16, 0u16).into()), 0);
+        assert_eq!(map.tile_property_at(&vram, (1u16, 0u16).into()), 3);
+
+        map.commit(&mut vram);
+        map.show();
+
+        crate::test_runner::assert_image_output("gfx/test_logo.png");
+
+        map.clear(&mut vram);
+        vram.remove_tileset_compressed(background_tilemap);
+        vram.gc();
+    }
 }
@@ -0,0 +1,245 @@
+#![deny(missing_docs)]
+//! Making objects and backgrounds flicker.
+//!
+//! Showing something only on every other frame makes it look half transparent,
+//! which is a common way to show that the player is invincible after being
+//! hit. [Flicker] counts frames from the vblank interrupt rather than from
+//! how often it is updated, so it keeps the same rhythm even when the game
+//! misses a frame.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! # use agb::display::{flicker::Flicker, object::Object};
+//! # fn foo(player: &mut Object, object: &agb::display::object::ObjectController) {
+//! let vblank = agb::interrupt::VBlank::get();
+//!
+//! let flicker = Flicker::new(2).with_duration(120);
+//! let _handler = flicker.setup_interrupt_handler();
+//!
+//! while !flicker.is_finished() {
+//!     flicker.apply_to_object(player);
+//!
+//!     vblank.wait_for_vblank();
+//!     object.commit();
+//! }
+//!
+//! flicker.apply_to_object(player);
+//! # }
+//! ```
+
+use core::cell::Cell;
+
+use bare_metal::Mutex;
+
+use super::object::{MetaSprite, Object};
+use super::tiled::TiledMap;
+use crate::interrupt::{add_interrupt_handler, free, Interrupt, InterruptHandler};
+
+/// Alternates between visible and hidden every `period` frames, optionally
+/// for a limited number of frames.
+pub struct Flicker {
+    period: u32,
+    duration: Option<u32>,
+    frames: Mutex<Cell<u32>>,
+}
+
+impl Flicker {
+    /// Creates a flicker which is visible for `period` frames then hidden for
+    /// `period` frames. A period of 1 gives the strongest transparency effect.
+    /// Panics if the period is 0.
+    #[must_use]
+    pub fn new(period: u32) -> Self {
+        assert!(period > 0, "the flicker period must be at least one frame");
+
+        Self {
+            period,
+            duration: None,
+            frames: Mutex::new(Cell::new(0)),
+        }
+    }
+
+    /// Stops flickering after the given number of frames, after which
+    /// everything it is applied to stays visible.
+    #[must_use]
+    pub fn with_duration(mut self, frames: u32) -> Self {
+        self.duration = Some(frames);
+        self
+    }
+
+    /// Counts frames for this flicker while the returned handler is alive.
+    #[must_use]
+    pub fn setup_interrupt_handler(&self) -> InterruptHandler<'_> {
+        add_interrupt_handler(Interrupt::VBlank, move |cs| {
+            let frames = self.frames.borrow(cs);
+            frames.set(frames.get().saturating_add(1));
+        })
+    }
+
+    /// Starts counting frames from zero again.
+    pub fn restart(&self) {
+        free(|cs| self.frames.borrow(cs).set(0));
+    }
+
+    /// The number of frames since the flicker started.
+    #[must_use]
+    pub fn frames_elapsed(&self) -> u32 {
+        free(|cs| self.frames.borrow(cs).get())
+    }
+
+    /// Whether the flicker has lasted for its duration. A flicker without a
+    /// duration never finishes.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.duration
+            .is_some_and(|duration| self.frames_elapsed() >= duration)
+    }
+
+    /// Whether things should be shown in the current frame.
+    #[must_use]
+    pub fn is_visible(&self) -> bool {
+        self.is_finished() || (self.frames_elapsed() / self.period) % 2 == 0
+    }
+
+    /// Shows or hides the object for the current frame. No change will be seen
+    /// until [ObjectController::commit](super::object::ObjectController::commit)
+    /// is called.
+    pub fn apply_to_object(&self, object: &mut Object) {
+        if self.is_visible() {
+            object.show();
+        } else {
+            object.hide();
+        }
+    }
+
+    /// Shows or hides every part of the metasprite for the current frame.
+    pub fn apply_to_metasprite(&self, metasprite: &mut MetaSprite) {
+        if self.is_visible() {
+            metasprite.show();
+        } else {
+            metasprite.hide();
+        }
+    }
+
+    /// Shows or hides the background. This takes effect immediately, so it
+    /// should be called during vblank.
+    pub fn apply_to_background(&self, map: &mut impl TiledMap) {
+        if self.is_visible() {
+            map.show();
+        } else {
+            map.hide();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::object::tests::EMU;
+    use crate::display::tiled::RegularBackgroundSize;
+    use crate::display::{Priority, DISPLAY_CONTROL};
+
+    fn set_frames(flicker: &Flicker, frames: u32) {
+        free(|cs| flicker.frames.borrow(cs).set(frames));
+    }
+
+    #[test_case]
+    fn flicker_follows_vblanks(_gba: &mut crate::Gba) {
+        let flicker = Flicker::new(2).with_duration(6);
+        assert!(flicker.is_visible());
+
+        let vblank = crate::interrupt::VBlank::get();
+        let _handler = flicker.setup_interrupt_handler();
+
+        // start each check just after a vblank, so the frame count can't
+        // change part way through
+        vblank.wait_for_vblank();
+
+        let mut seen = [(0, false); 6];
+        for (frame, visible) in &mut seen {
+            *frame = flicker.frames_elapsed();
+            *visible = flicker.is_visible();
+            while flicker.frames_elapsed() == *frame {
+                vblank.wait_for_vblank();
+            }
+        }
+
+        for (frame, visible) in seen {
+            assert_eq!(
+                visible,
+                frame >= 6 || (frame / 2) % 2 == 0,
+                "frame {}",
+                frame
+            );
+        }
+
+        while !flicker.is_finished() {
+            vblank.wait_for_vblank();
+        }
+        assert!(flicker.is_visible());
+
+        flicker.restart();
+        assert_eq!(flicker.frames_elapsed(), 0);
+    }
+
+    #[test_case]
+    fn flicker_alternates_every_period(_gba: &mut crate::Gba) {
+        let flicker = Flicker::new(2).with_duration(6);
+
+        let visibility: alloc::vec::Vec<_> = (0..8)
+            .map(|frames| {
+                set_frames(&flicker, frames);
+                flicker.is_visible()
+            })
+            .collect();
+
+        assert_eq!(
+            visibility,
+            [true, true, false, false, true, true, true, true]
+        );
+        assert!(flicker.is_finished());
+    }
+
+    #[test_case]
+    fn flicker_shows_and_hides_backgrounds(gba: &mut crate::Gba) {
+        let (gfx, _vram) = gba.display.video.tiled0();
+        let mut map = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+        let (_, background_id) = map.display_priority();
+        let enabled = || DISPLAY_CONTROL.get() & (1 << (8 + background_id)) != 0;
+
+        let flicker = Flicker::new(1);
+
+        set_frames(&flicker, 1);
+        flicker.apply_to_background(&mut *map);
+        assert!(!enabled());
+
+        set_frames(&flicker, 2);
+        flicker.apply_to_background(&mut *map);
+        assert!(enabled());
+
+        map.hide();
+    }
+
+    #[test_case]
+    fn flicker_shows_and_hides_objects(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let mut emu = object.object(object.sprite(EMU.sprite(0)));
+        emu.set_position((10, 10).into());
+
+        // the object mode is in bits 8 and 9 of the first attribute
+        let oam = 0x0700_0000 as *const u16;
+        let disabled = || unsafe { (oam.read_volatile() >> 8) & 0b11 == 0b10 };
+
+        let flicker = Flicker::new(1);
+
+        set_frames(&flicker, 1);
+        flicker.apply_to_object(&mut emu);
+        object.commit();
+        assert!(disabled());
+
+        set_frames(&flicker, 2);
+        flicker.apply_to_object(&mut emu);
+        object.commit();
+        assert!(!disabled());
+    }
+}
@@ -1,9 +1,12 @@
+use alloc::vec::Vec;
 use core::fmt::{Error, Write};
 
 use crate::fixnum::Vector2D;
 use crate::hash_map::HashMap;
 
+use super::object::{DynamicSprite, Object, ObjectController, PaletteVram, Size, SpriteBorrow};
 use super::tiled::{DynamicTile, RegularMap, TileSetting, VRamManager};
+use super::Priority;
 
 pub struct FontLetter {
     width: u8,
@@ -207,6 +210,240 @@ impl<'a> Drop for TextRenderer<'a> {
     }
 }
 
+/// Renders short strings, such as damage numbers or name tags, as objects
+/// rather than onto a background. Each distinct character is copied to sprite
+/// vram once and shared between every string created by the renderer.
+pub struct ObjectTextRenderer<'a> {
+    controller: &'a ObjectController,
+    font: &'a Font,
+    palette: PaletteVram,
+    foreground_colour: u8,
+    glyphs: HashMap<char, SpriteBorrow>,
+}
+
+impl<'a> ObjectTextRenderer<'a> {
+    /// Creates a renderer which draws text in the given colour of the palette.
+    /// Everything other than the text itself is transparent.
+    #[must_use]
+    pub fn new(
+        controller: &'a ObjectController,
+        font: &'a Font,
+        palette: PaletteVram,
+        foreground_colour: u8,
+    ) -> Self {
+        Self {
+            controller,
+            font,
+            palette,
+            foreground_colour,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// Creates the objects to display the text, with one object for each
+    /// visible character. The text starts hidden at the top left of the
+    /// screen. Panics if there aren't enough objects or room in vram.
+    pub fn text(&mut self, text: &str) -> ObjectText<'a> {
+        let mut letters = Vec::new();
+        let mut x = 0;
+        let mut y = 0;
+        let mut width = 0;
+
+        for c in text.chars() {
+            if c == '\n' {
+                y += self.font.line_height;
+                x = 0;
+                continue;
+            }
+
+            let letter = self.font.letter(c);
+
+            if letter.width > 0 && letter.height > 0 {
+                let sprite = self.glyph(c, letter);
+                let offset = (
+                    x + i32::from(letter.xmin),
+                    y + self.font.ascent - i32::from(letter.height) - i32::from(letter.ymin),
+                )
+                    .into();
+
+                letters.push((self.controller.object(sprite), offset));
+            }
+
+            x += i32::from(letter.advance_width);
+            width = width.max(x);
+        }
+
+        let mut text = ObjectText {
+            letters,
+            position: (0, 0).into(),
+            width,
+            visible: false,
+            z: 0,
+            priority: Priority::P0,
+        };
+
+        text.update_objects();
+        text
+    }
+
+    /// Frees the vram used by characters which aren't used by any remaining
+    /// text.
+    pub fn clear_glyph_cache(&mut self) {
+        self.glyphs.clear();
+    }
+
+    fn glyph(&mut self, c: char, letter: &FontLetter) -> SpriteBorrow {
+        let foreground_colour = self.foreground_colour;
+        let palette = &self.palette;
+
+        self.glyphs
+            .entry(c)
+            .or_insert_with(|| {
+                let size = glyph_size(letter.width, letter.height);
+                let data = glyph_tile_data(letter, size, foreground_colour);
+
+                let data = unsafe {
+                    core::slice::from_raw_parts(data.as_ptr().cast::<u8>(), data.len() * 4)
+                };
+
+                DynamicSprite::new(data, size).to_vram(palette.clone())
+            })
+            .clone()
+    }
+}
+
+/// The smallest sprite size which fits a glyph of the given size.
+fn glyph_size(width: u8, height: u8) -> Size {
+    let round = |length: u8| match length {
+        0..=8 => 8,
+        9..=16 => 16,
+        17..=32 => 32,
+        _ => 64,
+    };
+
+    let mut width = round(width);
+    let mut height = round(height);
+
+    // sprites can be at most 4 times as wide as they are tall, and 64 pixel
+    // long sprites at most twice as wide
+    if width == 64 {
+        height = height.max(32);
+    } else if width > height * 4 {
+        height = width / 4;
+    }
+
+    if height == 64 {
+        width = width.max(32);
+    } else if height > width * 4 {
+        width = height / 4;
+    }
+
+    Size::from_width_height(width, height)
+}
+
+/// Draws the glyph into the top left of a sprite of the given size, as 4 bit
+/// per pixel tiles.
+fn glyph_tile_data(letter: &FontLetter, size: Size, colour: u8) -> Vec<u32> {
+    let (sprite_width, sprite_height) = size.to_width_height();
+    let tiles_wide = sprite_width / 8;
+
+    let mut data = alloc::vec![0u32; sprite_width * sprite_height / 8];
+
+    for y in 0..usize::from(letter.height) {
+        for x in 0..usize::from(letter.width) {
+            let pos = x + y * usize::from(letter.width);
+            if (letter.data[pos / 8] >> (pos & 7)) & 1 == 0 {
+                continue;
+            }
+
+            let tile = (y / 8) * tiles_wide + x / 8;
+            data[tile * 8 + y % 8] |= u32::from(colour & 0xf) << ((x % 8) * 4);
+        }
+    }
+
+    data
+}
+
+/// A string displayed using objects, created by [ObjectTextRenderer::text].
+pub struct ObjectText<'a> {
+    letters: Vec<(Object<'a>, Vector2D<i32>)>,
+    position: Vector2D<i32>,
+    width: i32,
+    visible: bool,
+    z: i32,
+    priority: Priority,
+}
+
+impl<'a> ObjectText<'a> {
+    /// Sets the position of the top left of the text. No change will be seen
+    /// until [ObjectController::commit] is called.
+    pub fn set_position(&mut self, position: Vector2D<i32>) -> &mut Self {
+        self.position = position;
+        self.update_objects();
+        self
+    }
+
+    /// Sets the z position of every character. See [Object::set_z]. No change
+    /// will be seen until [ObjectController::commit] is called.
+    pub fn set_z(&mut self, z: i32) -> &mut Self {
+        self.z = z;
+        self.update_objects();
+        self
+    }
+
+    /// Sets the priority of every character. See [Object::set_priority]. No
+    /// change will be seen until [ObjectController::commit] is called.
+    pub fn set_priority(&mut self, priority: Priority) -> &mut Self {
+        self.priority = priority;
+        self.update_objects();
+        self
+    }
+
+    /// Shows the text. No change will be seen until [ObjectController::commit]
+    /// is called.
+    pub fn show(&mut self) -> &mut Self {
+        self.visible = true;
+        self.update_objects();
+        self
+    }
+
+    /// Hides the text. No change will be seen until [ObjectController::commit]
+    /// is called.
+    pub fn hide(&mut self) -> &mut Self {
+        self.visible = false;
+        self.update_objects();
+        self
+    }
+
+    /// The width of the longest line of the text in pixels, which is useful
+    /// for centring it.
+    #[must_use]
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// The number of objects used to display the text.
+    #[must_use]
+    pub fn objects_used(&self) -> usize {
+        self.letters.len()
+    }
+
+    fn update_objects(&mut self) {
+        for (object, offset) in self.letters.iter_mut() {
+            object
+                .set_position(self.position + *offset)
+                .set_z(self.z)
+                .set_priority(self.priority);
+
+            if self.visible {
+                object.show();
+            } else {
+                object.hide();
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -254,4 +491,31 @@ mod tests {
 
         crate::test_runner::assert_image_output("examples/font/font-test-output.png");
     }
+
+    #[test_case]
+    fn glyph_sizes_are_valid_sprite_sizes(_gba: &mut crate::Gba) {
+        assert!(glyph_size(5, 7) == Size::S8x8);
+        assert!(glyph_size(12, 7) == Size::S16x8);
+        assert!(glyph_size(40, 3) == Size::S64x32);
+        assert!(glyph_size(3, 30) == Size::S8x32);
+    }
+
+    #[test_case]
+    fn object_text_uses_an_object_per_visible_character(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let palette = object.palette(&crate::display::palette16::Palette16::new([0x7fff; 16]));
+
+        let mut renderer = ObjectTextRenderer::new(&object, &FONT, palette, 1);
+
+        let mut text = renderer.text("12");
+        assert_eq!(text.objects_used(), 2);
+        assert!(text.width() > 0);
+
+        text.set_position((10, 10).into()).show();
+        object.commit();
+
+        let other = renderer.text("2\n22");
+        assert_eq!(other.objects_used(), 3);
+        assert!(other.width() <= text.width() * 2);
+    }
 }
@@ -0,0 +1,186 @@
+#![deny(missing_docs)]
+//! Alternating between two background configurations on even and odd frames.
+//!
+//! Showing a background on only every other frame makes it look half
+//! transparent, and swapping which of two backgrounds is in front each frame
+//! blends them together. This can fake extra layers or transparency where the
+//! hardware blending is already in use. [Interlace] applies the
+//! configurations from the vblank interrupt, so it never falls out of step
+//! with the frames actually displayed.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! # use agb::display::{interlace::{Interlace, PlayfieldConfig}, tiled::BackgroundID};
+//! # fn foo(clouds: BackgroundID) {
+//! let vblank = agb::interrupt::VBlank::get();
+//!
+//! // the clouds are only shown on even frames, making them look see through
+//! let interlace = Interlace::new(
+//!     PlayfieldConfig::new().show(clouds),
+//!     PlayfieldConfig::new().hide(clouds),
+//! );
+//! let _handler = interlace.setup_interrupt_handler();
+//!
+//! loop {
+//!     vblank.wait_for_vblank();
+//! }
+//! # }
+//! ```
+
+use core::cell::{Cell, RefCell};
+
+use bare_metal::{CriticalSection, Mutex};
+
+use super::{tiled::BackgroundID, Priority, DISPLAY_CONTROL};
+use crate::interrupt::{add_interrupt_handler, free, Interrupt, InterruptHandler};
+use crate::memory_mapped::MemoryMapped;
+
+const BACKGROUNDS: usize = 4;
+
+fn bg_control_register(background: usize) -> MemoryMapped<u16> {
+    unsafe { MemoryMapped::new(0x0400_0008 + 2 * background) }
+}
+
+/// The visibility and priority changes made to the backgrounds on one frame
+/// of an [Interlace]. Backgrounds which aren't mentioned are left alone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlayfieldConfig {
+    shown: u8,
+    hidden: u8,
+    priorities: [Option<Priority>; BACKGROUNDS],
+}
+
+impl PlayfieldConfig {
+    /// A configuration which doesn't change any backgrounds.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            shown: 0,
+            hidden: 0,
+            priorities: [None; BACKGROUNDS],
+        }
+    }
+
+    /// Shows the background on this frame.
+    #[must_use]
+    pub const fn show(mut self, background: BackgroundID) -> Self {
+        self.shown |= 1 << background.0;
+        self.hidden &= !(1 << background.0);
+        self
+    }
+
+    /// Hides the background on this frame.
+    #[must_use]
+    pub const fn hide(mut self, background: BackgroundID) -> Self {
+        self.hidden |= 1 << background.0;
+        self.shown &= !(1 << background.0);
+        self
+    }
+
+    /// Gives the background a different priority on this frame.
+    #[must_use]
+    pub const fn with_priority(mut self, background: BackgroundID, priority: Priority) -> Self {
+        self.priorities[background.0 as usize] = Some(priority);
+        self
+    }
+
+    fn apply(self) {
+        let layers = DISPLAY_CONTROL.get();
+        let layers = (layers | (u16::from(self.shown) << 8)) & !(u16::from(self.hidden) << 8);
+        DISPLAY_CONTROL.set(layers);
+
+        for (background, priority) in self.priorities.iter().enumerate() {
+            if let Some(priority) = priority {
+                bg_control_register(background).set_bits(*priority as u16, 2, 0);
+            }
+        }
+    }
+}
+
+/// Switches between two [PlayfieldConfig]s at the start of every frame.
+pub struct Interlace {
+    configs: Mutex<RefCell<[PlayfieldConfig; 2]>>,
+    odd_frame: Mutex<Cell<bool>>,
+}
+
+impl Interlace {
+    /// Creates an interlace which uses `even` on one frame and `odd` on the
+    /// next. Nothing changes until the interrupt handler is set up.
+    #[must_use]
+    pub fn new(even: PlayfieldConfig, odd: PlayfieldConfig) -> Self {
+        Self {
+            configs: Mutex::new(RefCell::new([even, odd])),
+            odd_frame: Mutex::new(Cell::new(true)),
+        }
+    }
+
+    /// Replaces both configurations, taking effect from the next frame.
+    pub fn set_configs(&self, even: PlayfieldConfig, odd: PlayfieldConfig) {
+        free(|cs| *self.configs.borrow(cs).borrow_mut() = [even, odd]);
+    }
+
+    /// Whether the odd configuration is being displayed.
+    #[must_use]
+    pub fn is_odd_frame(&self) -> bool {
+        free(|cs| self.odd_frame.borrow(cs).get())
+    }
+
+    /// Applies the configuration for the current frame again. Committing a
+    /// background resets its priority, so call this after committing any
+    /// background whose priority the interlace changes.
+    pub fn reapply(&self) {
+        free(|cs| self.apply(cs));
+    }
+
+    /// Swaps between the configurations at every vblank while the returned
+    /// handler is alive. Backgrounds are left as they were on the last frame
+    /// once it is dropped.
+    #[must_use]
+    pub fn setup_interrupt_handler(&self) -> InterruptHandler<'_> {
+        add_interrupt_handler(Interrupt::VBlank, move |cs| {
+            let odd_frame = self.odd_frame.borrow(cs);
+            odd_frame.set(!odd_frame.get());
+            self.apply(cs);
+        })
+    }
+
+    fn apply(&self, cs: CriticalSection) {
+        let odd_frame = self.odd_frame.borrow(cs).get();
+        self.configs.borrow(cs).borrow()[usize::from(odd_frame)].apply();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::tiled::{RegularBackgroundSize, TiledMap};
+
+    #[test_case]
+    fn interlace_alternates_backgrounds(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+        let mut map = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+        map.commit(&mut vram);
+        map.show();
+
+        let background = map.background();
+        let interlace = Interlace::new(
+            PlayfieldConfig::new().show(background),
+            PlayfieldConfig::new()
+                .hide(background)
+                .with_priority(background, Priority::P3),
+        );
+
+        let vblank = crate::interrupt::VBlank::get();
+        let _handler = interlace.setup_interrupt_handler();
+
+        for _ in 0..4 {
+            vblank.wait_for_vblank();
+
+            let shown = DISPLAY_CONTROL.get() & (1 << (8 + background.0)) != 0;
+            assert_eq!(shown, !interlace.is_odd_frame());
+        }
+
+        interlace.set_configs(PlayfieldConfig::new(), PlayfieldConfig::new());
+    }
+}
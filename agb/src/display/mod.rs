@@ -1,15 +1,18 @@
+use crate::fixnum::{Rect, Vector2D};
 use crate::memory_mapped::MemoryMapped;
 use bitflags::bitflags;
 
 use modular_bitfield::BitfieldSpecifier;
 use video::Video;
 
-use self::{blend::Blend, object::ObjectController, window::Windows};
+use self::{blend::Blend, mosaic::Mosaic, object::ObjectController, window::Windows};
 
 /// Graphics mode 3. Bitmap mode that provides a 16-bit colour framebuffer.
 pub mod bitmap3;
 /// Graphics mode 4. Bitmap 4 provides two 8-bit paletted framebuffers with page switching.
 pub mod bitmap4;
+/// Graphics mode 5. Bitmap 5 provides two 160x128 16-bit colour framebuffers with page switching.
+pub mod bitmap5;
 /// Test logo of agb.
 pub mod example_logo;
 /// Implements sprites.
@@ -25,14 +28,23 @@ pub mod video;
 
 pub mod affine;
 pub mod blend;
+pub mod capture;
+pub mod commit_queue;
+pub mod control;
+pub mod flicker;
+pub mod interlace;
+pub mod mosaic;
+pub mod raster;
 pub mod window;
 
 pub mod font;
-pub use font::{Font, FontLetter};
+pub use font::{Font, FontLetter, ObjectText, ObjectTextRenderer};
 
-const DISPLAY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0000) };
+pub use control::{DisplayControl, ObjectTileMapping};
+
+pub(crate) const DISPLAY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0000) };
 pub(crate) const DISPLAY_STATUS: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0004) };
-const VCOUNT: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0006) };
+pub(crate) const VCOUNT: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0006) };
 
 bitflags! {
     struct GraphicsSettings: u16 {
@@ -56,6 +68,39 @@ pub const WIDTH: i32 = 240;
 /// Height of the Gameboy advance screen in pixels
 pub const HEIGHT: i32 = 160;
 
+/// The size of a tile in pixels, in both directions.
+pub const TILE_SIZE: i32 = 8;
+
+/// The area of the screen, in pixels from the top left corner.
+#[must_use]
+pub fn screen_rect() -> Rect<i32> {
+    Rect::new((0, 0).into(), (WIDTH, HEIGHT).into())
+}
+
+/// The tile containing a pixel, rounding down so that pixels with negative
+/// coordinates are in negative tiles.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::display::pixel_to_tile;
+/// use agb::fixnum::Vector2D;
+///
+/// # fn foo() {
+/// assert_eq!(pixel_to_tile((17, -1).into()), Vector2D::new(2, -1));
+/// # }
+/// ```
+#[must_use]
+pub fn pixel_to_tile(pixel: Vector2D<i32>) -> Vector2D<i32> {
+    Vector2D::new(pixel.x.div_euclid(TILE_SIZE), pixel.y.div_euclid(TILE_SIZE))
+}
+
+/// The top left pixel of a tile.
+#[must_use]
+pub fn tile_to_pixel(tile: Vector2D<i32>) -> Vector2D<i32> {
+    tile * TILE_SIZE
+}
+
 #[allow(dead_code)]
 enum DisplayMode {
     Tiled0 = 0,
@@ -73,6 +118,7 @@ pub struct Display {
     pub object: ObjectDistribution,
     pub window: WindowDist,
     pub blend: BlendDist,
+    pub mosaic: MosaicDist,
 }
 
 #[non_exhaustive]
@@ -102,6 +148,15 @@ impl BlendDist {
     }
 }
 
+#[non_exhaustive]
+pub struct MosaicDist;
+
+impl MosaicDist {
+    pub fn get(&mut self) -> Mosaic {
+        Mosaic::new()
+    }
+}
+
 impl Display {
     pub(crate) const unsafe fn new() -> Self {
         Display {
@@ -109,6 +164,7 @@ impl Display {
             object: ObjectDistribution,
             window: WindowDist,
             blend: BlendDist,
+            mosaic: MosaicDist,
         }
     }
 }
@@ -125,9 +181,14 @@ unsafe fn set_graphics_mode(mode: DisplayMode) {
 }
 
 unsafe fn set_graphics_settings(settings: GraphicsSettings) {
-    let current = DISPLAY_CONTROL.get();
-    // preserve display mode
-    let current = current & 0b111;
+    let replaced = GraphicsSettings::PAGE_SELECT
+        | GraphicsSettings::LAYER_BG0
+        | GraphicsSettings::LAYER_BG1
+        | GraphicsSettings::LAYER_BG2
+        | GraphicsSettings::LAYER_BG3;
+
+    // preserve display mode along with the object and window settings
+    let current = DISPLAY_CONTROL.get() & !replaced.bits();
     let s = settings.bits() | current;
 
     DISPLAY_CONTROL.set(s);
@@ -142,7 +203,7 @@ pub fn busy_wait_for_vblank() {
     while VCOUNT.get() < 160 {}
 }
 
-#[derive(BitfieldSpecifier, Clone, Copy)]
+#[derive(BitfieldSpecifier, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Priority {
     P0 = 0,
     P1 = 1,
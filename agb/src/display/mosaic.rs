@@ -0,0 +1,232 @@
+#![deny(missing_docs)]
+//! The mosaic effect, which pixelates backgrounds and objects by displaying
+//! blocks of pixels using the colour of the top left pixel of each block.
+//!
+//! The sizes set here only affect backgrounds which have mosaic enabled with
+//! `set_mosaic` on the map, and objects with mosaic enabled using
+//! [Object::set_mosaic](super::object::Object::set_mosaic).
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! # fn foo(gba: &mut agb::Gba) {
+//! use agb::display::mosaic::MosaicAnimation;
+//!
+//! let mut mosaic = gba.display.mosaic.get();
+//! let vblank = agb::interrupt::VBlank::get();
+//!
+//! // pixelate everything with mosaic enabled over half a second
+//! let mut animation = MosaicAnimation::new(1, 16, 30);
+//! while !animation.update(&mut mosaic) {
+//!     vblank.wait_for_vblank();
+//!     mosaic.commit();
+//! }
+//! # }
+//! ```
+
+use crate::fixnum::Vector2D;
+use crate::memory_mapped::MemoryMapped;
+
+const MOSAIC: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_004C) };
+
+/// The largest size of a mosaic block in either direction.
+pub const MAX_MOSAIC_SIZE: u8 = 16;
+
+/// Holds the sizes of the mosaic blocks for backgrounds and objects, which
+/// won't change until [Mosaic::commit] is called.
+pub struct Mosaic {
+    background: Vector2D<u8>,
+    object: Vector2D<u8>,
+}
+
+impl Mosaic {
+    pub(crate) fn new() -> Self {
+        let mosaic = Self {
+            background: (1, 1).into(),
+            object: (1, 1).into(),
+        };
+        mosaic.commit();
+
+        mosaic
+    }
+
+    /// Sets the width and height in pixels of the blocks backgrounds are
+    /// displayed with, where a size of 1 has no effect. Sizes are clamped
+    /// between 1 and [MAX_MOSAIC_SIZE].
+    pub fn set_background_size(&mut self, width: u8, height: u8) -> &mut Self {
+        self.background = clamp_size(width, height);
+
+        self
+    }
+
+    /// Sets the width and height in pixels of the blocks objects are
+    /// displayed with, where a size of 1 has no effect. Sizes are clamped
+    /// between 1 and [MAX_MOSAIC_SIZE].
+    pub fn set_object_size(&mut self, width: u8, height: u8) -> &mut Self {
+        self.object = clamp_size(width, height);
+
+        self
+    }
+
+    /// The size of the blocks backgrounds are displayed with.
+    #[must_use]
+    pub fn background_size(&self) -> Vector2D<u8> {
+        self.background
+    }
+
+    /// The size of the blocks objects are displayed with.
+    #[must_use]
+    pub fn object_size(&self) -> Vector2D<u8> {
+        self.object
+    }
+
+    /// Resets the sizes so mosaic has no effect.
+    pub fn reset(&mut self) -> &mut Self {
+        self.set_background_size(1, 1).set_object_size(1, 1)
+    }
+
+    fn register_value(&self) -> u16 {
+        let sizes = [
+            self.background.x,
+            self.background.y,
+            self.object.x,
+            self.object.y,
+        ];
+
+        sizes.iter().enumerate().fold(0, |value, (i, &size)| {
+            value | u16::from(size - 1) << (i * 4)
+        })
+    }
+
+    /// Commits the sizes, should be called shortly after waiting for vblank.
+    pub fn commit(&self) {
+        MOSAIC.set(self.register_value());
+    }
+}
+
+impl Drop for Mosaic {
+    fn drop(&mut self) {
+        self.reset().commit();
+    }
+}
+
+fn clamp_size(width: u8, height: u8) -> Vector2D<u8> {
+    (
+        width.clamp(1, MAX_MOSAIC_SIZE),
+        height.clamp(1, MAX_MOSAIC_SIZE),
+    )
+        .into()
+}
+
+/// Steps the mosaic size from one size to another over a number of frames,
+/// for example to pixelate the screen during a transition. By default both
+/// backgrounds and objects are animated.
+pub struct MosaicAnimation {
+    from: u8,
+    to: u8,
+    frames: u16,
+    frame: u16,
+    backgrounds: bool,
+    objects: bool,
+}
+
+impl MosaicAnimation {
+    /// Creates an animation from the `from` size to the `to` size which takes
+    /// the given number of frames.
+    #[must_use]
+    pub fn new(from: u8, to: u8, frames: u16) -> Self {
+        Self {
+            from,
+            to,
+            frames,
+            frame: 0,
+            backgrounds: true,
+            objects: true,
+        }
+    }
+
+    /// Sets whether the background mosaic size is animated.
+    #[must_use]
+    pub fn with_backgrounds(mut self, backgrounds: bool) -> Self {
+        self.backgrounds = backgrounds;
+        self
+    }
+
+    /// Sets whether the object mosaic size is animated.
+    #[must_use]
+    pub fn with_objects(mut self, objects: bool) -> Self {
+        self.objects = objects;
+        self
+    }
+
+    /// The size for the current frame of the animation.
+    #[must_use]
+    pub fn size(&self) -> u8 {
+        if self.frames == 0 {
+            return self.to;
+        }
+
+        let from = i32::from(self.from);
+        let to = i32::from(self.to);
+        let progress = i32::from(self.frame.min(self.frames));
+
+        (from + (to - from) * progress / i32::from(self.frames)) as u8
+    }
+
+    /// Whether the animation has reached its final size.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.frames
+    }
+
+    /// Sets the sizes in the mosaic for the current frame and advances to the
+    /// next, returning whether the animation has finished. This should be
+    /// called once per frame, and [Mosaic::commit] is still needed for the
+    /// change to be seen.
+    pub fn update(&mut self, mosaic: &mut Mosaic) -> bool {
+        let size = self.size();
+
+        if self.backgrounds {
+            mosaic.set_background_size(size, size);
+        }
+        if self.objects {
+            mosaic.set_object_size(size, size);
+        }
+
+        let finished = self.is_finished();
+        self.frame = self.frame.saturating_add(1);
+
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn mosaic_sizes_are_packed_into_the_register(gba: &mut crate::Gba) {
+        let mut mosaic = gba.display.mosaic.get();
+
+        mosaic.set_background_size(2, 3).set_object_size(0, 40);
+        assert_eq!(mosaic.object_size(), (1, 16).into());
+        assert_eq!(mosaic.register_value(), 0x1 | 0x2 << 4 | 0xf << 12);
+    }
+
+    #[test_case]
+    fn mosaic_animations_reach_their_final_size(gba: &mut crate::Gba) {
+        let mut mosaic = gba.display.mosaic.get();
+        let mut animation = MosaicAnimation::new(1, 9, 4).with_objects(false);
+
+        let mut sizes = [0; 5];
+        let mut finished = [false; 5];
+        for (size, finished) in sizes.iter_mut().zip(finished.iter_mut()) {
+            *finished = animation.update(&mut mosaic);
+            *size = mosaic.background_size().x;
+        }
+
+        assert_eq!(sizes, [1, 3, 5, 7, 9]);
+        assert_eq!(finished, [false, false, false, false, true]);
+        assert_eq!(mosaic.object_size(), (1, 1).into());
+    }
+}
@@ -2,8 +2,9 @@
 use alloc::rc::{Rc, Weak};
 use alloc::vec::Vec;
 use core::alloc::Layout;
+use core::cmp::Ordering;
 
-use core::cell::UnsafeCell;
+use core::cell::{Cell, UnsafeCell};
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
 use core::ops::DerefMut;
@@ -13,17 +14,29 @@ use modular_bitfield::prelude::{B10, B2, B3, B4, B5, B8, B9};
 use modular_bitfield::{bitfield, BitfieldSpecifier};
 
 const BYTES_PER_TILE_4BPP: usize = 32;
+const BYTES_PER_TILE_8BPP: usize = 64;
 
+use super::affine::{AffineMatrix, AffineMatrixObject};
+use super::commit_queue::CommitQueue;
 use super::palette16::Palette16;
-use super::{Priority, DISPLAY_CONTROL};
+use super::{Priority, DISPLAY_CONTROL, HEIGHT, WIDTH};
 use crate::agb_alloc::block_allocator::BlockAllocator;
 use crate::agb_alloc::bump_allocator::StartEnd;
 use crate::dma;
-use crate::fixnum::Vector2D;
+use crate::fixnum::{Num, Rect, Vector2D};
 use crate::hash_map::HashMap;
+use crate::hit_box::CollisionMask;
+use crate::sync::Static;
 
 use attributes::*;
 
+mod multiplexer;
+mod pool;
+
+pub(crate) use multiplexer::multiplexer_active;
+pub use multiplexer::{MultiplexedSprite, Multiplexer, MultiplexerStats};
+pub use pool::{ObjectKey, ObjectPool};
+
 /// Include this type if you call `get_object_controller` in impl block. This
 /// helps you use the right lifetimes and doesn't impl Sync (using from two
 /// "threads" without synchronisation is not safe), but sending to another
@@ -42,12 +55,21 @@ static mut OBJECT_CONTROLLER: MaybeUninit<core::cell::RefCell<ObjectControllerSt
 #[cfg(not(debug_assertions))]
 static mut OBJECT_CONTROLLER: MaybeUninit<ObjectControllerStatic> = MaybeUninit::uninit();
 
+static OBJECT_CONTROLLER_ACTIVE: Static<bool> = Static::new(false);
+
+/// Whether an [ObjectController] currently exists, which relies on one
+/// dimensional object tile mapping.
+pub(crate) fn object_controller_active() -> bool {
+    OBJECT_CONTROLLER_ACTIVE.read()
+}
+
 impl<'a> ObjectControllerReference<'a> {
     unsafe fn init() -> Self {
         #[cfg(debug_assertions)]
         OBJECT_CONTROLLER.write(core::cell::RefCell::new(ObjectControllerStatic::new()));
         #[cfg(not(debug_assertions))]
         OBJECT_CONTROLLER.write(ObjectControllerStatic::new());
+        OBJECT_CONTROLLER_ACTIVE.write(true);
         Self {
             #[cfg(debug_assertions)]
             reference: unsafe { OBJECT_CONTROLLER.assume_init_ref() },
@@ -57,6 +79,7 @@ impl<'a> ObjectControllerReference<'a> {
 
     unsafe fn uninit() {
         OBJECT_CONTROLLER.assume_init_drop();
+        OBJECT_CONTROLLER_ACTIVE.write(false);
     }
 
     #[track_caller]
@@ -102,10 +125,43 @@ const TILE_SPRITE: usize = 0x06010000;
 const OBJECT_ATTRIBUTE_MEMORY: usize = 0x0700_0000;
 
 /// Sprite data. Refers to the palette, pixel data, and the size of the sprite.
+/// Sprites either use one of the 16 colour palettes, or for 256 colour sprites
+/// the single palette set with [ObjectController::set_sprite_palette_256].
 pub struct Sprite {
-    palette: &'static Palette16,
+    palette: Option<&'static Palette16>,
     data: &'static [u8],
     size: Size,
+    duration: u16,
+    hit_box: HitBox,
+    collision_mask: Option<&'static CollisionMask>,
+}
+
+/// The area of a sprite containing non transparent pixels
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct HitBox {
+    x: u8,
+    y: u8,
+    width: u8,
+    height: u8,
+}
+
+impl HitBox {
+    const fn full(size: Size) -> Self {
+        let (width, height) = size.to_width_height();
+        Self {
+            x: 0,
+            y: 0,
+            width: width as u8,
+            height: height as u8,
+        }
+    }
+
+    fn to_rect(self) -> Rect<i32> {
+        Rect::new(
+            (self.x as i32, self.y as i32).into(),
+            (self.width as i32, self.height as i32).into(),
+        )
+    }
 }
 
 /// Sprite data that can be used to create sprites in vram.
@@ -217,18 +273,362 @@ macro_rules! include_aseprite {
     ($($aseprite_path: expr),*) => {{
         use $crate::display::object::{Size, Sprite, Tag, TagMap, Graphics};
         use $crate::display::palette16::Palette16;
-        use $crate::align_bytes;
 
-        $crate::include_aseprite_inner!($($aseprite_path),*);
+        $crate::include_aseprite_inner!($crate; $($aseprite_path),*);
+
+        &Graphics::new(SPRITES, TAGS)
+    }};
+}
+
+/// Stores the metasprites returned by [include_metasprites](crate::include_metasprites).
+pub struct MetaSpriteGraphics {
+    frames: &'static [MetaSpriteData],
+    tags: &'static [(&'static str, usize, usize)],
+}
+
+impl MetaSpriteGraphics {
+    #[doc(hidden)]
+    /// Creates metasprite graphics from its frames and tags. Used internally
+    /// by [include_metasprites](crate::include_metasprites).
+    #[must_use]
+    pub const fn new(
+        frames: &'static [MetaSpriteData],
+        tags: &'static [(&'static str, usize, usize)],
+    ) -> Self {
+        Self { frames, tags }
+    }
+
+    /// Every frame from the included aseprite files.
+    #[must_use]
+    pub const fn frames(&self) -> &'static [MetaSpriteData] {
+        self.frames
+    }
+
+    /// A single frame referred to by its index across all the included files.
+    #[must_use]
+    pub const fn frame(&self, idx: usize) -> &'static MetaSpriteData {
+        &self.frames[idx]
+    }
+
+    /// The frames making up the aseprite tag with the given name.
+    ///
+    /// # Panics
+    /// Panics if there is no tag with the given name.
+    #[must_use]
+    pub fn tag(&self, tag: &str) -> &'static [MetaSpriteData] {
+        let &(_, from, to) = self
+            .tags
+            .iter()
+            .find(|(name, _, _)| *name == tag)
+            .expect("metasprite tag should exist");
+
+        &self.frames[from..=to]
+    }
+}
+
+/// A single hardware sprite which makes up part of a [MetaSpriteData].
+pub struct MetaSpritePart {
+    sprite: &'static Sprite,
+    offset_x: i32,
+    offset_y: i32,
+}
+
+impl MetaSpritePart {
+    #[doc(hidden)]
+    /// Creates a part from the sprite and its offset from the top left of the
+    /// metasprite. Used internally by [include_metasprites](crate::include_metasprites).
+    #[must_use]
+    pub const fn new(sprite: &'static Sprite, offset_x: i32, offset_y: i32) -> Self {
+        Self {
+            sprite,
+            offset_x,
+            offset_y,
+        }
+    }
+
+    /// The sprite displayed by this part.
+    #[must_use]
+    pub const fn sprite(&self) -> &'static Sprite {
+        self.sprite
+    }
+
+    /// The offset of the top left of this part from the top left of the
+    /// metasprite.
+    #[must_use]
+    pub fn offset(&self) -> Vector2D<i32> {
+        (self.offset_x, self.offset_y).into()
+    }
+}
+
+/// A single frame of a metasprite, made up of several [MetaSpritePart]s.
+pub struct MetaSpriteData {
+    parts: &'static [MetaSpritePart],
+    width: u16,
+    height: u16,
+    duration: u16,
+}
+
+impl MetaSpriteData {
+    #[doc(hidden)]
+    /// Creates a metasprite frame from its parts. Used internally by
+    /// [include_metasprites](crate::include_metasprites).
+    #[must_use]
+    pub const fn new(parts: &'static [MetaSpritePart], width: u16, height: u16) -> Self {
+        Self {
+            parts,
+            width,
+            height,
+            duration: DEFAULT_FRAME_DURATION_MS,
+        }
+    }
+
+    #[doc(hidden)]
+    /// Sets how long this frame is shown for when animating, used internally
+    /// by [include_metasprites](crate::include_metasprites).
+    #[must_use]
+    pub const fn with_duration(mut self, duration_ms: u16) -> Self {
+        self.duration = duration_ms;
+        self
+    }
+
+    /// The hardware sprites which make up this frame.
+    #[must_use]
+    pub const fn parts(&self) -> &'static [MetaSpritePart] {
+        self.parts
+    }
+
+    /// The size of the frame in aseprite, which is used for mirroring parts
+    /// when flipped.
+    #[must_use]
+    pub fn size(&self) -> Vector2D<i32> {
+        (self.width as i32, self.height as i32).into()
+    }
+
+    /// How long this frame of the animation should be shown for, in
+    /// milliseconds, as set in aseprite.
+    #[must_use]
+    pub const fn duration_ms(&self) -> u16 {
+        self.duration
+    }
+}
+
+/// A group of objects which are positioned and flipped together to display a
+/// [MetaSpriteData], for characters too large or oddly shaped to be a single
+/// sprite. Uses one object per part of the metasprite.
+pub struct MetaSprite<'a> {
+    controller: &'a ObjectController,
+    objects: Vec<Object<'a>>,
+    data: &'static MetaSpriteData,
+    position: Vector2D<i32>,
+    hflip: bool,
+    vflip: bool,
+    visible: bool,
+    z: i32,
+    priority: Priority,
+}
+
+impl<'a> MetaSprite<'a> {
+    /// Creates the objects needed to display the metasprite. The metasprite
+    /// starts hidden. Panics if there aren't enough objects or room in vram.
+    #[must_use]
+    pub fn new(controller: &'a ObjectController, data: &'static MetaSpriteData) -> Self {
+        let mut metasprite = Self {
+            controller,
+            objects: Vec::new(),
+            data,
+            position: (0, 0).into(),
+            hflip: false,
+            vflip: false,
+            visible: false,
+            z: 0,
+            priority: Priority::P0,
+        };
+
+        metasprite.set_data(data);
+        metasprite
+    }
+
+    /// Changes the frame being displayed, creating or freeing objects if the
+    /// number of parts has changed. No change will be seen until
+    /// [ObjectController::commit] is called.
+    pub fn set_data(&mut self, data: &'static MetaSpriteData) -> &mut Self {
+        self.data = data;
+
+        self.objects.truncate(data.parts.len());
+
+        for (i, part) in data.parts.iter().enumerate() {
+            let sprite = self.controller.sprite(part.sprite);
+            match self.objects.get_mut(i) {
+                Some(object) => object.set_sprite(sprite),
+                None => self.objects.push(self.controller.object(sprite)),
+            }
+        }
+
+        self.update_objects();
+        self
+    }
+
+    /// Sets the position of the top left of the metasprite. No change will be
+    /// seen until [ObjectController::commit] is called.
+    pub fn set_position(&mut self, position: Vector2D<i32>) -> &mut Self {
+        self.position = position;
+        self.update_objects();
+        self
+    }
+
+    /// Flips the whole metasprite horizontally, mirroring the positions of its
+    /// parts. No change will be seen until [ObjectController::commit] is
+    /// called.
+    pub fn set_hflip(&mut self, flip: bool) -> &mut Self {
+        self.hflip = flip;
+        self.update_objects();
+        self
+    }
+
+    /// Flips the whole metasprite vertically, mirroring the positions of its
+    /// parts. No change will be seen until [ObjectController::commit] is
+    /// called.
+    pub fn set_vflip(&mut self, flip: bool) -> &mut Self {
+        self.vflip = flip;
+        self.update_objects();
+        self
+    }
+
+    /// Sets the z position of every part. See [Object::set_z]. No change will
+    /// be seen until [ObjectController::commit] is called.
+    pub fn set_z(&mut self, z: i32) -> &mut Self {
+        self.z = z;
+        self.update_objects();
+        self
+    }
+
+    /// Sets the priority of every part. See [Object::set_priority]. No change
+    /// will be seen until [ObjectController::commit] is called.
+    pub fn set_priority(&mut self, priority: Priority) -> &mut Self {
+        self.priority = priority;
+        self.update_objects();
+        self
+    }
+
+    /// Shows the metasprite. No change will be seen until
+    /// [ObjectController::commit] is called.
+    pub fn show(&mut self) -> &mut Self {
+        self.visible = true;
+        self.update_objects();
+        self
+    }
+
+    /// Hides the metasprite. No change will be seen until
+    /// [ObjectController::commit] is called.
+    pub fn hide(&mut self) -> &mut Self {
+        self.visible = false;
+        self.update_objects();
+        self
+    }
+
+    /// The frame currently being displayed.
+    #[must_use]
+    pub fn data(&self) -> &'static MetaSpriteData {
+        self.data
+    }
+
+    fn update_objects(&mut self) {
+        let frame_size = self.data.size();
+
+        for (object, part) in self.objects.iter_mut().zip(self.data.parts) {
+            let (width, height) = part.sprite.size().to_width_height();
+            let mut offset = part.offset();
+
+            if self.hflip {
+                offset.x = frame_size.x - offset.x - width as i32;
+            }
+            if self.vflip {
+                offset.y = frame_size.y - offset.y - height as i32;
+            }
+
+            object
+                .set_position(self.position + offset)
+                .set_hflip(self.hflip)
+                .set_vflip(self.vflip)
+                .set_z(self.z)
+                .set_priority(self.priority);
+
+            if self.visible {
+                object.show();
+            } else {
+                object.hide();
+            }
+        }
+    }
+}
+
+/// The duration aseprite gives frames by default, in milliseconds.
+const DEFAULT_FRAME_DURATION_MS: u16 = 100;
+
+/// Includes sprites in the same way as [include_aseprite], but also includes a
+/// 1 bit per pixel mask of each sprite for pixel perfect collisions. The masks
+/// can be found with [Sprite::collision_mask] and compared using
+/// [mask_collision](crate::hit_box::mask_collision).
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::{display::object::Graphics, include_aseprite_with_masks};
+/// const GRAPHICS: &Graphics = include_aseprite_with_masks!(
+///     "examples/gfx/boss.aseprite",
+///     "examples/gfx/objects.aseprite"
+/// );
+/// ```
+#[macro_export]
+macro_rules! include_aseprite_with_masks {
+    ($($aseprite_path: expr),*) => {{
+        use $crate::display::object::{Size, Sprite, Tag, TagMap, Graphics};
+        use $crate::display::palette16::Palette16;
+        use $crate::hit_box::CollisionMask;
+
+        $crate::include_aseprite_with_masks_inner!($crate; $($aseprite_path),*);
 
         &Graphics::new(SPRITES, TAGS)
     }};
 }
 
-/// Stores sprite and tag data returned by [include_aseprite].
+/// Includes sprites found in the referenced aseprite files as 256 colour
+/// sprites, which all share a single palette of up to 255 colours plus
+/// transparency. Otherwise this is the same as [include_aseprite]. The palette
+/// must be set using [ObjectController::set_sprite_palette_256] with the
+/// palette from [Graphics::palette_256] before the sprites are displayed.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::{display::object::Graphics, include_aseprite_256};
+/// const GRAPHICS: &Graphics = include_aseprite_256!(
+///     "examples/gfx/boss.aseprite",
+///     "examples/gfx/objects.aseprite"
+/// );
+///
+/// # fn foo(gba: &mut agb::Gba) {
+/// let object = gba.display.object.get();
+/// object.set_sprite_palette_256(GRAPHICS.palette_256().unwrap());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! include_aseprite_256 {
+    ($($aseprite_path: expr),*) => {{
+        use $crate::display::object::{Size, Sprite, Tag, TagMap, Graphics};
+
+        $crate::include_aseprite_256_inner!($crate; $($aseprite_path),*);
+
+        &Graphics::new(SPRITES, TAGS).with_palette_256(PALETTE)
+    }};
+}
+
+/// Stores sprite and tag data returned by [include_aseprite] and
+/// [include_aseprite_256].
 pub struct Graphics {
     sprites: &'static [Sprite],
     tag_map: &'static TagMap,
+    palette_256: Option<&'static [u16]>,
 }
 
 impl Graphics {
@@ -238,7 +638,28 @@ impl Graphics {
     /// use.
     #[must_use]
     pub const fn new(sprites: &'static [Sprite], tag_map: &'static TagMap) -> Self {
-        Self { sprites, tag_map }
+        Self {
+            sprites,
+            tag_map,
+            palette_256: None,
+        }
+    }
+    #[doc(hidden)]
+    /// Sets the palette used by 256 colour sprites. This is used internally by
+    /// [include_aseprite_256].
+    #[must_use]
+    pub const fn with_palette_256(self, palette: &'static [u16]) -> Self {
+        Self {
+            palette_256: Some(palette),
+            ..self
+        }
+    }
+    /// The palette shared by the sprites from [include_aseprite_256], which
+    /// should be set with [ObjectController::set_sprite_palette_256]. Returns
+    /// [None] for sprites from [include_aseprite].
+    #[must_use]
+    pub const fn palette_256(&self) -> Option<&'static [u16]> {
+        self.palette_256
     }
     #[must_use]
     /// Gets the tag map from the aseprite files. This allows reference to
@@ -253,6 +674,39 @@ impl Graphics {
     }
 }
 
+/// Includes frames from the referenced aseprite files as metasprites, which are
+/// made up of several hardware sprites so that they can be larger or a
+/// different shape than a single sprite can be. Each frame is split into parts
+/// which are valid sprite sizes, skipping parts which are fully transparent.
+/// Returns a reference to [MetaSpriteGraphics].
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::{display::object::{MetaSprite, MetaSpriteGraphics}, include_metasprites};
+/// const BOSS: &MetaSpriteGraphics = include_metasprites!("examples/gfx/boss.aseprite");
+///
+/// # fn foo(gba: &mut agb::Gba) {
+/// let object = gba.display.object.get();
+///
+/// let mut boss = MetaSprite::new(&object, BOSS.frame(0));
+/// boss.set_position((100, 50).into()).set_hflip(true).show();
+///
+/// object.commit();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! include_metasprites {
+    ($($aseprite_path: expr),*) => {{
+        use $crate::display::object::{MetaSpriteData, MetaSpriteGraphics, MetaSpritePart, Size, Sprite};
+        use $crate::display::palette16::Palette16;
+
+        $crate::include_metasprites_inner!($crate; $($aseprite_path),*);
+
+        &MetaSpriteGraphics::new(METASPRITES, TAGS)
+    }};
+}
+
 /// Stores aseprite tags. Can be used to refer to animation sequences by name.
 /// ```rust,no_run
 /// # #![no_std]
@@ -397,6 +851,10 @@ impl Tag {
     #[must_use]
     pub fn animation_sprite(&self, idx: usize) -> &'static Sprite {
         let len_sub_1 = self.len - 1;
+        if len_sub_1 == 0 {
+            return self.sprite(0);
+        }
+
         match self.direction {
             Direction::Forward => self.sprite(idx % self.len),
             Direction::Backward => self.sprite(len_sub_1 - (idx % self.len)),
@@ -407,6 +865,17 @@ impl Tag {
         }
     }
 
+    /// The number of steps in one full cycle of the animation. This is the
+    /// number of sprites, except for ping pong animations which don't repeat
+    /// the first and last sprite.
+    #[must_use]
+    pub fn animation_length(&self) -> usize {
+        match self.direction {
+            Direction::PingPong if self.len > 1 => (self.len - 1) * 2,
+            _ => self.len,
+        }
+    }
+
     #[doc(hidden)]
     /// Creates a new sprite from it's constituent parts. Used internally by
     /// [include_aseprite] and should generally not be used elsewhere.
@@ -422,6 +891,115 @@ impl Tag {
     }
 }
 
+/// The time taken to display a single frame on the GBA, in milliseconds. This
+/// is 280896 cycles at 16.78MHz, or about 16.74ms.
+const FRAME_TIME_MS: Num<u32, 8> = Num::from_raw(4286);
+
+/// Plays back a [Tag] on an [Object], using the frame durations and animation
+/// direction set in aseprite.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::display::object::{AnimationPlayer, Graphics, Tag};
+/// # fn foo(gba: &mut agb::Gba) {
+/// const GRAPHICS: &Graphics = agb::include_aseprite!("examples/gfx/objects.aseprite");
+/// const EMU_WALK: &Tag = GRAPHICS.tags().get("emu-walk");
+///
+/// let object = gba.display.object.get();
+/// let vblank = agb::interrupt::VBlank::get();
+///
+/// let mut player = AnimationPlayer::new(EMU_WALK);
+/// let mut emu = object.object(object.sprite(player.sprite()));
+/// emu.show();
+///
+/// loop {
+///     player.update(&mut emu, &object);
+///
+///     vblank.wait_for_vblank();
+///     object.commit();
+/// }
+/// # }
+/// ```
+pub struct AnimationPlayer {
+    tag: &'static Tag,
+    step: usize,
+    elapsed: Num<u32, 8>,
+    current: &'static Sprite,
+}
+
+impl AnimationPlayer {
+    /// Creates a player which starts at the beginning of the given tag.
+    #[must_use]
+    pub fn new(tag: &'static Tag) -> Self {
+        Self {
+            tag,
+            step: 0,
+            elapsed: 0.into(),
+            current: tag.animation_sprite(0),
+        }
+    }
+
+    /// Changes the tag being played. If this is a different tag to the one
+    /// currently playing, the animation restarts from the beginning of the new
+    /// tag, otherwise the animation carries on uninterrupted.
+    pub fn set_tag(&mut self, tag: &'static Tag) {
+        if !core::ptr::eq(self.tag, tag) {
+            *self = Self::new(tag);
+        }
+    }
+
+    /// Restarts the animation from the beginning of the current tag.
+    pub fn restart(&mut self) {
+        *self = Self::new(self.tag);
+    }
+
+    /// Advances the animation by one frame, and if the sprite to be displayed
+    /// has changed sets it on the given object. This should be called once
+    /// per frame. No change will be seen until [ObjectController::commit] is
+    /// called.
+    pub fn update(&mut self, object: &mut Object, controller: &ObjectController) {
+        if self.advance() {
+            object.set_sprite(controller.sprite(self.current));
+        }
+    }
+
+    /// Advances the animation by one frame without changing any object.
+    /// Returns whether the sprite to be displayed has changed.
+    pub fn advance(&mut self) -> bool {
+        self.elapsed += FRAME_TIME_MS;
+
+        let mut changed = false;
+        loop {
+            let duration: Num<u32, 8> = (self.current.duration_ms().max(1) as u32).into();
+            if self.elapsed < duration {
+                break;
+            }
+
+            self.elapsed -= duration;
+            self.step = (self.step + 1) % self.tag.animation_length();
+
+            let next = self.tag.animation_sprite(self.step);
+            changed |= !core::ptr::eq(next, self.current);
+            self.current = next;
+        }
+
+        changed
+    }
+
+    /// The sprite which should currently be displayed.
+    #[must_use]
+    pub fn sprite(&self) -> &'static Sprite {
+        self.current
+    }
+
+    /// The tag currently being played.
+    #[must_use]
+    pub fn tag(&self) -> &'static Tag {
+        self.tag
+    }
+}
+
 impl Size {
     const fn number_of_tiles(self) -> usize {
         match self {
@@ -447,6 +1025,20 @@ impl Size {
         Layout::from_size_align(self.number_of_tiles() * BYTES_PER_TILE_4BPP, 8).unwrap()
     }
 
+    fn layout_256(self) -> Layout {
+        // 256 colour sprites need to start on an even tile, but the sprite
+        // allocator only aligns to 8 bytes and hands out freed blocks wherever
+        // they are, so asking for 64 byte alignment wouldn't be honoured. An
+        // extra 4bpp tile is allocated instead so the sprite can always be
+        // moved to the next even tile within the allocation, at the cost of
+        // one tile per 256 colour sprite.
+        Layout::from_size_align(
+            self.number_of_tiles() * BYTES_PER_TILE_8BPP + BYTES_PER_TILE_4BPP,
+            8,
+        )
+        .unwrap()
+    }
+
     #[must_use]
     /// Creates a size from width and height in pixels, panics if the width and
     /// height is not representable by GBA sprites.
@@ -515,13 +1107,34 @@ impl Attributes {
         }
     }
 
-    fn commit(&self, location: usize) {
-        let mode = self.a0.object_mode();
-        let attrs: [[u8; 2]; 3] = match mode {
-            ObjectMode::Normal => [
-                self.a0.into_bytes(),
-                self.a1s.into_bytes(),
-                self.a2.into_bytes(),
+    fn set_sprite(&mut self, sprite: &SpriteArena) {
+        self.a2.set_tile_index(sprite.tile_index());
+
+        match &sprite.palette {
+            Some(palette) => {
+                self.a0.set_colour_mode(ColourMode::Four);
+                self.a2.set_palette_bank(palette.0.location.0 as u8);
+            }
+            None => {
+                self.a0.set_colour_mode(ColourMode::Eight);
+                self.a2.set_palette_bank(0);
+            }
+        }
+
+        let shape_size = sprite.size.shape_size();
+        self.a0.set_shape(shape_size.0);
+        self.a1a.set_size(shape_size.1);
+        self.a1s.set_size(shape_size.1);
+    }
+
+    /// Writes the attributes to the entry.
+    fn write(&self, oam_entry: &mut [u16]) {
+        let mode = self.a0.object_mode();
+        let attrs: [[u8; 2]; 3] = match mode {
+            ObjectMode::Normal => [
+                self.a0.into_bytes(),
+                self.a1s.into_bytes(),
+                self.a2.into_bytes(),
             ],
             _ => [
                 self.a0.into_bytes(),
@@ -530,14 +1143,9 @@ impl Attributes {
             ],
         };
 
-        unsafe {
-            let attrs: [u16; 3] = core::mem::transmute(attrs);
-            let ptr = (OBJECT_ATTRIBUTE_MEMORY as *mut u16).add(location * 4);
-
-            ptr.add(0).write_volatile(attrs[0]);
-            ptr.add(1).write_volatile(attrs[1]);
-            ptr.add(2).write_volatile(attrs[2]);
-        };
+        for (entry, attr) in oam_entry.iter_mut().zip(attrs) {
+            *entry = u16::from_ne_bytes(attr);
+        }
     }
 }
 
@@ -585,7 +1193,7 @@ impl PaletteVram {
         let dest = unsafe { PALETTE_ALLOCATOR.alloc(Palette16::layout())? };
 
         unsafe {
-            dma::dma_copy16(
+            dma::copy_to::<u16>(
                 palette.colours.as_ptr().cast(),
                 dest.as_ptr().cast(),
                 palette.colours.len(),
@@ -605,13 +1213,14 @@ impl SpriteVram {
         let dest = unsafe { SPRITE_ALLOCATOR.alloc(size.layout())? };
 
         unsafe {
-            dma::dma_copy16(data.as_ptr().cast(), dest.as_ptr().cast(), data.len() / 2);
+            dma::copy_to::<u16>(data.as_ptr().cast(), dest.as_ptr().cast(), data.len() / 2);
         }
 
         Some(SpriteVram(Rc::new(SpriteArena {
             location: Location::from_sprite_ptr(dest),
             size,
-            palette,
+            hit_box: HitBox::full(size),
+            palette: Some(palette),
         })))
     }
 }
@@ -625,19 +1234,79 @@ impl Drop for PaletteData {
 struct SpriteArena {
     location: Location,
     size: Size,
-    palette: PaletteVram,
+    hit_box: HitBox,
+    /// The palette used by 16 colour sprites, or [None] for 256 colour sprites
+    palette: Option<PaletteVram>,
+}
+
+impl SpriteArena {
+    fn layout(&self) -> Layout {
+        match self.palette {
+            Some(_) => self.size.layout(),
+            None => self.size.layout_256(),
+        }
+    }
+
+    fn tile_index(&self) -> u16 {
+        match self.palette {
+            Some(_) => self.location.0 as u16,
+            None => (self.location.0 as u16 + 1) & !1,
+        }
+    }
 }
 
 impl Drop for SpriteArena {
     fn drop(&mut self) {
-        unsafe { SPRITE_ALLOCATOR.dealloc(self.location.as_sprite_ptr(), self.size.layout()) }
+        unsafe { SPRITE_ALLOCATOR.dealloc(self.location.as_sprite_ptr(), self.layout()) }
+    }
+}
+
+struct StaticSprite {
+    sprite: SpriteVram,
+    last_used: u32,
+}
+
+impl StaticSprite {
+    fn is_unused(&self) -> bool {
+        Rc::strong_count(&self.sprite.0) == 1
     }
 }
 
 #[derive(Default)]
 struct SpriteControllerInner {
-    static_palette_map: HashMap<PaletteId, Weak<PaletteData>>,
-    static_sprite_map: HashMap<SpriteId, Weak<SpriteArena>>,
+    /// Palettes in vram by their colours, so that sprites with identical
+    /// palettes share the same palette bank
+    static_palette_map: HashMap<[u16; 16], Weak<PaletteData>>,
+    static_sprite_map: HashMap<SpriteId, StaticSprite>,
+    cache_size: usize,
+    use_counter: u32,
+    stats: SpriteVramStats,
+}
+
+/// Statistics about how sprites from [include_aseprite] are being kept in
+/// vram, obtained from [ObjectController::sprite_vram_stats]. Useful for
+/// tuning the size of the cache set with
+/// [ObjectController::set_sprite_cache_size].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SpriteVramStats {
+    /// The number of sprites currently in vram, including those in the cache.
+    pub sprites_in_vram: usize,
+    /// The number of sprites in vram which are not used by anything and are
+    /// only being kept around by the cache.
+    pub sprites_cached: usize,
+    /// The number of 4bpp tiles used by sprites in vram.
+    pub tiles_in_vram: usize,
+    /// The number of the 16 palette banks in use by sprites, including
+    /// palettes for dynamic sprites from [ObjectController::palette].
+    pub palettes_in_vram: usize,
+    /// The number of times a sprite had to be copied into vram.
+    pub uploads: u32,
+    /// The number of times a sprite was requested and was already in vram.
+    pub hits: u32,
+    /// The number of unused sprites which were removed from vram, either
+    /// because the cache was full or to make room for another sprite.
+    pub evictions: u32,
 }
 
 struct Loan<'a> {
@@ -649,12 +1318,15 @@ impl Drop for Loan<'_> {
     fn drop(&mut self) {
         let mut s = unsafe { self.controller.borrow_mut() };
 
-        unsafe {
+        let object_inner = unsafe {
             s.shadow_oam[self.index as usize]
                 .as_mut()
                 .unwrap_unchecked()
-                .destroy = true;
         };
+        object_inner.destroy = true;
+        // the object is hidden on the next commit, so its affine matrix can be
+        // given to something else straight away
+        object_inner.affine_matrix = None;
     }
 }
 
@@ -662,15 +1334,25 @@ struct ObjectInner {
     attrs: Attributes,
     sprite: SpriteBorrow,
     previous_sprite: SpriteBorrow,
+    affine_matrix: Option<ObjectAffineMatrix>,
+    double_size: bool,
+    position: Vector2D<i32>,
     destroy: bool,
     z: i32,
 }
 
 struct ObjectControllerStatic {
-    _free_affine_matrices: Vec<u8>,
+    affine_matrices: Vec<Weak<AffineMatrixData>>,
     free_object: Vec<u8>,
+    /// Objects set aside for a [Multiplexer], which uses the last entries of
+    /// object attribute memory
+    multiplexer_objects: Vec<u8>,
     shadow_oam: Vec<Option<ObjectInner>>,
+    oam_buffer: Vec<u16>,
+    oam_committed: Vec<u16>,
+    oam_committed_valid: bool,
     z_order: Vec<u8>,
+    sorting: ObjectSorting,
     sprite_controller: SpriteControllerInner,
 }
 
@@ -678,17 +1360,86 @@ impl ObjectControllerStatic {
     unsafe fn new() -> Self {
         Self {
             shadow_oam: (0..128).map(|_| None).collect(),
+            oam_buffer: alloc::vec![0; 128 * 4],
+            oam_committed: alloc::vec![0; 128 * 4],
+            oam_committed_valid: false,
             z_order: (0..128).collect(),
+            sorting: ObjectSorting::Z,
             free_object: (0..128).collect(),
-            _free_affine_matrices: (0..32).collect(),
+            multiplexer_objects: Vec::new(),
+            affine_matrices: (0..32).map(|_| Weak::new()).collect(),
             sprite_controller: SpriteControllerInner::new(),
         }
     }
 
     fn update_z_ordering(&mut self) {
         let shadow_oam = &self.shadow_oam;
-        self.z_order
-            .sort_by_key(|&a| shadow_oam[a as usize].as_ref().map_or(i32::MAX, |s| s.z));
+        let sorting = self.sorting;
+
+        self.z_order.sort_by(|&a, &b| {
+            match (&shadow_oam[a as usize], &shadow_oam[b as usize]) {
+                (Some(a), Some(b)) => {
+                    let (a, b) = (a.sort_key(), b.sort_key());
+                    // Objects are drawn in order of their priority before
+                    // their index in OAM is considered. Keeping OAM in
+                    // priority order avoids a hardware quirk where an object
+                    // with a lower index but behind a background would hide
+                    // another object in front of the background.
+                    (a.priority as u8)
+                        .cmp(&(b.priority as u8))
+                        .then_with(|| sorting.compare(&a, &b))
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        });
+    }
+}
+
+/// The information about an object used when deciding which objects are
+/// drawn in front of others, passed to custom comparisons in
+/// [ObjectSorting::Custom].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ObjectSortKey {
+    /// The z position set with [Object::set_z].
+    pub z: i32,
+    /// The x position of the left of the object on screen.
+    pub x: i32,
+    /// The y position of the top of the object on screen.
+    pub y: i32,
+    /// The y position of the bottom of the object on screen.
+    pub bottom: i32,
+    /// The priority of the object relative to the backgrounds.
+    pub priority: Priority,
+}
+
+/// Controls the order objects are drawn in, set using
+/// [ObjectController::set_sorting]. Objects are always grouped by their
+/// [Priority] first, since the hardware draws objects with different
+/// priorities in priority order regardless of the order they are in.
+#[derive(Clone, Copy)]
+pub enum ObjectSorting {
+    /// Objects with a lower z, set with [Object::set_z], are drawn in front
+    /// of objects with a higher z. This is the default.
+    Z,
+    /// Objects which are lower on the screen are drawn in front of objects
+    /// which are higher up, as is commonly needed for top down games. Objects
+    /// with the same bottom are ordered by their z.
+    Y,
+    /// Objects are ordered using the given comparison, where objects which
+    /// compare as [Ordering::Less] are drawn in front.
+    Custom(fn(&ObjectSortKey, &ObjectSortKey) -> Ordering),
+}
+
+impl ObjectSorting {
+    fn compare(self, a: &ObjectSortKey, b: &ObjectSortKey) -> Ordering {
+        match self {
+            ObjectSorting::Z => a.z.cmp(&b.z),
+            ObjectSorting::Y => b.bottom.cmp(&a.bottom).then(a.z.cmp(&b.z)),
+            ObjectSorting::Custom(compare) => compare(a, b),
+        }
     }
 }
 
@@ -708,40 +1459,125 @@ impl Drop for ObjectController {
 
 const HIDDEN_VALUE: u16 = 0b10 << 8;
 
+/// Runs of changed words shorter than this are written individually, since
+/// setting up a DMA transfer costs more than a few writes.
+const OAM_DMA_THRESHOLD: usize = 8;
+
+/// Copies the words to object attribute memory starting at the given word.
+fn copy_to_oam(words: &[u16], start: usize) {
+    let oam = unsafe { (OBJECT_ATTRIBUTE_MEMORY as *mut u16).add(start) };
+
+    if words.len() < OAM_DMA_THRESHOLD {
+        for (i, &word) in words.iter().enumerate() {
+            unsafe { oam.add(i).write_volatile(word) };
+        }
+    } else {
+        unsafe { dma::copy_to(words.as_ptr(), oam, words.len()) };
+    }
+}
+
+impl ObjectControllerStatic {
+    /// Copies the words of the buffer which differ from what was last
+    /// committed to object attribute memory, batching each contiguous run of
+    /// changed words into a single copy.
+    fn copy_changed_to_oam(&mut self, mut copy: impl FnMut(&[u16], usize)) {
+        if !self.oam_committed_valid {
+            copy(&self.oam_buffer, 0);
+            self.oam_committed.copy_from_slice(&self.oam_buffer);
+            self.oam_committed_valid = true;
+            return;
+        }
+
+        let len = self.oam_buffer.len();
+        let mut i = 0;
+
+        while i < len {
+            if self.oam_buffer[i] == self.oam_committed[i] {
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < len && self.oam_buffer[i] != self.oam_committed[i] {
+                i += 1;
+            }
+
+            copy(&self.oam_buffer[start..i], start);
+            self.oam_committed[start..i].copy_from_slice(&self.oam_buffer[start..i]);
+        }
+    }
+}
+
 impl ObjectController {
     /// Commits the objects to vram and delete sprites where possible. This
     /// should be called shortly after having waited for the next vblank to
     /// ensure what is displayed on screen doesn't change part way through.
+    ///
+    /// Objects are first written to a copy of object attribute memory which
+    /// is compared against what was last committed. Only the attributes which
+    /// have changed are copied to vram, with contiguous runs of changes
+    /// copied together.
     pub fn commit(&self) {
+        self.commit_with(copy_to_oam);
+    }
+
+    /// Does the same as [commit](ObjectController::commit), except that the
+    /// changed attributes are added to the queue rather than copied straight
+    /// away. This should be called before waiting for vblank, with the queue
+    /// flushed once vblank starts.
+    pub fn commit_queued(&self, queue: &mut CommitQueue) {
+        self.commit_with(|words, start| unsafe {
+            queue.copy(words, (OBJECT_ATTRIBUTE_MEMORY as *mut u16).add(start));
+        });
+    }
+
+    fn commit_with(&self, copy: impl FnMut(&[u16], usize)) {
         let mut s = unsafe { self.inner.borrow_mut() };
 
         let s = &mut *s;
 
-        for (i, &z) in s.z_order.iter().enumerate() {
+        s.update_z_ordering();
+
+        // unused objects are sorted last, so the entries at the end are free
+        // for the multiplexer
+        let entries = 128 - s.multiplexer_objects.len();
+
+        for (i, &z) in s.z_order.iter().enumerate().take(entries) {
+            let oam_entry = &mut s.oam_buffer[i * 4..i * 4 + 3];
+
             if let Some(o) = &mut s.shadow_oam[z as usize] {
                 if o.destroy {
                     s.free_object.push(z);
 
-                    unsafe {
-                        (OBJECT_ATTRIBUTE_MEMORY as *mut u16)
-                            .add(i * 4)
-                            .write_volatile(HIDDEN_VALUE);
-                    }
+                    oam_entry[0] = HIDDEN_VALUE;
 
                     let _ = unsafe { s.shadow_oam[z as usize].take().unwrap_unchecked() };
                 } else {
-                    o.attrs.commit(i);
+                    // objects which are entirely off screen are hidden, rather
+                    // than letting their position wrap around onto the screen
+                    if o.is_off_screen() {
+                        oam_entry[0] = HIDDEN_VALUE;
+                    } else {
+                        o.attrs.write(oam_entry);
+                    }
                     o.previous_sprite = o.sprite.clone();
                 }
             } else {
-                unsafe {
-                    (OBJECT_ATTRIBUTE_MEMORY as *mut u16)
-                        .add(i * 4)
-                        .write_volatile(HIDDEN_VALUE);
+                oam_entry[0] = HIDDEN_VALUE;
+            }
+        }
+
+        for (i, matrix) in s.affine_matrices.iter().enumerate() {
+            if let Some(matrix) = matrix.upgrade() {
+                let components = matrix.matrix.get().components();
+                for (j, &component) in components.iter().enumerate() {
+                    s.oam_buffer[i * 16 + j * 4 + 3] = component;
                 }
             }
         }
 
+        s.copy_changed_to_oam(copy);
+
         s.sprite_controller.gc();
     }
 
@@ -866,15 +1702,7 @@ impl ObjectController {
         let mut s = unsafe { self.inner.borrow_mut() };
 
         let mut attrs = Attributes::new();
-
-        attrs.a2.set_tile_index(sprite.sprite.0.location.0 as u16);
-        let shape_size = sprite.sprite.0.size.shape_size();
-        attrs
-            .a2
-            .set_palette_bank((sprite.sprite.0.palette.0.location.0) as u8);
-        attrs.a0.set_shape(shape_size.0);
-        attrs.a1a.set_size(shape_size.1);
-        attrs.a1s.set_size(shape_size.1);
+        attrs.set_sprite(&sprite.sprite.0);
 
         let index = s.free_object.pop()?;
 
@@ -882,6 +1710,9 @@ impl ObjectController {
             attrs,
             z: 0,
             previous_sprite: sprite.clone(),
+            affine_matrix: None,
+            double_size: false,
+            position: (0, 0).into(),
             destroy: false,
             sprite,
         });
@@ -891,11 +1722,39 @@ impl ObjectController {
             controller: self.inner,
         };
 
-        s.update_z_ordering();
-
         Some(Object { loan })
     }
 
+    /// Creates an affine matrix which can be used by affine objects, panics
+    /// if all 32 of the affine matrices are in use. The matrix can be shared
+    /// between many objects by cloning it, and its slot is freed once it is no
+    /// longer used by any object.
+    #[must_use]
+    pub fn affine_matrix(&self, matrix: AffineMatrixObject) -> ObjectAffineMatrix {
+        self.try_get_affine_matrix(matrix)
+            .expect("No affine matrix available")
+    }
+
+    /// Creates an affine matrix which can be used by affine objects. Returns
+    /// [None] if all 32 of the affine matrices are in use.
+    #[must_use]
+    pub fn try_get_affine_matrix(&self, matrix: AffineMatrixObject) -> Option<ObjectAffineMatrix> {
+        let mut s = unsafe { self.inner.borrow_mut() };
+
+        let index = s
+            .affine_matrices
+            .iter()
+            .position(|slot| slot.strong_count() == 0)?;
+
+        let data = Rc::new(AffineMatrixData {
+            index: index as u8,
+            matrix: Cell::new(matrix),
+        });
+        s.affine_matrices[index] = Rc::downgrade(&data);
+
+        Some(ObjectAffineMatrix(data))
+    }
+
     /// Creates a [SpriteBorrow] from the given sprite, panics if the sprite
     /// could not be allocated. This will reuse an existing copy of the sprite
     /// in vram if possible.
@@ -947,6 +1806,125 @@ impl ObjectController {
             .sprite_controller
             .try_get_sprite(sprite)
     }
+
+    /// Sets the order objects are drawn in, which is applied each time
+    /// [ObjectController::commit] is called. See [ObjectSorting] for the
+    /// available orderings.
+    pub fn set_sorting(&self, sorting: ObjectSorting) {
+        unsafe { self.inner.borrow_mut() }.sorting = sorting;
+    }
+
+    /// Gets a palette bank containing the given palette for use with
+    /// [DynamicSprite]s, copying it to vram if an identical palette isn't
+    /// already there. Sprites with identical palettes share the same palette
+    /// bank, which is freed once nothing uses it. Panics if all 16 palette
+    /// banks are in use.
+    #[must_use]
+    pub fn palette(&self, palette: &Palette16) -> PaletteVram {
+        self.try_get_palette(palette)
+            .expect("No palette bank available")
+    }
+
+    /// Gets a palette bank containing the given palette for use with
+    /// [DynamicSprite]s, copying it to vram if an identical palette isn't
+    /// already there. Returns [None] if all 16 palette banks are in use.
+    #[must_use]
+    pub fn try_get_palette(&self, palette: &Palette16) -> Option<PaletteVram> {
+        let mut s = unsafe { self.inner.borrow_mut() };
+        let sprite_controller = &mut s.sprite_controller;
+
+        loop {
+            if let Some(palette_vram) = sprite_controller.palette(palette) {
+                return Some(palette_vram);
+            }
+
+            if !sprite_controller.evict_least_recently_used() {
+                return None;
+            }
+        }
+    }
+
+    /// Sets the palette used by all 256 colour sprites, such as those from
+    /// [include_aseprite_256]. This shares palette memory with the 16 colour
+    /// palettes used by other sprites, so 16 colour sprites shouldn't be
+    /// displayed at the same time unless their colours have been accounted for.
+    ///
+    /// # Panics
+    /// Panics if the palette doesn't have exactly 256 colours.
+    pub fn set_sprite_palette_256(&self, palette: &[u16]) {
+        assert_eq!(palette.len(), 256, "a 256 colour palette needs 256 colours");
+
+        unsafe {
+            dma::copy_to(palette.as_ptr(), PALETTE_SPRITE as *mut u16, palette.len());
+        }
+    }
+
+    /// Sets how many sprites which are no longer used by any object are kept
+    /// in vram, so that they don't need to be copied again if they are used
+    /// soon after. The least recently used sprites are removed first, either
+    /// when there are more than this many unused sprites during
+    /// [ObjectController::commit] or when room is needed for another sprite.
+    /// Defaults to 0, removing sprites as soon as they are no longer used.
+    ///
+    /// Note that cached sprites are only removed to make room for sprites from
+    /// [include_aseprite], so a large cache can cause [DynamicSprite::try_vram]
+    /// to fail.
+    pub fn set_sprite_cache_size(&self, unused_sprites: usize) {
+        unsafe { self.inner.borrow_mut() }
+            .sprite_controller
+            .cache_size = unused_sprites;
+    }
+
+    /// Sets aside the given number of hardware objects to be reused by a
+    /// [Multiplexer] to display more sprites than there are hardware objects.
+    /// The objects are returned once the multiplexer is dropped. Panics if
+    /// there aren't enough free objects or if a multiplexer already exists.
+    #[must_use]
+    pub fn multiplexer(&self, hardware_objects: usize) -> Multiplexer<'_> {
+        let first_slot = {
+            let mut s = unsafe { self.inner.borrow_mut() };
+            let s = &mut *s;
+
+            assert!(
+                s.multiplexer_objects.is_empty(),
+                "Only one multiplexer can exist at a time"
+            );
+            assert!(
+                hardware_objects <= s.free_object.len(),
+                "Not enough free objects for the multiplexer"
+            );
+
+            let remaining = s.free_object.len() - hardware_objects;
+            s.multiplexer_objects = s.free_object.split_off(remaining);
+
+            let first_slot = 128 - hardware_objects;
+            for slot in first_slot..128 {
+                s.oam_buffer[slot * 4] = HIDDEN_VALUE;
+            }
+            // the multiplexer writes these objects to object attribute memory
+            // directly, so what was last committed is no longer known
+            s.oam_committed_valid = false;
+
+            first_slot
+        };
+
+        Multiplexer::new(self, first_slot, hardware_objects)
+    }
+
+    fn release_multiplexer_objects(&self) {
+        let mut s = unsafe { self.inner.borrow_mut() };
+        let s = &mut *s;
+
+        s.free_object.append(&mut s.multiplexer_objects);
+        s.oam_committed_valid = false;
+    }
+
+    /// Statistics about sprites from [include_aseprite] currently in vram and
+    /// how often they have needed to be copied there.
+    #[must_use]
+    pub fn sprite_vram_stats(&self) -> SpriteVramStats {
+        unsafe { self.inner.borrow_mut() }.sprite_controller.stats()
+    }
 }
 
 impl<'a> Object<'a> {
@@ -972,28 +1950,105 @@ impl<'a> Object<'a> {
     /// etc. No change will be seen until [ObjectController::commit] is called.
     pub fn set_sprite(&'_ mut self, sprite: SpriteBorrow) {
         let mut object_inner = unsafe { self.object_inner() };
-        object_inner
-            .attrs
-            .a2
-            .set_tile_index(sprite.sprite.0.location.0 as u16);
-        let shape_size = sprite.sprite.0.size.shape_size();
-        object_inner
-            .attrs
-            .a2
-            .set_palette_bank(sprite.sprite.0.palette.0.location.0 as u8);
-        object_inner.attrs.a0.set_shape(shape_size.0);
-        object_inner.attrs.a1a.set_size(shape_size.1);
-        object_inner.attrs.a1s.set_size(shape_size.1);
-
+        object_inner.attrs.set_sprite(&sprite.sprite.0);
         object_inner.sprite = sprite;
     }
 
+    /// Swaps out the current sprite for one from [include_aseprite], copying it
+    /// to vram if it isn't already there. This sets the tile index, shape,
+    /// size and palette of the object in one go using what is stored with the
+    /// sprite. No change will be seen until [ObjectController::commit] is
+    /// called.
+    ///
+    /// # Panics
+    /// Panics if there is no room in vram for the sprite.
+    pub fn set_static_sprite(&mut self, sprite: &'static Sprite) -> &mut Self {
+        let sprite = unsafe { self.loan.controller.borrow_mut() }
+            .sprite_controller
+            .try_get_sprite(sprite)
+            .expect("No slot for sprite available");
+
+        self.set_sprite(sprite);
+        self
+    }
+
+    /// The hit box of the object's sprite on screen, taking into account the
+    /// position of the object and whether it is flipped. See
+    /// [Sprite::hit_box]. This doesn't take into account any rotation or
+    /// scaling of affine objects.
+    #[must_use]
+    pub fn hit_box(&self) -> Rect<i32> {
+        let object_inner = unsafe { self.object_inner() };
+        let sprite = &object_inner.sprite.sprite.0;
+        let (width, height) = sprite.size.to_width_height();
+
+        let hit_box = crate::hit_box::flip(
+            sprite.hit_box.to_rect(),
+            (width as i32, height as i32).into(),
+            object_inner.attrs.a1s.horizontal_flip(),
+            object_inner.attrs.a1s.vertical_flip(),
+        );
+
+        Rect::new(hit_box.position + object_inner.position, hit_box.size)
+    }
+
+    /// Whether the hit boxes of the two objects overlap. See
+    /// [Object::hit_box].
+    #[must_use]
+    pub fn collides_with(&self, other: &Object) -> bool {
+        crate::hit_box::overlaps(self.hit_box(), other.hit_box())
+    }
+
     /// Shows the sprite. No change will be seen until
     /// [ObjectController::commit] is called.
     pub fn show(&mut self) -> &mut Self {
         {
             let mut object_inner = unsafe { self.object_inner() };
-            object_inner.attrs.a0.set_object_mode(ObjectMode::Normal);
+            let mode = object_inner.visible_mode();
+            object_inner.attrs.a0.set_object_mode(mode);
+        }
+
+        self
+    }
+
+    /// Makes this an affine object using the given matrix, which allows it to
+    /// be rotated and scaled. The same matrix can be used by many objects. No
+    /// change will be seen until [ObjectController::commit] is called.
+    pub fn set_affine_matrix(&mut self, matrix: ObjectAffineMatrix) -> &mut Self {
+        {
+            let mut object_inner = unsafe { self.object_inner() };
+            object_inner.attrs.a1a.set_affine_index(matrix.0.index);
+            object_inner.affine_matrix = Some(matrix);
+            object_inner.update_visible_mode();
+        }
+
+        self
+    }
+
+    /// Stops this object from being an affine object, freeing its use of the
+    /// affine matrix. No change will be seen until [ObjectController::commit]
+    /// is called.
+    pub fn clear_affine_matrix(&mut self) -> &mut Self {
+        {
+            let mut object_inner = unsafe { self.object_inner() };
+            object_inner.affine_matrix = None;
+            object_inner.update_visible_mode();
+        }
+
+        self
+    }
+
+    /// Controls whether an affine object is rendered in an area double its
+    /// size, which stops the corners of the sprite being clipped when it is
+    /// rotated or scaled up. Note that this also moves the sprite down and to
+    /// the right by half of its size. Has no effect on objects without an
+    /// affine matrix. No change will be seen until [ObjectController::commit]
+    /// is called.
+    pub fn set_double_size(&mut self, double_size: bool) -> &mut Self {
+        {
+            let mut object_inner = unsafe { self.object_inner() };
+            object_inner.double_size = double_size;
+            object_inner.update_visible_mode();
         }
 
         self
@@ -1021,6 +2076,29 @@ impl<'a> Object<'a> {
         self
     }
 
+    /// Controls whether the object is affected by the object mosaic effect,
+    /// which pixelates the object by the amount set with
+    /// [Mosaic::set_object_size](super::mosaic::Mosaic::set_object_size). No
+    /// change will be seen until [ObjectController::commit] is called.
+    pub fn set_mosaic(&mut self, mosaic: bool) -> &mut Self {
+        {
+            let mut object_inner = unsafe { self.object_inner() };
+            object_inner.attrs.a0.set_mosaic(mosaic);
+        }
+        self
+    }
+
+    /// Sets whether the object is drawn normally, semi-transparently or makes
+    /// up the object window. See [GraphicsMode] for details. No change will be
+    /// seen until [ObjectController::commit] is called.
+    pub fn set_graphics_mode(&mut self, mode: GraphicsMode) -> &mut Self {
+        {
+            let mut object_inner = unsafe { self.object_inner() };
+            object_inner.attrs.a0.set_graphics_mode(mode);
+        }
+        self
+    }
+
     /// Sets the x position of the object. The coordinate refers to the top-left
     /// corner of the sprite. No change will be seen until
     /// [ObjectController::commit] is called.
@@ -1029,6 +2107,10 @@ impl<'a> Object<'a> {
             let mut object_inner = unsafe { self.object_inner() };
             object_inner.attrs.a1a.set_x(x.rem_euclid(1 << 9));
             object_inner.attrs.a1s.set_x(x.rem_euclid(1 << 9));
+            // positions wrap around the screen, so large values are treated
+            // as being off the left
+            let x = x.rem_euclid(1 << 9) as i32;
+            object_inner.position.x = if x >= 512 - 128 { x - 512 } else { x };
         }
         self
     }
@@ -1061,6 +2143,10 @@ impl<'a> Object<'a> {
         {
             let mut object_inner = unsafe { self.object_inner() };
             object_inner.attrs.a0.set_y(y as u8);
+            // positions wrap around the screen, so large values are treated
+            // as being off the top
+            let y = (y as u8) as i32;
+            object_inner.position.y = if y >= 256 - 64 { y - 256 } else { y };
         }
 
         self
@@ -1074,7 +2160,6 @@ impl<'a> Object<'a> {
             let mut object_inner = unsafe { self.object_inner() };
             object_inner.z = z;
         }
-        unsafe { self.loan.controller.borrow_mut().update_z_ordering() };
 
         self
     }
@@ -1085,6 +2170,7 @@ impl<'a> Object<'a> {
     pub fn set_position(&mut self, position: Vector2D<i32>) -> &mut Self {
         {
             let mut object_inner = unsafe { self.object_inner() };
+            object_inner.position = position;
             object_inner.attrs.a0.set_y(position.y as u8);
             object_inner
                 .attrs
@@ -1099,20 +2185,99 @@ impl<'a> Object<'a> {
     }
 }
 
+impl ObjectInner {
+    /// The size the object takes up on screen, which is double the size of
+    /// the sprite for double size affine objects.
+    fn screen_size(&self) -> Vector2D<i32> {
+        let (width, height) = self.sprite.sprite.0.size.to_width_height();
+        let scale = if matches!(self.attrs.a0.object_mode(), ObjectMode::AffineDouble) {
+            2
+        } else {
+            1
+        };
+
+        (width as i32 * scale, height as i32 * scale).into()
+    }
+
+    fn is_off_screen(&self) -> bool {
+        let size = self.screen_size();
+
+        self.position.x + size.x <= 0
+            || self.position.y + size.y <= 0
+            || self.position.x >= WIDTH
+            || self.position.y >= HEIGHT
+    }
+
+    fn sort_key(&self) -> ObjectSortKey {
+        ObjectSortKey {
+            z: self.z,
+            x: self.position.x,
+            y: self.position.y,
+            bottom: self.position.y + self.screen_size().y,
+            priority: self.attrs.a2.priority(),
+        }
+    }
+
+    fn visible_mode(&self) -> ObjectMode {
+        match (&self.affine_matrix, self.double_size) {
+            (None, _) => ObjectMode::Normal,
+            (Some(_), false) => ObjectMode::Affine,
+            (Some(_), true) => ObjectMode::AffineDouble,
+        }
+    }
+
+    fn update_visible_mode(&mut self) {
+        if !matches!(self.attrs.a0.object_mode(), ObjectMode::Disabled) {
+            let mode = self.visible_mode();
+            self.attrs.a0.set_object_mode(mode);
+        }
+    }
+}
+
+struct AffineMatrixData {
+    index: u8,
+    matrix: Cell<AffineMatrixObject>,
+}
+
+/// One of the 32 affine matrices available to objects, obtained from
+/// [ObjectController::affine_matrix]. This is reference counted, so cloning it
+/// lets many objects share the same matrix and changing the matrix affects
+/// every object using it. The slot is freed once no references remain.
+#[derive(Clone)]
+pub struct ObjectAffineMatrix(Rc<AffineMatrixData>);
+
+impl ObjectAffineMatrix {
+    /// Sets the matrix used by every object sharing this affine matrix. No
+    /// change will be seen until [ObjectController::commit] is called.
+    pub fn set_matrix(&self, matrix: AffineMatrixObject) {
+        self.0.matrix.set(matrix);
+    }
+
+    /// Sets the matrix to scale objects by `scale`, so a scale of (2, 2)
+    /// doubles the size of the object, and then rotate them by `angle`,
+    /// measured in revolutions. Scales which can't be represented are wrapped.
+    /// No change will be seen until [ObjectController::commit] is called.
+    pub fn set_transform(&self, angle: Num<i32, 8>, scale: Vector2D<Num<i32, 8>>) {
+        let one: Num<i32, 8> = 1.into();
+        let matrix = AffineMatrix::from_scale((one / scale.x, one / scale.y).into())
+            * AffineMatrix::from_rotation(angle);
+
+        self.set_matrix(matrix.to_object_wrapping());
+    }
+
+    /// The matrix currently used by objects sharing this affine matrix.
+    #[must_use]
+    pub fn matrix(&self) -> AffineMatrixObject {
+        self.0.matrix.get()
+    }
+}
+
 /// The Sprite Id is a thin wrapper around the pointer to the sprite in
 /// rom and is therefore a unique identifier to a sprite
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct SpriteId(usize);
 
-/// The palette id is a thin wrapper around the pointer to the palette in rom
-/// and is therefore a unique reference to a palette
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
-struct PaletteId(usize);
-
 impl Palette16 {
-    fn id(&'static self) -> PaletteId {
-        PaletteId(self as *const _ as usize)
-    }
     const fn layout() -> Layout {
         Layout::new::<Self>()
     }
@@ -1123,7 +2288,10 @@ impl Sprite {
         SpriteId(self as *const _ as usize)
     }
     fn layout(&self) -> Layout {
-        Layout::from_size_align(self.size.number_of_tiles() * BYTES_PER_TILE_4BPP, 8).unwrap()
+        match self.palette {
+            Some(_) => self.size.layout(),
+            None => self.size.layout_256(),
+        }
     }
     #[doc(hidden)]
     /// Creates a sprite from it's constituent data, used internally by
@@ -1134,72 +2302,223 @@ impl Sprite {
     #[must_use]
     pub const unsafe fn new(palette: &'static Palette16, data: &'static [u8], size: Size) -> Self {
         Self {
-            palette,
+            palette: Some(palette),
+            data,
+            size,
+            duration: DEFAULT_FRAME_DURATION_MS,
+            hit_box: HitBox::full(size),
+            collision_mask: None,
+        }
+    }
+    #[doc(hidden)]
+    /// Creates a 256 colour sprite from it's constituent data, used internally
+    /// by [include_aseprite_256] and should generally not be used outside it.
+    ///
+    /// # Safety
+    /// The data should be aligned to a 2 byte boundary
+    #[must_use]
+    pub const unsafe fn new_256(data: &'static [u8], size: Size) -> Self {
+        Self {
+            palette: None,
             data,
             size,
+            duration: DEFAULT_FRAME_DURATION_MS,
+            hit_box: HitBox::full(size),
+            collision_mask: None,
         }
     }
     #[must_use]
+    /// Whether this is a 256 colour sprite, which uses the palette set with
+    /// [ObjectController::set_sprite_palette_256].
+    pub const fn is_256_colour(&self) -> bool {
+        self.palette.is_none()
+    }
+    #[doc(hidden)]
+    /// Sets how long this sprite is shown for when animating, used internally
+    /// by [include_aseprite].
+    #[must_use]
+    pub const fn with_duration(mut self, duration_ms: u16) -> Self {
+        self.duration = duration_ms;
+        self
+    }
+    #[doc(hidden)]
+    /// Sets the area of the sprite containing non transparent pixels, used
+    /// internally by [include_aseprite].
+    #[must_use]
+    pub const fn with_hit_box(mut self, x: u8, y: u8, width: u8, height: u8) -> Self {
+        self.hit_box = HitBox {
+            x,
+            y,
+            width,
+            height,
+        };
+        self
+    }
+    #[must_use]
+    /// The smallest rectangle containing all the non transparent pixels of the
+    /// sprite, relative to the top left of the sprite. This is empty for
+    /// sprites with no visible pixels.
+    pub fn hit_box(&self) -> Rect<i32> {
+        self.hit_box.to_rect()
+    }
+    #[doc(hidden)]
+    /// Sets the pixel mask used for collisions, used internally by
+    /// [include_aseprite_with_masks].
+    #[must_use]
+    pub const fn with_collision_mask(mut self, mask: &'static CollisionMask) -> Self {
+        self.collision_mask = Some(mask);
+        self
+    }
+    #[must_use]
+    /// The mask of non transparent pixels for use with
+    /// [mask_collision](crate::hit_box::mask_collision). Only sprites included
+    /// with [include_aseprite_with_masks] have a mask.
+    pub const fn collision_mask(&self) -> Option<&'static CollisionMask> {
+        self.collision_mask
+    }
+    #[must_use]
     /// The size of the sprite in it's form that is displayable on the GBA.
     pub const fn size(&self) -> Size {
         self.size
     }
+    #[must_use]
+    /// How long this frame of the animation should be shown for, in
+    /// milliseconds, as set in aseprite.
+    pub const fn duration_ms(&self) -> u16 {
+        self.duration
+    }
 }
 
 impl SpriteControllerInner {
     fn try_get_sprite(&mut self, sprite: &'static Sprite) -> Option<SpriteBorrow> {
         let id = sprite.id();
+        self.use_counter = self.use_counter.wrapping_add(1);
+
         if let Some(storage) = self.static_sprite_map.get_mut(&id) {
-            if let Some(strong) = storage.upgrade() {
-                return Some(SpriteBorrow {
-                    sprite: SpriteVram(strong),
-                });
-            }
+            storage.last_used = self.use_counter;
+            self.stats.hits = self.stats.hits.wrapping_add(1);
+
+            return Some(SpriteBorrow {
+                sprite: storage.sprite.clone(),
+            });
         }
 
-        // layout is non zero sized, so this is safe to call
+        // if vram is full, make room by removing the least recently used
+        // sprites which aren't being used by anything
+        let sprite_vram = loop {
+            if let Some(sprite_vram) = self.upload_sprite(sprite) {
+                break sprite_vram;
+            }
 
-        let palette_location = self.palette(sprite.palette);
-        let palette_location = match palette_location {
-            Some(a) => a,
-            None => {
+            if !self.evict_least_recently_used() {
                 return None;
             }
         };
 
-        let dest = unsafe { SPRITE_ALLOCATOR.alloc(sprite.layout())? };
+        self.stats.uploads = self.stats.uploads.wrapping_add(1);
+        self.static_sprite_map.insert(
+            id,
+            StaticSprite {
+                sprite: sprite_vram.clone(),
+                last_used: self.use_counter,
+            },
+        );
 
-        unsafe {
-            dma::dma_copy16(
-                sprite.data.as_ptr().cast(),
-                dest.as_ptr().cast(),
-                sprite.data.len() / 2,
-            );
-        }
+        Some(SpriteBorrow {
+            sprite: sprite_vram,
+        })
+    }
 
-        let sprite = SpriteVram(Rc::new(SpriteArena {
+    fn upload_sprite(&mut self, sprite: &'static Sprite) -> Option<SpriteVram> {
+        let palette_location = match sprite.palette {
+            Some(palette) => Some(self.palette(palette)?),
+            None => None,
+        };
+
+        // layout is non zero sized, so this is safe to call
+        let dest = unsafe { SPRITE_ALLOCATOR.alloc(sprite.layout())? };
+
+        let arena = SpriteArena {
             location: Location::from_sprite_ptr(dest),
             size: sprite.size(),
+            hit_box: sprite.hit_box,
             palette: palette_location,
-        }));
+        };
+
+        let tile_ptr =
+            (arena.tile_index() as usize * BYTES_PER_TILE_4BPP + TILE_SPRITE) as *mut u16;
 
-        self.static_sprite_map.insert(id, Rc::downgrade(&sprite.0));
+        unsafe {
+            dma::copy_to(sprite.data.as_ptr().cast(), tile_ptr, sprite.data.len() / 2);
+        }
 
-        Some(SpriteBorrow { sprite })
+        Some(SpriteVram(Rc::new(arena)))
+    }
+
+    /// Removes the least recently used sprite which is no longer used by
+    /// anything from vram. Returns whether a sprite was removed.
+    fn evict_least_recently_used(&mut self) -> bool {
+        let use_counter = self.use_counter;
+        let least_recently_used = self
+            .static_sprite_map
+            .iter()
+            .filter(|(_, v)| v.is_unused())
+            .max_by_key(|(_, v)| use_counter.wrapping_sub(v.last_used))
+            .map(|(&id, _)| id);
+
+        match least_recently_used {
+            Some(id) => {
+                self.static_sprite_map.remove(&id);
+                self.stats.evictions = self.stats.evictions.wrapping_add(1);
+                true
+            }
+            None => false,
+        }
     }
 
-    /// Cleans up weak references to sprites and palettes no longer in vram
+    /// Cleans up sprites no longer in use beyond the size of the cache and
+    /// weak references to palettes no longer in vram
     fn gc(&mut self) {
+        let unused = self
+            .static_sprite_map
+            .values()
+            .filter(|v| v.is_unused())
+            .count();
+
+        for _ in self.cache_size..unused {
+            self.evict_least_recently_used();
+        }
+
         self.static_palette_map.retain(|_, v| v.strong_count() != 0);
-        self.static_sprite_map.retain(|_, v| v.strong_count() != 0);
+    }
+
+    fn stats(&self) -> SpriteVramStats {
+        SpriteVramStats {
+            sprites_in_vram: self.static_sprite_map.len(),
+            sprites_cached: self
+                .static_sprite_map
+                .values()
+                .filter(|v| v.is_unused())
+                .count(),
+            tiles_in_vram: self
+                .static_sprite_map
+                .values()
+                .map(|v| v.sprite.0.layout().size() / BYTES_PER_TILE_4BPP)
+                .sum(),
+            palettes_in_vram: self
+                .static_palette_map
+                .values()
+                .filter(|v| v.strong_count() != 0)
+                .count(),
+            ..self.stats
+        }
     }
 
     fn new() -> Self {
         Default::default()
     }
-    fn palette(&mut self, palette: &'static Palette16) -> Option<PaletteVram> {
-        let id = palette.id();
-        if let Some(storage) = self.static_palette_map.get(&id) {
+    fn palette(&mut self, palette: &Palette16) -> Option<PaletteVram> {
+        if let Some(storage) = self.static_palette_map.get(&palette.colours) {
             if let Some(up) = storage.upgrade() {
                 return Some(PaletteVram(up));
             }
@@ -1208,7 +2527,7 @@ impl SpriteControllerInner {
         let palette_vram = PaletteVram::new(palette)?;
 
         self.static_palette_map
-            .insert(id, Rc::downgrade(&palette_vram.0));
+            .insert(palette.colours, Rc::downgrade(&palette_vram.0));
 
         Some(palette_vram)
     }
@@ -1222,11 +2541,19 @@ enum ObjectMode {
     AffineDouble,
 }
 
-#[derive(BitfieldSpecifier, Clone, Copy)]
+/// How an object interacts with the rest of the screen, set with
+/// [Object::set_graphics_mode].
+#[derive(BitfieldSpecifier, Clone, Copy, Debug, PartialEq, Eq)]
 #[bits = 2]
-enum GraphicsMode {
+pub enum GraphicsMode {
+    /// The object is drawn normally, this is the default.
     Normal,
-    AlphaBlending,
+    /// The object is semi-transparent and is always the first target of alpha
+    /// blending, regardless of the blend settings for objects.
+    AlphaBlend,
+    /// The object isn't drawn, instead the non transparent pixels make up the
+    /// shape of the object window. The object window needs to be enabled using
+    /// [Windows::win_obj](crate::display::window::Windows::win_obj).
     Window,
 }
 
@@ -1280,10 +2607,26 @@ mod attributes {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::fixnum::num;
     use core::mem::size_of;
 
+    /// The sprites used by the object tests here and in the submodules.
+    pub(crate) const GRAPHICS: &Graphics = crate::include_aseprite_with_masks!(
+        "../examples/the-purple-night/gfx/objects.aseprite",
+        "../examples/the-purple-night/gfx/boss.aseprite"
+    );
+
+    pub(crate) const BOSS: &Tag = GRAPHICS.tags().get("Boss");
+    pub(crate) const EMU: &Tag = GRAPHICS.tags().get("emu - idle");
+
+    /// The same sprites as [GRAPHICS] using a single 256 colour palette.
+    const GRAPHICS_256: &Graphics =
+        include_aseprite_256!("../examples/the-purple-night/gfx/objects.aseprite");
+
+    const EMU_256: &Tag = GRAPHICS_256.tags().get("emu - idle");
+
     #[test_case]
     fn size_of_ObjectControllerReference(_: &mut crate::Gba) {
         if !cfg!(debug_assertions) {
@@ -1293,14 +2636,6 @@ mod tests {
 
     #[test_case]
     fn object_usage(gba: &mut crate::Gba) {
-        const GRAPHICS: &Graphics = include_aseprite!(
-            "../examples/the-purple-night/gfx/objects.aseprite",
-            "../examples/the-purple-night/gfx/boss.aseprite"
-        );
-
-        const BOSS: &Tag = GRAPHICS.tags().get("Boss");
-        const EMU: &Tag = GRAPHICS.tags().get("emu - idle");
-
         let object = gba.display.object.get();
 
         {
@@ -1326,4 +2661,455 @@ mod tests {
 
         object.commit();
     }
+
+    #[test_case]
+    fn animation_player_follows_frame_durations(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+
+        let mut player = AnimationPlayer::new(EMU);
+        let mut emu = object.object(object.sprite(player.sprite()));
+
+        // emu - idle ping-pongs over three 100ms frames, so it shows sprites
+        // 0, 1, 2, 1. A frame lasts 4286 / 256 ms, so the steps happen after
+        // 6, 12 and 18 updates and the animation wraps after 24.
+        let expected = [
+            (5, 0),
+            (6, 1),
+            (11, 1),
+            (12, 2),
+            (18, 1),
+            (23, 1),
+            (24, 0),
+            (30, 1),
+        ];
+
+        assert!(core::ptr::eq(player.sprite(), EMU.sprite(0)));
+
+        let mut updates = 0;
+        for (after_updates, sprite) in expected {
+            while updates < after_updates {
+                player.update(&mut emu, &object);
+                updates += 1;
+            }
+
+            assert!(core::ptr::eq(player.sprite(), EMU.sprite(sprite)));
+        }
+
+        object.commit();
+    }
+
+    #[test_case]
+    fn unused_sprites_are_cached_until_evicted(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        object.commit();
+        let initial = object.sprite_vram_stats();
+
+        object.set_sprite_cache_size(1);
+
+        {
+            let _first = object.sprite(BOSS.sprite(0));
+            let _second = object.sprite(BOSS.sprite(1));
+        }
+
+        object.commit();
+
+        let stats = object.sprite_vram_stats();
+        assert_eq!(stats.sprites_in_vram, 1);
+        assert_eq!(stats.sprites_cached, 1);
+        assert_eq!(stats.uploads, initial.uploads + 2);
+        assert_eq!(stats.evictions, initial.evictions + 1);
+
+        // the most recently used sprite is the one which was kept
+        let _second = object.sprite(BOSS.sprite(1));
+        assert_eq!(object.sprite_vram_stats().hits, initial.hits + 1);
+
+        object.set_sprite_cache_size(0);
+        object.commit();
+    }
+
+    #[test_case]
+    fn vram_stats_count_the_tiles_of_256_colour_sprites(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        object.commit();
+        let initial = object.sprite_vram_stats();
+
+        let sprite = EMU_256.sprite(0);
+        let _emu = object.sprite(sprite);
+
+        // each 8bpp tile takes up two 4bpp tiles, plus one for alignment
+        let stats = object.sprite_vram_stats();
+        assert_eq!(
+            stats.tiles_in_vram,
+            initial.tiles_in_vram + sprite.size().number_of_tiles() * 2 + 1
+        );
+    }
+
+    #[test_case]
+    fn y_sorting_draws_lower_objects_in_front(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        object.commit();
+        object.set_sorting(ObjectSorting::Y);
+
+        let mut top = object.object(object.sprite(EMU.sprite(0)));
+        let mut bottom = object.object(object.sprite(EMU.sprite(0)));
+        let mut behind_background = object.object(object.sprite(EMU.sprite(0)));
+
+        top.set_position((10, 10).into());
+        bottom.set_position((10, 50).into());
+        behind_background
+            .set_position((10, 100).into())
+            .set_priority(Priority::P1);
+
+        object.commit();
+
+        let order: Vec<_> = {
+            let s = unsafe { object.inner.borrow_mut() };
+            s.z_order[..3].to_vec()
+        };
+
+        assert_eq!(
+            order,
+            [
+                bottom.loan.index,
+                top.loan.index,
+                behind_background.loan.index
+            ]
+        );
+
+        object.set_sorting(ObjectSorting::Z);
+    }
+
+    #[test_case]
+    fn commit_only_copies_to_oam_when_something_changed(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+
+        let mut emu = object.object(object.sprite(EMU.sprite(0)));
+        emu.set_position((10, 10).into()).show();
+        object.commit();
+
+        let oam = OBJECT_ATTRIBUTE_MEMORY as *mut u16;
+        let committed = unsafe { oam.read_volatile() };
+
+        unsafe { oam.write_volatile(0x1234) };
+        object.commit();
+        assert_eq!(unsafe { oam.read_volatile() }, 0x1234);
+
+        emu.set_position((10, 20).into());
+        object.commit();
+        assert_ne!(unsafe { oam.read_volatile() }, 0x1234);
+        assert_ne!(unsafe { oam.read_volatile() }, committed);
+    }
+
+    #[test_case]
+    fn commit_queued_copies_to_oam_once_flushed(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let mut queue = CommitQueue::new();
+
+        let mut emu = object.object(object.sprite(EMU.sprite(0)));
+        emu.set_position((10, 10).into()).show();
+        object.commit();
+
+        let oam = OBJECT_ATTRIBUTE_MEMORY as *mut u16;
+        let attribute_1 = unsafe { oam.add(1).read_volatile() };
+
+        emu.set_x(20);
+        object.commit_queued(&mut queue);
+
+        assert_eq!(queue.len(), 1);
+        assert_eq!(unsafe { oam.add(1).read_volatile() }, attribute_1);
+
+        queue.flush();
+        assert_ne!(unsafe { oam.add(1).read_volatile() }, attribute_1);
+
+        // nothing changed, so nothing more is queued
+        object.commit_queued(&mut queue);
+        assert!(queue.is_empty());
+    }
+
+    #[test_case]
+    fn commit_only_writes_changed_attributes(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+
+        let mut emu = object.object(object.sprite(EMU.sprite(0)));
+        emu.set_position((10, 10).into()).show();
+        object.commit();
+
+        let oam = OBJECT_ATTRIBUTE_MEMORY as *mut u16;
+        let attribute_1 = unsafe { oam.add(1).read_volatile() };
+
+        // attribute 2 holds the sprite, which isn't changed by moving the
+        // object horizontally so shouldn't be written again
+        unsafe { oam.add(2).write_volatile(0x1234) };
+
+        emu.set_x(20);
+        object.commit();
+
+        assert_ne!(unsafe { oam.add(1).read_volatile() }, attribute_1);
+        assert_eq!(unsafe { oam.add(2).read_volatile() }, 0x1234);
+    }
+
+    #[test_case]
+    fn mosaic_and_graphics_mode_are_written_to_attribute_0(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+
+        let mut emu = object.object(object.sprite(EMU.sprite(0)));
+        emu.set_mosaic(true)
+            .set_graphics_mode(GraphicsMode::AlphaBlend);
+
+        let attr0 = unsafe { emu.object_inner() }.attrs.a0;
+        let attr0 = u16::from_ne_bytes(attr0.into_bytes());
+
+        assert_eq!(attr0 & (1 << 12), 1 << 12);
+        assert_eq!((attr0 >> 10) & 0b11, 0b01);
+    }
+
+    #[test_case]
+    fn sprites_can_use_256_colours(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        object.set_sprite_palette_256(GRAPHICS_256.palette_256().unwrap());
+
+        let sprite = EMU_256.sprite(0);
+        assert!(sprite.is_256_colour());
+
+        // allocate a 16 colour sprite first so that the 256 colour sprite
+        // would start on an odd tile if it weren't aligned
+        let _dynamic = DynamicSprite::new(&[0; 32], Size::S8x8)
+            .to_vram(PaletteVram::new(&Palette16::new([0; 16])).unwrap());
+        let mut emu = object.object(object.sprite(sprite));
+        emu.show();
+
+        let (a0, a2) = {
+            let object_inner = unsafe { emu.object_inner() };
+            (object_inner.attrs.a0, object_inner.attrs.a2)
+        };
+        assert_eq!(a2.tile_index() % 2, 0);
+        assert!(matches!(a0.colour_mode(), ColourMode::Eight));
+
+        let tile = (a2.tile_index() as usize * BYTES_PER_TILE_4BPP + TILE_SPRITE) as *const u8;
+        let copied = unsafe { slice::from_raw_parts(tile, sprite.data.len()) };
+        assert_eq!(copied, sprite.data);
+
+        object.commit();
+    }
+
+    #[test_case]
+    fn metasprite_parts_are_mirrored_when_flipped(gba: &mut crate::Gba) {
+        const BOSS: &MetaSpriteGraphics =
+            include_metasprites!("../examples/the-purple-night/gfx/boss.aseprite");
+
+        let object = gba.display.object.get();
+
+        let data = BOSS.frame(0);
+        let mut boss = MetaSprite::new(&object, data);
+        boss.set_position((50, 20).into()).show();
+
+        let positions = |boss: &MetaSprite| -> Vec<(u16, u8)> {
+            boss.objects
+                .iter()
+                .map(|o| {
+                    let object_inner = unsafe { o.object_inner() };
+                    (object_inner.attrs.a1s.x(), object_inner.attrs.a0.y())
+                })
+                .collect()
+        };
+
+        let unflipped = positions(&boss);
+        for ((x, y), part) in unflipped.iter().zip(data.parts()) {
+            assert_eq!(*x as i32, 50 + part.offset().x);
+            assert_eq!(*y as i32, 20 + part.offset().y);
+        }
+
+        boss.set_hflip(true);
+        for ((x, y), part) in positions(&boss).iter().zip(data.parts()) {
+            let (width, _) = part.sprite().size().to_width_height();
+            assert_eq!(
+                *x as i32,
+                50 + data.size().x - part.offset().x - width as i32
+            );
+            assert_eq!(*y as i32, 20 + part.offset().y);
+        }
+
+        object.commit();
+    }
+
+    #[test_case]
+    fn objects_off_screen_are_hidden(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        object.commit();
+
+        let mut emu = object.object(object.sprite(EMU.sprite(0)));
+        emu.show();
+
+        let mut is_hidden_at = |x: i32, y: i32| {
+            emu.set_position((x, y).into());
+            object.commit();
+
+            let oam = OBJECT_ATTRIBUTE_MEMORY as *const u16;
+            unsafe { oam.read_volatile() == HIDDEN_VALUE }
+        };
+
+        let (width, height) = EMU.sprite(0).size().to_width_height();
+        let (width, height) = (width as i32, height as i32);
+
+        assert!(!is_hidden_at(10, 10));
+        assert!(!is_hidden_at(-width + 1, -height + 1));
+        assert!(!is_hidden_at(WIDTH - 1, HEIGHT - 1));
+        assert!(is_hidden_at(-width, 10));
+        assert!(is_hidden_at(10, -height));
+        assert!(is_hidden_at(WIDTH, 10));
+        assert!(is_hidden_at(10, HEIGHT));
+        // this would wrap around to x = 88 on the hardware
+        assert!(is_hidden_at(600, 10));
+    }
+
+    #[test_case]
+    fn objects_can_form_the_object_window(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let mut window = gba.display.window.get();
+
+        let mut emu = object.object(object.sprite(EMU.sprite(0)));
+        emu.set_graphics_mode(GraphicsMode::Window).show();
+
+        window.win_obj().enable().set_object_enable(true);
+        window.commit();
+        object.commit();
+
+        let window_out = unsafe { (0x0400_004A as *const u16).read_volatile() };
+        assert_eq!(window_out >> 8, 0b1001_0000);
+        assert_eq!(DISPLAY_CONTROL.get() & (1 << 15), 1 << 15);
+
+        window.win_obj().reset();
+        window.commit();
+    }
+
+    #[test_case]
+    fn static_sprites_set_the_shape_and_size(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+
+        let mut emu = object.object(object.sprite(EMU.sprite(0)));
+        emu.set_static_sprite(BOSS.sprite(0));
+
+        let (shape, size) = BOSS.sprite(0).size().shape_size();
+        let object_inner = unsafe { emu.object_inner() };
+        assert_eq!(object_inner.attrs.a0.shape(), shape);
+        assert_eq!(object_inner.attrs.a1s.size(), size);
+        assert_eq!(
+            object_inner.attrs.a2.tile_index(),
+            object_inner.sprite.sprite.0.tile_index()
+        );
+    }
+
+    #[test_case]
+    fn identical_palettes_share_a_bank(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        object.commit();
+
+        let before = object.sprite_vram_stats().palettes_in_vram;
+
+        let colours = [0x1234; 16];
+        let first = object.palette(&Palette16::new(colours));
+        let second = object.palette(&Palette16::new(colours));
+        let different = object.palette(&Palette16::new([0x4321; 16]));
+
+        assert_eq!(first.0.location.0, second.0.location.0);
+        assert_ne!(first.0.location.0, different.0.location.0);
+        assert_eq!(object.sprite_vram_stats().palettes_in_vram, before + 2);
+
+        drop((first, second, different));
+        assert_eq!(object.sprite_vram_stats().palettes_in_vram, before);
+    }
+
+    #[test_case]
+    fn object_hit_boxes_follow_position_and_flips(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+
+        let sprite = EMU.sprite(0);
+        let mut emu = object.object(object.sprite(sprite));
+        let mut other = object.object(object.sprite(sprite));
+
+        emu.set_position((20, 30).into()).set_hflip(true);
+        assert_eq!(
+            emu.hit_box(),
+            crate::hit_box::sprite_hit_box(sprite, (20, 30).into(), true, false)
+        );
+
+        let hit_box = sprite.hit_box();
+        let (width, _) = sprite.size().to_width_height();
+        assert!(hit_box.size.x > 0 && hit_box.size.x <= width as i32);
+
+        other.set_position((20, 30).into());
+        assert!(emu.collides_with(&other));
+
+        other.set_position((200, 30).into());
+        assert!(!emu.collides_with(&other));
+    }
+
+    #[test_case]
+    fn sprites_included_with_masks_have_collision_masks(_gba: &mut crate::Gba) {
+        let sprite = EMU.sprite(0);
+        let mask = sprite.collision_mask().unwrap();
+        let (width, height) = sprite.size().to_width_height();
+        assert_eq!(mask.size(), (width as i32, height as i32).into());
+
+        let origin = (0, 0).into();
+        assert!(crate::hit_box::mask_collision(mask, origin, mask, origin));
+
+        let hit_box = sprite.hit_box();
+        let past_hit_box = (hit_box.position.x + hit_box.size.x, 0).into();
+        assert!(!crate::hit_box::mask_collision(
+            mask,
+            origin,
+            mask,
+            past_hit_box
+        ));
+    }
+
+    #[test_case]
+    fn affine_matrices_are_shared_and_freed(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+
+        {
+            let matrix = object.affine_matrix(AffineMatrix::identity().to_object_wrapping());
+            matrix.set_transform(num!(0.25), (num!(2.), num!(1.)).into());
+
+            // the object is stretched along its own x axis before being
+            // rotated, so a point 8 pixels below the centre on screen is 4
+            // pixels along the sprite's x axis
+            assert_eq!(
+                matrix
+                    .matrix()
+                    .to_affine_matrix()
+                    .transform_point((0, 8).into()),
+                (-4, 0).into()
+            );
+
+            let mut first = object.object(object.sprite(EMU.sprite(0)));
+            let mut second = object.object(object.sprite(EMU.sprite(0)));
+            first.set_affine_matrix(matrix.clone()).show();
+            second
+                .set_affine_matrix(matrix)
+                .set_double_size(true)
+                .show();
+
+            let matrices: Vec<_> = (0..31)
+                .map(|_| object.affine_matrix(AffineMatrix::identity().to_object_wrapping()))
+                .collect();
+            assert!(object
+                .try_get_affine_matrix(AffineMatrix::identity().to_object_wrapping())
+                .is_none());
+
+            drop(matrices);
+        }
+
+        // the objects have been dropped but not yet committed, and their
+        // matrix is already free
+        let _matrices: Vec<_> = (0..32)
+            .map(|_| object.affine_matrix(AffineMatrix::identity().to_object_wrapping()))
+            .collect();
+        assert!(object
+            .try_get_affine_matrix(AffineMatrix::identity().to_object_wrapping())
+            .is_none());
+
+        object.commit();
+    }
 }
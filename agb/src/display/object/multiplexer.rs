@@ -0,0 +1,432 @@
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use bare_metal::Mutex;
+
+use super::{
+    Attributes, ObjectController, ObjectMode, SpriteBorrow, HIDDEN_VALUE, OBJECT_ATTRIBUTE_MEMORY,
+};
+use crate::display::{Priority, DISPLAY_CONTROL, HEIGHT, VCOUNT, WIDTH};
+use crate::fixnum::Vector2D;
+use crate::interrupt::{add_interrupt_handler, Interrupt, InterruptHandler};
+use crate::sync::Static;
+
+/// The number of cycles the hardware has to draw objects on each scanline while
+/// object attribute memory can be accessed during hblank. A regular object
+/// costs one cycle for each pixel of its width on every line it covers.
+const CYCLES_PER_LINE: u16 = 954;
+
+static MULTIPLEXER_ACTIVE: Static<bool> = Static::new(false);
+
+/// Whether a [Multiplexer] currently exists, which needs object attribute
+/// memory to be accessible during hblank.
+pub(crate) fn multiplexer_active() -> bool {
+    MULTIPLEXER_ACTIVE.read()
+}
+
+/// A sprite to be displayed by a [Multiplexer] for a single frame.
+pub struct MultiplexedSprite {
+    sprite: SpriteBorrow,
+    position: Vector2D<i32>,
+    hflip: bool,
+    vflip: bool,
+    priority: Priority,
+}
+
+impl MultiplexedSprite {
+    /// Creates a sprite with its top left at the given position.
+    #[must_use]
+    pub fn new(sprite: SpriteBorrow, position: Vector2D<i32>) -> Self {
+        Self {
+            sprite,
+            position,
+            hflip: false,
+            vflip: false,
+            priority: Priority::P0,
+        }
+    }
+
+    /// Flips the sprite horizontally.
+    #[must_use]
+    pub fn with_hflip(mut self, flip: bool) -> Self {
+        self.hflip = flip;
+        self
+    }
+
+    /// Flips the sprite vertically.
+    #[must_use]
+    pub fn with_vflip(mut self, flip: bool) -> Self {
+        self.vflip = flip;
+        self
+    }
+
+    /// Sets the priority of the sprite relative to the backgrounds.
+    #[must_use]
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    fn size(&self) -> Vector2D<i32> {
+        let (width, height) = self.sprite.sprite.0.size.to_width_height();
+        (width as i32, height as i32).into()
+    }
+
+    fn attributes(&self) -> [u16; 3] {
+        let mut attrs = Attributes::new();
+        attrs.set_sprite(&self.sprite.sprite.0);
+        attrs.a0.set_object_mode(ObjectMode::Normal);
+        attrs.a0.set_y(self.position.y as u8);
+        attrs.a1s.set_x(self.position.x.rem_euclid(1 << 9) as u16);
+        attrs.a1s.set_horizontal_flip(self.hflip);
+        attrs.a1s.set_vertical_flip(self.vflip);
+        attrs.a2.set_priority(self.priority);
+
+        let mut entry = [0; 3];
+        attrs.write(&mut entry);
+        entry
+    }
+}
+
+/// Statistics about the last frame committed by a [Multiplexer].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MultiplexerStats {
+    /// The number of sprites added to the frame, including any off screen.
+    pub sprites: usize,
+    /// The number of sprites which are being displayed.
+    pub displayed: usize,
+    /// The number of on screen sprites which couldn't be displayed, either
+    /// because a scanline they cover would have taken too long to draw or
+    /// because no hardware object was free in time.
+    pub dropped: usize,
+    /// The number of cycles spent drawing sprites on the busiest scanline,
+    /// out of a maximum of 954.
+    pub busiest_line_cycles: u16,
+}
+
+/// Rewrites a hardware object during the hblank of the given line.
+struct Rewrite {
+    line: u16,
+    slot: usize,
+    attributes: [u16; 3],
+}
+
+struct Schedule {
+    first_slot: usize,
+    initial: Vec<[u16; 3]>,
+    rewrites: Vec<Rewrite>,
+    next: usize,
+}
+
+impl Schedule {
+    fn hblank(&mut self, line: u16) {
+        // if the frame wasn't committed, display the previous one again
+        if line == HEIGHT as u16 && self.next != 0 {
+            self.write_initial();
+        }
+
+        while let Some(rewrite) = self.rewrites.get(self.next) {
+            if rewrite.line > line {
+                break;
+            }
+
+            write_slot(self.first_slot + rewrite.slot, rewrite.attributes);
+            self.next += 1;
+        }
+    }
+
+    fn write_initial(&mut self) {
+        for (slot, &attributes) in self.initial.iter().enumerate() {
+            write_slot(self.first_slot + slot, attributes);
+        }
+
+        self.next = 0;
+    }
+}
+
+fn write_slot(slot: usize, attributes: [u16; 3]) {
+    let entry = (OBJECT_ATTRIBUTE_MEMORY as *mut u16).wrapping_add(slot * 4);
+
+    for (i, &attribute) in attributes.iter().enumerate() {
+        unsafe { entry.add(i).write_volatile(attribute) };
+    }
+}
+
+/// Displays more sprites than there are hardware objects by rewriting objects
+/// part way down the screen once the sprite they were showing has been drawn,
+/// which is useful for scenes with lots of bullets. Created with
+/// [ObjectController::multiplexer], which sets aside some of the hardware
+/// objects to be reused.
+///
+/// Sprites are added each frame with [Multiplexer::add] and then displayed
+/// with [Multiplexer::commit], which should be called just after
+/// [ObjectController::commit]. The interrupt handler from
+/// [Multiplexer::setup_interrupt_handler] must be kept alive for the rewrites
+/// to happen.
+///
+/// Sprites which would make a scanline take too long to draw, or for which
+/// no hardware object is free in time, are dropped and counted in
+/// [Multiplexer::stats]. The order overlapping multiplexed sprites are drawn
+/// in isn't defined.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::display::object::{Graphics, MultiplexedSprite, Tag};
+/// # fn foo(gba: &mut agb::Gba) {
+/// # const GRAPHICS: &Graphics = agb::include_aseprite!("examples/gfx/objects.aseprite");
+/// # const BULLET: &Tag = GRAPHICS.tags().get("emu-walk");
+/// let object = gba.display.object.get();
+/// let mut multiplexer = object.multiplexer(64);
+/// let _handler = multiplexer.setup_interrupt_handler();
+/// let vblank = agb::interrupt::VBlank::get();
+///
+/// let bullet = object.sprite(BULLET.sprite(0));
+///
+/// loop {
+///     for i in 0..200 {
+///         let position = (i % 20 * 12, i / 20 * 16).into();
+///         multiplexer.add(MultiplexedSprite::new(bullet.clone(), position));
+///     }
+///
+///     vblank.wait_for_vblank();
+///     object.commit();
+///     multiplexer.commit();
+/// }
+/// # }
+/// ```
+pub struct Multiplexer<'a> {
+    controller: &'a ObjectController,
+    first_slot: usize,
+    slots: usize,
+    pending: Vec<MultiplexedSprite>,
+    displayed: Vec<SpriteBorrow>,
+    schedule: Mutex<RefCell<Schedule>>,
+    stats: MultiplexerStats,
+}
+
+impl<'a> Multiplexer<'a> {
+    pub(super) fn new(controller: &'a ObjectController, first_slot: usize, slots: usize) -> Self {
+        // allows object attribute memory to be written during hblank
+        DISPLAY_CONTROL.set_bits(1, 1, 5);
+        MULTIPLEXER_ACTIVE.write(true);
+
+        Self {
+            controller,
+            first_slot,
+            slots,
+            pending: Vec::new(),
+            displayed: Vec::new(),
+            schedule: Mutex::new(RefCell::new(Schedule {
+                first_slot,
+                initial: alloc::vec![[HIDDEN_VALUE, 0, 0]; slots],
+                rewrites: Vec::new(),
+                next: 0,
+            })),
+            stats: MultiplexerStats::default(),
+        }
+    }
+
+    /// Adds a sprite to be displayed once [Multiplexer::commit] is next
+    /// called.
+    pub fn add(&mut self, sprite: MultiplexedSprite) {
+        self.pending.push(sprite);
+    }
+
+    /// Works out which hardware object displays each sprite added since the
+    /// last commit and when it needs to be rewritten, replacing the previous
+    /// frame. This should be called during vblank, just after
+    /// [ObjectController::commit].
+    pub fn commit(&mut self) {
+        let mut sprites = core::mem::take(&mut self.pending);
+        let total = sprites.len();
+
+        sprites.retain(|sprite| {
+            let size = sprite.size();
+            sprite.position.x + size.x > 0
+                && sprite.position.y + size.y > 0
+                && sprite.position.x < WIDTH
+                && sprite.position.y < HEIGHT
+        });
+        sprites.sort_by_key(|sprite| sprite.position.y);
+
+        let mut line_cycles = [0u16; HEIGHT as usize];
+        // the line after the bottom of the sprite each hardware object is
+        // showing, or None if it hasn't been used yet this frame
+        let mut free_from: Vec<Option<i32>> = alloc::vec![None; self.slots];
+        let mut initial = alloc::vec![[HIDDEN_VALUE, 0, 0]; self.slots];
+        let mut rewrites = Vec::new();
+        let mut displayed = Vec::new();
+        let mut dropped = 0;
+
+        for sprite in sprites {
+            let size = sprite.size();
+            let top = sprite.position.y.max(0);
+            let bottom = (sprite.position.y + size.y).min(HEIGHT);
+            let cost = size.x as u16;
+
+            let lines = &mut line_cycles[top as usize..bottom as usize];
+            if lines.iter().any(|&cycles| cycles + cost > CYCLES_PER_LINE) {
+                dropped += 1;
+                continue;
+            }
+
+            // Objects are drawn a line ahead, so an object can be rewritten
+            // during the hblank two lines before the new sprite's top once
+            // the old sprite has been drawn.
+            let slot = free_from.iter().position(Option::is_none).or_else(|| {
+                free_from.iter().position(|&free_from| {
+                    free_from.is_some_and(|free_from| free_from <= top && top >= 2)
+                })
+            });
+
+            let slot = match slot {
+                Some(slot) => slot,
+                None => {
+                    dropped += 1;
+                    continue;
+                }
+            };
+
+            let attributes = sprite.attributes();
+            if free_from[slot].is_none() {
+                initial[slot] = attributes;
+            } else {
+                rewrites.push(Rewrite {
+                    line: (top - 2) as u16,
+                    slot,
+                    attributes,
+                });
+            }
+
+            free_from[slot] = Some(bottom);
+            for cycles in lines {
+                *cycles += cost;
+            }
+
+            displayed.push(sprite.sprite);
+        }
+
+        rewrites.sort_by_key(|rewrite| rewrite.line);
+
+        self.stats = MultiplexerStats {
+            sprites: total,
+            displayed: displayed.len(),
+            dropped,
+            busiest_line_cycles: line_cycles.iter().copied().max().unwrap_or(0),
+        };
+
+        crate::interrupt::free(|cs| {
+            let mut schedule = self.schedule.borrow(cs).borrow_mut();
+            schedule.initial = initial;
+            schedule.rewrites = rewrites;
+            schedule.write_initial();
+        });
+
+        // the sprites from the previous frame are no longer displayed so
+        // can be freed
+        self.displayed = displayed;
+    }
+
+    /// Statistics about the last frame committed.
+    #[must_use]
+    pub fn stats(&self) -> MultiplexerStats {
+        self.stats
+    }
+
+    /// The number of hardware objects being reused to display the sprites.
+    #[must_use]
+    pub fn hardware_objects(&self) -> usize {
+        self.slots
+    }
+
+    /// Creates the hblank interrupt handler which rewrites the hardware
+    /// objects part way down the screen. Sprites past the first use of each
+    /// hardware object won't be displayed unless this is kept alive.
+    pub fn setup_interrupt_handler(&self) -> InterruptHandler<'_> {
+        let schedule = &self.schedule;
+
+        add_interrupt_handler(Interrupt::HBlank, move |cs| {
+            schedule.borrow(cs).borrow_mut().hblank(VCOUNT.get());
+        })
+    }
+}
+
+impl Drop for Multiplexer<'_> {
+    fn drop(&mut self) {
+        for slot in self.first_slot..self.first_slot + self.slots {
+            write_slot(slot, [HIDDEN_VALUE, 0, 0]);
+        }
+
+        DISPLAY_CONTROL.set_bits(0, 1, 5);
+        MULTIPLEXER_ACTIVE.write(false);
+
+        self.controller.release_multiplexer_objects();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::object::tests::EMU;
+
+    #[test_case]
+    fn hardware_objects_are_reused_further_down_the_screen(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let sprite = object.sprite(EMU.sprite(0));
+        let (width, height) = EMU.sprite(0).size().to_width_height();
+        let (width, height) = (width as i32, height as i32);
+
+        let mut multiplexer = object.multiplexer(4);
+
+        // a column of sprites needs only a single hardware object
+        for i in 0..HEIGHT / height {
+            multiplexer.add(MultiplexedSprite::new(
+                sprite.clone(),
+                (0, i * height).into(),
+            ));
+        }
+        // five sprites on the same lines can't all be displayed, and off
+        // screen sprites are ignored
+        for i in 0..5 {
+            multiplexer.add(MultiplexedSprite::new(
+                sprite.clone(),
+                ((i + 1) * width, 0).into(),
+            ));
+        }
+        multiplexer.add(MultiplexedSprite::new(sprite.clone(), (0, HEIGHT).into()));
+
+        object.commit();
+        multiplexer.commit();
+
+        let stats = multiplexer.stats();
+        assert_eq!(stats.sprites, (HEIGHT / height + 6) as usize);
+        assert_eq!(stats.dropped, 2);
+        assert_eq!(stats.displayed, stats.sprites - 3);
+        assert_eq!(stats.busiest_line_cycles, 4 * width as u16);
+
+        drop(multiplexer);
+
+        // the objects are available again once the multiplexer is dropped
+        let mut objects: Vec<_> = (0..128)
+            .map(|_| object.try_get_object(sprite.clone()).unwrap())
+            .collect();
+        for o in &mut objects {
+            o.show();
+        }
+        object.commit();
+
+        // and are written out in full even though the multiplexer had written
+        // to them directly
+        let s = unsafe { object.inner.borrow_mut() };
+        for word in (128 - 4) * 4..128 * 4 {
+            let oam = unsafe {
+                (OBJECT_ATTRIBUTE_MEMORY as *const u16)
+                    .add(word)
+                    .read_volatile()
+            };
+            assert_eq!(oam, s.oam_buffer[word]);
+        }
+    }
+}
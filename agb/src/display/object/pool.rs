@@ -0,0 +1,239 @@
+use alloc::vec::Vec;
+
+use super::Object;
+
+/// A handle to an object in an [ObjectPool]. Keys are small and [Copy], so
+/// they can be stored in game structures without the lifetimes that come with
+/// holding an [Object]. A key for an object which has been removed from the
+/// pool won't refer to a different object when its slot is reused, unless the
+/// slot has since been reused 2<sup>32</sup> times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObjectKey {
+    index: u16,
+    generation: u32,
+}
+
+struct Slot<'a> {
+    generation: u32,
+    object: Option<Object<'a>>,
+}
+
+/// Owns objects and gives out [ObjectKey]s to refer to them.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::display::object::{Graphics, ObjectPool, Tag};
+/// # fn foo(gba: &mut agb::Gba) {
+/// # const GRAPHICS: &Graphics = agb::include_aseprite!("examples/gfx/objects.aseprite");
+/// # const EMU_WALK: &Tag = GRAPHICS.tags().get("emu-walk");
+/// struct Enemy {
+///     object: agb::display::object::ObjectKey,
+///     health: i32,
+/// }
+///
+/// let object = gba.display.object.get();
+/// let mut pool = ObjectPool::new();
+///
+/// let mut enemy = Enemy {
+///     object: pool.insert(object.object(object.sprite(EMU_WALK.sprite(0)))),
+///     health: 10,
+/// };
+///
+/// if let Some(enemy_object) = pool.get_mut(enemy.object) {
+///     enemy_object.set_x(50).show();
+/// }
+/// # }
+/// ```
+pub struct ObjectPool<'a> {
+    slots: Vec<Slot<'a>>,
+    free: Vec<u16>,
+}
+
+impl<'a> ObjectPool<'a> {
+    /// Creates an empty pool.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Adds the object to the pool, returning the key which refers to it.
+    pub fn insert(&mut self, object: Object<'a>) -> ObjectKey {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.object = Some(object);
+
+            return ObjectKey {
+                index,
+                generation: slot.generation,
+            };
+        }
+
+        let index = self.slots.len() as u16;
+        self.slots.push(Slot {
+            generation: 0,
+            object: Some(object),
+        });
+
+        ObjectKey {
+            index,
+            generation: 0,
+        }
+    }
+
+    fn slot(&self, key: ObjectKey) -> Option<&Slot<'a>> {
+        self.slots
+            .get(key.index as usize)
+            .filter(|slot| slot.generation == key.generation)
+    }
+
+    /// Gets the object referred to by the key, or [None] if it has been
+    /// removed.
+    #[must_use]
+    pub fn get(&self, key: ObjectKey) -> Option<&Object<'a>> {
+        self.slot(key)?.object.as_ref()
+    }
+
+    /// Gets the object referred to by the key, or [None] if it has been
+    /// removed.
+    pub fn get_mut(&mut self, key: ObjectKey) -> Option<&mut Object<'a>> {
+        self.slots
+            .get_mut(key.index as usize)
+            .filter(|slot| slot.generation == key.generation)?
+            .object
+            .as_mut()
+    }
+
+    /// Whether the key refers to an object still in the pool.
+    #[must_use]
+    pub fn contains(&self, key: ObjectKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes the object from the pool and returns it, or [None] if it has
+    /// already been removed. Any copies of the key will no longer refer to
+    /// anything.
+    pub fn remove(&mut self, key: ObjectKey) -> Option<Object<'a>> {
+        let slot = self
+            .slots
+            .get_mut(key.index as usize)
+            .filter(|slot| slot.generation == key.generation)?;
+
+        let object = slot.object.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index);
+
+        Some(object)
+    }
+
+    /// The number of objects in the pool.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Whether the pool has no objects.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over every object in the pool along with its key.
+    pub fn iter(&self) -> impl Iterator<Item = (ObjectKey, &Object<'a>)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let key = ObjectKey {
+                index: index as u16,
+                generation: slot.generation,
+            };
+            slot.object.as_ref().map(|object| (key, object))
+        })
+    }
+
+    /// Iterates mutably over every object in the pool along with its key.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ObjectKey, &mut Object<'a>)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let key = ObjectKey {
+                    index: index as u16,
+                    generation: slot.generation,
+                };
+                slot.object.as_mut().map(|object| (key, object))
+            })
+    }
+
+    /// Hides every object in the pool. No change will be seen until
+    /// [ObjectController::commit](super::ObjectController::commit) is called.
+    pub fn hide_all(&mut self) {
+        for (_, object) in self.iter_mut() {
+            object.hide();
+        }
+    }
+
+    /// Removes every object from the pool, freeing them. Keys to any of the
+    /// objects will no longer refer to anything.
+    pub fn clear(&mut self) {
+        for index in 0..self.slots.len() {
+            let slot = &mut self.slots[index];
+            if slot.object.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free.push(index as u16);
+            }
+        }
+    }
+
+    /// Removes every object for which the predicate returns false.
+    pub fn retain(&mut self, mut predicate: impl FnMut(ObjectKey, &mut Object<'a>) -> bool) {
+        let keys: Vec<_> = self
+            .iter_mut()
+            .filter_map(|(key, object)| (!predicate(key, object)).then_some(key))
+            .collect();
+
+        for key in keys {
+            self.remove(key);
+        }
+    }
+}
+
+impl Default for ObjectPool<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::object::tests::EMU;
+
+    #[test_case]
+    fn removed_keys_are_not_reused(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let mut pool = ObjectPool::new();
+
+        let first = pool.insert(object.object(object.sprite(EMU.sprite(0))));
+        let second = pool.insert(object.object(object.sprite(EMU.sprite(0))));
+        assert_eq!(pool.len(), 2);
+
+        assert!(pool.remove(first).is_some());
+        assert!(pool.remove(first).is_none());
+        assert!(!pool.contains(first));
+
+        let third = pool.insert(object.object(object.sprite(EMU.sprite(0))));
+        assert_ne!(first, third);
+        assert!(pool.get(first).is_none());
+        assert!(pool.get(third).is_some());
+
+        pool.retain(|key, _| key != second);
+        assert!(!pool.contains(second));
+        assert_eq!(pool.iter().count(), 1);
+
+        pool.clear();
+        assert!(pool.is_empty());
+        object.commit();
+    }
+}
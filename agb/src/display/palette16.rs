@@ -1,3 +1,17 @@
+use alloc::vec::Vec;
+
+use crate::display::commit_queue::CommitQueue;
+use crate::display::tiled::VRamManager;
+use crate::dma::copy_to;
+use crate::fixnum::Num;
+
+/// Palette ram holds 256 background colours followed by 256 object colours.
+pub(crate) const PALETTE_BACKGROUND: usize = 0x0500_0000;
+pub(crate) const PALETTE_OBJECT: usize = 0x0500_0200;
+
+const PALETTE_RAM: *mut u16 = PALETTE_BACKGROUND as *mut u16;
+const PALETTE_RAM_COLOURS: usize = 512;
+
 #[repr(C)]
 #[derive(Clone)]
 pub struct Palette16 {
@@ -22,3 +36,227 @@ impl Palette16 {
         self.colours[index]
     }
 }
+
+/// Linearly interpolates every colour in the palette towards `target_colour`. An
+/// `amount` of 0 returns the palette unchanged and an amount of 1 returns a
+/// palette made entirely of `target_colour`. Amounts outside of this range are
+/// clamped.
+///
+/// Colours are in the GBA's 15 bit format, so use `0` to fade to black and
+/// `0x7fff` to fade to white.
+#[must_use]
+pub fn fade(palette: &Palette16, target_colour: u16, amount: Num<i32, 8>) -> Palette16 {
+    let amount = amount.clamp(0.into(), 1.into());
+
+    let mut colours = palette.colours;
+    for colour in &mut colours {
+        *colour = fade_colour(*colour, target_colour, amount);
+    }
+
+    Palette16::new(colours)
+}
+
+pub(crate) fn fade_colour(colour: u16, target_colour: u16, amount: Num<i32, 8>) -> u16 {
+    let mut result = 0;
+
+    for shift in [0, 5, 10] {
+        let from = i32::from((colour >> shift) & 0x1f);
+        let to = i32::from((target_colour >> shift) & 0x1f);
+
+        let channel = (Num::new(from) + (Num::new(to) - from) * amount).floor();
+        result |= (channel as u16) << shift;
+    }
+
+    result
+}
+
+/// Fades all the background palettes towards a single colour (usually black or
+/// white) by recalculating and re-uploading them each frame. This gives you a
+/// fade in or out without needing to use the blend registers.
+pub struct BackgroundPaletteFader<'a> {
+    palettes: &'a [Palette16],
+    target_colour: u16,
+    fading_out: bool,
+    frame: u16,
+    duration: u16,
+}
+
+impl<'a> BackgroundPaletteFader<'a> {
+    /// Creates a new fader for the given palettes, which are the palettes used
+    /// when not faded at all. It starts fully faded in.
+    #[must_use]
+    pub fn new(palettes: &'a [Palette16], target_colour: u16) -> Self {
+        assert!(
+            palettes.len() <= 16,
+            "There are only 16 background palettes"
+        );
+
+        Self {
+            palettes,
+            target_colour,
+            fading_out: false,
+            frame: 0,
+            duration: 0,
+        }
+    }
+
+    /// Starts fading towards the target colour over the given number of frames.
+    pub fn fade_out(&mut self, frames: u16) {
+        self.fading_out = true;
+        self.frame = 0;
+        self.duration = frames;
+    }
+
+    /// Starts fading from the target colour back to the original palettes over
+    /// the given number of frames.
+    pub fn fade_in(&mut self, frames: u16) {
+        self.fading_out = false;
+        self.frame = 0;
+        self.duration = frames;
+    }
+
+    /// Whether the current fade has finished.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.frame >= self.duration
+    }
+
+    /// Advances the fade by one frame and uploads the faded palettes. This
+    /// should be called once per frame, ideally during vblank.
+    pub fn update(&mut self, vram: &mut VRamManager) {
+        self.frame = self.frame.saturating_add(1).min(self.duration);
+
+        let progress = if self.duration == 0 {
+            Num::new(1)
+        } else {
+            Num::new(i32::from(self.frame)) / i32::from(self.duration)
+        };
+
+        let amount = if self.fading_out {
+            progress
+        } else {
+            Num::new(1) - progress
+        };
+
+        let faded: Vec<Palette16> = self
+            .palettes
+            .iter()
+            .map(|palette| fade(palette, self.target_colour, amount))
+            .collect();
+
+        vram.set_background_palettes(&faded);
+    }
+}
+
+/// Fades every colour in palette ram, covering both backgrounds and objects,
+/// towards a single colour. Unlike the brightness fades in
+/// [Blend](crate::display::blend::Blend) this also works on objects which are
+/// using alpha blending. The palettes are read from palette ram when the fader
+/// is created, so they can be restored once the fade is over.
+pub struct PaletteRamFader {
+    original: Vec<u16>,
+    faded: Vec<u16>,
+    target_colour: u16,
+}
+
+impl PaletteRamFader {
+    /// Reads the palettes currently in palette ram, which are used as the
+    /// unfaded palettes.
+    #[must_use]
+    pub fn new(target_colour: u16) -> Self {
+        let original: Vec<u16> = (0..PALETTE_RAM_COLOURS)
+            .map(|i| unsafe { PALETTE_RAM.add(i).read_volatile() })
+            .collect();
+
+        Self {
+            faded: original.clone(),
+            original,
+            target_colour,
+        }
+    }
+
+    /// Writes the palettes faded by the given amount towards the target colour
+    /// into palette ram, where 0 is the original palettes and 1 is entirely
+    /// the target colour. This should be called during vblank.
+    pub fn set_amount(&mut self, amount: Num<i32, 8>) {
+        self.fade(amount);
+
+        unsafe {
+            copy_to(self.faded.as_ptr(), PALETTE_RAM, self.faded.len());
+        }
+    }
+
+    /// Does the same as [set_amount](PaletteRamFader::set_amount), except that
+    /// the faded palettes are added to the queue to be copied when it is
+    /// flushed during vblank.
+    pub fn set_amount_queued(&mut self, amount: Num<i32, 8>, queue: &mut CommitQueue) {
+        self.fade(amount);
+
+        unsafe { queue.copy(&self.faded, PALETTE_RAM) };
+    }
+
+    fn fade(&mut self, amount: Num<i32, 8>) {
+        let amount = amount.clamp(0.into(), 1.into());
+
+        for (faded, &original) in self.faded.iter_mut().zip(&self.original) {
+            *faded = fade_colour(original, self.target_colour, amount);
+        }
+    }
+
+    /// Writes the original palettes back into palette ram.
+    pub fn restore(&self) {
+        unsafe {
+            copy_to(self.original.as_ptr(), PALETTE_RAM, self.original.len());
+        }
+    }
+
+    /// The colours which were in palette ram when the fader was created, with
+    /// the 256 background colours followed by the 256 object colours.
+    #[must_use]
+    pub fn original_colours(&self) -> &[u16] {
+        &self.original
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    #[allow(clippy::unusual_byte_groupings)] // grouped by colour channel
+    fn fade_interpolates_each_channel(_gba: &mut crate::Gba) {
+        let palette = Palette16::new([0b11111_01000_00000; 16]);
+
+        let unchanged = fade(&palette, 0, Num::new(0));
+        assert_eq!(unchanged.colour(0), 0b11111_01000_00000);
+
+        let halfway = fade(&palette, 0x7fff, Num::new(1) / 2);
+        assert_eq!(halfway.colour(0), 0b11111_10011_01111);
+
+        let black = fade(&palette, 0, Num::new(2));
+        assert_eq!(black.colour(15), 0);
+    }
+
+    #[test_case]
+    #[allow(clippy::unusual_byte_groupings)] // grouped by colour channel
+    fn palette_ram_fades_are_reversible(_gba: &mut crate::Gba) {
+        let colour = |i: usize| unsafe { PALETTE_RAM.add(i).read_volatile() };
+        unsafe {
+            PALETTE_RAM.add(1).write_volatile(0x7fff);
+            PALETTE_RAM.add(256 + 1).write_volatile(0x1234);
+        }
+
+        let mut fader = PaletteRamFader::new(0);
+
+        fader.set_amount(Num::new(1));
+        assert_eq!(colour(1), 0);
+        assert_eq!(colour(256 + 1), 0);
+
+        fader.set_amount(Num::new(1) / 2);
+        assert_eq!(colour(1), 0b01111_01111_01111);
+
+        fader.restore();
+        assert_eq!(colour(1), 0x7fff);
+        assert_eq!(colour(256 + 1), 0x1234);
+    }
+}
@@ -0,0 +1,289 @@
+#![deny(missing_docs)]
+//! Changing display registers part way through drawing the screen.
+//!
+//! A [RasterEffect] holds one value of a register for each of the 160 lines
+//! of the screen, and writes the value for each line during the hblank just
+//! before it is drawn. This gives effects such as gradient skies, wavy
+//! backgrounds or palette raster bars, where each line has its own backdrop
+//! colour, scroll position or blend weight.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::display::raster::{RasterEffect, RasterRegister};
+//!
+//! # fn foo(gba: &mut agb::Gba) {
+//! let vblank = agb::interrupt::VBlank::get();
+//!
+//! // a gradient from black at the top to blue at the bottom
+//! let mut sky = RasterEffect::from_fn(RasterRegister::backdrop_colour(), |line| {
+//!     ((line * 32 / 160) as u16) << 10
+//! });
+//! sky.commit();
+//!
+//! let _dma = sky.setup_dma();
+//!
+//! loop {
+//!     vblank.wait_for_vblank();
+//! }
+//! # }
+//! ```
+
+use alloc::{vec, vec::Vec};
+use core::cell::RefCell;
+
+use bare_metal::Mutex;
+
+use super::{tiled::BackgroundID, window::WindowId, HEIGHT, VCOUNT};
+use crate::dma::DmaChannel;
+use crate::interrupt::{add_interrupt_handler, free, Interrupt, InterruptHandler};
+
+const LINES: usize = HEIGHT as usize;
+/// The last line of vblank, during whose hblank the first line is prepared.
+const LAST_LINE: u16 = 227;
+
+/// A 16 bit display register which a [RasterEffect] can change on every line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RasterRegister(usize);
+
+impl RasterRegister {
+    /// Creates a register from its address.
+    ///
+    /// # Safety
+    /// The address must be a 16 bit hardware register or palette entry which
+    /// can be written at any time.
+    #[must_use]
+    pub const unsafe fn new(address: usize) -> Self {
+        Self(address)
+    }
+
+    /// The horizontal scroll of a regular background.
+    #[must_use]
+    pub const fn background_x_scroll(background: BackgroundID) -> Self {
+        Self(0x0400_0010 + 4 * background.0 as usize)
+    }
+
+    /// The vertical scroll of a regular background.
+    #[must_use]
+    pub const fn background_y_scroll(background: BackgroundID) -> Self {
+        Self(0x0400_0012 + 4 * background.0 as usize)
+    }
+
+    /// The colour shown where nothing else is drawn, the first background
+    /// palette entry.
+    #[must_use]
+    pub const fn backdrop_colour() -> Self {
+        Self(0x0500_0000)
+    }
+
+    /// A colour in the 256 entry background palette.
+    #[must_use]
+    pub const fn background_palette_colour(index: u8) -> Self {
+        Self(0x0500_0000 + 2 * index as usize)
+    }
+
+    /// A colour in the 256 entry object palette.
+    #[must_use]
+    pub const fn object_palette_colour(index: u8) -> Self {
+        Self(0x0500_0200 + 2 * index as usize)
+    }
+
+    /// The blend weights used by alpha blending, with the top layer weight in
+    /// the low byte and the bottom layer weight in the high byte.
+    #[must_use]
+    pub const fn blend_alpha() -> Self {
+        Self(0x0400_0052)
+    }
+
+    /// The brightness used when fading to black or white.
+    #[must_use]
+    pub const fn blend_brightness() -> Self {
+        Self(0x0400_0054)
+    }
+
+    /// The horizontal extent of a movable window, with the right edge in the
+    /// low byte and the left edge in the high byte. Panics for windows other
+    /// than [WindowId::Win0] and [WindowId::Win1].
+    #[must_use]
+    pub const fn window_horizontal(window: WindowId) -> Self {
+        match window {
+            WindowId::Win0 => Self(0x0400_0040),
+            WindowId::Win1 => Self(0x0400_0042),
+            _ => panic!("only the movable windows have a position"),
+        }
+    }
+
+    fn pointer(self) -> *mut u16 {
+        self.0 as *mut u16
+    }
+}
+
+/// A table of values for a register, one for each line of the screen.
+///
+/// Changes are made to a pending table, which becomes the one used for
+/// drawing when [commit](RasterEffect::commit) is called. Either
+/// [setup_dma](RasterEffect::setup_dma) or
+/// [setup_interrupt_handler](RasterEffect::setup_interrupt_handler) then
+/// writes the committed values while the screen is being drawn.
+pub struct RasterEffect {
+    register: RasterRegister,
+    pending: Vec<u16>,
+    // The committed values for every line, followed by the value for the first
+    // line again. The DMA writes one value in every hblank of the frame,
+    // including the one after the last line, so the extra entry keeps it in
+    // bounds.
+    committed: Mutex<RefCell<Vec<u16>>>,
+}
+
+impl RasterEffect {
+    /// Creates an effect which writes `value` to the register on every line.
+    #[must_use]
+    pub fn new(register: RasterRegister, value: u16) -> Self {
+        Self {
+            register,
+            pending: vec![value; LINES],
+            committed: Mutex::new(RefCell::new(vec![value; LINES + 1])),
+        }
+    }
+
+    /// Creates an effect with the value for each line given by `f`. Nothing
+    /// is displayed until the effect is committed.
+    #[must_use]
+    pub fn from_fn(register: RasterRegister, f: impl FnMut(usize) -> u16) -> Self {
+        let mut effect = Self::new(register, 0);
+        effect.pending = (0..LINES).map(f).collect();
+        effect
+    }
+
+    /// The register this effect writes to.
+    #[must_use]
+    pub fn register(&self) -> RasterRegister {
+        self.register
+    }
+
+    /// Sets the pending value for a line. Panics if the line is not on the
+    /// screen.
+    pub fn set_line(&mut self, line: usize, value: u16) {
+        self.pending[line] = value;
+    }
+
+    /// The pending value for a line. Panics if the line is not on the screen.
+    #[must_use]
+    pub fn line(&self, line: usize) -> u16 {
+        self.pending[line]
+    }
+
+    /// The pending values for every line, which can be changed directly.
+    pub fn lines_mut(&mut self) -> &mut [u16] {
+        &mut self.pending
+    }
+
+    /// Makes the pending values the ones used to draw the screen. This should
+    /// be called during vblank so a frame isn't drawn with a mix of both.
+    pub fn commit(&mut self) {
+        free(|cs| {
+            let mut committed = self.committed.borrow(cs).borrow_mut();
+            committed[..LINES].copy_from_slice(&self.pending);
+            committed[LINES] = self.pending[0];
+        });
+    }
+
+    /// Writes the values using hblank DMA, which costs no CPU time. Only one
+    /// effect can use DMA at a time, so this panics if another is already
+    /// doing so. The effect stops when the returned value is dropped.
+    ///
+    /// The source of the DMA must be in internal memory, which the heap is.
+    #[must_use]
+    pub fn setup_dma(&self) -> RasterDma<'_> {
+        assert!(
+            DmaChannel::Dma0.reserve(),
+            "only one raster effect can use DMA at a time"
+        );
+
+        let register = self.register;
+        let committed = &self.committed;
+
+        // the source address only moves forward, so restart the DMA from
+        // the top of the table at the start of every frame
+        let handler = add_interrupt_handler(Interrupt::VBlank, move |cs| {
+            let values = committed.borrow(cs).borrow();
+            unsafe {
+                register.pointer().write_volatile(values[0]);
+                DmaChannel::Dma0.hblank_repeat(values[1..].as_ptr(), register.pointer(), 1);
+            }
+        });
+
+        RasterDma {
+            handler: Some(handler),
+        }
+    }
+
+    /// Writes the values from an hblank interrupt. This works for any number
+    /// of effects at once, at the cost of a little CPU time on every line. The
+    /// effect stops when the returned handler is dropped.
+    #[must_use]
+    pub fn setup_interrupt_handler(&self) -> InterruptHandler<'_> {
+        let register = self.register;
+        let committed = &self.committed;
+
+        add_interrupt_handler(Interrupt::HBlank, move |cs| {
+            let line = VCOUNT.get();
+            let next = if line == LAST_LINE {
+                0
+            } else if usize::from(line) + 1 < LINES {
+                usize::from(line) + 1
+            } else {
+                return;
+            };
+
+            let value = committed.borrow(cs).borrow()[next];
+            unsafe { register.pointer().write_volatile(value) };
+        })
+    }
+}
+
+/// Applies a [RasterEffect] using hblank DMA while it is alive, created by
+/// [RasterEffect::setup_dma].
+pub struct RasterDma<'a> {
+    handler: Option<InterruptHandler<'a>>,
+}
+
+impl Drop for RasterDma<'_> {
+    fn drop(&mut self) {
+        // remove the handler first so it can't restart the DMA
+        drop(self.handler.take());
+        DmaChannel::Dma0.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn raster_effects_commit_their_lines(_gba: &mut crate::Gba) {
+        let mut effect =
+            RasterEffect::from_fn(RasterRegister::backdrop_colour(), |line| line as u16);
+        effect.set_line(3, 0x7fff);
+        effect.commit();
+
+        free(|cs| {
+            let committed = effect.committed.borrow(cs).borrow();
+            assert_eq!(committed[3], 0x7fff);
+            assert_eq!(committed[159], 159);
+            assert_eq!(committed[LINES], 0);
+        });
+
+        let vblank = crate::interrupt::VBlank::get();
+        {
+            let _dma = effect.setup_dma();
+            vblank.wait_for_vblank();
+            vblank.wait_for_vblank();
+        }
+        {
+            let _handler = effect.setup_interrupt_handler();
+            vblank.wait_for_vblank();
+            vblank.wait_for_vblank();
+        }
+    }
+}
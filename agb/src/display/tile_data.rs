@@ -2,6 +2,7 @@
 pub struct TileData {
     pub tiles: &'static [u8],
     pub palette_assignments: &'static [u8],
+    pub tile_properties: &'static [u16],
 }
 
 impl TileData {
@@ -10,6 +11,48 @@ impl TileData {
         TileData {
             tiles,
             palette_assignments,
+            tile_properties: &[],
+        }
+    }
+
+    /// Attaches a property to each tile, such as whether it is solid. See
+    /// [`TileSet::with_properties`](crate::display::tiled::TileSet::with_properties).
+    #[must_use]
+    pub const fn with_tile_properties(self, tile_properties: &'static [u16]) -> Self {
+        TileData {
+            tile_properties,
+            ..self
+        }
+    }
+}
+
+/// Tile data compressed using one of the formats understood by the GBA BIOS, produced
+/// by `include_gfx!` when `compression` is set for an image. Load it with
+/// [`VRamManager::add_tileset_compressed`](crate::display::tiled::VRamManager::add_tileset_compressed).
+#[non_exhaustive]
+pub struct CompressedTileData {
+    pub data: &'static [u8],
+    pub palette_assignments: &'static [u8],
+    pub tile_properties: &'static [u16],
+}
+
+impl CompressedTileData {
+    #[must_use]
+    pub const fn new(data: &'static [u8], palette_assignments: &'static [u8]) -> Self {
+        CompressedTileData {
+            data,
+            palette_assignments,
+            tile_properties: &[],
+        }
+    }
+
+    /// Attaches a property to each tile, such as whether it is solid. See
+    /// [`TileSet::with_properties`](crate::display::tiled::TileSet::with_properties).
+    #[must_use]
+    pub const fn with_tile_properties(self, tile_properties: &'static [u16]) -> Self {
+        CompressedTileData {
+            tile_properties,
+            ..self
         }
     }
 }
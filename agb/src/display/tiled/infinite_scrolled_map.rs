@@ -6,8 +6,8 @@ use super::{
 };
 
 use crate::{
-    display,
-    fixnum::{Rect, Vector2D},
+    display::{self, commit_queue::CommitQueue},
+    fixnum::{Num, Rect, Vector2D},
 };
 
 /// The infinite scrolled map allows you to create a game space larger than a single GBA background.
@@ -79,6 +79,7 @@ pub struct InfiniteScrolledMap<'a> {
     tile: Box<dyn Fn(Vector2D<i32>) -> (&'a TileSet<'a>, TileSetting) + 'a>,
 
     current_pos: Vector2D<i32>,
+    precise_pos: Vector2D<Num<i32, 8>>,
     offset: Vector2D<i32>,
 
     copied_up_to: i32,
@@ -106,6 +107,7 @@ impl<'a> InfiniteScrolledMap<'a> {
             map,
             tile,
             current_pos: (0, 0).into(),
+            precise_pos: (0, 0).into(),
             offset: (0, 0).into(),
             copied_up_to: 0,
         }
@@ -249,6 +251,7 @@ impl<'a> InfiniteScrolledMap<'a> {
         pos: Vector2D<i32>,
     ) -> PartialUpdateStatus {
         self.current_pos = pos;
+        self.precise_pos = pos.into();
 
         let x_start = div_floor(self.current_pos.x, 8);
         let y_start = div_floor(self.current_pos.y, 8);
@@ -296,13 +299,48 @@ impl<'a> InfiniteScrolledMap<'a> {
         &mut self,
         vram: &mut VRamManager,
         new_pos: Vector2D<i32>,
+    ) -> PartialUpdateStatus {
+        self.precise_pos = new_pos.into();
+        self.update_pos(vram, new_pos)
+    }
+
+    /// Set the top left corner of the map with sub-pixel precision. This is useful for smooth
+    /// camera movement at speeds of less than a pixel per frame, as the fractional part of the
+    /// position is remembered between calls.
+    ///
+    /// The background itself can only be positioned on whole pixels, so the position is rounded
+    /// down to the nearest pixel before deciding which rows and columns of tiles need to be updated.
+    /// You may need to call this method multiple times if [`PartialUpdateStatus::Continue`] is returned.
+    pub fn set_scroll_pos(
+        &mut self,
+        vram: &mut VRamManager,
+        new_pos: Vector2D<Num<i32, 8>>,
+    ) -> PartialUpdateStatus {
+        self.precise_pos = new_pos;
+        self.update_pos(vram, new_pos.floor())
+    }
+
+    /// The current position of the top left corner of the map, including any fractional part
+    /// passed to [`.set_scroll_pos()`](`InfiniteScrolledMap::set_scroll_pos`).
+    #[must_use]
+    pub fn scroll_pos(&self) -> Vector2D<Num<i32, 8>> {
+        self.precise_pos
+    }
+
+    fn update_pos(
+        &mut self,
+        vram: &mut VRamManager,
+        new_pos: Vector2D<i32>,
     ) -> PartialUpdateStatus {
         let old_pos = self.current_pos;
 
         let difference = new_pos - old_pos;
 
         if difference.x.abs() > 10 * 8 || difference.y.abs() > 10 * 8 {
-            return self.init_partial(vram, new_pos);
+            let precise_pos = self.precise_pos;
+            let status = self.init_partial(vram, new_pos);
+            self.precise_pos = precise_pos;
+            return status;
         }
 
         self.current_pos = new_pos;
@@ -389,6 +427,15 @@ impl<'a> InfiniteScrolledMap<'a> {
         PartialUpdateStatus::Done
     }
 
+    /// Returns the property of the tile at the given position in the world, measured in tiles,
+    /// as attached to its tile set with [`TileSet::with_properties`]. This doesn't need the tile
+    /// to currently be on screen.
+    #[must_use]
+    pub fn tile_property_at(&self, pos: Vector2D<i32>) -> u16 {
+        let (tileset, tile_setting) = (self.tile)(pos);
+        tileset.property(tile_setting.index())
+    }
+
     /// Makes the map visible
     pub fn show(&mut self) {
         self.map.show();
@@ -404,6 +451,12 @@ impl<'a> InfiniteScrolledMap<'a> {
         self.map.commit(vram);
     }
 
+    /// Adds the data to copy to vram to the queue, to be copied when it is
+    /// flushed during vblank.
+    pub fn commit_queued(&mut self, vram: &mut VRamManager, queue: &mut CommitQueue) {
+        self.map.commit_queued(vram, queue);
+    }
+
     /// Clears the underlying map. You must call this before the scrolled map goes out of scope
     /// or you will leak VRam.
     pub fn clear(&mut self, vram: &mut VRamManager) {
@@ -435,3 +488,48 @@ fn div_ceil(x: i32, y: i32) -> i32 {
         x / y
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::tiled::{RegularBackgroundSize, TileFormat};
+    use crate::display::Priority;
+    use crate::fixnum::num;
+
+    static TILES: [u8; 32] = [0; 32];
+
+    #[test_case]
+    fn scroll_pos_keeps_the_fractional_part(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+        let tile_set = TileSet::new(&TILES, TileFormat::FourBpp);
+
+        let mut map = InfiniteScrolledMap::new(
+            gfx.background(Priority::P0, RegularBackgroundSize::Background32x32),
+            Box::new(|_| (&tile_set, TileSetting::new(0, false, false, 0))),
+        );
+        map.init(&mut vram, (0, 0).into(), &mut || {});
+
+        let pos: Vector2D<Num<i32, 8>> = (num!(1.5), num!(2.25)).into();
+        while map.set_scroll_pos(&mut vram, pos) != PartialUpdateStatus::Done {}
+        assert_eq!(map.scroll_pos(), pos);
+        assert_eq!(map.map.scroll_pos(), (1i16, 2i16).into());
+
+        let pos = pos + (num!(0.75), num!(0.)).into();
+        while map.set_scroll_pos(&mut vram, pos) != PartialUpdateStatus::Done {}
+        assert_eq!(map.scroll_pos(), pos);
+        assert_eq!(map.map.scroll_pos(), (2i16, 2i16).into());
+
+        // jumping a long way starts again from the new position, but keeps the
+        // fractional part
+        let pos: Vector2D<Num<i32, 8>> = (num!(200.75), num!(2.25)).into();
+        while map.set_scroll_pos(&mut vram, pos) != PartialUpdateStatus::Done {}
+        assert_eq!(map.scroll_pos(), pos);
+        assert_eq!(map.map.scroll_pos(), (0i16, 2i16).into());
+
+        while map.set_pos(&mut vram, (3, 4).into()) != PartialUpdateStatus::Done {}
+        assert_eq!(map.scroll_pos(), (3, 4).into());
+
+        map.clear(&mut vram);
+        vram.gc();
+    }
+}
@@ -3,8 +3,11 @@ use core::ops::{Deref, DerefMut};
 
 use crate::bitarray::Bitarray;
 use crate::display::affine::AffineMatrixBackground;
+use crate::display::commit_queue::CommitQueue;
+use crate::display::palette16::PALETTE_BACKGROUND;
+use crate::display::window::{WindowId, Windows};
 use crate::display::{Priority, DISPLAY_CONTROL};
-use crate::dma::dma_copy16;
+use crate::dma::copy_to;
 use crate::fixnum::Vector2D;
 use crate::memory_mapped::MemoryMapped;
 
@@ -15,6 +18,8 @@ use super::{
 
 use alloc::{vec, vec::Vec};
 
+const TILE_RAM_START: usize = 0x0600_0000;
+
 pub trait TiledMapTypes: private::Sealed {
     type Size: BackgroundSize + Copy;
 }
@@ -29,9 +34,11 @@ trait TiledMapPrivate: TiledMapTypes {
     fn background_id(&self) -> usize;
     fn screenblock(&self) -> usize;
     fn priority(&self) -> Priority;
+    fn mosaic(&self) -> bool;
     fn map_size(&self) -> Self::Size;
 
     fn update_bg_registers(&self);
+    fn queue_bg_registers(&self, queue: &mut CommitQueue);
 
     fn bg_control_register(&self) -> MemoryMapped<u16> {
         unsafe { MemoryMapped::new(0x0400_0008 + 2 * self.background_id()) }
@@ -41,6 +48,42 @@ trait TiledMapPrivate: TiledMapTypes {
     }
 }
 
+fn bg_control_value<T>(map: &T) -> u16
+where
+    T: TiledMapPrivate,
+    T::Size: BackgroundSizePrivate,
+{
+    (map.priority() as u16)
+        | ((map.mosaic() as u16) << 6)
+        | ((map.screenblock() as u16) << 8)
+        | (map.map_size().size_flag() << 14)
+}
+
+/// Copies the tiles to the screenblock using `copy` if they have changed since
+/// the last commit.
+fn commit_tiles<T>(map: &mut T, vram: &mut VRamManager, copy: impl FnOnce(&[u16], *mut u16))
+where
+    T: TiledMapPrivate,
+    T::Size: BackgroundSizePrivate,
+{
+    let screenblock_memory = map.screenblock_memory();
+    let x: TileIndex = unsafe { *map.tiles_mut().get_unchecked(0) }.into();
+    let x = x.format().tile_size() / TileFormat::FourBpp.tile_size();
+    if *map.tiles_dirty() {
+        let tiles = unsafe {
+            core::slice::from_raw_parts(
+                map.tiles_mut().as_ptr() as *const u16,
+                map.map_size().num_tiles() / x,
+            )
+        };
+        copy(tiles, screenblock_memory);
+    }
+
+    vram.gc();
+
+    *map.tiles_dirty() = false;
+}
+
 /// Trait which describes methods available on both tiled maps and affine maps. Note that
 /// it is 'sealed' so you cannot implement this yourself.
 pub trait TiledMap: TiledMapTypes {
@@ -48,6 +91,11 @@ pub trait TiledMap: TiledMapTypes {
     fn show(&mut self);
     fn hide(&mut self);
     fn commit(&mut self, vram: &mut VRamManager);
+    /// Does the same as `commit`, except that the registers and any changed
+    /// tiles are added to the queue rather than written straight away. This
+    /// should be called before waiting for vblank, with the queue flushed once
+    /// vblank starts.
+    fn commit_queued(&mut self, vram: &mut VRamManager, queue: &mut CommitQueue);
     fn size(&self) -> Self::Size;
 }
 
@@ -79,29 +127,22 @@ where
     }
 
     fn commit(&mut self, vram: &mut VRamManager) {
-        let new_bg_control_value = (self.priority() as u16)
-            | ((self.screenblock() as u16) << 8)
-            | (self.map_size().size_flag() << 14);
-
-        self.bg_control_register().set(new_bg_control_value);
+        self.bg_control_register().set(bg_control_value(self));
         self.update_bg_registers();
 
-        let screenblock_memory = self.screenblock_memory();
-        let x: TileIndex = unsafe { *self.tiles_mut().get_unchecked(0) }.into();
-        let x = x.format().tile_size() / TileFormat::FourBpp.tile_size();
-        if *self.tiles_dirty() {
-            unsafe {
-                dma_copy16(
-                    self.tiles_mut().as_ptr() as *const u16,
-                    screenblock_memory,
-                    self.map_size().num_tiles() / x,
-                );
-            }
-        }
+        commit_tiles(self, vram, |tiles, screenblock_memory| unsafe {
+            copy_to(tiles.as_ptr(), screenblock_memory, tiles.len());
+        });
+    }
 
-        vram.gc();
+    fn commit_queued(&mut self, vram: &mut VRamManager, queue: &mut CommitQueue) {
+        let bg_control = [bg_control_value(self)];
+        unsafe { queue.copy(&bg_control, self.bg_control_register().as_ptr()) };
+        self.queue_bg_registers(queue);
 
-        *self.tiles_dirty() = false;
+        commit_tiles(self, vram, |tiles, screenblock_memory| unsafe {
+            queue.copy(tiles, screenblock_memory);
+        });
     }
 
     fn size(&self) -> T::Size {
@@ -114,6 +155,7 @@ pub struct RegularMap {
     screenblock: u8,
     priority: Priority,
     size: RegularBackgroundSize,
+    mosaic: bool,
 
     scroll: Vector2D<i16>,
 
@@ -147,6 +189,9 @@ impl TiledMapPrivate for RegularMap {
     fn priority(&self) -> Priority {
         self.priority
     }
+    fn mosaic(&self) -> bool {
+        self.mosaic
+    }
     fn map_size(&self) -> Self::Size {
         self.size
     }
@@ -154,6 +199,11 @@ impl TiledMapPrivate for RegularMap {
         self.x_register().set(self.scroll.x);
         self.y_register().set(self.scroll.y);
     }
+
+    fn queue_bg_registers(&self, queue: &mut CommitQueue) {
+        let scroll = [self.scroll.x as u16, self.scroll.y as u16];
+        unsafe { queue.copy(&scroll, self.x_register().as_ptr().cast()) };
+    }
 }
 
 impl RegularMap {
@@ -168,6 +218,7 @@ impl RegularMap {
             screenblock,
             priority,
             size,
+            mosaic: false,
 
             scroll: Default::default(),
 
@@ -176,6 +227,14 @@ impl RegularMap {
         }
     }
 
+    /// Sets whether the background is pixelated by the mosaic effect, using
+    /// the size set with
+    /// [Mosaic::set_background_size](crate::display::mosaic::Mosaic::set_background_size).
+    /// No change will be seen until the map is committed.
+    pub fn set_mosaic(&mut self, mosaic: bool) {
+        self.mosaic = mosaic;
+    }
+
     pub fn set_tile(
         &mut self,
         vram: &mut VRamManager,
@@ -213,10 +272,129 @@ impl RegularMap {
         self.scroll
     }
 
+    /// Returns the property of the tile at the given position, as attached to its tile set
+    /// with [`TileSet::with_properties`]. Empty tiles and tiles without a property return 0.
+    #[must_use]
+    pub fn tile_property_at(&self, vram: &VRamManager, pos: Vector2D<u16>) -> u16 {
+        let tile = self.tiles[self.map_size().gba_offset(pos)];
+        if tile == Tile::default() {
+            return 0;
+        }
+
+        vram.tile_property(tile.into())
+    }
+
+    /// Takes a snapshot of the tiles and scroll position of this map, which can later be
+    /// put back with [`restore_state`](RegularMap::restore_state). This is useful if you
+    /// want to temporarily use the background for something else, like a pause menu.
+    ///
+    /// The snapshot keeps every tile it uses alive in video RAM, so you must either restore
+    /// it or [`discard`](RegularMapState::discard) it once you are done with it to avoid
+    /// leaking video RAM.
+    #[must_use]
+    pub fn save_state(&self, vram: &mut VRamManager) -> RegularMapState {
+        for &tile in &self.tiles {
+            if tile != Tile::default() {
+                vram.increase_reference(tile.into());
+            }
+        }
+
+        RegularMapState {
+            tiles: self.tiles.clone(),
+            scroll: self.scroll,
+        }
+    }
+
+    /// Restores a snapshot taken with [`save_state`](RegularMap::save_state) and commits it,
+    /// releasing any tiles which were on the map beforehand.
+    pub fn restore_state(&mut self, vram: &mut VRamManager, state: RegularMapState) {
+        assert_eq!(
+            state.tiles.len(),
+            self.tiles.len(),
+            "Can only restore state taken from a map of the same size"
+        );
+
+        self.clear(vram);
+
+        self.tiles = state.tiles;
+        self.scroll = state.scroll;
+        self.tiles_dirty = true;
+
+        self.commit(vram);
+    }
+
     pub fn set_scroll_pos(&mut self, pos: Vector2D<i16>) {
         self.scroll = pos;
     }
 
+    /// Whether this background is currently enabled in the display control register.
+    pub(crate) fn is_shown(&self) -> bool {
+        DISPLAY_CONTROL.get() & (1 << (self.background_id + 0x08)) != 0
+    }
+
+    /// The priority used to order this background against the others.
+    pub(crate) fn display_priority(&self) -> (Priority, u8) {
+        (self.priority, self.background_id)
+    }
+
+    /// The colour this map draws at the given screen position, read from the
+    /// tiles in video RAM and the background palettes. The character block and
+    /// colour mode are read from the control register, so this also works if
+    /// they have been changed there. Returns [None] for transparent pixels.
+    pub(crate) fn screen_colour(&self, x: i32, y: i32) -> Option<u16> {
+        let x = self.map_size().px_offset_x(x + i32::from(self.scroll.x));
+        let y = self.map_size().px_offset_y(y + i32::from(self.scroll.y));
+
+        let tile = self.tiles[self.map_size().gba_offset((x / 8, y / 8).into())];
+        let setting = tile.0;
+
+        let mut pixel_x = x % 8;
+        let mut pixel_y = y % 8;
+        if setting & (1 << 10) != 0 {
+            pixel_x = 7 - pixel_x;
+        }
+        if setting & (1 << 11) != 0 {
+            pixel_y = 7 - pixel_y;
+        }
+
+        let control = self.bg_control_register().get();
+        let character_block = TILE_RAM_START + usize::from((control >> 2) & 0b11) * 0x4000;
+        let format = if control & (1 << 7) != 0 {
+            TileFormat::EightBpp
+        } else {
+            TileFormat::FourBpp
+        };
+
+        let tile_data =
+            character_block + usize::from(setting & ((1 << 10) - 1)) * format.tile_size();
+
+        let palette_index = match format {
+            TileFormat::FourBpp => {
+                let byte = unsafe {
+                    ((tile_data + usize::from(pixel_y * 4 + pixel_x / 2)) as *const u8)
+                        .read_volatile()
+                };
+                let colour_index = (byte >> ((pixel_x % 2) * 4)) & 0xf;
+
+                (colour_index != 0)
+                    .then(|| usize::from(setting >> 12) * 16 + usize::from(colour_index))
+            }
+            TileFormat::EightBpp => {
+                let byte = unsafe {
+                    ((tile_data + usize::from(pixel_y * 8 + pixel_x)) as *const u8).read_volatile()
+                };
+
+                (byte != 0).then(|| usize::from(byte))
+            }
+        }?;
+
+        Some(unsafe {
+            (PALETTE_BACKGROUND as *const u16)
+                .add(palette_index)
+                .read_volatile()
+        })
+    }
+
     fn x_register(&self) -> MemoryMapped<i16> {
         unsafe { MemoryMapped::new(0x0400_0010 + 4 * self.background_id as usize) }
     }
@@ -226,11 +404,30 @@ impl RegularMap {
     }
 }
 
+/// A snapshot of the tiles and scroll position of a [`RegularMap`], created by
+/// [`RegularMap::save_state`].
+pub struct RegularMapState {
+    tiles: Vec<Tile>,
+    scroll: Vector2D<i16>,
+}
+
+impl RegularMapState {
+    /// Releases the tiles held by this snapshot without restoring it.
+    pub fn discard(self, vram: &mut VRamManager) {
+        for tile in self.tiles {
+            if tile != Tile::default() {
+                vram.remove_tile(tile.into());
+            }
+        }
+    }
+}
+
 pub struct AffineMap {
     background_id: u8,
     screenblock: u8,
     priority: Priority,
     size: AffineBackgroundSize,
+    mosaic: bool,
 
     transform: AffineMatrixBackground,
 
@@ -261,12 +458,19 @@ impl TiledMapPrivate for AffineMap {
     fn priority(&self) -> Priority {
         self.priority
     }
+    fn mosaic(&self) -> bool {
+        self.mosaic
+    }
     fn map_size(&self) -> Self::Size {
         self.size
     }
     fn update_bg_registers(&self) {
         self.bg_affine_matrix().set(self.transform);
     }
+
+    fn queue_bg_registers(&self, queue: &mut CommitQueue) {
+        unsafe { queue.copy_value(&self.transform, self.bg_affine_matrix().as_ptr()) };
+    }
 }
 
 impl AffineMap {
@@ -281,6 +485,7 @@ impl AffineMap {
             screenblock,
             priority,
             size,
+            mosaic: false,
 
             transform: Default::default(),
 
@@ -289,6 +494,14 @@ impl AffineMap {
         }
     }
 
+    /// Sets whether the background is pixelated by the mosaic effect, using
+    /// the size set with
+    /// [Mosaic::set_background_size](crate::display::mosaic::Mosaic::set_background_size).
+    /// No change will be seen until the map is committed.
+    pub fn set_mosaic(&mut self, mosaic: bool) {
+        self.mosaic = mosaic;
+    }
+
     pub fn set_tile(
         &mut self,
         vram: &mut VRamManager,
@@ -376,6 +589,15 @@ impl<'a, T> MapLoan<'a, T> {
     pub const fn background(&self) -> BackgroundID {
         BackgroundID(self.background_id)
     }
+
+    /// Sets whether this background will be rendered inside the given window.
+    /// This updates the state held by `windows`, so you must call
+    /// [`Windows::commit`] for this change to be seen.
+    pub fn set_window(&self, windows: &mut Windows, window: WindowId, enable: bool) {
+        windows
+            .window(window)
+            .set_background_enable(self.background(), enable);
+    }
 }
 
 impl<'a, T> Drop for MapLoan<'a, T> {
@@ -398,3 +620,105 @@ mod private {
     impl Sealed for super::RegularMap {}
     impl Sealed for super::AffineMap {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::tiled::TileSetting;
+
+    #[test_case]
+    fn restoring_state_brings_back_tiles_and_scroll(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+        let mut map = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+
+        let dynamic_tile = vram.new_dynamic_tile().fill_with(1);
+        map.set_tile(
+            &mut vram,
+            (0u16, 0u16).into(),
+            &dynamic_tile.tile_set(),
+            TileSetting::from_raw(dynamic_tile.tile_index()),
+        );
+        map.set_scroll_pos((3i16, 4i16).into());
+        map.commit(&mut vram);
+
+        let tile = map.tiles[0];
+        let state = map.save_state(&mut vram);
+
+        map.clear(&mut vram);
+        map.set_scroll_pos((0i16, 0i16).into());
+        map.commit(&mut vram);
+
+        map.restore_state(&mut vram, state);
+
+        assert_eq!(map.tiles[0], tile);
+        assert_eq!(map.scroll_pos(), (3i16, 4i16).into());
+
+        map.clear(&mut vram);
+        vram.remove_dynamic_tile(dynamic_tile);
+        vram.gc();
+    }
+
+    #[test_case]
+    fn tile_properties_come_from_the_tile_set(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+        let mut map = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+
+        let tiles = [0; 3 * 32];
+        let tile_set = TileSet::new(&tiles, TileFormat::FourBpp).with_properties(&[0, 5]);
+
+        map.set_tile(
+            &mut vram,
+            (0u16, 0u16).into(),
+            &tile_set,
+            TileSetting::new(1, false, false, 0),
+        );
+        map.set_tile(
+            &mut vram,
+            (1u16, 0u16).into(),
+            &tile_set,
+            TileSetting::new(2, false, false, 0),
+        );
+
+        assert_eq!(map.tile_property_at(&vram, (0u16, 0u16).into()), 5);
+        assert_eq!(map.tile_property_at(&vram, (1u16, 0u16).into()), 0);
+        assert_eq!(map.tile_property_at(&vram, (2u16, 0u16).into()), 0);
+
+        map.clear(&mut vram);
+        vram.gc();
+    }
+
+    #[test_case]
+    fn queued_commits_copy_tiles_once_flushed(gba: &mut crate::Gba) {
+        let (gfx, mut vram) = gba.display.video.tiled0();
+        let mut map = gfx.background(Priority::P0, RegularBackgroundSize::Background32x32);
+        let mut queue = CommitQueue::new();
+
+        let dynamic_tile = vram.new_dynamic_tile().fill_with(1);
+        map.set_tile(
+            &mut vram,
+            (0u16, 0u16).into(),
+            &dynamic_tile.tile_set(),
+            TileSetting::from_raw(dynamic_tile.tile_index()),
+        );
+
+        let screenblock_memory = map.screenblock_memory();
+        unsafe { screenblock_memory.write_volatile(0x1234) };
+
+        map.commit_queued(&mut vram, &mut queue);
+        assert!(!queue.is_empty());
+        assert_eq!(unsafe { screenblock_memory.read_volatile() }, 0x1234);
+
+        queue.flush();
+        let tile = unsafe { *(map.tiles.as_ptr() as *const u16) };
+        assert_eq!(unsafe { screenblock_memory.read_volatile() }, tile);
+
+        // the tiles haven't changed, so only the registers are queued again
+        map.commit_queued(&mut vram, &mut queue);
+        assert_eq!(queue.bytes(), 2 + 4);
+        queue.clear();
+
+        map.clear(&mut vram);
+        vram.remove_dynamic_tile(dynamic_tile);
+        vram.gc();
+    }
+}
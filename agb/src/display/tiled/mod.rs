@@ -10,11 +10,13 @@ use crate::display::Priority;
 use agb_fixnum::Vector2D;
 use core::cell::RefCell;
 pub use infinite_scrolled_map::{InfiniteScrolledMap, PartialUpdateStatus};
-pub use map::{AffineMap, MapLoan, RegularMap, TiledMap};
+pub use map::{AffineMap, MapLoan, RegularMap, RegularMapState, TiledMap};
 pub use tiled0::Tiled0;
 pub use tiled1::Tiled1;
 pub use tiled2::Tiled2;
-pub use vram_manager::{DynamicTile, TileFormat, TileIndex, TileSet, VRamManager};
+pub use vram_manager::{
+    CompressedTileSet, DynamicTile, TileFormat, TileIndex, TileSet, VRamManager,
+};
 
 // affine layers start at BG2
 pub(crate) const AFFINE_BG_ID_OFFSET: usize = 2;
@@ -348,4 +350,17 @@ mod test {
             assert_eq!(size.tile_pos_x(-17 - width * 8), (size.width() - 17) as u16);
         }
     }
+
+    #[test_case]
+    fn tiled1_has_2_regular_and_1_affine_background(gba: &mut crate::Gba) {
+        let (gfx, _vram) = gba.display.video.tiled1();
+
+        let bg0 = gfx.regular(Priority::P0, RegularBackgroundSize::Background32x32);
+        let bg1 = gfx.regular(Priority::P0, RegularBackgroundSize::Background32x32);
+        let bg2 = gfx.affine(Priority::P0, AffineBackgroundSize::Background16x16);
+
+        assert_eq!(bg0.background(), BackgroundID(0));
+        assert_eq!(bg1.background(), BackgroundID(1));
+        assert_eq!(bg2.background(), BackgroundID(2));
+    }
 }
@@ -9,6 +9,10 @@ use crate::{
     display::{set_graphics_mode, DisplayMode, Priority},
 };
 
+/// Tiled mode 0, which provides 4 regular backgrounds (BG0 to BG3).
+///
+/// Use [`background()`](Tiled0::background) to create [`RegularMap`]s. Affine backgrounds
+/// are not available in this mode.
 pub struct Tiled0 {
     regular: RefCell<Bitarray<1>>,
     screenblocks: RefCell<Bitarray<1>>,
@@ -1,11 +1,19 @@
 use core::cell::RefCell;
 
-use super::{CreatableAffineTiledMode, CreatableRegularTiledMode, TiledMode};
+use super::{
+    AffineBackgroundSize, AffineMap, AffineTiledMode, CreatableAffineTiledMode,
+    CreatableRegularTiledMode, MapLoan, RegularBackgroundSize, RegularMap, RegularTiledMode,
+    TiledMode,
+};
 use crate::{
     bitarray::Bitarray,
-    display::{set_graphics_mode, tiled::AFFINE_BG_ID_OFFSET, DisplayMode},
+    display::{set_graphics_mode, tiled::AFFINE_BG_ID_OFFSET, DisplayMode, Priority},
 };
 
+/// Tiled mode 1, which provides 2 regular backgrounds (BG0 and BG1) and 1 affine background (BG2).
+///
+/// Regular backgrounds are created with [`regular()`](Tiled1::regular) and the affine background
+/// with [`affine()`](Tiled1::affine), so it is not possible to put the wrong kind of map in a slot.
 pub struct Tiled1 {
     regular: RefCell<Bitarray<1>>,
     affine: RefCell<Bitarray<1>>,
@@ -27,6 +35,20 @@ impl Tiled1 {
             screenblocks: Default::default(),
         }
     }
+
+    /// Creates one of the 2 regular backgrounds available in this mode (BG0 or BG1).
+    pub fn regular(
+        &self,
+        priority: Priority,
+        size: RegularBackgroundSize,
+    ) -> MapLoan<'_, RegularMap> {
+        self.regular_background(priority, size)
+    }
+
+    /// Creates the single affine background available in this mode (BG2).
+    pub fn affine(&self, priority: Priority, size: AffineBackgroundSize) -> MapLoan<'_, AffineMap> {
+        self.affine_background(priority, size)
+    }
 }
 
 impl TiledMode for Tiled1 {
@@ -8,6 +8,10 @@ use crate::{
     display::{set_graphics_mode, tiled::AFFINE_BG_ID_OFFSET, DisplayMode, Priority},
 };
 
+/// Tiled mode 2, which provides 2 affine backgrounds (BG2 and BG3).
+///
+/// Use [`background()`](Tiled2::background) to create [`AffineMap`]s. Regular backgrounds
+/// are not available in this mode.
 pub struct Tiled2 {
     affine: RefCell<Bitarray<1>>,
     screenblocks: RefCell<Bitarray<1>>,
@@ -6,9 +6,10 @@ use crate::display::tiled::Tile;
 use crate::{
     agb_alloc::{block_allocator::BlockAllocator, bump_allocator::StartEnd},
     display::palette16,
-    dma::dma_copy16,
+    dma::copy_to,
     hash_map::HashMap,
     memory_mapped::MemoryMapped1DArray,
+    syscall,
 };
 
 const TILE_RAM_START: usize = 0x0600_0000;
@@ -46,12 +47,36 @@ impl TileFormat {
 pub struct TileSet<'a> {
     tiles: &'a [u8],
     format: TileFormat,
+    properties: &'a [u16],
 }
 
 impl<'a> TileSet<'a> {
     #[must_use]
     pub fn new(tiles: &'a [u8], format: TileFormat) -> Self {
-        Self { tiles, format }
+        Self {
+            tiles,
+            format,
+            properties: &[],
+        }
+    }
+
+    /// Attaches a property to each tile in the tile set, for example whether the tile
+    /// is solid, a spike or water. These can be queried later with
+    /// [`RegularMap::tile_property_at`](super::RegularMap::tile_property_at) or
+    /// [`InfiniteScrolledMap::tile_property_at`](super::InfiniteScrolledMap::tile_property_at)
+    /// so that collision code can share the data used for rendering.
+    #[must_use]
+    pub fn with_properties(self, properties: &'a [u16]) -> Self {
+        Self { properties, ..self }
+    }
+
+    /// Returns the property of the given tile, or 0 if it doesn't have one.
+    #[must_use]
+    pub fn property(&self, tile_id: u16) -> u16 {
+        self.properties
+            .get(tile_id as usize)
+            .copied()
+            .unwrap_or_default()
     }
 
     fn reference(&self) -> NonNull<[u8]> {
@@ -59,6 +84,56 @@ impl<'a> TileSet<'a> {
     }
 }
 
+/// A tile set compressed in one of the formats understood by the GBA BIOS, either
+/// LZ77 or run length encoding. The compression format is read from the header
+/// of the data, which is the same format produced by `include_gfx!` when
+/// `compression` is set for the image.
+///
+/// Use [`VRamManager::add_tileset_compressed`] to decompress it into video RAM.
+pub struct CompressedTileSet<'a> {
+    data: &'a [u8],
+    format: TileFormat,
+    properties: &'static [u16],
+}
+
+impl<'a> CompressedTileSet<'a> {
+    /// Creates a new compressed tile set. The data must be 4 byte aligned.
+    #[must_use]
+    pub fn new(data: &'a [u8], format: TileFormat) -> Self {
+        assert_eq!(
+            data.as_ptr() as usize % 4,
+            0,
+            "Compressed tile data must be 4 byte aligned"
+        );
+        assert!(
+            data.len() >= 4,
+            "Compressed tile data must contain a header"
+        );
+
+        Self {
+            data,
+            format,
+            properties: &[],
+        }
+    }
+
+    /// Attaches a property to each tile, which are kept by the tile set
+    /// returned from [`VRamManager::add_tileset_compressed`]. See
+    /// [`TileSet::with_properties`].
+    #[must_use]
+    pub fn with_properties(self, properties: &'static [u16]) -> Self {
+        Self { properties, ..self }
+    }
+
+    fn header(&self) -> u32 {
+        u32::from_le_bytes([self.data[0], self.data[1], self.data[2], self.data[3]])
+    }
+
+    fn decompressed_size(&self) -> usize {
+        (self.header() >> 8) as usize
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum TileIndex {
     FourBpp(u16),
@@ -128,13 +203,15 @@ impl TileInTileSetReference {
 #[derive(Clone, Default)]
 struct TileReferenceCount {
     reference_count: u16,
+    property: u16,
     tile_in_tile_set: Option<TileInTileSetReference>,
 }
 
 impl TileReferenceCount {
-    fn new(tile_in_tile_set: TileInTileSetReference) -> Self {
+    fn new(tile_in_tile_set: TileInTileSetReference, property: u16) -> Self {
         Self {
             reference_count: 1,
+            property,
             tile_in_tile_set: Some(tile_in_tile_set),
         }
     }
@@ -155,6 +232,7 @@ impl TileReferenceCount {
 
     fn clear(&mut self) {
         self.reference_count = 0;
+        self.property = 0;
         self.tile_in_tile_set = None;
     }
 
@@ -258,7 +336,7 @@ impl VRamManager {
         self.reference_counts
             .resize(self.reference_counts.len().max(key + 1), Default::default());
         self.reference_counts[key] =
-            TileReferenceCount::new(TileInTileSetReference::new(&tile_set, index.raw_index()));
+            TileReferenceCount::new(TileInTileSetReference::new(&tile_set, index.raw_index()), 0);
 
         DynamicTile {
             tile_data: unsafe {
@@ -284,6 +362,87 @@ impl VRamManager {
         self.remove_tile(tile_index);
     }
 
+    /// Decompresses the tile set directly into video RAM using the BIOS, without
+    /// needing to stage the decompressed data anywhere else first. Returns a tile set
+    /// which can be used with [`RegularMap::set_tile`](super::RegularMap::set_tile)
+    /// as normal, and will not copy the tiles again.
+    ///
+    /// Once you are done with the tile set, pass it to
+    /// [`remove_tileset_compressed`](VRamManager::remove_tileset_compressed) to free
+    /// the video RAM it uses.
+    pub fn add_tileset_compressed(
+        &mut self,
+        compressed: &CompressedTileSet<'_>,
+    ) -> TileSet<'static> {
+        let format = compressed.format;
+        let size = compressed.decompressed_size();
+        assert_eq!(
+            size % format.tile_size(),
+            0,
+            "Decompressed tile data must be a whole number of tiles"
+        );
+
+        let layout = Layout::from_size_align(size, format.tile_size()).unwrap();
+        let location = unsafe { TILE_ALLOCATOR.alloc(layout) }
+            .expect("Not enough video RAM for compressed tile set");
+
+        let src = compressed.data.as_ptr().cast();
+        let dest = location.as_ptr().cast();
+        unsafe {
+            match compressed.header() & 0xF0 {
+                0x10 => syscall::lz77_uncompress_vram(src, dest),
+                0x30 => syscall::rl_uncompress_vram(src, dest),
+                _ => panic!("Unsupported compression format for tile set"),
+            }
+        }
+
+        let tiles = unsafe { slice::from_raw_parts(location.as_ptr(), size) };
+        let tile_set = TileSet::new(tiles, format).with_properties(compressed.properties);
+
+        for tile in 0..size / format.tile_size() {
+            let tile_reference = TileReference(
+                NonNull::new(unsafe { location.as_ptr().add(tile * format.tile_size()) })
+                    .unwrap()
+                    .cast(),
+            );
+
+            let index = Self::index_from_reference(tile_reference, format);
+            let key = index.refcount_key();
+
+            self.tile_set_to_vram.insert(
+                TileInTileSetReference::new(&tile_set, tile as u16),
+                tile_reference,
+            );
+
+            self.reference_counts
+                .resize(self.reference_counts.len().max(key + 1), Default::default());
+            self.reference_counts[key] = TileReferenceCount::new(
+                TileInTileSetReference::new(&tile_set, tile as u16),
+                tile_set.property(tile as u16),
+            );
+        }
+
+        tile_set
+    }
+
+    /// Frees the video RAM used by a tile set returned from
+    /// [`add_tileset_compressed`](VRamManager::add_tileset_compressed). Tiles which are
+    /// still in use by a background will be freed once they are no longer used.
+    // This needs to take ownership of the tile set because it will no longer be valid after this call
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn remove_tileset_compressed(&mut self, tile_set: TileSet<'_>) {
+        let format = tile_set.format;
+
+        for tile in 0..tile_set.tiles.len() / format.tile_size() {
+            let pointer =
+                NonNull::new(tile_set.tiles[tile * format.tile_size()..].as_ptr() as *mut u32)
+                    .unwrap();
+
+            let tile_index = Self::index_from_reference(TileReference(pointer), format);
+            self.remove_tile(tile_index);
+        }
+    }
+
     pub(crate) fn add_tile(&mut self, tile_set: &TileSet<'_>, tile: u16) -> TileIndex {
         let reference = self
             .tile_set_to_vram
@@ -307,18 +466,37 @@ impl VRamManager {
         let index = Self::index_from_reference(tile_reference, tile_set.format);
         let key = index.refcount_key();
 
+        crate::trace!(
+            "copied tile {} of a tile set to vram tile {}",
+            tile,
+            index.raw_index()
+        );
+
         self.tile_set_to_vram
             .insert(TileInTileSetReference::new(tile_set, tile), tile_reference);
 
         self.reference_counts
             .resize(self.reference_counts.len().max(key + 1), Default::default());
 
-        self.reference_counts[key] =
-            TileReferenceCount::new(TileInTileSetReference::new(tile_set, tile));
+        self.reference_counts[key] = TileReferenceCount::new(
+            TileInTileSetReference::new(tile_set, tile),
+            tile_set.property(tile),
+        );
 
         index
     }
 
+    pub(crate) fn tile_property(&self, tile_index: TileIndex) -> u16 {
+        self.reference_counts
+            .get(tile_index.refcount_key())
+            .map_or(0, |reference_count| reference_count.property)
+    }
+
+    pub(crate) fn increase_reference(&mut self, tile_index: TileIndex) {
+        let key = tile_index.refcount_key();
+        self.reference_counts[key].increment_reference_count();
+    }
+
     pub(crate) fn remove_tile(&mut self, tile_index: TileIndex) {
         let key = tile_index.refcount_key();
 
@@ -332,12 +510,16 @@ impl VRamManager {
     }
 
     pub(crate) fn gc(&mut self) {
+        let mut freed = 0;
+
         for tile_index in self.indices_to_gc.drain(..) {
             let key = tile_index.refcount_key();
             if self.reference_counts[key].current_count() > 0 {
                 continue; // it has since been added back
             }
 
+            freed += 1;
+
             let tile_reference = Self::reference_from_index(tile_index);
             unsafe {
                 TILE_ALLOCATOR.dealloc_no_normalise(
@@ -354,6 +536,10 @@ impl VRamManager {
             self.tile_set_to_vram.remove(tile_ref);
             self.reference_counts[key].clear();
         }
+
+        if freed > 0 {
+            crate::trace!("freed {} vram tiles", freed);
+        }
     }
 
     pub fn replace_tile(
@@ -391,7 +577,7 @@ impl VRamManager {
         let target_location = tile_reference.0.as_ptr() as *mut _;
 
         unsafe {
-            dma_copy16(
+            copy_to(
                 tile_slice.as_ptr() as *const u16,
                 target_location,
                 tile_size_in_half_words,
@@ -402,7 +588,7 @@ impl VRamManager {
     /// Copies raw palettes to the background palette without any checks.
     pub fn set_background_palette_raw(&mut self, palette: &[u16]) {
         unsafe {
-            dma_copy16(palette.as_ptr(), PALETTE_BACKGROUND.as_ptr(), palette.len());
+            copy_to(palette.as_ptr(), PALETTE_BACKGROUND.as_ptr(), palette.len());
         }
     }
 
@@ -1,6 +1,7 @@
 use super::{
     bitmap3::Bitmap3,
     bitmap4::Bitmap4,
+    bitmap5::Bitmap5,
     tiled::{Tiled0, Tiled1, Tiled2, VRamManager},
 };
 
@@ -22,6 +23,12 @@ impl Video {
         unsafe { Bitmap4::new() }
     }
 
+    /// Bitmap 5 provides two 160x128 16-bit colour framebuffers with page
+    /// switching, which can be stretched to fill the screen
+    pub fn bitmap5(&mut self) -> Bitmap5 {
+        unsafe { Bitmap5::new() }
+    }
+
     /// Tiled 0 mode provides 4 regular, tiled backgrounds
     pub fn tiled0(&mut self) -> (Tiled0, VRamManager) {
         (unsafe { Tiled0::new() }, VRamManager::new())
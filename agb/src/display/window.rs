@@ -26,6 +26,19 @@ pub enum WinIn {
     Win1,
 }
 
+/// Identifies any of the four windows managed by [`Windows`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowId {
+    /// The higher priority movable window
+    Win0,
+    /// The lower priority movable window
+    Win1,
+    /// The window that is used when outside all other windows
+    WinOut,
+    /// The window that is controlled by sprites and objects
+    WinObj,
+}
+
 impl Windows {
     pub(crate) fn new() -> Self {
         let s = Self {
@@ -49,12 +62,50 @@ impl Windows {
         &mut self.wins[id as usize]
     }
 
-    /// Gives a reference to the window that is controlled by sprites and objects
+    /// Gives a reference to the window that is controlled by sprites and objects.
+    /// The inside of this window is made up of the non transparent pixels of
+    /// every object using
+    /// [GraphicsMode::Window](crate::display::object::GraphicsMode::Window),
+    /// so objects can be used as masks with any shape.
+    ///
+    /// ```rust,no_run
+    /// # #![no_std]
+    /// # #![no_main]
+    /// # use agb::display::object::{Graphics, GraphicsMode, Tag};
+    /// # fn foo(gba: &mut agb::Gba) {
+    /// # const GRAPHICS: &Graphics = agb::include_aseprite!("examples/gfx/objects.aseprite");
+    /// # const LIGHT: &Tag = GRAPHICS.tags().get("emu-walk");
+    /// let object = gba.display.object.get();
+    /// let mut window = gba.display.window.get();
+    ///
+    /// // only display what is under the light, everything else is black
+    /// let mut light = object.object(object.sprite(LIGHT.sprite(0)));
+    /// light.set_graphics_mode(GraphicsMode::Window).show();
+    ///
+    /// window.win_obj().enable().set_object_enable(true);
+    /// window.win_out().enable();
+    ///
+    /// object.commit();
+    /// window.commit();
+    /// # }
+    /// ```
     #[inline(always)]
     pub fn win_obj(&mut self) -> &mut Window {
         &mut self.obj
     }
 
+    /// Gives a reference to the window with the given id. For the movable
+    /// windows, this only gives access to the settings shared by all windows.
+    #[inline(always)]
+    pub fn window(&mut self, id: WindowId) -> &mut Window {
+        match id {
+            WindowId::Win0 => &mut self.wins[0].inner,
+            WindowId::Win1 => &mut self.wins[1].inner,
+            WindowId::WinOut => &mut self.out,
+            WindowId::WinObj => &mut self.obj,
+        }
+    }
+
     /// Commits the state of the windows as dictated by the various functions to
     /// modify them. This should be done during vblank shortly after the wait
     /// for next vblank call.
@@ -247,17 +298,45 @@ impl MovableWindow {
         self
     }
 
-    /// Sets the position of the area that is inside the window.
+    /// Sets the position of the area that is inside the window. Any part of
+    /// the area which is off the screen is ignored.
     #[inline(always)]
     pub fn set_position(&mut self, rect: &Rect<i32>) -> &mut Self {
+        let left = rect.position.x.clamp(0, WIDTH);
+        let right = (rect.position.x + rect.size.x).clamp(left, WIDTH);
+        let top = rect.position.y.clamp(0, HEIGHT);
+        let bottom = (rect.position.y + rect.size.y).clamp(top, HEIGHT);
+
         let new_rect = Rect::new(
-            (
-                rect.position.x.clamp(0, WIDTH) as u8,
-                rect.position.y.clamp(0, HEIGHT) as u8,
-            )
-                .into(),
-            (rect.size.x as u8, rect.size.y as u8).into(),
+            (left as u8, top as u8).into(),
+            ((right - left) as u8, (bottom - top) as u8).into(),
         );
         self.set_position_u8(new_rect)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn window_positions_are_clipped_to_the_screen(gba: &mut crate::Gba) {
+        let mut windows = gba.display.window.get();
+
+        windows
+            .win_in(WinIn::Win0)
+            .set_position(&Rect::new((-20, 150).into(), (50, 40).into()));
+        assert_eq!(
+            windows.wins[0].rect,
+            Rect::new((0, 150).into(), (30, 10).into())
+        );
+
+        windows
+            .win_in(WinIn::Win1)
+            .set_position(&Rect::new((230, -50).into(), (30, 20).into()));
+        assert_eq!(
+            windows.wins[1].rect,
+            Rect::new((230, 0).into(), (10, 0).into())
+        );
+    }
+}
@@ -1,4 +1,36 @@
+#![deny(missing_docs)]
+//! Copying and filling memory quickly using direct memory access (DMA).
+//!
+//! DMA copies memory while the CPU is paused, which is much faster than a
+//! loop, especially when copying to video memory. [`copy`] and [`fill`] use
+//! DMA channel 3, and move 32 bits at a time whenever both ends are aligned
+//! to 4 bytes, falling back to 16 bits at a time otherwise. Only `u16` and
+//! `u32` slices can be used, as DMA can't copy single bytes.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! # fn foo() {
+//! let source = [0x1234u16; 64];
+//! let mut destination = [0u16; 64];
+//!
+//! agb::dma::copy(&source, &mut destination);
+//! agb::dma::fill(&mut destination, 0);
+//! # }
+//! ```
+//!
+//! While a transfer is running, the CPU doesn't run, so interrupts are
+//! delayed until it finishes. Very large transfers can delay interrupts by
+//! long enough to cause problems, for example with sound. Interrupts are
+//! disabled while a transfer is being set up, so that an interrupt handler
+//! which also uses DMA channel 3 can't change its registers half way through.
+//! Channels 0, 1 and 2 have a higher priority, so the [mixer's](crate::sound)
+//! transfers and [raster effects](crate::display::raster) pause a transfer on
+//! channel 3 rather than being delayed by it.
+
+use crate::interrupt::{add_interrupt_handler, Interrupt, InterruptHandler};
 use crate::memory_mapped::MemoryMapped;
+use crate::sync::Static;
 
 const fn dma_source_addr(dma: usize) -> usize {
     0x0400_00b0 + 0x0c * dma
@@ -25,6 +57,380 @@ pub(crate) unsafe fn dma_copy16(src: *const u16, dest: *mut u16, count: usize) {
     DMA3_CONTROL.set(count as u32 | (1 << 31));
 }
 
+const DMA_ENABLE: u32 = 1 << 31;
+const DMA_32_BIT: u32 = 1 << 26;
+const DMA_SOURCE_FIXED: u32 = 2 << 23;
+/// The most units DMA3 can move in one transfer is 0x10000, this is kept a
+/// power of two below it so that chunks stay aligned.
+const DMA3_MAX_CHUNK: usize = 0x8000;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+/// The types which can be copied with DMA, which are `u16` and `u32`.
+pub trait Transfer: Copy + Send + Sync + sealed::Sealed {
+    #[doc(hidden)]
+    fn fill_word(self) -> u32;
+}
+
+impl Transfer for u16 {
+    fn fill_word(self) -> u32 {
+        u32::from(self) | (u32::from(self) << 16)
+    }
+}
+
+impl Transfer for u32 {
+    fn fill_word(self) -> u32 {
+        self
+    }
+}
+
+/// Starts DMA3 with interrupts disabled and waits for it to finish, which
+/// happens straight away as the CPU is paused during the transfer.
+///
+/// While channel 3 is being used by [`HBlankDma`], the CPU does the transfer
+/// instead so that the effect isn't stopped.
+unsafe fn dma3_transfer(src: *const u8, dest: *mut u8, bytes: usize, flags: u32) {
+    let unit = if flags & DMA_32_BIT != 0 { 4 } else { 2 };
+
+    let source_fixed = flags & DMA_SOURCE_FIXED != 0;
+
+    if DmaChannel::Dma3.is_reserved() {
+        if unit == 4 {
+            cpu_transfer::<u32>(src.cast(), dest.cast(), bytes / 4, source_fixed);
+        } else {
+            cpu_transfer::<u16>(src.cast(), dest.cast(), bytes / 2, source_fixed);
+        }
+        return;
+    }
+
+    let mut done = 0;
+
+    while done < bytes {
+        let chunk = (bytes - done).min(DMA3_MAX_CHUNK * unit);
+        let src = if source_fixed { src } else { src.add(done) };
+
+        crate::interrupt::free(|_| {
+            DMA3_SOURCE_ADDR.set(src as u32);
+            DMA3_DEST_ADDR.set(dest.add(done) as u32);
+            DMA3_CONTROL.set((chunk / unit) as u32 | flags | DMA_ENABLE);
+        });
+
+        done += chunk;
+    }
+}
+
+unsafe fn cpu_transfer<T: Copy>(src: *const T, dest: *mut T, count: usize, source_fixed: bool) {
+    for i in 0..count {
+        let src = if source_fixed { src } else { src.add(i) };
+        dest.add(i).write_volatile(src.read_volatile());
+    }
+}
+
+fn can_use_32_bit(src: *const u8, dest: *const u8, bytes: usize) -> bool {
+    (src as usize | dest as usize | bytes) & 3 == 0
+}
+
+/// Copies `count` values starting at `src` to `dest` using DMA3, 32 bits at a
+/// time if everything is aligned to 4 bytes.
+///
+/// # Safety
+///
+/// Both ranges must be valid and mustn't overlap. Unlike
+/// [`core::ptr::copy_nonoverlapping`], `dest` can be in video memory, which
+/// can't be written a byte at a time.
+pub(crate) unsafe fn copy_to<T: Transfer>(src: *const T, dest: *mut T, count: usize) {
+    let bytes = count * core::mem::size_of::<T>();
+    let (src, dest) = (src.cast::<u8>(), dest.cast::<u8>());
+
+    if can_use_32_bit(src, dest, bytes) {
+        dma3_transfer(src, dest, bytes, DMA_32_BIT);
+    } else if can_use_32_bit(src, dest, bytes & !3) && bytes > 2 {
+        // a u16 slice with an odd length, so copy all but the last halfword
+        // 32 bits at a time
+        dma3_transfer(src, dest, bytes & !3, DMA_32_BIT);
+        dma3_transfer(src.add(bytes & !3), dest.add(bytes & !3), 2, 0);
+    } else {
+        dma3_transfer(src, dest, bytes, 0);
+    }
+}
+
+/// Sets `count` values starting at `dest` to `value` using DMA3.
+///
+/// # Safety
+///
+/// The range must be valid for writes.
+pub(crate) unsafe fn fill_to<T: Transfer>(dest: *mut T, value: T, count: usize) {
+    let bytes = count * core::mem::size_of::<T>();
+    let dest = dest.cast::<u8>();
+
+    // DMA reads the source on every transfer, so it needs to be in memory
+    let word = value.fill_word();
+    let src = (&word as *const u32).cast::<u8>();
+
+    if dest as usize & 3 == 0 {
+        dma3_transfer(src, dest, bytes & !3, DMA_32_BIT | DMA_SOURCE_FIXED);
+        if bytes & 3 != 0 {
+            dma3_transfer(src, dest.add(bytes & !3), 2, DMA_SOURCE_FIXED);
+        }
+    } else {
+        dma3_transfer(src, dest, bytes, DMA_SOURCE_FIXED);
+    }
+}
+
+/// Copies `src` into `dest` using DMA.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+pub fn copy<T: Transfer>(src: &[T], dest: &mut [T]) {
+    assert_eq!(
+        src.len(),
+        dest.len(),
+        "source and destination must have the same length"
+    );
+
+    unsafe { copy_to(src.as_ptr(), dest.as_mut_ptr(), src.len()) };
+}
+
+/// Sets every element of `dest` to `value` using DMA.
+pub fn fill<T: Transfer>(dest: &mut [T], value: T) {
+    unsafe { fill_to(dest.as_mut_ptr(), value, dest.len()) };
+}
+
+/// The channels which can be armed to transfer at the start of every hblank.
+///
+/// Channels 1 and 2 aren't included as the [mixer](crate::sound::mixer) uses
+/// them while it is enabled. [Raster effects](crate::display::raster) which
+/// use DMA use channel 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DmaChannel {
+    /// Channel 0, the highest priority, which is the only channel that can't
+    /// read from the cartridge.
+    Dma0 = 0,
+    /// Channel 3, the lowest priority. While it is used for hblank DMA,
+    /// [`copy`] and [`fill`] are done by the CPU instead, which is slower.
+    /// Saving to EEPROM still needs channel 3, so it stops the effect until
+    /// the next vblank.
+    Dma3 = 3,
+}
+
+impl DmaChannel {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn source(self) -> MemoryMapped<u32> {
+        unsafe { MemoryMapped::new(dma_source_addr(self.index())) }
+    }
+
+    fn dest(self) -> MemoryMapped<u32> {
+        unsafe { MemoryMapped::new(dma_dest_addr(self.index())) }
+    }
+
+    fn control(self) -> MemoryMapped<u32> {
+        unsafe { MemoryMapped::new(dma_control_addr(self.index())) }
+    }
+
+    fn is_reserved(self) -> bool {
+        HBLANK_CHANNELS.read() & (1 << self.index()) != 0
+    }
+
+    /// Marks the channel as used by hblank DMA, returning false if it already
+    /// is.
+    pub(crate) fn reserve(self) -> bool {
+        crate::interrupt::free(|_| {
+            let reserved = HBLANK_CHANNELS.read();
+            let bit = 1 << self.index();
+
+            HBLANK_CHANNELS.write(reserved | bit);
+            reserved & bit == 0
+        })
+    }
+
+    /// Stops the channel and lets it be reserved again.
+    pub(crate) fn release(self) {
+        self.stop();
+
+        crate::interrupt::free(|_| {
+            HBLANK_CHANNELS.write(HBLANK_CHANNELS.read() & !(1 << self.index()));
+        });
+    }
+
+    /// Starts the channel copying `per_line` values from `src` to `dest` at
+    /// the start of every hblank, moving on through `src` each time. The
+    /// destination is the same for every line.
+    ///
+    /// # Safety
+    ///
+    /// `src` must stay valid until the channel is stopped, and enough values
+    /// must follow it for every hblank until then. It can't be in the
+    /// cartridge for channel 0.
+    pub(crate) unsafe fn hblank_repeat<T: Transfer>(
+        self,
+        src: *const T,
+        dest: *mut T,
+        per_line: u16,
+    ) {
+        self.stop();
+
+        self.source().set(src as u32);
+        self.dest().set(dest as u32);
+
+        let dest_control = if per_line == 1 {
+            DMA_DEST_FIXED
+        } else {
+            DMA_DEST_RELOAD
+        };
+        let size = if core::mem::size_of::<T>() == 4 {
+            DMA_32_BIT
+        } else {
+            0
+        };
+
+        self.control().set(
+            u32::from(per_line) | dest_control | DMA_REPEAT | size | DMA_HBLANK_TIMING | DMA_ENABLE,
+        );
+    }
+
+    pub(crate) fn stop(self) {
+        self.control().set(0);
+    }
+}
+
+const DMA_DEST_FIXED: u32 = 2 << 21;
+const DMA_DEST_RELOAD: u32 = 3 << 21;
+const DMA_REPEAT: u32 = 1 << 25;
+const DMA_HBLANK_TIMING: u32 = 2 << 28;
+
+static HBLANK_CHANNELS: Static<u8> = Static::new(0);
+
+/// Lines on the screen, each of which has an hblank after it which triggers
+/// hblank DMA. There is no hblank DMA during vblank.
+const HBLANKS: usize = crate::display::HEIGHT as usize;
+
+/// Writes a table of values, one entry for each line of the screen, to any
+/// register or address using hblank DMA while it is alive.
+///
+/// This is the lower level version of [`RasterEffect`], for when the
+/// destination isn't a single 16 bit register, or channel 3 is needed to run
+/// more than one effect at once. The table is restarted during
+/// every vblank, where its first entry is written straight away. After that,
+/// one entry is written during each hblank, including the one after the
+/// last line, so the table needs an entry for each line and one more after
+/// the last, which is usually the same as the first.
+///
+/// The channel is stopped when the handle is dropped. To change the values,
+/// drop the handle and create a new one, ideally during vblank.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::dma::{DmaChannel, HBlankDma};
+///
+/// # fn foo() {
+/// // move the horizontal and vertical scroll of background 0 together, with
+/// // the vertical scroll in the top half of each value
+/// let wave: [u32; 161] = core::array::from_fn(|line| (line as u32 % 8) << 16 | line as u32 % 4);
+/// let background_0_scroll = 0x0400_0010 as *mut u32;
+///
+/// let _dma = unsafe { HBlankDma::new(DmaChannel::Dma0, background_0_scroll, &wave, 1) };
+///
+/// let vblank = agb::interrupt::VBlank::get();
+/// loop {
+///     vblank.wait_for_vblank();
+/// }
+/// # }
+/// ```
+///
+/// [`RasterEffect`]: crate::display::raster::RasterEffect
+pub struct HBlankDma<'a> {
+    channel: DmaChannel,
+    handler: Option<InterruptHandler<'a>>,
+}
+
+impl<'a> HBlankDma<'a> {
+    /// Arms `channel` to write `per_line` values from `table` to `dest` during
+    /// every hblank, starting from the next vblank. When `per_line` is more
+    /// than 1, the values for each line are written to consecutive addresses
+    /// starting at `dest`, for example to change several palette entries at
+    /// once.
+    ///
+    /// # Safety
+    ///
+    /// `dest` must be a writable address, such as a hardware register, video
+    /// memory or a palette entry, with room for `per_line` values which can be
+    /// written at any time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel is already in use by hblank DMA, if `per_line` is
+    /// 0, if the table has fewer than `per_line` entries for each line and
+    /// the one after the last line, or if channel 0 is given a table in the
+    /// cartridge, which it can't read from.
+    #[must_use]
+    pub unsafe fn new<T: Transfer>(
+        channel: DmaChannel,
+        dest: *mut T,
+        table: &'a [T],
+        per_line: u16,
+    ) -> Self {
+        let per_line_usize = usize::from(per_line);
+        assert!(per_line > 0, "at least one value must be written per line");
+        assert!(
+            table.len() >= (HBLANKS + 1) * per_line_usize,
+            "the table needs {} entries, but has {}",
+            (HBLANKS + 1) * per_line_usize,
+            table.len()
+        );
+        assert!(
+            channel != DmaChannel::Dma0 || (table.as_ptr() as usize) < 0x0800_0000,
+            "DMA channel 0 can't read from the cartridge"
+        );
+        assert!(
+            channel.reserve(),
+            "{:?} is already being used for hblank DMA",
+            channel
+        );
+
+        let dest = dest as usize;
+
+        // the source address only moves forward, so restart the DMA from the
+        // top of the table at the start of every frame
+        let handler = add_interrupt_handler(Interrupt::VBlank, move |_| {
+            let dest = dest as *mut T;
+
+            for (i, value) in table[..per_line_usize].iter().enumerate() {
+                dest.add(i).write_volatile(*value);
+            }
+            channel.hblank_repeat(table[per_line_usize..].as_ptr(), dest, per_line);
+        });
+
+        Self {
+            channel,
+            handler: Some(handler),
+        }
+    }
+
+    /// The channel this is using.
+    #[must_use]
+    pub fn channel(&self) -> DmaChannel {
+        self.channel
+    }
+}
+
+impl Drop for HBlankDma<'_> {
+    fn drop(&mut self) {
+        // remove the handler first so it can't restart the channel
+        drop(self.handler.take());
+        self.channel.release();
+    }
+}
+
 pub(crate) fn dma3_exclusive<R>(f: impl FnOnce() -> R) -> R {
     const DMA0_CTRL_HI: MemoryMapped<u16> = unsafe { MemoryMapped::new(dma_control_addr(0) + 2) };
     const DMA1_CTRL_HI: MemoryMapped<u16> = unsafe { MemoryMapped::new(dma_control_addr(1) + 2) };
@@ -49,4 +455,71 @@ pub(crate) fn dma3_exclusive<R>(f: impl FnOnce() -> R) -> R {
         // returns the return value
         ret
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn copy_and_fill_handle_any_alignment(_gba: &mut crate::Gba) {
+        let source: [u16; 9] = core::array::from_fn(|i| i as u16 * 3 + 1);
+
+        for offset in 0..2 {
+            let mut destination = [0u16; 10];
+            let len = source.len() - offset;
+
+            copy(&source[offset..], &mut destination[offset..offset + len]);
+            assert_eq!(&destination[offset..offset + len], &source[offset..]);
+
+            fill(&mut destination[offset..offset + len], 0xabcd);
+            assert!(destination[offset..offset + len]
+                .iter()
+                .all(|&value| value == 0xabcd));
+            assert_eq!(destination[9], 0, "wrote past the end");
+        }
+
+        let mut words = [0u32; 5];
+        fill(&mut words, 0x1234_5678);
+        assert_eq!(words, [0x1234_5678; 5]);
+    }
+
+    #[test_case]
+    fn copy_and_fill_work_while_channel_3_is_reserved(_gba: &mut crate::Gba) {
+        assert!(DmaChannel::Dma3.reserve());
+
+        let source = [1u16, 2, 3];
+        let mut destination = [0u16; 3];
+        copy(&source, &mut destination);
+        assert_eq!(destination, source);
+
+        let mut words = [0u32; 3];
+        fill(&mut words, 0x1234_5678);
+        assert_eq!(words, [0x1234_5678; 3]);
+
+        DmaChannel::Dma3.release();
+    }
+
+    #[test_case]
+    fn hblank_dma_reserves_its_channel(_gba: &mut crate::Gba) {
+        let mut target = [0u16; 2];
+        let table: alloc::vec::Vec<u16> = (0..2 * (HBLANKS as u16 + 1)).collect();
+        let vblank = crate::interrupt::VBlank::get();
+
+        {
+            let dma = unsafe { HBlankDma::new(DmaChannel::Dma3, target.as_mut_ptr(), &table, 2) };
+            assert_eq!(dma.channel(), DmaChannel::Dma3);
+            assert!(!DmaChannel::Dma3.reserve());
+
+            vblank.wait_for_vblank();
+            vblank.wait_for_vblank();
+        }
+
+        assert!(DmaChannel::Dma3.reserve());
+        DmaChannel::Dma3.release();
+
+        // the first pair of values are written during vblank, which is when the
+        // handle was dropped
+        assert_eq!(target, [0, 1]);
+    }
+}
@@ -0,0 +1,68 @@
+//! Finding out whether the game is running in an emulator or on real hardware.
+
+use crate::memory_mapped::MemoryMapped1DArray;
+
+/// no$gba puts its name and version at this address, such as "no$gba v3.05".
+const NO_CASH_EMULATION_ID: MemoryMapped1DArray<u8, 6> =
+    unsafe { MemoryMapped1DArray::new(0x04FF_FA00) };
+const NO_CASH_NAME: &[u8; 6] = b"no$gba";
+
+/// What the game is running on, from [`environment`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Environment {
+    /// The mGBA emulator, which has a debug log that
+    /// [`println`](crate::println) writes to.
+    Mgba,
+    /// The no$gba emulator.
+    NoCashGba,
+    /// A real GBA, or an emulator which doesn't identify itself.
+    Hardware,
+}
+
+impl Environment {
+    /// Whether this is an emulator which identified itself.
+    #[must_use]
+    pub fn is_emulator(self) -> bool {
+        self != Environment::Hardware
+    }
+}
+
+/// Finds out what the game is running on by probing the debug registers which
+/// mGBA and no$gba add, which don't exist on real hardware.
+///
+/// This can be used to only do emulator specific things such as logging when
+/// they will work, or to skip hardware which emulators may leave out.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # fn foo() {
+/// use agb::Environment;
+///
+/// if agb::environment() == Environment::Hardware {
+///     // save the logs somewhere else as there is no debug log to write to
+/// }
+/// # }
+/// ```
+#[must_use]
+pub fn environment() -> Environment {
+    if crate::mgba::is_running_in_mgba() {
+        Environment::Mgba
+    } else if (0..NO_CASH_NAME.len()).all(|i| NO_CASH_EMULATION_ID.get(i) == NO_CASH_NAME[i]) {
+        Environment::NoCashGba
+    } else {
+        Environment::Hardware
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn tests_run_in_mgba(_gba: &mut crate::Gba) {
+        assert_eq!(environment(), Environment::Mgba);
+        assert!(environment().is_emulator());
+        assert!(!Environment::Hardware.is_emulator());
+    }
+}
@@ -0,0 +1,232 @@
+#![deny(missing_docs)]
+//! Running `async` code one frame at a time.
+//!
+//! Sequences which take many frames, such as cutscenes, fades and dialogue,
+//! are awkward to write as state machines which are updated once per frame.
+//! With an [`Executor`] they can be written as `async` functions instead,
+//! which `.await` [`VBlank::wait`](crate::interrupt::VBlank::wait) wherever
+//! they need to wait for the next frame.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::executor::{wait_frames, Executor};
+//! use agb::interrupt::VBlank;
+//!
+//! async fn fade_in(vblank: &VBlank) {
+//!     for _ in 0..16 {
+//!         // make the screen a bit brighter
+//!         vblank.wait().await;
+//!     }
+//! }
+//!
+//! async fn cutscene(vblank: &VBlank) {
+//!     fade_in(vblank).await;
+//!     // show some dialogue, then wait for a second before carrying on
+//!     wait_frames(60).await;
+//! }
+//!
+//! # fn foo() {
+//! let vblank = VBlank::get();
+//! let mut executor = Executor::new();
+//! executor.spawn(cutscene(&vblank));
+//! executor.run(&vblank);
+//! # }
+//! ```
+//!
+//! The executor polls every task once per frame, so there's no need for the
+//! tasks to wake it, and it works with any futures which are driven by
+//! polling. Waiting for a frame completes on the executor's next poll, so
+//! games which call [`Executor::poll`] from their own main loop must call it
+//! exactly once per frame.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use bare_metal::Mutex;
+
+use crate::interrupt::{free, VBlank};
+
+/// The number of frames the executors have run, which lets a [`NextFrame`]
+/// tell whether a frame has passed since it was first polled.
+static FRAME: Mutex<Cell<u32>> = Mutex::new(Cell::new(0));
+
+fn current_frame() -> u32 {
+    free(|cs| FRAME.borrow(cs).get())
+}
+
+fn next_frame() {
+    free(|cs| {
+        let frame = FRAME.borrow(cs);
+        frame.set(frame.get().wrapping_add(1));
+    });
+}
+
+const NOOP_VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |_| RawWaker::new(core::ptr::null(), &NOOP_VTABLE),
+    |_| {},
+    |_| {},
+    |_| {},
+);
+
+fn noop_waker() -> Waker {
+    // Safety: the vtable functions do nothing, so are fine to call with any data
+    unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &NOOP_VTABLE)) }
+}
+
+/// A future which completes on the frame after it is first polled. Created by
+/// [`VBlank::wait`](crate::interrupt::VBlank::wait).
+#[must_use = "futures do nothing unless awaited"]
+#[derive(Debug)]
+pub struct NextFrame {
+    polled_on: Option<u32>,
+}
+
+impl NextFrame {
+    pub(crate) fn new() -> Self {
+        Self { polled_on: None }
+    }
+}
+
+impl Future for NextFrame {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        let frame = current_frame();
+
+        match self.polled_on {
+            Some(polled_on) if polled_on != frame => Poll::Ready(()),
+            Some(_) => Poll::Pending,
+            None => {
+                self.polled_on = Some(frame);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Waits for the given number of frames. Waiting for 0 frames completes
+/// straight away.
+pub async fn wait_frames(frames: u32) {
+    for _ in 0..frames {
+        NextFrame::new().await;
+    }
+}
+
+/// Runs `async` tasks, polling each of them once per frame until they finish.
+#[derive(Default)]
+pub struct Executor<'a> {
+    tasks: Vec<Pin<Box<dyn Future<Output = ()> + 'a>>>,
+}
+
+impl<'a> Executor<'a> {
+    /// Creates an executor with no tasks.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { tasks: Vec::new() }
+    }
+
+    /// Adds a task, which is first polled in the next call to
+    /// [`poll`](Executor::poll).
+    pub fn spawn(&mut self, task: impl Future<Output = ()> + 'a) {
+        self.tasks.push(Box::pin(task));
+    }
+
+    /// The number of tasks which haven't finished yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns `true` if every task has finished.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Starts a new frame, polls every task once in the order they were
+    /// spawned, and removes the ones which finished. Games which want to run
+    /// tasks alongside their own main loop should call this once per frame.
+    pub fn poll(&mut self) {
+        next_frame();
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        self.tasks
+            .retain_mut(|task| task.as_mut().poll(&mut cx).is_pending());
+    }
+
+    /// Polls every task once per frame until they have all finished.
+    pub fn run(&mut self, vblank: &VBlank) {
+        loop {
+            self.poll();
+            if self.is_empty() {
+                return;
+            }
+
+            vblank.wait_for_vblank();
+        }
+    }
+}
+
+/// Runs a single future to completion, polling it once per frame, and returns
+/// its output.
+pub fn block_on<F: Future>(vblank: &VBlank, future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        next_frame();
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+
+        vblank.wait_for_vblank();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+
+    #[test_case]
+    fn tasks_wait_for_frames(_gba: &mut crate::Gba) {
+        let vblank = VBlank::get();
+        let steps = Rc::new(Cell::new(0));
+
+        let mut executor = Executor::new();
+        for frames in [1, 3] {
+            let steps = steps.clone();
+            executor.spawn(async move {
+                wait_frames(frames).await;
+                steps.set(steps.get() + frames);
+            });
+        }
+        assert_eq!(executor.len(), 2);
+
+        executor.poll();
+        assert_eq!(steps.get(), 0);
+
+        executor.poll();
+        assert_eq!(steps.get(), 1);
+
+        executor.run(&vblank);
+        assert_eq!(steps.get(), 4);
+        assert!(executor.is_empty());
+
+        let frames_waited = block_on(&vblank, async {
+            let start = current_frame();
+            vblank.wait().await;
+            vblank.wait().await;
+            current_frame().wrapping_sub(start)
+        });
+        assert_eq!(frames_waited, 2);
+    }
+}
@@ -0,0 +1,19 @@
+//! The cartridge's general purpose I/O port, which some cartridges use to
+//! connect extra hardware such as a real time clock, rumble motor or sensors.
+//!
+//! The port has 4 pins, whose levels are the low 4 bits of [`DATA`] and whose
+//! directions are set by [`DIRECTION`], with 1 meaning an output. It sits on
+//! top of the ROM at 0x080000c4, so reading it needs [`CONTROL`] set to 1,
+//! which [`enable`] does. On cartridges without a port, writes are ignored and
+//! reads return the ROM's contents, which agb leaves as 0.
+
+use crate::memory_mapped::MemoryMapped;
+
+pub(crate) const DATA: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0800_00c4) };
+pub(crate) const DIRECTION: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0800_00c6) };
+pub(crate) const CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0800_00c8) };
+
+/// Makes the port readable.
+pub(crate) fn enable() {
+    CONTROL.set(1);
+}
@@ -0,0 +1,236 @@
+use core::convert::TryFrom;
+
+use crate::display::object::Sprite;
+use crate::fixnum::{Rect, Vector2D};
+
+/// Whether two rectangles share any pixels. Rectangles which only share an
+/// edge don't overlap.
+#[must_use]
+pub fn overlaps(a: Rect<i32>, b: Rect<i32>) -> bool {
+    a.size.x > 0 && a.size.y > 0 && b.size.x > 0 && b.size.y > 0 && a.touches(b)
+}
+
+/// Whether the pixel at the given point is inside the rectangle. Unlike
+/// [Rect::contains_point], the pixels just past the right and bottom edges
+/// aren't counted as being inside.
+#[must_use]
+pub fn contains_point(rect: Rect<i32>, point: Vector2D<i32>) -> bool {
+    point.x >= rect.position.x
+        && point.x < rect.position.x + rect.size.x
+        && point.y >= rect.position.y
+        && point.y < rect.position.y + rect.size.y
+}
+
+/// Flips a hit box within something of the given size, for example to find
+/// the hit box of a sprite which has been flipped.
+#[must_use]
+pub fn flip(hit_box: Rect<i32>, size: Vector2D<i32>, hflip: bool, vflip: bool) -> Rect<i32> {
+    let mut position = hit_box.position;
+
+    if hflip {
+        position.x = size.x - position.x - hit_box.size.x;
+    }
+    if vflip {
+        position.y = size.y - position.y - hit_box.size.y;
+    }
+
+    Rect::new(position, hit_box.size)
+}
+
+/// The hit box of the sprite when its top left is at the given position and it
+/// is flipped as given.
+#[must_use]
+pub fn sprite_hit_box(
+    sprite: &Sprite,
+    position: Vector2D<i32>,
+    hflip: bool,
+    vflip: bool,
+) -> Rect<i32> {
+    let (width, height) = sprite.size().to_width_height();
+    let hit_box = flip(
+        sprite.hit_box(),
+        (width as i32, height as i32).into(),
+        hflip,
+        vflip,
+    );
+
+    Rect::new(hit_box.position + position, hit_box.size)
+}
+
+/// A 1 bit per pixel mask of which pixels of a sprite are solid, as generated
+/// by [include_aseprite_with_masks](crate::include_aseprite_with_masks). Each
+/// row starts on a new 32 bit word with the leftmost pixel in the least
+/// significant bit.
+pub struct CollisionMask {
+    width: u16,
+    height: u16,
+    data: &'static [u32],
+}
+
+impl CollisionMask {
+    #[doc(hidden)]
+    /// Creates a mask from its packed data, used internally by
+    /// [include_aseprite_with_masks](crate::include_aseprite_with_masks).
+    /// Any bits past the width of each row must be zero.
+    #[must_use]
+    pub const fn new(width: u16, height: u16, data: &'static [u32]) -> Self {
+        assert!(data.len() == (width as usize).div_ceil(32) * height as usize);
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// The width and height of the mask in pixels.
+    #[must_use]
+    pub fn size(&self) -> Vector2D<i32> {
+        (self.width as i32, self.height as i32).into()
+    }
+
+    fn words_per_row(&self) -> usize {
+        (self.width as usize).div_ceil(32)
+    }
+
+    fn row(&self, y: i32) -> &[u32] {
+        let words_per_row = self.words_per_row();
+        let start = y as usize * words_per_row;
+        &self.data[start..start + words_per_row]
+    }
+
+    /// Whether the pixel at the given point is solid. Points outside the mask
+    /// are never solid.
+    #[must_use]
+    pub fn is_set(&self, point: Vector2D<i32>) -> bool {
+        contains_point(Rect::new((0, 0).into(), self.size()), point)
+            && self.row(point.y)[point.x as usize / 32] & (1 << (point.x % 32)) != 0
+    }
+}
+
+/// The 32 pixels of the row starting at x, which may be before the start of
+/// the row or extend past its end.
+fn row_window(row: &[u32], x: i32) -> u32 {
+    let word = |index: i32| {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| row.get(index))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let index = x.div_euclid(32);
+    let shift = x.rem_euclid(32);
+
+    if shift == 0 {
+        word(index)
+    } else {
+        word(index) >> shift | word(index + 1) << (32 - shift)
+    }
+}
+
+/// Whether any solid pixel of mask `a` with its top left at `a_position`
+/// overlaps a solid pixel of mask `b` with its top left at `b_position`. The
+/// overlapping rows are compared 32 pixels at a time. This doesn't take into
+/// account any flipping or rotation of the sprites the masks came from.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # use agb::display::object::Graphics;
+/// # use agb::hit_box::mask_collision;
+/// const GRAPHICS: &Graphics = agb::include_aseprite_with_masks!(
+///     "examples/gfx/objects.aseprite"
+/// );
+///
+/// # fn foo() {
+/// let sprite = GRAPHICS.sprites()[0].collision_mask().unwrap();
+/// let colliding = mask_collision(sprite, (10, 10).into(), sprite, (14, 12).into());
+/// # }
+/// ```
+#[must_use]
+pub fn mask_collision(
+    a: &CollisionMask,
+    a_position: Vector2D<i32>,
+    b: &CollisionMask,
+    b_position: Vector2D<i32>,
+) -> bool {
+    let a_rect = Rect::new(a_position, a.size());
+    let b_rect = Rect::new(b_position, b.size());
+
+    if !overlaps(a_rect, b_rect) {
+        return false;
+    }
+
+    let offset = b_position - a_position;
+    let top = offset.y.max(0);
+    let bottom = (offset.y + b_rect.size.y).min(a_rect.size.y);
+
+    for y in top..bottom {
+        let a_row = a.row(y);
+        let b_row = b.row(y - offset.y);
+
+        for (word, &a_word) in a_row.iter().enumerate() {
+            let b_word = row_window(b_row, word as i32 * 32 - offset.x);
+            if a_word & b_word != 0 {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: i32, height: i32) -> Rect<i32> {
+        Rect::new((x, y).into(), (width, height).into())
+    }
+
+    #[test_case]
+    fn rects_sharing_an_edge_do_not_overlap(_gba: &mut crate::Gba) {
+        assert!(overlaps(rect(0, 0, 8, 8), rect(7, 7, 8, 8)));
+        assert!(!overlaps(rect(0, 0, 8, 8), rect(8, 0, 8, 8)));
+        assert!(!overlaps(rect(0, 0, 8, 8), rect(0, 8, 8, 8)));
+        assert!(!overlaps(rect(0, 0, 0, 0), rect(0, 0, 8, 8)));
+    }
+
+    #[test_case]
+    fn points_on_the_far_edge_are_outside(_gba: &mut crate::Gba) {
+        assert!(contains_point(rect(2, 2, 4, 4), (2, 2).into()));
+        assert!(contains_point(rect(2, 2, 4, 4), (5, 5).into()));
+        assert!(!contains_point(rect(2, 2, 4, 4), (6, 5).into()));
+        assert!(!contains_point(rect(2, 2, 4, 4), (5, 6).into()));
+    }
+
+    #[test_case]
+    fn flipping_mirrors_the_hit_box(_gba: &mut crate::Gba) {
+        let hit_box = rect(1, 2, 3, 4);
+        let size = (16, 16).into();
+
+        assert_eq!(flip(hit_box, size, false, false), hit_box);
+        assert_eq!(flip(hit_box, size, true, false), rect(12, 2, 3, 4));
+        assert_eq!(flip(hit_box, size, false, true), rect(1, 10, 3, 4));
+    }
+
+    #[test_case]
+    fn masks_only_collide_on_solid_pixels(_gba: &mut crate::Gba) {
+        // a 40x2 mask with the leftmost and rightmost pixels of each row set
+        static CORNERS: CollisionMask = CollisionMask::new(40, 2, &[1, 1 << 7, 1, 1 << 7]);
+        // a 2x2 solid block
+        static BLOCK: CollisionMask = CollisionMask::new(2, 2, &[0b11, 0b11]);
+
+        assert!(CORNERS.is_set((39, 1).into()));
+        assert!(!CORNERS.is_set((40, 1).into()));
+
+        let origin = (0, 0).into();
+
+        assert!(mask_collision(&CORNERS, origin, &BLOCK, (-1, -1).into()));
+        assert!(mask_collision(&CORNERS, origin, &BLOCK, (38, 1).into()));
+        assert!(mask_collision(&BLOCK, (38, 1).into(), &CORNERS, origin));
+        assert!(!mask_collision(&CORNERS, origin, &BLOCK, (10, 0).into()));
+        assert!(!mask_collision(&CORNERS, origin, &BLOCK, (40, 0).into()));
+        assert!(!mask_collision(&CORNERS, origin, &BLOCK, (0, 2).into()));
+    }
+}
@@ -3,6 +3,14 @@ use crate::fixnum::Vector2D;
 use bitflags::bitflags;
 use core::convert::From;
 
+mod combo;
+mod map;
+mod replay;
+
+pub use combo::{Combo, ComboDetector, ComboStep};
+pub use map::InputMap;
+pub use replay::{Recorder, Replayer};
+
 /// Tri-state enum. Allows for -1, 0 and +1.
 /// Useful if checking if the D-Pad is pointing left, right, or unpressed.
 ///
@@ -64,6 +72,8 @@ bitflags! {
     }
 }
 
+const BUTTON_COUNT: usize = 10;
+
 const BUTTON_INPUT: *mut u16 = (0x04000130) as *mut u16;
 
 // const BUTTON_INTERRUPT: *mut u16 = (0x04000132) as *mut u16;
@@ -93,6 +103,8 @@ const BUTTON_INPUT: *mut u16 = (0x04000130) as *mut u16;
 pub struct ButtonController {
     previous: u16,
     current: u16,
+    held_frames: [u16; BUTTON_COUNT],
+    released_after: [u16; BUTTON_COUNT],
 }
 
 impl Default for ButtonController {
@@ -107,18 +119,97 @@ impl ButtonController {
     #[must_use]
     pub fn new() -> Self {
         let pressed = !unsafe { BUTTON_INPUT.read_volatile() };
-        ButtonController {
+        let mut controller = ButtonController {
             previous: pressed,
             current: pressed,
-        }
+            held_frames: [0; BUTTON_COUNT],
+            released_after: [0; BUTTON_COUNT],
+        };
+        controller.update_held_frames();
+        controller
     }
 
     /// Updates the state of the button controller.
     /// You should call this every frame (either at the start or the end) to ensure that you have the latest state of each button press.
     /// Calls to any method won't change until you call this.
     pub fn update(&mut self) {
+        self.set_pressed(!unsafe { BUTTON_INPUT.read_volatile() });
+    }
+
+    pub(crate) fn set_pressed(&mut self, pressed: u16) {
         self.previous = self.current;
-        self.current = !unsafe { BUTTON_INPUT.read_volatile() };
+        self.current = pressed;
+        self.update_held_frames();
+    }
+
+    fn update_held_frames(&mut self) {
+        for (i, (frames, released_after)) in self
+            .held_frames
+            .iter_mut()
+            .zip(&mut self.released_after)
+            .enumerate()
+        {
+            if self.current & (1 << i) != 0 {
+                *frames = frames.saturating_add(1);
+                *released_after = 0;
+            } else {
+                *released_after = *frames;
+                *frames = 0;
+            }
+        }
+    }
+
+    fn longest(counts: &[u16; BUTTON_COUNT], keys: Button) -> u16 {
+        counts
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| keys.bits() & (1 << i) != 0)
+            .map(|(_, &frames)| frames)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of frames the buttons specified in `keys` have been
+    /// held for, including the current one, or 0 if they aren't pressed. If
+    /// more than one button is given, this is the longest any of them has been
+    /// held.
+    ///
+    /// This is useful for charge attacks, where holding a button for longer
+    /// gives a stronger attack.
+    #[must_use]
+    pub fn frames_held(&self, keys: Button) -> u16 {
+        Self::longest(&self.held_frames, keys)
+    }
+
+    /// If the buttons specified in `keys` were released in the last frame,
+    /// returns the number of frames they were held for before being released.
+    /// This makes it easy to do different things for short and long presses.
+    ///
+    /// # Example
+    /// ```no_run,rust
+    /// # #![no_std]
+    /// use agb::input::{Button, ButtonController};
+    ///
+    /// # fn main() {
+    /// let mut button_controller = ButtonController::new();
+    ///
+    /// loop {
+    ///     button_controller.update();
+    ///
+    ///     match button_controller.just_released_after(Button::A) {
+    ///         Some(frames) if frames < 20 => { /* a short press, so jump */ }
+    ///         Some(_) => { /* a long press, so do a charged attack */ }
+    ///         None => {}
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn just_released_after(&self, keys: Button) -> Option<u16> {
+        match Self::longest(&self.released_after, keys) {
+            0 => None,
+            frames => Some(frames),
+        }
     }
 
     /// Returns [Tri::Positive] if right is pressed, [Tri::Negative] if left is pressed and [Tri::Zero] if neither or both are pressed.
@@ -227,6 +318,53 @@ impl ButtonController {
         ((current & keys) != 0) && ((previous & keys) == 0)
     }
 
+    /// Returns true on the frame the buttons specified in `keys` are pressed
+    /// and then, if they're held, repeatedly every `repeat_rate` frames after
+    /// the first `initial_delay` frames. This gives the usual behaviour of
+    /// scrolling through a menu by holding down a direction.
+    ///
+    /// # Example
+    /// ```no_run,rust
+    /// # #![no_std]
+    /// use agb::input::{Button, ButtonController};
+    ///
+    /// # fn main() {
+    /// let mut button_controller = ButtonController::new();
+    /// let mut selected = 0;
+    ///
+    /// loop {
+    ///     button_controller.update();
+    ///
+    ///     // move once straight away, and then 5 times a second after half a second
+    ///     if button_controller.is_pressed_with_repeat(Button::DOWN, 30, 12) {
+    ///         selected += 1;
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `repeat_rate` is 0.
+    #[must_use]
+    pub fn is_pressed_with_repeat(
+        &self,
+        keys: Button,
+        initial_delay: u16,
+        repeat_rate: u16,
+    ) -> bool {
+        assert_ne!(repeat_rate, 0, "repeat rate must be at least 1 frame");
+
+        match self.frames_held(keys) {
+            0 => false,
+            1 => true,
+            frames if frames <= initial_delay => false,
+            // stop repeating rather than wrapping around if held for a very long time
+            u16::MAX => false,
+            frames => (frames - 1 - initial_delay) % repeat_rate == 0,
+        }
+    }
+
     /// Returns true if all the buttons specified in `keys` went from pressed to not pressed in the last frame.
     /// Very useful for menu navigation or selection if you want players actions to only happen for one frame.
     #[must_use]
@@ -237,3 +375,49 @@ impl ButtonController {
         ((current & keys) == 0) && ((previous & keys) != 0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn held_buttons_repeat_after_the_delay(_gba: &mut crate::Gba) {
+        let mut input = ButtonController::new();
+        input.set_pressed(0);
+
+        let repeats: [bool; 10] = core::array::from_fn(|_| {
+            input.set_pressed(Button::DOWN.bits() as u16);
+            input.is_pressed_with_repeat(Button::DOWN, 3, 2)
+        });
+
+        assert_eq!(
+            repeats,
+            [true, false, false, true, false, true, false, true, false, true]
+        );
+
+        input.set_pressed(0);
+        assert!(!input.is_pressed_with_repeat(Button::DOWN, 3, 2));
+    }
+
+    #[test_case]
+    fn held_durations_are_tracked(_gba: &mut crate::Gba) {
+        let mut input = ButtonController::new();
+        input.set_pressed(0);
+        assert_eq!(input.frames_held(Button::A), 0);
+
+        for frame in 1..=5 {
+            input.set_pressed(Button::A.bits() as u16);
+            assert_eq!(input.frames_held(Button::A), frame);
+            assert_eq!(input.just_released_after(Button::A), None);
+        }
+
+        input.set_pressed(Button::B.bits() as u16);
+        assert_eq!(input.frames_held(Button::A), 0);
+        assert_eq!(input.frames_held(Button::A | Button::B), 1);
+        assert_eq!(input.just_released_after(Button::A), Some(5));
+
+        input.set_pressed(0);
+        assert_eq!(input.just_released_after(Button::A), None);
+        assert_eq!(input.just_released_after(Button::B), Some(1));
+    }
+}
@@ -0,0 +1,335 @@
+use super::{Button, ButtonController};
+use crate::collections::RingBuffer;
+
+/// The most input changes which are remembered when looking for combos
+const HISTORY_LENGTH: usize = 32;
+
+/// Buttons pressed within this many frames of each other count as pressed at
+/// the same time, as pressing them on exactly the same frame is very hard.
+const SIMULTANEOUS_FRAMES: u32 = 3;
+
+fn d_pad() -> Button {
+    Button::UP | Button::DOWN | Button::LEFT | Button::RIGHT
+}
+
+fn mirror(direction: Button) -> Button {
+    let mut mirrored = direction & (Button::UP | Button::DOWN);
+    mirrored.set(Button::LEFT, direction.contains(Button::RIGHT));
+    mirrored.set(Button::RIGHT, direction.contains(Button::LEFT));
+    mirrored
+}
+
+/// One part of a [`Combo`], which is either moving the d-pad to a direction,
+/// pressing some buttons, or pressing some buttons while holding a direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComboStep {
+    direction: Option<Button>,
+    buttons: Button,
+}
+
+impl ComboStep {
+    /// Moving the d-pad so that exactly these directions are held. Diagonals
+    /// are given as two directions, such as `Button::DOWN | Button::RIGHT`,
+    /// and [`Button::empty()`] means letting go of the d-pad.
+    #[must_use]
+    pub const fn direction(direction: Button) -> Self {
+        Self {
+            direction: Some(direction),
+            buttons: Button::empty(),
+        }
+    }
+
+    /// Pressing all of the buttons at the same time, whatever direction the
+    /// d-pad is in.
+    #[must_use]
+    pub const fn press(buttons: Button) -> Self {
+        Self {
+            direction: None,
+            buttons,
+        }
+    }
+
+    /// Pressing all of the buttons at the same time while exactly these
+    /// directions are held.
+    #[must_use]
+    pub const fn direction_and_press(direction: Button, buttons: Button) -> Self {
+        Self {
+            direction: Some(direction),
+            buttons,
+        }
+    }
+}
+
+/// A sequence of [`ComboStep`]s which must all be done within a number of
+/// frames, like the motion inputs for special moves in fighting games.
+///
+/// Combos are written as if the player is facing right, and are mirrored by
+/// [`ComboDetector::set_mirrored`] when they face left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Combo {
+    steps: &'static [ComboStep],
+    window: u32,
+}
+
+impl Combo {
+    /// A combo which completes when every step is done in order, with no more
+    /// than `window` frames between the first and last steps.
+    #[must_use]
+    pub const fn new(steps: &'static [ComboStep], window: u32) -> Self {
+        Self { steps, window }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct HistoryEntry {
+    frame: u32,
+    direction: Button,
+    direction_changed: bool,
+    pressed: Button,
+}
+
+/// Watches the buttons for any of a list of [`Combo`]s being done.
+///
+/// Inputs are buffered, so steps can be done slightly early and other buttons
+/// can be pressed between steps without breaking the combo.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{Button, ButtonController, Combo, ComboDetector, ComboStep};
+///
+/// const FIREBALL: Combo = Combo::new(
+///     &[
+///         ComboStep::direction(Button::DOWN),
+///         ComboStep::direction(Button::DOWN.union(Button::RIGHT)),
+///         ComboStep::direction_and_press(Button::RIGHT, Button::A),
+///     ],
+///     20,
+/// );
+///
+/// # fn main() {
+/// let mut input = ButtonController::new();
+/// let mut combos = ComboDetector::new(&[FIREBALL]);
+///
+/// loop {
+///     input.update();
+///
+///     if combos.update(&input) == Some(0) {
+///         // throw a fireball
+///     }
+/// }
+/// # }
+/// ```
+pub struct ComboDetector<'a> {
+    combos: &'a [Combo],
+    history: RingBuffer<HistoryEntry, HISTORY_LENGTH>,
+    frame: u32,
+    mirrored: bool,
+}
+
+impl<'a> ComboDetector<'a> {
+    /// Creates a detector which looks for the given combos.
+    #[must_use]
+    pub fn new(combos: &'a [Combo]) -> Self {
+        Self {
+            combos,
+            history: RingBuffer::new(),
+            frame: 0,
+            mirrored: false,
+        }
+    }
+
+    /// Mirrors the combos so that left and right are swapped, for when the
+    /// player is facing left.
+    pub fn set_mirrored(&mut self, mirrored: bool) {
+        self.mirrored = mirrored;
+    }
+
+    /// Forgets all the buffered inputs, so no combo can be completed using
+    /// steps done before now.
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// Adds the current state of the buttons to the buffer and returns the
+    /// index of the combo which was just completed, if any. Call this once per
+    /// frame after [`ButtonController::update`].
+    ///
+    /// If more than one combo is completed in the same frame, the one which
+    /// comes first in the list is returned, so put longer combos before any
+    /// shorter ones which they end with.
+    pub fn update(&mut self, input: &ButtonController) -> Option<usize> {
+        self.frame = self.frame.wrapping_add(1);
+
+        let direction = Button::from_bits_truncate(u32::from(input.current)) & d_pad();
+        let pressed =
+            Button::from_bits_truncate(u32::from(input.current & !input.previous)) - d_pad();
+
+        let direction_changed = self
+            .history
+            .back()
+            .is_none_or(|last| last.direction != direction);
+
+        if !direction_changed && pressed.is_empty() {
+            return None;
+        }
+
+        self.history.push_overwriting(HistoryEntry {
+            frame: self.frame,
+            direction,
+            direction_changed,
+            pressed,
+        });
+
+        self.combos
+            .iter()
+            .position(|combo| self.is_completed(combo))
+    }
+
+    fn is_completed(&self, combo: &Combo) -> bool {
+        let Some((last_step, steps)) = combo.steps.split_last() else {
+            return false;
+        };
+
+        // the last step has to be done in the newest entry, so a combo is only reported once
+        let mut position = self.history.len() - 1;
+        if !self.step_done_at(last_step, position) {
+            return false;
+        }
+
+        for step in steps.iter().rev() {
+            let Some(found) = (0..position).rev().find(|&i| self.step_done_at(step, i)) else {
+                return false;
+            };
+
+            position = found;
+        }
+
+        self.frame.wrapping_sub(self.history[position].frame) <= combo.window
+    }
+
+    fn step_done_at(&self, step: &ComboStep, index: usize) -> bool {
+        let entry = &self.history[index];
+
+        if let Some(direction) = step.direction {
+            let direction = if self.mirrored {
+                mirror(direction)
+            } else {
+                direction
+            };
+
+            if entry.direction != direction {
+                return false;
+            }
+
+            if step.buttons.is_empty() {
+                return entry.direction_changed;
+            }
+        }
+
+        if !entry.pressed.intersects(step.buttons) {
+            return false;
+        }
+
+        // the other buttons may have been pressed in the frames just before this one
+        let pressed = self
+            .history
+            .iter()
+            .take(index + 1)
+            .rev()
+            .take_while(|earlier| entry.frame.wrapping_sub(earlier.frame) < SIMULTANEOUS_FRAMES)
+            .fold(Button::empty(), |pressed, earlier| {
+                pressed | earlier.pressed
+            });
+
+        pressed.contains(step.buttons)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREBALL: Combo = Combo::new(
+        &[
+            ComboStep::direction(Button::DOWN),
+            ComboStep::direction(Button::DOWN.union(Button::RIGHT)),
+            ComboStep::direction_and_press(Button::RIGHT, Button::A),
+        ],
+        10,
+    );
+    const THROW: Combo = Combo::new(&[ComboStep::press(Button::A.union(Button::B))], 0);
+
+    fn run(detector: &mut ComboDetector, frames: &[Button]) -> Option<usize> {
+        let mut input = ButtonController::new();
+        input.set_pressed(0);
+
+        let mut completed = None;
+        for buttons in frames {
+            input.set_pressed(buttons.bits() as u16);
+            if let Some(combo) = detector.update(&input) {
+                completed = Some(combo);
+            }
+        }
+
+        completed
+    }
+
+    #[test_case]
+    fn motion_combos_are_detected(_gba: &mut crate::Gba) {
+        let mut detector = ComboDetector::new(&[FIREBALL, THROW]);
+
+        let fireball = [
+            Button::DOWN,
+            Button::DOWN | Button::RIGHT,
+            Button::RIGHT,
+            Button::RIGHT | Button::A,
+        ];
+        assert_eq!(run(&mut detector, &fireball), Some(0));
+
+        detector.clear();
+        detector.set_mirrored(true);
+        assert_eq!(run(&mut detector, &fireball), None);
+
+        detector.clear();
+        let mirrored = fireball.map(|buttons| mirror(buttons & d_pad()) | (buttons - d_pad()));
+        assert_eq!(run(&mut detector, &mirrored), Some(0));
+    }
+
+    #[test_case]
+    fn slow_combos_are_not_detected(_gba: &mut crate::Gba) {
+        let mut detector = ComboDetector::new(&[FIREBALL]);
+
+        let mut frames = [Button::DOWN | Button::RIGHT; 12];
+        frames[0] = Button::DOWN;
+        frames[11] = Button::RIGHT | Button::A;
+
+        assert_eq!(run(&mut detector, &frames), None);
+    }
+
+    #[test_case]
+    fn simultaneous_presses_can_be_slightly_apart(_gba: &mut crate::Gba) {
+        let mut detector = ComboDetector::new(&[THROW]);
+        assert_eq!(
+            run(
+                &mut detector,
+                &[Button::A, Button::A, Button::A | Button::B]
+            ),
+            Some(0)
+        );
+
+        detector.clear();
+        assert_eq!(
+            run(
+                &mut detector,
+                &[
+                    Button::A,
+                    Button::A,
+                    Button::A,
+                    Button::A,
+                    Button::A | Button::B
+                ]
+            ),
+            None
+        );
+    }
+}
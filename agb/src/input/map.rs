@@ -0,0 +1,213 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use super::{Button, ButtonController};
+
+/// Maps the actions in a game to the buttons which perform them, so that
+/// gameplay code can check for actions rather than for specific buttons and
+/// the player can change the controls.
+///
+/// Actions are usually a fieldless enum which can be converted to an index
+/// with [`Into<usize>`], and `N` is the number of actions. Each action can be
+/// bound to any number of buttons, and is pressed if any of them are.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{Button, ButtonController, InputMap};
+///
+/// #[derive(Clone, Copy)]
+/// enum Action {
+///     Jump,
+///     Attack,
+/// }
+///
+/// impl From<Action> for usize {
+///     fn from(action: Action) -> usize {
+///         action as usize
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut input = ButtonController::new();
+/// let mut map = InputMap::<Action, 2>::new([Button::A | Button::UP, Button::B]);
+///
+/// loop {
+///     input.update();
+///     map.update(&input);
+///
+///     if map.just_pressed(Action::Jump) {
+///         // jump
+///     }
+/// }
+/// # }
+/// ```
+///
+/// The bindings can be stored in save data with
+/// [`to_bytes`](InputMap::to_bytes) and loaded back with
+/// [`from_bytes`](InputMap::from_bytes).
+#[derive(Clone, Debug)]
+pub struct InputMap<A, const N: usize> {
+    bindings: [Button; N],
+    current: u32,
+    previous: u32,
+    _actions: PhantomData<A>,
+}
+
+impl<A, const N: usize> InputMap<A, N>
+where
+    A: Copy + Into<usize>,
+{
+    /// Creates a map with the buttons for each action, in the order of the
+    /// actions' indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 32 actions.
+    #[must_use]
+    pub fn new(bindings: [Button; N]) -> Self {
+        assert!(N <= 32, "an input map can have at most 32 actions");
+
+        Self {
+            bindings,
+            current: 0,
+            previous: 0,
+            _actions: PhantomData,
+        }
+    }
+
+    /// Loads bindings stored by [`to_bytes`](InputMap::to_bytes). Returns
+    /// `None` if the bytes don't contain valid bindings for `N` actions, for
+    /// example if the save data has never been written.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != N * 2 {
+            return None;
+        }
+
+        let mut bindings = [Button::empty(); N];
+        for (binding, pair) in bindings.iter_mut().zip(bytes.as_chunks::<2>().0) {
+            *binding = Button::from_bits(u32::from(u16::from_le_bytes(*pair)))?;
+        }
+
+        Some(Self::new(bindings))
+    }
+
+    /// The bindings in a form which can be stored in save data, using 2 bytes
+    /// per action.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bindings
+            .iter()
+            .flat_map(|binding| (binding.bits() as u16).to_le_bytes())
+            .collect()
+    }
+
+    /// Updates which actions are pressed from the state of the buttons. Call
+    /// this once per frame after [`ButtonController::update`].
+    pub fn update(&mut self, input: &ButtonController) {
+        self.previous = self.current;
+        self.current = self
+            .bindings
+            .iter()
+            .enumerate()
+            .filter(|(_, &binding)| input.is_pressed(binding))
+            .fold(0, |pressed, (i, _)| pressed | (1 << i));
+    }
+
+    /// The buttons which perform the action.
+    #[must_use]
+    pub fn binding(&self, action: A) -> Button {
+        self.bindings[action.into()]
+    }
+
+    /// Replaces the buttons which perform the action.
+    pub fn bind(&mut self, action: A, buttons: Button) {
+        self.bindings[action.into()] = buttons;
+    }
+
+    /// Binds the action to the buttons which were just pressed, if any, and
+    /// returns whether it was rebound. This is useful for a controls menu
+    /// which asks the player to press the button they want to use.
+    pub fn bind_just_pressed(&mut self, action: A, input: &ButtonController) -> bool {
+        let just_pressed = (0..super::BUTTON_COUNT)
+            .map(|i| Button::from_bits_truncate(1 << i))
+            .filter(|&button| input.is_just_pressed(button))
+            .fold(Button::empty(), |buttons, button| buttons | button);
+
+        if just_pressed.is_empty() {
+            return false;
+        }
+
+        self.bind(action, just_pressed);
+        true
+    }
+
+    /// Returns `true` if any of the buttons for the action are pressed.
+    #[must_use]
+    pub fn pressed(&self, action: A) -> bool {
+        self.current & Self::bit(action) != 0
+    }
+
+    /// Returns `true` if none of the buttons for the action are pressed.
+    #[must_use]
+    pub fn released(&self, action: A) -> bool {
+        !self.pressed(action)
+    }
+
+    /// Returns `true` if the action went from not pressed to pressed in the
+    /// last frame.
+    #[must_use]
+    pub fn just_pressed(&self, action: A) -> bool {
+        self.current & !self.previous & Self::bit(action) != 0
+    }
+
+    /// Returns `true` if the action went from pressed to not pressed in the
+    /// last frame.
+    #[must_use]
+    pub fn just_released(&self, action: A) -> bool {
+        !self.current & self.previous & Self::bit(action) != 0
+    }
+
+    fn bit(action: A) -> u32 {
+        1 << action.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    enum Action {
+        Jump,
+        Attack,
+    }
+
+    impl From<Action> for usize {
+        fn from(action: Action) -> usize {
+            action as usize
+        }
+    }
+
+    #[test_case]
+    fn actions_follow_their_buttons(_gba: &mut crate::Gba) {
+        let mut input = ButtonController::new();
+        let mut map = InputMap::<Action, 2>::new([Button::A | Button::UP, Button::B]);
+
+        input.set_pressed(Button::UP.bits() as u16);
+        map.update(&input);
+        assert!(map.just_pressed(Action::Jump));
+        assert!(map.released(Action::Attack));
+
+        map.bind(Action::Jump, Button::L);
+        map.update(&input);
+        assert!(map.just_released(Action::Jump));
+
+        let loaded = InputMap::<Action, 2>::from_bytes(&map.to_bytes()).unwrap();
+        assert_eq!(loaded.binding(Action::Jump), Button::L);
+        assert_eq!(loaded.binding(Action::Attack), Button::B);
+
+        assert!(InputMap::<Action, 2>::from_bytes(&[0xff; 4]).is_none());
+        assert!(InputMap::<Action, 2>::from_bytes(&[]).is_none());
+    }
+}
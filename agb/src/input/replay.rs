@@ -0,0 +1,214 @@
+use alloc::vec::Vec;
+
+use super::ButtonController;
+
+/// The number of bytes used for each run of frames with the same buttons pressed
+const ENTRY_SIZE: usize = 4;
+
+/// Records the state of the buttons every frame, so that it can be played back
+/// later with a [`Replayer`]. This is useful for attract mode demos, and for
+/// reproducing bugs deterministically.
+///
+/// The recording is stored as runs of frames where the same buttons were
+/// pressed, so it stays small even for long recordings. The bytes given by
+/// [`as_bytes`](Recorder::as_bytes) can be written to save data or included
+/// in the ROM with `include_bytes!` and then given to [`Replayer::new`].
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// use agb::input::{ButtonController, Recorder};
+///
+/// # fn main() {
+/// let mut input = ButtonController::new();
+/// let mut recorder = Recorder::new();
+///
+/// loop {
+///     input.update();
+///     recorder.record(&input);
+///
+///     // use input as normal
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    data: Vec<u8>,
+}
+
+impl Recorder {
+    /// Creates an empty recording.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    /// Adds the buttons which are currently pressed on the controller as the
+    /// next frame of the recording. Call this once per frame after
+    /// [`ButtonController::update`].
+    pub fn record(&mut self, input: &ButtonController) {
+        let buttons = input.current.to_le_bytes();
+
+        if let Some(last) = self.data.rchunks_exact_mut(ENTRY_SIZE).next() {
+            let frames = u16::from_le_bytes([last[2], last[3]]);
+            if last[..2] == buttons && frames != u16::MAX {
+                last[2..].copy_from_slice(&(frames + 1).to_le_bytes());
+                return;
+            }
+        }
+
+        self.data.extend_from_slice(&buttons);
+        self.data.extend_from_slice(&1u16.to_le_bytes());
+    }
+
+    /// The number of frames which have been recorded.
+    #[must_use]
+    pub fn frames(&self) -> usize {
+        entries(&self.data)
+            .map(|(_, frames)| usize::from(frames))
+            .sum()
+    }
+
+    /// The recording in the format understood by [`Replayer::new`].
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Removes everything which has been recorded.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+}
+
+/// Plays back a recording made by a [`Recorder`] through a
+/// [`ButtonController`], so the rest of the game sees the recorded buttons
+/// exactly as if they were being pressed.
+///
+/// Call [`update`](Replayer::update) in place of [`ButtonController::update`]
+/// once per frame. Once the recording has finished, no buttons are reported as
+/// pressed.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::input::{ButtonController, Replayer};
+///
+/// # fn foo(recording: &[u8]) {
+/// // the recording could be read from the save data or included in the ROM
+/// let mut input = ButtonController::new();
+/// let mut replayer = Replayer::new(recording);
+///
+/// while !replayer.is_finished() {
+///     replayer.update(&mut input);
+///
+///     // use input as normal
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Replayer<'a> {
+    data: &'a [u8],
+    position: usize,
+    frame: u16,
+}
+
+impl<'a> Replayer<'a> {
+    /// Creates a replayer for a recording made by [`Recorder::as_bytes`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the recording isn't a multiple of 4 bytes, which
+    /// means it can't have come from a [`Recorder`].
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        assert_eq!(
+            data.len() % ENTRY_SIZE,
+            0,
+            "input recordings are made of 4 byte entries"
+        );
+
+        Self {
+            data,
+            position: 0,
+            frame: 0,
+        }
+    }
+
+    /// Moves the recording on by one frame and updates the controller with the
+    /// buttons which were pressed in that frame.
+    pub fn update(&mut self, input: &mut ButtonController) {
+        let buttons = match entries(&self.data[self.position..]).next() {
+            Some((buttons, frames)) => {
+                self.frame += 1;
+                if self.frame >= frames {
+                    self.frame = 0;
+                    self.position += ENTRY_SIZE;
+                }
+
+                buttons
+            }
+            None => 0,
+        };
+
+        input.set_pressed(buttons);
+    }
+
+    /// Whether every frame of the recording has been played.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    /// Starts playing the recording from the beginning again.
+    pub fn restart(&mut self) {
+        self.position = 0;
+        self.frame = 0;
+    }
+}
+
+fn entries(data: &[u8]) -> impl Iterator<Item = (u16, u16)> + '_ {
+    data.as_chunks::<ENTRY_SIZE>().0.iter().map(
+        |&[buttons_low, buttons_high, frames_low, frames_high]| {
+            (
+                u16::from_le_bytes([buttons_low, buttons_high]),
+                u16::from_le_bytes([frames_low, frames_high]),
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Button;
+
+    #[test_case]
+    fn recordings_replay_the_same_buttons(_gba: &mut crate::Gba) {
+        let mut input = ButtonController::new();
+        let mut recorder = Recorder::new();
+
+        let frames = [0, 0, Button::A.bits(), Button::A.bits(), 0];
+        for &buttons in &frames {
+            input.set_pressed(buttons as u16);
+            recorder.record(&input);
+        }
+
+        assert_eq!(recorder.frames(), 5);
+        assert_eq!(recorder.as_bytes().len(), 3 * ENTRY_SIZE);
+
+        let mut replayer = Replayer::new(recorder.as_bytes());
+        let mut replayed = ButtonController::new();
+
+        for &buttons in &frames {
+            assert!(!replayer.is_finished());
+            replayer.update(&mut replayed);
+            assert_eq!(u32::from(replayed.current), buttons);
+        }
+
+        assert!(replayer.is_finished());
+        assert!(replayed.is_just_released(Button::A));
+
+        replayer.update(&mut replayed);
+        assert!(!replayed.is_pressed(Button::A));
+    }
+}
@@ -7,23 +7,44 @@ use core::{
 use alloc::boxed::Box;
 use bare_metal::CriticalSection;
 
-use crate::{display::DISPLAY_STATUS, memory_mapped::MemoryMapped};
+use crate::{
+    display::{DISPLAY_STATUS, HEIGHT, VCOUNT},
+    memory_mapped::MemoryMapped,
+};
 
+/// The interrupts which handlers can be added for with
+/// [`add_interrupt_handler`].
 #[derive(Clone, Copy)]
 pub enum Interrupt {
+    /// At the start of the vertical blank, after the last visible line is drawn.
     VBlank = 0,
+    /// In the horizontal blank after each line is drawn. See [`HBlank`].
     HBlank = 1,
+    /// When the display reaches the line set with [`VCount`].
     VCounter = 2,
+    /// When timer 0 overflows, if its interrupt is enabled.
     Timer0 = 3,
+    /// When timer 1 overflows, if its interrupt is enabled.
     Timer1 = 4,
+    /// When timer 2 overflows, if its interrupt is enabled.
     Timer2 = 5,
+    /// When timer 3 overflows, if its interrupt is enabled.
     Timer3 = 6,
+    /// When a transfer over the serial port finishes, or in UART mode when
+    /// data is sent or received. Adding a handler enables the interrupt in the
+    /// serial control register, so set up the serial port's mode first.
     Serial = 7,
+    /// When a transfer on DMA channel 0 finishes, if its interrupt is enabled.
     Dma0 = 8,
+    /// When a transfer on DMA channel 1 finishes, if its interrupt is enabled.
     Dma1 = 9,
+    /// When a transfer on DMA channel 2 finishes, if its interrupt is enabled.
     Dma2 = 10,
+    /// When a transfer on DMA channel 3 finishes, if its interrupt is enabled.
     Dma3 = 11,
+    /// When the buttons set in the key control register are pressed.
     Keypad = 12,
+    /// When the cartridge is removed, or from some cartridge hardware.
     Gamepak = 13,
 }
 
@@ -52,6 +73,12 @@ impl Interrupt {
             Interrupt::HBlank => {
                 DISPLAY_STATUS.set_bits(1, 1, 4);
             }
+            Interrupt::VCounter => {
+                DISPLAY_STATUS.set_bits(1, 1, 5);
+            }
+            Interrupt::Serial => {
+                SERIAL_CONTROL.set_bits(1, 1, 14);
+            }
             _ => {}
         }
     }
@@ -64,11 +91,20 @@ impl Interrupt {
             Interrupt::HBlank => {
                 DISPLAY_STATUS.set_bits(0, 1, 4);
             }
+            Interrupt::VCounter => {
+                DISPLAY_STATUS.set_bits(0, 1, 5);
+            }
+            Interrupt::Serial => {
+                SERIAL_CONTROL.set_bits(0, 1, 14);
+            }
             _ => {}
         }
     }
 }
 
+/// The interrupt request bit is in the same place in every serial mode
+const SERIAL_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0128) };
+
 const ENABLED_INTERRUPTS: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x04000200) };
 const INTERRUPTS_ENABLED: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x04000208) };
 
@@ -90,7 +126,7 @@ fn temporary_interrupt_disable() -> Disable {
     d
 }
 
-fn disable_interrupts() {
+pub(crate) fn disable_interrupts() {
     INTERRUPTS_ENABLED.set(0);
 }
 
@@ -284,6 +320,60 @@ pub fn add_interrupt_handler<'a>(
     do_with_inner(interrupt, inner)
 }
 
+extern "C" {
+    static mut agb_rs__high_priority_interrupts: u16;
+}
+
+/// Sets whether an interrupt is high priority. The handlers of high priority
+/// interrupts can run in the middle of the handlers for other interrupts,
+/// rather than waiting for them to finish. This is useful for interrupts which
+/// need to be handled on time, such as the timer which the
+/// [mixer](crate::sound::mixer) uses to swap its buffers, when the game has
+/// long running vblank handlers.
+///
+/// High priority interrupts can't interrupt each other, or code running in
+/// [`free`].
+///
+/// # Safety
+///
+/// Every handler is given a [`CriticalSection`], but a high priority handler
+/// can run in the middle of any handler for an interrupt which isn't high
+/// priority, so the [`CriticalSection`] those handlers are given no longer
+/// stops it running. The caller must make sure that no handler for an
+/// interrupt which isn't high priority uses its [`CriticalSection`] to access
+/// anything which a high priority handler also accesses. Those handlers must
+/// use [`free`] instead.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::interrupt::{set_high_priority, Interrupt};
+///
+/// # fn foo() {
+/// // Safety: the timer 1 handler doesn't share anything with other handlers
+/// unsafe { set_high_priority(Interrupt::Timer1, true) };
+/// # }
+/// ```
+pub unsafe fn set_high_priority(interrupt: Interrupt, high_priority: bool) {
+    free(|_| {
+        let bit = 1 << (interrupt as u16);
+        unsafe {
+            if high_priority {
+                agb_rs__high_priority_interrupts |= bit;
+            } else {
+                agb_rs__high_priority_interrupts &= !bit;
+            }
+        }
+    });
+}
+
+/// Whether the interrupt has been made high priority with
+/// [`set_high_priority`].
+#[must_use]
+pub fn is_high_priority(interrupt: Interrupt) -> bool {
+    free(|_| unsafe { agb_rs__high_priority_interrupts } & (1 << (interrupt as u16)) != 0)
+}
+
 /// How you can access mutexes outside of interrupts by being given a
 /// [`CriticalSection`]
 ///
@@ -322,7 +412,13 @@ impl VBlank {
     /// Pauses CPU until vblank interrupt is triggered where code execution is
     /// resumed.
     pub fn wait_for_vblank(&self) {
-        crate::syscall::wait_for_vblank();
+        crate::syscall::vblank_interrupt_wait();
+    }
+
+    /// Waits until the next frame in `async` code run by an
+    /// [`Executor`](crate::executor::Executor).
+    pub fn wait(&self) -> crate::executor::NextFrame {
+        crate::executor::NextFrame::new()
     }
 }
 
@@ -332,6 +428,141 @@ impl Drop for VBlank {
     }
 }
 
+/// Runs code in the horizontal blank between each scanline, which is how
+/// raster effects such as wavy backgrounds and per-line colour changes are
+/// made.
+///
+/// The horizontal blank only lasts for 272 cycles ([`HBlank::CYCLES`]), and
+/// dispatching the interrupt uses up a good part of that before the handler
+/// is called. Handlers should do little more than write a few registers, and
+/// should read anything they need from tables prepared during the frame
+/// rather than calculating it. Anything written to the video registers or
+/// palette during the horizontal blank is used for the next line. Handlers
+/// which take too long will still work, but their changes will appear part
+/// way along the line.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::interrupt::HBlank;
+///
+/// # fn foo() {
+/// static OFFSETS: [u16; 160] = [0; 160];
+///
+/// let _handler = HBlank::add_handler(|_, line| {
+///     // write OFFSETS[line] to a background's scroll register
+///     let _offset = OFFSETS[usize::from(line)];
+/// });
+/// # }
+/// ```
+#[non_exhaustive]
+pub struct HBlank {}
+
+impl HBlank {
+    /// The number of CPU cycles in each horizontal blank.
+    pub const CYCLES: u32 = 272;
+
+    /// Calls `handler` in the horizontal blank before each visible line is
+    /// drawn for as long as the returned value is alive. The handler is given
+    /// the line which is about to be drawn, from 0 to 159.
+    pub fn add_handler<'a>(
+        handler: impl Fn(CriticalSection, u16) + Send + Sync + 'a,
+    ) -> InterruptHandler<'a> {
+        add_interrupt_handler(Interrupt::HBlank, move |cs| {
+            if let Some(line) = Self::next_line() {
+                handler(cs, line);
+            }
+        })
+    }
+
+    /// The visible line which will be drawn after the current horizontal blank,
+    /// if there is one.
+    fn next_line() -> Option<u16> {
+        // the horizontal blank also happens on the lines of the vertical blank,
+        // and the last of those is followed by the first line of the next frame
+        match VCOUNT.get() {
+            227 => Some(0),
+            line if line + 1 < HEIGHT as u16 => Some(line + 1),
+            _ => None,
+        }
+    }
+}
+
+/// Runs code when the display reaches a particular line, which is the
+/// cheapest way to change the display part way down the screen, such as for a
+/// status bar at the bottom of a scrolling level. Unlike [`HBlank`], nothing
+/// runs on the other lines.
+///
+/// The handler runs at the start of the line it is set to, so changes to the
+/// video registers apply from the line after it. There is only one line which
+/// can be matched at a time, and the handler can move it with
+/// [`VCountMatch::set_line`] to split the screen in more than one place.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::interrupt::VCount;
+///
+/// # fn foo() {
+/// let _handler = VCount::add_handler(20, |_, matched| {
+///     if matched.line() == 20 {
+///         // change the background for the middle of the screen
+///         matched.set_line(140);
+///     } else {
+///         // and change it again for the bottom
+///         matched.set_line(20);
+///     }
+/// });
+/// # }
+/// ```
+#[non_exhaustive]
+pub struct VCount {}
+
+/// The line which triggered a [`VCount`] handler, which can be used to choose
+/// the next line to trigger on.
+#[non_exhaustive]
+pub struct VCountMatch {}
+
+impl VCountMatch {
+    /// The line the handler is configured to trigger on, which is the line it
+    /// was triggered for unless [`set_line`](VCountMatch::set_line) has been
+    /// called since. This isn't the line currently being drawn, which may have
+    /// moved on if the handler was delayed by another interrupt.
+    #[must_use]
+    pub fn line(&self) -> u16 {
+        DISPLAY_STATUS.get() >> 8
+    }
+
+    /// Sets the line the handler will next be triggered for. Panics if the
+    /// line isn't less than 228.
+    pub fn set_line(&self, line: u16) {
+        VCount::set_line(line);
+    }
+}
+
+impl VCount {
+    /// Calls `handler` at the start of the given line for as long as the
+    /// returned value is alive. Lines 160 to 227 are part of the vertical blank.
+    /// Panics if the line isn't less than 228.
+    pub fn add_handler<'a>(
+        line: u16,
+        handler: impl Fn(CriticalSection, &VCountMatch) + Send + Sync + 'a,
+    ) -> InterruptHandler<'a> {
+        Self::set_line(line);
+
+        add_interrupt_handler(Interrupt::VCounter, move |cs| {
+            handler(cs, &VCountMatch {});
+        })
+    }
+
+    /// Changes the line which the handlers are triggered for. Panics if the
+    /// line isn't less than 228.
+    pub fn set_line(line: u16) {
+        assert!(line < 228, "the display only has 228 lines");
+        DISPLAY_STATUS.set_bits(line, 8, 8);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -366,6 +597,114 @@ mod tests {
         );
     }
 
+    #[test_case]
+    fn hblank_handlers_are_given_each_visible_line(_gba: &mut crate::Gba) {
+        let lines = Mutex::new(RefCell::new([false; 160]));
+        let vblank = VBlank::get();
+
+        {
+            let _handler = HBlank::add_handler(|cs, line| {
+                lines.borrow(cs).borrow_mut()[usize::from(line)] = true;
+            });
+
+            vblank.wait_for_vblank();
+            vblank.wait_for_vblank();
+        }
+
+        assert!(free(|cs| lines
+            .borrow(cs)
+            .borrow()
+            .iter()
+            .all(|&seen| seen)));
+    }
+
+    #[test_case]
+    fn vcount_handlers_can_move_the_line(_gba: &mut crate::Gba) {
+        let lines = Mutex::new(RefCell::new([0u16; 4]));
+        let count = Mutex::new(Cell::new(0));
+        let vblank = VBlank::get();
+
+        {
+            let _handler = VCount::add_handler(40, |cs, matched| {
+                let count = count.borrow(cs);
+                if count.get() < 4 {
+                    lines.borrow(cs).borrow_mut()[count.get()] = VCOUNT.get();
+                    count.set(count.get() + 1);
+                }
+
+                matched.set_line(if matched.line() == 40 { 100 } else { 40 });
+            });
+
+            for _ in 0..3 {
+                vblank.wait_for_vblank();
+            }
+        }
+
+        assert_eq!(free(|cs| *lines.borrow(cs).borrow()), [40, 100, 40, 100]);
+    }
+
+    #[test_case]
+    fn high_priority_interrupts_run_during_other_handlers(gba: &mut crate::Gba) {
+        let mut timer = gba.timers.timers().timer2;
+        timer
+            .set_divider(crate::timer::Divider::Divider1)
+            .set_overflow_amount(1000)
+            .set_enabled(true);
+
+        let timer_count = Mutex::new(Cell::new(0));
+        let nested = Mutex::new(Cell::new(false));
+
+        unsafe { set_high_priority(Interrupt::Timer2, true) };
+        assert!(is_high_priority(Interrupt::Timer2));
+
+        let vblank = VBlank::get();
+        {
+            let _timer_handler = timer.add_overflow_handler(|cs| {
+                let count = timer_count.borrow(cs);
+                count.set(count.get() + 1);
+            });
+
+            // the timer handler can run during this one, so the count has to be read in `free`
+            let _vblank_handler = add_interrupt_handler(Interrupt::VBlank, |cs| {
+                let count = || free(|cs| timer_count.borrow(cs).get());
+                let before = count();
+
+                // spend long enough in the handler for the timer to overflow a few times
+                let mut wait = 0;
+                while count() == before && wait < 10_000 {
+                    wait += 1;
+                    crate::sync::memory_write_hint(&mut wait);
+                }
+
+                if count() != before {
+                    nested.borrow(cs).set(true);
+                }
+            });
+
+            vblank.wait_for_vblank();
+            vblank.wait_for_vblank();
+        }
+
+        unsafe { set_high_priority(Interrupt::Timer2, false) };
+        timer.set_enabled(false);
+
+        assert!(free(|cs| nested.borrow(cs).get()));
+    }
+
+    #[test_case]
+    fn serial_handlers_enable_the_serial_interrupt(_gba: &mut crate::Gba) {
+        {
+            let _handler = add_interrupt_handler(Interrupt::Serial, |_| {});
+            assert_eq!(SERIAL_CONTROL.get() & (1 << 14), 1 << 14);
+            assert_ne!(
+                ENABLED_INTERRUPTS.get() & (1 << Interrupt::Serial as u16),
+                0
+            );
+        }
+
+        assert_eq!(SERIAL_CONTROL.get() & (1 << 14), 0);
+    }
+
     #[test_case]
     fn test_interrupt_table_length(_gba: &mut crate::Gba) {
         assert_eq!(
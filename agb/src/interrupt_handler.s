@@ -1,5 +1,7 @@
 
-@ An interrupt handler that simply acknowledges all interrupts
+@ An interrupt handler that acknowledges the interrupts which were raised and
+@ calls the rust handler for them. Interrupts marked as high priority can
+@ interrupt the rust handlers for any other interrupts.
     .arm
     .global InterruptHandler
     .section .iwram, "ax", %progbits
@@ -15,32 +17,70 @@ InterruptHandler:
     ldrh r3, [r2, #2] @ load 16 bit interrupt request to r3
     and r0, r1, r3 @ interrupts both enabled and requested
 
-    ldr r1, [sp, #20]
-    ldr r3, =agb_rs__program_counter
-    str r1, [r3]
+    @ acknowledge the interrupts now, so they can be raised again while the handlers run
+    strh r0, [r2, #2]
 
-    @ change to system mode
-    mrs r1, cpsr
-    orr r1, r1, #0xD
-    msr cpsr_c, r1
+    ldr r3, [sp, #20]
+    ldr r12, =agb_rs__program_counter
+    str r3, [r12]
+
+    @ a nested interrupt would overwrite these, so keep them on the irq stack
+    mrs r3, spsr
+    push {r3, lr}
+
+    @ work out which interrupts can interrupt this one, with 0 meaning none can.
+    @ high priority interrupts are never interrupted themselves.
+    ldr r3, =agb_rs__high_priority_interrupts
+    ldrh r3, [r3]
+    tst r0, r3
+    movne r3, #0
+    and r3, r3, r1
+
+    @ change to system mode, allowing interrupts in the cpsr if nesting
+    mrs r12, cpsr
+    orr r12, r12, #0xD
+    cmp r3, #0
+    bicne r12, r12, #0x80
+    msr cpsr_c, r12
+
+    @ save the interrupt enable register from before the handler along with the one used while
+    @ nested, so any changes the rust handlers make to it can be kept
+    push {r1, r2, r3, lr}
+
+    cmp r3, #0
+    strneh r3, [r2] @ only allow the high priority interrupts
+    movne r12, #1
+    strneh r12, [r2, #8]
 
     @ call the rust interrupt handler with r0 set to the triggered interrupts
     ldr r1, =__RUST_INTERRUPT_HANDLER
-    push {r2, lr}
     mov lr, pc
     bx r1
-    pop {r2, lr}
 
-    @ change back to interrupt mode
+    pop {r1, r2, r3, lr}
+
+    mov r12, #0
+    strh r12, [r2, #8]
+
+    @ put back the interrupts which weren't allowed while nested, keeping any the handlers enabled
+    cmp r3, #0
+    ldrneh r12, [r2]
+    bicne r1, r1, r3
+    orrne r12, r12, r1
+    strneh r12, [r2]
+
+    @ change back to interrupt mode with interrupts disabled in the cpsr
     mrs r1, cpsr
     bic r1, r1, #0xD
+    orr r1, r1, #0x80
     msr cpsr_c, r1
 
+    pop {r3, lr}
+    msr spsr_cf, r3
+
     mov r1, #1
     strh r1, [r2, #8]
 
-    strh r0, [r2, #2] @ store to interrupt request
-
     ldr r2, =0x03007FF8 @ load bios interrupt request location
     ldrh r1, [r2] @ load bios interrupt requests
     orr r1, r1, r0 @ or with enabled and requested interrupts
@@ -55,3 +95,9 @@ InterruptHandler:
     .balign 4
 agb_rs__program_counter:
     .word 0
+
+.section .iwram.high_priority_interrupts
+    .global agb_rs__high_priority_interrupts
+    .balign 2
+agb_rs__high_priority_interrupts:
+    .hword 0
@@ -72,6 +72,23 @@
 ///
 /// And tiles will be an instance of [`TileData`][crate::display::tile_data::TileData]
 ///
+/// If you add `compression = "lz77"` or `compression = "rle"` to an image, its tiles will instead be
+/// compressed into a [`CompressedTileData`][crate::display::tile_data::CompressedTileData], which can be
+/// loaded directly into video RAM using
+/// [`VRamManager::add_tileset_compressed`][crate::display::tiled::VRamManager::add_tileset_compressed].
+///
+/// You can also attach a `u16` property to individual tiles, for example to mark which tiles are solid.
+/// These end up in `tile_properties` and can be passed to
+/// [`TileSet::with_properties`][crate::display::tiled::TileSet::with_properties], or to
+/// [`CompressedTileSet::with_properties`][crate::display::tiled::CompressedTileSet::with_properties]
+/// for compressed tiles:
+///
+/// ```toml
+/// [image.tiles.tile_properties]
+/// 3 = 1 # solid
+/// 4 = 2 # water
+/// ```
+///
 /// # Examples
 ///
 /// Assume the tiles are loaded as above
@@ -117,6 +134,18 @@ pub use agb_image_converter::include_gfx;
 #[doc(hidden)]
 pub use agb_image_converter::include_aseprite_inner;
 
+#[doc(hidden)]
+pub use agb_image_converter::include_aseprite_with_masks_inner;
+
+#[doc(hidden)]
+pub use agb_image_converter::include_aseprite_256_inner;
+
+#[doc(hidden)]
+pub use agb_image_converter::include_metasprites_inner;
+
+#[doc(hidden)]
+pub use agb_image_converter::include_bitmap_inner;
+
 #[doc(hidden)]
 pub use agb_image_converter::include_font as include_font_inner;
 
@@ -145,22 +174,312 @@ macro_rules! include_font {
 ///     loop {}
 /// }
 /// ```
+///
+/// It also puts the header which the GBA checks before starting the game at
+/// the start of the ROM, so the game doesn't need to be fixed by gbafix to run
+/// on real hardware. The title, game code, maker code and version in the
+/// header can be set with arguments. The title is up to 12 characters, the
+/// game code 4 characters and the maker code 2 characters, which can be
+/// capital letters, digits or spaces. They are all blank by default.
+///
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// use agb::Gba;
+///
+/// #[agb::entry(title = "MY GAME", game_code = "AMYE", maker_code = "01", version = 1)]
+/// fn main(mut gba: Gba) -> ! {
+///     loop {}
+/// }
+/// ```
+///
+/// By default the stack starts at the top of IWRAM and shares it with the
+/// [`InternalAllocator`], and the [`ExternalAllocator`] uses all of EWRAM
+/// after the statics. The stack can instead be given `stack_size` bytes of its
+/// own, in `"iwram"` (the default) or `"ewram"` as chosen by `stack`, and the
+/// allocators can be limited to `iwram_heap_size` and `ewram_heap_size`
+/// bytes. Sizes must be non zero multiples of 8, and the space is reserved after the
+/// statics when the game is linked, so the link fails with an error like
+/// "section '.iwram_reserved' will not fit in region 'iwram'" if it doesn't
+/// fit. See [`mem`] to find out how much is actually used.
+///
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// use agb::Gba;
+///
+/// // a deep stack in the slower but much bigger EWRAM, leaving IWRAM for fast code and data
+/// #[agb::entry(stack = "ewram", stack_size = 16384, ewram_heap_size = 131072)]
+/// fn main(mut gba: Gba) -> ! {
+///     loop {}
+/// }
+/// ```
+///
+/// A size of 0 is rejected when the game is compiled, rather than leaving
+/// nothing for the stack or heap.
+///
+/// ```compile_fail
+/// #![no_std]
+/// #![no_main]
+///
+/// use agb::Gba;
+///
+/// #[agb::entry(stack_size = 0)]
+/// fn main(mut gba: Gba) -> ! {
+///     loop {}
+/// }
+/// ```
 pub use agb_macros::entry;
 
-pub use agb_sound_converter::include_wav;
+#[doc(hidden)]
+pub use rom_header::rom_header;
+
+/// Places a function or static in internal working ram (IWRAM), which is
+/// fast 32-bit memory. Functions are also compiled as ARM code rather than
+/// Thumb code, which together makes them several times faster than functions
+/// in ROM. This is useful for inner loops which run many times per frame, but
+/// there are only 32KiB of IWRAM, which is also used for the stack.
+///
+/// # Examples
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// #[agb::iwram]
+/// fn sum(values: &[u32]) -> u32 {
+///     values.iter().sum()
+/// }
+///
+/// #[agb::iwram]
+/// static mut LOOKUP: [u8; 256] = [0; 256];
+/// ```
+pub use agb_macros::iwram;
+
+/// Places a function or static in external working ram (EWRAM). EWRAM is
+/// slower than IWRAM, but there are 256KiB of it, and functions in it still
+/// run faster than functions in ROM. Functions are compiled as Thumb code as
+/// EWRAM has a 16-bit bus.
+///
+/// # Examples
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// #[agb::ewram]
+/// static mut LEVEL: [u16; 4096] = [0; 4096];
+/// ```
+///
+/// Statics which aren't mutable are copied from ROM to EWRAM at startup, which
+/// makes large lookup tables faster to read at the cost of EWRAM.
+///
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// #[agb::ewram]
+/// static SINE: [i16; 256] = [0; 256];
+/// ```
+pub use agb_macros::ewram;
+
+/// Keeps a static in ROM. Statics which aren't mutable and don't contain a
+/// `Cell` or other interior mutability are normally kept in ROM anyway, but
+/// this makes the choice explicit and puts each static in its own section,
+/// so it shows up separately in the linker's map file. Use [`macro@ewram`] or
+/// [`macro@iwram`] instead to trade RAM for faster access.
+///
+/// The static can't be `mut`, as writes to ROM are ignored. It also mustn't
+/// contain anything with interior mutability, which the macro can't check.
+///
+/// # Examples
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// #[agb::rom]
+/// static LEVEL_DATA: [u8; 4] = [1, 2, 3, 4];
+/// ```
+pub use agb_macros::rom;
+
+/// Puts a function or static in one of the 4 overlays, numbered 0 to 3, which
+/// are kept in ROM and take turns running from the same part of EWRAM. The
+/// overlay must be loaded with [`overlay::load`] before anything in it is
+/// used. Nothing checks this, so it can only be applied to an `unsafe fn` or a
+/// `static mut`.
+///
+/// # Examples
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// #[agb::overlay(2)]
+/// static mut BOSS_PATTERNS: [u16; 4] = [1, 2, 3, 4];
+/// ```
+pub use agb_macros::overlay;
+
+/// Compiles a function as ARM code rather than Thumb code. ARM code is
+/// faster when it runs from IWRAM, which has a 32-bit bus, but slower from ROM
+/// or EWRAM. [`macro@iwram`] already compiles the functions it places as ARM
+/// code, so this is for functions placed in IWRAM some other way, such as
+/// with `#[link_section]`. Calls between ARM and Thumb functions switch
+/// instruction set automatically.
+///
+/// # Examples
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// #[agb::arm]
+/// #[link_section = ".iwram.mix"]
+/// fn mix(a: &mut [i16], b: &[i16]) {
+///     for (a, b) in a.iter_mut().zip(b) {
+///         *a = a.saturating_add(*b);
+///     }
+/// }
+/// ```
+pub use agb_macros::arm;
+
+/// Compiles a function as Thumb code, which is the default. This is useful
+/// for functions in IWRAM which are too large for the speed of ARM code to be
+/// worth the extra space.
+///
+/// # Examples
+/// ```no_run,rust
+/// #![no_std]
+/// #![no_main]
+///
+/// #[agb::iwram]
+/// static mut TABLE: [u8; 16] = [0; 16];
+///
+/// #[agb::thumb]
+/// fn lookup(i: usize) -> u8 {
+///     unsafe { TABLE[i] }
+/// }
+/// ```
+pub use agb_macros::thumb;
+
+#[doc(hidden)]
+pub use agb_sound_converter::include_wav as include_wav_inner;
+
+/// Includes a wav file as [`SoundData`](sound::mixer::SoundData) which can be
+/// played by the [mixer](sound::mixer). Samples are converted to signed 8 bit
+/// and aligned for the mixer at compile time, and the first loop in the file's
+/// `smpl` chunk becomes the sound's loop point.
+///
+/// The loop can also be given as `loop_start` and `loop_end` arguments, in
+/// frames, which override the ones in the file. `loop_end` is exclusive and
+/// defaults to the end of the sound, and anything after it is never stored.
+///
+/// If the wav isn't at the frequency the mixer will run at, pass that
+/// frequency as the `sample_rate` argument and it will be resampled at compile
+/// time. Loop points are still given in frames of the original file. There
+/// will be a warning if so much of the sound's high frequencies would be
+/// removed that the result will sound noticeably duller.
+///
+/// Passing `adpcm = true` stores a mono sound as 4-bit ADPCM, which takes half
+/// the space in ROM at the cost of some quality and a little CPU time to
+/// decode it while it plays.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{include_wav, sound::mixer::SoundData};
+///
+/// const JUMP: SoundData = include_wav!("examples/sfx/jump.wav");
+/// const HUM: SoundData = include_wav!("examples/sfx/jump.wav", loop_start = 200, loop_end = 1200);
+/// const JUMP_18157: SoundData = include_wav!("examples/sfx/jump.wav", sample_rate = 18157);
+/// const JUMP_SMALL: SoundData = include_wav!("examples/sfx/jump.wav", adpcm = true);
+/// ```
+#[macro_export]
+macro_rules! include_wav {
+    ($wav_path: literal $(, $name: ident = $value: literal)* $(,)?) => {{
+        use $crate::sound::mixer::SoundData;
+        $crate::include_wav_inner!($wav_path $(, $name = $value)*)
+    }};
+}
+
+#[doc(hidden)]
+pub use agb_sound_converter::include_tracker as include_tracker_inner;
+
+/// Includes a MOD, XM, S3M or IT module as a [`Track`](sound::tracker::Track)
+/// which can be played by a [`Tracker`](sound::tracker::Tracker). The patterns
+/// and samples are converted at compile time, and any effects or features in
+/// the module which the tracker doesn't support are reported as warnings.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{include_tracker, sound::tracker::Track};
+///
+/// static TUNE: Track = include_tracker!("examples/sfx/test_tune.mod");
+/// ```
+#[macro_export]
+macro_rules! include_tracker {
+    ($module_path: literal) => {{
+        use $crate::sound::mixer::SoundData;
+        use $crate::sound::tracker::{Pattern, PatternEffect, PatternSlot, Track, TrackSample};
+        $crate::include_tracker_inner!($module_path)
+    }};
+}
+
+#[doc(hidden)]
+pub use agb_sound_converter::include_midi as include_midi_inner;
+
+/// Includes a MIDI file as a [`Sequence`](sound::sequencer::Sequence) which can
+/// be played on the DMG channels by a
+/// [`Sequencer`](sound::sequencer::Sequencer). The notes are converted to
+/// hardware frequencies and the timings to frames at compile time.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{include_midi, sound::sequencer::Sequence};
+///
+/// static TUNE: Sequence = include_midi!("examples/sfx/test_tune.mid");
+/// ```
+#[macro_export]
+macro_rules! include_midi {
+    ($midi_path: literal) => {{
+        use $crate::sound::dmg::DutyCycle;
+        use $crate::sound::sequencer::{PsgChannel, Sequence, SequenceEvent};
+        $crate::include_midi_inner!($midi_path)
+    }};
+}
+
+/// The address of a symbol defined by the linker script.
+macro_rules! linker_address {
+    ($symbol: ident) => {{
+        extern "C" {
+            static $symbol: u32;
+        }
+
+        (unsafe { &$symbol }) as *const u32 as usize
+    }};
+}
 
 extern crate alloc;
 mod agb_alloc;
 
 mod agbabi;
+pub mod arena;
+mod backtrace;
 mod bitarray;
+pub mod collections;
+pub mod debug;
 /// Implements everything relating to things that are displayed on screen.
 pub mod display;
-mod dma;
+pub mod dma;
+mod environment;
+pub mod executor;
+mod gpio;
 /// Button inputs to the system.
 pub mod input;
 /// Interacting with the GBA interrupts
 pub mod interrupt;
+pub mod link;
+pub mod log;
+pub mod mem;
 mod memory_mapped;
 /// Implements logging to the mgba emulator.
 pub mod mgba;
@@ -168,9 +487,22 @@ pub mod mgba;
 pub use agb_fixnum as fixnum;
 /// Contains an implementation of a hashmap which suits the gameboy advance's hardware.
 pub mod hash_map;
+/// Simple rectangle based collision detection, for example using the hit boxes
+/// of sprites.
+pub mod hit_box;
+pub mod noise;
+pub mod overlay;
+mod panic_screen;
+pub mod pathfinding;
+pub mod profiler;
 /// Simple random number generator
 pub mod rng;
+mod rom_header;
+pub mod rtc;
+pub mod rumble;
 pub mod save;
+pub mod sensor;
+pub mod shapes;
 mod single;
 /// Implements sound output.
 pub mod sound;
@@ -180,19 +512,31 @@ pub mod sync;
 pub mod syscall;
 /// Interactions with the internal timers
 pub mod timer;
+pub mod tween;
+pub mod waitstate;
 
-pub use {agb_alloc::ExternalAllocator, agb_alloc::InternalAllocator};
+pub use environment::{environment, Environment};
+pub use {agb_alloc::AllocatorStats, agb_alloc::ExternalAllocator, agb_alloc::InternalAllocator};
 
 #[cfg(not(any(test, feature = "testing")))]
 #[panic_handler]
 #[allow(unused_must_use)]
 fn panic_implementation(info: &core::panic::PanicInfo) -> ! {
     use core::fmt::Write;
+    interrupt::disable_interrupts();
+    let backtrace = backtrace::Backtrace::capture();
+
     if let Some(mut mgba) = mgba::Mgba::new() {
+        mgba.print(
+            format_args!("crash code: {}", backtrace),
+            mgba::DebugLevel::Error,
+        );
         write!(mgba, "{}", info);
         mgba.set_level(mgba::DebugLevel::Fatal);
     }
 
+    panic_screen::show(info, Some(&backtrace));
+
     #[allow(clippy::empty_loop)]
     loop {}
 }
@@ -230,6 +574,8 @@ pub struct Gba {
     pub save: save::SaveManager,
     /// Manages access to the Game Boy Advance's 4 timers.
     pub timers: timer::TimerController,
+    /// Manages access to the link port, for communicating with other consoles.
+    pub link: link::LinkController,
 }
 
 impl Gba {
@@ -246,6 +592,7 @@ impl Gba {
             mixer: sound::mixer::MixerController::new(),
             save: save::SaveManager::new(),
             timers: timer::TimerController::new(),
+            link: link::LinkController::new(),
         }
     }
 }
@@ -272,6 +619,14 @@ impl Gba {
 /// }
 /// ```
 ///
+/// Tests can check what is on the screen against a screenshot using
+/// [`assert_screen_matches`](crate::assert_screen_matches).
+///
+/// Gameplay can be tested from start to finish by playing an input recording
+/// made with a [`Recorder`](crate::input::Recorder) through the game with
+/// [`run_input_script`](test_runner::run_input_script), and then checking the
+/// state of the game or the [`screen_hash`](test_runner::screen_hash).
+///
 /// You can run the tests using `cargo test`, but it will work better through `mgba-test-runner` by
 /// running something along the lines of `CARGO_TARGET_THUMBV4T_NONE_EABI_RUNNER=mgba-test-runner cargo test`.
 pub mod test_runner {
@@ -317,6 +672,17 @@ pub mod test_runner {
                 .unwrap();
         }
 
+        let backtrace = crate::backtrace::Backtrace::capture();
+        if let Some(mut mgba) = mgba::Mgba::new() {
+            mgba.print(
+                format_args!("crash code: {}", backtrace),
+                mgba::DebugLevel::Info,
+            )
+            .unwrap();
+        }
+
+        crate::panic_screen::show(info, Some(&backtrace));
+
         loop {}
     }
 
@@ -349,6 +715,9 @@ pub mod test_runner {
         pub mod test_runner {
             pub use super::super::agb_start_tests;
         }
+
+        #[cfg(test)]
+        pub use crate::rom_header;
     }
 
     #[cfg(test)]
@@ -366,6 +735,10 @@ pub mod test_runner {
         loop {}
     }
 
+    /// Waits for the screen to be drawn and asks the test runner to check it
+    /// against the image at the given path, failing the test if they differ.
+    /// [`assert_screen_matches`](crate::assert_screen_matches) is usually
+    /// easier to use.
     pub fn assert_image_output(image: &str) {
         display::busy_wait_for_vblank();
         display::busy_wait_for_vblank();
@@ -377,6 +750,124 @@ pub mod test_runner {
         .unwrap();
         display::busy_wait_for_vblank();
     }
+
+    /// Plays `recording` through a [`ButtonController`](crate::input::ButtonController)
+    /// for `frames` frames, calling `frame` with it once per frame and waiting
+    /// for the next vblank after each call. Once the recording runs out no
+    /// buttons are pressed.
+    ///
+    /// `frame` should run one frame of the game, so that the test can then
+    /// check what happened.
+    ///
+    /// ```rust,no_run
+    /// # #![no_std]
+    /// # #![no_main]
+    /// # fn foo(gba: &mut agb::Gba) {
+    /// use agb::input::Button;
+    /// use agb::test_runner::run_input_script;
+    ///
+    /// // recorded with agb::input::Recorder while playing the game
+    /// static WALK_RIGHT: &[u8] = &[0x10, 0x00, 0x3c, 0x00];
+    ///
+    /// let mut player_x = 0;
+    /// run_input_script(WALK_RIGHT, 90, |input| {
+    ///     if input.is_pressed(Button::RIGHT) {
+    ///         player_x += 1;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(player_x, 60);
+    /// # }
+    /// ```
+    pub fn run_input_script(
+        recording: &[u8],
+        frames: usize,
+        mut frame: impl FnMut(&crate::input::ButtonController),
+    ) {
+        let vblank = crate::interrupt::VBlank::get();
+        let mut input = crate::input::ButtonController::new();
+        let mut replayer = crate::input::Replayer::new(recording);
+
+        for _ in 0..frames {
+            replayer.update(&mut input);
+            frame(&input);
+            vblank.wait_for_vblank();
+        }
+    }
+
+    /// A hash of everything which decides what is shown on the screen: the
+    /// display registers, palettes, video memory and objects. Two screens with
+    /// the same hash look the same, so a test can check the result of running
+    /// a game against a hash from a run which was known to be right, which is
+    /// quicker to update than a screenshot.
+    ///
+    /// Objects are only copied to the screen by
+    /// [`ObjectController::commit`](crate::display::object::ObjectController::commit),
+    /// so call that first if the game uses them.
+    #[must_use]
+    pub fn screen_hash() -> u64 {
+        use core::hash::Hasher;
+
+        // only the display registers which can be read back. The status and
+        // vcount registers change as the screen is drawn, and the scroll,
+        // affine, window size, mosaic and fade registers are write only.
+        const DISPLAY_CONTROL: (usize, usize) = (0x0400_0000, 0x0400_0002);
+        const BACKGROUND_CONTROL: (usize, usize) = (0x0400_0008, 0x0400_0010);
+        const WINDOW_CONTROL: (usize, usize) = (0x0400_0048, 0x0400_004C);
+        const BLEND_CONTROL: (usize, usize) = (0x0400_0050, 0x0400_0054);
+        const PALETTE_RAM: (usize, usize) = (0x0500_0000, 0x0500_0400);
+        const VRAM: (usize, usize) = (0x0600_0000, 0x0601_8000);
+        const OAM: (usize, usize) = (0x0700_0000, 0x0700_0400);
+
+        let mut hasher = rustc_hash::FxHasher::default();
+        for (start, end) in [
+            DISPLAY_CONTROL,
+            BACKGROUND_CONTROL,
+            WINDOW_CONTROL,
+            BLEND_CONTROL,
+            PALETTE_RAM,
+            VRAM,
+            OAM,
+        ] {
+            for address in (start..end).step_by(2) {
+                hasher.write_u16(unsafe { (address as *const u16).read_volatile() });
+            }
+        }
+
+        hasher.finish()
+    }
+}
+
+/// Checks that the screen matches the screenshot `screenshots/<name>.png` in
+/// the crate being tested, failing the test if it doesn't. This only works
+/// when the tests are run with `mgba-test-runner`, which writes the actual
+/// screen to a temporary file when they don't match.
+///
+/// To add or update screenshots, run the tests with the
+/// `AGB_UPDATE_SCREENSHOTS` environment variable set, which saves the screen
+/// rather than comparing it, and check the new images in.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # fn foo(gba: &mut agb::Gba) {
+/// let mut bitmap = gba.display.video.bitmap3();
+/// bitmap.clear(0x7c00);
+///
+/// agb::assert_screen_matches!("blue_screen");
+/// # }
+/// ```
+#[cfg(any(test, feature = "testing"))]
+#[macro_export]
+macro_rules! assert_screen_matches {
+    ($name: literal) => {
+        $crate::test_runner::assert_image_output(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/screenshots/",
+            $name,
+            ".png"
+        ))
+    };
 }
 
 #[cfg(test)]
@@ -408,6 +899,45 @@ mod test {
         }
     }
 
+    #[test_case]
+    fn input_scripts_are_played_into_the_game(_gba: &mut Gba) {
+        use crate::input::{Button, ButtonController, Recorder};
+        use crate::test_runner::{run_input_script, screen_hash};
+
+        let mut recorder = Recorder::new();
+        let mut input = ButtonController::new();
+        for frame in 0..20 {
+            let buttons = if frame % 4 == 0 { Button::A } else { Button::B };
+            input.set_pressed(buttons.bits() as u16);
+            recorder.record(&input);
+        }
+
+        let mut a_presses = 0;
+        let mut frames = 0;
+        run_input_script(recorder.as_bytes(), 25, |input| {
+            frames += 1;
+            if input.is_just_pressed(Button::A) {
+                a_presses += 1;
+            }
+        });
+
+        assert_eq!(frames, 25);
+        assert_eq!(a_presses, 5);
+
+        let hash = screen_hash();
+        assert_eq!(screen_hash(), hash);
+
+        // the screen being part way through drawing doesn't change the hash
+        crate::interrupt::VBlank::get().wait_for_vblank();
+        assert_eq!(screen_hash(), hash);
+
+        let backdrop = 0x0500_0000 as *mut u16;
+        let colour = unsafe { backdrop.read_volatile() };
+        unsafe { backdrop.write_volatile(colour ^ 1) };
+        assert_ne!(screen_hash(), hash);
+        unsafe { backdrop.write_volatile(colour) };
+    }
+
     #[link_section = ".ewram"]
     static mut EWRAM_TEST: u32 = 5;
     #[test_case]
@@ -428,6 +958,76 @@ mod test {
         }
     }
 
+    #[crate::iwram]
+    fn iwram_function(x: u32) -> u32 {
+        x * 3
+    }
+
+    #[crate::ewram]
+    static mut EWRAM_ATTRIBUTE: u32 = 7;
+
+    #[crate::ewram]
+    static EWRAM_TABLE: [u16; 4] = [1, 2, 3, 4];
+
+    #[crate::rom]
+    static ROM_TABLE: [u16; 4] = [5, 6, 7, 8];
+
+    #[crate::arm]
+    fn arm_function(x: u32) -> u32 {
+        x + 1
+    }
+
+    #[crate::thumb]
+    fn thumb_function(x: u32) -> u32 {
+        x + 2
+    }
+
+    #[test_case]
+    fn arm_and_thumb_functions_can_call_each_other(_gba: &mut Gba) {
+        assert_eq!(arm_function as fn(u32) -> u32 as usize & 1, 0);
+        assert_eq!(thumb_function as fn(u32) -> u32 as usize & 1, 1);
+        assert_eq!(thumb_function(arm_function(1)), 4);
+    }
+
+    #[test_case]
+    fn iwram_and_ewram_attributes_place_items(_gba: &mut Gba) {
+        let function = iwram_function as fn(u32) -> u32 as usize;
+        assert!(
+            (0x0300_0000..0x0300_8000).contains(&function),
+            "function should be in iwram, but was at {:#010X}",
+            function
+        );
+        assert_eq!(
+            function & 1,
+            0,
+            "function should be arm code, which doesn't set the lowest bit"
+        );
+        assert_eq!(iwram_function(4), 12);
+
+        let address = unsafe { &EWRAM_ATTRIBUTE as *const u32 as usize };
+        assert!((0x0200_0000..0x0204_0000).contains(&address));
+        assert_eq!(unsafe { EWRAM_ATTRIBUTE }, 7);
+    }
+
+    #[test_case]
+    fn constant_tables_can_be_placed_in_ewram_or_rom(_gba: &mut Gba) {
+        let ewram = EWRAM_TABLE.as_ptr() as usize;
+        assert!(
+            (0x0200_0000..0x0204_0000).contains(&ewram),
+            "table should be in ewram, but was at {:#010X}",
+            ewram
+        );
+        assert_eq!(EWRAM_TABLE, [1, 2, 3, 4]);
+
+        let rom = ROM_TABLE.as_ptr() as usize;
+        assert!(
+            (0x0800_0000..0x0A00_0000).contains(&rom),
+            "table should be in rom, but was at {:#010X}",
+            rom
+        );
+        assert_eq!(ROM_TABLE, [5, 6, 7, 8]);
+    }
+
     #[link_section = ".iwram"]
     static mut IWRAM_EXPLICIT: u32 = 9;
     #[test_case]
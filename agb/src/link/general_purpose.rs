@@ -0,0 +1,145 @@
+use core::marker::PhantomData;
+
+use super::{LinkController, SavedRegisters, MODE_CONTROL};
+
+const GENERAL_PURPOSE_MODE: u16 = 0b10 << 14;
+const DIRECTION_SHIFT: u16 = 4;
+
+/// One of the 4 pins of the link port which can be controlled in general
+/// purpose mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pin {
+    /// The SC pin, which is the serial clock in the other modes.
+    Sc = 0,
+    /// The SD pin.
+    Sd = 1,
+    /// The SI pin, which is the serial input in the other modes.
+    Si = 2,
+    /// The SO pin, which is the serial output in the other modes.
+    So = 3,
+}
+
+impl Pin {
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+/// Whether a pin is read from or driven by the console.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The pin's level is set by whatever is connected to it.
+    Input,
+    /// The pin's level is set by the console.
+    Output,
+}
+
+/// General purpose mode, where each pin of the link port can be read or set
+/// directly, for driving homemade hardware such as LEDs or buttons.
+///
+/// Every pin starts as an input. The port uses 3.3V logic, so anything
+/// connected to it must be too.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::link::{Direction, Pin};
+///
+/// # fn foo(gba: &mut agb::Gba) {
+/// let pins = gba.link.general_purpose();
+/// pins.set_direction(Pin::So, Direction::Output);
+///
+/// // light an LED on SO while a button connected to SI is held
+/// loop {
+///     pins.set_high(Pin::So, !pins.is_high(Pin::Si));
+/// }
+/// # }
+/// ```
+pub struct GeneralPurpose<'a> {
+    _saved: SavedRegisters,
+    _link: PhantomData<&'a mut LinkController>,
+}
+
+impl GeneralPurpose<'_> {
+    pub(super) fn new() -> Self {
+        let saved = SavedRegisters::save();
+        MODE_CONTROL.set(GENERAL_PURPOSE_MODE);
+
+        Self {
+            _saved: saved,
+            _link: PhantomData,
+        }
+    }
+
+    /// Sets whether a pin is an input or an output.
+    pub fn set_direction(&self, pin: Pin, direction: Direction) {
+        let bit = pin.bit() << DIRECTION_SHIFT;
+        let mode = MODE_CONTROL.get();
+
+        MODE_CONTROL.set(match direction {
+            Direction::Input => mode & !bit,
+            Direction::Output => mode | bit,
+        });
+    }
+
+    /// Whether a pin is an input or an output.
+    #[must_use]
+    pub fn direction(&self, pin: Pin) -> Direction {
+        if MODE_CONTROL.get() & (pin.bit() << DIRECTION_SHIFT) != 0 {
+            Direction::Output
+        } else {
+            Direction::Input
+        }
+    }
+
+    /// Sets the level of an output pin. This has no effect on inputs.
+    pub fn set_high(&self, pin: Pin, high: bool) {
+        let mode = MODE_CONTROL.get();
+
+        MODE_CONTROL.set(if high {
+            mode | pin.bit()
+        } else {
+            mode & !pin.bit()
+        });
+    }
+
+    /// Whether a pin is high. For outputs, this is the level it is being set
+    /// to.
+    #[must_use]
+    pub fn is_high(&self, pin: Pin) -> bool {
+        MODE_CONTROL.get() & pin.bit() != 0
+    }
+
+    /// The levels of all 4 pins, with [`Pin::Sc`] in bit 0 up to [`Pin::So`]
+    /// in bit 3.
+    #[must_use]
+    pub fn levels(&self) -> u8 {
+        (MODE_CONTROL.get() & 0b1111) as u8
+    }
+
+    /// Sets the levels of every output pin at once, with [`Pin::Sc`] in bit 0
+    /// up to [`Pin::So`] in bit 3.
+    pub fn set_levels(&self, levels: u8) {
+        MODE_CONTROL.set((MODE_CONTROL.get() & !0b1111) | u16::from(levels & 0b1111));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn general_purpose_pins_can_be_set(gba: &mut crate::Gba) {
+        let pins = gba.link.general_purpose();
+        assert_eq!(pins.direction(Pin::So), Direction::Input);
+
+        pins.set_direction(Pin::So, Direction::Output);
+        assert_eq!(pins.direction(Pin::So), Direction::Output);
+
+        pins.set_high(Pin::So, true);
+        assert!(pins.is_high(Pin::So));
+
+        pins.set_high(Pin::So, false);
+        assert!(!pins.is_high(Pin::So));
+    }
+}
@@ -0,0 +1,167 @@
+#![deny(missing_docs)]
+//! Communicating with other consoles over the link cable.
+//!
+//! The link port can only be in one mode at a time, so each mode is started
+//! from the [`LinkController`] in [`Gba`](crate::Gba), which it borrows for as
+//! long as it is in use. Dropping the mode's handle puts the link port back
+//! the way it was.
+//!
+//! * [`Multiplayer`] exchanges a 16-bit word between up to 4 consoles connected
+//!   with a Game Boy Advance link cable.
+//! * [`Uart`] sends and receives bytes with the serial protocol used by PCs,
+//!   for tools which talk to the game while it runs.
+//! * [`GeneralPurpose`] reads and sets each pin of the link port directly.
+//!
+//! [`LinkController::send_multiboot`] sends a program to consoles without a
+//! cartridge, so that several people can play with only one copy of the game.
+
+use crate::memory_mapped::MemoryMapped;
+
+mod general_purpose;
+mod multiboot;
+mod multiplayer;
+mod uart;
+
+pub use general_purpose::{Direction, GeneralPurpose, Pin};
+pub use multiboot::MultibootError;
+pub use multiplayer::{Multiplayer, Received};
+pub use uart::Uart;
+
+/// The received words in multiplayer mode, one for each console.
+const SIO_MULTI: [MemoryMapped<u16>; 4] = unsafe {
+    [
+        MemoryMapped::new(0x0400_0120),
+        MemoryMapped::new(0x0400_0122),
+        MemoryMapped::new(0x0400_0124),
+        MemoryMapped::new(0x0400_0126),
+    ]
+};
+const SIO_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0128) };
+/// The word to send in multiplayer mode, which is also the 8-bit data
+/// register in the other modes.
+const SIO_SEND: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_012a) };
+const MODE_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0134) };
+
+/// The serial control bit which requests an interrupt when a transfer
+/// finishes, which is in the same place in every mode.
+const SIO_INTERRUPT: u16 = 1 << 14;
+
+// serial control bits for multiplayer mode, which is used by both `Multiplayer`
+// and `LinkController::send_multiboot`
+const MULTIPLAYER_MODE: u16 = 2 << 12;
+const IS_CHILD: u16 = 1 << 2;
+const BUSY: u16 = 1 << 7;
+
+/// How fast data is sent over the link cable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaudRate {
+    /// 9600 bits per second.
+    B9600 = 0,
+    /// 38400 bits per second.
+    B38400 = 1,
+    /// 57600 bits per second.
+    B57600 = 2,
+    /// 115200 bits per second, the fastest.
+    B115200 = 3,
+}
+
+/// Saves the link port registers and restores them when dropped, so that
+/// each mode leaves the port as it found it.
+struct SavedRegisters {
+    control: u16,
+    mode: u16,
+}
+
+impl SavedRegisters {
+    fn save() -> Self {
+        Self {
+            control: SIO_CONTROL.get() & !SIO_INTERRUPT,
+            mode: MODE_CONTROL.get(),
+        }
+    }
+}
+
+impl Drop for SavedRegisters {
+    fn drop(&mut self) {
+        SIO_CONTROL.set(self.control);
+        MODE_CONTROL.set(self.mode);
+    }
+}
+
+/// Manages access to the link port.
+#[non_exhaustive]
+pub struct LinkController {}
+
+impl LinkController {
+    pub(crate) const fn new() -> Self {
+        Self {}
+    }
+
+    /// Starts multiplayer mode, which connects up to 4 consoles using a Game
+    /// Boy Advance link cable. Every console must use the same baud rate.
+    pub fn multiplayer(&mut self, baud_rate: BaudRate) -> Multiplayer<'_> {
+        Multiplayer::new(baud_rate)
+    }
+
+    /// Starts UART mode, for talking to a PC or microcontroller. With flow
+    /// control, bytes are only sent while the other end sets the SC pin low to
+    /// say it is ready for them.
+    pub fn uart(&mut self, baud_rate: BaudRate, flow_control: bool) -> Uart<'_> {
+        Uart::new(baud_rate, flow_control)
+    }
+
+    /// Starts general purpose mode, where each pin of the link port is read
+    /// or set directly.
+    pub fn general_purpose(&mut self) -> GeneralPurpose<'_> {
+        GeneralPurpose::new()
+    }
+
+    /// Sends a program to the consoles connected by a Game Boy Advance link
+    /// cable which have been turned on without a cartridge, and waits until
+    /// they have received it. This console must have the purple plug of the
+    /// cable in it.
+    ///
+    /// The program must be built for multiboot, so that it runs from work RAM.
+    /// It can be another program included with
+    /// [`include_bytes!`](core::include_bytes), for example a smaller version
+    /// of the game which the other players run, or this game itself if it
+    /// was built for multiboot, from [`running_multiboot_image`]. Sending takes
+    /// a few seconds for a large program, during which interrupts are
+    /// disabled.
+    ///
+    /// Consoles only accept a program while they are showing the logo after
+    /// being turned on, so this returns [`MultibootError::NoClients`] if
+    /// none are found straight away, and should be called again to keep
+    /// looking.
+    ///
+    /// ```rust,no_run
+    /// # #![no_std]
+    /// # #![no_main]
+    /// use agb::link::MultibootError;
+    ///
+    /// # fn foo(gba: &mut agb::Gba) {
+    /// let game = agb::link::running_multiboot_image().expect("built for multiboot");
+    ///
+    /// let vblank = agb::interrupt::VBlank::get();
+    /// loop {
+    ///     match gba.link.send_multiboot(game) {
+    ///         Ok(()) => break,
+    ///         Err(MultibootError::NoClients) => vblank.wait_for_vblank(),
+    ///         Err(e) => panic!("failed to send the game: {:?}", e),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn send_multiboot(&mut self, image: &[u8]) -> Result<(), MultibootError> {
+        multiboot::send(image)
+    }
+}
+
+/// The program which is running, if it was built for multiboot, so that it
+/// can send itself to other consoles with
+/// [`LinkController::send_multiboot`]. Statics stored in work RAM are sent as
+/// they are now, so this should be sent before changing any of them.
+#[must_use]
+pub fn running_multiboot_image() -> Option<&'static [u8]> {
+    multiboot::running_image()
+}
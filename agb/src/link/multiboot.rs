@@ -0,0 +1,254 @@
+use super::{
+    BaudRate, SavedRegisters, BUSY, IS_CHILD, MODE_CONTROL, MULTIPLAYER_MODE, SIO_CONTROL,
+    SIO_MULTI, SIO_SEND,
+};
+use crate::interrupt::VBlank;
+use crate::syscall::{self, MultiBootParameters};
+
+/// The length of the header at the start of every program.
+const HEADER_LENGTH: usize = 0xc0;
+/// The largest program which fits in the receiving console's work RAM.
+const MAX_LENGTH: usize = 0x4_0000;
+
+/// How the BIOS draws the logo while the program downloads, which sets its
+/// colour and how it moves.
+const PALETTE_DATA: u8 = 0xd1;
+
+/// How many times a console is asked whether it is ready before giving up.
+const ATTEMPTS: usize = 16;
+
+/// Why a program couldn't be sent with
+/// [`send_multiboot`](super::LinkController::send_multiboot).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultibootError {
+    /// No consoles waiting to receive a program were found, which includes
+    /// when this console isn't the one with the purple plug of the cable in
+    /// it.
+    NoClients,
+    /// A console stopped responding part way through, for example because
+    /// the cable was unplugged.
+    Failed,
+    /// The program can't be sent. It must start 4 byte aligned with a
+    /// header, be at most 256kB long, and be a multiple of 16 bytes longer
+    /// than its header.
+    InvalidImage,
+}
+
+/// Sends one word to every console at once, and returns what each of the
+/// other consoles sent back.
+fn transfer(word: u16) -> Result<[u16; 3], MultibootError> {
+    SIO_SEND.set(word);
+    SIO_CONTROL.set(SIO_CONTROL.get() | BUSY);
+
+    // a transfer takes under a millisecond, even with every console
+    // connected, so this only runs out if something is very wrong
+    for _ in 0..0x1_0000 {
+        if SIO_CONTROL.get() & BUSY == 0 {
+            return Ok([SIO_MULTI[1].get(), SIO_MULTI[2].get(), SIO_MULTI[3].get()]);
+        }
+    }
+
+    Err(MultibootError::Failed)
+}
+
+/// The bit each console uses to identify itself during the handshake.
+fn client_bit(client: usize) -> u16 {
+    1 << (client + 1)
+}
+
+/// Checks that every console being sent to answered with its own bit in the
+/// low byte, and nothing else.
+fn check_replies(
+    replies: [u16; 3],
+    clients: u16,
+    high_byte: Option<u16>,
+) -> Result<(), MultibootError> {
+    for (client, reply) in replies.iter().copied().enumerate() {
+        let bit = client_bit(client);
+        if clients & bit == 0 {
+            continue;
+        }
+
+        let high_matches = high_byte.is_none_or(|high| reply >> 8 == high);
+        if reply & 0xff != bit || !high_matches {
+            return Err(MultibootError::Failed);
+        }
+    }
+
+    Ok(())
+}
+
+fn wait_sixteenth_of_a_second(vblank: &VBlank) {
+    for _ in 0..4 {
+        vblank.wait_for_vblank();
+    }
+}
+
+pub(super) fn send(image: &[u8]) -> Result<(), MultibootError> {
+    if image.as_ptr() as usize % 4 != 0
+        || image.len() > MAX_LENGTH
+        || image.len() < HEADER_LENGTH + 0x100
+        || (image.len() - HEADER_LENGTH) % 16 != 0
+    {
+        return Err(MultibootError::InvalidImage);
+    }
+
+    let _saved = SavedRegisters::save();
+    MODE_CONTROL.set(0);
+    SIO_CONTROL.set(BaudRate::B115200 as u16 | MULTIPLAYER_MODE);
+
+    if SIO_CONTROL.get() & IS_CHILD != 0 {
+        return Err(MultibootError::NoClients);
+    }
+
+    let vblank = VBlank::get();
+
+    // consoles waiting for a program reply with their bit once they are
+    // ready
+    let mut clients = 0;
+    for _ in 0..ATTEMPTS {
+        let replies = transfer(0x6200)?;
+
+        clients = 0;
+        for (client, reply) in replies.iter().copied().enumerate() {
+            if reply == 0x7200 | client_bit(client) {
+                clients |= client_bit(client);
+            }
+        }
+
+        if clients != 0 {
+            break;
+        }
+
+        wait_sixteenth_of_a_second(&vblank);
+    }
+
+    if clients == 0 {
+        return Err(MultibootError::NoClients);
+    }
+
+    check_replies(transfer(0x6100 | clients)?, clients, Some(0x72))?;
+
+    // the header is sent a halfword at a time, with each console replying
+    // with how much it still expects in the high byte
+    for halfword in image[..HEADER_LENGTH].as_chunks::<2>().0 {
+        let replies = transfer(u16::from_le_bytes(*halfword))?;
+        check_replies(replies, clients, None)?;
+    }
+
+    check_replies(transfer(0x6200)?, clients, Some(0))?;
+    check_replies(transfer(0x6200 | clients)?, clients, Some(0x72))?;
+
+    // each console answers the palette with a random byte once it has
+    // finished checking the header, which is mixed into the handshake
+    let mut client_data = [0xff; 3];
+    let mut answered = false;
+    for _ in 0..ATTEMPTS {
+        let replies = transfer(0x6300 | u16::from(PALETTE_DATA))?;
+
+        answered = true;
+        for (client, reply) in replies.iter().copied().enumerate() {
+            if clients & client_bit(client) == 0 {
+                continue;
+            }
+
+            if reply >> 8 == 0x73 {
+                client_data[client] = reply as u8;
+            } else {
+                answered = false;
+            }
+        }
+
+        if answered {
+            break;
+        }
+
+        wait_sixteenth_of_a_second(&vblank);
+    }
+
+    if !answered {
+        return Err(MultibootError::Failed);
+    }
+
+    let handshake_data = client_data
+        .iter()
+        .fold(0x11_u8, |sum, &data| sum.wrapping_add(data));
+
+    let replies = transfer(0x6400 | u16::from(handshake_data))?;
+    for (client, reply) in replies.iter().copied().enumerate() {
+        if clients & client_bit(client) != 0 && reply >> 8 != 0x73 {
+            return Err(MultibootError::Failed);
+        }
+    }
+
+    wait_sixteenth_of_a_second(&vblank);
+
+    let parameters = MultiBootParameters {
+        handshake_data,
+        client_data,
+        palette_data: PALETTE_DATA,
+        client_bit: clients as u8,
+        boot_start: image.as_ptr() as usize + HEADER_LENGTH,
+        boot_end: image.as_ptr() as usize + image.len(),
+        ..Default::default()
+    };
+
+    // the BIOS sends the rest of the program with interrupts disabled
+    match unsafe { syscall::multi_boot(&parameters, 1) } {
+        0 => Ok(()),
+        _ => Err(MultibootError::Failed),
+    }
+}
+
+/// The program which is running, if it was built to be sent by multiboot.
+pub(super) fn running_image() -> Option<&'static [u8]> {
+    extern "C" {
+        static __text_start: u32;
+        static __ewram_end: u32;
+    }
+
+    let start = (unsafe { &__text_start }) as *const u32 as usize;
+    let end = (unsafe { &__ewram_end }) as *const u32 as usize;
+
+    if start != 0x0200_0000 {
+        return None;
+    }
+
+    // the image must be a multiple of 16 bytes after the header, and
+    // everything after the end of it is still work RAM
+    let length = (end - start - HEADER_LENGTH).next_multiple_of(16) + HEADER_LENGTH;
+    Some(unsafe {
+        core::slice::from_raw_parts(start as *const u8, length.max(HEADER_LENGTH + 0x100))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn invalid_images_are_rejected(gba: &mut crate::Gba) {
+        static SHORT: [u8; HEADER_LENGTH] = [0; HEADER_LENGTH];
+        assert_eq!(
+            gba.link.send_multiboot(&SHORT),
+            Err(MultibootError::InvalidImage)
+        );
+
+        // the tests aren't built for multiboot, so have to come from the
+        // cartridge
+        assert_eq!(super::super::running_multiboot_image(), None);
+    }
+
+    #[test_case]
+    fn multiboot_without_a_cable_finds_no_clients(gba: &mut crate::Gba) {
+        static IMAGE: [u32; (HEADER_LENGTH + 0x100) / 4] = [0; (HEADER_LENGTH + 0x100) / 4];
+        let image = unsafe {
+            core::slice::from_raw_parts(IMAGE.as_ptr().cast::<u8>(), HEADER_LENGTH + 0x100)
+        };
+
+        assert_eq!(
+            gba.link.send_multiboot(image),
+            Err(MultibootError::NoClients)
+        );
+    }
+}
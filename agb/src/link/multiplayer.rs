@@ -0,0 +1,312 @@
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use bare_metal::Mutex;
+
+use super::{
+    BaudRate, LinkController, SavedRegisters, BUSY, IS_CHILD, MODE_CONTROL, MULTIPLAYER_MODE,
+    SIO_CONTROL, SIO_INTERRUPT, SIO_MULTI, SIO_SEND,
+};
+use crate::collections::RingBuffer;
+use crate::interrupt::{add_interrupt_handler, free, Interrupt, InterruptHandler};
+
+/// Sent by consoles with nothing to send, and read for consoles which aren't
+/// connected.
+const IDLE: u16 = 0xffff;
+
+const ALL_READY: u16 = 1 << 3;
+const ERROR: u16 = 1 << 6;
+
+/// The number of words which can be waiting to be sent or read.
+const QUEUE_LENGTH: usize = 16;
+const DEFAULT_TIMEOUT_FRAMES: u16 = 30;
+
+/// The words received in one transfer, one from each console.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Received {
+    words: [Option<u16>; 4],
+}
+
+impl Received {
+    const NOTHING: Self = Self { words: [None; 4] };
+
+    /// The word sent by a player, or `None` if that console had nothing to
+    /// send or isn't connected. The host is player 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the player is 4 or more.
+    #[must_use]
+    pub fn word(&self, player: usize) -> Option<u16> {
+        self.words[player]
+    }
+
+    /// The words sent by every player.
+    #[must_use]
+    pub fn words(&self) -> [Option<u16>; 4] {
+        self.words
+    }
+}
+
+struct State {
+    outgoing: RingBuffer<u16, QUEUE_LENGTH>,
+    received: RingBuffer<Received, QUEUE_LENGTH>,
+    /// Whether the send register holds a word from the queue, rather than
+    /// the idle value.
+    send_loaded: bool,
+    frames_since_transfer: u16,
+    timeout_frames: u16,
+    transfers: u32,
+    errors: u32,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            outgoing: RingBuffer::new(),
+            received: RingBuffer::new(),
+            send_loaded: false,
+            frames_since_transfer: 0,
+            timeout_frames: DEFAULT_TIMEOUT_FRAMES,
+            transfers: 0,
+            errors: 0,
+        }
+    }
+
+    /// Puts the next word to send in the send register, ready for the next
+    /// transfer.
+    fn load_next(&mut self) {
+        let next = self.outgoing.pop();
+        SIO_SEND.set(next.unwrap_or(IDLE));
+        self.send_loaded = next.is_some();
+    }
+}
+
+static STATE: Mutex<RefCell<State>> = Mutex::new(RefCell::new(State::new()));
+
+/// Multiplayer mode, where up to 4 consoles connected with a link cable each
+/// send a 16-bit word to all of the others in every transfer.
+///
+/// One console is the host, the one with the purple plug of the cable in it,
+/// which starts every transfer. The others are guests. Transfers are started by
+/// the host calling [`update`](Multiplayer::update) once per frame, which every
+/// console should do anyway to notice when the others have disconnected.
+///
+/// Words are queued by [`send`](Multiplayer::send) and go out one per
+/// transfer. What was received is queued in the serial interrupt, and read
+/// with [`receive`](Multiplayer::receive).
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::link::BaudRate;
+///
+/// # fn foo(gba: &mut agb::Gba) {
+/// let vblank = agb::interrupt::VBlank::get();
+/// let input = agb::input::ButtonController::new();
+/// let link = gba.link.multiplayer(BaudRate::B115200);
+///
+/// loop {
+///     let _ = link.send(input.is_pressed(agb::input::Button::A) as u16);
+///
+///     while let Some(received) = link.receive() {
+///         for player in 0..4 {
+///             if let Some(word) = received.word(player) {
+///                 agb::println!("player {} sent {}", player, word);
+///             }
+///         }
+///     }
+///
+///     link.update();
+///     vblank.wait_for_vblank();
+/// }
+/// # }
+/// ```
+pub struct Multiplayer<'a> {
+    _handler: InterruptHandler<'static>,
+    _saved: SavedRegisters,
+    _link: PhantomData<&'a mut LinkController>,
+}
+
+impl Multiplayer<'_> {
+    pub(super) fn new(baud_rate: BaudRate) -> Self {
+        let saved = SavedRegisters::save();
+
+        free(|cs| {
+            let mut state = STATE.borrow(cs).borrow_mut();
+            *state = State::new();
+
+            MODE_CONTROL.set(0);
+            SIO_CONTROL.set(baud_rate as u16 | MULTIPLAYER_MODE | SIO_INTERRUPT);
+            state.load_next();
+        });
+
+        let handler = add_interrupt_handler(Interrupt::Serial, |cs| {
+            let mut state = STATE.borrow(cs).borrow_mut();
+
+            if SIO_CONTROL.get() & ERROR != 0 {
+                state.errors += 1;
+            } else {
+                let mut received = Received::NOTHING;
+                for (word, register) in received.words.iter_mut().zip(SIO_MULTI) {
+                    let value = register.get();
+                    *word = (value != IDLE).then_some(value);
+                }
+
+                if received != Received::NOTHING {
+                    let _ = state.received.push_overwriting(received);
+                }
+
+                state.transfers += 1;
+                state.frames_since_transfer = 0;
+            }
+
+            state.load_next();
+        });
+
+        Self {
+            _handler: handler,
+            _saved: saved,
+            _link: PhantomData,
+        }
+    }
+
+    /// The value sent by consoles with nothing to send, which can't be sent
+    /// with [`send`](Multiplayer::send).
+    pub const IDLE: u16 = IDLE;
+
+    /// Queues a word to send to the other consoles, and returns false if the
+    /// queue is full. Each transfer sends one word, with consoles that have
+    /// nothing queued sending nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the word is [`IDLE`](Multiplayer::IDLE), as it can't be told
+    /// apart from sending nothing.
+    #[must_use]
+    pub fn send(&self, word: u16) -> bool {
+        assert_ne!(word, IDLE, "0xffff can't be sent in multiplayer mode");
+
+        free(|cs| {
+            let mut state = STATE.borrow(cs).borrow_mut();
+            if state.outgoing.push(word).is_err() {
+                return false;
+            }
+
+            if !state.send_loaded && SIO_CONTROL.get() & BUSY == 0 {
+                state.load_next();
+            }
+
+            true
+        })
+    }
+
+    /// The oldest transfer which hasn't been read yet, ignoring transfers where
+    /// no console sent anything. Only the most recent transfers are kept, so
+    /// this should be called every frame.
+    #[must_use]
+    pub fn receive(&self) -> Option<Received> {
+        free(|cs| STATE.borrow(cs).borrow_mut().received.pop())
+    }
+
+    /// Throws away every word waiting to be sent or read.
+    pub fn clear(&self) {
+        free(|cs| {
+            let mut state = STATE.borrow(cs).borrow_mut();
+            state.outgoing.clear();
+            state.received.clear();
+        });
+    }
+
+    /// Call this once per frame. On the host this starts the next transfer,
+    /// and on every console it counts the frames since the last transfer to
+    /// tell when the others have disconnected.
+    pub fn update(&self) {
+        free(|cs| {
+            let mut state = STATE.borrow(cs).borrow_mut();
+            state.frames_since_transfer = state.frames_since_transfer.saturating_add(1);
+
+            let control = SIO_CONTROL.get();
+            if self.is_host() && control & ALL_READY != 0 && control & BUSY == 0 {
+                SIO_CONTROL.set(control | BUSY);
+            }
+        });
+    }
+
+    /// Whether this console is the host, which starts the transfers. Without a
+    /// cable connected, every console is a guest.
+    #[must_use]
+    pub fn is_host(&self) -> bool {
+        SIO_CONTROL.get() & IS_CHILD == 0
+    }
+
+    /// Whether every console connected by the cable is in multiplayer mode.
+    #[must_use]
+    pub fn all_ready(&self) -> bool {
+        SIO_CONTROL.get() & ALL_READY != 0
+    }
+
+    /// This console's player number, from 0 for the host to 3, which is only
+    /// known once a transfer has happened.
+    #[must_use]
+    pub fn player_id(&self) -> Option<u8> {
+        free(|cs| {
+            (STATE.borrow(cs).borrow().transfers > 0)
+                .then(|| ((SIO_CONTROL.get() >> 4) & 0b11) as u8)
+        })
+    }
+
+    /// Whether a transfer has finished within the timeout, which is 30 frames
+    /// unless changed by [`set_timeout`](Multiplayer::set_timeout).
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        free(|cs| {
+            let state = STATE.borrow(cs).borrow();
+            state.transfers > 0 && state.frames_since_transfer <= state.timeout_frames
+        })
+    }
+
+    /// Sets how many calls to [`update`](Multiplayer::update) can happen
+    /// without a transfer before the consoles count as disconnected.
+    pub fn set_timeout(&self, frames: u16) {
+        free(|cs| STATE.borrow(cs).borrow_mut().timeout_frames = frames);
+    }
+
+    /// The number of transfers which have failed, for example because a cable
+    /// was unplugged part way through.
+    #[must_use]
+    pub fn errors(&self) -> u32 {
+        free(|cs| STATE.borrow(cs).borrow().errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn multiplayer_without_a_cable_is_disconnected(gba: &mut crate::Gba) {
+        let link = gba.link.multiplayer(BaudRate::B115200);
+
+        assert!(link.send(5));
+        for _ in 0..QUEUE_LENGTH {
+            assert!(link.send(6));
+        }
+        assert!(!link.send(7), "the queue should be full");
+
+        link.update();
+        assert!(!link.is_connected());
+        assert_eq!(link.player_id(), None);
+        assert_eq!(link.receive(), None);
+
+        link.clear();
+        assert!(link.send(8));
+    }
+
+    #[test_case]
+    fn multiplayer_requests_the_serial_interrupt(gba: &mut crate::Gba) {
+        let _link = gba.link.multiplayer(BaudRate::B115200);
+
+        assert_eq!(SIO_CONTROL.get() & SIO_INTERRUPT, SIO_INTERRUPT);
+    }
+}
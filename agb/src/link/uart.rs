@@ -0,0 +1,226 @@
+use core::cell::RefCell;
+use core::fmt;
+use core::marker::PhantomData;
+
+use bare_metal::Mutex;
+
+use super::{
+    BaudRate, LinkController, SavedRegisters, MODE_CONTROL, SIO_CONTROL, SIO_INTERRUPT, SIO_SEND,
+};
+use crate::collections::RingBuffer;
+use crate::interrupt::{add_interrupt_handler, free, Interrupt, InterruptHandler};
+
+const CLEAR_TO_SEND: u16 = 1 << 2;
+const SEND_FULL: u16 = 1 << 4;
+const RECEIVE_EMPTY: u16 = 1 << 5;
+const ERROR: u16 = 1 << 6;
+const EIGHT_BITS: u16 = 1 << 7;
+const FIFO: u16 = 1 << 8;
+const SEND_ENABLE: u16 = 1 << 10;
+const RECEIVE_ENABLE: u16 = 1 << 11;
+const UART_MODE: u16 = 3 << 12;
+
+/// The number of bytes which can be waiting to be sent or read.
+const BUFFER_LENGTH: usize = 256;
+
+struct State {
+    outgoing: RingBuffer<u8, BUFFER_LENGTH>,
+    received: RingBuffer<u8, BUFFER_LENGTH>,
+    errors: u32,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            outgoing: RingBuffer::new(),
+            received: RingBuffer::new(),
+            errors: 0,
+        }
+    }
+
+    /// Moves bytes between the hardware and the buffers for as long as the
+    /// hardware can take them.
+    fn transfer(&mut self) {
+        loop {
+            let control = SIO_CONTROL.get();
+
+            if control & ERROR != 0 {
+                self.errors += 1;
+            }
+
+            if control & RECEIVE_EMPTY == 0 {
+                // if the buffer is full, the oldest byte is lost
+                let _ = self.received.push_overwriting(SIO_SEND.get() as u8);
+            } else if control & SEND_FULL == 0 && !self.outgoing.is_empty() {
+                if let Some(byte) = self.outgoing.pop() {
+                    SIO_SEND.set(u16::from(byte));
+                }
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+static STATE: Mutex<RefCell<State>> = Mutex::new(RefCell::new(State::new()));
+
+/// UART mode, which sends and receives bytes with the serial protocol used by
+/// PCs and microcontrollers, for example through a USB UART adapter wired to
+/// a link cable.
+///
+/// Bytes are sent with 8 data bits, no parity and one stop bit, which is
+/// usually written as 8N1. Both directions are buffered, with the buffers
+/// filled and emptied in the serial interrupt, so the game doesn't need to
+/// wait for the bytes to be sent.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::link::BaudRate;
+/// use core::fmt::Write;
+///
+/// # fn foo(gba: &mut agb::Gba) {
+/// let mut uart = gba.link.uart(BaudRate::B115200, false);
+///
+/// writeln!(uart, "hello from the game boy advance").unwrap();
+///
+/// let mut command = [0; 16];
+/// let length = uart.read(&mut command);
+/// # }
+/// ```
+pub struct Uart<'a> {
+    _handler: InterruptHandler<'static>,
+    _saved: SavedRegisters,
+    _link: PhantomData<&'a mut LinkController>,
+}
+
+impl Uart<'_> {
+    pub(super) fn new(baud_rate: BaudRate, flow_control: bool) -> Self {
+        let saved = SavedRegisters::save();
+
+        let flow_control = if flow_control { CLEAR_TO_SEND } else { 0 };
+
+        free(|cs| {
+            *STATE.borrow(cs).borrow_mut() = State::new();
+
+            MODE_CONTROL.set(0);
+            // the fifos are cleared by turning them off and on again
+            let control = baud_rate as u16 | flow_control | EIGHT_BITS | UART_MODE | SIO_INTERRUPT;
+            SIO_CONTROL.set(control);
+            SIO_CONTROL.set(control | FIFO | SEND_ENABLE | RECEIVE_ENABLE);
+        });
+
+        let handler = add_interrupt_handler(Interrupt::Serial, |cs| {
+            STATE.borrow(cs).borrow_mut().transfer();
+        });
+
+        Self {
+            _handler: handler,
+            _saved: saved,
+            _link: PhantomData,
+        }
+    }
+
+    /// Queues as many of the bytes as there is space for, and returns how many
+    /// were queued.
+    #[must_use]
+    pub fn write(&self, bytes: &[u8]) -> usize {
+        free(|cs| {
+            let mut state = STATE.borrow(cs).borrow_mut();
+
+            let mut written = 0;
+            for &byte in bytes {
+                if state.outgoing.push(byte).is_err() {
+                    break;
+                }
+
+                written += 1;
+            }
+
+            // start sending, after which the interrupt keeps it going
+            state.transfer();
+            written
+        })
+    }
+
+    /// Queues all of the bytes, waiting for space in the buffer if needed.
+    pub fn write_all(&self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let written = self.write(bytes);
+            bytes = &bytes[written..];
+        }
+    }
+
+    /// Moves the bytes which have been received into `buffer`, and returns how
+    /// many there were.
+    pub fn read(&self, buffer: &mut [u8]) -> usize {
+        free(|cs| {
+            let mut state = STATE.borrow(cs).borrow_mut();
+            state.transfer();
+
+            let mut read = 0;
+            for slot in buffer {
+                match state.received.pop() {
+                    Some(byte) => *slot = byte,
+                    None => break,
+                }
+                read += 1;
+            }
+
+            read
+        })
+    }
+
+    /// The next byte which has been received.
+    #[must_use]
+    pub fn read_byte(&self) -> Option<u8> {
+        let mut byte = [0];
+        (self.read(&mut byte) == 1).then_some(byte[0])
+    }
+
+    /// Whether every queued byte has been handed to the hardware to send.
+    #[must_use]
+    pub fn is_sent(&self) -> bool {
+        free(|cs| STATE.borrow(cs).borrow().outgoing.is_empty())
+    }
+
+    /// The number of errors seen since UART mode started, which happen when a
+    /// byte isn't received properly or arrives when the hardware is already
+    /// full.
+    #[must_use]
+    pub fn errors(&self) -> u32 {
+        free(|cs| STATE.borrow(cs).borrow().errors)
+    }
+}
+
+impl fmt::Write for Uart<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn uart_buffers_bytes_without_a_cable(gba: &mut crate::Gba) {
+        let uart = gba.link.uart(BaudRate::B115200, true);
+
+        let long = [0x55; BUFFER_LENGTH + 16];
+        let written = uart.write(&long);
+        assert!(written >= BUFFER_LENGTH, "only wrote {} bytes", written);
+
+        let mut buffer = [0; 4];
+        assert_eq!(uart.read(&mut buffer), 0);
+        assert_eq!(uart.read_byte(), None);
+    }
+
+    #[test_case]
+    fn uart_requests_the_serial_interrupt(gba: &mut crate::Gba) {
+        let _uart = gba.link.uart(BaudRate::B115200, false);
+
+        assert_eq!(SIO_CONTROL.get() & SIO_INTERRUPT, SIO_INTERRUPT);
+    }
+}
@@ -0,0 +1,281 @@
+#![deny(missing_docs)]
+//! Logging to the mGBA debug log with levels and per-module filtering.
+//!
+//! The [`error!`](crate::error!), [`warn!`](crate::warn!),
+//! [`info!`](crate::info!), [`debug!`](crate::debug!) and
+//! [`trace!`](crate::trace!) macros log a message at their level, tagged with
+//! the module it came from. Messages are only written if their level is
+//! enabled for that module, so detailed logging can be left in the code and
+//! turned on only for the part of the game being worked on.
+//!
+//! By default everything up to [`Level::Info`] is logged. This can be changed
+//! for everything with [`set_max_level`], or for a module and the modules
+//! inside it with [`set_target_level`].
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::log::{self, Level};
+//!
+//! # fn foo() {
+//! // see everything the mixer does without drowning in messages from elsewhere
+//! log::set_target_level("agb::sound::mixer", Level::Trace);
+//!
+//! let enemies = 5;
+//! agb::info!("spawning {} enemies", enemies);
+//! agb::trace!(target: "my_game::ai", "this is only logged if enabled for my_game::ai");
+//! # }
+//! ```
+//!
+//! Messages above a maximum level can also be removed from the game
+//! completely, with the `log_max_level_error`, `log_max_level_warn`,
+//! `log_max_level_info` or `log_max_level_debug` features. Logging which is
+//! removed this way costs nothing, but needs the game to be rebuilt to turn
+//! it back on.
+
+use core::cell::RefCell;
+use core::fmt;
+
+use bare_metal::Mutex;
+
+use crate::interrupt::free;
+use crate::mgba::{DebugLevel, Mgba};
+
+/// How important a log message is. Less important levels compare as greater.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Something has gone wrong.
+    Error = 1,
+    /// Something could be wrong.
+    Warn,
+    /// Useful information about what the game is doing.
+    Info,
+    /// Information which is only useful when debugging.
+    Debug,
+    /// Very detailed information, which could be logged several times a frame.
+    Trace,
+}
+
+impl Level {
+    fn mgba_level(self) -> DebugLevel {
+        match self {
+            Level::Error => DebugLevel::Error,
+            Level::Warn => DebugLevel::Warning,
+            Level::Info => DebugLevel::Info,
+            Level::Debug | Level::Trace => DebugLevel::Debug,
+        }
+    }
+}
+
+/// The most detailed level which is kept in the game, set with the
+/// `log_max_level_*` features. Messages at a more detailed level are removed
+/// when the game is compiled.
+pub const STATIC_MAX_LEVEL: Level = if cfg!(feature = "log_max_level_error") {
+    Level::Error
+} else if cfg!(feature = "log_max_level_warn") {
+    Level::Warn
+} else if cfg!(feature = "log_max_level_info") {
+    Level::Info
+} else if cfg!(feature = "log_max_level_debug") {
+    Level::Debug
+} else {
+    Level::Trace
+};
+
+/// The number of targets which can have their own level at once.
+const MAX_TARGETS: usize = 8;
+
+struct Filter {
+    max_level: Level,
+    targets: [Option<(&'static str, Level)>; MAX_TARGETS],
+}
+
+static FILTER: Mutex<RefCell<Filter>> = Mutex::new(RefCell::new(Filter {
+    max_level: Level::Info,
+    targets: [None; MAX_TARGETS],
+}));
+
+/// Sets the most detailed level which is logged for targets which haven't
+/// been given their own level with [`set_target_level`].
+pub fn set_max_level(level: Level) {
+    free(|key| FILTER.borrow(key).borrow_mut().max_level = level);
+}
+
+/// Sets the most detailed level which is logged for `target` and any targets
+/// inside it, so `"my_game::enemies"` also covers `"my_game::enemies::boss"`.
+/// When several targets match, the longest one is used.
+///
+/// # Panics
+///
+/// Panics if more than 8 targets are given their own level.
+pub fn set_target_level(target: &'static str, level: Level) {
+    free(|key| {
+        let mut filter = FILTER.borrow(key).borrow_mut();
+
+        let slot = filter
+            .targets
+            .iter_mut()
+            .find(|slot| slot.is_none_or(|(existing, _)| existing == target))
+            .expect("too many log targets have their own level");
+
+        *slot = Some((target, level));
+    });
+}
+
+/// Removes the level set with [`set_target_level`] for exactly `target`.
+pub fn clear_target_level(target: &str) {
+    free(|key| {
+        for slot in &mut FILTER.borrow(key).borrow_mut().targets {
+            if slot.is_some_and(|(existing, _)| existing == target) {
+                *slot = None;
+            }
+        }
+    });
+}
+
+/// Whether a message at `level` from `target` would be logged.
+#[must_use]
+pub fn enabled(level: Level, target: &str) -> bool {
+    if level > STATIC_MAX_LEVEL {
+        return false;
+    }
+
+    free(|key| {
+        let filter = FILTER.borrow(key).borrow();
+
+        let target_level = filter
+            .targets
+            .iter()
+            .flatten()
+            .filter(|(prefix, _)| is_inside(target, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(filter.max_level, |&(_, level)| level);
+
+        level <= target_level
+    })
+}
+
+/// Whether the module path `target` is `prefix` or a module inside it.
+fn is_inside(target: &str, prefix: &str) -> bool {
+    target
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// Used by the logging macros to write a message which is enabled.
+#[doc(hidden)]
+pub fn write(level: Level, target: &str, message: fmt::Arguments) {
+    if let Some(mut mgba) = Mgba::new() {
+        let _ = mgba.print(format_args!("{}: {}", target, message), level.mgba_level());
+    }
+}
+
+/// Logs a message at the given [`Level`], tagged with the current module or
+/// the target given with `target:`.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::log::Level;
+///
+/// # fn foo() {
+/// agb::log!(Level::Warn, "running low on sprites");
+/// agb::log!(target: "my_game::save", Level::Info, "saved in slot {}", 2);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! log {
+    (target: $target: expr, $level: expr, $($arg: tt)+) => {{
+        let level = $level;
+        let target = $target;
+        if level <= $crate::log::STATIC_MAX_LEVEL && $crate::log::enabled(level, target) {
+            $crate::log::write(level, target, format_args!($($arg)+));
+        }
+    }};
+    ($level: expr, $($arg: tt)+) => {
+        $crate::log!(target: module_path!(), $level, $($arg)+)
+    };
+}
+
+/// Logs a message at [`Level::Error`](crate::log::Level::Error). See
+/// [`log!`](crate::log!).
+#[macro_export]
+macro_rules! error {
+    ($($arg: tt)+) => {
+        $crate::__log_at_level!($crate::log::Level::Error, $($arg)+)
+    };
+}
+
+/// Logs a message at [`Level::Warn`](crate::log::Level::Warn). See
+/// [`log!`](crate::log!).
+#[macro_export]
+macro_rules! warn {
+    ($($arg: tt)+) => {
+        $crate::__log_at_level!($crate::log::Level::Warn, $($arg)+)
+    };
+}
+
+/// Logs a message at [`Level::Info`](crate::log::Level::Info). See
+/// [`log!`](crate::log!).
+#[macro_export]
+macro_rules! info {
+    ($($arg: tt)+) => {
+        $crate::__log_at_level!($crate::log::Level::Info, $($arg)+)
+    };
+}
+
+/// Logs a message at [`Level::Debug`](crate::log::Level::Debug). See
+/// [`log!`](crate::log!).
+#[macro_export]
+macro_rules! debug {
+    ($($arg: tt)+) => {
+        $crate::__log_at_level!($crate::log::Level::Debug, $($arg)+)
+    };
+}
+
+/// Logs a message at [`Level::Trace`](crate::log::Level::Trace). See
+/// [`log!`](crate::log!).
+#[macro_export]
+macro_rules! trace {
+    ($($arg: tt)+) => {
+        $crate::__log_at_level!($crate::log::Level::Trace, $($arg)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_at_level {
+    ($level: expr, target: $target: expr, $($arg: tt)+) => {
+        $crate::log!(target: $target, $level, $($arg)+)
+    };
+    ($level: expr, $($arg: tt)+) => {
+        $crate::log!($level, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn targets_can_have_their_own_level(_gba: &mut crate::Gba) {
+        assert!(enabled(Level::Info, "agb::sound::mixer"));
+        assert!(!enabled(Level::Trace, "agb::sound::mixer"));
+
+        set_target_level("agb::sound", Level::Trace);
+        set_target_level("agb::sound::dmg", Level::Warn);
+
+        assert!(enabled(Level::Trace, "agb::sound"));
+        assert!(enabled(Level::Trace, "agb::sound::mixer"));
+        assert!(!enabled(Level::Info, "agb::sound::dmg"));
+        assert!(!enabled(Level::Trace, "agb::soundtrack"));
+
+        crate::trace!(target: "agb::sound::mixer", "logged at {}", "trace");
+        crate::error!("logged from {}", module_path!());
+
+        clear_target_level("agb::sound");
+        clear_target_level("agb::sound::dmg");
+        assert!(!enabled(Level::Trace, "agb::sound::mixer"));
+        assert!(enabled(Level::Warn, "agb::sound::dmg"));
+    }
+}
@@ -0,0 +1,263 @@
+#![deny(missing_docs)]
+//! Finding out how much memory the game uses.
+//!
+//! [`report`] gathers the space used by statics in IWRAM and EWRAM, the
+//! allocators' statistics and the deepest the stack has been, which can be
+//! printed to the mGBA log to keep an eye on how close the game is to running
+//! out of memory.
+//!
+//! The stack and the IWRAM allocator share the IWRAM which isn't used by
+//! statics, and nothing stops them from growing into each other. A
+//! [`StackGuard`] checks every frame that the stack hasn't come close to the
+//! allocations and statics below it, so an overflow panics with a message
+//! rather than causing glitches much later.
+//!
+//! Games which need a different split can give the stack a fixed size in
+//! IWRAM or EWRAM, and the heaps fixed sizes, with the `stack`, `stack_size`,
+//! `iwram_heap_size` and `ewram_heap_size` arguments to
+//! [`#[agb::entry]`](crate::entry). The space is then reserved when the game is
+//! linked, so a layout which doesn't fit fails to link rather than crashing
+//! while the game runs.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! # fn foo() {
+//! agb::println!("{}", agb::mem::report());
+//! # }
+//! ```
+
+use core::fmt;
+
+use crate::agb_alloc;
+use crate::interrupt::{add_interrupt_handler, Interrupt, InterruptHandler};
+use crate::{AllocatorStats, ExternalAllocator, InternalAllocator};
+
+const IWRAM_START: usize = 0x0300_0000;
+const EWRAM_START: usize = 0x0200_0000;
+
+/// Where the BIOS starts the stack
+const BIOS_STACK_TOP: usize = 0x0300_7f00;
+/// Written to the unused memory below the stack at startup, so the part of it
+/// which the stack has used can be found later.
+const STACK_PATTERN: u32 = 0x57ac_57ac;
+/// How close the stack can get to the IWRAM statics and allocations, or the
+/// end of the space reserved for it, before [`check_stack`] reports an
+/// overflow.
+pub const STACK_GUARD_BYTES: usize = 256;
+
+/// Space for the stack or a heap reserved by `#[agb::entry]`.
+#[doc(hidden)]
+#[repr(C, align(8))]
+pub struct Reservation<const N: usize>(pub [u8; N]);
+
+/// Whether `#[agb::entry]` reserved space for the stack, rather than it
+/// sharing the rest of IWRAM with the IWRAM heap.
+pub(crate) fn has_reserved_stack() -> bool {
+    linker_address!(__stack_bottom) != linker_address!(__stack_top)
+}
+
+/// The address the stack grows down from.
+pub(crate) fn stack_top() -> usize {
+    if has_reserved_stack() {
+        linker_address!(__stack_top)
+    } else {
+        BIOS_STACK_TOP
+    }
+}
+
+/// How much memory the game is using, from [`report`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryReport {
+    /// The bytes of IWRAM used by statics, including any functions placed in
+    /// IWRAM.
+    pub iwram_static_bytes: usize,
+    /// The bytes of EWRAM used by statics.
+    pub ewram_static_bytes: usize,
+    /// The most bytes the stack has used since startup.
+    pub stack_peak_bytes: usize,
+    /// The allocator used by `Box`, `Vec` and friends, which uses EWRAM.
+    pub heap: AllocatorStats,
+    /// The allocator for IWRAM. This shares the IWRAM which isn't used by
+    /// statics with the stack.
+    pub iwram_heap: AllocatorStats,
+}
+
+/// Gathers how much memory is used.
+///
+/// The stack usage is found by looking for the deepest part of the stack
+/// which has been written to, so it only counts the stack below any IWRAM
+/// which has been allocated, and is slow enough that it shouldn't be called
+/// every frame.
+#[must_use]
+pub fn report() -> MemoryReport {
+    MemoryReport {
+        iwram_static_bytes: agb_alloc::iwram_data_end() - IWRAM_START,
+        ewram_static_bytes: agb_alloc::data_end() - EWRAM_START,
+        stack_peak_bytes: stack_peak_bytes(),
+        heap: ExternalAllocator::stats(),
+        iwram_heap: InternalAllocator::stats(),
+    }
+}
+
+/// The lowest address the stack can use without overwriting IWRAM statics or
+/// allocations, or going outside of the space reserved for it.
+fn stack_limit() -> usize {
+    if has_reserved_stack() {
+        return linker_address!(__stack_bottom);
+    }
+
+    // a heap with a fixed size ends at __iwram_heap_end, otherwise that is the end of the statics
+    let limit = agb_alloc::iwram_heap_high_water_mark().max(linker_address!(__iwram_heap_end));
+    (limit + 3) & !3
+}
+
+fn stack_peak_bytes() -> usize {
+    let lowest_possible = stack_limit();
+    let top = stack_top();
+
+    let deepest_written = (lowest_possible..top)
+        .step_by(4)
+        .find(|&address| unsafe { (address as *const u32).read_volatile() } != STACK_PATTERN)
+        .unwrap_or(top);
+
+    top - deepest_written
+}
+
+/// Panics if the stack has ever come within [`STACK_GUARD_BYTES`] of the
+/// IWRAM statics or allocations, or the end of the space reserved for it,
+/// which means it has overflowed or is about to.
+///
+/// This only reads the guard bytes, so it is cheap enough to call every frame,
+/// but [`StackGuard`] will do that for you.
+pub fn check_stack() {
+    let limit = stack_limit();
+    let guard_end = (limit + STACK_GUARD_BYTES).min(stack_top());
+
+    let overwritten = (limit..guard_end)
+        .step_by(4)
+        .find(|&address| unsafe { (address as *const u32).read_volatile() } != STACK_PATTERN);
+
+    if let Some(address) = overwritten {
+        panic!(
+            "stack overflow: the stack reached {:#010x}, {} bytes from its limit at {:#010x}",
+            address,
+            address - limit,
+            limit
+        );
+    }
+}
+
+/// Calls [`check_stack`] every vblank for as long as it exists.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// # fn foo() {
+/// let _stack_guard = agb::mem::StackGuard::new();
+/// # }
+/// ```
+pub struct StackGuard {
+    _handler: InterruptHandler<'static>,
+}
+
+impl StackGuard {
+    /// Starts checking the stack every vblank.
+    #[must_use]
+    pub fn new() -> Self {
+        check_stack();
+
+        Self {
+            _handler: add_interrupt_handler(Interrupt::VBlank, |_| check_stack()),
+        }
+    }
+}
+
+impl Default for StackGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "iwram: {} bytes of statics, {} bytes of stack at most, {} of {} bytes allocated",
+            self.iwram_static_bytes,
+            self.stack_peak_bytes,
+            self.iwram_heap.used_bytes,
+            self.iwram_heap.total_bytes
+        )?;
+        write!(
+            f,
+            "ewram: {} bytes of statics, {} of {} bytes allocated, largest free block {} bytes",
+            self.ewram_static_bytes,
+            self.heap.used_bytes,
+            self.heap.total_bytes,
+            self.heap.largest_free_block
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[inline(never)]
+    fn use_stack(depth: u32) -> u32 {
+        let buffer = [depth; 64];
+        let buffer = core::hint::black_box(buffer);
+
+        if depth == 0 {
+            buffer[0]
+        } else {
+            use_stack(depth - 1) + buffer[63]
+        }
+    }
+
+    #[test_case]
+    fn the_stack_and_heaps_are_not_reserved_by_default(_gba: &mut crate::Gba) {
+        assert!(!has_reserved_stack());
+        assert_eq!(stack_top(), BIOS_STACK_TOP);
+
+        assert_eq!(
+            linker_address!(__iwram_heap_start),
+            agb_alloc::iwram_data_end()
+        );
+        assert_eq!(
+            linker_address!(__ewram_heap_start),
+            (agb_alloc::data_end() + 7) & !7
+        );
+    }
+
+    #[test_case]
+    fn report_finds_the_stack_usage(_gba: &mut crate::Gba) {
+        let before = report();
+        assert!(before.iwram_static_bytes > 0);
+        assert!(before.stack_peak_bytes > 0);
+
+        assert_eq!(use_stack(8), 36);
+
+        let after = report();
+        assert!(
+            after.stack_peak_bytes >= before.stack_peak_bytes,
+            "stack peak went from {} to {}",
+            before.stack_peak_bytes,
+            after.stack_peak_bytes
+        );
+        assert!(after.stack_peak_bytes > 8 * 256);
+    }
+
+    #[test_case]
+    fn stack_guard_is_intact(_gba: &mut crate::Gba) {
+        let vblank = crate::interrupt::VBlank::get();
+        let _stack_guard = StackGuard::new();
+
+        assert_eq!(use_stack(4), 20);
+        vblank.wait_for_vblank();
+
+        check_stack();
+    }
+}
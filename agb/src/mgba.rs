@@ -21,7 +21,7 @@ const ENABLE_HANDSHAKE_OUT: u16 = 0x1DEA;
 const DEBUG_LEVEL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x04FF_F700) };
 const DEBUG_FLAG_CODE: u16 = 0x0100;
 
-fn is_running_in_mgba() -> bool {
+pub(crate) fn is_running_in_mgba() -> bool {
     DEBUG_ENABLE.set(ENABLE_HANDSHAKE_IN);
     DEBUG_ENABLE.get() == ENABLE_HANDSHAKE_OUT
 }
@@ -34,13 +34,17 @@ pub fn number_of_cycles_tagged(tag: u16) {
 
 pub struct Mgba {
     bytes_written: usize,
+    level: DebugLevel,
 }
 
 impl Mgba {
     #[must_use]
     pub fn new() -> Option<Self> {
         if is_running_in_mgba() {
-            Some(Mgba { bytes_written: 0 })
+            Some(Mgba {
+                bytes_written: 0,
+                level: DebugLevel::Info,
+            })
         } else {
             None
         }
@@ -51,6 +55,7 @@ impl Mgba {
         output: core::fmt::Arguments,
         level: DebugLevel,
     ) -> Result<(), core::fmt::Error> {
+        self.level = level;
         write!(self, "{}", output)?;
         self.set_level(level);
         Ok(())
@@ -64,29 +69,94 @@ impl Mgba {
     }
 }
 
+/// The length of the longest part of `s` which fits in `max` bytes without
+/// splitting a character.
+fn split_point(s: &str, max: usize) -> usize {
+    if max >= s.len() {
+        return s.len();
+    }
+
+    (0..=max)
+        .rev()
+        .find(|&i| s.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
 impl core::fmt::Write for Mgba {
-    fn write_str(&mut self, s: &str) -> Result<(), core::fmt::Error> {
-        let mut str_iter = s.bytes();
-        while self.bytes_written < 255 {
-            match str_iter.next() {
-                Some(byte) => {
-                    OUTPUT_STRING.set(self.bytes_written, byte);
-                    self.bytes_written += 1;
-                }
-                None => return Ok(()),
+    fn write_str(&mut self, mut s: &str) -> Result<(), core::fmt::Error> {
+        while !s.is_empty() {
+            let end = split_point(s, 255 - self.bytes_written);
+
+            // long messages are split over several lines rather than cut off,
+            // but never part way through a character
+            if end == 0 {
+                self.set_level(self.level);
+                continue;
+            }
+
+            for (i, byte) in s[..end].bytes().enumerate() {
+                OUTPUT_STRING.set(self.bytes_written + i, byte);
             }
+
+            self.bytes_written += end;
+            s = &s[end..];
         }
         Ok(())
     }
 }
 
+/// Prints a line to the mGBA debug log at the info level, using the same
+/// formatting as `println!` in the standard library. Does nothing when not
+/// running in mGBA, such as on real hardware.
 #[macro_export]
 macro_rules! println {
-    ($( $x:expr ),*) => {
+    ($($arg:tt)*) => {
+        {
+            if let Some(mut mgba) = $crate::mgba::Mgba::new() {
+                let _ = mgba.print(format_args!($($arg)*), $crate::mgba::DebugLevel::Info);
+            }
+        }
+    };
+}
+
+/// Prints a line to the mGBA debug log at the error level, using the same
+/// formatting as `eprintln!` in the standard library. Does nothing when not
+/// running in mGBA, such as on real hardware.
+#[macro_export]
+macro_rules! eprintln {
+    ($($arg:tt)*) => {
         {
             if let Some(mut mgba) = $crate::mgba::Mgba::new() {
-                let _ = mgba.print(format_args!($($x,)*), $crate::mgba::DebugLevel::Info);
+                let _ = mgba.print(format_args!($($arg)*), $crate::mgba::DebugLevel::Error);
             }
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn messages_are_only_split_between_characters(_gba: &mut crate::Gba) {
+        assert_eq!(split_point("abc", 255), 3);
+        assert_eq!(split_point("abc", 2), 2);
+        assert_eq!(split_point("aé", 2), 1);
+        assert_eq!(split_point("aé", 3), 3);
+        assert_eq!(split_point("é", 1), 0);
+        assert_eq!(split_point("é", 0), 0);
+    }
+
+    #[test_case]
+    fn long_messages_can_be_printed(_gba: &mut crate::Gba) {
+        let value = 5;
+        crate::println!("the value is {}", value);
+        crate::eprintln!("the value is still {value}",);
+
+        let long = [b'a'; 600];
+        crate::println!("{}", core::str::from_utf8(&long).unwrap());
+
+        let long = "é".repeat(300);
+        crate::println!("a{}", long);
+    }
+}
@@ -0,0 +1,183 @@
+#![deny(missing_docs)]
+//! Smooth random noise in one and two dimensions, for things like generating
+//! terrain or shaking the screen, using only fixed point maths.
+//!
+//! [`ValueNoise`] gives a random value at each whole number coordinate and
+//! smoothly interpolates between them, so nearby positions give similar
+//! values. The same seed and position always give the same value, so maps can
+//! be regenerated from their seed rather than saved.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::fixnum::{num, Num};
+//! use agb::noise::ValueNoise;
+//!
+//! # fn foo() {
+//! let noise = ValueNoise::new(1234);
+//!
+//! // the height of the ground in each column of tiles, in 8ths of the screen
+//! let heights = (0..30).map(|column| {
+//!     let height = noise.fractal(Num::new(column) / 8, 3);
+//!     4 + (height * 3).floor()
+//! });
+//!
+//! // a screen shake which moves smoothly rather than jumping every frame
+//! let frame = 12;
+//! let shake = noise.get_2d((Num::new(frame) / 4, num!(0.5)).into()) * 4;
+//! # }
+//! ```
+
+use crate::fixnum::{Num, Vector2D};
+use crate::rng::RandomNumberGenerator;
+use crate::tween::Lerp;
+
+/// A deterministic value noise generator. Every value it gives is between -1
+/// and 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueNoise {
+    seed: u32,
+}
+
+impl ValueNoise {
+    /// Creates a noise generator with the given seed. Generators with the same
+    /// seed always give the same values.
+    #[must_use]
+    pub const fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    /// Creates a noise generator seeded from a random number generator.
+    #[must_use]
+    pub fn from_rng(rng: &mut RandomNumberGenerator) -> Self {
+        Self::new(rng.gen() as u32)
+    }
+
+    /// The noise at `x`. Whole numbers are completely random relative to each
+    /// other, so scale `x` down to make the noise smoother.
+    #[must_use]
+    pub fn get(self, x: Num<i32, 8>) -> Num<i32, 8> {
+        let t = smooth(x);
+        let (left, right) = (self.lattice(x.floor(), 0), self.lattice(x.floor() + 1, 0));
+
+        left.lerp(right, t)
+    }
+
+    /// The noise at `position` on a 2D plane.
+    #[must_use]
+    pub fn get_2d(self, position: Vector2D<Num<i32, 8>>) -> Num<i32, 8> {
+        let (x, y) = (position.x.floor(), position.y.floor());
+        let (tx, ty) = (smooth(position.x), smooth(position.y));
+
+        let top = self.lattice(x, y).lerp(self.lattice(x + 1, y), tx);
+        let bottom = self.lattice(x, y + 1).lerp(self.lattice(x + 1, y + 1), tx);
+
+        top.lerp(bottom, ty)
+    }
+
+    /// The sum of `octaves` layers of noise at `x`, where each layer has twice
+    /// the detail and half the strength of the previous one, scaled back to
+    /// between -1 and 1. This looks more natural than a single layer.
+    #[must_use]
+    pub fn fractal(self, x: Num<i32, 8>, octaves: u8) -> Num<i32, 8> {
+        self.sum_octaves(octaves, |noise, scale| noise.get(x * scale))
+    }
+
+    /// Like [`fractal`](ValueNoise::fractal), but on a 2D plane.
+    #[must_use]
+    pub fn fractal_2d(self, position: Vector2D<Num<i32, 8>>, octaves: u8) -> Num<i32, 8> {
+        self.sum_octaves(octaves, |noise, scale| noise.get_2d(position * scale))
+    }
+
+    fn sum_octaves(
+        self,
+        octaves: u8,
+        layer: impl Fn(ValueNoise, i32) -> Num<i32, 8>,
+    ) -> Num<i32, 8> {
+        let mut total = Num::new(0);
+        let mut strengths = Num::new(0);
+        let mut strength: Num<i32, 8> = Num::new(1);
+
+        for octave in 0..octaves.min(8) {
+            // give each layer its own seed so they don't line up at the origin
+            let noise = ValueNoise::new(self.seed.wrapping_add(u32::from(octave)));
+
+            total += layer(noise, 1 << octave) * strength;
+            strengths += strength;
+            strength /= 2;
+        }
+
+        if strengths == Num::new(0) {
+            return strengths;
+        }
+
+        total / strengths
+    }
+
+    /// The random value at a whole number coordinate, between -1 and 1.
+    fn lattice(self, x: i32, y: i32) -> Num<i32, 8> {
+        let mut hash =
+            self.seed ^ (x as u32).wrapping_mul(0x27d4_eb2d) ^ (y as u32).wrapping_mul(0x1656_67b1);
+
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(0x2c1b_3c6d);
+        hash ^= hash >> 12;
+        hash = hash.wrapping_mul(0x297a_2d39);
+        hash ^= hash >> 15;
+
+        Num::from_raw((hash >> 23) as i32 - 256)
+    }
+}
+
+/// How far between two whole numbers `x` is, eased so that the noise has no
+/// sharp corners at whole numbers.
+fn smooth(x: Num<i32, 8>) -> Num<i32, 8> {
+    let t: Num<i32, 8> = Num::from_raw(x.frac());
+    t * t * (Num::new(3) - t * 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn noise_is_deterministic_and_in_range(_gba: &mut crate::Gba) {
+        let noise = ValueNoise::new(42);
+        let other = ValueNoise::new(43);
+        let mut differs = false;
+
+        for i in -64..64 {
+            let x = Num::new(i) / 5;
+            let position = (x, Num::new(i) / 3).into();
+
+            for value in [
+                noise.get(x),
+                noise.get_2d(position),
+                noise.fractal(x, 4),
+                noise.fractal_2d(position, 4),
+            ] {
+                assert!(value >= Num::new(-1) && value <= Num::new(1), "{}", value);
+            }
+
+            assert_eq!(noise.get(x), ValueNoise::new(42).get(x));
+            differs |= noise.get_2d(position) != other.get_2d(position);
+        }
+
+        assert!(differs);
+    }
+
+    #[test_case]
+    fn noise_is_smooth(_gba: &mut crate::Gba) {
+        let noise = ValueNoise::new(7);
+        let step: Num<i32, 8> = Num::new(1) / 32;
+
+        for i in 0..256 {
+            let x = step * i;
+            let change = (noise.get(x + step) - noise.get(x)).abs();
+            assert!(change <= Num::new(1) / 8, "{} changes by {}", x, change);
+        }
+
+        // whole numbers give exactly the random value there
+        assert_eq!(noise.get(Num::new(3)), noise.get_2d((3, 0).into()));
+    }
+}
@@ -0,0 +1,176 @@
+#![deny(missing_docs)]
+//! Loading code and data from ROM into a shared window of EWRAM when it is
+//! needed.
+//!
+//! Code in EWRAM runs faster than code in ROM, and data there is quicker to
+//! read, but there is only 256KiB of it. Overlays let parts of the game which
+//! are never needed at the same time, such as the code for different levels
+//! or minigames, take turns using the same part of EWRAM.
+//!
+//! Functions and statics are put in one of the 4 overlays with
+//! [`#[agb::overlay(n)]`](macro@crate::overlay). The linker script keeps each
+//! overlay in ROM, but links it to run from the overlay window, which is just
+//! after the other EWRAM statics and is as large as the largest overlay. As
+//! everything is linked for the address it runs at, nothing needs relocating
+//! when an overlay is loaded, [`load`] only has to copy it into the window.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! #[agb::overlay(0)]
+//! unsafe fn forest_update(x: i32) -> i32 {
+//!     x + 1
+//! }
+//!
+//! #[agb::overlay(1)]
+//! unsafe fn castle_update(x: i32) -> i32 {
+//!     x - 1
+//! }
+//!
+//! # fn foo() {
+//! let x = unsafe {
+//!     agb::overlay::load(0);
+//!     forest_update(5)
+//! };
+//!
+//! let x = unsafe {
+//!     agb::overlay::load(1);
+//!     castle_update(x)
+//! };
+//! # }
+//! ```
+//!
+//! The linker refuses to build a game where code in one overlay refers to
+//! another, but it can't check that the overlay an item is in is loaded when it
+//! is used. So overlays can only contain `unsafe fn`s and `static mut`s, and
+//! using them is only safe while their overlay is loaded.
+//!
+//! When multibooting, everything is already in EWRAM, so every overlay stays
+//! loaded and [`load`] doesn't need to copy anything.
+
+use crate::sync::Static;
+
+/// The number of overlays, which are numbered from 0.
+pub const OVERLAYS: usize = 4;
+
+static LOADED: Static<Option<usize>> = Static::new(None);
+
+/// The address of an overlay's contents in ROM, and of the end of them.
+fn rom_range(overlay: usize) -> (usize, usize) {
+    match overlay {
+        0 => (
+            linker_address!(__load_start_overlay0),
+            linker_address!(__load_stop_overlay0),
+        ),
+        1 => (
+            linker_address!(__load_start_overlay1),
+            linker_address!(__load_stop_overlay1),
+        ),
+        2 => (
+            linker_address!(__load_start_overlay2),
+            linker_address!(__load_stop_overlay2),
+        ),
+        3 => (
+            linker_address!(__load_start_overlay3),
+            linker_address!(__load_stop_overlay3),
+        ),
+        _ => panic!(
+            "there are only {} overlays, but tried to use overlay {}",
+            OVERLAYS, overlay
+        ),
+    }
+}
+
+fn window_start() -> usize {
+    linker_address!(__overlay_window_start)
+}
+
+/// The size in bytes of the window in EWRAM which overlays are loaded into,
+/// which is the size of the largest overlay.
+#[must_use]
+pub fn window_size() -> usize {
+    linker_address!(__overlay_window_end) - window_start()
+}
+
+/// The size in bytes of an overlay.
+///
+/// # Panics
+///
+/// Panics if `overlay` isn't less than [`OVERLAYS`].
+#[must_use]
+pub fn size(overlay: usize) -> usize {
+    let (start, end) = rom_range(overlay);
+    end - start
+}
+
+/// Copies an overlay from ROM into the overlay window, replacing whichever
+/// overlay was there before. Nothing is copied if it is already loaded.
+///
+/// # Safety
+///
+/// Nothing from the overlay which was loaded before may be used again until
+/// it is loaded again. This includes functions which are still running, so an
+/// overlay can't load another one, and references to statics.
+///
+/// # Panics
+///
+/// Panics if `overlay` isn't less than [`OVERLAYS`].
+pub unsafe fn load(overlay: usize) {
+    let (start, end) = rom_range(overlay);
+
+    if LOADED.read() == Some(overlay) {
+        return;
+    }
+
+    // the overlays are packed together in ROM, so they may not be aligned
+    core::ptr::copy_nonoverlapping(start as *const u8, window_start() as *mut u8, end - start);
+    LOADED.write(Some(overlay));
+}
+
+/// The overlay which was loaded most recently.
+#[must_use]
+pub fn loaded() -> Option<usize> {
+    LOADED.read()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[crate::overlay(0)]
+    unsafe fn overlay_function(x: u32) -> u32 {
+        x * 7
+    }
+
+    #[crate::overlay(1)]
+    static mut OVERLAY_TABLE: [u32; 4] = [3, 1, 4, 1];
+
+    #[test_case]
+    fn overlays_share_a_window_in_ewram(_gba: &mut crate::Gba) {
+        let table = core::ptr::addr_of!(OVERLAY_TABLE) as usize;
+
+        if crate::syscall::is_multiboot() {
+            // every overlay stays where it was linked
+            assert_eq!(size(0), 0);
+            assert_eq!(window_size(), 0);
+            assert_ne!(table, window_start());
+        } else {
+            assert!(size(0) > 0);
+            assert_eq!(size(1), 16);
+            assert!(window_size() >= size(0).max(size(1)));
+
+            let function = overlay_function as unsafe fn(u32) -> u32 as usize;
+            assert_eq!(function & !1, window_start());
+            assert_eq!(table, window_start());
+        }
+
+        unsafe { load(0) };
+        assert_eq!(loaded(), Some(0));
+        assert_eq!(unsafe { overlay_function(6) }, 42);
+
+        unsafe { load(1) };
+        assert_eq!(loaded(), Some(1));
+        let table = unsafe { (table as *const u32).read_volatile() };
+        assert_eq!(table, 3);
+    }
+}
@@ -0,0 +1,224 @@
+//! Shows panic messages on the screen, so that crashes on real hardware can be
+//! diagnosed without an emulator's log.
+//!
+//! Nothing else can be trusted during a panic, so this sets up the display
+//! registers itself and draws with a tiny built in font rather than using any
+//! of the display code.
+//!
+//! Below the message is a [crash code](crate::backtrace) for the functions
+//! which led to the panic, so players can report where the game crashed.
+
+use core::fmt::{self, Write};
+
+use crate::backtrace::Backtrace;
+use crate::display::{HEIGHT, WIDTH};
+use crate::memory_mapped::{MemoryMapped, MemoryMapped2DArray};
+
+const DISPLAY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0000) };
+const BG2_AFFINE: [MemoryMapped<u16>; 4] = unsafe {
+    [
+        MemoryMapped::new(0x0400_0020),
+        MemoryMapped::new(0x0400_0022),
+        MemoryMapped::new(0x0400_0024),
+        MemoryMapped::new(0x0400_0026),
+    ]
+};
+const BG2_POSITION: [MemoryMapped<u32>; 2] = unsafe {
+    [
+        MemoryMapped::new(0x0400_0028),
+        MemoryMapped::new(0x0400_002C),
+    ]
+};
+const MOSAIC: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_004C) };
+const BLEND_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0050) };
+const MASTER_BRIGHTNESS: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0054) };
+// the sound DMAs would otherwise keep playing the last buffer forever
+const SOUND_DMA_CONTROL: [MemoryMapped<u16>; 2] = unsafe {
+    [
+        MemoryMapped::new(0x0400_00C6),
+        MemoryMapped::new(0x0400_00D2),
+    ]
+};
+
+const MODE_3_WITH_BG2: u16 = 3 | (1 << 10);
+
+const BITMAP: MemoryMapped2DArray<u16, { WIDTH as usize }, { HEIGHT as usize }> =
+    unsafe { MemoryMapped2DArray::new(0x0600_0000) };
+
+const BACKGROUND: u16 = 0x2800;
+const TEXT: u16 = 0x7fff;
+const HEADING: u16 = 0x021f;
+
+const GLYPH_WIDTH: i32 = 3;
+const GLYPH_HEIGHT: i32 = 5;
+const CELL_WIDTH: i32 = GLYPH_WIDTH + 1;
+const CELL_HEIGHT: i32 = GLYPH_HEIGHT + 2;
+const MARGIN: i32 = 4;
+
+/// A 3x5 pixel font for the characters from space to underscore. Each octal
+/// digit is a row from top to bottom, and the bits of the digit are the
+/// pixels from left to right.
+const FONT: [u16; 64] = [
+    0o00000, 0o22202, 0o55000, 0o57575, 0o36236, 0o51245, 0o25253, 0o22000, // space to '
+    0o12221, 0o42224, 0o05250, 0o02720, 0o00024, 0o00700, 0o00002, 0o11244, // ( to /
+    0o75557, 0o26227, 0o71747, 0o71717, 0o55711, 0o74717, 0o74757, 0o71111, // 0 to 7
+    0o75757, 0o75717, 0o02020, 0o02024, 0o12421, 0o07070, 0o42124, 0o71202, // 8 to ?
+    0o25743, 0o25755, 0o65656, 0o34443, 0o65556, 0o74647, 0o74644, 0o34553, // @ to G
+    0o55755, 0o72227, 0o11152, 0o55655, 0o44447, 0o57755, 0o65555, 0o25552, // H to O
+    0o65644, 0o25563, 0o65655, 0o34216, 0o72222, 0o55557, 0o55552, 0o55775, // P to W
+    0o55255, 0o55222, 0o71247, 0o64446, 0o44211, 0o62226, 0o25000, 0o00007, // X to _
+];
+
+fn glyph(c: char) -> u16 {
+    match c {
+        ' '..='_' => FONT[c as usize - ' ' as usize],
+        'a'..='z' => glyph(c.to_ascii_uppercase()),
+        '`' => 0o42000,
+        '{' => 0o32623,
+        '|' => 0o22222,
+        '}' => 0o62326,
+        '~' => 0o03600,
+        _ => glyph('?'),
+    }
+}
+
+/// Writes text onto the mode 3 bitmap, wrapping at the edge of the screen and
+/// stopping at the bottom.
+struct ScreenWriter {
+    x: i32,
+    y: i32,
+    colour: u16,
+}
+
+impl ScreenWriter {
+    fn new() -> Self {
+        Self {
+            x: MARGIN,
+            y: MARGIN,
+            colour: TEXT,
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.x = MARGIN;
+        self.y += CELL_HEIGHT;
+    }
+
+    fn draw_char(&mut self, c: char) {
+        if c == '\n' {
+            self.new_line();
+            return;
+        }
+
+        if self.x + GLYPH_WIDTH > WIDTH - MARGIN {
+            self.new_line();
+        }
+        if self.y + GLYPH_HEIGHT > HEIGHT - MARGIN {
+            return;
+        }
+
+        let glyph = glyph(c);
+        for row in 0..GLYPH_HEIGHT {
+            let bits = glyph >> ((GLYPH_HEIGHT - 1 - row) * GLYPH_WIDTH);
+
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    BITMAP.set(
+                        (self.x + column) as usize,
+                        (self.y + row) as usize,
+                        self.colour,
+                    );
+                }
+            }
+        }
+
+        self.x += CELL_WIDTH;
+    }
+}
+
+impl Write for ScreenWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.draw_char(c);
+        }
+        Ok(())
+    }
+}
+
+/// Takes over the display and shows `message` on it, followed by the crash
+/// code for `backtrace` if there is one.
+pub(crate) fn show(message: impl fmt::Display, backtrace: Option<&Backtrace>) {
+    for control in SOUND_DMA_CONTROL {
+        control.set(0);
+    }
+
+    DISPLAY_CONTROL.set(MODE_3_WITH_BG2);
+    for (register, value) in BG2_AFFINE.iter().zip([1 << 8, 0, 0, 1 << 8]) {
+        register.set(value);
+    }
+    for register in BG2_POSITION {
+        register.set(0);
+    }
+    MOSAIC.set(0);
+    BLEND_CONTROL.set(0);
+    MASTER_BRIGHTNESS.set(0);
+
+    for y in 0..HEIGHT as usize {
+        for x in 0..WIDTH as usize {
+            BITMAP.set(x, y, BACKGROUND);
+        }
+    }
+
+    let mut writer = ScreenWriter::new();
+    writer.colour = HEADING;
+    let _ = writer.write_str("The game crashed :(\n\n");
+
+    writer.colour = TEXT;
+    let _ = write!(writer, "{}", message);
+
+    if let Some(backtrace) = backtrace {
+        writer.colour = HEADING;
+        let _ = writer.write_str("\n\nCrash code:\n");
+
+        writer.colour = TEXT;
+        let _ = write!(writer, "{}", backtrace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn panic_messages_are_drawn(_gba: &mut crate::Gba) {
+        show(
+            format_args!("panicked at 'oh no', src/main.rs:{}:5", 12),
+            None,
+        );
+
+        assert_eq!(DISPLAY_CONTROL.get(), MODE_3_WITH_BG2);
+        assert_eq!(BITMAP.get(0, 0), BACKGROUND);
+
+        let drawn = |colour| {
+            (0..CELL_HEIGHT * 4).any(|y| {
+                (0..WIDTH).any(|x| BITMAP.get(x as usize, (MARGIN + y) as usize) == colour)
+            })
+        };
+        assert!(drawn(HEADING));
+        assert!(drawn(TEXT));
+
+        crate::assert_screen_matches!("panic_screen");
+    }
+
+    #[test_case]
+    fn long_lines_wrap_and_stop_at_the_bottom(_gba: &mut crate::Gba) {
+        let mut writer = ScreenWriter::new();
+        for _ in 0..2000 {
+            writer.draw_char('#');
+        }
+
+        assert!(writer.y + GLYPH_HEIGHT > HEIGHT - MARGIN);
+        assert_eq!(glyph('a'), glyph('A'));
+        assert_eq!(glyph('\u{e9}'), glyph('?'));
+    }
+}
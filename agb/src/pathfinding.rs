@@ -0,0 +1,487 @@
+#![deny(missing_docs)]
+//! A* pathfinding over grids of tiles, which doesn't need an allocator.
+//!
+//! The [`Pathfinder`] works in buffers given to it, which need an entry for
+//! every tile of the area being searched and enough space for the tiles which
+//! are waiting to be looked at. Searches can be spread over several frames by
+//! giving each call to [`step`](Pathfinder::step) a budget of tiles to look
+//! at.
+//!
+//! What the grid looks like comes from a function giving the cost of moving
+//! onto each tile, or `None` if it can't be entered. This will usually look
+//! up the tile's property, as set by
+//! [`TileSet::with_properties`](crate::display::tiled::TileSet::with_properties).
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::fixnum::{Rect, Vector2D};
+//! use agb::pathfinding::{OpenEntry, Pathfinder, SearchCell, SearchStatus};
+//!
+//! const SOLID: u16 = 1;
+//!
+//! # fn foo(map: &agb::display::tiled::RegularMap, vram: &agb::display::tiled::VRamManager) {
+//! let mut cells = [SearchCell::EMPTY; 32 * 32];
+//! let mut open = [OpenEntry::EMPTY; 128];
+//! let mut pathfinder = Pathfinder::new(
+//!     Rect::new((0, 0).into(), (32, 32).into()),
+//!     &mut cells,
+//!     &mut open,
+//! );
+//!
+//! pathfinder.start((1, 1).into(), (20, 12).into());
+//!
+//! // look at no more than 64 tiles each frame
+//! let status = pathfinder.step(64, |tile| {
+//!     let property = map.tile_property_at(vram, (tile.x as u16, tile.y as u16).into());
+//!     (property != SOLID).then_some(1)
+//! });
+//!
+//! if let SearchStatus::Found(_) = status {
+//!     let mut path = [Vector2D::new(0, 0); 64];
+//!     if let Some(path) = pathfinder.path(&mut path) {
+//!         let next_tile = path[0];
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::fixnum::{Rect, Vector2D};
+
+const UNVISITED: u8 = 0;
+const OPEN: u8 = 1;
+const CLOSED: u8 = 2;
+
+const NO_PARENT: u8 = u8::MAX;
+
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// What the pathfinder knows about one tile, of which it needs one for every
+/// tile in the area being searched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchCell {
+    cost: u32,
+    steps: u16,
+    parent: u8,
+    state: u8,
+}
+
+impl SearchCell {
+    /// A cell which hasn't been searched, for filling arrays of cells.
+    pub const EMPTY: Self = Self {
+        cost: 0,
+        steps: 0,
+        parent: NO_PARENT,
+        state: UNVISITED,
+    };
+}
+
+impl Default for SearchCell {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// A tile waiting to be looked at by the pathfinder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpenEntry {
+    priority: u32,
+    cell: u32,
+}
+
+impl OpenEntry {
+    /// An empty entry, for filling arrays of entries.
+    pub const EMPTY: Self = Self {
+        priority: 0,
+        cell: 0,
+    };
+}
+
+impl Default for OpenEntry {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// How far a search has got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchStatus {
+    /// No search has been started.
+    Idle,
+    /// The search needs more calls to [`step`](Pathfinder::step).
+    InProgress,
+    /// A path was found, which is this many tiles long, not counting the tile
+    /// it starts on.
+    Found(usize),
+    /// There is no path between the two tiles.
+    NoPath,
+    /// There are too many tiles waiting to be looked at to fit in the buffer,
+    /// so the search was abandoned.
+    OutOfSpace,
+}
+
+/// Finds the cheapest path between two tiles of a grid using the A* algorithm,
+/// moving up, down, left and right.
+pub struct Pathfinder<'a> {
+    area: Rect<i32>,
+    cells: &'a mut [SearchCell],
+    open: &'a mut [OpenEntry],
+    open_len: usize,
+    goal: Vector2D<i32>,
+    status: SearchStatus,
+}
+
+impl<'a> Pathfinder<'a> {
+    /// Creates a pathfinder which searches the tiles in `area`. It needs a
+    /// cell for every tile in the area, and the longer the paths the more open
+    /// entries it needs, with a few times the length of the longest path
+    /// usually being enough.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer cells than tiles in the area.
+    #[must_use]
+    pub fn new(area: Rect<i32>, cells: &'a mut [SearchCell], open: &'a mut [OpenEntry]) -> Self {
+        assert!(
+            area.size.x >= 0 && area.size.y >= 0,
+            "area must not have a negative size"
+        );
+        assert!(
+            cells.len() >= (area.size.x * area.size.y) as usize,
+            "need a cell for every tile in the area"
+        );
+
+        Self {
+            area,
+            cells,
+            open,
+            open_len: 0,
+            goal: (0, 0).into(),
+            status: SearchStatus::Idle,
+        }
+    }
+
+    /// Starts searching for a path from `from` to `goal`, forgetting any
+    /// previous search.
+    pub fn start(&mut self, from: Vector2D<i32>, goal: Vector2D<i32>) {
+        self.goal = goal;
+        self.open_len = 0;
+
+        let tiles = (self.area.size.x * self.area.size.y) as usize;
+        self.cells[..tiles].fill(SearchCell::EMPTY);
+
+        let start = match (self.index(from), self.index(goal)) {
+            (Some(start), Some(_)) => start,
+            _ => {
+                self.status = SearchStatus::NoPath;
+                return;
+            }
+        };
+
+        self.cells[start].state = OPEN;
+        self.status = if self.push(start, self.heuristic(from)) {
+            SearchStatus::InProgress
+        } else {
+            SearchStatus::OutOfSpace
+        };
+    }
+
+    /// Continues the search, looking at no more than `budget` tiles. `cost`
+    /// gives the cost of moving onto a tile, which should be at least 1, or
+    /// `None` if it can't be moved onto.
+    pub fn step(
+        &mut self,
+        budget: usize,
+        mut cost: impl FnMut(Vector2D<i32>) -> Option<u16>,
+    ) -> SearchStatus {
+        for _ in 0..budget {
+            if self.status != SearchStatus::InProgress {
+                break;
+            }
+
+            let index = match self.pop() {
+                Some(index) => index,
+                None => {
+                    self.status = SearchStatus::NoPath;
+                    break;
+                }
+            };
+
+            // tiles are added again when a cheaper way to them is found, so
+            // the more expensive entries are skipped
+            if self.cells[index].state == CLOSED {
+                continue;
+            }
+            self.cells[index].state = CLOSED;
+
+            let position = self.position(index);
+            if position == self.goal {
+                self.status = SearchStatus::Found(self.cells[index].steps.into());
+                break;
+            }
+
+            for (direction, &(x, y)) in DIRECTIONS.iter().enumerate() {
+                let neighbour = position + (x, y).into();
+                let neighbour_index = match self.index(neighbour) {
+                    Some(neighbour_index) => neighbour_index,
+                    None => continue,
+                };
+
+                if self.cells[neighbour_index].state == CLOSED {
+                    continue;
+                }
+
+                let step_cost = match cost(neighbour) {
+                    Some(step_cost) => u32::from(step_cost),
+                    None => continue,
+                };
+
+                let new_cost = self.cells[index].cost + step_cost;
+                let cell = &self.cells[neighbour_index];
+                if cell.state == OPEN && cell.cost <= new_cost {
+                    continue;
+                }
+
+                let steps = self.cells[index].steps + 1;
+                self.cells[neighbour_index] = SearchCell {
+                    cost: new_cost,
+                    steps,
+                    parent: direction as u8,
+                    state: OPEN,
+                };
+
+                if !self.push(neighbour_index, new_cost + self.heuristic(neighbour)) {
+                    self.status = SearchStatus::OutOfSpace;
+                    break;
+                }
+            }
+        }
+
+        self.status
+    }
+
+    /// Searches until the search finishes, however long it takes.
+    pub fn find_path(
+        &mut self,
+        from: Vector2D<i32>,
+        goal: Vector2D<i32>,
+        cost: impl FnMut(Vector2D<i32>) -> Option<u16>,
+    ) -> SearchStatus {
+        self.start(from, goal);
+        self.step(usize::MAX, cost)
+    }
+
+    /// How far the current search has got.
+    #[must_use]
+    pub fn status(&self) -> SearchStatus {
+        self.status
+    }
+
+    /// Writes the path which was found into `buffer`, and returns the part of
+    /// it which holds the path. The path starts with the tile after the start
+    /// and ends with the goal. Returns `None` if no path has been found, or if
+    /// it doesn't fit.
+    pub fn path<'b>(&self, buffer: &'b mut [Vector2D<i32>]) -> Option<&'b [Vector2D<i32>]> {
+        let length = match self.status {
+            SearchStatus::Found(length) => length,
+            _ => return None,
+        };
+
+        let path = buffer.get_mut(..length)?;
+
+        let mut position = self.goal;
+        for slot in path.iter_mut().rev() {
+            *slot = position;
+
+            let parent = self.cells[self.index(position)?].parent;
+            let (x, y) = DIRECTIONS[usize::from(parent)];
+            position -= (x, y).into();
+        }
+
+        Some(path)
+    }
+
+    /// The total cost of the path which was found, which is the sum of the
+    /// costs of every tile on it after the start.
+    #[must_use]
+    pub fn path_cost(&self) -> Option<u32> {
+        match self.status {
+            SearchStatus::Found(_) => Some(self.cells[self.index(self.goal)?].cost),
+            _ => None,
+        }
+    }
+
+    fn index(&self, position: Vector2D<i32>) -> Option<usize> {
+        let relative = position - self.area.position;
+        if relative.x < 0
+            || relative.y < 0
+            || relative.x >= self.area.size.x
+            || relative.y >= self.area.size.y
+        {
+            return None;
+        }
+
+        Some((relative.y * self.area.size.x + relative.x) as usize)
+    }
+
+    fn position(&self, index: usize) -> Vector2D<i32> {
+        let index = index as i32;
+        self.area.position + (index % self.area.size.x, index / self.area.size.x).into()
+    }
+
+    /// The Manhattan distance to the goal, which never overestimates the cost
+    /// when every tile costs at least 1.
+    fn heuristic(&self, position: Vector2D<i32>) -> u32 {
+        ((position.x - self.goal.x).abs() + (position.y - self.goal.y).abs()) as u32
+    }
+
+    /// Adds a tile to the binary heap of open tiles, returning false if it is
+    /// full.
+    fn push(&mut self, cell: usize, priority: u32) -> bool {
+        if self.open_len == self.open.len() {
+            return false;
+        }
+
+        let mut child = self.open_len;
+        self.open[child] = OpenEntry {
+            priority,
+            cell: cell as u32,
+        };
+        self.open_len += 1;
+
+        while child > 0 {
+            let parent = (child - 1) / 2;
+            if self.open[parent].priority <= self.open[child].priority {
+                break;
+            }
+
+            self.open.swap(parent, child);
+            child = parent;
+        }
+
+        true
+    }
+
+    /// Removes the open tile with the lowest priority.
+    fn pop(&mut self) -> Option<usize> {
+        if self.open_len == 0 {
+            return None;
+        }
+
+        let top = self.open[0].cell as usize;
+        self.open_len -= 1;
+        self.open[0] = self.open[self.open_len];
+
+        let mut parent = 0;
+        loop {
+            let left = parent * 2 + 1;
+            let right = left + 1;
+            let mut smallest = parent;
+
+            if left < self.open_len && self.open[left].priority < self.open[smallest].priority {
+                smallest = left;
+            }
+            if right < self.open_len && self.open[right].priority < self.open[smallest].priority {
+                smallest = right;
+            }
+            if smallest == parent {
+                break;
+            }
+
+            self.open.swap(parent, smallest);
+            parent = smallest;
+        }
+
+        Some(top)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // S is the start, G the goal and # walls, with the only way through the
+    // wall at the bottom
+    const GRID: [&[u8; 8]; 6] = [
+        b"S..#....",
+        b"...#....",
+        b"...#..G.",
+        b"...#....",
+        b"........",
+        b"...#....",
+    ];
+
+    fn cost(position: Vector2D<i32>) -> Option<u16> {
+        (GRID[position.y as usize][position.x as usize] != b'#').then_some(1)
+    }
+
+    fn area() -> Rect<i32> {
+        Rect::new((0, 0).into(), (8, 6).into())
+    }
+
+    #[test_case]
+    fn finds_the_shortest_path_around_walls(_gba: &mut crate::Gba) {
+        let mut cells = [SearchCell::EMPTY; 48];
+        let mut open = [OpenEntry::EMPTY; 48];
+        let mut pathfinder = Pathfinder::new(area(), &mut cells, &mut open);
+
+        let status = pathfinder.find_path((0, 0).into(), (6, 2).into(), cost);
+        assert_eq!(status, SearchStatus::Found(12));
+        assert_eq!(pathfinder.path_cost(), Some(12));
+
+        let mut buffer = [Vector2D::new(0, 0); 16];
+        let path = pathfinder.path(&mut buffer).unwrap();
+        assert_eq!(path.len(), 12);
+        assert_eq!(path[11], (6, 2).into());
+        assert!(path.contains(&(3, 4).into()), "must go through the gap");
+
+        for pair in path.windows(2) {
+            let step = pair[1] - pair[0];
+            assert_eq!(step.x.abs() + step.y.abs(), 1);
+            assert!(cost(pair[1]).is_some());
+        }
+
+        let mut too_short = [Vector2D::new(0, 0); 4];
+        assert_eq!(pathfinder.path(&mut too_short), None);
+    }
+
+    #[test_case]
+    fn searches_can_be_spread_across_frames(_gba: &mut crate::Gba) {
+        let mut cells = [SearchCell::EMPTY; 48];
+        let mut open = [OpenEntry::EMPTY; 48];
+        let mut pathfinder = Pathfinder::new(area(), &mut cells, &mut open);
+
+        pathfinder.start((0, 0).into(), (6, 2).into());
+
+        let mut frames = 0;
+        while pathfinder.step(4, cost) == SearchStatus::InProgress {
+            frames += 1;
+        }
+
+        assert!(frames > 1);
+        assert_eq!(pathfinder.status(), SearchStatus::Found(12));
+    }
+
+    #[test_case]
+    fn reports_missing_paths_and_full_buffers(_gba: &mut crate::Gba) {
+        let mut cells = [SearchCell::EMPTY; 48];
+        let mut open = [OpenEntry::EMPTY; 96];
+        let mut pathfinder = Pathfinder::new(area(), &mut cells, &mut open);
+
+        assert_eq!(
+            pathfinder.find_path((0, 0).into(), (3, 0).into(), cost),
+            SearchStatus::NoPath
+        );
+        assert_eq!(
+            pathfinder.find_path((0, 0).into(), (20, 0).into(), cost),
+            SearchStatus::NoPath
+        );
+
+        let mut small_open = [OpenEntry::EMPTY; 2];
+        let mut pathfinder = Pathfinder::new(area(), &mut cells, &mut small_open);
+        assert_eq!(
+            pathfinder.find_path((0, 0).into(), (6, 2).into(), cost),
+            SearchStatus::OutOfSpace
+        );
+    }
+}
@@ -0,0 +1,437 @@
+#![deny(missing_docs)]
+//! Measuring how much of each frame the game uses.
+//!
+//! A [`FrameProfiler`] chains timers 2 and 3 together to count CPU cycles,
+//! and measures the time from [`frame_start`](FrameProfiler::frame_start) to
+//! [`wait_for_vblank`](FrameProfiler::wait_for_vblank) as a percentage of a
+//! frame. It also keeps the largest percentage from the last second of frames,
+//! and the time spent in named [scopes](FrameProfiler::scope), so it's easy to
+//! see which part of the game is responsible for slowdown.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::profiler::FrameProfiler;
+//!
+//! # fn foo(gba: &mut agb::Gba) {
+//! let vblank = agb::interrupt::VBlank::get();
+//! let mut profiler = FrameProfiler::new(gba.timers.timers());
+//!
+//! loop {
+//!     profiler.frame_start();
+//!
+//!     profiler.scope("physics", || {
+//!         // update the physics
+//!     });
+//!
+//!     profiler.wait_for_vblank(&vblank);
+//!     profiler.log();
+//! }
+//! # }
+//! ```
+//!
+//! To see the frame time while playing on hardware, a [`CpuOverlay`] shows it
+//! as a bar made of sprites.
+
+use alloc::vec::Vec;
+
+use crate::display::object::{
+    AlignedAs, DynamicSprite, Object, ObjectController, PaletteVram, Size, SpriteBorrow,
+};
+use crate::display::palette16::Palette16;
+use crate::display::Priority;
+use crate::fixnum::{Num, Vector2D};
+use crate::interrupt::VBlank;
+use crate::timer::{Divider, Timer, Timers, CYCLES_PER_FRAME};
+
+/// The number of frames the rolling maximum is taken over
+const ROLLING_FRAMES: usize = 60;
+
+/// How long part of a frame took.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    /// The number of CPU cycles.
+    pub cycles: u32,
+}
+
+impl Measurement {
+    /// The percentage of a frame this took.
+    #[must_use]
+    pub fn percent(self) -> Num<u32, 8> {
+        Num::from_raw((u64::from(self.cycles) * (100 << 8) / u64::from(CYCLES_PER_FRAME)) as u32)
+    }
+}
+
+/// Measures the CPU time used by each frame, and by named parts of it.
+pub struct FrameProfiler {
+    low: Timer,
+    high: Timer,
+
+    frame_started_at: Option<u32>,
+    history: [u32; ROLLING_FRAMES],
+    history_position: usize,
+    last_frame: Option<Measurement>,
+    scopes: Vec<(&'static str, u32)>,
+}
+
+impl FrameProfiler {
+    /// Creates a profiler which uses timers 2 and 3 to count cycles.
+    #[must_use]
+    pub fn new(timers: Timers) -> Self {
+        let Timers {
+            timer2: mut low,
+            timer3: mut high,
+            ..
+        } = timers;
+
+        high.set_overflow_amount(0)
+            .set_cascade(true)
+            .set_enabled(true);
+        low.set_overflow_amount(0)
+            .set_divider(Divider::Divider1)
+            .set_enabled(true);
+
+        Self {
+            low,
+            high,
+
+            frame_started_at: None,
+            history: [0; ROLLING_FRAMES],
+            history_position: 0,
+            last_frame: None,
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Stops the profiler, giving back its timers.
+    #[must_use]
+    pub fn into_timers(self) -> (Timer, Timer) {
+        let Self {
+            mut low, mut high, ..
+        } = self;
+
+        low.set_enabled(false);
+        high.set_enabled(false);
+        (low, high)
+    }
+
+    /// Starts measuring a frame, and resets the times of the scopes. This is
+    /// usually called straight after the vblank.
+    pub fn frame_start(&mut self) {
+        self.frame_started_at = Some(self.cycles());
+
+        for (_, cycles) in &mut self.scopes {
+            *cycles = 0;
+        }
+    }
+
+    /// Finishes measuring the frame, and then waits for the vblank.
+    pub fn wait_for_vblank(&mut self, vblank: &VBlank) {
+        self.frame_end();
+        vblank.wait_for_vblank();
+    }
+
+    /// Finishes measuring the frame without waiting for the vblank, for games
+    /// which wait for it some other way.
+    pub fn frame_end(&mut self) {
+        let Some(started_at) = self.frame_started_at.take() else {
+            return;
+        };
+
+        let cycles = self.cycles().wrapping_sub(started_at);
+        self.last_frame = Some(Measurement { cycles });
+
+        self.history[self.history_position] = cycles;
+        self.history_position = (self.history_position + 1) % ROLLING_FRAMES;
+    }
+
+    /// Runs `f` and adds the time it takes to the scope with the given name.
+    /// The same scope can be used more than once in a frame.
+    pub fn scope<R>(&mut self, name: &'static str, f: impl FnOnce() -> R) -> R {
+        let start = self.cycles();
+        let result = f();
+        let cycles = self.cycles().wrapping_sub(start);
+
+        match self.scopes.iter_mut().find(|(scope, _)| *scope == name) {
+            Some((_, total)) => *total += cycles,
+            None => self.scopes.push((name, cycles)),
+        }
+
+        result
+    }
+
+    /// How long the last measured frame took, or `None` if no frames have
+    /// been measured.
+    #[must_use]
+    pub fn last_frame(&self) -> Option<Measurement> {
+        self.last_frame
+    }
+
+    /// The longest of the last 60 measured frames.
+    #[must_use]
+    pub fn rolling_max(&self) -> Measurement {
+        Measurement {
+            cycles: self.history.iter().copied().max().unwrap_or(0),
+        }
+    }
+
+    /// How long each scope has taken in the current frame, in the order they
+    /// were first used.
+    pub fn scopes(&self) -> impl Iterator<Item = (&'static str, Measurement)> + '_ {
+        self.scopes
+            .iter()
+            .map(|&(name, cycles)| (name, Measurement { cycles }))
+    }
+
+    /// Prints the last frame's time, the rolling maximum and the time of each
+    /// scope to the mgba log.
+    pub fn log(&self) {
+        if let Some(frame) = self.last_frame {
+            crate::println!(
+                "frame: {}% (max {}%)",
+                frame.percent(),
+                self.rolling_max().percent()
+            );
+        }
+
+        for (name, measurement) in self.scopes() {
+            crate::println!("  {}: {}%", name, measurement.percent());
+        }
+    }
+
+    /// The number of cycles counted by the chained timers.
+    fn cycles(&self) -> u32 {
+        // if the low timer overflows between reading the two halves, read again
+        loop {
+            let high = self.high.value();
+            let low = self.low.value();
+
+            if self.high.value() == high {
+                return (u32::from(high) << 16) | u32::from(low);
+            }
+        }
+    }
+}
+
+/// The number of 8 pixel wide sprites in the overlay's bar
+const OVERLAY_SEGMENTS: usize = 8;
+
+/// The overlay turns yellow when the frame uses more than this percentage
+const OVERLAY_WARNING_PERCENT: u32 = 75;
+
+const OVERLAY_PALETTE: Palette16 = Palette16::new([
+    0x0000, 0x03e0, 0x03ff, 0x001f, 0x1084, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+]);
+const OVERLAY_EMPTY_COLOUR: u8 = 4;
+
+/// A bar in the corner of the screen showing how much of the last frame was
+/// used, which is green when there is time to spare, yellow when it is
+/// nearly full and red when frames are being dropped. The bar is made of
+/// sprites, so it appears whatever the backgrounds are doing.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::input::{Button, ButtonController};
+/// use agb::profiler::{CpuOverlay, FrameProfiler};
+///
+/// # fn foo(gba: &mut agb::Gba) {
+/// let vblank = agb::interrupt::VBlank::get();
+/// let objects = gba.display.object.get();
+/// let mut input = ButtonController::new();
+///
+/// let mut profiler = FrameProfiler::new(gba.timers.timers());
+/// let mut overlay = CpuOverlay::new(&objects);
+///
+/// loop {
+///     profiler.frame_start();
+///     input.update();
+///
+///     if input.is_just_pressed(Button::SELECT) {
+///         overlay.toggle();
+///     }
+///
+///     // update the game
+///
+///     overlay.update(&profiler);
+///     profiler.wait_for_vblank(&vblank);
+///     objects.commit();
+/// }
+/// # }
+/// ```
+pub struct CpuOverlay<'a> {
+    segments: [Object<'a>; OVERLAY_SEGMENTS],
+    /// A sprite for each colour and number of filled columns
+    sprites: Vec<SpriteBorrow>,
+    visible: bool,
+}
+
+impl<'a> CpuOverlay<'a> {
+    /// Creates an overlay in the top left of the screen, which is visible to
+    /// start with. It uses 8 objects and 27 tiles of sprite memory.
+    #[must_use]
+    pub fn new(objects: &'a ObjectController) -> Self {
+        let palette = PaletteVram::new(&OVERLAY_PALETTE).expect("no space for the overlay palette");
+
+        let mut sprites = Vec::new();
+        for colour in 1..=3 {
+            for filled in 0..=8 {
+                sprites.push(overlay_sprite(colour, filled, palette.clone()));
+            }
+        }
+
+        let segments = core::array::from_fn(|_| {
+            let mut object = objects.object(sprites[0].clone());
+            object.set_priority(Priority::P0).set_z(i32::MIN);
+            object
+        });
+
+        let mut overlay = Self {
+            segments,
+            sprites,
+            visible: true,
+        };
+
+        overlay.set_position((4, 4).into());
+        overlay.set_visible(true);
+        overlay
+    }
+
+    /// Moves the top left of the bar to `position`.
+    pub fn set_position(&mut self, position: Vector2D<i32>) {
+        for (i, segment) in self.segments.iter_mut().enumerate() {
+            segment.set_position(position + (i as i32 * 8, 0).into());
+        }
+    }
+
+    /// Shows or hides the overlay.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+
+        for segment in &mut self.segments {
+            if visible {
+                segment.show();
+            } else {
+                segment.hide();
+            }
+        }
+    }
+
+    /// Shows the overlay if it is hidden, or hides it if it is shown.
+    pub fn toggle(&mut self) {
+        self.set_visible(!self.visible);
+    }
+
+    /// Whether the overlay is being shown.
+    #[must_use]
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows the time taken by the profiler's last frame. Call this once per
+    /// frame before committing the objects.
+    pub fn update(&mut self, profiler: &FrameProfiler) {
+        if !self.visible {
+            return;
+        }
+
+        let percent = profiler
+            .last_frame()
+            .map_or(0, |frame| frame.percent().floor());
+
+        // the sprites for each colour follow on from those of the previous one
+        let colour = match percent {
+            0..=OVERLAY_WARNING_PERCENT => 0,
+            _ if percent < 100 => 1,
+            _ => 2,
+        };
+
+        let width = OVERLAY_SEGMENTS as u32 * 8;
+        let filled = (percent * width / 100).min(width) as usize;
+
+        for (i, segment) in self.segments.iter_mut().enumerate() {
+            let segment_filled = filled.saturating_sub(i * 8).min(8);
+            segment.set_sprite(self.sprites[colour * 9 + segment_filled].clone());
+        }
+    }
+}
+
+/// An 8x8 sprite with the left `filled` columns in the given colour, and the
+/// rest in the empty colour.
+fn overlay_sprite(colour: u8, filled: usize, palette: PaletteVram) -> SpriteBorrow {
+    let mut data = AlignedAs::<u16, [u8; 32]> {
+        _align: [],
+        bytes: [0; 32],
+    };
+
+    for (i, byte) in data.bytes.iter_mut().enumerate() {
+        // each byte is two pixels, with the left one in the low nibble
+        let x = (i % 4) * 2;
+        let pixel = |x| {
+            if x < filled {
+                colour
+            } else {
+                OVERLAY_EMPTY_COLOUR
+            }
+        };
+        *byte = pixel(x) | (pixel(x + 1) << 4);
+    }
+
+    DynamicSprite::new(&data.bytes, Size::S8x8).to_vram(palette)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn profiler_measures_frames_and_scopes(gba: &mut crate::Gba) {
+        let vblank = VBlank::get();
+        let mut profiler = FrameProfiler::new(gba.timers.timers());
+        assert_eq!(profiler.last_frame(), None);
+
+        profiler.frame_start();
+        let result = profiler.scope("work", || {
+            (0..1000).fold(0, |total, i| total + crate::syscall::sqrt(i))
+        });
+        assert!(result > 0);
+        profiler.wait_for_vblank(&vblank);
+
+        let frame = profiler.last_frame().unwrap();
+        let (name, scope) = profiler.scopes().next().unwrap();
+        assert_eq!(name, "work");
+        assert!(scope.cycles > 0);
+        assert!(frame.cycles >= scope.cycles);
+        assert_eq!(profiler.rolling_max(), frame);
+
+        assert_eq!(
+            Measurement {
+                cycles: CYCLES_PER_FRAME / 2
+            }
+            .percent(),
+            50.into()
+        );
+
+        let _ = profiler.into_timers();
+    }
+
+    #[test_case]
+    fn cpu_overlay_can_be_toggled(gba: &mut crate::Gba) {
+        let objects = gba.display.object.get();
+        let mut profiler = FrameProfiler::new(gba.timers.timers());
+        let mut overlay = CpuOverlay::new(&objects);
+        assert!(overlay.is_visible());
+
+        profiler.frame_start();
+        profiler.frame_end();
+        overlay.update(&profiler);
+
+        overlay.toggle();
+        assert!(!overlay.is_visible());
+        overlay.update(&profiler);
+        objects.commit();
+
+        drop(overlay);
+        let _ = profiler.into_timers();
+    }
+}
@@ -1,4 +1,5 @@
 use core::cell::RefCell;
+use core::ops::Range;
 
 use bare_metal::Mutex;
 
@@ -50,6 +51,46 @@ impl RandomNumberGenerator {
 
         result as i32
     }
+
+    /// Returns a random number in the range, with every number in it equally
+    /// likely to within about 1 in 2^32.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty.
+    /// ```rust,no_run
+    /// # #![no_std]
+    /// # #![no_main]
+    /// # fn foo() {
+    /// let mut rng = agb::rng::RandomNumberGenerator::new();
+    /// let dice_roll = rng.gen_range(1..7);
+    /// # }
+    /// ```
+    pub fn gen_range(&mut self, range: Range<i32>) -> i32 {
+        assert!(range.start < range.end, "range must not be empty");
+
+        let span = range.end.wrapping_sub(range.start) as u32;
+        let offset = (u64::from(self.gen() as u32) * u64::from(span)) >> 32;
+
+        range.start.wrapping_add(offset as i32)
+    }
+
+    /// Returns true with a probability of `numerator / denominator`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the denominator is 0.
+    pub fn gen_ratio(&mut self, numerator: u32, denominator: u32) -> bool {
+        assert_ne!(denominator, 0, "denominator must not be 0");
+
+        ((u64::from(self.gen() as u32) * u64::from(denominator)) >> 32) < u64::from(numerator)
+    }
+}
+
+impl Default for RandomNumberGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 static GLOBAL_RNG: Mutex<RefCell<RandomNumberGenerator>> =
@@ -61,6 +102,22 @@ pub fn gen() -> i32 {
     free(|cs| GLOBAL_RNG.borrow(cs).borrow_mut().gen())
 }
 
+/// Using a global random number generator, provides a random number in the
+/// range. See [`RandomNumberGenerator::gen_range`].
+#[must_use]
+pub fn gen_range(range: Range<i32>) -> i32 {
+    free(|cs| GLOBAL_RNG.borrow(cs).borrow_mut().gen_range(range))
+}
+
+/// Resets the global random number generator with the given seed, so that the
+/// numbers it produces from now on are the same every time, for example when
+/// replaying a recorded game. None of the values can be 0, see
+/// [`RandomNumberGenerator::new_with_seed`].
+pub fn set_seed(seed: [u32; 4]) {
+    let rng = RandomNumberGenerator::new_with_seed(seed);
+    free(|cs| *GLOBAL_RNG.borrow(cs).borrow_mut() = rng);
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -85,6 +142,41 @@ mod test {
         }
     }
 
+    #[test_case]
+    fn ranges_are_respected(_gba: &mut Gba) {
+        let mut rng = RandomNumberGenerator::new();
+        let mut seen = [false; 6];
+
+        for _ in 0..500 {
+            let roll = rng.gen_range(1..7);
+            assert!((1..7).contains(&roll), "{} is out of range", roll);
+            seen[roll as usize - 1] = true;
+
+            assert_eq!(rng.gen_range(-3..-2), -3);
+            assert!(!rng.gen_ratio(0, 3));
+            assert!(rng.gen_ratio(3, 3));
+        }
+
+        assert_eq!(seen, [true; 6]);
+
+        let full = rng.gen_range(i32::MIN..i32::MAX);
+        assert!(full < i32::MAX);
+    }
+
+    #[test_case]
+    fn seeding_the_global_rng_is_deterministic(_gba: &mut Gba) {
+        let seed = [1, 2, 3, 4];
+
+        set_seed(seed);
+        let first = [super::gen(), super::gen(), super::gen()];
+
+        set_seed(seed);
+        assert_eq!([super::gen(), super::gen(), super::gen()], first);
+
+        let mut rng = RandomNumberGenerator::new_with_seed(seed);
+        assert_eq!([rng.gen(), rng.gen(), rng.gen()], first);
+    }
+
     #[test_case]
     fn global_rng_should_be_reasonably_distributed(_gba: &mut Gba) {
         let mut values: [u32; 16] = Default::default();
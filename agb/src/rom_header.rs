@@ -0,0 +1,115 @@
+//! The header at the start of the ROM, which the GBA checks before it starts
+//! the game. [`entry`](crate::entry) puts one in every game, so the ROM works
+//! without needing to be fixed up by gbafix afterwards.
+
+/// The GBA only starts games which have exactly this logo in their header.
+const NINTENDO_LOGO: [u8; 156] = [
+    0x24, 0xFF, 0xAE, 0x51, 0x69, 0x9A, 0xA2, 0x21, 0x3D, 0x84, 0x82, 0x0A, 0x84, 0xE4, 0x09, 0xAD,
+    0x11, 0x24, 0x8B, 0x98, 0xC0, 0x81, 0x7F, 0x21, 0xA3, 0x52, 0xBE, 0x19, 0x93, 0x09, 0xCE, 0x20,
+    0x10, 0x46, 0x4A, 0x4A, 0xF8, 0x27, 0x31, 0xEC, 0x58, 0xC7, 0xE8, 0x33, 0x82, 0xE3, 0xCE, 0xBF,
+    0x85, 0xF4, 0xDF, 0x94, 0xCE, 0x4B, 0x09, 0xC1, 0x94, 0x56, 0x8A, 0xC0, 0x13, 0x72, 0xA7, 0xFC,
+    0x9F, 0x84, 0x4D, 0x73, 0xA3, 0xCA, 0x9A, 0x61, 0x58, 0x97, 0xA3, 0x27, 0xFC, 0x03, 0x98, 0x76,
+    0x23, 0x1D, 0xC7, 0x61, 0x03, 0x04, 0xAE, 0x56, 0xBF, 0x38, 0x84, 0x00, 0x40, 0xA7, 0x0E, 0xFD,
+    0xFF, 0x52, 0xFE, 0x03, 0x6F, 0x95, 0x30, 0xF1, 0x97, 0xFB, 0xC0, 0x85, 0x60, 0xD6, 0x80, 0x25,
+    0xA9, 0x63, 0xBE, 0x03, 0x01, 0x4E, 0x38, 0xE2, 0xF9, 0xA2, 0x34, 0xFF, 0xBB, 0x3E, 0x03, 0x44,
+    0x78, 0x00, 0x90, 0xCB, 0x88, 0x11, 0x3A, 0x94, 0x65, 0xC0, 0x7C, 0x63, 0x87, 0xF0, 0x3C, 0xAF,
+    0xD6, 0x25, 0xE4, 0x8B, 0x38, 0x0A, 0xAC, 0x72, 0x21, 0xD4, 0xF8, 0x07,
+];
+
+/// The header starts after the 4 byte branch to the start of the game, so
+/// these are the offsets in the ROM minus 4.
+const TITLE: usize = 0x9C;
+const GAME_CODE: usize = 0xA8;
+const MAKER_CODE: usize = 0xAC;
+const FIXED_VALUE: usize = 0xAE;
+const VERSION: usize = 0xB8;
+const CHECKSUM: usize = 0xB9;
+
+const HEADER_SIZE: usize = 188;
+
+const fn copy_into(
+    mut header: [u8; HEADER_SIZE],
+    offset: usize,
+    max_len: usize,
+    value: &[u8],
+) -> [u8; HEADER_SIZE] {
+    assert!(
+        value.len() <= max_len,
+        "value is too long for the rom header"
+    );
+
+    let mut i = 0;
+    while i < value.len() {
+        header[offset + i] = value[i];
+        i += 1;
+    }
+
+    header
+}
+
+/// Builds the ROM header, with the checksum which the GBA checks filled in.
+/// This is used by [`entry`](crate::entry), which checks the values it is
+/// given.
+#[doc(hidden)]
+#[must_use]
+pub const fn rom_header(
+    title: &[u8],
+    game_code: &[u8],
+    maker_code: &[u8],
+    version: u8,
+) -> [u8; HEADER_SIZE] {
+    let mut header = [0; HEADER_SIZE];
+
+    header = copy_into(header, 0, NINTENDO_LOGO.len(), &NINTENDO_LOGO);
+    header = copy_into(header, TITLE, 12, title);
+    header = copy_into(header, GAME_CODE, 4, game_code);
+    header = copy_into(header, MAKER_CODE, 2, maker_code);
+    header[FIXED_VALUE] = 0x96;
+    header[VERSION] = version;
+
+    let mut checksum = 0u8;
+    let mut i = TITLE;
+    while i < CHECKSUM {
+        checksum = checksum.wrapping_sub(header[i]);
+        i += 1;
+    }
+    header[CHECKSUM] = checksum.wrapping_sub(0x19);
+
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn this_roms_header_is_valid(_gba: &mut crate::Gba) {
+        let rom_header = unsafe { &*(0x0800_0004 as *const [u8; HEADER_SIZE]) };
+
+        assert_eq!(rom_header[..NINTENDO_LOGO.len()], NINTENDO_LOGO);
+        assert_eq!(rom_header[FIXED_VALUE], 0x96);
+
+        let sum = rom_header[TITLE..=CHECKSUM]
+            .iter()
+            .fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        assert_eq!(sum.wrapping_add(0x19), 0);
+    }
+
+    #[test_case]
+    fn the_multiboot_entry_point_follows_the_header(_gba: &mut crate::Gba) {
+        let multiboot_entry = unsafe { (0x0800_00C0 as *const u32).read_volatile() };
+
+        // an unconditional arm branch
+        assert_eq!(multiboot_entry >> 24, 0xEA);
+    }
+
+    #[test_case]
+    fn header_values_are_placed_correctly(_gba: &mut crate::Gba) {
+        let header = rom_header(b"MY GAME", b"AMYE", b"01", 3);
+
+        assert_eq!(&header[TITLE..TITLE + 12], b"MY GAME\0\0\0\0\0");
+        assert_eq!(&header[GAME_CODE..GAME_CODE + 4], b"AMYE");
+        assert_eq!(&header[MAKER_CODE..MAKER_CODE + 2], b"01");
+        assert_eq!(header[VERSION], 3);
+    }
+}
@@ -0,0 +1,338 @@
+#![deny(missing_docs)]
+//! The real time clock found in some cartridges.
+//!
+//! Cartridges such as the Pokémon games have a battery powered Seiko S-3511
+//! clock connected to the cartridge's GPIO port, which keeps track of the date
+//! and time while the console is off. mGBA emulates it using the computer's
+//! clock.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::rtc::Rtc;
+//!
+//! # fn foo() {
+//! if let Some(rtc) = Rtc::detect() {
+//!     if let Some(now) = rtc.read() {
+//!         let is_night = now.hour() >= 20 || now.hour() < 6;
+//!     }
+//! }
+//! # }
+//! ```
+
+use core::fmt;
+
+use crate::gpio;
+use crate::interrupt::free;
+
+const SCK: u16 = 1 << 0;
+const SIO: u16 = 1 << 1;
+const CS: u16 = 1 << 2;
+
+const RESET: u8 = 0x60;
+const STATUS: u8 = 0x62;
+const DATE_TIME: u8 = 0x64;
+/// Added to a command to read rather than write.
+const READ: u8 = 1;
+
+/// Set in the status register when the clock lost power, which means the time
+/// it holds is meaningless.
+const STATUS_POWER_LOST: u8 = 1 << 7;
+/// Set in the status register when the clock counts hours from 0 to 23.
+const STATUS_24_HOUR: u8 = 1 << 6;
+
+/// A date between the years 2000 and 2099 and a time of day, which is what
+/// the clock can hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl DateTime {
+    /// Creates a date and time, or returns `None` if it doesn't exist or the
+    /// year isn't between 2000 and 2099. Months and days count from 1, and
+    /// hours count from 0 to 23.
+    #[must_use]
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        let valid = (2000..=2099).contains(&year)
+            && (1..=12).contains(&month)
+            && day >= 1
+            && day <= days_in_month(year, month)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+
+        valid.then_some(Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// The year, from 2000 to 2099.
+    #[must_use]
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month, from 1 for January to 12 for December.
+    #[must_use]
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, starting from 1.
+    #[must_use]
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The hour, from 0 to 23.
+    #[must_use]
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute, from 0 to 59.
+    #[must_use]
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// The second, from 0 to 59.
+    #[must_use]
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// The day of the week, from 0 for Sunday to 6 for Saturday.
+    #[must_use]
+    pub fn weekday(&self) -> u8 {
+        // days since a fixed date, counting March as the first month of the
+        // year so that leap days come last
+        let (year, month) = if self.month < 3 {
+            (u32::from(self.year) - 1, u32::from(self.month) + 12)
+        } else {
+            (u32::from(self.year), u32::from(self.month))
+        };
+
+        let days = 365 * year + year / 4 - year / 100
+            + year / 400
+            + (153 * (month - 3) + 2) / 5
+            + u32::from(self.day);
+
+        ((days + 2) % 7) as u8
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn from_bcd(value: u8) -> Option<u8> {
+    let (tens, units) = (value >> 4, value & 0xf);
+    (tens < 10 && units < 10).then_some(tens * 10 + units)
+}
+
+fn to_bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+/// The cartridge's real time clock.
+pub struct Rtc {
+    _private: (),
+}
+
+impl Rtc {
+    /// Looks for a clock, and sets it up to count hours from 0 to 23 if there
+    /// is one. Returns `None` on cartridges without a clock.
+    #[must_use]
+    pub fn detect() -> Option<Self> {
+        gpio::enable();
+
+        let mut status = read_status();
+        if status & STATUS_POWER_LOST != 0 {
+            command(RESET, &[]);
+            status = 0;
+        }
+
+        command(STATUS, &[status | STATUS_24_HOUR]);
+
+        // cartridges without a clock read back as 0
+        (read_status() & STATUS_24_HOUR != 0).then_some(Self { _private: () })
+    }
+
+    /// The current date and time, or `None` if the clock holds something
+    /// which isn't a valid time, for example because its battery has run out
+    /// and it hasn't been set since.
+    #[must_use]
+    pub fn read(&self) -> Option<DateTime> {
+        let mut data = [0; 7];
+        read(DATE_TIME, &mut data);
+
+        let [year, month, day, _weekday, hour, minute, second] = data;
+
+        DateTime::new(
+            2000 + u16::from(from_bcd(year)?),
+            from_bcd(month)?,
+            from_bcd(day)?,
+            // the top bit is set in the afternoon, even in 24 hour mode
+            from_bcd(hour & 0x7f)?,
+            from_bcd(minute)?,
+            from_bcd(second)?,
+        )
+    }
+
+    /// Sets the clock.
+    pub fn set(&self, date_time: &DateTime) {
+        command(
+            DATE_TIME,
+            &[
+                to_bcd((date_time.year - 2000) as u8),
+                to_bcd(date_time.month),
+                to_bcd(date_time.day),
+                date_time.weekday(),
+                to_bcd(date_time.hour),
+                to_bcd(date_time.minute),
+                to_bcd(date_time.second),
+            ],
+        );
+    }
+}
+
+fn read_status() -> u8 {
+    let mut status = [0];
+    read(STATUS, &mut status);
+    status[0]
+}
+
+/// Sends a command and the data which goes with it.
+fn command(command: u8, data: &[u8]) {
+    free(|_| {
+        start();
+        write_byte(command, true);
+        for &byte in data {
+            write_byte(byte, false);
+        }
+        stop();
+    });
+}
+
+/// Sends a command which reads data from the clock.
+fn read(command: u8, data: &mut [u8]) {
+    free(|_| {
+        start();
+        write_byte(command | READ, true);
+
+        gpio::DIRECTION.set(SCK | CS);
+        for byte in data {
+            *byte = read_byte();
+        }
+        stop();
+    });
+}
+
+fn start() {
+    gpio::DATA.set(SCK);
+    gpio::DATA.set(SCK | CS);
+    gpio::DIRECTION.set(SCK | SIO | CS);
+}
+
+fn stop() {
+    gpio::DATA.set(SCK);
+    gpio::DATA.set(SCK);
+}
+
+/// Commands are sent with their highest bit first and data with its lowest
+/// bit first. The clock reads each bit when the clock line rises.
+fn write_byte(byte: u8, highest_bit_first: bool) {
+    for i in 0..8 {
+        let bit = if highest_bit_first {
+            (byte >> (7 - i)) & 1
+        } else {
+            (byte >> i) & 1
+        };
+        let sio = if bit != 0 { SIO } else { 0 };
+
+        // the clock needs the line low for a few cycles
+        for _ in 0..3 {
+            gpio::DATA.set(sio | CS);
+        }
+        gpio::DATA.set(sio | SCK | CS);
+    }
+}
+
+fn read_byte() -> u8 {
+    let mut byte = 0;
+
+    for _ in 0..8 {
+        for _ in 0..5 {
+            gpio::DATA.set(CS);
+        }
+        gpio::DATA.set(SCK | CS);
+
+        let bit = (gpio::DATA.get() & SIO != 0) as u8;
+        byte = (byte >> 1) | (bit << 7);
+    }
+
+    byte
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn date_times_are_validated(_gba: &mut crate::Gba) {
+        assert!(DateTime::new(2024, 2, 29, 12, 0, 0).is_some());
+        assert!(DateTime::new(2023, 2, 29, 12, 0, 0).is_none());
+        assert!(DateTime::new(2100, 1, 1, 0, 0, 0).is_none());
+        assert!(DateTime::new(2022, 4, 31, 0, 0, 0).is_none());
+        assert!(DateTime::new(2022, 4, 30, 24, 0, 0).is_none());
+
+        let date_time = DateTime::new(2022, 11, 17, 9, 5, 0).unwrap();
+        assert_eq!(
+            date_time.weekday(),
+            4,
+            "the 17th of November 2022 was a Thursday"
+        );
+        assert_eq!(
+            DateTime::new(2000, 1, 1, 0, 0, 0).unwrap().weekday(),
+            6,
+            "the 1st of January 2000 was a Saturday"
+        );
+
+        assert_eq!(from_bcd(to_bcd(59)), Some(59));
+        assert_eq!(from_bcd(0x1a), None);
+    }
+
+    #[test_case]
+    fn clock_reads_a_valid_time_if_present(_gba: &mut crate::Gba) {
+        if let Some(rtc) = Rtc::detect() {
+            assert!(rtc.read().is_some());
+        }
+    }
+}
@@ -0,0 +1,258 @@
+#![deny(missing_docs)]
+//! Rumble for cartridges with a rumble motor.
+//!
+//! Some cartridges have a rumble motor connected to pin 3 of the cartridge's
+//! GPIO port, which mGBA also emulates. The motor can only be on or off, so
+//! weaker rumbles are made by turning it on for only some of the frames.
+//!
+//! A [`Rumble`] plays patterns of [`RumbleStep`]s, each of which rumbles at
+//! an intensity for a number of frames. The pattern is stepped through in the
+//! vblank interrupt, so it keeps playing without the game needing to update
+//! it.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::rumble::{Rumble, RumbleStep};
+//!
+//! static HIT: &[RumbleStep] = &[
+//!     RumbleStep::new(RumbleStep::MAX_INTENSITY, 10),
+//!     RumbleStep::new(2, 20),
+//! ];
+//!
+//! # fn foo() {
+//! let rumble = Rumble::new();
+//! let _handler = rumble.setup_interrupt_handler();
+//!
+//! rumble.play(HIT);
+//! # }
+//! ```
+//!
+//! Writing to the GPIO port does nothing on cartridges without one, so it is
+//! safe to use rumble in games which may be played without it.
+
+use core::cell::Cell;
+
+use bare_metal::Mutex;
+
+use crate::gpio;
+use crate::interrupt::{add_interrupt_handler, free, Interrupt, InterruptHandler};
+
+const RUMBLE_PIN: u16 = 1 << 3;
+
+/// Part of a rumble pattern, which rumbles at a fixed intensity for a number
+/// of frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RumbleStep {
+    intensity: u8,
+    frames: u16,
+}
+
+impl RumbleStep {
+    /// The intensity with the motor on for every frame.
+    pub const MAX_INTENSITY: u8 = 8;
+
+    /// Creates a step which lasts for the given number of frames. The motor is
+    /// on for `intensity` out of every [`MAX_INTENSITY`](Self::MAX_INTENSITY)
+    /// frames, so an intensity of 0 is a pause in the pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the intensity is more than
+    /// [`MAX_INTENSITY`](Self::MAX_INTENSITY).
+    #[must_use]
+    pub const fn new(intensity: u8, frames: u16) -> Self {
+        assert!(
+            intensity <= Self::MAX_INTENSITY,
+            "rumble intensity can be at most 8"
+        );
+
+        Self { intensity, frames }
+    }
+}
+
+static CONTINUOUS: &[RumbleStep] = &[RumbleStep::new(RumbleStep::MAX_INTENSITY, 1)];
+
+#[derive(Clone, Copy)]
+struct PatternState {
+    steps: &'static [RumbleStep],
+    step: usize,
+    frame: u16,
+    should_loop: bool,
+}
+
+impl PatternState {
+    const STOPPED: Self = Self {
+        steps: &[],
+        step: 0,
+        frame: 0,
+        should_loop: false,
+    };
+
+    /// Moves on by a frame, and returns whether the motor should be on
+    fn next_frame(&mut self) -> bool {
+        let Some(step) = self.steps.get(self.step) else {
+            return false;
+        };
+
+        // spread the frames where the motor is on as evenly as possible
+        let intensity = u32::from(step.intensity);
+        let frame = u32::from(self.frame);
+        let motor_on = (frame + 1) * intensity / 8 != frame * intensity / 8;
+
+        self.frame += 1;
+        if self.frame >= step.frames {
+            self.frame = 0;
+            self.step += 1;
+
+            if self.should_loop && self.step >= self.steps.len() {
+                self.step = 0;
+            }
+        }
+
+        motor_on
+    }
+
+    fn is_playing(&self) -> bool {
+        self.step < self.steps.len()
+    }
+}
+
+/// Drives the rumble motor found in some cartridges. The motor is turned off
+/// when this is dropped.
+pub struct Rumble {
+    state: Mutex<Cell<PatternState>>,
+}
+
+impl Default for Rumble {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rumble {
+    /// Sets up the cartridge's GPIO port for controlling the rumble motor.
+    #[must_use]
+    pub fn new() -> Self {
+        gpio::enable();
+        gpio::DIRECTION.set(gpio::DIRECTION.get() | RUMBLE_PIN);
+        set_motor(false);
+
+        Self {
+            state: Mutex::new(Cell::new(PatternState::STOPPED)),
+        }
+    }
+
+    /// Plays the rumble patterns while the returned handler is alive.
+    #[must_use]
+    pub fn setup_interrupt_handler(&self) -> InterruptHandler<'_> {
+        add_interrupt_handler(Interrupt::VBlank, move |cs| {
+            let state = self.state.borrow(cs);
+
+            let mut pattern = state.get();
+            set_motor(pattern.next_frame());
+            state.set(pattern);
+        })
+    }
+
+    /// Rumbles at full intensity until [`stop`](Rumble::stop) is called or
+    /// another pattern is played.
+    pub fn start(&self) {
+        self.play_looping(CONTINUOUS);
+        set_motor(true);
+    }
+
+    /// Stops rumbling immediately.
+    pub fn stop(&self) {
+        self.set_pattern(PatternState::STOPPED);
+        set_motor(false);
+    }
+
+    /// Plays the pattern once, replacing anything which is already playing.
+    pub fn play(&self, pattern: &'static [RumbleStep]) {
+        self.set_pattern(PatternState {
+            steps: pattern,
+            should_loop: false,
+            ..PatternState::STOPPED
+        });
+    }
+
+    /// Plays the pattern repeatedly until [`stop`](Rumble::stop) is called or
+    /// another pattern is played.
+    pub fn play_looping(&self, pattern: &'static [RumbleStep]) {
+        self.set_pattern(PatternState {
+            steps: pattern,
+            should_loop: true,
+            ..PatternState::STOPPED
+        });
+    }
+
+    /// Whether a pattern is still playing.
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        free(|cs| self.state.borrow(cs).get().is_playing())
+    }
+
+    fn set_pattern(&self, pattern: PatternState) {
+        free(|cs| self.state.borrow(cs).set(pattern));
+    }
+}
+
+impl Drop for Rumble {
+    fn drop(&mut self) {
+        set_motor(false);
+    }
+}
+
+fn set_motor(on: bool) {
+    let data = gpio::DATA.get() & !RUMBLE_PIN;
+    gpio::DATA.set(if on { data | RUMBLE_PIN } else { data });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn rumble_intensity_is_spread_over_frames(_gba: &mut crate::Gba) {
+        static STEPS: &[RumbleStep] = &[RumbleStep::new(4, 4), RumbleStep::new(2, 8)];
+
+        let mut pattern = PatternState {
+            steps: STEPS,
+            ..PatternState::STOPPED
+        };
+
+        let frames: [bool; 12] = core::array::from_fn(|_| pattern.next_frame());
+        assert_eq!(
+            frames,
+            [false, true, false, true, false, false, false, true, false, false, false, true]
+        );
+
+        assert!(!pattern.is_playing());
+        assert!(!pattern.next_frame());
+    }
+
+    #[test_case]
+    fn rumble_patterns_play_in_vblank(_gba: &mut crate::Gba) {
+        static SHORT: &[RumbleStep] = &[RumbleStep::new(RumbleStep::MAX_INTENSITY, 2)];
+
+        let vblank = crate::interrupt::VBlank::get();
+        let rumble = Rumble::new();
+        let _handler = rumble.setup_interrupt_handler();
+
+        rumble.play(SHORT);
+        assert!(rumble.is_playing());
+
+        for _ in 0..3 {
+            vblank.wait_for_vblank();
+        }
+        assert!(!rumble.is_playing());
+
+        rumble.start();
+        vblank.wait_for_vblank();
+        assert!(rumble.is_playing());
+
+        rumble.stop();
+        assert!(!rumble.is_playing());
+    }
+}
@@ -107,7 +107,7 @@ impl EepromProperties {
         buf.write_bit(0);
         buf.submit();
 
-        // Receive the buffer data. The EEPROM sends 3 irrelevant bits followed
+        // Receive the buffer data. The EEPROM sends 4 irrelevant bits followed
         // by 64 data bits.
         buf.receive(68);
         let mut out = [0; 8];
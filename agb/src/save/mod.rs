@@ -19,7 +19,7 @@
 //!   chip ID.
 //!
 //! As these various types of save media cannot be easily distinguished at
-//! runtime, the kind of media in use should be set manually.
+//! runtime, the kind of media in use should usually be set manually.
 //!
 //! ## Setting save media type
 //!
@@ -32,17 +32,24 @@
 //! * For 512 byte EEPROM, call [`init_eeprom_512b`].
 //! * For 8 KiB EEPROM, call [`init_eeprom_8k`].
 //!
+//! If the same ROM has to work with different save media, for example on flash
+//! carts which provide different kinds of save media, call
+//! [`init_detected`] instead. It can detect SRAM and flash, but not EEPROM.
+//!
 //! [`init_sram`]: SaveManager::init_sram
 //! [`init_flash_64k`]: SaveManager::init_flash_64k
 //! [`init_flash_128k`]: SaveManager::init_flash_128k
 //! [`init_eeprom_512b`]: SaveManager::init_eeprom_512b
 //! [`init_eeprom_8k`]: SaveManager::init_eeprom_8k
+//! [`init_detected`]: SaveManager::init_detected
 //!
 //! ## Using save media
 //!
 //! To access save media, use the [`SaveManager::access`] or
 //! [`SaveManager::access_with_timer`] methods to create a new [`SaveData`]
-//! object. Its methods are used to read or write save media.
+//! object. A timer is used to time out operations on flash and EEPROM chips
+//! which stop responding, which returns [`Error::OperationTimedOut`] rather
+//! than hanging the game. Its methods are used to read or write save media.
 //!
 //! Reading data from the save media is simple. Use [`read`] to copy data from an
 //! offset in the save media into a buffer in memory.
@@ -67,14 +74,29 @@
 //! [`sector_size`]: SaveData::sector_size
 //! [`align_range`]: SaveData::align_range
 //!
+//! ## Save slots
+//!
+//! Rather than reading and writing bytes directly, a [`SaveSlot`] can store a
+//! type implementing [`SaveFormat`]. Slots have a header with the version of
+//! the format and a checksum of the data, so loading gives a [`SlotError`]
+//! for empty or corrupt slots, or ones saved with a different version, rather
+//! than garbage.
+//!
+//! Losing power part of the way through saving leaves the slot corrupt. A
+//! [`DualSaveSlot`] avoids this by alternating between two slots, so the
+//! previous save can still be loaded.
+//!
 //! ## Performance and Other Details
 //!
 //! The performance characteristics of the media types are as follows:
 //!
 //! * SRAM is simply a form of battery backed memory, and has no particular
 //!   performance characteristics.  Reads and writes at any alignment are
-//!   efficient. Furthermore, no timer is needed for accesses to this type of
-//!   media. `prepare_write` does not immediately erase any data.
+//!   efficient. It is only connected to an 8-bit bus, so is always accessed a
+//!   byte at a time, and creating a [`SaveData`] sets the SRAM wait cycles to
+//!   8 in case the game has changed them. Furthermore, no timer is needed for
+//!   accesses to this type of media. `prepare_write` does not immediately
+//!   erase any data.
 //! * Non-Atmel flash chips have a sector size of 4096 bytes. Reads and writes
 //!   to any alignment are efficient, however, `prepare_write` will erase all
 //!   data in an entire sector before writing.
@@ -83,7 +105,8 @@
 //!   `prepare_write` does not immediately erase any data.
 //! * EEPROM has a sector size of 8 bytes. Unaligned reads and writes are slower
 //!   than aligned writes, however, this is easily mitigated by the small sector
-//!   size.
+//!   size. EEPROM is accessed with DMA channel 3, and needs 8 wait cycles on
+//!   wait state 2, which creating a [`SaveData`] sets.
 
 use crate::save::utils::Timeout;
 use crate::sync::{Mutex, RawMutexGuard};
@@ -93,9 +116,12 @@ use core::ops::Range;
 mod asm_utils;
 mod eeprom;
 mod flash;
+mod slot;
 mod sram;
 mod utils;
 
+pub use slot::{DualSaveSlot, SaveFormat, SaveReader, SaveSlot, SaveWriter, SlotError};
+
 /// A list of save media types.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 #[non_exhaustive]
@@ -205,12 +231,17 @@ impl SaveData {
     /// Creates a new save accessor around the current save implementation.
     fn new(timer: Option<Timer>) -> Result<SaveData, Error> {
         match get_save_implementation() {
-            Some(access) => Ok(SaveData {
-                _lock: utils::lock_media_access()?,
-                access,
-                info: access.info()?,
-                timeout: utils::Timeout::new(timer),
-            }),
+            Some(access) => {
+                let lock = utils::lock_media_access()?;
+                utils::set_save_waitstates();
+
+                Ok(SaveData {
+                    _lock: lock,
+                    access,
+                    info: access.info()?,
+                    timeout: utils::Timeout::new(timer),
+                })
+            }
             None => Err(Error::NoMedia),
         }
     }
@@ -455,12 +486,54 @@ impl SaveManager {
         set_save_implementation(&eeprom::Eeprom8K);
     }
 
+    /// Detects whether the cartridge has SRAM or flash save media and
+    /// configures the save manager to use it, returning the type which was
+    /// found.
+    ///
+    /// Returns `None` if neither was found, in which case the cartridge
+    /// probably uses EEPROM, or has no save media. EEPROM can't be reliably
+    /// detected, and the two sizes of EEPROM can't be told apart, so call
+    /// [`init_eeprom_512b`](SaveManager::init_eeprom_512b) or
+    /// [`init_eeprom_8k`](SaveManager::init_eeprom_8k) for cartridges with
+    /// EEPROM.
+    ///
+    /// Detecting SRAM briefly overwrites the first byte of the save data.
+    /// Unlike the other `init_*` functions, this doesn't create a marker in the
+    /// ROM, so emulators will need to detect the save type themselves.
+    ///
+    /// Only one `init_*` function may be called in the lifetime of the program,
+    /// apart from after this returns `None`.
+    pub fn init_detected(&mut self) -> Option<MediaType> {
+        utils::set_save_waitstates();
+
+        // this is tried first as checking for flash writes commands into SRAM
+        if sram::is_present() {
+            set_save_implementation(&sram::BatteryBackedAccess);
+            return Some(MediaType::Sram32K);
+        }
+
+        if matches!(flash::FlashChipType::detect(), Ok(chip) if chip != flash::FlashChipType::Unknown)
+        {
+            set_save_implementation(&flash::FlashAccess);
+            return flash::FlashAccess.info().ok().map(|info| info.media_type);
+        }
+
+        None
+    }
+
     /// Creates a new accessor to the save data.
     ///
+    /// Flash and EEPROM operations time out so that a faulty chip can't hang
+    /// the game. This uses timer 3, or timer 2 if timer 3 is in use, for the
+    /// timeouts while the accessor exists, and waits forever if both are in
+    /// use. Timers 0 and 1 are never used, as sound needs them. Use
+    /// [`access_with_timer`](SaveManager::access_with_timer) to choose the
+    /// timer.
+    ///
     /// You must have initialized the save manager beforehand to use a specific
     /// type of media before calling this method.
     pub fn access(&mut self) -> Result<SaveData, Error> {
-        SaveData::new(None)
+        SaveData::new(Timer::try_reserve_spare())
     }
 
     /// Creates a new accessor to the save data that uses the given timer for timeouts.
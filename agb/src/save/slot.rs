@@ -0,0 +1,580 @@
+//! Typed save slots with a header, format version and checksum.
+
+use alloc::vec::Vec;
+
+use super::{Error, SaveData};
+
+/// Written at the start of every slot, so an empty or erased slot can be told
+/// apart from a corrupt one.
+const MAGIC: [u8; 4] = *b"agbs";
+/// The magic, version, sequence number, payload length and checksum
+const HEADER_LEN: usize = 16;
+/// Everything in the header apart from the checksum is included in the
+/// checksum along with the payload.
+const CHECKED_HEADER_LEN: usize = 12;
+
+/// Errors which can happen when saving or loading a [`SaveSlot`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum SlotError {
+    /// Reading or writing the save media failed.
+    Media(Error),
+    /// Nothing has been saved in the slot.
+    Empty,
+    /// The saved data doesn't match its checksum, or couldn't be read back
+    /// into the type.
+    Corrupt,
+    /// The slot was saved with a different version of the format, given by
+    /// [`SaveFormat::VERSION`].
+    WrongVersion {
+        /// The version the slot was saved with.
+        found: u16,
+    },
+    /// The data is too large to fit in the slot.
+    TooLarge,
+}
+
+impl From<Error> for SlotError {
+    fn from(error: Error) -> Self {
+        SlotError::Media(error)
+    }
+}
+
+/// A type which can be stored in a [`SaveSlot`].
+///
+/// The fields are written and read back in a fixed order using a
+/// [`SaveWriter`] and [`SaveReader`], so the layout doesn't change when the
+/// type's layout in memory does.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::save::{SaveFormat, SaveReader, SaveWriter, SlotError};
+///
+/// struct Progress {
+///     level: u8,
+///     coins: u32,
+///     has_sword: bool,
+/// }
+///
+/// impl SaveFormat for Progress {
+///     const VERSION: u16 = 1;
+///
+///     fn write(&self, writer: &mut SaveWriter) {
+///         writer.write_u8(self.level);
+///         writer.write_u32(self.coins);
+///         writer.write_bool(self.has_sword);
+///     }
+///
+///     fn read(reader: &mut SaveReader) -> Result<Self, SlotError> {
+///         Ok(Progress {
+///             level: reader.read_u8()?,
+///             coins: reader.read_u32()?,
+///             has_sword: reader.read_bool()?,
+///         })
+///     }
+/// }
+/// ```
+pub trait SaveFormat: Sized {
+    /// The version of the format. Change this whenever the fields which are
+    /// written change, so that saves from older versions aren't misread.
+    const VERSION: u16;
+
+    /// Writes each field to the writer.
+    fn write(&self, writer: &mut SaveWriter);
+
+    /// Reads the fields back in the same order they were written.
+    fn read(reader: &mut SaveReader) -> Result<Self, SlotError>;
+}
+
+/// Builds the bytes stored in a [`SaveSlot`]. All numbers are little endian.
+#[derive(Default)]
+pub struct SaveWriter {
+    data: Vec<u8>,
+}
+
+impl SaveWriter {
+    /// Writes a byte.
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    /// Writes a `u16` as 2 bytes.
+    pub fn write_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes a `u32` as 4 bytes.
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes an `i32` as 4 bytes.
+    pub fn write_i32(&mut self, value: i32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes a `bool` as a single byte.
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(u8::from(value));
+    }
+
+    /// Writes the bytes as they are. The length isn't written, so must be
+    /// known when reading them back.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+}
+
+/// Reads back the bytes written by a [`SaveWriter`]. Reading past the end of
+/// the data returns [`SlotError::Corrupt`].
+pub struct SaveReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SaveReader<'a> {
+    /// Reads the next `len` bytes.
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SlotError> {
+        if len > self.data.len() {
+            return Err(SlotError::Corrupt);
+        }
+
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], SlotError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    /// Reads a byte.
+    pub fn read_u8(&mut self) -> Result<u8, SlotError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    /// Reads a `u16`.
+    pub fn read_u16(&mut self) -> Result<u16, SlotError> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a `u32`.
+    pub fn read_u32(&mut self) -> Result<u32, SlotError> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads an `i32`.
+    pub fn read_i32(&mut self) -> Result<i32, SlotError> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a `bool`, returning [`SlotError::Corrupt`] if the byte is
+    /// neither 0 nor 1.
+    pub fn read_bool(&mut self) -> Result<bool, SlotError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SlotError::Corrupt),
+        }
+    }
+}
+
+/// The CRC-32 used by zip and png, calculated a bit at a time to avoid the
+/// kilobyte of table.
+fn crc32<'a>(data: impl IntoIterator<Item = &'a u8>) -> u32 {
+    !data.into_iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+fn encode<T: SaveFormat>(value: &T, sequence: u16) -> Vec<u8> {
+    let mut writer = SaveWriter::default();
+    writer.write_bytes(&MAGIC);
+    writer.write_u16(T::VERSION);
+    writer.write_u16(sequence);
+    writer.write_u32(0);
+    writer.write_u32(0);
+
+    value.write(&mut writer);
+
+    let mut data = writer.data;
+    let payload_len = (data.len() - HEADER_LEN) as u32;
+    data[8..12].copy_from_slice(&payload_len.to_le_bytes());
+    let checksum = crc32(data[..CHECKED_HEADER_LEN].iter().chain(&data[HEADER_LEN..]));
+    data[12..16].copy_from_slice(&checksum.to_le_bytes());
+
+    data
+}
+
+struct Header {
+    version: u16,
+    sequence: u16,
+    payload_len: usize,
+    checksum: u32,
+}
+
+impl Header {
+    /// The bytes of the header which are included in the checksum.
+    fn checked_bytes(&self) -> [u8; CHECKED_HEADER_LEN] {
+        let mut bytes = [0; CHECKED_HEADER_LEN];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[8..12].copy_from_slice(&(self.payload_len as u32).to_le_bytes());
+        bytes
+    }
+}
+
+fn decode_header(bytes: &[u8]) -> Result<Header, SlotError> {
+    let mut reader = SaveReader { data: bytes };
+    if reader.read_bytes(MAGIC.len())? != MAGIC {
+        return Err(SlotError::Empty);
+    }
+
+    Ok(Header {
+        version: reader.read_u16()?,
+        sequence: reader.read_u16()?,
+        payload_len: reader.read_u32()? as usize,
+        checksum: reader.read_u32()?,
+    })
+}
+
+/// The header and data of a slot whose data matches its checksum
+type CheckedSlot = Result<(Header, Vec<u8>), SlotError>;
+
+fn check_payload(header: &Header, payload: &[u8]) -> Result<(), SlotError> {
+    if crc32(header.checked_bytes().iter().chain(payload)) == header.checksum {
+        Ok(())
+    } else {
+        Err(SlotError::Corrupt)
+    }
+}
+
+fn decode_payload<T: SaveFormat>(header: &Header, payload: &[u8]) -> Result<T, SlotError> {
+    if header.version != T::VERSION {
+        return Err(SlotError::WrongVersion {
+            found: header.version,
+        });
+    }
+
+    T::read(&mut SaveReader { data: payload })
+}
+
+/// An area of the save media which stores a value of a [`SaveFormat`] type,
+/// along with a header which is used to check that the value is from the
+/// same version of the format and hasn't been corrupted.
+///
+/// Saving prepares the sectors the slot overlaps for writing, which erases
+/// them on some save media, so slots should start and end on sector
+/// boundaries. [`SaveData::align_range`] gives the sectors a slot will use.
+#[derive(Clone, Debug)]
+pub struct SaveSlot {
+    offset: usize,
+    len: usize,
+}
+
+impl SaveSlot {
+    /// A slot taking up `len` bytes of the save media starting at `offset`.
+    /// 16 bytes of this are used by the header.
+    #[must_use]
+    pub const fn new(offset: usize, len: usize) -> Self {
+        Self { offset, len }
+    }
+
+    /// Loads the value saved in the slot.
+    pub fn load<T: SaveFormat>(&self, access: &mut SaveData) -> Result<T, SlotError> {
+        let (header, payload) = self.read_checked(access)?;
+        decode_payload(&header, &payload)
+    }
+
+    /// Saves the value in the slot.
+    pub fn save<T: SaveFormat>(&self, access: &mut SaveData, value: &T) -> Result<(), SlotError> {
+        self.save_with_sequence(access, value, 0)
+    }
+
+    /// Reads the header and data, and checks the data matches the checksum.
+    fn read_checked(&self, access: &mut SaveData) -> CheckedSlot {
+        let mut header = [0; HEADER_LEN];
+        access.read(self.offset, &mut header)?;
+        let header = decode_header(&header)?;
+
+        if header.payload_len > self.len.saturating_sub(HEADER_LEN) {
+            return Err(SlotError::Corrupt);
+        }
+
+        let mut payload = alloc::vec![0; header.payload_len];
+        access.read(self.offset + HEADER_LEN, &mut payload)?;
+        check_payload(&header, &payload)?;
+
+        Ok((header, payload))
+    }
+
+    fn save_with_sequence<T: SaveFormat>(
+        &self,
+        access: &mut SaveData,
+        value: &T,
+        sequence: u16,
+    ) -> Result<(), SlotError> {
+        let data = encode(value, sequence);
+        if data.len() > self.len {
+            return Err(SlotError::TooLarge);
+        }
+
+        access
+            .prepare_write(self.offset..self.offset + self.len)?
+            .write_and_verify(self.offset, &data)?;
+
+        Ok(())
+    }
+}
+
+/// Whether sequence number `a` was saved after `b`, allowing for the sequence
+/// numbers wrapping around.
+fn is_newer(a: u16, b: u16) -> bool {
+    (a.wrapping_sub(b) as i16) > 0
+}
+
+/// Picks the slot with the newest data which isn't corrupt, or the error to
+/// report if neither slot can be used.
+fn choose_newest(first: CheckedSlot, second: CheckedSlot) -> CheckedSlot {
+    match (first, second) {
+        (Ok(first), Ok(second)) if !is_newer(second.0.sequence, first.0.sequence) => Ok(first),
+        (_, Ok(second)) => Ok(second),
+        (Ok(first), Err(_)) => Ok(first),
+        (Err(SlotError::Empty), Err(error)) | (Err(error), Err(_)) => Err(error),
+    }
+}
+
+/// Two [`SaveSlot`]s used together so that losing power while saving can't
+/// lose the saved data.
+///
+/// Each save goes to the slot which doesn't have the newest data, along with
+/// a sequence number one higher than the other slot's. Loading uses the slot
+/// with the highest sequence number which isn't corrupt, so if saving is
+/// interrupted, the previous save is loaded instead.
+///
+/// The two slots mustn't overlap any of the same sectors.
+#[derive(Clone, Debug)]
+pub struct DualSaveSlot {
+    slots: [SaveSlot; 2],
+}
+
+impl DualSaveSlot {
+    /// Uses the two slots to store the value.
+    #[must_use]
+    pub const fn new(first: SaveSlot, second: SaveSlot) -> Self {
+        Self {
+            slots: [first, second],
+        }
+    }
+
+    /// Loads the newest value which was saved successfully.
+    ///
+    /// If neither slot can be loaded, this returns [`SlotError::Empty`] if
+    /// nothing has been saved, and otherwise the error from one of the slots
+    /// which isn't empty.
+    pub fn load<T: SaveFormat>(&self, access: &mut SaveData) -> Result<T, SlotError> {
+        let (first, second) = self.read_both(access);
+        let (header, data) = choose_newest(first, second)?;
+
+        decode_payload(&header, &data)
+    }
+
+    /// Saves the value in the slot which doesn't have the newest data.
+    pub fn save<T: SaveFormat>(&self, access: &mut SaveData, value: &T) -> Result<(), SlotError> {
+        let (first, second) = self.read_both(access);
+
+        let (slot, sequence) = match (first, second) {
+            (Ok((first, _)), Ok((second, _))) => {
+                if is_newer(second.sequence, first.sequence) {
+                    (0, second.sequence)
+                } else {
+                    (1, first.sequence)
+                }
+            }
+            (Ok((first, _)), Err(_)) => (1, first.sequence),
+            (Err(_), Ok((second, _))) => (0, second.sequence),
+            (Err(_), Err(_)) => (0, 0),
+        };
+
+        self.slots[slot].save_with_sequence(access, value, sequence.wrapping_add(1))
+    }
+
+    fn read_both(&self, access: &mut SaveData) -> (CheckedSlot, CheckedSlot) {
+        (
+            self.slots[0].read_checked(access),
+            self.slots[1].read_checked(access),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Progress {
+        level: u8,
+        coins: u32,
+        has_sword: bool,
+    }
+
+    impl SaveFormat for Progress {
+        const VERSION: u16 = 3;
+
+        fn write(&self, writer: &mut SaveWriter) {
+            writer.write_u8(self.level);
+            writer.write_u32(self.coins);
+            writer.write_bool(self.has_sword);
+        }
+
+        fn read(reader: &mut SaveReader) -> Result<Self, SlotError> {
+            Ok(Progress {
+                level: reader.read_u8()?,
+                coins: reader.read_u32()?,
+                has_sword: reader.read_bool()?,
+            })
+        }
+    }
+
+    fn check(bytes: &[u8]) -> CheckedSlot {
+        let header = decode_header(bytes)?;
+        let payload = bytes[HEADER_LEN..].to_vec();
+        check_payload(&header, &payload)?;
+        Ok((header, payload))
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Progress, SlotError> {
+        let (header, payload) = check(bytes)?;
+        decode_payload(&header, &payload)
+    }
+
+    fn at_level(level: u8) -> Progress {
+        Progress {
+            level,
+            coins: 0,
+            has_sword: false,
+        }
+    }
+
+    fn chosen_level(first: &[u8], second: &[u8]) -> Result<u8, SlotError> {
+        let (header, payload) = choose_newest(check(first), check(second))?;
+        decode_payload::<Progress>(&header, &payload).map(|progress| progress.level)
+    }
+
+    /// Updates the checksum after changing the header or payload.
+    fn reseal(bytes: &mut [u8]) {
+        let checksum = crc32(
+            bytes[..CHECKED_HEADER_LEN]
+                .iter()
+                .chain(&bytes[HEADER_LEN..]),
+        );
+        bytes[12..16].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    #[test_case]
+    fn crc32_matches_the_standard_check_value(_gba: &mut crate::Gba) {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test_case]
+    fn slots_detect_corruption_and_version_changes(_gba: &mut crate::Gba) {
+        let progress = Progress {
+            level: 4,
+            coins: 1234,
+            has_sword: true,
+        };
+
+        let mut bytes = encode(&progress, 0);
+        assert_eq!(bytes.len(), HEADER_LEN + 6);
+        assert_eq!(decode(&bytes).unwrap(), progress);
+
+        assert!(matches!(decode(&[0xff; 32]), Err(SlotError::Empty)));
+
+        bytes[4] = 2;
+        reseal(&mut bytes);
+        assert!(matches!(
+            decode(&bytes),
+            Err(SlotError::WrongVersion { found: 2 })
+        ));
+
+        bytes[4] = 3;
+        reseal(&mut bytes);
+        bytes[HEADER_LEN + 1] ^= 1;
+        assert!(matches!(decode(&bytes), Err(SlotError::Corrupt)));
+    }
+
+    #[test_case]
+    fn the_header_is_part_of_the_checksum(_gba: &mut crate::Gba) {
+        let progress = Progress {
+            level: 4,
+            coins: 1234,
+            has_sword: true,
+        };
+
+        let bytes = encode(&progress, 7);
+        assert!(decode(&bytes).is_ok());
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] ^= 1;
+        assert!(matches!(decode(&wrong_version), Err(SlotError::Corrupt)));
+
+        let mut wrong_sequence = bytes;
+        wrong_sequence[6] ^= 1;
+        assert!(matches!(decode(&wrong_sequence), Err(SlotError::Corrupt)));
+    }
+
+    #[test_case]
+    fn sequence_numbers_can_wrap(_gba: &mut crate::Gba) {
+        assert!(is_newer(1, 0));
+        assert!(is_newer(0, u16::MAX));
+        assert!(!is_newer(5, 5));
+        assert!(!is_newer(u16::MAX, 0));
+    }
+
+    #[test_case]
+    fn dual_slots_load_the_newest_save(_gba: &mut crate::Gba) {
+        let older = encode(&at_level(1), 3);
+        let newer = encode(&at_level(2), 4);
+
+        assert_eq!(chosen_level(&older, &newer).unwrap(), 2);
+        assert_eq!(chosen_level(&newer, &older).unwrap(), 2);
+
+        let wrapped = encode(&at_level(3), 0);
+        let before_wrapping = encode(&at_level(4), u16::MAX);
+        assert_eq!(chosen_level(&before_wrapping, &wrapped).unwrap(), 3);
+    }
+
+    #[test_case]
+    fn dual_slots_skip_corrupt_saves(_gba: &mut crate::Gba) {
+        let older = encode(&at_level(1), 3);
+
+        let mut corrupt_payload = encode(&at_level(2), 4);
+        corrupt_payload[HEADER_LEN] ^= 1;
+        assert_eq!(chosen_level(&older, &corrupt_payload).unwrap(), 1);
+        assert_eq!(chosen_level(&corrupt_payload, &older).unwrap(), 1);
+
+        // a partly written header mustn't make a stale save look newest
+        let mut corrupt_sequence = encode(&at_level(2), 2);
+        corrupt_sequence[6] = 9;
+        assert_eq!(chosen_level(&older, &corrupt_sequence).unwrap(), 1);
+
+        let empty = [0xff; 32];
+        assert!(matches!(
+            chosen_level(&empty, &empty),
+            Err(SlotError::Empty)
+        ));
+        assert!(matches!(
+            chosen_level(&empty, &corrupt_payload),
+            Err(SlotError::Corrupt)
+        ));
+        assert!(matches!(
+            chosen_level(&corrupt_payload, &empty),
+            Err(SlotError::Corrupt)
+        ));
+    }
+}
@@ -8,6 +8,7 @@ use crate::save::asm_utils::*;
 use crate::save::utils::Timeout;
 
 const SRAM_SIZE: usize = 32 * 1024; // 32 KiB
+const SRAM_START: usize = 0x0E000000;
 
 /// Checks whether an offset is contained within the bounds of the SRAM.
 fn check_bounds(offset: usize, len: usize) -> Result<(), Error> {
@@ -17,6 +18,21 @@ fn check_bounds(offset: usize, len: usize) -> Result<(), Error> {
     Ok(())
 }
 
+/// Checks whether there is SRAM by writing to the first byte and reading it
+/// back. The first byte is restored afterwards.
+pub fn is_present() -> bool {
+    unsafe {
+        let original = read_raw_byte(SRAM_START);
+        let present = [0x55, 0xAA].iter().all(|&test| {
+            write_raw_buf(SRAM_START, &[test]);
+            read_raw_byte(SRAM_START) == test
+        });
+        write_raw_buf(SRAM_START, &[original]);
+
+        present
+    }
+}
+
 /// The [`RawSaveAccess`] used for battery backed SRAM.
 pub struct BatteryBackedAccess;
 impl RawSaveAccess for BatteryBackedAccess {
@@ -32,14 +48,14 @@ impl RawSaveAccess for BatteryBackedAccess {
     fn read(&self, offset: usize, buffer: &mut [u8], _: &mut Timeout) -> Result<(), Error> {
         check_bounds(offset, buffer.len())?;
         unsafe {
-            read_raw_buf(buffer, 0x0E000000 + offset);
+            read_raw_buf(buffer, SRAM_START + offset);
         }
         Ok(())
     }
 
     fn verify(&self, offset: usize, buffer: &[u8], _: &mut Timeout) -> Result<bool, Error> {
         check_bounds(offset, buffer.len())?;
-        let val = unsafe { verify_raw_buf(buffer, 0x0E000000 + offset) };
+        let val = unsafe { verify_raw_buf(buffer, SRAM_START + offset) };
         Ok(val)
     }
 
@@ -50,7 +66,7 @@ impl RawSaveAccess for BatteryBackedAccess {
     fn write(&self, offset: usize, buffer: &[u8], _: &mut Timeout) -> Result<(), Error> {
         check_bounds(offset, buffer.len())?;
         unsafe {
-            write_raw_buf(0x0E000000 + offset, buffer);
+            write_raw_buf(SRAM_START + offset, buffer);
         }
         Ok(())
     }
@@ -3,6 +3,7 @@
 use super::Error;
 use crate::sync::{RawMutex, RawMutexGuard};
 use crate::timer::{Timer, Divider};
+use crate::waitstate::WAITSTATE_CONTROL;
 
 /// A timeout type used to prevent hardware errors in save media from hanging
 /// the game.
@@ -36,7 +37,7 @@ impl Timeout {
     /// to [`Timeout::start()`].
     pub fn check_timeout_met(&self, check_ms: u16) -> bool {
         if let Some(timer) = &self.timer {
-            check_ms * 17 < timer.value()
+            u32::from(check_ms) * 17 < u32::from(timer.value())
         } else {
             false
         }
@@ -57,3 +58,11 @@ pub fn lock_media_access() -> Result<RawMutexGuard<'static>, Error> {
         None => Err(Error::MediaInUse),
     }
 }
+
+/// Sets the SRAM region and the first access to wait state 2, which is where
+/// EEPROM is, to 8 wait cycles. This is the only setting that works with every
+/// save chip, and while it is set at startup the game may have changed it
+/// since.
+pub fn set_save_waitstates() {
+    WAITSTATE_CONTROL.set(WAITSTATE_CONTROL.get() | 0b11 | (0b11 << 8));
+}
@@ -0,0 +1,194 @@
+#![deny(missing_docs)]
+//! Sensors found in some cartridges.
+//!
+//! * [`SolarSensor`] measures sunlight, like the sensor in the Boktai games.
+//! * [`Gyro`] measures how fast the console is being turned, like the gyro in
+//!   WarioWare: Twisted!.
+//!
+//! Both are connected to the cartridge's GPIO port, which they share with the
+//! [real time clock](crate::rtc) and [rumble](crate::rumble) found in other
+//! cartridges. Neither can say what it is, so each is detected by checking
+//! that it responds the way the sensor would. mGBA emulates both, taking the
+//! readings from the computer's controller or keyboard.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::sensor::{Gyro, SolarSensor};
+//!
+//! # fn foo() {
+//! if let Some(sensor) = SolarSensor::detect() {
+//!     let is_sunny = sensor.light() > 128;
+//! }
+//!
+//! if let Some(gyro) = Gyro::detect() {
+//!     let steering = gyro.rotation() / 16;
+//! }
+//! # }
+//! ```
+
+use crate::gpio;
+use crate::interrupt::free;
+
+mod solar {
+    pub const CLOCK: u16 = 1 << 0;
+    pub const RESET: u16 = 1 << 1;
+    pub const CHIP_SELECT: u16 = 1 << 2;
+    /// Set by the sensor once the counter has passed the light level.
+    pub const FLAG: u16 = 1 << 3;
+}
+
+mod gyro {
+    pub const START: u16 = 1 << 0;
+    pub const CLOCK: u16 = 1 << 1;
+    pub const DATA: u16 = 1 << 2;
+    /// The rumble motor, which shares the port with the gyro.
+    pub const RUMBLE: u16 = 1 << 3;
+}
+
+/// A sensor which measures how much sunlight falls on the cartridge.
+pub struct SolarSensor {
+    _private: (),
+}
+
+impl SolarSensor {
+    /// Looks for a solar sensor, and returns `None` on cartridges without one.
+    #[must_use]
+    pub fn detect() -> Option<Self> {
+        gpio::enable();
+
+        // without a sensor, the flag is never set
+        measure().map(|_| Self { _private: () })
+    }
+
+    /// The light level, from 0 in darkness to 255 in direct sunlight. Readings
+    /// from the sensor aren't very precise, so games usually group them into a
+    /// few levels.
+    #[must_use]
+    pub fn light(&self) -> u8 {
+        // the sensor was there when detected, so it can only fail to respond
+        // in complete darkness
+        measure().map_or(0, |count| u8::MAX - count)
+    }
+}
+
+/// Counts up until the sensor's flag is set, which takes longer the darker it
+/// is. Returns `None` if the flag is never set.
+fn measure() -> Option<u8> {
+    free(|_| {
+        gpio::DIRECTION.set(solar::CLOCK | solar::RESET | solar::CHIP_SELECT);
+
+        gpio::DATA.set(solar::RESET);
+        gpio::DATA.set(0);
+
+        for count in 0..=u8::MAX {
+            if gpio::DATA.get() & solar::FLAG != 0 {
+                return Some(count);
+            }
+
+            gpio::DATA.set(solar::CLOCK);
+            gpio::DATA.set(0);
+        }
+
+        None
+    })
+}
+
+/// A gyro which measures how fast the console is being rotated flat on a table,
+/// like turning a steering wheel.
+pub struct Gyro {
+    centre: u16,
+}
+
+impl Gyro {
+    /// Looks for a gyro, and returns `None` on cartridges without one. The
+    /// console should be still when this is called, as the reading taken now
+    /// is what counts as not rotating.
+    #[must_use]
+    pub fn detect() -> Option<Self> {
+        gpio::enable();
+
+        // without a gyro the data pin always reads 0, where a gyro reads close
+        // to the middle of its range
+        let centre = sample();
+        (centre != 0).then_some(Self { centre })
+    }
+
+    /// The raw 12-bit reading from the gyro, which is around 0x6c0 when the
+    /// console is still.
+    #[must_use]
+    pub fn raw(&self) -> u16 {
+        sample()
+    }
+
+    /// How fast the console is rotating compared to when the gyro was
+    /// detected or last calibrated, which is positive when turned one way and
+    /// negative when turned the other.
+    #[must_use]
+    pub fn rotation(&self) -> i16 {
+        self.centre as i16 - sample() as i16
+    }
+
+    /// Takes the current reading as what counts as not rotating, to correct for
+    /// the gyro drifting.
+    pub fn calibrate(&mut self) {
+        self.centre = sample();
+    }
+}
+
+/// Reads the gyro one bit at a time, most significant bit first, leaving the
+/// rumble motor as it was.
+fn sample() -> u16 {
+    free(|_| {
+        let rumble = gpio::DATA.get() & gyro::RUMBLE;
+        gpio::DIRECTION.set(gyro::START | gyro::CLOCK | gyro::RUMBLE);
+
+        gpio::DATA.set(rumble | gyro::START | gyro::CLOCK);
+        gpio::DATA.set(rumble | gyro::CLOCK);
+
+        let mut value = 0;
+        for _ in 0..16 {
+            // the gyro sends each bit as the clock falls
+            gpio::DATA.set(rumble);
+            value = (value << 1) | u16::from(gpio::DATA.get() & gyro::DATA != 0);
+            gpio::DATA.set(rumble | gyro::CLOCK);
+        }
+
+        value & 0xfff
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn sensors_read_if_present(_gba: &mut crate::Gba) {
+        match SolarSensor::detect() {
+            Some(sensor) => {
+                let _ = sensor.light();
+            }
+            None => {
+                assert_eq!(measure(), None);
+
+                // a sensor which stops responding reads as complete darkness
+                let sensor = SolarSensor { _private: () };
+                assert_eq!(sensor.light(), 0);
+            }
+        }
+
+        match Gyro::detect() {
+            Some(gyro) => assert!(gyro.raw() <= 0xfff),
+            None => {
+                assert_eq!(sample(), 0);
+
+                let mut gyro = Gyro { centre: 0x6c0 };
+                assert_eq!(gyro.raw(), 0);
+                assert_eq!(gyro.rotation(), 0x6c0);
+
+                gyro.calibrate();
+                assert_eq!(gyro.rotation(), 0);
+            }
+        }
+    }
+}
@@ -0,0 +1,474 @@
+#![deny(missing_docs)]
+//! Iterators over the points making up lines, circles, ellipses and filled
+//! triangles, using only integer maths.
+//!
+//! These can be drawn with the bitmap modes using
+//! [`Bitmap3::draw_points`](crate::display::bitmap3::Bitmap3::draw_points), or
+//! used in game logic, such as checking for line of sight across a grid of
+//! tiles.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::fixnum::Vector2D;
+//! use agb::shapes::Line;
+//!
+//! # fn foo(is_wall: impl Fn(Vector2D<i32>) -> bool) {
+//! let guard = (3, 4).into();
+//! let player = (12, 9).into();
+//!
+//! let can_see_player = Line::new(guard, player).all(|tile| !is_wall(tile));
+//! # }
+//! ```
+
+use crate::fixnum::Vector2D;
+
+/// The points on a straight line between two points, including both ends, in
+/// order from the start to the end. Uses Bresenham's line algorithm.
+#[derive(Clone, Debug)]
+pub struct Line {
+    position: Vector2D<i32>,
+    delta: Vector2D<i32>,
+    step: Vector2D<i32>,
+    error: i32,
+    remaining: usize,
+}
+
+impl Line {
+    /// The line from `start` to `end`.
+    #[must_use]
+    pub fn new(start: Vector2D<i32>, end: Vector2D<i32>) -> Self {
+        let delta = Vector2D::new((end.x - start.x).abs(), -(end.y - start.y).abs());
+
+        Self {
+            position: start,
+            delta,
+            step: Vector2D::new((end.x - start.x).signum(), (end.y - start.y).signum()),
+            error: delta.x + delta.y,
+            remaining: delta.x.max(-delta.y) as usize + 1,
+        }
+    }
+}
+
+impl Iterator for Line {
+    type Item = Vector2D<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let point = self.position;
+
+        let error2 = 2 * self.error;
+        if error2 >= self.delta.y {
+            self.error += self.delta.y;
+            self.position.x += self.step.x;
+        }
+        if error2 <= self.delta.x {
+            self.error += self.delta.x;
+            self.position.y += self.step.y;
+        }
+
+        Some(point)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Line {}
+
+/// The points from one step of a symmetric shape, skipping any which are the
+/// same as an earlier one in the step.
+#[derive(Clone, Debug)]
+struct Reflections {
+    points: [Vector2D<i32>; 8],
+    len: usize,
+    next: usize,
+}
+
+impl Reflections {
+    const fn new() -> Self {
+        Self {
+            points: [Vector2D { x: 0, y: 0 }; 8],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    fn fill(&mut self, centre: Vector2D<i32>, offsets: &[(i32, i32)]) {
+        self.len = 0;
+        self.next = 0;
+
+        for &(x, y) in offsets {
+            let point = centre + Vector2D::new(x, y);
+            if !self.points[..self.len].contains(&point) {
+                self.points[self.len] = point;
+                self.len += 1;
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<Vector2D<i32>> {
+        let point = self.points[..self.len].get(self.next).copied();
+        self.next += 1;
+        point
+    }
+}
+
+/// The points on the outline of a circle, using the midpoint circle
+/// algorithm. Each point is given once, but not in order around the circle.
+#[derive(Clone, Debug)]
+pub struct Circle {
+    centre: Vector2D<i32>,
+    x: i32,
+    y: i32,
+    error: i32,
+    reflections: Reflections,
+}
+
+impl Circle {
+    /// The circle around `centre`. A radius of 0 gives just the centre.
+    #[must_use]
+    pub fn new(centre: Vector2D<i32>, radius: u16) -> Self {
+        let radius = i32::from(radius);
+
+        Self {
+            centre,
+            x: radius,
+            y: 0,
+            error: 1 - radius,
+            reflections: Reflections::new(),
+        }
+    }
+}
+
+impl Iterator for Circle {
+    type Item = Vector2D<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(point) = self.reflections.next() {
+                return Some(point);
+            }
+
+            if self.x < self.y {
+                return None;
+            }
+
+            let (x, y) = (self.x, self.y);
+            self.reflections.fill(
+                self.centre,
+                &[
+                    (x, y),
+                    (-x, y),
+                    (x, -y),
+                    (-x, -y),
+                    (y, x),
+                    (-y, x),
+                    (y, -x),
+                    (-y, -x),
+                ],
+            );
+
+            self.y += 1;
+            if self.error < 0 {
+                self.error += 2 * self.y + 1;
+            } else {
+                self.x -= 1;
+                self.error += 2 * (self.y - self.x) + 1;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EllipseRegion {
+    /// Where the outline is closer to horizontal, so x always increases.
+    Shallow,
+    /// Where the outline is closer to vertical, so y always decreases.
+    Steep,
+    /// The ends of very flat ellipses which the steep region misses.
+    Flat,
+    Finished,
+}
+
+/// The points on the outline of an ellipse whose axes are horizontal and
+/// vertical, using the midpoint ellipse algorithm. Each point is given once,
+/// but not in order around the ellipse.
+#[derive(Clone, Debug)]
+pub struct Ellipse {
+    centre: Vector2D<i32>,
+    radius_x: i64,
+    radius_y: i64,
+    region: EllipseRegion,
+    x: i64,
+    y: i64,
+    // the decision variables are 4 times their usual value to stay integers
+    decision: i64,
+    change_x: i64,
+    change_y: i64,
+    reflections: Reflections,
+}
+
+impl Ellipse {
+    /// The ellipse around `centre` with the given horizontal and vertical
+    /// radii. If a radius is 0 this gives a line.
+    #[must_use]
+    pub fn new(centre: Vector2D<i32>, radius_x: u16, radius_y: u16) -> Self {
+        let (radius_x, radius_y) = (i64::from(radius_x), i64::from(radius_y));
+        let (rx2, ry2) = (radius_x * radius_x, radius_y * radius_y);
+
+        Self {
+            centre,
+            radius_x,
+            radius_y,
+            region: EllipseRegion::Shallow,
+            x: 0,
+            y: radius_y,
+            decision: 4 * ry2 - 4 * rx2 * radius_y + rx2,
+            change_x: 0,
+            change_y: 2 * rx2 * radius_y,
+            reflections: Reflections::new(),
+        }
+    }
+
+    fn advance(&mut self) {
+        let (rx2, ry2) = (self.radius_x * self.radius_x, self.radius_y * self.radius_y);
+
+        match self.region {
+            EllipseRegion::Shallow => {
+                self.x += 1;
+                self.change_x += 2 * ry2;
+
+                if self.decision < 0 {
+                    self.decision += 4 * (self.change_x + ry2);
+                } else {
+                    self.y -= 1;
+                    self.change_y -= 2 * rx2;
+                    self.decision += 4 * (self.change_x - self.change_y + ry2);
+                }
+            }
+            EllipseRegion::Steep => {
+                if self.y == 0 {
+                    self.region = EllipseRegion::Flat;
+                    self.x += 1;
+                    return;
+                }
+
+                self.y -= 1;
+                self.change_y -= 2 * rx2;
+
+                if self.decision > 0 {
+                    self.decision += 4 * (rx2 - self.change_y);
+                } else {
+                    self.x += 1;
+                    self.change_x += 2 * ry2;
+                    self.decision += 4 * (self.change_x - self.change_y + rx2);
+                }
+            }
+            EllipseRegion::Flat => self.x += 1,
+            EllipseRegion::Finished => {}
+        }
+    }
+
+    fn update_region(&mut self) {
+        let (rx2, ry2) = (self.radius_x * self.radius_x, self.radius_y * self.radius_y);
+
+        if self.region == EllipseRegion::Shallow && self.change_x >= self.change_y {
+            self.region = EllipseRegion::Steep;
+            self.decision = ry2 * (2 * self.x + 1) * (2 * self.x + 1)
+                + 4 * rx2 * (self.y - 1) * (self.y - 1)
+                - 4 * rx2 * ry2;
+        }
+
+        if self.region == EllipseRegion::Flat && self.x > self.radius_x {
+            self.region = EllipseRegion::Finished;
+        }
+    }
+}
+
+impl Iterator for Ellipse {
+    type Item = Vector2D<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(point) = self.reflections.next() {
+                return Some(point);
+            }
+
+            self.update_region();
+            if self.region == EllipseRegion::Finished {
+                return None;
+            }
+
+            let (x, y) = (self.x as i32, self.y as i32);
+            self.reflections
+                .fill(self.centre, &[(x, y), (-x, y), (x, -y), (-x, -y)]);
+
+            self.advance();
+        }
+    }
+}
+
+/// Every point inside a triangle, including its edges, going along each row
+/// from the top of the triangle to the bottom.
+#[derive(Clone, Debug)]
+pub struct FilledTriangle {
+    vertices: [Vector2D<i32>; 3],
+    x: i32,
+    row_end: i32,
+    y: i32,
+    bottom: i32,
+}
+
+impl FilledTriangle {
+    /// The triangle with corners at `a`, `b` and `c`, in any order.
+    #[must_use]
+    pub fn new(a: Vector2D<i32>, b: Vector2D<i32>, c: Vector2D<i32>) -> Self {
+        let top = a.y.min(b.y).min(c.y);
+        let mut triangle = Self {
+            vertices: [a, b, c],
+            x: 0,
+            row_end: -1,
+            y: top - 1,
+            bottom: a.y.max(b.y).max(c.y),
+        };
+
+        triangle.next_row();
+        triangle
+    }
+
+    /// Moves to the start of the next row, leaving `y` past the bottom if
+    /// there are no more.
+    fn next_row(&mut self) {
+        self.y += 1;
+        if self.y > self.bottom {
+            return;
+        }
+
+        let mut left = i32::MAX;
+        let mut right = i32::MIN;
+
+        for i in 0..3 {
+            let (mut from, mut to) = (self.vertices[i], self.vertices[(i + 1) % 3]);
+            if from.y > to.y {
+                core::mem::swap(&mut from, &mut to);
+            }
+
+            if self.y < from.y || self.y > to.y {
+                continue;
+            }
+
+            let (start, end) = if from.y == to.y {
+                (from.x.min(to.x), from.x.max(to.x))
+            } else {
+                // round to the nearest x along the edge at this row
+                let numerator = 2 * (self.y - from.y) * (to.x - from.x) + (to.y - from.y);
+                let x = from.x + numerator.div_euclid(2 * (to.y - from.y));
+                (x, x)
+            };
+
+            left = left.min(start);
+            right = right.max(end);
+        }
+
+        self.x = left;
+        self.row_end = right;
+    }
+}
+
+impl Iterator for FilledTriangle {
+    type Item = Vector2D<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y > self.bottom {
+            return None;
+        }
+
+        let point = Vector2D::new(self.x, self.y);
+
+        if self.x < self.row_end {
+            self.x += 1;
+        } else {
+            self.next_row();
+        }
+
+        Some(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn lines_include_both_ends(_gba: &mut crate::Gba) {
+        let line = Line::new((2, 1).into(), (-3, 4).into());
+        assert_eq!(line.len(), 6);
+
+        let points: crate::collections::FixedVec<Vector2D<i32>, 8> = {
+            let mut points = crate::collections::FixedVec::new();
+            for point in line {
+                points.push(point).unwrap();
+            }
+            points
+        };
+
+        assert_eq!(points[0], (2, 1).into());
+        assert_eq!(points[5], (-3, 4).into());
+        for pair in points.windows(2) {
+            let step = pair[1] - pair[0];
+            assert!(step.x.abs() <= 1 && step.y.abs() <= 1);
+        }
+
+        assert!(Line::new((5, 5).into(), (5, 5).into()).eq([(5, 5).into()]));
+    }
+
+    #[test_case]
+    fn circles_and_ellipses_stay_near_their_outline(_gba: &mut crate::Gba) {
+        let centre: Vector2D<i32> = (10, -4).into();
+
+        for point in Circle::new(centre, 6) {
+            let offset = point - centre;
+            let distance2 = offset.x * offset.x + offset.y * offset.y;
+            assert!((25..=49).contains(&distance2), "{:?}", point);
+        }
+        assert_eq!(
+            Circle::new(centre, 6)
+                .filter(|&point| point == centre + (6, 0).into())
+                .count(),
+            1
+        );
+        assert!(Circle::new(centre, 0).eq([centre]));
+
+        let ellipse = || Ellipse::new(centre, 8, 3);
+        for extreme in [(8, 0), (-8, 0), (0, 3), (0, -3)] {
+            let extreme = centre + extreme.into();
+            assert_eq!(ellipse().filter(|&point| point == extreme).count(), 1);
+        }
+        assert!(ellipse().all(|point| (point - centre).x.abs() <= 8));
+
+        assert_eq!(Ellipse::new(centre, 4, 0).count(), 9);
+    }
+
+    #[test_case]
+    fn filled_triangles_cover_their_area(_gba: &mut crate::Gba) {
+        let triangle = || FilledTriangle::new((0, 0).into(), (4, 4).into(), (0, 4).into());
+
+        // a right angled triangle with sides of 5 pixels covers 1 + 2 + ... + 5
+        assert_eq!(triangle().count(), 15);
+        assert!(triangle().all(|point| point.x <= point.y));
+        for corner in [(0, 0), (4, 4), (0, 4)] {
+            assert!(triangle().any(|point| point == corner.into()));
+        }
+
+        assert_eq!(
+            FilledTriangle::new((0, 2).into(), (5, 2).into(), (2, 2).into()).count(),
+            6
+        );
+    }
+}
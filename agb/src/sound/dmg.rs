@@ -1,4 +1,7 @@
 use crate::memory_mapped::MemoryMapped;
+use crate::sync::Static;
+
+use super::{SOUND_CONTROL_H, SOUND_CONTROL_L, SOUND_CONTROL_X};
 
 const CHANNEL_1_SWEEP: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0060) };
 const CHANNEL_1_LENGTH_DUTY_ENVELOPE: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0062) };
@@ -7,12 +10,26 @@ const CHANNEL_1_FREQUENCY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::ne
 const CHANNEL_2_LENGTH_DUTY_ENVELOPE: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0068) };
 const CHANNEL_2_FREQUENCY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_006c) };
 
+const CHANNEL_3_STOP_WAVE_RAM_SELECT: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0070) };
+const CHANNEL_3_LENGTH_VOLUME: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0072) };
+const CHANNEL_3_FREQUENCY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0074) };
+
+const WAVE_RAM: *mut u16 = 0x0400_0090 as *mut u16;
+
 const CHANNEL_4_LENGTH_ENVELOPE: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0078) };
 const CHANNEL_4_FREQUENCY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_007c) };
 
-const MASTER_SOUND_VOLUME_ENABLE: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0080) };
-const MASTER_SOUND_VOLUME_MIXING: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0082) };
-const MASTER_SOUND_STATUS: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0084) };
+// The frequency control registers can't be read back, so the last value written to each is kept
+// so that the channel can be restarted with a new volume.
+static CHANNEL_1_FREQUENCY: Static<u16> = Static::new(0);
+static CHANNEL_2_FREQUENCY: Static<u16> = Static::new(0);
+static CHANNEL_4_FREQUENCY: Static<u16> = Static::new(0);
+
+const DUTY_MASK: u16 = 0b11 << 6;
+
+// A channel is switched off if its envelope starts at volume 0 and decreases, so volumes set directly
+// use an increasing envelope which never steps.
+const ENVELOPE_INCREASE: u16 = 1 << 11;
 
 #[non_exhaustive]
 pub struct Sound {}
@@ -32,17 +49,26 @@ impl Sound {
         Channel2 {}
     }
 
+    /// The programmable wave channel, which repeatedly plays a waveform of 32
+    /// 4-bit samples.
+    #[must_use]
+    pub fn wave(&self) -> Wave {
+        Wave {}
+    }
+
     #[must_use]
     pub fn noise(&self) -> Noise {
         Noise {}
     }
 
     pub fn enable(&self) {
-        MASTER_SOUND_STATUS.set_bits(1, 1, 7);
+        SOUND_CONTROL_X.set_bits(1, 1, 7);
 
         #[allow(clippy::unusual_byte_groupings)] // I've split these like this for a reason
-        MASTER_SOUND_VOLUME_ENABLE.set(0b1111_1111_0_111_0_111);
-        MASTER_SOUND_VOLUME_MIXING.set(0b10);
+        SOUND_CONTROL_L.set(0b1111_1111_0_111_0_111);
+        SOUND_CONTROL_H.set(0b10);
+
+        super::update_mute();
     }
 }
 
@@ -69,8 +95,26 @@ impl Channel1 {
 
         CHANNEL_1_LENGTH_DUTY_ENVELOPE
             .set(envelope_settings.as_bits() | duty_cycle.as_bits() | length_bits);
+        CHANNEL_1_FREQUENCY.write(frequency | length_flag);
         CHANNEL_1_FREQUENCY_CONTROL.set(frequency | length_flag | initial);
     }
+
+    /// Changes the volume of the playing sound, between 0 and 15. The hardware
+    /// can only do this by restarting the sound, which also restarts its sweep,
+    /// its length and any hardware envelope. Use this with an
+    /// [`EnvelopeState`](crate::sound::envelope::EnvelopeState) for envelopes
+    /// which the hardware can't do.
+    pub fn set_volume(&self, volume: u8) {
+        assert!(volume < 16, "Volume must be less than 16");
+
+        if !is_channel_playing(0) {
+            return;
+        }
+
+        let duty = CHANNEL_1_LENGTH_DUTY_ENVELOPE.get() & DUTY_MASK;
+        CHANNEL_1_LENGTH_DUTY_ENVELOPE.set(duty | ENVELOPE_INCREASE | (u16::from(volume) << 12));
+        CHANNEL_1_FREQUENCY_CONTROL.set(CHANNEL_1_FREQUENCY.read() | (1 << 15));
+    }
 }
 
 #[non_exhaustive]
@@ -94,8 +138,106 @@ impl Channel2 {
 
         CHANNEL_2_LENGTH_DUTY_ENVELOPE
             .set(envelope_settings.as_bits() | duty_cycle.as_bits() | length_bits);
+        CHANNEL_2_FREQUENCY.write(frequency | length_flag);
         CHANNEL_2_FREQUENCY_CONTROL.set(frequency | length_flag | initial);
     }
+
+    /// Changes the volume of the playing sound, between 0 and 15. The hardware
+    /// can only do this by restarting the sound, which also restarts its length
+    /// and any hardware envelope. Use this with an
+    /// [`EnvelopeState`](crate::sound::envelope::EnvelopeState) for envelopes
+    /// which the hardware can't do.
+    pub fn set_volume(&self, volume: u8) {
+        assert!(volume < 16, "Volume must be less than 16");
+
+        if !is_channel_playing(1) {
+            return;
+        }
+
+        let duty = CHANNEL_2_LENGTH_DUTY_ENVELOPE.get() & DUTY_MASK;
+        CHANNEL_2_LENGTH_DUTY_ENVELOPE.set(duty | ENVELOPE_INCREASE | (u16::from(volume) << 12));
+        CHANNEL_2_FREQUENCY_CONTROL.set(CHANNEL_2_FREQUENCY.read() | (1 << 15));
+    }
+}
+
+/// The wave channel. Its waveform is 32 4-bit samples, stored 2 to a byte
+/// with the high nibble played first.
+///
+/// The hardware has 2 banks of waveform RAM and only the one which isn't
+/// playing can be written to, so [`set_waveform`](Wave::set_waveform) can be
+/// called while a sound is playing without any glitches.
+#[non_exhaustive]
+pub struct Wave {}
+
+impl Wave {
+    /// Uploads a new waveform and switches to playing it.
+    pub fn set_waveform(&self, waveform: &[u8; 16]) {
+        let control = CHANNEL_3_STOP_WAVE_RAM_SELECT.get();
+
+        // the bank which isn't currently being played is the one which can be written to
+        for (i, samples) in waveform.as_chunks::<2>().0.iter().enumerate() {
+            unsafe { WAVE_RAM.add(i).write_volatile(u16::from_le_bytes(*samples)) };
+        }
+
+        // single bank mode, and swap to the bank just written
+        CHANNEL_3_STOP_WAVE_RAM_SELECT.set((control ^ (1 << 6)) & !(1 << 5));
+    }
+
+    /// Starts playing the current waveform. The frequency must be less than
+    /// 2048, and the waveform is repeated `65536 / (2048 - frequency)` times
+    /// per second. The length is in units of 1/256th of a second and sound
+    /// plays for `256 - length` of those units, or forever if it is `None`.
+    pub fn play_sound(&self, frequency: u16, length: Option<u8>, volume: WaveVolume) {
+        assert!(frequency < 2048, "Frequency must be less than 2048");
+
+        let length_bits = u16::from(length.unwrap_or(0));
+        let length_flag: u16 = length.map_or(0, |_| 1 << 14);
+        let initial: u16 = 1 << 15;
+
+        CHANNEL_3_STOP_WAVE_RAM_SELECT.set_bits(1, 1, 7);
+        CHANNEL_3_LENGTH_VOLUME.set(length_bits | volume.as_bits());
+        CHANNEL_3_FREQUENCY_CONTROL.set(frequency | length_flag | initial);
+    }
+
+    /// Stops the wave channel from playing.
+    pub fn stop(&self) {
+        CHANNEL_3_STOP_WAVE_RAM_SELECT.set_bits(0, 1, 7);
+    }
+
+    /// Whether the wave channel is currently enabled.
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        CHANNEL_3_STOP_WAVE_RAM_SELECT.get() & (1 << 7) != 0
+    }
+}
+
+/// The volume of the [`Wave`] channel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WaveVolume {
+    /// No sound at all.
+    Mute,
+    /// 25% volume.
+    Quarter,
+    /// 50% volume.
+    Half,
+    /// 75% volume.
+    ThreeQuarters,
+    /// 100% volume.
+    Full,
+}
+
+impl WaveVolume {
+    fn as_bits(self) -> u16 {
+        use WaveVolume::*;
+
+        match self {
+            Mute => 0,
+            Full => 1 << 13,
+            Half => 2 << 13,
+            Quarter => 3 << 13,
+            ThreeQuarters => 1 << 15,
+        }
+    }
 }
 
 #[non_exhaustive]
@@ -127,17 +269,37 @@ impl Noise {
 
         let counter_step_bit = if counter_step_width_15 { 0 } else { 1 << 3 };
 
+        let frequency = u16::from(frequency_divider)
+            | counter_step_bit
+            | (u16::from(shift_clock_frequency) << 4)
+            | length_flag;
+
         CHANNEL_4_LENGTH_ENVELOPE.set(length_bits | envelope_setting.as_bits());
-        CHANNEL_4_FREQUENCY_CONTROL.set(
-            u16::from(frequency_divider)
-                | counter_step_bit
-                | (u16::from(shift_clock_frequency) << 4)
-                | length_flag
-                | initial,
-        );
+        CHANNEL_4_FREQUENCY.write(frequency);
+        CHANNEL_4_FREQUENCY_CONTROL.set(frequency | initial);
+    }
+
+    /// Changes the volume of the playing sound, between 0 and 15. The hardware
+    /// can only do this by restarting the sound, which also restarts its length
+    /// and any hardware envelope. Use this with an
+    /// [`EnvelopeState`](crate::sound::envelope::EnvelopeState) for envelopes
+    /// which the hardware can't do.
+    pub fn set_volume(&self, volume: u8) {
+        assert!(volume < 16, "Volume must be less than 16");
+
+        if !is_channel_playing(3) {
+            return;
+        }
+
+        CHANNEL_4_LENGTH_ENVELOPE.set(ENVELOPE_INCREASE | (u16::from(volume) << 12));
+        CHANNEL_4_FREQUENCY_CONTROL.set(CHANNEL_4_FREQUENCY.read() | (1 << 15));
     }
 }
 
+fn is_channel_playing(channel: u16) -> bool {
+    SOUND_CONTROL_X.get() & (1 << channel) != 0
+}
+
 pub enum SoundDirection {
     Increase,
     Decrease,
@@ -222,7 +384,7 @@ impl Default for EnvelopeSettings {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum DutyCycle {
     OneEighth,
     OneQuarter,
@@ -234,11 +396,54 @@ impl DutyCycle {
     fn as_bits(self) -> u16 {
         use DutyCycle::*;
 
-        match self {
+        let duty = match self {
             OneEighth => 0,
             OneQuarter => 1,
             Half => 2,
             ThreeQuarters => 3,
-        }
+        };
+
+        duty << 6
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn wave_channel_swaps_banks_when_uploading(gba: &mut crate::Gba) {
+        gba.sound.enable();
+        let wave = gba.sound.wave();
+
+        let bank = CHANNEL_3_STOP_WAVE_RAM_SELECT.get() & (1 << 6);
+        wave.set_waveform(&[0x0f; 16]);
+        assert_ne!(CHANNEL_3_STOP_WAVE_RAM_SELECT.get() & (1 << 6), bank);
+
+        wave.play_sound(1024, None, WaveVolume::Half);
+        assert!(wave.is_playing());
+
+        wave.stop();
+        assert!(!wave.is_playing());
+    }
+
+    #[test_case]
+    fn square_channel_volume_can_be_changed(gba: &mut crate::Gba) {
+        gba.sound.enable();
+        let channel2 = gba.sound.channel2();
+
+        channel2.play_sound(1024, None, &EnvelopeSettings::default(), DutyCycle::Half);
+        channel2.set_volume(7);
+
+        let bits = CHANNEL_2_LENGTH_DUTY_ENVELOPE.get();
+        assert_eq!(bits >> 12, 7);
+        assert_eq!(bits & DUTY_MASK, DutyCycle::Half.as_bits());
+
+        // a volume of 0 keeps the channel playing so it can be turned back up
+        channel2.set_volume(0);
+        assert!(is_channel_playing(1));
+
+        channel2.set_volume(3);
+        assert_eq!(CHANNEL_2_LENGTH_DUTY_ENVELOPE.get() >> 12, 3);
     }
 }
@@ -0,0 +1,229 @@
+#![deny(missing_docs)]
+
+//! Software volume envelopes.
+//!
+//! An [`Envelope`] shapes the volume of a sound over time with the usual
+//! attack, decay, sustain and release stages, all measured in frames. This
+//! lets a single sample or square wave be used for many different sounding
+//! effects without baking the fades into the sound data.
+//!
+//! Sounds played by the [mixer](crate::sound::mixer) are given an envelope with
+//! [`SoundChannel::envelope`](crate::sound::mixer::SoundChannel::envelope) and
+//! the mixer updates it for you. For the [DMG](crate::sound::dmg) square and
+//! noise channels, keep an [`EnvelopeState`], call
+//! [`update`](EnvelopeState::update) once per frame and pass
+//! [`dmg_volume`](EnvelopeState::dmg_volume) to the channel's `set_volume`
+//! method.
+
+use crate::fixnum::Num;
+
+/// The shape of a volume envelope.
+///
+/// The volume rises linearly from 0 to full over the attack, falls to the
+/// sustain level over the decay and then stays there until the sound is
+/// released, after which it falls to 0 over the release.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    attack: u16,
+    decay: u16,
+    sustain: Num<i16, 4>,
+    release: u16,
+}
+
+impl Envelope {
+    /// Creates a new envelope. The attack, decay and release are lengths in
+    /// frames, and the sustain is a volume between 0 and 1.
+    #[must_use]
+    pub fn new(attack: u16, decay: u16, sustain: impl Into<Num<i16, 4>>, release: u16) -> Self {
+        let sustain = sustain.into();
+        assert!(
+            sustain >= 0.into() && sustain <= 1.into(),
+            "sustain must be between 0 and 1"
+        );
+
+        Self {
+            attack,
+            decay,
+            sustain,
+            release,
+        }
+    }
+
+    /// An envelope which plays at full volume until released, and then fades
+    /// out over the given number of frames.
+    #[must_use]
+    pub fn fade_out(release: u16) -> Self {
+        Self::new(0, 0, 1, release)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Finished,
+}
+
+/// An [`Envelope`] being applied to a sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnvelopeState {
+    envelope: Envelope,
+    stage: Stage,
+    frame: u16,
+    volume: Num<i16, 4>,
+    release_from: Num<i16, 4>,
+}
+
+impl EnvelopeState {
+    /// Starts the envelope from the beginning of its attack.
+    #[must_use]
+    pub fn new(envelope: Envelope) -> Self {
+        let mut state = Self {
+            envelope,
+            stage: Stage::Attack,
+            frame: 0,
+            volume: 0.into(),
+            release_from: 0.into(),
+        };
+
+        state.calculate_volume();
+        state
+    }
+
+    /// Moves the envelope on by one frame and returns the new volume.
+    pub fn update(&mut self) -> Num<i16, 4> {
+        if self.stage != Stage::Sustain && self.stage != Stage::Finished {
+            self.frame += 1;
+        }
+
+        self.calculate_volume();
+        self.volume
+    }
+
+    /// Starts the release stage from the current volume. Does nothing if the
+    /// envelope has already been released.
+    pub fn release(&mut self) {
+        if self.stage == Stage::Release || self.stage == Stage::Finished {
+            return;
+        }
+
+        self.release_from = self.volume;
+        self.stage = Stage::Release;
+        self.frame = 0;
+        self.calculate_volume();
+    }
+
+    /// The current volume, between 0 and 1.
+    #[must_use]
+    pub fn volume(&self) -> Num<i16, 4> {
+        self.volume
+    }
+
+    /// The current volume scaled to the 0 to 15 range used by the DMG
+    /// channels.
+    #[must_use]
+    pub fn dmg_volume(&self) -> u8 {
+        (self.volume * 15 + Num::new(1) / 2).floor() as u8
+    }
+
+    /// Whether the envelope has been released and has faded out completely.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.stage == Stage::Finished
+    }
+
+    fn calculate_volume(&mut self) {
+        let envelope = &self.envelope;
+
+        // skip over any stages which have finished, including ones with no length
+        loop {
+            let length = match self.stage {
+                Stage::Attack => envelope.attack,
+                Stage::Decay => envelope.decay,
+                Stage::Release => envelope.release,
+                Stage::Sustain | Stage::Finished => break,
+            };
+
+            if self.frame < length {
+                break;
+            }
+
+            self.frame = 0;
+            self.stage = match self.stage {
+                Stage::Attack => Stage::Decay,
+                Stage::Decay => Stage::Sustain,
+                _ => Stage::Finished,
+            };
+        }
+
+        self.volume = match self.stage {
+            Stage::Attack => lerp(0.into(), 1.into(), self.frame, envelope.attack),
+            Stage::Decay => lerp(1.into(), envelope.sustain, self.frame, envelope.decay),
+            Stage::Sustain => envelope.sustain,
+            Stage::Release => lerp(self.release_from, 0.into(), self.frame, envelope.release),
+            Stage::Finished => 0.into(),
+        };
+    }
+}
+
+fn lerp(from: Num<i16, 4>, to: Num<i16, 4>, frame: u16, length: u16) -> Num<i16, 4> {
+    let from = i32::from(from.to_raw());
+    let to = i32::from(to.to_raw());
+
+    Num::from_raw((from + (to - from) * i32::from(frame) / i32::from(length)) as i16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn envelope_goes_through_each_stage(_gba: &mut crate::Gba) {
+        let mut state = EnvelopeState::new(Envelope::new(2, 2, Num::new(1) / 2, 4));
+
+        assert_eq!(state.volume(), 0.into());
+        assert_eq!(state.update(), Num::new(1) / 2);
+        assert_eq!(state.update(), 1.into());
+        assert_eq!(state.update(), Num::new(3) / 4);
+        assert_eq!(state.update(), Num::new(1) / 2);
+
+        for _ in 0..100 {
+            assert_eq!(state.update(), Num::new(1) / 2);
+        }
+
+        state.release();
+        assert_eq!(state.volume(), Num::new(1) / 2);
+        assert_eq!(state.update(), Num::new(3) / 8);
+        assert!(!state.is_finished());
+
+        for _ in 0..3 {
+            state.update();
+        }
+
+        assert_eq!(state.volume(), 0.into());
+        assert!(state.is_finished());
+    }
+
+    #[test_case]
+    fn envelope_can_be_released_during_the_attack(_gba: &mut crate::Gba) {
+        let mut state = EnvelopeState::new(Envelope::new(4, 0, 1, 2));
+        state.update();
+        state.update();
+
+        state.release();
+        assert_eq!(state.volume(), Num::new(1) / 2);
+        assert_eq!(state.update(), Num::new(1) / 4);
+        assert_eq!(state.update(), 0.into());
+        assert!(state.is_finished());
+    }
+
+    #[test_case]
+    fn envelopes_with_no_attack_start_at_full_volume(_gba: &mut crate::Gba) {
+        let state = EnvelopeState::new(Envelope::fade_out(10));
+
+        assert_eq!(state.volume(), 1.into());
+        assert_eq!(state.dmg_volume(), 15);
+    }
+}
@@ -0,0 +1,166 @@
+//! Decoding 4-bit IMA ADPCM sounds as they are played.
+
+const STEP_TABLE: [u16; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+const INDEX_TABLE: [i8; 8] = [-1, -1, -1, -1, 2, 4, 6, 8];
+
+/// The state of the decoder just before decoding a sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct AdpcmState {
+    predictor: i16,
+    step_index: u8,
+}
+
+impl AdpcmState {
+    pub(super) const fn new(predictor: i16, step_index: u8) -> Self {
+        Self {
+            predictor,
+            step_index,
+        }
+    }
+
+    #[inline(always)]
+    fn decode(&mut self, data: &[u8], index: usize) -> i8 {
+        let nibble = (data[index / 2] >> ((index % 2) * 4)) & 0xf;
+        let step = i32::from(STEP_TABLE[usize::from(self.step_index)]);
+
+        let mut difference = step >> 3;
+        if nibble & 1 != 0 {
+            difference += step >> 2;
+        }
+        if nibble & 2 != 0 {
+            difference += step >> 1;
+        }
+        if nibble & 4 != 0 {
+            difference += step;
+        }
+        if nibble & 8 != 0 {
+            difference = -difference;
+        }
+
+        self.predictor = (i32::from(self.predictor) + difference)
+            .clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16;
+        self.step_index =
+            (self.step_index as i8 + INDEX_TABLE[usize::from(nibble & 7)]).clamp(0, 88) as u8;
+
+        (self.predictor >> 8) as i8
+    }
+}
+
+/// Keeps track of where a channel playing an ADPCM sound has decoded up to.
+pub(super) struct AdpcmChannel {
+    state: AdpcmState,
+    position: usize,
+
+    loop_start: Option<usize>,
+    loop_state: AdpcmState,
+}
+
+impl AdpcmChannel {
+    pub(super) fn new(loop_start: Option<usize>, loop_state: AdpcmState) -> Self {
+        Self {
+            state: AdpcmState::default(),
+            position: 0,
+            loop_start,
+            loop_state,
+        }
+    }
+
+    /// Decodes samples from `start` into `output`, and then remembers the state at `next_start`
+    /// which is where the next call will start from. `next_start` must be within the decoded
+    /// samples.
+    #[link_section = ".iwram.adpcm_decode"]
+    #[cfg_attr(not(doc), instruction_set(arm::a32))]
+    pub(super) fn decode(
+        &mut self,
+        data: &[u8],
+        start: usize,
+        output: &mut [i8],
+        next_start: usize,
+    ) {
+        if start < self.position {
+            // going backwards needs to start decoding from somewhere with a known state
+            match self.loop_start {
+                Some(loop_start) if loop_start <= start => {
+                    self.state = self.loop_state;
+                    self.position = loop_start;
+                }
+                _ => {
+                    self.state = AdpcmState::default();
+                    self.position = 0;
+                }
+            }
+        }
+
+        let mut state = self.state;
+        for index in self.position..start {
+            state.decode(data, index);
+        }
+
+        let mut next_state = state;
+        for (i, sample) in output.iter_mut().enumerate() {
+            if start + i == next_start {
+                next_state = state;
+            }
+
+            *sample = state.decode(data, start + i);
+        }
+
+        self.state = next_state;
+        self.position = next_start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::mixer::SoundData;
+
+    const JUMP: SoundData = crate::include_wav!("examples/sfx/jump.wav");
+    const JUMP_ADPCM: SoundData = crate::include_wav!("examples/sfx/jump.wav", adpcm = true);
+
+    #[test_case]
+    fn adpcm_sounds_decode_close_to_the_original(_gba: &mut crate::Gba) {
+        assert_eq!(JUMP_ADPCM.len(), JUMP.len());
+        assert_eq!(JUMP_ADPCM.data().len(), JUMP.len().div_ceil(2));
+
+        let mut channel = AdpcmChannel::new(None, AdpcmState::default());
+        let mut decoded = alloc::vec![0; JUMP.len()];
+
+        // decode in uneven chunks, the way the mixer does
+        let mut start = 0;
+        loop {
+            let end = (start + 177).min(JUMP.len());
+            channel.decode(JUMP_ADPCM.data(), start, &mut decoded[start..end], end - 1);
+
+            if end == JUMP.len() {
+                break;
+            }
+            start = end - 1;
+        }
+
+        let total_error: i32 = JUMP
+            .data()
+            .iter()
+            .zip(&decoded)
+            .map(|(&expected, &actual)| (i32::from(expected as i8) - i32::from(actual)).abs())
+            .sum();
+
+        assert!(
+            total_error / (JUMP.len() as i32) < 4,
+            "average error was {}",
+            total_error / (JUMP.len() as i32)
+        );
+
+        // going backwards decodes from the start again
+        let mut sample = [0];
+        channel.decode(JUMP_ADPCM.data(), 10, &mut sample, 10);
+        assert_eq!(sample[0], decoded[10]);
+    }
+}
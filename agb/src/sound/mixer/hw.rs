@@ -1,4 +1,5 @@
 use crate::memory_mapped::MemoryMapped;
+use crate::sound::{SOUND_BIAS, SOUND_CONTROL_H, SOUND_CONTROL_X};
 use crate::timer::Timer;
 
 const fn dma_source_addr(dma: usize) -> usize {
@@ -25,11 +26,6 @@ const DMA2_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(dma_control_a
 const FIFO_A_DEST_ADDR: u32 = 0x0400_00a0;
 const FIFO_B_DEST_ADDR: u32 = 0x0400_00a4;
 
-const SOUND_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0082) };
-const SOUND_CONTROL_X: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0084) };
-
-const SOUND_BIAS: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0088) };
-
 const DMA_CONTROL_SETTING_FOR_SOUND: u16 = {
     let dest_fixed: u16 = 2 << 5; // dest addr control = fixed
     let repeat: u16 = 1 << 9;
@@ -78,7 +74,7 @@ pub(super) fn set_sound_control_register_for_mixer() {
     let sound_b_lout: u16 = 0 << 13;
     let sound_b_fifo_reset: u16 = 1 << 15;
 
-    SOUND_CONTROL.set(
+    SOUND_CONTROL_H.set(
         sound_a_volume_100
             | sound_a_rout
             | sound_a_lout
@@ -94,9 +90,11 @@ pub(super) fn set_sound_control_register_for_mixer() {
 
     // Set the sound bias PWM resampling rate to 8bit at 65536Hz (default for most games)
     SOUND_BIAS.set(SOUND_BIAS.get() | 1 << 14);
+
+    crate::sound::update_mute();
 }
 
 pub(super) fn set_timer_counter_for_frequency_and_enable(timer: &mut Timer, frequency: i32) {
-    timer.set_overflow_amount((16777216 / frequency) as u16);
+    timer.set_overflow_amount(((16777216 + frequency / 2) / frequency) as u16);
     timer.set_enabled(true);
 }
@@ -91,6 +91,51 @@ same_modification:
 
 agb_arm_end agb_rs__mixer_add
 
+agb_arm_func agb_rs__mixer_add_interpolated
+    @ Arguments are the same as agb_rs__mixer_add, but samples are linearly interpolated
+    @ between the two nearest samples rather than using the nearest one.
+    @ r0 - pointer to the data to be copied (u8 array)
+    @ r1 - pointer to the sound buffer (i16 array which will alternate left and right channels, 32-bit aligned)
+    @ r2 - playback speed (usize fixnum with 8 bits)
+    @ r3 - amount to modify the left channel by (u16 fixnum with 4 bits)
+    @ stack position 1 - amount to modify the right channel by (u16 fixnum with 4 bits)
+    push {r4-r9}
+
+    ldr r7, [sp, #24]        @ load the right channel modification amount into r7
+    orr r7, r7, r3, lsl #16  @ r7 now is the left channel followed by the right channel modifications.
+
+    mov r5, #0                   @ current index we're reading from
+    ldr r8, =agb_rs__buffer_size @ the number of steps left
+    ldr r8, [r8]
+
+1:
+.rept 4
+    add r4, r0, r5, asr #8    @ calculate the address of the next read from the sound buffer
+    ldrsb r6, [r4]           @ load the current sound sample to r6
+    ldrsb r9, [r4, #1]       @ and the one after it to r9
+
+    and r12, r5, #0xff       @ r12 = fractional part of the position
+    sub r9, r9, r6           @ r9 = difference between the two samples
+    mul r9, r12, r9          @ r9 = difference * fractional part
+    add r6, r6, r9, asr #8   @ r6 = interpolated sample
+
+    add r5, r5, r2           @ calculate the position to read the next sample from
+
+    ldr r4, [r1]             @ read the current value
+
+    mla r4, r6, r7, r4       @ r4 += r6 * r7 (calculating both the left and right samples together)
+
+    str r4, [r1], #4         @ store the new value, and increment the pointer
+.endr
+
+    subs r8, r8, #4          @ loop counter
+    bne 1b                   @ jump back if we're done with the loop
+
+    pop {r4-r9}
+    bx lr
+
+agb_arm_end agb_rs__mixer_add_interpolated
+
 agb_arm_func agb_rs__mixer_add_stereo
     @ Arguments
     @ r0 - pointer to the data to be copied (u8 array)
@@ -10,7 +10,10 @@
 //! have to use a software mixer.
 //!
 //! agb's software mixer allows for up to 8 simultaneous sounds played at once at
-//! various speeds and volumes.
+//! various speeds and volumes by default. Use
+//! [`mixer_with_channels`](MixerController::mixer_with_channels) to choose a
+//! different number of channels, bearing in mind that each one costs CPU time
+//! while it is playing.
 //!
 //! # Concepts
 //!
@@ -19,6 +22,8 @@
 //!
 //! All wav files you use within your application / game must use this _exact_ frequency.
 //! If you don't use this frequency, the sound will play either too slowly or too quickly.
+//! [`include_wav!`](crate::include_wav) can resample files which are at a different
+//! frequency at compile time with its `sample_rate` argument.
 //!
 //! The mixer can play both mono and stereo sounds, but only mono sound effects can have
 //! effects applied to them (such as changing the speed at which they play or the panning).
@@ -95,8 +100,8 @@
 //! To load a sample, you must have it in `wav` format (both stereo and mono work) at exactly the
 //! selected frequency based on the features enabled in the agb crate.
 //!
-//! Use the [`include_wav!`](crate::include_wav) macro in order to load the sound. This will produce
-//! an error if your wav file is of the wrong frequency.
+//! Use the [`include_wav!`](crate::include_wav) macro in order to load the sound. This converts
+//! it to the mixer's format at compile time and produces a [`SoundData`].
 //!
 //! ```rust,no_run
 //! # #![no_std]
@@ -106,7 +111,7 @@
 //! # let vblank = agb::interrupt::VBlank::get();
 //! # use agb::{*, sound::mixer::*};
 //! // Outside your main function in global scope:
-//! const MY_CRAZY_SOUND: &[u8] = include_wav!("examples/sfx/jump.wav");
+//! const MY_CRAZY_SOUND: SoundData = include_wav!("examples/sfx/jump.wav");
 //!
 //! // Then to play the sound:
 //! let mut channel = SoundChannel::new(MY_CRAZY_SOUND);
@@ -119,13 +124,26 @@
 //!
 //! Once you have run [`play_sound`](Mixer::play_sound), the mixer will play that sound until
 //! it has finished.
+//!
+//! ## Long sounds
+//!
+//! Sounds are never copied into RAM. The data produced by [`include_wav!`](crate::include_wav)
+//! stays in ROM, and each frame the mixer reads just the part of it which is needed for that
+//! frame directly from the cartridge. So a long piece of music or voice clip uses the same
+//! amount of RAM as a short sound effect, and the only limit on its length is the size of the
+//! ROM.
+mod adpcm;
 mod hw;
 mod sw_mixer;
 
 pub use sw_mixer::ChannelId;
 pub use sw_mixer::Mixer;
+pub use sw_mixer::MixerProfile;
 
 use crate::fixnum::Num;
+use crate::sound::envelope::{Envelope, EnvelopeState};
+use crate::timer::{CYCLES_PER_FRAME, CYCLES_PER_SECOND};
+use adpcm::{AdpcmChannel, AdpcmState};
 
 /// Controls access to the mixer and the underlying hardware it uses. A zero sized type that
 /// ensures that mixer access is exclusive.
@@ -139,26 +157,66 @@ impl MixerController {
 
     /// Get a [`Mixer`] in order to start producing sounds.
     pub fn mixer(&mut self, frequency: Frequency) -> Mixer {
-        Mixer::new(frequency)
+        Mixer::new(frequency, 8)
+    }
+
+    /// Get a [`Mixer`] which can play up to `channels` sounds at once. Panics
+    /// if `channels` is 0.
+    pub fn mixer_with_channels(&mut self, frequency: Frequency, channels: usize) -> Mixer {
+        Mixer::new(frequency, channels)
     }
 }
 
-#[derive(PartialEq, Eq)]
+/// Which group a [`SoundChannel`] belongs to. Each group has its own volume
+/// which can be set with [`Mixer::set_group_volume`], and the music group can be
+/// faded between tracks with [`Mixer::crossfade_to`] and ducked while important
+/// sound effects play.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundGroup {
+    /// Background music. This is the default for high priority channels.
+    Music,
+    /// Sound effects. This is the default for low priority channels.
+    SoundEffects,
+}
+
+impl SoundGroup {
+    const fn index(self) -> usize {
+        match self {
+            SoundGroup::Music => 0,
+            SoundGroup::SoundEffects => 1,
+        }
+    }
+}
+
+// Low priority sounds come before high priority ones, and are ordered by their priority
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum SoundPriority {
+    Low(u8),
     High,
-    Low,
 }
 
 /// The supported frequencies within AGB. These are chosen to work well with
 /// the hardware. Note that the higher the frequency, the better the quality of
 /// the sound but the more CPU time sound mixing will take.
+///
+/// Apart from 32768Hz, each of these produces an exact whole number of samples
+/// every frame, so the buffer size and timer reload value are derived from
+/// the frequency.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum Frequency {
     /// 10512Hz
     Hz10512,
+    /// 13379Hz
+    Hz13379,
     /// 18157Hz
     Hz18157,
+    /// 21024Hz
+    Hz21024,
+    /// 26758Hz
+    Hz26758,
+    /// 31536Hz
+    Hz31536,
     /// 32768Hz - note that this option requires interrupts for buffer swapping
     Hz32768,
 }
@@ -170,39 +228,153 @@ impl Frequency {
 
         match self {
             Hz10512 => 10512,
+            Hz13379 => 13379,
             Hz18157 => 18157,
+            Hz21024 => 21024,
+            Hz26758 => 26758,
+            Hz31536 => 31536,
             Hz32768 => 32768,
         }
     }
 
     pub(crate) fn buffer_size(self) -> usize {
-        use Frequency::*;
-
         match self {
-            Hz10512 => 176,
-            Hz18157 => 304,
-            Hz32768 => 560,
+            // 32768Hz isn't a whole number of samples per frame, so the buffers are swapped
+            // by an interrupt whenever they run out instead
+            Frequency::Hz32768 => 560,
+            _ => {
+                let samples = i64::from(self.frequency()) * i64::from(CYCLES_PER_FRAME);
+                let cycles_per_second = i64::from(CYCLES_PER_SECOND);
+                ((samples + cycles_per_second / 2) / cycles_per_second) as usize
+            }
+        }
+    }
+}
+
+/// A sound which can be played by the mixer, usually created with
+/// [`include_wav!`](crate::include_wav).
+///
+/// The samples are signed 8 bit values, interleaved left then right for stereo
+/// sounds, or mono 4-bit ADPCM which is decoded as the sound plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoundData {
+    data: &'static [u8],
+    length: usize,
+    sample_rate: u32,
+    is_stereo: bool,
+    loop_start: Option<usize>,
+    // the decoder state at the loop start for ADPCM sounds
+    adpcm: Option<AdpcmState>,
+}
+
+impl SoundData {
+    /// Creates sound data from raw samples. `loop_start` is the index into
+    /// `data` that the sound goes back to when it loops, if it has a loop
+    /// point.
+    #[must_use]
+    pub const fn new(
+        data: &'static [u8],
+        sample_rate: u32,
+        is_stereo: bool,
+        loop_start: Option<usize>,
+    ) -> Self {
+        Self {
+            data,
+            length: data.len(),
+            sample_rate,
+            is_stereo,
+            loop_start,
+            adpcm: None,
+        }
+    }
+
+    #[doc(hidden)]
+    /// Used internally by [`include_wav!`](crate::include_wav) for sounds
+    /// compressed with ADPCM. The predictor and step index are the state of
+    /// the decoder just before the loop start.
+    #[must_use]
+    pub const fn new_adpcm(
+        data: &'static [u8],
+        sample_rate: u32,
+        length: usize,
+        loop_start: Option<usize>,
+        loop_predictor: i16,
+        loop_step_index: u8,
+    ) -> Self {
+        Self {
+            data,
+            length,
+            sample_rate,
+            is_stereo: false,
+            loop_start,
+            adpcm: Some(AdpcmState::new(loop_predictor, loop_step_index)),
         }
     }
+
+    /// The raw samples, or the compressed data for ADPCM sounds.
+    #[must_use]
+    pub const fn data(&self) -> &'static [u8] {
+        self.data
+    }
+
+    /// The number of samples, counting the left and right samples of stereo
+    /// sounds separately.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Whether the sound has no samples.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Whether the sound is compressed with ADPCM, which takes a little CPU
+    /// time to decode while it is playing.
+    #[must_use]
+    pub const fn is_adpcm(&self) -> bool {
+        self.adpcm.is_some()
+    }
+
+    /// The sample rate of the original file in Hz.
+    #[must_use]
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Whether the sound has both left and right channels.
+    #[must_use]
+    pub const fn is_stereo(&self) -> bool {
+        self.is_stereo
+    }
+
+    /// Where the sound goes back to when it loops, if it has a loop point.
+    #[must_use]
+    pub const fn loop_start(&self) -> Option<usize> {
+        self.loop_start
+    }
 }
 
 /// Describes one sound which should be playing. This could be a sound effect or
 /// the background music. Use the factory methods on this to modify how it is played.
 ///
-/// You _must_ set stereo sounds with [`.stereo()`](SoundChannel::stereo) or it will play as mono and at
-/// half the intended speed.
+/// Stereo wav files are played in stereo automatically. Other stereo data must be set
+/// with [`.stereo()`](SoundChannel::stereo) or it will play as mono and at half the
+/// intended speed.
 ///
 /// SoundChannels are very cheap to create, so don't worry about creating a brand new
 /// one for every single sound you want to play.
 ///
 /// SoundChannels can be either 'low priority' or 'high priority'. A high priority
 /// sound channel will override 'low priority' sound channels which are already playing
-/// to ensure that it is always running. A 'low priority' sound channel will not override
-/// any other channel.
+/// to ensure that it is always running. A 'low priority' sound channel will only override
+/// other low priority channels whose [`priority`](SoundChannel::priority) is no higher
+/// than its own, picking the lowest priority and then the oldest one.
 ///
-/// This is because you can only play up to 8 channels at once, and so high priority channels
-/// are prioritised over low priority channels to ensure that sounds that you always want
-/// playing will always play.
+/// This is because the mixer can only play a limited number of channels at once (8 by
+/// default), and so high priority channels are prioritised over low priority channels to
+/// ensure that sounds that you always want playing will always play.
 ///
 /// # Example
 ///
@@ -218,7 +390,7 @@ impl Frequency {
 /// # use agb::sound::mixer::*;
 /// # use agb::*;
 /// // in global scope:
-/// const MY_BGM: &[u8] = include_wav!("examples/sfx/my_bgm.wav");
+/// const MY_BGM: SoundData = include_wav!("examples/sfx/my_bgm.wav");
 ///
 /// // somewhere in code
 /// # fn foo(gba: &mut Gba) {
@@ -237,7 +409,7 @@ impl Frequency {
 /// # use agb::sound::mixer::*;
 /// # use agb::*;
 /// // in global scope:
-/// const JUMP_SOUND: &[u8] = include_wav!("examples/sfx/jump.wav");
+/// const JUMP_SOUND: SoundData = include_wav!("examples/sfx/jump.wav");
 ///
 /// // somewhere in code
 /// # fn foo(gba: &mut Gba) {
@@ -248,8 +420,11 @@ impl Frequency {
 /// ```
 pub struct SoundChannel {
     data: &'static [u8],
+    length: usize,
+    adpcm: Option<AdpcmChannel>,
     pos: Num<usize, 8>,
     should_loop: bool,
+    loop_start: usize,
 
     playback_speed: Num<usize, 8>,
     volume: Num<i16, 4>, // between 0 and 1
@@ -258,8 +433,13 @@ pub struct SoundChannel {
     is_done: bool,
 
     is_stereo: bool,
+    interpolate: bool,
 
     priority: SoundPriority,
+    group: SoundGroup,
+    ducks_music: bool,
+
+    envelope: Option<EnvelopeState>,
 }
 
 impl SoundChannel {
@@ -280,7 +460,7 @@ impl SoundChannel {
     /// # fn foo(gba: &mut Gba) {
     /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
     /// // in global scope:
-    /// const JUMP_SOUND: &[u8] = include_wav!("examples/sfx/jump.wav");
+    /// const JUMP_SOUND: SoundData = include_wav!("examples/sfx/jump.wav");
     ///
     /// // somewhere in code
     /// let jump_sound = SoundChannel::new(JUMP_SOUND);
@@ -289,17 +469,26 @@ impl SoundChannel {
     /// ```
     #[inline(always)]
     #[must_use]
-    pub fn new(data: &'static [u8]) -> Self {
+    pub fn new(sound: SoundData) -> Self {
         SoundChannel {
-            data,
+            data: sound.data,
+            length: sound.length,
+            adpcm: sound
+                .adpcm
+                .map(|loop_state| AdpcmChannel::new(sound.loop_start, loop_state)),
             pos: 0.into(),
-            should_loop: false,
+            should_loop: sound.loop_start.is_some(),
+            loop_start: sound.loop_start.unwrap_or(0),
             playback_speed: 1.into(),
             panning: 0.into(),
             is_done: false,
-            priority: SoundPriority::Low,
+            priority: SoundPriority::Low(0),
             volume: 1.into(),
-            is_stereo: false,
+            is_stereo: sound.is_stereo,
+            interpolate: false,
+            group: SoundGroup::SoundEffects,
+            ducks_music: false,
+            envelope: None,
         }
     }
 
@@ -322,7 +511,7 @@ impl SoundChannel {
     /// # fn foo(gba: &mut Gba) {
     /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
     /// // in global scope:
-    /// const MY_BGM: &[u8] = include_wav!("examples/sfx/my_bgm.wav");
+    /// const MY_BGM: SoundData = include_wav!("examples/sfx/my_bgm.wav");
     ///
     /// // somewhere in code
     /// let mut bgm = SoundChannel::new_high_priority(MY_BGM);
@@ -332,22 +521,32 @@ impl SoundChannel {
     /// ```
     #[inline(always)]
     #[must_use]
-    pub fn new_high_priority(data: &'static [u8]) -> Self {
+    pub fn new_high_priority(sound: SoundData) -> Self {
         SoundChannel {
-            data,
+            data: sound.data,
+            length: sound.length,
+            adpcm: sound
+                .adpcm
+                .map(|loop_state| AdpcmChannel::new(sound.loop_start, loop_state)),
             pos: 0.into(),
-            should_loop: false,
+            should_loop: sound.loop_start.is_some(),
+            loop_start: sound.loop_start.unwrap_or(0),
             playback_speed: 1.into(),
             panning: 0.into(),
             is_done: false,
             priority: SoundPriority::High,
             volume: 1.into(),
-            is_stereo: false,
+            is_stereo: sound.is_stereo,
+            interpolate: false,
+            group: SoundGroup::Music,
+            ducks_music: false,
+            envelope: None,
         }
     }
 
     /// Sets that a sound channel should loop back to the start once it has
-    /// finished playing rather than stopping.
+    /// finished playing rather than stopping. Sounds with a loop point loop
+    /// back to it automatically.
     #[inline(always)]
     pub fn should_loop(&mut self) -> &mut Self {
         self.should_loop = true;
@@ -358,7 +557,8 @@ impl SoundChannel {
     /// to 1 with values between 0 and 1 being slower above 1 being faster.
     ///
     /// Note that this only works for mono sounds. Stereo sounds will not change
-    /// how fast they play.
+    /// how fast they play. The nearest sample is used unless the channel is set
+    /// to [`interpolate`](SoundChannel::interpolate).
     #[inline(always)]
     pub fn playback(&mut self, playback_speed: impl Into<Num<usize, 8>>) -> &mut Self {
         self.playback_speed = playback_speed.into();
@@ -397,7 +597,42 @@ impl SoundChannel {
         self
     }
 
-    /// Sets that the sound effect should be played in stereo. Not setting this
+    /// Sets the volume of the left and right speakers separately, by setting
+    /// both the [`volume`](SoundChannel::volume) and the
+    /// [`panning`](SoundChannel::panning). Both must be >= 0, and this doesn't
+    /// affect the balance of stereo sounds.
+    #[inline]
+    pub fn left_right_volume(
+        &mut self,
+        left: impl Into<Num<i16, 4>>,
+        right: impl Into<Num<i16, 4>>,
+    ) -> &mut Self {
+        let (left, right) = (left.into(), right.into());
+
+        assert!(left >= Num::new(0), "left volume must be >= 0");
+        assert!(right >= Num::new(0), "right volume must be >= 0");
+
+        self.volume = left + right;
+        self.panning = if self.volume == Num::new(0) {
+            0.into()
+        } else {
+            (right - left) / self.volume
+        };
+
+        self
+    }
+
+    /// The amount the left and right speakers are each multiplied by, from
+    /// the volume and panning.
+    fn left_right_amounts(&self) -> (Num<i16, 4>, Num<i16, 4>) {
+        let left = ((-self.panning + 1) / 2) * self.volume;
+        let right = ((self.panning + 1) / 2) * self.volume;
+
+        (left, right)
+    }
+
+    /// Sets that the sound effect should be played in stereo. This is set
+    /// automatically for stereo wav files. Not setting this for stereo data
     /// will result in the sound playing at half speed and mono. Setting this on
     /// a mono sound will cause some interesting results (and play it at double speed).
     #[inline(always)]
@@ -407,6 +642,65 @@ impl SoundChannel {
         self
     }
 
+    /// Sets the priority of a low priority sound, which defaults to 0. When
+    /// the mixer runs out of channels, a new low priority sound replaces the
+    /// playing low priority sound with the lowest priority which is no higher
+    /// than its own, and the oldest of those if there is a tie. This has no
+    /// effect on high priority sounds, which are never replaced.
+    #[inline(always)]
+    pub fn priority(&mut self, priority: u8) -> &mut Self {
+        if let SoundPriority::Low(_) = self.priority {
+            self.priority = SoundPriority::Low(priority);
+        }
+
+        self
+    }
+
+    /// Sets that the sound should be linearly interpolated between samples
+    /// rather than using the nearest one. This sounds much better when playing
+    /// a sound slower or faster than its original speed with
+    /// [`playback`](SoundChannel::playback), but takes a little more CPU time.
+    /// This doesn't affect stereo sounds.
+    #[inline(always)]
+    pub fn interpolate(&mut self) -> &mut Self {
+        self.interpolate = true;
+        self
+    }
+
+    /// Sets which [`SoundGroup`] the sound belongs to.
+    #[inline(always)]
+    pub fn group(&mut self, group: SoundGroup) -> &mut Self {
+        self.group = group;
+        self
+    }
+
+    /// Sets that the music group should be quieter while this sound plays, so
+    /// that it can be heard clearly. See [`Mixer::set_ducked_volume`].
+    #[inline(always)]
+    pub fn duck_music(&mut self) -> &mut Self {
+        self.ducks_music = true;
+        self
+    }
+
+    /// Shapes the sound's volume with an [`Envelope`], which the mixer moves
+    /// on by one frame every time it mixes a frame of sound. The sound stops
+    /// once it has been [released](SoundChannel::release) and faded out.
+    #[inline(always)]
+    pub fn envelope(&mut self, envelope: Envelope) -> &mut Self {
+        self.envelope = Some(EnvelopeState::new(envelope));
+        self
+    }
+
+    /// Starts the release stage of the sound's envelope, or stops the sound
+    /// immediately if it doesn't have one.
+    #[inline(always)]
+    pub fn release(&mut self) {
+        match &mut self.envelope {
+            Some(envelope) => envelope.release(),
+            None => self.stop(),
+        }
+    }
+
     /// Stops the sound from playing.
     #[inline(always)]
     pub fn stop(&mut self) {
@@ -427,3 +721,60 @@ impl SoundChannel {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SILENCE: SoundData = SoundData::new(&[0; 64], 10512, false, None);
+
+    #[test_case]
+    fn buffer_sizes_suit_the_mixer(_gba: &mut crate::Gba) {
+        assert_eq!(Frequency::Hz10512.buffer_size(), 176);
+        assert_eq!(Frequency::Hz18157.buffer_size(), 304);
+
+        for frequency in [
+            Frequency::Hz10512,
+            Frequency::Hz13379,
+            Frequency::Hz18157,
+            Frequency::Hz21024,
+            Frequency::Hz26758,
+            Frequency::Hz31536,
+            Frequency::Hz32768,
+        ] {
+            // the assembly mixes 16 samples at a time
+            assert_eq!(frequency.buffer_size() % 16, 0, "{:?}", frequency);
+        }
+    }
+
+    #[test_case]
+    fn sounds_are_played_from_rom(_gba: &mut crate::Gba) {
+        const JUMP: SoundData = crate::include_wav!("examples/sfx/jump.wav");
+
+        let channel = SoundChannel::new(JUMP);
+        let address = channel.data.as_ptr() as usize;
+
+        assert!(
+            (0x0800_0000..0x0E00_0000).contains(&address),
+            "sound data at {:#x} should be in ROM",
+            address
+        );
+    }
+
+    #[test_case]
+    fn left_and_right_volumes_become_volume_and_panning(_gba: &mut crate::Gba) {
+        let mut channel = SoundChannel::new(SILENCE);
+
+        channel.left_right_volume(1, 0);
+        assert_eq!(channel.left_right_amounts(), (1.into(), 0.into()));
+
+        channel.left_right_volume(Num::new(1) / 2, Num::new(1) / 2);
+        assert_eq!(
+            channel.left_right_amounts(),
+            (Num::new(1) / 2, Num::new(1) / 2)
+        );
+
+        channel.left_right_volume(0, 0);
+        assert_eq!(channel.left_right_amounts(), (0.into(), 0.into()));
+    }
+}
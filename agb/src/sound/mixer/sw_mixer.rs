@@ -5,8 +5,10 @@ use alloc::vec::Vec;
 use bare_metal::{CriticalSection, Mutex};
 
 use super::hw::LeftOrRight;
+#[cfg(test)]
+use super::SoundData;
 use super::{hw, Frequency};
-use super::{SoundChannel, SoundPriority};
+use super::{SoundChannel, SoundGroup, SoundPriority};
 
 use crate::InternalAllocator;
 use crate::{
@@ -27,6 +29,14 @@ extern "C" {
         right_amount: Num<i16, 4>,
     );
 
+    fn agb_rs__mixer_add_interpolated(
+        sound_data: *const u8,
+        sound_buffer: *mut Num<i16, 4>,
+        playback_speed: Num<usize, 8>,
+        left_amount: Num<i16, 4>,
+        right_amount: Num<i16, 4>,
+    );
+
     fn agb_rs__mixer_add_stereo(
         sound_data: *const u8,
         sound_buffer: *mut Num<i16, 4>,
@@ -63,7 +73,7 @@ extern "C" {
 /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
 /// # let vblank = agb::interrupt::VBlank::get();
 /// // Outside your main function in global scope:
-/// const MY_CRAZY_SOUND: &[u8] = include_wav!("examples/sfx/jump.wav");
+/// const MY_CRAZY_SOUND: SoundData = include_wav!("examples/sfx/jump.wav");
 ///
 /// // in your main function:
 /// let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
@@ -80,11 +90,48 @@ extern "C" {
 /// ```
 pub struct Mixer {
     buffer: MixerBuffer,
-    channels: [Option<SoundChannel>; 8],
-    indices: [i32; 8],
+    channels: Vec<Option<SoundChannel>>,
+    indices: Vec<i32>,
+    // when each channel started playing, used to find the oldest sound to replace
+    started: Vec<u32>,
+    sounds_played: u32,
     frequency: Frequency,
 
+    group_volumes: [Num<i16, 4>; 2],
+    master_volume: Num<i16, 4>,
+    ducked_volume: Num<i16, 4>,
+    crossfade: Option<Crossfade>,
+
+    timer: Timer,
+    // counts the samples played from the current buffer
+    sample_counter: Timer,
+    profiler: Option<Profiler>,
+}
+
+struct Profiler {
     timer: Timer,
+    profile: MixerProfile,
+}
+
+/// How long the mixer took to mix the most recent frame, and the longest it
+/// has taken since profiling started. Both are measured in CPU cycles to the
+/// nearest 64, and there are 280,896 cycles in a frame.
+///
+/// See [`Mixer::enable_profiling`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MixerProfile {
+    /// The number of cycles taken to mix the most recent frame.
+    pub cycles: u32,
+    /// The largest number of cycles taken to mix a single frame.
+    pub peak_cycles: u32,
+}
+
+struct Crossfade {
+    // the channels fading out and the volumes they started at
+    from: Vec<(ChannelId, Num<i16, 4>)>,
+    to: Option<(ChannelId, Num<i16, 4>)>,
+    frame: u16,
+    frames: u16,
 }
 
 /// A pointer to a currently playing channel.
@@ -100,7 +147,7 @@ pub struct Mixer {
 /// # use agb::*;
 /// # fn foo(gba: &mut Gba) {
 /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
-/// # const MY_BGM: &[u8] = include_wav!("examples/sfx/my_bgm.wav");
+/// # const MY_BGM: SoundData = include_wav!("examples/sfx/my_bgm.wav");
 /// let mut channel = SoundChannel::new_high_priority(MY_BGM);
 /// let bgm_channel_id = mixer.play_sound(channel).unwrap(); // will always be Some if high priority
 ///
@@ -111,14 +158,25 @@ pub struct Mixer {
 pub struct ChannelId(usize, i32);
 
 impl Mixer {
-    pub(super) fn new(frequency: Frequency) -> Self {
+    pub(super) fn new(frequency: Frequency, channels: usize) -> Self {
+        assert!(channels > 0, "the mixer needs at least one channel");
+
         Self {
             frequency,
             buffer: MixerBuffer::new(frequency),
-            channels: Default::default(),
-            indices: Default::default(),
+            channels: (0..channels).map(|_| None).collect(),
+            indices: alloc::vec![0; channels],
+            started: alloc::vec![0; channels],
+            sounds_played: 0,
+
+            group_volumes: [1.into(); 2],
+            master_volume: 1.into(),
+            ducked_volume: Num::new(1) / 2,
+            crossfade: None,
 
             timer: unsafe { Timer::new(0) },
+            sample_counter: unsafe { Timer::new(1) },
+            profiler: None,
         }
     }
 
@@ -129,6 +187,14 @@ impl Mixer {
     pub fn enable(&mut self) {
         hw::set_timer_counter_for_frequency_and_enable(&mut self.timer, self.frequency.frequency());
         hw::set_sound_control_register_for_mixer();
+
+        // setup_interrupt_handler borrows the mixer, so can only be called
+        // after this and switches the counter over to using the interrupt
+        start_sample_counter(
+            &mut self.sample_counter,
+            false,
+            self.frequency.buffer_size(),
+        );
     }
 
     /// Do post-vblank work. You can use either this or [`setup_interrupt_handler()`](Mixer::setup_interrupt_handler),
@@ -154,6 +220,9 @@ impl Mixer {
     #[cfg(not(feature = "freq32768"))]
     pub fn after_vblank(&mut self) {
         free(|cs| self.buffer.swap(cs));
+
+        // restart the count for the new buffer
+        self.sample_counter.set_enabled(false).set_enabled(true);
     }
 
     /// Use timer interrupts to do the timing required for ensuring the music runs smoothly.
@@ -183,13 +252,9 @@ impl Mixer {
     /// # }
     /// ```
     pub fn setup_interrupt_handler(&self) -> InterruptHandler<'_> {
-        let mut timer1 = unsafe { Timer::new(1) };
-        timer1
-            .set_cascade(true)
-            .set_divider(Divider::Divider1)
-            .set_interrupt(true)
-            .set_overflow_amount(self.frequency.buffer_size() as u16)
-            .set_enabled(true);
+        // the mixer already has timer 1 reserved as its sample counter
+        let mut timer1 = unsafe { Timer::new_unreserved(1) };
+        start_sample_counter(&mut timer1, true, self.frequency.buffer_size());
 
         add_interrupt_handler(timer1.interrupt(), move |cs| self.buffer.swap(cs))
     }
@@ -201,6 +266,12 @@ impl Mixer {
     ///
     /// Normally you would run this during vdraw, just before the vblank interrupt.
     ///
+    /// The mixer keeps up to 2 frames of sound ready ahead of the one which is playing, and the
+    /// hardware plays them by DMA without any further CPU time. If the game goes so long without
+    /// calling this that every frame of sound has been played, the mixer plays silence until it is
+    /// called again rather than repeating old sound. Each frame of silence is counted in
+    /// [`underruns`](Mixer::underruns), which is useful for finding frames which run too long.
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -219,21 +290,208 @@ impl Mixer {
     /// # }
     /// ```
     pub fn frame(&mut self) {
+        self.update_crossfade();
+
         if !self.buffer.should_calculate() {
             return;
         }
 
+        let mut group_volumes = self.group_volumes.map(|volume| volume * self.master_volume);
+        let ducking = self
+            .channels
+            .iter()
+            .flatten()
+            .any(|channel| channel.ducks_music && !channel.is_done);
+        if ducking {
+            group_volumes[SoundGroup::Music.index()] *= self.ducked_volume;
+        }
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler
+                .timer
+                .set_enabled(false)
+                .set_overflow_amount(0)
+                .set_divider(Divider::Divider64)
+                .set_enabled(true);
+        }
+
         self.buffer
-            .write_channels(self.channels.iter_mut().flatten());
+            .write_channels(self.channels.iter_mut().flatten(), group_volumes);
+
+        if let Some(profiler) = &mut self.profiler {
+            let cycles = u32::from(profiler.timer.value()) * 64;
+            profiler.timer.set_enabled(false);
+
+            profiler.profile.cycles = cycles;
+            profiler.profile.peak_cycles = profiler.profile.peak_cycles.max(cycles);
+        }
+    }
+
+    /// Starts measuring how long [`frame`](Mixer::frame) takes to mix each
+    /// frame, using the given timer which mustn't be used for anything else
+    /// while the mixer has it. Use this to find out how close the mixer is to
+    /// using up the frame, and tune the number of channels and the frequency to
+    /// suit.
+    pub fn enable_profiling(&mut self, timer: Timer) {
+        self.profiler = Some(Profiler {
+            timer,
+            profile: MixerProfile::default(),
+        });
+    }
+
+    /// Stops profiling, returning the timer given to
+    /// [`enable_profiling`](Mixer::enable_profiling).
+    pub fn disable_profiling(&mut self) -> Option<Timer> {
+        self.profiler.take().map(|profiler| profiler.timer)
+    }
+
+    /// The most recent and peak mixing times, or `None` if profiling isn't
+    /// enabled.
+    #[must_use]
+    pub fn profile(&self) -> Option<MixerProfile> {
+        self.profiler.as_ref().map(|profiler| profiler.profile)
+    }
+
+    /// Resets the peak mixing time to the most recent one.
+    pub fn reset_peak_profile(&mut self) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.profile.peak_cycles = profiler.profile.cycles;
+        }
+    }
+
+    /// The number of frames of silence which have been played because [`frame`](Mixer::frame)
+    /// wasn't called in time.
+    #[must_use]
+    pub fn underruns(&self) -> u32 {
+        free(|cs| self.buffer.state.borrow(cs).borrow().underruns)
+    }
+
+    /// Sets the volume of every sound in the given group, which multiplies the
+    /// volume of each sound. Defaults to 1.
+    pub fn set_group_volume(&mut self, group: SoundGroup, volume: impl Into<Num<i16, 4>>) {
+        let volume = volume.into();
+        assert!(volume >= Num::new(0), "volume must be >= 0");
+
+        self.group_volumes[group.index()] = volume;
+    }
+
+    /// Sets the volume of everything the mixer plays, which multiplies the
+    /// volume of every sound and group. Defaults to 1. To silence all sound
+    /// immediately, including the DMG channels, use [`mute`](crate::sound::mute).
+    pub fn set_master_volume(&mut self, volume: impl Into<Num<i16, 4>>) {
+        let volume = volume.into();
+        assert!(volume >= Num::new(0), "volume must be >= 0");
+
+        self.master_volume = volume;
+    }
+
+    /// The master volume set by [`set_master_volume`](Mixer::set_master_volume).
+    #[must_use]
+    pub fn master_volume(&self) -> Num<i16, 4> {
+        self.master_volume
+    }
+
+    /// The volume of the given group.
+    #[must_use]
+    pub fn group_volume(&self, group: SoundGroup) -> Num<i16, 4> {
+        self.group_volumes[group.index()]
+    }
+
+    /// Sets how much the music group's volume is multiplied by while a sound
+    /// set to [`duck_music`](SoundChannel::duck_music) is playing. Defaults to
+    /// 0.5.
+    pub fn set_ducked_volume(&mut self, volume: impl Into<Num<i16, 4>>) {
+        let volume = volume.into();
+        assert!(volume >= Num::new(0), "volume must be >= 0");
+
+        self.ducked_volume = volume;
+    }
+
+    /// Starts playing `new_track` in the [`Music`](SoundGroup::Music) group,
+    /// fading it in while fading out and then stopping the music which is
+    /// currently playing. The fade lasts for `frames` calls to
+    /// [`frame`](Mixer::frame).
+    ///
+    /// Returns the id of the new track, or None if it couldn't be played.
+    pub fn crossfade_to(&mut self, mut new_track: SoundChannel, frames: u16) -> Option<ChannelId> {
+        // finish any fade which is already in progress
+        if let Some(crossfade) = &mut self.crossfade {
+            crossfade.frame = crossfade.frames;
+        }
+        self.update_crossfade();
+
+        let from: Vec<_> = self
+            .channels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, channel)| {
+                let channel = channel.as_ref()?;
+                (channel.group == SoundGroup::Music && !channel.is_done)
+                    .then(|| (ChannelId(i, self.indices[i]), channel.volume))
+            })
+            .collect();
+
+        let target_volume = new_track.volume;
+        new_track.group(SoundGroup::Music).volume(0);
+        let to = self.play_sound(new_track);
+
+        self.crossfade = Some(Crossfade {
+            from,
+            to: to.as_ref().map(|id| (ChannelId(id.0, id.1), target_volume)),
+            frame: 0,
+            frames,
+        });
+        self.update_crossfade();
+
+        to
+    }
+
+    fn update_crossfade(&mut self) {
+        let crossfade = match self.crossfade.take() {
+            Some(crossfade) => crossfade,
+            None => return,
+        };
+
+        let finished = crossfade.frame >= crossfade.frames;
+        let progress: Num<i16, 4> = if finished {
+            Num::new(1)
+        } else {
+            let progress = (u32::from(crossfade.frame) << 4) / u32::from(crossfade.frames);
+            Num::from_raw(progress as i16)
+        };
+
+        for (id, volume) in &crossfade.from {
+            if let Some(channel) = self.channel(id) {
+                if finished {
+                    channel.stop();
+                } else {
+                    channel.volume(*volume * (Num::new(1) - progress));
+                }
+            }
+        }
+
+        if let Some((id, volume)) = &crossfade.to {
+            if let Some(channel) = self.channel(id) {
+                channel.volume(*volume * progress);
+            }
+        }
+
+        if !finished {
+            self.crossfade = Some(Crossfade {
+                frame: crossfade.frame + 1,
+                ..crossfade
+            });
+        }
     }
 
     /// Start playing a given [`SoundChannel`].
     ///
     /// Returns a [`ChannelId`] which you can later use to modify the playing sound.
     ///
-    /// Will first try to play the sound in an unused channel (of the 8 possible channels)
-    /// followed by overriding a low priority sound (if the sound channel being passed in
-    /// is high priority).
+    /// Will first try to play the sound in an unused channel followed by overriding a low
+    /// priority sound. High priority sounds override the low priority sound with the lowest
+    /// [`priority`](SoundChannel::priority), and low priority sounds override one whose
+    /// priority is no higher than their own. Ties are broken by overriding the oldest sound.
     ///
     /// Returns Some if the channel is now playing (which is guaranteed if the channel is
     /// high priority) or None if it failed to find a slot.
@@ -249,39 +507,150 @@ impl Mixer {
     /// # use agb::*;
     /// # fn foo(gba: &mut Gba) {
     /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
-    /// # const MY_BGM: &[u8] = include_wav!("examples/sfx/my_bgm.wav");
+    /// # const MY_BGM: SoundData = include_wav!("examples/sfx/my_bgm.wav");
     /// let mut channel = SoundChannel::new_high_priority(MY_BGM);
     /// let bgm_channel_id = mixer.play_sound(channel).unwrap(); // will always be Some if high priority
     /// # }
     /// ```
     pub fn play_sound(&mut self, new_channel: SoundChannel) -> Option<ChannelId> {
-        for (i, channel) in self.channels.iter_mut().enumerate() {
-            if let Some(some_channel) = channel {
-                if !some_channel.is_done {
-                    continue;
-                }
+        let free_channel = self
+            .channels
+            .iter()
+            .position(|channel| channel.as_ref().is_none_or(|channel| channel.is_done));
+
+        let started = &self.started;
+        let replaced_channel = free_channel.or_else(|| {
+            self.channels
+                .iter()
+                .enumerate()
+                .filter_map(|(i, channel)| Some((i, channel.as_ref()?.priority)))
+                .filter(|(_, priority)| {
+                    *priority != SoundPriority::High && *priority <= new_channel.priority
+                })
+                .min_by_key(|&(i, priority)| (priority, started[i]))
+                .map(|(i, _)| i)
+        });
+
+        let i = match replaced_channel {
+            Some(i) => i,
+            // don't bother even playing it
+            None if new_channel.priority != SoundPriority::High => {
+                crate::debug!("no free channel, so a low priority sound wasn't played");
+                return None;
+            }
+            None => panic!(
+                "Cannot play more than {} sounds at once",
+                self.channels.len()
+            ),
+        };
+
+        crate::trace!(
+            "playing a sound on channel {}, which was {}",
+            i,
+            if free_channel.is_some() {
+                "free"
+            } else {
+                "replaced"
             }
+        );
 
-            channel.replace(new_channel);
-            self.indices[i] += 1;
-            return Some(ChannelId(i, self.indices[i]));
+        self.channels[i] = Some(new_channel);
+        self.indices[i] += 1;
+        self.started[i] = self.sounds_played;
+        self.sounds_played = self.sounds_played.wrapping_add(1);
+
+        Some(ChannelId(i, self.indices[i]))
+    }
+
+    /// Whether the sound with the given id is still playing, which is false
+    /// once it has finished, been stopped or been replaced by another sound.
+    #[must_use]
+    pub fn is_playing(&self, id: &ChannelId) -> bool {
+        match &self.channels[id.0] {
+            Some(channel) => self.indices[id.0] == id.1 && !channel.is_done,
+            None => false,
         }
+    }
 
-        if new_channel.priority == SoundPriority::Low {
-            return None; // don't bother even playing it
+    /// The position of the sound with the given id which is being heard right
+    /// now, in the same units as [`SoundChannel::pos`]. This takes into account
+    /// the sound which has been mixed but not played yet, so is the position to
+    /// use for keeping things like rhythm games in time with the audio.
+    #[must_use]
+    pub fn position(&self, id: &ChannelId) -> Option<Num<usize, 8>> {
+        if !self.is_playing(id) {
+            return None;
         }
 
-        for (i, channel) in self.channels.iter_mut().enumerate() {
-            if channel.as_ref().unwrap().priority == SoundPriority::High {
-                continue;
+        let channel = self.channels[id.0].as_ref()?;
+        let playback_speed = if channel.is_stereo {
+            2.into()
+        } else {
+            channel.playback_speed
+        };
+
+        let buffered = playback_speed * self.buffered_samples();
+        Some(if buffered > channel.pos {
+            0.into()
+        } else {
+            channel.pos - buffered
+        })
+    }
+
+    /// The number of samples which have been mixed but haven't been played yet,
+    /// which is how far behind the mixer the audio being heard is.
+    #[must_use]
+    pub fn buffered_samples(&self) -> usize {
+        let buffer_size = self.frequency.buffer_size();
+
+        // In interrupt mode the counter reloads to 65536 - buffer_size and
+        // interrupts when it overflows, otherwise it counts up from 0 and is
+        // restarted in after_vblank. A frame is far too short for the count to
+        // reach the interrupt mode values without interrupts.
+        let count = usize::from(self.sample_counter.value());
+        let played = count
+            .checked_sub(0x1_0000 - buffer_size)
+            .unwrap_or(count)
+            .min(buffer_size);
+
+        free(|cs| {
+            let state = self.buffer.state.borrow(cs).borrow();
+            if state.playing_silence {
+                return 0;
             }
 
-            channel.replace(new_channel);
-            self.indices[i] += 1;
-            return Some(ChannelId(i, self.indices[i]));
-        }
+            let queued = (state.active_buffer + 3 - state.playing_buffer) % 3;
+            queued * buffer_size + buffer_size - played
+        })
+    }
+
+    /// The frequency the mixer is producing sound at.
+    #[must_use]
+    pub fn frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    /// The number of sounds which can be played at once.
+    #[must_use]
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// The number of sounds which are currently playing.
+    #[must_use]
+    pub fn playing_channels(&self) -> usize {
+        self.channels
+            .iter()
+            .flatten()
+            .filter(|channel| !channel.is_done)
+            .count()
+    }
 
-        panic!("Cannot play more than 8 sounds at once");
+    /// Stops every sound which is currently playing.
+    pub fn stop_all(&mut self) {
+        for channel in self.channels.iter_mut().flatten() {
+            channel.stop();
+        }
     }
 
     /// Lets you modify an already playing channel.
@@ -298,7 +667,7 @@ impl Mixer {
     /// # use agb::*;
     /// # fn foo(gba: &mut Gba) {
     /// # let mut mixer = gba.mixer.mixer(agb::sound::mixer::Frequency::Hz10512);
-    /// # const MY_BGM: &[u8] = include_wav!("examples/sfx/my_bgm.wav");
+    /// # const MY_BGM: SoundData = include_wav!("examples/sfx/my_bgm.wav");
     /// let mut channel = SoundChannel::new_high_priority(MY_BGM);
     /// let bgm_channel_id = mixer.play_sound(channel).unwrap(); // will always be Some if high priority
     ///
@@ -342,6 +711,8 @@ impl SoundBuffer {
 struct MixerBuffer {
     buffers: [SoundBuffer; 3],
     working_buffer: Box<[Num<i16, 4>], InternalAllocator>,
+    // where ADPCM sounds are decoded to before being mixed
+    decode_buffer: Vec<i8, InternalAllocator>,
     frequency: Frequency,
 
     state: Mutex<RefCell<MixerBufferState>>,
@@ -350,8 +721,14 @@ struct MixerBuffer {
 struct MixerBufferState {
     active_buffer: usize,
     playing_buffer: usize,
+    underruns: u32,
+    playing_silence: bool,
 }
 
+/// Played when the mixer hasn't calculated the next buffer in time. This is as long as the longest
+/// buffer, and stays in ROM.
+static SILENCE: [i8; 560] = [0; 560];
+
 /// Only returns a valid result if 0 <= x <= 3
 const fn mod3_estimate(x: usize) -> usize {
     match x & 0b11 {
@@ -393,10 +770,13 @@ impl MixerBuffer {
             ],
 
             working_buffer: working_buffer.into_boxed_slice(),
+            decode_buffer: Vec::new_in(InternalAllocator),
 
             state: Mutex::new(RefCell::new(MixerBufferState {
                 active_buffer: 0,
                 playing_buffer: 0,
+                underruns: 0,
+                playing_silence: false,
             })),
 
             frequency,
@@ -408,7 +788,21 @@ impl MixerBuffer {
     }
 
     fn swap(&self, cs: CriticalSection) {
-        let buffer = self.state.borrow(cs).borrow_mut().playing_advanced();
+        let mut state = self.state.borrow(cs).borrow_mut();
+
+        if state.playing_buffer == state.active_buffer {
+            // everything which has been calculated has already been played
+            state.underruns = state.underruns.wrapping_add(1);
+            state.playing_silence = true;
+
+            let silence = &SILENCE[..self.frequency.buffer_size()];
+            hw::enable_dma_for_sound(silence, LeftOrRight::Left);
+            hw::enable_dma_for_sound(silence, LeftOrRight::Right);
+            return;
+        }
+
+        state.playing_silence = false;
+        let buffer = state.playing_advanced();
 
         let (left_buffer, right_buffer) = self.buffers[buffer]
             .0
@@ -418,7 +812,11 @@ impl MixerBuffer {
         hw::enable_dma_for_sound(right_buffer, LeftOrRight::Right);
     }
 
-    fn write_channels<'a>(&mut self, channels: impl Iterator<Item = &'a mut SoundChannel>) {
+    fn write_channels<'a>(
+        &mut self,
+        channels: impl Iterator<Item = &'a mut SoundChannel>,
+        group_volumes: [Num<i16, 4>; 2],
+    ) {
         set_asm_buffer_size(self.frequency);
 
         self.working_buffer.fill(0.into());
@@ -428,18 +826,27 @@ impl MixerBuffer {
                 continue;
             }
 
+            let mut group_volume = group_volumes[channel.group.index()];
+            if let Some(envelope) = &mut channel.envelope {
+                if envelope.is_finished() {
+                    channel.is_done = true;
+                    continue;
+                }
+
+                group_volume *= envelope.volume();
+                envelope.update();
+            }
+
             let playback_speed = if channel.is_stereo {
                 2.into()
             } else {
                 channel.playback_speed
             };
 
-            if (channel.pos + playback_speed * self.frequency.buffer_size()).floor()
-                >= channel.data.len()
-            {
+            if runs_past_end(channel, playback_speed, self.frequency.buffer_size()) {
                 // TODO: This should probably play what's left rather than skip the last bit
                 if channel.should_loop {
-                    channel.pos = 0.into();
+                    channel.pos = channel.loop_start.into();
                 } else {
                     channel.is_done = true;
                     continue;
@@ -451,16 +858,49 @@ impl MixerBuffer {
                     agb_rs__mixer_add_stereo(
                         channel.data.as_ptr().add(channel.pos.floor()),
                         self.working_buffer.as_mut_ptr(),
-                        channel.volume,
+                        channel.volume * group_volume,
                     );
                 }
             } else {
-                let right_amount = ((channel.panning + 1) / 2) * channel.volume;
-                let left_amount = ((-channel.panning + 1) / 2) * channel.volume;
+                let (left_amount, right_amount) = channel.left_right_amounts();
+                let (left_amount, right_amount) =
+                    (left_amount * group_volume, right_amount * group_volume);
+
+                let start = channel.pos.floor();
+                let data = match &mut channel.adpcm {
+                    Some(adpcm) => {
+                        let next_start =
+                            (channel.pos + playback_speed * self.frequency.buffer_size()).floor();
+
+                        // one extra sample for interpolation, which stays at 0 after the end
+                        let needed = next_start - start + 2;
+                        if self.decode_buffer.len() < needed {
+                            self.decode_buffer.resize(needed, 0);
+                        }
+
+                        let decoded = (next_start + 2).min(channel.length) - start;
+                        self.decode_buffer[decoded..needed].fill(0);
+                        adpcm.decode(
+                            channel.data,
+                            start,
+                            &mut self.decode_buffer[..decoded],
+                            next_start,
+                        );
+
+                        self.decode_buffer.as_ptr().cast()
+                    }
+                    None => unsafe { channel.data.as_ptr().add(start) },
+                };
+
+                let mixer_add = if channel.interpolate {
+                    agb_rs__mixer_add_interpolated
+                } else {
+                    agb_rs__mixer_add
+                };
 
                 unsafe {
-                    agb_rs__mixer_add(
-                        channel.data.as_ptr().add(channel.pos.floor()),
+                    mixer_add(
+                        data,
                         self.working_buffer.as_mut_ptr(),
                         playback_speed,
                         left_amount,
@@ -481,3 +921,287 @@ impl MixerBuffer {
         }
     }
 }
+
+/// Restarts the sample counter, which counts the samples played from the
+/// current buffer. With the interrupt, it overflows once the whole buffer has
+/// been played so the buffers can be swapped. Otherwise it counts up from 0
+/// until [`after_vblank`](Mixer::after_vblank) restarts it.
+///
+/// The timer is stopped first, as the count is only reloaded when it is
+/// started.
+fn start_sample_counter(sample_counter: &mut Timer, interrupt: bool, buffer_size: usize) {
+    let overflow_amount = if interrupt { buffer_size as u16 } else { 0 };
+
+    sample_counter
+        .set_enabled(false)
+        .set_cascade(true)
+        .set_divider(Divider::Divider1)
+        .set_interrupt(interrupt)
+        .set_overflow_amount(overflow_amount)
+        .set_enabled(true);
+}
+
+/// Whether mixing the next `buffer_size` samples of the channel would need
+/// samples from after the end of its data.
+fn runs_past_end(
+    channel: &SoundChannel,
+    playback_speed: Num<usize, 8>,
+    buffer_size: usize,
+) -> bool {
+    if (channel.pos + playback_speed * buffer_size).floor() >= channel.length {
+        return true;
+    }
+
+    // interpolating also reads the sample after the last one played
+    channel.interpolate
+        && !channel.is_stereo
+        && (channel.pos + playback_speed * (buffer_size - 1)).floor() + 1 >= channel.length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::envelope::Envelope;
+
+    const SILENCE: SoundData = SoundData::new(&[0; 64], 10512, false, None);
+
+    #[test_case]
+    fn mixer_plays_up_to_its_channel_count(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer_with_channels(Frequency::Hz10512, 2);
+        assert_eq!(mixer.channel_count(), 2);
+
+        assert!(mixer.play_sound(SoundChannel::new(SILENCE)).is_some());
+        assert!(mixer.play_sound(SoundChannel::new(SILENCE)).is_some());
+        assert_eq!(mixer.playing_channels(), 2);
+
+        let id = mixer
+            .play_sound(SoundChannel::new_high_priority(SILENCE))
+            .unwrap();
+        assert!(mixer.channel(&id).is_some());
+        assert_eq!(mixer.playing_channels(), 2);
+
+        mixer.stop_all();
+        assert_eq!(mixer.playing_channels(), 0);
+        assert!(mixer.channel(&id).is_none());
+    }
+
+    fn play_with_priority(mixer: &mut Mixer, priority: u8) -> Option<ChannelId> {
+        let mut channel = SoundChannel::new(SILENCE);
+        channel.priority(priority);
+        mixer.play_sound(channel)
+    }
+
+    #[test_case]
+    fn lower_priority_and_older_sounds_are_replaced_first(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer_with_channels(Frequency::Hz10512, 3);
+
+        let important = play_with_priority(&mut mixer, 2).unwrap();
+        let oldest = play_with_priority(&mut mixer, 0).unwrap();
+        let newest = play_with_priority(&mut mixer, 0).unwrap();
+
+        // equal priorities replace the oldest sound
+        let replacement = play_with_priority(&mut mixer, 0).unwrap();
+        assert!(!mixer.is_playing(&oldest));
+        assert!(mixer.is_playing(&newest));
+        assert!(mixer.is_playing(&replacement));
+
+        // higher priorities replace the lowest priority sounds first
+        assert!(play_with_priority(&mut mixer, 1).is_some());
+        assert!(play_with_priority(&mut mixer, 1).is_some());
+        assert!(!mixer.is_playing(&newest));
+        assert!(!mixer.is_playing(&replacement));
+
+        // lower priorities can't replace anything
+        assert!(play_with_priority(&mut mixer, 0).is_none());
+        assert!(mixer.is_playing(&important));
+    }
+
+    #[test_case]
+    fn loop_points_and_sample_rate_can_be_given_to_include_wav(_gba: &mut crate::Gba) {
+        const LOOPED: SoundData =
+            crate::include_wav!("examples/sfx/jump.wav", loop_start = 200, loop_end = 1200);
+
+        assert_eq!(LOOPED.loop_start(), Some(200));
+        assert_eq!(LOOPED.len(), 1200);
+
+        const RESAMPLED: SoundData =
+            crate::include_wav!("examples/sfx/jump.wav", sample_rate = 18157);
+
+        assert_eq!(RESAMPLED.sample_rate(), 18157);
+        assert_eq!(RESAMPLED.len(), 2318);
+    }
+
+    #[test_case]
+    fn interpolation_never_reads_past_the_end(_gba: &mut crate::Gba) {
+        let buffer_size = Frequency::Hz10512.buffer_size();
+        let speed = Num::new(1) / 4;
+
+        // the last sample played is 44 (from 44.25), so interpolating reads 45
+        let mut channel = SoundChannel::new(SoundData::new(&[0; 45], 10512, false, None));
+        channel.pos = Num::new(1) / 2;
+        assert!(!runs_past_end(&channel, speed, buffer_size));
+
+        channel.interpolate();
+        assert!(runs_past_end(&channel, speed, buffer_size));
+
+        let mut channel = SoundChannel::new(SoundData::new(&[0; 46], 10512, false, None));
+        channel.pos = Num::new(1) / 2;
+        channel.interpolate();
+        assert!(!runs_past_end(&channel, speed, buffer_size));
+    }
+
+    fn assert_counts_within_buffer(mixer: &Mixer) {
+        let buffer_size = mixer.frequency.buffer_size();
+        let vblank = crate::interrupt::VBlank::get();
+
+        for _ in 0..3 {
+            vblank.wait_for_vblank();
+
+            let count = usize::from(mixer.sample_counter.value());
+            assert!(
+                count >= 0x1_0000 - buffer_size,
+                "the sample counter was at {:#x}, so isn't reloading for the buffer size",
+                count
+            );
+        }
+    }
+
+    #[test_case]
+    fn interrupt_mode_works_after_enabling(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+        mixer.enable();
+
+        let _handler = mixer.setup_interrupt_handler();
+        assert_counts_within_buffer(&mixer);
+    }
+
+    #[test_case]
+    fn interpolated_sounds_can_change_speed(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+        let vblank = crate::interrupt::VBlank::get();
+        mixer.enable();
+
+        let mut channel = SoundChannel::new(SILENCE);
+        channel
+            .should_loop()
+            .interpolate()
+            .playback(Num::new(3) / 4);
+        let id = mixer.play_sound(channel).unwrap();
+
+        for _ in 0..3 {
+            mixer.frame();
+            vblank.wait_for_vblank();
+            mixer.after_vblank();
+        }
+
+        assert!(mixer.is_playing(&id));
+    }
+
+    #[test_case]
+    fn crossfading_replaces_the_music(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+
+        let old = mixer
+            .play_sound(SoundChannel::new_high_priority(SILENCE))
+            .unwrap();
+        let effect = mixer.play_sound(SoundChannel::new(SILENCE)).unwrap();
+
+        let new = mixer
+            .crossfade_to(SoundChannel::new_high_priority(SILENCE), 4)
+            .unwrap();
+        assert_eq!(mixer.channel(&new).unwrap().volume, 0.into());
+
+        for _ in 0..2 {
+            mixer.frame();
+        }
+        assert_eq!(mixer.channel(&old).unwrap().volume, Num::new(1) / 2);
+        assert_eq!(mixer.channel(&new).unwrap().volume, Num::new(1) / 2);
+
+        for _ in 0..2 {
+            mixer.frame();
+        }
+        assert!(!mixer.is_playing(&old));
+        assert!(mixer.is_playing(&effect));
+        assert_eq!(mixer.channel(&new).unwrap().volume, 1.into());
+    }
+
+    #[test_case]
+    fn released_envelopes_stop_the_sound(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+        let vblank = crate::interrupt::VBlank::get();
+        mixer.enable();
+
+        let mut channel = SoundChannel::new(SILENCE);
+        channel.should_loop().envelope(Envelope::new(2, 0, 1, 2));
+        let id = mixer.play_sound(channel).unwrap();
+
+        for _ in 0..4 {
+            mixer.frame();
+            vblank.wait_for_vblank();
+            mixer.after_vblank();
+        }
+        assert!(mixer.is_playing(&id));
+
+        mixer.channel(&id).unwrap().release();
+        for _ in 0..3 {
+            mixer.frame();
+            vblank.wait_for_vblank();
+            mixer.after_vblank();
+        }
+        assert!(!mixer.is_playing(&id));
+    }
+
+    #[test_case]
+    fn mixer_can_be_profiled(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+        let timers = gba.timers.timers();
+        assert_eq!(mixer.profile(), None);
+
+        mixer.enable_profiling(timers.timer2);
+        mixer.play_sound(SoundChannel::new(SILENCE));
+        mixer.frame();
+
+        let profile = mixer.profile().unwrap();
+        assert!(profile.cycles > 0);
+        assert_eq!(profile.peak_cycles, profile.cycles);
+
+        assert!(mixer.disable_profiling().is_some());
+        assert_eq!(mixer.profile(), None);
+    }
+
+    #[test_case]
+    fn position_allows_for_buffered_sound(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+        let vblank = crate::interrupt::VBlank::get();
+        mixer.enable();
+
+        let mut channel = SoundChannel::new(SILENCE);
+        channel.should_loop();
+        let id = mixer.play_sound(channel).unwrap();
+
+        mixer.frame();
+        vblank.wait_for_vblank();
+        mixer.after_vblank();
+
+        let buffered = mixer.buffered_samples();
+        assert!(buffered > 0 && buffered <= Frequency::Hz10512.buffer_size() * 3);
+
+        let position = mixer.position(&id).unwrap();
+        assert!(position <= mixer.channel(&id).unwrap().pos());
+    }
+
+    #[test_case]
+    fn late_frames_play_silence(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+
+        mixer.after_vblank();
+        assert_eq!(mixer.underruns(), 1);
+
+        mixer.frame();
+        mixer.after_vblank();
+        assert_eq!(mixer.underruns(), 1);
+
+        mixer.after_vblank();
+        assert_eq!(mixer.underruns(), 2);
+    }
+}
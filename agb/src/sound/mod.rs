@@ -6,12 +6,167 @@
 //! which allows for Game Boy and Game Boy Color style sound effects, or the mixer
 //! which allows for more advanced sounds.
 //!
-//! The [`dmg`](crate::sound::dmg) module is very rudimentary and doesn't support most of the possible
-//! sounds possible. However, it may be expanded in the future.
+//! The [`dmg`](crate::sound::dmg) module gives access to the 2 square wave channels, the
+//! programmable wave channel and the noise channel. It is quite rudimentary, but needs no CPU time
+//! once a sound is playing.
 //!
 //! The [`mixer`](crate::sound::mixer) module is high performance, and allows for playing wav files at
 //! various levels of quality. Check out the module documentation for more.
+//!
+//! The [`tracker`](crate::sound::tracker) module plays MOD, XM, S3M and IT music through the mixer.
+//!
+//! The [`envelope`](crate::sound::envelope) module shapes the volume of sounds from either over time.
+//!
+//! The [`sequencer`](crate::sound::sequencer) module plays MIDI music on the DMG channels.
+//!
+//! All sound from both can be silenced at once with [`mute`](crate::sound::mute), for example
+//! while the game is paused.
 
 pub mod dmg;
 
+pub mod envelope;
+
 pub mod mixer;
+
+pub mod sequencer;
+
+pub mod tracker;
+
+use crate::memory_mapped::MemoryMapped;
+use crate::sync::Static;
+
+// The sound registers shared by the DMG channels and the mixer. L controls the volume and outputs
+// of the DMG channels, H the direct sound channels, and X switches all sound on and off and shows
+// which DMG channels are playing.
+pub(crate) const SOUND_CONTROL_L: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0080) };
+pub(crate) const SOUND_CONTROL_H: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0082) };
+pub(crate) const SOUND_CONTROL_X: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0084) };
+pub(crate) const SOUND_BIAS: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0088) };
+
+// the bits of the sound bias register which hold the level
+const BIAS_LEVEL_BITS: u16 = 0x3fe;
+
+/// The sound bias level the BIOS sets when the GBA is turned on, which is
+/// halfway between the lowest and highest levels.
+pub const DEFAULT_BIAS_LEVEL: u16 = 0x200;
+
+// the bits which send each DMG channel to the left and right speakers
+const DMG_OUTPUT_BITS: u16 = 0xff00;
+// the bits which send the two direct sound channels used by the mixer to the speakers
+const DIRECT_SOUND_OUTPUT_BITS: u16 = 0b0011_0011_0000_0000;
+
+// the output bits which were set when muted
+static MUTED_OUTPUTS: Static<Option<(u16, u16)>> = Static::new(None);
+
+/// Immediately silences all sound, from both the DMG channels and the
+/// [mixer](crate::sound::mixer), until [`unmute`] is called. Everything keeps
+/// playing silently in the meantime, so sounds carry on from where they would
+/// have been.
+pub fn mute() {
+    if is_muted() {
+        return;
+    }
+
+    MUTED_OUTPUTS.write(Some((0, 0)));
+    update_mute();
+}
+
+/// Restores the sound silenced by [`mute`].
+pub fn unmute() {
+    if let Some((dmg_outputs, direct_sound_outputs)) = MUTED_OUTPUTS.replace(None) {
+        SOUND_CONTROL_L.set(SOUND_CONTROL_L.get() | dmg_outputs);
+        SOUND_CONTROL_H.set((SOUND_CONTROL_H.get() | direct_sound_outputs) & !FIFO_RESET_BITS);
+    }
+}
+
+/// Whether sound is currently [muted](mute).
+#[must_use]
+pub fn is_muted() -> bool {
+    MUTED_OUTPUTS.read().is_some()
+}
+
+/// The current sound bias level, which is the level the speaker rests at when
+/// the sound is silent. See [`set_bias_level`].
+#[must_use]
+pub fn bias_level() -> u16 {
+    SOUND_BIAS.get() & BIAS_LEVEL_BITS
+}
+
+/// Sets the sound bias level immediately. Sound is centred on this level, so
+/// anything other than [`DEFAULT_BIAS_LEVEL`] clips quiet or loud sounds, but
+/// setting it to 0 reduces the power used by the speaker while nothing is
+/// playing. Jumping between levels makes the speaker pop, which
+/// [`syscall::sound_bias`](crate::syscall::sound_bias) avoids by moving
+/// slowly.
+///
+/// # Panics
+///
+/// Panics if `level` is odd or greater than `0x3fe`, which the hardware can't
+/// represent.
+pub fn set_bias_level(level: u16) {
+    assert_eq!(
+        level & !BIAS_LEVEL_BITS,
+        0,
+        "the sound bias level must be even and at most 0x3fe"
+    );
+
+    SOUND_BIAS.set((SOUND_BIAS.get() & !BIAS_LEVEL_BITS) | level);
+}
+
+// writing these bits would clear the mixer's buffered sound
+const FIFO_RESET_BITS: u16 = (1 << 11) | (1 << 15);
+
+/// Moves any outputs enabled since muting into the saved outputs, so that
+/// setting up sound while muted doesn't make it audible.
+pub(crate) fn update_mute() {
+    let Some((dmg_outputs, direct_sound_outputs)) = MUTED_OUTPUTS.read() else {
+        return;
+    };
+
+    let control_l = SOUND_CONTROL_L.get();
+    let control_h = SOUND_CONTROL_H.get();
+
+    MUTED_OUTPUTS.write(Some((
+        dmg_outputs | (control_l & DMG_OUTPUT_BITS),
+        direct_sound_outputs | (control_h & DIRECT_SOUND_OUTPUT_BITS),
+    )));
+
+    SOUND_CONTROL_L.set(control_l & !DMG_OUTPUT_BITS);
+    SOUND_CONTROL_H.set(control_h & !DIRECT_SOUND_OUTPUT_BITS & !FIFO_RESET_BITS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn muting_silences_every_output(gba: &mut crate::Gba) {
+        gba.sound.enable();
+        let control_l = SOUND_CONTROL_L.get();
+
+        mute();
+        assert!(is_muted());
+        assert_eq!(SOUND_CONTROL_L.get() & DMG_OUTPUT_BITS, 0);
+        assert_eq!(SOUND_CONTROL_H.get() & DIRECT_SOUND_OUTPUT_BITS, 0);
+
+        // enabling sound while muted stays silent
+        gba.sound.enable();
+        assert_eq!(SOUND_CONTROL_L.get() & DMG_OUTPUT_BITS, 0);
+
+        unmute();
+        assert!(!is_muted());
+        assert_eq!(SOUND_CONTROL_L.get(), control_l);
+    }
+
+    #[test_case]
+    fn bias_level_keeps_the_resolution(_gba: &mut crate::Gba) {
+        let resolution = SOUND_BIAS.get() & !BIAS_LEVEL_BITS;
+
+        set_bias_level(0x100);
+        assert_eq!(bias_level(), 0x100);
+        assert_eq!(SOUND_BIAS.get() & !BIAS_LEVEL_BITS, resolution);
+
+        set_bias_level(DEFAULT_BIAS_LEVEL);
+        assert_eq!(bias_level(), DEFAULT_BIAS_LEVEL);
+    }
+}
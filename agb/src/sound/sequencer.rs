@@ -0,0 +1,281 @@
+#![deny(missing_docs)]
+//! Playing MIDI music on the [DMG](super::dmg) channels.
+//!
+//! Simple MIDI files are converted at compile time with
+//! [`include_midi!`](crate::include_midi) into a [`Sequence`] of events, which
+//! a [`Sequencer`] plays on the square and noise channels. The hardware
+//! generates the sound itself, so this costs almost no CPU time compared to
+//! playing music through the [mixer](super::mixer).
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::{include_midi, sound::sequencer::{Sequence, Sequencer}};
+//!
+//! static SONG: Sequence = include_midi!("examples/sfx/test_tune.mid");
+//!
+//! # fn foo(gba: &mut agb::Gba) {
+//! let vblank = agb::interrupt::VBlank::get();
+//! gba.sound.enable();
+//!
+//! let mut sequencer = Sequencer::new(&SONG);
+//! sequencer.should_loop();
+//!
+//! loop {
+//!     sequencer.update(&gba.sound);
+//!     vblank.wait_for_vblank();
+//! }
+//! # }
+//! ```
+//!
+//! Notes on MIDI channel 10 are played on the noise channel, with higher notes
+//! giving higher pitched noise. The first two other MIDI channels to be used
+//! are played on the two square channels, and the rest are ignored. Each
+//! channel plays one note at a time, so chords only play their last note.
+//!
+//! A program change sets the duty cycle of a square channel to the program
+//! number modulo 4, from 1/8 up to 3/4. Controller 72 (release time) sets a
+//! fading hardware envelope with a step time of the value divided by 16, with
+//! 0 meaning the note doesn't fade. Note velocities become the initial volume.
+
+use super::dmg::{DutyCycle, EnvelopeSettings, Sound, SoundDirection, SweepSettings};
+
+/// One of the DMG channels which a [`Sequence`] can play notes on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PsgChannel {
+    /// The first square channel, [`Channel1`](super::dmg::Channel1).
+    Square1,
+    /// The second square channel, [`Channel2`](super::dmg::Channel2).
+    Square2,
+    /// The [`Noise`](super::dmg::Noise) channel.
+    Noise,
+}
+
+impl PsgChannel {
+    fn index(self) -> usize {
+        match self {
+            PsgChannel::Square1 => 0,
+            PsgChannel::Square2 => 1,
+            PsgChannel::Noise => 2,
+        }
+    }
+}
+
+/// An event in a [`Sequence`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SequenceEvent {
+    /// Waits for the given number of frames before the next event.
+    Wait(u16),
+    /// Starts a note with the given value for the channel's frequency register
+    /// and initial volume between 0 and 15. For the noise channel, the
+    /// divider is in bits 0 to 2 and the clock shift in bits 4 to 7.
+    NoteOn(PsgChannel, u16, u8),
+    /// Stops the note playing on the channel.
+    NoteOff(PsgChannel),
+    /// Sets the duty cycle of the following notes on a square channel.
+    Duty(PsgChannel, DutyCycle),
+    /// Sets the step time of the fading hardware envelope of the following
+    /// notes on the channel, with 0 meaning they don't fade.
+    Envelope(PsgChannel, u8),
+}
+
+/// A MIDI file converted by [`include_midi!`](crate::include_midi).
+#[derive(Debug)]
+pub struct Sequence {
+    events: &'static [SequenceEvent],
+}
+
+impl Sequence {
+    #[doc(hidden)]
+    /// Used internally by [`include_midi!`](crate::include_midi).
+    #[must_use]
+    pub const fn new(events: &'static [SequenceEvent]) -> Self {
+        Self { events }
+    }
+
+    /// The events which make up the sequence.
+    #[must_use]
+    pub const fn events(&self) -> &'static [SequenceEvent] {
+        self.events
+    }
+}
+
+/// Plays a [`Sequence`] on the DMG channels. Call
+/// [`update`](Sequencer::update) once per frame.
+pub struct Sequencer {
+    sequence: &'static Sequence,
+    position: usize,
+    wait: u16,
+    should_loop: bool,
+
+    duty_cycles: [DutyCycle; 2],
+    step_times: [u8; 3],
+}
+
+impl Sequencer {
+    /// Creates a sequencer which starts at the beginning of the sequence.
+    #[must_use]
+    pub fn new(sequence: &'static Sequence) -> Self {
+        Self {
+            sequence,
+            position: 0,
+            wait: 0,
+            should_loop: false,
+
+            duty_cycles: [DutyCycle::Half; 2],
+            step_times: [0; 3],
+        }
+    }
+
+    /// Sets that the sequence should start again from the beginning once it
+    /// finishes.
+    pub fn should_loop(&mut self) -> &mut Self {
+        self.should_loop = true;
+        self
+    }
+
+    /// Plays every event up to the next wait. This should be called once per
+    /// frame. A looping sequence without any waits plays each of its events
+    /// once per call.
+    pub fn update(&mut self, sound: &Sound) {
+        if self.wait > 0 {
+            self.wait -= 1;
+            if self.wait > 0 {
+                return;
+            }
+        }
+
+        let events = self.sequence.events;
+        for _ in 0..events.len() {
+            if self.position >= events.len() {
+                if !self.should_loop {
+                    return;
+                }
+
+                self.position = 0;
+            }
+
+            let event = events[self.position];
+            self.position += 1;
+
+            match event {
+                SequenceEvent::Wait(frames) => {
+                    self.wait = frames;
+                    return;
+                }
+                SequenceEvent::NoteOn(channel, frequency, volume) => {
+                    self.play_note(sound, channel, frequency, volume);
+                }
+                SequenceEvent::NoteOff(channel) => match channel {
+                    PsgChannel::Square1 => sound.channel1().set_volume(0),
+                    PsgChannel::Square2 => sound.channel2().set_volume(0),
+                    PsgChannel::Noise => sound.noise().set_volume(0),
+                },
+                SequenceEvent::Duty(channel, duty_cycle) => {
+                    if channel != PsgChannel::Noise {
+                        self.duty_cycles[channel.index()] = duty_cycle;
+                    }
+                }
+                SequenceEvent::Envelope(channel, step_time) => {
+                    self.step_times[channel.index()] = step_time.min(7);
+                }
+            }
+        }
+    }
+
+    /// Whether a sequence which doesn't loop has played all of its events.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        !self.should_loop && self.wait == 0 && self.position >= self.sequence.events.len()
+    }
+
+    fn play_note(&self, sound: &Sound, channel: PsgChannel, frequency: u16, volume: u8) {
+        let envelope = EnvelopeSettings::new(
+            self.step_times[channel.index()],
+            SoundDirection::Decrease,
+            volume.min(15),
+        );
+
+        match channel {
+            PsgChannel::Square1 => sound.channel1().play_sound(
+                frequency,
+                None,
+                &SweepSettings::default(),
+                &envelope,
+                self.duty_cycles[0],
+            ),
+            PsgChannel::Square2 => {
+                sound
+                    .channel2()
+                    .play_sound(frequency, None, &envelope, self.duty_cycles[1]);
+            }
+            PsgChannel::Noise => sound.noise().play_sound(
+                None,
+                &envelope,
+                (frequency & 0b111) as u8,
+                true,
+                ((frequency >> 4) & 0b1111) as u8,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SEQUENCE: Sequence = Sequence::new(&[
+        SequenceEvent::Duty(PsgChannel::Square2, DutyCycle::OneQuarter),
+        SequenceEvent::NoteOn(PsgChannel::Square2, 1750, 15),
+        SequenceEvent::Wait(2),
+        SequenceEvent::NoteOff(PsgChannel::Square2),
+    ]);
+
+    #[test_case]
+    fn sequencer_waits_between_events(gba: &mut crate::Gba) {
+        gba.sound.enable();
+        let mut sequencer = Sequencer::new(&SEQUENCE);
+
+        sequencer.update(&gba.sound);
+        assert_eq!(sequencer.position, 3);
+        assert_eq!(sequencer.duty_cycles[1], DutyCycle::OneQuarter);
+
+        sequencer.update(&gba.sound);
+        assert_eq!(sequencer.position, 3);
+        assert!(!sequencer.is_finished());
+
+        sequencer.update(&gba.sound);
+        assert!(sequencer.is_finished());
+    }
+
+    #[test_case]
+    fn looping_sequences_without_waits_play_once_per_update(gba: &mut crate::Gba) {
+        static NO_WAITS: Sequence = Sequence::new(&[
+            SequenceEvent::Duty(PsgChannel::Square1, DutyCycle::OneEighth),
+            SequenceEvent::Envelope(PsgChannel::Square1, 3),
+        ]);
+
+        gba.sound.enable();
+        let mut sequencer = Sequencer::new(&NO_WAITS);
+        sequencer.should_loop();
+
+        sequencer.update(&gba.sound);
+        assert_eq!(sequencer.position, 2);
+        assert_eq!(sequencer.step_times[0], 3);
+
+        sequencer.update(&gba.sound);
+        assert_eq!(sequencer.position, 2);
+        assert!(!sequencer.is_finished());
+    }
+
+    #[test_case]
+    fn midi_files_can_be_included(_gba: &mut crate::Gba) {
+        static SONG: Sequence = crate::include_midi!("examples/sfx/test_tune.mid");
+
+        assert!(SONG
+            .events()
+            .iter()
+            .any(|event| matches!(event, SequenceEvent::NoteOn(..))));
+    }
+}
@@ -0,0 +1,741 @@
+#![deny(missing_docs)]
+//! Playing tracker music (MOD, XM, S3M and IT files) through the [mixer](super::mixer).
+//!
+//! Modules are converted at compile time with
+//! [`include_tracker!`](crate::include_tracker) into a compact [`Track`],
+//! and then played by a [`Tracker`] which needs to be stepped once per frame.
+//! Each channel of the module uses one channel of the mixer, so create the
+//! mixer with
+//! [`mixer_with_channels`](super::mixer::MixerController::mixer_with_channels)
+//! if the module has more channels than the mixer does by default.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::{include_tracker, sound::mixer::Frequency, sound::tracker::{Track, Tracker}};
+//!
+//! static SONG: Track = include_tracker!("examples/sfx/test_tune.mod");
+//!
+//! # fn foo(gba: &mut agb::Gba) {
+//! let vblank = agb::interrupt::VBlank::get();
+//! let mut mixer = gba.mixer.mixer(Frequency::Hz18157);
+//! mixer.enable();
+//!
+//! let mut tracker = Tracker::new(&SONG);
+//!
+//! loop {
+//!     tracker.step(&mut mixer);
+//!     mixer.frame();
+//!     vblank.wait_for_vblank();
+//!     mixer.after_vblank();
+//! }
+//! # }
+//! ```
+//!
+//! Only the commonly used effects are supported: arpeggio, portamento, tone
+//! portamento, panning, sample offset, volume slides, position jumps, pattern
+//! breaks, note cuts, speed and tempo changes and the global volume. The XM
+//! volume column supports setting and sliding the volume and setting the
+//! panning. Envelopes, vibrato and tremolo are ignored.
+//!
+//! S3M and IT modules have their effects converted to the equivalent ones
+//! above, including the fine and extra fine slides and effect memory. IT
+//! instruments are only used to pick which sample each note plays, and
+//! compressed IT samples can't be included.
+//!
+//! Anything in a module which isn't supported gives a warning when it is
+//! included rather than being silently ignored.
+
+use alloc::vec::Vec;
+
+use super::mixer::{ChannelId, Mixer, SoundChannel, SoundData};
+use crate::collections::RingBuffer;
+use crate::fixnum::Num;
+
+/// Pitches are measured in 64ths of a semitone relative to C-4.
+const PITCH_PER_SEMITONE: i32 = 64;
+const PITCH_PER_OCTAVE: i32 = PITCH_PER_SEMITONE * 12;
+
+/// 2^(i / 12) as 16.16 fixed point.
+const SEMITONE_RATIOS: [u32; 12] = [
+    65536, 69433, 73562, 77936, 82570, 87480, 92682, 98193, 104032, 110218, 116772, 123715,
+];
+
+/// 2^(i / 768) as 16.16 fixed point.
+const FINE_RATIOS: [u32; 64] = [
+    65536, 65595, 65654, 65714, 65773, 65832, 65892, 65951, 66011, 66071, 66130, 66190, 66250,
+    66309, 66369, 66429, 66489, 66549, 66609, 66670, 66730, 66790, 66850, 66911, 66971, 67032,
+    67092, 67153, 67213, 67274, 67335, 67395, 67456, 67517, 67578, 67639, 67700, 67761, 67823,
+    67884, 67945, 68007, 68068, 68129, 68191, 68252, 68314, 68376, 68438, 68499, 68561, 68623,
+    68685, 68747, 68809, 68871, 68933, 68996, 69058, 69120, 69183, 69245, 69308, 69370,
+];
+
+/// The number of frames per tick at 1 beat per minute, as 24.8 fixed point.
+/// Trackers run at `tempo * 2 / 5` ticks per second and the GBA at 59.73
+/// frames per second.
+const FRAMES_PER_TICK_AT_1_BPM: u32 = 38231;
+
+/// The frequency in Hz of a sample playing at `pitch`, given the frequency it
+/// plays at C-4.
+fn frequency_at(rate: u32, pitch: i32) -> u64 {
+    let octave = pitch.div_euclid(PITCH_PER_OCTAVE);
+    let within_octave = pitch.rem_euclid(PITCH_PER_OCTAVE);
+
+    let semitone = SEMITONE_RATIOS[(within_octave / PITCH_PER_SEMITONE) as usize];
+    let fine = FINE_RATIOS[(within_octave % PITCH_PER_SEMITONE) as usize];
+
+    let frequency = (u64::from(rate) * u64::from(semitone)) >> 16;
+    let frequency = (frequency * u64::from(fine)) >> 16;
+
+    if octave >= 0 {
+        frequency << octave.min(16)
+    } else {
+        frequency >> (-octave).min(63)
+    }
+}
+
+/// A sample used by a [`Track`].
+#[derive(Clone, Copy, Debug)]
+pub struct TrackSample {
+    sound: SoundData,
+    volume: u8,
+    rate: u32,
+}
+
+impl TrackSample {
+    #[doc(hidden)]
+    /// Used internally by [`include_tracker!`](crate::include_tracker).
+    #[must_use]
+    pub const fn new(sound: SoundData, volume: u8, rate: u32) -> Self {
+        Self {
+            sound,
+            volume,
+            rate,
+        }
+    }
+
+    /// The samples and loop point.
+    #[must_use]
+    pub const fn sound(&self) -> SoundData {
+        self.sound
+    }
+
+    /// The default volume of the sample, between 0 and 64.
+    #[must_use]
+    pub const fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// The frequency in Hz the sample plays at for C-4.
+    #[must_use]
+    pub const fn rate(&self) -> u32 {
+        self.rate
+    }
+}
+
+/// An effect from either the effect or volume column of a pattern. Pitch
+/// changes are in 64ths of a semitone and volumes are out of 64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PatternEffect {
+    /// No effect.
+    None,
+    /// Stops the note.
+    Stop,
+    /// Cycles between the note and the note raised by each number of
+    /// semitones on every tick.
+    Arpeggio(u8, u8),
+    /// Sets the panning, from -128 (left) to 127 (right).
+    Panning(i8),
+    /// Sets the volume.
+    Volume(u8),
+    /// Changes the volume on every tick but the first.
+    VolumeSlide(i8),
+    /// Changes the volume once, on the first tick.
+    FineVolumeSlide(i8),
+    /// Stops the note on the given tick.
+    NoteCut(u8),
+    /// Changes the pitch on every tick but the first.
+    Portamento(i16),
+    /// Changes the pitch once, on the first tick.
+    FinePortamento(i16),
+    /// Slides the pitch towards the note in the same slot by this amount on
+    /// every tick but the first, rather than starting a new note. A speed of
+    /// 0 uses the previous speed.
+    TonePortamento(u16),
+    /// Starts the sample this many bytes in.
+    SampleOffset(u16),
+    /// Sets the number of ticks per row.
+    SetSpeed(u8),
+    /// Sets the tempo in beats per minute.
+    SetTempo(u8),
+    /// Sets the global volume.
+    SetGlobalVolume(u8),
+    /// Changes the global volume on every tick but the first.
+    GlobalVolumeSlide(i8),
+    /// Moves to the given position in the order table after this row.
+    PositionJump(u8),
+    /// Moves to the given row of the next pattern after this row.
+    PatternBreak(u8),
+}
+
+/// One channel of one row of a pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PatternSlot {
+    pitch: i16,
+    sample: u16,
+    volume_effect: PatternEffect,
+    effect: PatternEffect,
+}
+
+impl PatternSlot {
+    /// The pitch of a slot which doesn't start a new note.
+    pub const NO_NOTE: i16 = i16::MIN;
+
+    #[doc(hidden)]
+    /// Used internally by [`include_tracker!`](crate::include_tracker).
+    /// `sample` is one more than the index of the sample, or 0 for none.
+    #[must_use]
+    pub const fn new(
+        pitch: i16,
+        sample: u16,
+        volume_effect: PatternEffect,
+        effect: PatternEffect,
+    ) -> Self {
+        Self {
+            pitch,
+            sample,
+            volume_effect,
+            effect,
+        }
+    }
+
+    fn effects(&self) -> [PatternEffect; 2] {
+        [self.volume_effect, self.effect]
+    }
+}
+
+/// The location of a pattern within the pattern data of a [`Track`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    rows: u16,
+    start: u32,
+}
+
+impl Pattern {
+    #[doc(hidden)]
+    /// Used internally by [`include_tracker!`](crate::include_tracker).
+    #[must_use]
+    pub const fn new(rows: u16, start: u32) -> Self {
+        Self { rows, start }
+    }
+}
+
+/// A MOD, XM, S3M or IT module converted by [`include_tracker!`](crate::include_tracker).
+#[derive(Debug)]
+pub struct Track {
+    samples: &'static [TrackSample],
+    pattern_data: &'static [PatternSlot],
+    patterns: &'static [Pattern],
+    orders: &'static [u8],
+    channels: usize,
+    initial_speed: u8,
+    initial_tempo: u8,
+    restart_position: usize,
+}
+
+impl Track {
+    #[doc(hidden)]
+    /// Used internally by [`include_tracker!`](crate::include_tracker).
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub const fn new(
+        samples: &'static [TrackSample],
+        pattern_data: &'static [PatternSlot],
+        patterns: &'static [Pattern],
+        orders: &'static [u8],
+        channels: usize,
+        initial_speed: u8,
+        initial_tempo: u8,
+        restart_position: usize,
+    ) -> Self {
+        Self {
+            samples,
+            pattern_data,
+            patterns,
+            orders,
+            channels,
+            initial_speed,
+            initial_tempo,
+            restart_position,
+        }
+    }
+
+    /// The number of channels, each of which uses a mixer channel while it is
+    /// playing.
+    #[must_use]
+    pub const fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// The samples used by the track.
+    #[must_use]
+    pub const fn samples(&self) -> &'static [TrackSample] {
+        self.samples
+    }
+
+    /// The number of entries in the order table.
+    #[must_use]
+    pub const fn length(&self) -> usize {
+        self.orders.len()
+    }
+
+    fn slot(&self, position: usize, row: usize, channel: usize) -> &'static PatternSlot {
+        let pattern = &self.patterns[usize::from(self.orders[position])];
+        &self.pattern_data[pattern.start as usize + row * self.channels + channel]
+    }
+
+    fn rows(&self, position: usize) -> usize {
+        usize::from(self.patterns[usize::from(self.orders[position])].rows)
+    }
+}
+
+#[derive(Default)]
+struct TrackerChannel {
+    channel_id: Option<ChannelId>,
+    sample: Option<&'static TrackSample>,
+    sample_volume: u8,
+    pitch: i32,
+    pitch_offset: i32,
+    target_pitch: i32,
+    tone_portamento_speed: u16,
+    volume: i32,
+    panning: i8,
+}
+
+impl TrackerChannel {
+    fn start_note(&mut self, mixer: &mut Mixer, sample: &'static TrackSample, offset: u16) {
+        if let Some(channel) = self.channel_id.take().and_then(|id| mixer.channel(&id)) {
+            channel.stop();
+        }
+
+        let mut channel = SoundChannel::new_high_priority(sample.sound);
+        if usize::from(offset) < sample.sound.len() {
+            channel.set_pos(usize::from(offset));
+        }
+
+        self.sample = Some(sample);
+        self.channel_id = mixer.play_sound(channel);
+    }
+
+    fn stop(&mut self, mixer: &mut Mixer) {
+        if let Some(channel) = self.channel_id.take().and_then(|id| mixer.channel(&id)) {
+            channel.stop();
+        }
+    }
+
+    fn update_mixer(&self, mixer: &mut Mixer, global_volume: i32) {
+        let mixer_frequency = mixer.frequency().frequency() as u64;
+        let (Some(sample), Some(channel)) = (
+            self.sample,
+            self.channel_id.as_ref().and_then(|id| mixer.channel(id)),
+        ) else {
+            return;
+        };
+
+        let playback_frequency = frequency_at(sample.rate, self.pitch + self.pitch_offset);
+        let speed = (playback_frequency << 8) / mixer_frequency;
+        channel.playback(Num::<usize, 8>::from_raw(speed as usize));
+
+        // volume, sample volume and global volume are all out of 64
+        let volume = self.volume * i32::from(self.sample_volume) * global_volume;
+        channel.volume(Num::<i16, 4>::from_raw((volume >> 14) as i16));
+        channel.panning(Num::<i16, 4>::from_raw(i16::from(self.panning) >> 3));
+    }
+}
+
+/// A position in a [`Track`], as returned by [`Tracker::song_position`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SongPosition {
+    /// The position in the order table.
+    pub position: usize,
+    /// The row of the pattern.
+    pub row: usize,
+    /// The tick within the row.
+    pub tick: u32,
+}
+
+/// How many of the most recent ticks are remembered, which needs to cover the
+/// few frames of sound the mixer keeps ready even at the fastest tempo.
+const TICK_HISTORY_LENGTH: usize = 16;
+
+/// Plays a [`Track`] through the mixer.
+pub struct Tracker {
+    track: &'static Track,
+    channels: Vec<TrackerChannel>,
+
+    frame: Num<u32, 8>,
+    frames_per_tick: Num<u32, 8>,
+    tick: u32,
+    speed: u32,
+
+    position: usize,
+    row: usize,
+    next: Option<(usize, usize)>,
+
+    global_volume: i32,
+
+    frames_stepped: u32,
+    // the frame each of the recent ticks were processed in
+    tick_history: RingBuffer<(u32, SongPosition), TICK_HISTORY_LENGTH>,
+}
+
+impl Tracker {
+    /// Creates a tracker which starts at the beginning of the track.
+    #[must_use]
+    pub fn new(track: &'static Track) -> Self {
+        let mut tracker = Self {
+            track,
+            channels: (0..track.channels).map(|_| Default::default()).collect(),
+
+            frame: 0.into(),
+            frames_per_tick: 0.into(),
+            tick: 0,
+            speed: u32::from(track.initial_speed),
+
+            position: 0,
+            row: 0,
+            next: None,
+
+            global_volume: 64,
+
+            frames_stepped: 0,
+            tick_history: RingBuffer::new(),
+        };
+
+        tracker.set_tempo(track.initial_tempo);
+        // process the first tick straight away
+        tracker.frame = tracker.frames_per_tick;
+        tracker
+    }
+
+    /// The current position in the order table.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The current row of the pattern being played.
+    #[must_use]
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// The position in the track which is being heard right now. The tracker
+    /// runs a little ahead of what can be heard because the mixer keeps some
+    /// sound ready to play, so this uses [`Mixer::buffered_samples`] to work
+    /// out which tick is actually playing. Call it after [`Mixer::frame`].
+    #[must_use]
+    pub fn song_position(&self, mixer: &Mixer) -> SongPosition {
+        let buffer_size = mixer.frequency().buffer_size() as u32;
+        let latency =
+            Num::<u32, 8>::from_raw((mixer.buffered_samples() as u32 * 256) / buffer_size);
+        let now = Num::new(self.frames_stepped);
+
+        self.tick_history
+            .iter()
+            .rev()
+            .find(|(frame, _)| Num::new(*frame) + latency <= now)
+            .or_else(|| self.tick_history.front())
+            .map_or(
+                SongPosition {
+                    position: self.position,
+                    row: self.row,
+                    tick: self.tick,
+                },
+                |(_, position)| *position,
+            )
+    }
+
+    /// Advances the track by one frame, starting and updating notes in the
+    /// mixer. Call this once per frame, before [`Mixer::frame`].
+    pub fn step(&mut self, mixer: &mut Mixer) {
+        while self.frame >= self.frames_per_tick {
+            self.frame -= self.frames_per_tick;
+            self.process_tick(mixer);
+        }
+
+        self.frame += 1;
+        self.frames_stepped = self.frames_stepped.wrapping_add(1);
+    }
+
+    /// Stops every note which is currently playing.
+    pub fn stop(&mut self, mixer: &mut Mixer) {
+        for channel in &mut self.channels {
+            channel.stop(mixer);
+        }
+    }
+
+    fn set_tempo(&mut self, tempo: u8) {
+        self.frames_per_tick = Num::from_raw(FRAMES_PER_TICK_AT_1_BPM / u32::from(tempo.max(1)));
+    }
+
+    fn process_tick(&mut self, mixer: &mut Mixer) {
+        self.tick_history.push_overwriting((
+            self.frames_stepped,
+            SongPosition {
+                position: self.position,
+                row: self.row,
+                tick: self.tick,
+            },
+        ));
+
+        for channel in 0..self.channels.len() {
+            let slot = self.track.slot(self.position, self.row, channel);
+
+            if self.tick == 0 {
+                self.start_row(mixer, channel, slot);
+            }
+
+            for effect in slot.effects() {
+                self.apply_effect(mixer, channel, effect);
+            }
+        }
+
+        for channel in &self.channels {
+            channel.update_mixer(mixer, self.global_volume);
+        }
+
+        self.tick += 1;
+        if self.tick >= self.speed {
+            self.tick = 0;
+            self.next_row();
+        }
+    }
+
+    fn start_row(&mut self, mixer: &mut Mixer, channel: usize, slot: &PatternSlot) {
+        let samples = self.track.samples;
+        let state = &mut self.channels[channel];
+        state.pitch_offset = 0;
+
+        let sample = slot
+            .sample
+            .checked_sub(1)
+            .and_then(|sample| samples.get(usize::from(sample)));
+
+        if let Some(sample) = sample {
+            state.sample_volume = sample.volume;
+            state.volume = 64;
+        }
+
+        if slot.pitch == PatternSlot::NO_NOTE {
+            return;
+        }
+
+        let tone_portamento = slot
+            .effects()
+            .iter()
+            .any(|effect| matches!(effect, PatternEffect::TonePortamento(_)));
+
+        if tone_portamento && state.channel_id.is_some() {
+            state.target_pitch = i32::from(slot.pitch);
+            return;
+        }
+
+        let offset = slot
+            .effects()
+            .iter()
+            .find_map(|effect| match effect {
+                PatternEffect::SampleOffset(offset) => Some(*offset),
+                _ => None,
+            })
+            .unwrap_or(0);
+
+        if let Some(sample) = sample.or(state.sample) {
+            if sample.sound.is_empty() {
+                return;
+            }
+
+            state.pitch = i32::from(slot.pitch);
+            state.target_pitch = state.pitch;
+            state.start_note(mixer, sample, offset);
+        }
+    }
+
+    fn apply_effect(&mut self, mixer: &mut Mixer, channel: usize, effect: PatternEffect) {
+        let first_tick = self.tick == 0;
+        let state = &mut self.channels[channel];
+
+        match effect {
+            PatternEffect::None | PatternEffect::SampleOffset(_) => {}
+            PatternEffect::Stop => {
+                if first_tick {
+                    state.stop(mixer);
+                }
+            }
+            PatternEffect::Arpeggio(first, second) => {
+                let semitones = match self.tick % 3 {
+                    0 => 0,
+                    1 => first,
+                    _ => second,
+                };
+                state.pitch_offset = i32::from(semitones) * PITCH_PER_SEMITONE;
+            }
+            PatternEffect::Panning(panning) => state.panning = panning,
+            PatternEffect::Volume(volume) => {
+                if first_tick {
+                    state.volume = i32::from(volume.min(64));
+                }
+            }
+            PatternEffect::VolumeSlide(amount) => {
+                if !first_tick {
+                    state.volume = (state.volume + i32::from(amount)).clamp(0, 64);
+                }
+            }
+            PatternEffect::FineVolumeSlide(amount) => {
+                if first_tick {
+                    state.volume = (state.volume + i32::from(amount)).clamp(0, 64);
+                }
+            }
+            PatternEffect::NoteCut(tick) => {
+                if self.tick == u32::from(tick) {
+                    state.volume = 0;
+                }
+            }
+            PatternEffect::Portamento(amount) => {
+                if !first_tick {
+                    state.pitch += i32::from(amount);
+                }
+            }
+            PatternEffect::FinePortamento(amount) => {
+                if first_tick {
+                    state.pitch += i32::from(amount);
+                }
+            }
+            PatternEffect::TonePortamento(speed) => {
+                if first_tick {
+                    if speed != 0 {
+                        state.tone_portamento_speed = speed;
+                    }
+                } else {
+                    let speed = i32::from(state.tone_portamento_speed);
+                    let difference = state.target_pitch - state.pitch;
+                    state.pitch += difference.clamp(-speed, speed);
+                }
+            }
+            PatternEffect::SetSpeed(speed) => {
+                if first_tick && speed > 0 {
+                    self.speed = u32::from(speed);
+                }
+            }
+            PatternEffect::SetTempo(tempo) => {
+                if first_tick {
+                    self.set_tempo(tempo);
+                }
+            }
+            PatternEffect::SetGlobalVolume(volume) => {
+                if first_tick {
+                    self.global_volume = i32::from(volume.min(64));
+                }
+            }
+            PatternEffect::GlobalVolumeSlide(amount) => {
+                if !first_tick {
+                    self.global_volume = (self.global_volume + i32::from(amount)).clamp(0, 64);
+                }
+            }
+            PatternEffect::PositionJump(position) => {
+                if first_tick {
+                    let row = self.next.map_or(0, |(_, row)| row);
+                    self.next = Some((usize::from(position), row));
+                }
+            }
+            PatternEffect::PatternBreak(row) => {
+                if first_tick {
+                    let position = self
+                        .next
+                        .map_or(self.position + 1, |(position, _)| position);
+                    self.next = Some((position, usize::from(row)));
+                }
+            }
+        }
+    }
+
+    fn next_row(&mut self) {
+        let (position, row) = self.next.take().unwrap_or_else(|| {
+            if self.row + 1 < self.track.rows(self.position) {
+                (self.position, self.row + 1)
+            } else {
+                (self.position + 1, 0)
+            }
+        });
+
+        self.position = if position < self.track.length() {
+            position
+        } else {
+            self.track.restart_position
+        };
+        self.row = if row < self.track.rows(self.position) {
+            row
+        } else {
+            0
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::mixer::Frequency;
+
+    static TUNE: Track = crate::include_tracker!("examples/sfx/test_tune.mod");
+
+    #[test_case]
+    fn pitches_are_relative_to_the_sample_rate(_gba: &mut crate::Gba) {
+        assert_eq!(frequency_at(8363, 0), 8363);
+        assert_eq!(frequency_at(8363, PITCH_PER_OCTAVE), 8363 * 2);
+        assert_eq!(frequency_at(8363, -PITCH_PER_OCTAVE), 8363 / 2);
+        assert_eq!(frequency_at(8000, 7 * PITCH_PER_SEMITONE), 11986);
+    }
+
+    #[test_case]
+    fn tracker_moves_through_the_track(gba: &mut crate::Gba) {
+        assert_eq!(TUNE.channels(), 4);
+        assert_eq!(TUNE.length(), 2);
+
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+        let mut tracker = Tracker::new(&TUNE);
+
+        tracker.step(&mut mixer);
+        assert_eq!((tracker.position(), tracker.row()), (0, 0));
+        assert!(mixer.playing_channels() > 0);
+
+        // 6 ticks per row at 125bpm is a little over 7 frames per row
+        for _ in 0..64 * 8 {
+            tracker.step(&mut mixer);
+        }
+        assert_eq!(tracker.position(), 1);
+
+        tracker.stop(&mut mixer);
+        assert_eq!(mixer.playing_channels(), 0);
+    }
+
+    #[test_case]
+    fn song_position_lags_behind_the_tracker(gba: &mut crate::Gba) {
+        let mut mixer = gba.mixer.mixer(Frequency::Hz10512);
+        let vblank = crate::interrupt::VBlank::get();
+        mixer.enable();
+
+        let mut tracker = Tracker::new(&TUNE);
+        for _ in 0..20 {
+            tracker.step(&mut mixer);
+            mixer.frame();
+            vblank.wait_for_vblank();
+            mixer.after_vblank();
+        }
+
+        let heard = tracker.song_position(&mixer);
+        assert_eq!(heard.position, 0);
+        assert!(heard.row <= tracker.row());
+        assert!(heard.row + 1 >= tracker.row());
+    }
+}
@@ -1,9 +1,16 @@
 use agb_fixnum::Vector2D;
+use alloc::vec::Vec;
+use bitflags::bitflags;
 use core::arch::asm;
+use core::convert::TryFrom;
 use core::mem::MaybeUninit;
 
 use crate::display::affine::AffineMatrixBackground;
 use crate::fixnum::Num;
+use crate::input::{Button, ButtonController};
+use crate::interrupt::{add_interrupt_handler, Interrupt, VBlank};
+use crate::memory_mapped::MemoryMapped;
+use crate::sync::Static;
 
 #[allow(non_snake_case)]
 
@@ -15,6 +22,7 @@ const fn swi_map(thumb_id: u32) -> u32 {
     }
 }
 
+/// Halts the CPU until the next enabled interrupt using the Halt BIOS call.
 pub fn halt() {
     unsafe {
         asm!(
@@ -28,6 +36,9 @@ pub fn halt() {
     }
 }
 
+/// Stops the CPU, the display, the sound and the timers until a keypad,
+/// cartridge or serial interrupt using the Stop BIOS call. [`sleep`] sets
+/// this up so the GBA can be woken again.
 pub fn stop() {
     unsafe {
         asm!(
@@ -54,9 +65,29 @@ pub fn wait_for_interrupt() {
     }
 }
 
-/// The vblank interrupt handler [VBlank][crate::interrupt::VBlank] should be
-/// used instead of calling this function directly.
+static VBLANK_ENABLED: Static<bool> = Static::new(false);
+
+/// Halts the CPU until the next vblank using the VBlankIntrWait BIOS call,
+/// which uses far less power than checking the current line in a loop. The
+/// first call enables the vblank interrupt, which then stays enabled.
+/// Interrupts must not be disabled with [`free`](crate::interrupt::free) or
+/// this will never return.
+///
+/// Holding on to a [`VBlank`] and calling
+/// [`wait_for_vblank`](VBlank::wait_for_vblank) on it does the same, but
+/// disables the interrupt again once the [`VBlank`] is dropped.
 pub fn wait_for_vblank() {
+    if !VBLANK_ENABLED.read() {
+        core::mem::forget(VBlank::get());
+        VBLANK_ENABLED.write(true);
+    }
+
+    vblank_interrupt_wait();
+}
+
+/// The VBlankIntrWait BIOS call, for when the vblank interrupt is already
+/// enabled.
+pub(crate) fn vblank_interrupt_wait() {
     unsafe {
         asm!(
             "swi {SWI}",
@@ -69,8 +100,219 @@ pub fn wait_for_vblank() {
     }
 }
 
+const KEY_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0132) };
+const KEY_INTERRUPT_ENABLE: u16 = 1 << 14;
+const KEY_INTERRUPT_ALL_PRESSED: u16 = 1 << 15;
+const FORCED_BLANK: u16 = 1 << 7;
+
+/// Puts the GBA into sleep mode until all of `wake_buttons` are pressed
+/// together, which is how games let players keep their place without using up
+/// the battery. While asleep the screen is off, the sound is silent and the
+/// CPU, timers and DMA are stopped, so nothing happens until the GBA wakes up
+/// and this returns.
+///
+/// The sound bias is lowered to silence the speaker while asleep, which stops
+/// the pop it would otherwise make, and is raised again once awake. This waits
+/// for `wake_buttons` to be released both before going to sleep and after
+/// waking up, so the buttons used to go to sleep don't wake the GBA straight
+/// away and the buttons used to wake it don't end up being seen by the game.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::input::{Button, ButtonController};
+///
+/// # fn foo(input: &ButtonController) {
+/// if input.is_pressed(Button::L) && input.is_pressed(Button::R) && input.is_just_pressed(Button::SELECT) {
+///     agb::syscall::sleep(Button::L | Button::R | Button::SELECT);
+/// }
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `wake_buttons` is empty, as nothing could wake the GBA again.
+pub fn sleep(wake_buttons: Button) {
+    assert!(
+        !wake_buttons.is_empty(),
+        "the GBA couldn't be woken up from sleep"
+    );
+
+    let vblank = VBlank::get();
+    let wait_for_release = || {
+        while ButtonController::new().is_pressed(wake_buttons) {
+            vblank.wait_for_vblank();
+        }
+    };
+
+    wait_for_release();
+
+    let display_control = crate::display::DISPLAY_CONTROL.get();
+    let key_control = KEY_CONTROL.get();
+
+    sound_bias(false);
+    crate::display::DISPLAY_CONTROL.set(display_control | FORCED_BLANK);
+    KEY_CONTROL.set(wake_buttons.bits() as u16 | KEY_INTERRUPT_ENABLE | KEY_INTERRUPT_ALL_PRESSED);
+
+    {
+        let _wake_up = add_interrupt_handler(Interrupt::Keypad, |_| {});
+        stop();
+    }
+
+    KEY_CONTROL.set(key_control);
+    crate::display::DISPLAY_CONTROL.set(display_control);
+    sound_bias(true);
+
+    wait_for_release();
+}
+
+/// Slowly moves the sound bias to 0 if `enable` is false, or back to its usual
+/// level if it is true, using the SoundBias BIOS call. Changing it suddenly
+/// makes the speaker pop, so this is used around [`stop`] to silence the
+/// speaker while the GBA sleeps. See [`sound::set_bias_level`](crate::sound::set_bias_level)
+/// to change it straight away.
+pub fn sound_bias(enable: bool) {
+    unsafe {
+        asm!(
+            "swi {SWI}",
+            SWI = const { swi_map(0x19) },
+            in("r0") u32::from(enable),
+
+            clobber_abi("C")
+        );
+    }
+}
+
+bitflags! {
+    /// The parts of the hardware which [`register_ram_reset`] can clear.
+    pub struct ResetFlags: u8 {
+        /// Sets every colour in both palettes to black.
+        const PALETTE = 1 << 2;
+        /// Clears all of video RAM.
+        const VRAM = 1 << 3;
+        /// Clears object attribute memory, which makes every sprite a visible
+        /// 8x8 sprite in the top left corner.
+        const OAM = 1 << 4;
+        /// Resets the serial registers, which disconnects any link cable
+        /// session.
+        const SERIAL = 1 << 5;
+        /// Resets the sound registers, which stops the sound chip.
+        const SOUND = 1 << 6;
+    }
+}
+
+const RESET_EWRAM: u8 = 1 << 0;
+const RESET_IWRAM: u8 = 1 << 1;
+const RESET_REGISTERS: u8 = 1 << 7;
+
+/// Where [`soft_reset`] restarts the game from, 0 for ROM and anything else
+/// for EWRAM.
+const SOFT_RESET_TARGET: MemoryMapped<u8> = unsafe { MemoryMapped::new(0x0300_7FFA) };
+const DMA_CONTROL: [MemoryMapped<u16>; 4] = unsafe {
+    [
+        MemoryMapped::new(0x0400_00BA),
+        MemoryMapped::new(0x0400_00C6),
+        MemoryMapped::new(0x0400_00D2),
+        MemoryMapped::new(0x0400_00DE),
+    ]
+};
+
+/// Clears the parts of the hardware given in `flags` using the
+/// RegisterRamReset BIOS call, for example to get rid of everything on the
+/// screen before returning to the title screen.
+///
+/// Anything which keeps track of what it has put in the cleared memory, such
+/// as a [`VRamManager`](crate::display::tiled::VRamManager) or the
+/// [`ObjectController`](crate::display::object::ObjectController), will be
+/// wrong afterwards, so this should be done before creating them or after
+/// dropping them.
+pub fn register_ram_reset(flags: ResetFlags) {
+    unsafe {
+        asm!(
+            "swi {SWI}",
+            SWI = const { swi_map(0x01) },
+            in("r0") u32::from(flags.bits()),
+
+            clobber_abi("C")
+        );
+    }
+}
+
+/// Restarts the game from the beginning using the SoftReset BIOS call, like
+/// turning the GBA off and on again but without going through the boot
+/// animation. Commercial games usually do this when A, B, Start and Select
+/// are held together.
+///
+/// Interrupts, DMA, sound and every other register is stopped and memory is
+/// cleared first, so the game starts in the same state as it does when the
+/// GBA is turned on.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::input::{Button, ButtonController};
+///
+/// # fn foo(input: &ButtonController) {
+/// let reset_buttons = [Button::A, Button::B, Button::START, Button::SELECT];
+/// if reset_buttons.iter().all(|&button| input.is_pressed(button)) {
+///     agb::syscall::soft_reset();
+/// }
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the game was multibooted. Multiboot games run from EWRAM, where
+/// the starting values of statics have been overwritten while the game ran, so
+/// the game can't be started again.
+pub fn soft_reset() -> ! {
+    assert!(
+        !is_multiboot(),
+        "multiboot games can't be soft reset, as their starting state is lost"
+    );
+
+    crate::interrupt::disable_interrupts();
+    for control in DMA_CONTROL {
+        control.set(0);
+    }
+    SOFT_RESET_TARGET.set(0);
+
+    let flags = ResetFlags::all().bits() | RESET_EWRAM | RESET_IWRAM | RESET_REGISTERS;
+
+    // the stack is cleared along with the rest of IWRAM, so the reset has to
+    // happen without returning to any rust code
+    unsafe {
+        asm!(
+            "swi {REGISTER_RAM_RESET}",
+            "swi {SOFT_RESET}",
+            REGISTER_RAM_RESET = const { swi_map(0x01) },
+            SOFT_RESET = const { swi_map(0x00) },
+            in("r0") u32::from(flags),
+            options(noreturn)
+        );
+    }
+}
+
+pub(crate) fn is_multiboot() -> bool {
+    extern "C" {
+        static __text_start: u8;
+    }
+
+    let text_start = unsafe { &__text_start } as *const u8 as usize;
+    (0x0200_0000..0x0204_0000).contains(&text_start)
+}
+
+/// Divides `numerator` by `denominator` using the Div BIOS call, returning the
+/// quotient rounded towards zero, the remainder and the absolute value of the
+/// quotient.
+///
+/// # Panics
+///
+/// Panics if `denominator` is zero, which would make the BIOS hang.
 #[must_use]
 pub fn div(numerator: i32, denominator: i32) -> (i32, i32, i32) {
+    assert_ne!(denominator, 0, "attempt to divide by zero");
+
     let divide: i32;
     let modulo: i32;
     let abs_divide: i32;
@@ -88,6 +330,35 @@ pub fn div(numerator: i32, denominator: i32) -> (i32, i32, i32) {
     (divide, modulo, abs_divide)
 }
 
+/// The same as [`div`], but using the DivArm BIOS call which takes its
+/// arguments the other way around. This is only useful for matching code
+/// written for other GBA libraries.
+///
+/// # Panics
+///
+/// Panics if `denominator` is zero, which would make the BIOS hang.
+#[must_use]
+pub fn div_arm(numerator: i32, denominator: i32) -> (i32, i32, i32) {
+    assert_ne!(denominator, 0, "attempt to divide by zero");
+
+    let divide: i32;
+    let modulo: i32;
+    let abs_divide: i32;
+    unsafe {
+        asm!(
+            "swi {SWI}",
+            SWI = const { swi_map(0x07) },
+            in("r0") denominator,
+            in("r1") numerator,
+            lateout("r0") divide,
+            lateout("r1") modulo,
+            lateout("r3") abs_divide,
+        );
+    }
+    (divide, modulo, abs_divide)
+}
+
+/// The square root of `n` using the Sqrt BIOS call, rounded down.
 #[must_use]
 pub fn sqrt(n: i32) -> i32 {
     let result: i32;
@@ -105,6 +376,9 @@ pub fn sqrt(n: i32) -> i32 {
     result
 }
 
+/// The arc tangent of `n` using the ArcTan BIOS call, where `n` has 14
+/// fractional bits and the result is from -0x4000 to 0x4000 for -1/4 to 1/4 of
+/// a revolution. Use [`arc_tan2`] to find the angle of a point instead.
 #[must_use]
 pub fn arc_tan(n: i16) -> i16 {
     let result: i16;
@@ -122,16 +396,19 @@ pub fn arc_tan(n: i16) -> i16 {
     result
 }
 
+/// The angle of the point (x, y) from the positive x axis using the ArcTan2
+/// BIOS call, from 0 up to 0xffff for a whole revolution.
 #[must_use]
-pub fn arc_tan2(x: i16, y: i32) -> i16 {
-    let result: i16;
+pub fn arc_tan2(x: i16, y: i16) -> u16 {
+    let result: u16;
     unsafe {
         asm!(
             "swi {SWI}",
-            SWI = const { swi_map(0x09) },
+            SWI = const { swi_map(0x0A) },
             in("r0") x,
             in("r1") y,
             lateout("r0") result,
+            lateout("r1") _,
             lateout("r2") _,
             lateout("r3") _
         );
@@ -139,6 +416,463 @@ pub fn arc_tan2(x: i16, y: i32) -> i16 {
     result
 }
 
+/// The square root of a fixed point number using the Sqrt BIOS call, which is
+/// faster than [`Num::sqrt`]. The result is rounded down to a multiple of
+/// 1/2<sup>N/2</sup>, so it is less precise than `N` bits.
+///
+/// # Panics
+///
+/// Panics if `N` is odd, or if `n` is negative.
+#[must_use]
+pub fn sqrt_fixed<const N: usize>(n: Num<i32, N>) -> Num<i32, N> {
+    assert_eq!(N % 2, 0, "N must be even to be able to square root");
+    assert!(n >= 0.into(), "sqrt is only valid for positive numbers");
+
+    // the square root of the raw value has half as many fractional bits
+    Num::from_raw(sqrt(n.to_raw()) << (N / 2))
+}
+
+/// The angle of the point (x, y) from the positive x axis in revolutions, from
+/// 0 up to 1, using the ArcTan2 BIOS call. This matches [`Num::atan2`] but is
+/// faster.
+///
+/// The BIOS works out the angle with 16 bits of precision, which is accurate
+/// to within about 1/1000 of a revolution. Large coordinates are scaled down to
+/// fit in 16 bits first, which loses precision for points very close to an
+/// axis.
+#[must_use]
+pub fn arc_tan2_fixed<const N: usize>(y: Num<i32, N>, x: Num<i32, N>) -> Num<i32, N> {
+    let (mut x, mut y) = (x.to_raw(), y.to_raw());
+    while x != i32::from(x as i16) || y != i32::from(y as i16) {
+        x >>= 1;
+        y >>= 1;
+    }
+
+    let angle = i32::from(arc_tan2(x as i16, y as i16));
+    if N >= 16 {
+        Num::from_raw(angle << (N - 16))
+    } else {
+        Num::from_raw(angle >> (16 - N))
+    }
+}
+
+/// Copies `src` into `dest` using the CpuSet BIOS call.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths, or are longer than 2 million
+/// words.
+pub fn cpu_copy(src: &[u32], dest: &mut [u32]) {
+    let control = cpu_set_length(src.len(), dest.len()) | CPU_SET_32_BIT;
+    unsafe { cpu_set(src.as_ptr().cast(), dest.as_mut_ptr().cast(), control) };
+}
+
+/// Sets every word of `dest` to `value` using the CpuSet BIOS call.
+///
+/// # Panics
+///
+/// Panics if `dest` is longer than 2 million words.
+pub fn cpu_fill(dest: &mut [u32], value: u32) {
+    let control = cpu_set_length(dest.len(), dest.len()) | CPU_SET_32_BIT | CPU_SET_FILL;
+    let value: *const u32 = &value;
+    unsafe { cpu_set(value.cast(), dest.as_mut_ptr().cast(), control) };
+}
+
+/// Copies `src` into `dest` 2 bytes at a time using the CpuSet BIOS call, which
+/// is needed for memory which can't be written a byte at a time, such as video
+/// memory.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths, or are longer than 2 million
+/// halfwords.
+pub fn cpu_copy_u16(src: &[u16], dest: &mut [u16]) {
+    let control = cpu_set_length(src.len(), dest.len());
+    unsafe { cpu_set(src.as_ptr(), dest.as_mut_ptr(), control) };
+}
+
+/// Sets every halfword of `dest` to `value` using the CpuSet BIOS call.
+///
+/// # Panics
+///
+/// Panics if `dest` is longer than 2 million halfwords.
+pub fn cpu_fill_u16(dest: &mut [u16], value: u16) {
+    let control = cpu_set_length(dest.len(), dest.len()) | CPU_SET_FILL;
+    unsafe { cpu_set(&value, dest.as_mut_ptr(), control) };
+}
+
+const CPU_SET_FILL: u32 = 1 << 24;
+const CPU_SET_32_BIT: u32 = 1 << 26;
+
+fn cpu_set_length(src: usize, dest: usize) -> u32 {
+    assert_eq!(
+        src, dest,
+        "source and destination must have the same length"
+    );
+    assert!(src < 1 << 21, "CpuSet can copy at most 2 million units");
+
+    src as u32
+}
+
+unsafe fn cpu_set(src: *const u16, dest: *mut u16, control: u32) {
+    asm!(
+        "swi {SWI}",
+        SWI = const { swi_map(0x0B) },
+        inlateout("r0") src => _,
+        inlateout("r1") dest => _,
+        inlateout("r2") control => _,
+        lateout("r3") _,
+    );
+}
+
+/// Copies `src` into `dest` using the CpuFastSet BIOS call, which copies 32
+/// bytes at a time. This is faster than `copy_from_slice` for large buffers
+/// which are a multiple of 32 bytes long, such as tiles.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths, or their length isn't a
+/// multiple of 8 words.
+pub fn cpu_fast_copy(src: &[u32], dest: &mut [u32]) {
+    assert_eq!(
+        src.len(),
+        dest.len(),
+        "source and destination must have the same length"
+    );
+    assert!(src.len() % 8 == 0, "length must be a multiple of 8 words");
+
+    unsafe { cpu_fast_set(src.as_ptr(), dest.as_mut_ptr(), src.len() as u32) };
+}
+
+/// Sets every word of `dest` to `value` using the CpuFastSet BIOS call, which
+/// writes 32 bytes at a time.
+///
+/// # Panics
+///
+/// Panics if the length of `dest` isn't a multiple of 8 words.
+pub fn cpu_fast_fill(dest: &mut [u32], value: u32) {
+    assert!(dest.len() % 8 == 0, "length must be a multiple of 8 words");
+
+    let fill = 1 << 24;
+    unsafe { cpu_fast_set(&value, dest.as_mut_ptr(), dest.len() as u32 | fill) };
+}
+
+unsafe fn cpu_fast_set(src: *const u32, dest: *mut u32, control: u32) {
+    asm!(
+        "swi {SWI}",
+        SWI = const { swi_map(0x0C) },
+        inlateout("r0") src => _,
+        inlateout("r1") dest => _,
+        inlateout("r2") control => _,
+        lateout("r3") _,
+    );
+}
+
+/// Decompresses data compressed in the GBA BIOS's LZ77 or run length encoded
+/// formats, such as the output of `gbalzss` or `grit`, using the BIOS.
+///
+/// The compressed data is checked before it is given to the BIOS, so that
+/// invalid data can't make it read or write outside of the buffers.
+///
+/// # Panics
+///
+/// Panics if the data isn't valid LZ77 or run length encoded data.
+#[must_use]
+pub fn decompress(compressed: &[u32]) -> Vec<u8> {
+    let bytes = unsafe {
+        core::slice::from_raw_parts(compressed.as_ptr().cast::<u8>(), compressed.len() * 4)
+    };
+    let header = *compressed
+        .first()
+        .expect("compressed data must have a header");
+    let size = (header >> 8) as usize;
+
+    // the BIOS can write a whole block past the end before it notices that it
+    // has finished
+    let mut output = Vec::with_capacity(size + 0x84);
+    let src = compressed.as_ptr();
+    let dest = output.as_mut_ptr();
+
+    unsafe {
+        match header & 0xF0 {
+            0x10 => {
+                assert!(valid_lz77(bytes, size), "invalid LZ77 compressed data");
+                lz77_uncompress_wram(src, dest);
+            }
+            0x30 => {
+                assert!(
+                    valid_run_length(bytes, size),
+                    "invalid run length encoded data"
+                );
+                rl_uncompress_wram(src, dest);
+            }
+            _ => panic!("unsupported compression type {:#x}", header & 0xF0),
+        }
+
+        output.set_len(size);
+    }
+
+    output
+}
+
+fn valid_lz77(data: &[u8], size: usize) -> bool {
+    let (mut read, mut written) = (4, 0);
+
+    while written < size {
+        let Some(&flags) = data.get(read) else {
+            return false;
+        };
+        read += 1;
+
+        for bit in (0..8).rev() {
+            if written >= size {
+                break;
+            }
+
+            if flags & (1 << bit) == 0 {
+                read += 1;
+                written += 1;
+            } else {
+                let Some(block) = data.get(read..read + 2) else {
+                    return false;
+                };
+                let distance = ((usize::from(block[0]) & 0xF) << 8 | usize::from(block[1])) + 1;
+                if distance > written {
+                    return false;
+                }
+
+                read += 2;
+                written += usize::from(block[0] >> 4) + 3;
+            }
+        }
+    }
+
+    read <= data.len()
+}
+
+fn valid_run_length(data: &[u8], size: usize) -> bool {
+    let (mut read, mut written) = (4, 0);
+
+    while written < size {
+        let Some(&flag) = data.get(read) else {
+            return false;
+        };
+        let length = usize::from(flag & 0x7F);
+
+        if flag & 0x80 == 0 {
+            read += length + 2;
+            written += length + 1;
+        } else {
+            read += 2;
+            written += length + 3;
+        }
+    }
+
+    read <= data.len()
+}
+
+/// Decompresses LZ77 compressed data in the GBA BIOS format, writing a byte at
+/// a time so it can't be used for video RAM. The decompressed size is read
+/// from the header of the compressed data. [`decompress`] is a safe version.
+///
+/// # Safety
+///
+/// `src` must point to valid, 4 byte aligned LZ77 compressed data and `dest`
+/// must point to enough memory to hold the decompressed output.
+pub unsafe fn lz77_uncompress_wram(src: *const u32, dest: *mut u8) {
+    asm!(
+        "swi {SWI}",
+        SWI = const { swi_map(0x11) },
+        in("r0") src,
+        in("r1") dest,
+
+        clobber_abi("C")
+    );
+}
+
+/// Decompresses run length encoded data in the GBA BIOS format, writing a byte
+/// at a time so it can't be used for video RAM. The decompressed size is read
+/// from the header of the compressed data. [`decompress`] is a safe version.
+///
+/// # Safety
+///
+/// `src` must point to valid, 4 byte aligned run length encoded data and
+/// `dest` must point to enough memory to hold the decompressed output.
+pub unsafe fn rl_uncompress_wram(src: *const u32, dest: *mut u8) {
+    asm!(
+        "swi {SWI}",
+        SWI = const { swi_map(0x14) },
+        in("r0") src,
+        in("r1") dest,
+
+        clobber_abi("C")
+    );
+}
+
+/// Decompresses Huffman compressed data in the GBA BIOS format. The
+/// decompressed size is read from the header of the compressed data, and the
+/// output is written 4 bytes at a time.
+///
+/// # Safety
+///
+/// `src` must point to valid, 4 byte aligned Huffman compressed data and
+/// `dest` must point to enough 4 byte aligned memory to hold the decompressed
+/// output rounded up to a multiple of 4 bytes.
+pub unsafe fn huffman_uncompress(src: *const u32, dest: *mut u32) {
+    asm!(
+        "swi {SWI}",
+        SWI = const { swi_map(0x13) },
+        in("r0") src,
+        in("r1") dest,
+
+        clobber_abi("C")
+    );
+}
+
+/// Spreads out each `src_bits` wide value in `src` to be `dest_bits` wide in
+/// `dest` using the BitUnPack BIOS call, for example to turn a 1 bit per pixel
+/// font into 4 bit per pixel tiles. `offset` is added to every value, or only
+/// to the values which aren't zero if `offset_zero` is false.
+///
+/// # Panics
+///
+/// Panics if the widths aren't powers of two with `src_bits` at most 8 and
+/// `dest_bits` at most 32, if `dest_bits` is smaller than `src_bits`, if `src`
+/// is longer than 65535 bytes or if `dest` is too small to hold the output.
+pub fn bit_unpack(
+    src: &[u8],
+    src_bits: u8,
+    dest: &mut [u32],
+    dest_bits: u8,
+    offset: u32,
+    offset_zero: bool,
+) {
+    #[repr(C)]
+    struct UnpackInfo {
+        length: u16,
+        src_bits: u8,
+        dest_bits: u8,
+        offset: u32,
+    }
+
+    assert!(
+        [1, 2, 4, 8].contains(&src_bits) && [1, 2, 4, 8, 16, 32].contains(&dest_bits),
+        "bit widths must be powers of two"
+    );
+    assert!(dest_bits >= src_bits, "values can't be packed more tightly");
+    assert!(offset < 1 << 31, "the offset must fit in 31 bits");
+
+    let length = u16::try_from(src.len()).expect("BitUnPack can unpack at most 65535 bytes");
+    let output_bits = src.len() * 8 / usize::from(src_bits) * usize::from(dest_bits);
+    assert!(
+        dest.len() * 32 >= output_bits,
+        "the destination is too small for the unpacked data"
+    );
+
+    let info = UnpackInfo {
+        length,
+        src_bits,
+        dest_bits,
+        offset: offset | (u32::from(offset_zero) << 31),
+    };
+
+    unsafe {
+        asm!(
+            "swi {SWI}",
+            SWI = const { swi_map(0x10) },
+            in("r0") src.as_ptr(),
+            in("r1") dest.as_mut_ptr(),
+            in("r2") &info as *const UnpackInfo,
+
+            clobber_abi("C")
+        );
+    }
+}
+
+/// Decompresses LZ77 compressed data in the GBA BIOS format into video RAM.
+/// The decompressed size is read from the header of the compressed data.
+///
+/// # Safety
+///
+/// `src` must point to valid, 4 byte aligned LZ77 compressed data and `dest`
+/// must point to enough 2 byte aligned memory to hold the decompressed output.
+pub unsafe fn lz77_uncompress_vram(src: *const u32, dest: *mut u16) {
+    asm!(
+        "swi {SWI}",
+        SWI = const { swi_map(0x12) },
+        in("r0") src,
+        in("r1") dest,
+
+        clobber_abi("C")
+    );
+}
+
+/// Decompresses run length encoded data in the GBA BIOS format into video RAM.
+/// The decompressed size is read from the header of the compressed data.
+///
+/// # Safety
+///
+/// `src` must point to valid, 4 byte aligned run length encoded data and
+/// `dest` must point to enough 2 byte aligned memory to hold the decompressed
+/// output.
+pub unsafe fn rl_uncompress_vram(src: *const u32, dest: *mut u16) {
+    asm!(
+        "swi {SWI}",
+        SWI = const { swi_map(0x15) },
+        in("r0") src,
+        in("r1") dest,
+
+        clobber_abi("C")
+    );
+}
+
+/// The parameters of the MultiBoot BIOS call, which are filled in from the
+/// handshake with the consoles being sent to.
+#[repr(C)]
+#[derive(Default)]
+pub(crate) struct MultiBootParameters {
+    pub reserved1: [u32; 5],
+    pub handshake_data: u8,
+    pub padding: u8,
+    pub handshake_timeout: u16,
+    pub probe_count: u8,
+    pub client_data: [u8; 3],
+    pub palette_data: u8,
+    pub response_bit: u8,
+    pub client_bit: u8,
+    pub reserved2: u8,
+    pub boot_start: usize,
+    pub boot_end: usize,
+    pub master: usize,
+    pub reserved3: [usize; 3],
+    pub system_work: [u32; 4],
+    pub send_flag: u8,
+    pub probe_target_bit: u8,
+    pub check_wait: u8,
+    pub server_type: u8,
+}
+
+/// Sends a program to other consoles once the multiboot handshake has been
+/// done, returning 0 if it was sent successfully. `mode` is 1 for multiplayer
+/// mode at 115200 baud.
+///
+/// # Safety
+///
+/// The parameters must point to the program after its header, which must
+/// stay readable until the call returns.
+pub(crate) unsafe fn multi_boot(parameters: &MultiBootParameters, mode: u32) -> u32 {
+    let result: u32;
+    asm!(
+        "swi {SWI}",
+        SWI = const { swi_map(0x25) },
+        inlateout("r0") parameters as *const MultiBootParameters => result,
+        in("r1") mode,
+
+        clobber_abi("C")
+    );
+    result
+}
+
 /// `rotation` is in revolutions. It is hard to create the rotation, usually
 /// you'll go in from a larger sized type.
 #[must_use]
@@ -205,4 +939,140 @@ mod tests {
         let matrix = aff.to_affine_matrix();
         assert_eq!(matrix, AffineMatrix::identity());
     }
+
+    #[test_case]
+    fn bios_maths_matches_the_fixed_point_versions(_gba: &mut crate::Gba) {
+        for i in 0..100 {
+            let n: Num<i32, 8> = Num::new(i) / 3;
+            assert!((sqrt_fixed(n) - n.sqrt()).abs() <= Num::new(1) / 16);
+        }
+
+        for y in -4..=4 {
+            for x in -4..=4 {
+                let (y, x): (Num<i32, 12>, Num<i32, 12>) = (y.into(), x.into());
+                if x == 0.into() && y == 0.into() {
+                    continue;
+                }
+
+                let difference = (arc_tan2_fixed(y, x) - y.atan2(x)).abs();
+                let difference = difference.min(Num::new(1) - difference);
+                assert!(difference < Num::new(1) / 256);
+            }
+        }
+
+        assert_eq!(arc_tan2(0x100, 0), 0);
+        assert_eq!(arc_tan2(0, 0x100), 0x4000);
+    }
+
+    #[test_case]
+    fn register_ram_reset_clears_what_it_is_asked_to(_gba: &mut crate::Gba) {
+        let palette: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0500_0002) };
+        let oam: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0700_0000) };
+
+        palette.set(0x1234);
+        oam.set(0x0200);
+        register_ram_reset(ResetFlags::PALETTE);
+
+        assert_eq!(palette.get(), 0);
+        assert_eq!(oam.get(), 0x0200);
+
+        register_ram_reset(ResetFlags::OAM);
+        assert_eq!(oam.get(), 0);
+
+        assert!(!is_multiboot());
+    }
+
+    #[test_case]
+    fn waiting_for_vblank_enables_the_interrupt(_gba: &mut crate::Gba) {
+        for _ in 0..2 {
+            wait_for_vblank();
+        }
+
+        sound_bias(false);
+        assert_eq!(crate::sound::bias_level(), 0);
+        sound_bias(true);
+        assert_eq!(crate::sound::bias_level(), crate::sound::DEFAULT_BIAS_LEVEL);
+    }
+
+    #[test_case]
+    fn division_matches_rust(_gba: &mut crate::Gba) {
+        for (numerator, denominator) in [(7, 2), (-7, 2), (7, -2), (0, 5), (i32::MAX, 3)] {
+            let expected = (
+                numerator / denominator,
+                numerator % denominator,
+                (numerator / denominator).abs(),
+            );
+            assert_eq!(div(numerator, denominator), expected);
+            assert_eq!(div_arm(numerator, denominator), expected);
+        }
+
+        assert_eq!(arc_tan(0), 0);
+    }
+
+    #[test_case]
+    fn cpu_set_copies_and_fills(_gba: &mut crate::Gba) {
+        let source: [u32; 5] = core::array::from_fn(|i| i as u32 * 0x0101_0101);
+        let mut dest = [0; 5];
+        cpu_copy(&source, &mut dest);
+        assert_eq!(dest, source);
+
+        cpu_fill(&mut dest[2..], 7);
+        assert_eq!(dest, [source[0], source[1], 7, 7, 7]);
+
+        let mut halfwords = [0u16; 3];
+        cpu_copy_u16(&[1, 2, 3], &mut halfwords);
+        assert_eq!(halfwords, [1, 2, 3]);
+        cpu_fill_u16(&mut halfwords[1..], 9);
+        assert_eq!(halfwords, [1, 9, 9]);
+    }
+
+    #[test_case]
+    fn compressed_data_is_decompressed(_gba: &mut crate::Gba) {
+        // "ab" followed by a copy of the last 2 bytes 3 times
+        let lz77 = [
+            u32::from_le_bytes([0x10, 8, 0, 0]),
+            u32::from_le_bytes([0b0010_0000, b'a', b'b', 0x30]),
+            u32::from_le_bytes([0x01, 0, 0, 0]),
+        ];
+        assert_eq!(decompress(&lz77), b"abababab");
+
+        // 5 'x's then the bytes "yz"
+        let run_length = [
+            u32::from_le_bytes([0x30, 7, 0, 0]),
+            u32::from_le_bytes([0x82, b'x', 0x01, b'y']),
+            u32::from_le_bytes([b'z', 0, 0, 0]),
+        ];
+        assert_eq!(decompress(&run_length), b"xxxxxyz");
+
+        // referring back before the start of the output
+        let bytes = [0x10, 8, 0, 0, 0b1000_0000, 0x50, 0x05, 0];
+        assert!(!valid_lz77(&bytes, 8));
+        assert!(!valid_run_length(&[0x30, 8, 0, 0, 0x82], 8));
+    }
+
+    #[test_case]
+    fn bits_are_unpacked(_gba: &mut crate::Gba) {
+        let mut dest = [0; 2];
+        bit_unpack(&[0b0000_0101], 1, &mut dest, 4, 0, false);
+        assert_eq!(dest, [0x0000_0101, 0]);
+
+        bit_unpack(&[0b0000_0101], 1, &mut dest, 4, 2, false);
+        assert_eq!(dest, [0x0000_0303, 0]);
+
+        bit_unpack(&[0b0000_0101], 1, &mut dest, 4, 2, true);
+        assert_eq!(dest, [0x2222_2323, 0]);
+    }
+
+    #[test_case]
+    fn cpu_fast_set_copies_and_fills(_gba: &mut crate::Gba) {
+        let source: [u32; 16] = core::array::from_fn(|i| i as u32 * 0x0101_0101);
+        let mut dest = [0; 16];
+
+        cpu_fast_copy(&source, &mut dest);
+        assert_eq!(dest, source);
+
+        cpu_fast_fill(&mut dest[8..], 0xdead_beef);
+        assert_eq!(dest[..8], source[..8]);
+        assert_eq!(dest[8..], [0xdead_beef; 8]);
+    }
 }
@@ -1,3 +1,42 @@
+#![deny(missing_docs)]
+//! The Game Boy Advance has 4 hardware timers which count up at a fraction of
+//! the CPU clock speed, or count the overflows of the previous timer when
+//! cascaded, and can trigger an interrupt when they overflow.
+//!
+//! Timers 0 and 1 are used by the [mixer](crate::sound::mixer) while it
+//! exists, and timers 2 and 3 are available from
+//! [`TimerController::timers`](crate::timer::TimerController::timers). Each
+//! timer can only have one [`Timer`](crate::timer::Timer) handle at a time,
+//! which is released when the handle is dropped. Trying to use a timer which
+//! is already in use panics rather than letting two parts of the game
+//! silently fight over it, and
+//! [`try_reserve`](crate::timer::TimerController::try_reserve) can be used to
+//! check first.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::timer::Divider;
+//!
+//! # fn foo(gba: &mut agb::Gba) {
+//! let mut timer = gba.timers.timers().timer2;
+//! timer
+//!     .set_divider(Divider::Divider1024)
+//!     .set_overflow_amount(16384)
+//!     .set_enabled(true);
+//!
+//! // called once per second
+//! let _handler = timer.add_overflow_handler(|_| {});
+//! # }
+//! ```
+
+use core::cell::Cell;
+use core::ops::Mul;
+
+use bare_metal::{CriticalSection, Mutex};
+
+use crate::fixnum::Num;
+use crate::interrupt::{add_interrupt_handler, free, Interrupt, InterruptHandler};
 use crate::memory_mapped::MemoryMapped;
 
 const fn timer_data(timer: usize) -> MemoryMapped<u16> {
@@ -8,15 +47,19 @@ const fn timer_control(timer: usize) -> MemoryMapped<u16> {
     unsafe { MemoryMapped::new(0x0400_0102 + 4 * timer) }
 }
 
-#[derive(Clone, Copy)]
+/// A bit for each timer which has a [`Timer`] handle
+static RESERVED_TIMERS: Mutex<Cell<u8>> = Mutex::new(Cell::new(0));
+
+/// How many CPU cycles make up one tick of a timer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Divider {
-    // 16.78MHz or 59.59ns
+    /// 16.78MHz or 59.59ns
     Divider1,
-    // 262.21kHz or 3.815us
+    /// 262.21kHz or 3.815us
     Divider64,
-    // 65.536kHz or 15.26us
+    /// 65.536kHz or 15.26us
     Divider256,
-    // 16.384kHz or 61.04us
+    /// 16.384kHz or 61.04us
     Divider1024,
 }
 
@@ -33,14 +76,45 @@ impl Divider {
     }
 }
 
+/// One of the 4 hardware timers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerId {
+    /// Timer 0, which is used by the mixer.
+    Timer0,
+    /// Timer 1, which is used by the mixer.
+    Timer1,
+    /// Timer 2.
+    Timer2,
+    /// Timer 3.
+    Timer3,
+}
+
+impl TimerId {
+    const fn number(self) -> u16 {
+        match self {
+            TimerId::Timer0 => 0,
+            TimerId::Timer1 => 1,
+            TimerId::Timer2 => 2,
+            TimerId::Timer3 => 3,
+        }
+    }
+}
+
+/// A handle to one of the hardware timers. Only one handle can exist for
+/// each timer at a time, and the timer is free to be used by something else
+/// once this is dropped.
 #[non_exhaustive]
 pub struct Timer {
     timer_number: u16,
+    reserved: bool,
 }
 
+/// Timers 2 and 3, which aren't used by anything in agb itself.
 #[non_exhaustive]
 pub struct Timers {
+    /// Timer 2.
     pub timer2: Timer,
+    /// Timer 3.
     pub timer3: Timer,
 }
 
@@ -53,49 +127,123 @@ impl Timers {
     }
 }
 
+fn try_reserve(timer_number: u16) -> bool {
+    free(|cs| {
+        let reserved = RESERVED_TIMERS.borrow(cs);
+        let bit = 1 << timer_number;
+
+        if reserved.get() & bit != 0 {
+            return false;
+        }
+
+        reserved.set(reserved.get() | bit);
+        true
+    })
+}
+
 impl Timer {
+    /// Creates the handle for the timer and resets it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timer is already in use.
     pub(crate) unsafe fn new(timer_number: u16) -> Self {
-        let new_timer = Self { timer_number };
+        assert!(
+            try_reserve(timer_number),
+            "timer {} is already in use",
+            timer_number
+        );
+
+        Self::from_reserved(timer_number)
+    }
+
+    /// Reserves timer 3, or timer 2 if timer 3 is in use, or returns `None` if
+    /// they both are. Timers 0 and 1 are never picked, as they are the only
+    /// timers which can drive sound.
+    pub(crate) fn try_reserve_spare() -> Option<Self> {
+        (2..4)
+            .rev()
+            .find(|&timer_number| try_reserve(timer_number))
+            .map(|timer_number| unsafe { Self::from_reserved(timer_number) })
+    }
+
+    /// Creates the handle for a timer which has just been reserved and resets
+    /// it.
+    unsafe fn from_reserved(timer_number: u16) -> Self {
+        let new_timer = Self {
+            timer_number,
+            reserved: true,
+        };
         new_timer.data_register().set(0);
         new_timer.control_register().set(0);
 
         new_timer
     }
 
+    /// Creates another handle for a timer which is already reserved, without
+    /// resetting it. The original handle keeps the reservation.
+    pub(crate) unsafe fn new_unreserved(timer_number: u16) -> Self {
+        Self {
+            timer_number,
+            reserved: false,
+        }
+    }
+
+    /// Sets the timer to overflow after counting `n` ticks. This takes effect
+    /// when the timer is enabled or next overflows.
     pub fn set_overflow_amount(&mut self, n: u16) -> &mut Self {
         let count_up_value = 0u16.wrapping_sub(n);
         self.data_register().set(count_up_value);
         self
     }
 
+    /// The current count of the timer.
     #[must_use]
     pub fn value(&self) -> u16 {
         self.data_register().get()
     }
 
+    /// Sets how many CPU cycles make up each tick of the timer. This has no
+    /// effect while the timer is cascaded.
     pub fn set_divider(&mut self, divider: Divider) -> &mut Self {
         self.control_register().set_bits(divider.as_bits(), 2, 0);
         self
     }
 
+    /// Starts or stops the timer. Starting the timer resets its count to the
+    /// amount set by [`set_overflow_amount`](Timer::set_overflow_amount).
     pub fn set_enabled(&mut self, enabled: bool) -> &mut Self {
         let bit = u16::from(enabled);
         self.control_register().set_bits(bit, 1, 7);
         self
     }
 
+    /// Makes the timer tick each time the previous timer overflows rather than
+    /// at a rate set by its divider. This lets timers be chained together to
+    /// count for longer. Cascading has no effect on timer 0.
     pub fn set_cascade(&mut self, cascade: bool) -> &mut Self {
         let bit = u16::from(cascade);
         self.control_register().set_bits(bit, 1, 2);
         self
     }
 
+    /// Sets whether the timer triggers its interrupt when it overflows.
     pub fn set_interrupt(&mut self, interrupt: bool) -> &mut Self {
         let bit = u16::from(interrupt);
         self.control_register().set_bits(bit, 1, 6);
         self
     }
 
+    /// Enables the overflow interrupt and calls `handler` every time the timer
+    /// overflows while the returned handler is alive.
+    pub fn add_overflow_handler<'a>(
+        &mut self,
+        handler: impl Fn(CriticalSection) + Send + Sync + 'a,
+    ) -> InterruptHandler<'a> {
+        self.set_interrupt(true);
+        add_interrupt_handler(self.interrupt(), handler)
+    }
+
     fn data_register(&self) -> MemoryMapped<u16> {
         timer_data(self.timer_number())
     }
@@ -108,9 +256,21 @@ impl Timer {
         self.timer_number as usize
     }
 
+    /// Which of the hardware timers this is.
     #[must_use]
-    pub fn interrupt(&self) -> crate::interrupt::Interrupt {
-        use crate::interrupt::Interrupt;
+    pub fn id(&self) -> TimerId {
+        match self.timer_number {
+            0 => TimerId::Timer0,
+            1 => TimerId::Timer1,
+            2 => TimerId::Timer2,
+            3 => TimerId::Timer3,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The interrupt which is triggered when this timer overflows.
+    #[must_use]
+    pub fn interrupt(&self) -> Interrupt {
         match self.timer_number {
             0 => Interrupt::Timer0,
             1 => Interrupt::Timer1,
@@ -121,6 +281,18 @@ impl Timer {
     }
 }
 
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if self.reserved {
+            free(|cs| {
+                let reserved = RESERVED_TIMERS.borrow(cs);
+                reserved.set(reserved.get() & !(1 << self.timer_number));
+            });
+        }
+    }
+}
+
+/// Manages access to the timers.
 #[non_exhaustive]
 pub struct TimerController {}
 
@@ -129,7 +301,327 @@ impl TimerController {
         Self {}
     }
 
+    /// Gets timers 2 and 3.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either of them is already in use.
     pub fn timers(&mut self) -> Timers {
         unsafe { Timers::new() }
     }
+
+    /// Gets any of the timers, or `None` if it is already in use. Timers 0
+    /// and 1 are in use while a [mixer](crate::sound::mixer::Mixer) exists,
+    /// and using them stops a mixer being created until the timer is dropped.
+    pub fn try_reserve(&mut self, timer: TimerId) -> Option<Timer> {
+        let timer_number = timer.number();
+        try_reserve(timer_number).then(|| unsafe { Timer::from_reserved(timer_number) })
+    }
+
+    /// Whether the timer currently has a [`Timer`] handle.
+    #[must_use]
+    pub fn is_reserved(&self, timer: TimerId) -> bool {
+        free(|cs| RESERVED_TIMERS.borrow(cs).get() & (1 << timer.number()) != 0)
+    }
+}
+
+/// The number of CPU cycles in each tick of a [`Stopwatch`]
+const STOPWATCH_CYCLES_PER_TICK: u64 = 1024;
+/// The number of CPU cycles in each frame
+pub const CYCLES_PER_FRAME: u32 = 280_896;
+/// The CPU clock speed
+pub(crate) const CYCLES_PER_SECOND: u32 = 1 << 24;
+
+/// Measures the time between laps using a single timer, which makes it easy
+/// to move things at the same speed whatever the frame rate is, and to notice
+/// frames which took too long.
+///
+/// The stopwatch ticks 16384 times a second, so it can measure laps of up to
+/// 4 seconds. Longer laps wrap around, so call [`lap`](Stopwatch::lap) at
+/// least that often, or [`restart`](Stopwatch::restart) it after pausing the
+/// game.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{fixnum::Num, timer::Stopwatch};
+///
+/// # fn foo(gba: &mut agb::Gba) {
+/// let vblank = agb::interrupt::VBlank::get();
+/// let mut stopwatch = Stopwatch::new(gba.timers.timers().timer2);
+/// let mut x: Num<u32, 8> = 0.into();
+///
+/// loop {
+///     let delta = stopwatch.lap().frames();
+///     if delta > 2.into() {
+///         agb::println!("lag spike of {} frames", delta);
+///     }
+///
+///     // move at 1 pixel per frame even if frames are dropped
+///     x += delta;
+///
+///     vblank.wait_for_vblank();
+/// }
+/// # }
+/// ```
+pub struct Stopwatch {
+    timer: Timer,
+    lap_start: u16,
+}
+
+/// An amount of time measured by a [`Stopwatch`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Elapsed {
+    ticks: u16,
+}
+
+impl Elapsed {
+    /// The number of ticks, of which there are 16384 every second.
+    #[must_use]
+    pub fn ticks(self) -> u16 {
+        self.ticks
+    }
+
+    /// The elapsed time as a number of frames.
+    #[must_use]
+    pub fn frames(self) -> Num<u32, 8> {
+        Num::from_raw(
+            (u64::from(self.ticks) * STOPWATCH_CYCLES_PER_TICK * 256 / u64::from(CYCLES_PER_FRAME))
+                as u32,
+        )
+    }
+
+    /// The elapsed time in milliseconds.
+    #[must_use]
+    pub fn milliseconds(self) -> Num<u32, 8> {
+        Num::from_raw(
+            (u64::from(self.ticks) * STOPWATCH_CYCLES_PER_TICK * 1000 * 256
+                / u64::from(CYCLES_PER_SECOND)) as u32,
+        )
+    }
+}
+
+impl Stopwatch {
+    /// Starts a stopwatch which uses the given timer.
+    #[must_use]
+    pub fn new(mut timer: Timer) -> Self {
+        timer
+            .set_cascade(false)
+            .set_divider(Divider::Divider1024)
+            .set_overflow_amount(0)
+            .set_enabled(true);
+
+        Self {
+            lap_start: timer.value(),
+            timer,
+        }
+    }
+
+    /// The time since the last lap, without starting a new one.
+    #[must_use]
+    pub fn elapsed(&self) -> Elapsed {
+        Elapsed {
+            ticks: self.timer.value().wrapping_sub(self.lap_start),
+        }
+    }
+
+    /// Returns the time since the last lap, or since the stopwatch was
+    /// started, and starts a new lap.
+    pub fn lap(&mut self) -> Elapsed {
+        let now = self.timer.value();
+        let elapsed = Elapsed {
+            ticks: now.wrapping_sub(self.lap_start),
+        };
+
+        self.lap_start = now;
+        elapsed
+    }
+
+    /// Starts a new lap without returning the time of the last one.
+    pub fn restart(&mut self) {
+        self.lap_start = self.timer.value();
+    }
+
+    /// Starts a new lap and returns how long the last one took as a
+    /// [`DeltaTime`] for moving things with [`move_by`]. Laps longer than
+    /// `max_frames` are treated as lasting `max_frames`, so that a lag spike
+    /// slows the game down rather than making things jump across the screen.
+    pub fn delta_time(&mut self, max_frames: u8) -> DeltaTime {
+        DeltaTime::from_elapsed(self.lap(), max_frames)
+    }
+
+    /// Stops the stopwatch, giving back its timer.
+    #[must_use]
+    pub fn into_timer(mut self) -> Timer {
+        self.timer.set_enabled(false);
+        self.timer
+    }
+}
+
+/// The time between two frames in frames, which is 1 when the game is keeping
+/// up and more when frames have been missed. Made by
+/// [`Stopwatch::delta_time`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DeltaTime {
+    frames: Num<i32, 8>,
+}
+
+impl DeltaTime {
+    /// Exactly one frame, for when the time hasn't been measured such as on the
+    /// first frame.
+    pub const ONE_FRAME: Self = Self {
+        frames: Num::from_raw(1 << 8),
+    };
+
+    /// The time which `elapsed` measures, but no more than `max_frames`.
+    #[must_use]
+    pub fn from_elapsed(elapsed: Elapsed, max_frames: u8) -> Self {
+        let frames = Num::from_raw(elapsed.frames().to_raw() as i32);
+
+        Self {
+            frames: frames.min(Num::new(max_frames.into())),
+        }
+    }
+
+    /// The number of frames.
+    #[must_use]
+    pub fn frames(self) -> Num<i32, 8> {
+        self.frames
+    }
+}
+
+/// How far something moving at `velocity` per frame goes in `dt`. The
+/// velocity can be a fixed point number or a vector of them.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::fixnum::{num, Num, Vector2D};
+/// use agb::timer::{move_by, Stopwatch};
+///
+/// # fn foo(gba: &mut agb::Gba) {
+/// let vblank = agb::interrupt::VBlank::get();
+/// let mut stopwatch = Stopwatch::new(gba.timers.timers().timer2);
+///
+/// let mut position: Vector2D<Num<i32, 8>> = (0, 80).into();
+/// let velocity: Vector2D<Num<i32, 8>> = (num!(1.5), 0.into()).into();
+///
+/// loop {
+///     // catch up on at most 4 frames at once
+///     let dt = stopwatch.delta_time(4);
+///     position += move_by(velocity, dt);
+///
+///     vblank.wait_for_vblank();
+/// }
+/// # }
+/// ```
+#[must_use]
+pub fn move_by<T>(velocity: T, dt: DeltaTime) -> T
+where
+    T: Mul<Num<i32, 8>, Output = T>,
+{
+    velocity * dt.frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixnum::Vector2D;
+
+    #[test_case]
+    fn delta_time_is_clamped(_gba: &mut crate::Gba) {
+        let one_frame = Elapsed {
+            ticks: (u64::from(CYCLES_PER_FRAME) / STOPWATCH_CYCLES_PER_TICK) as u16,
+        };
+        let dt = DeltaTime::from_elapsed(one_frame, 4);
+        assert!((dt.frames() - 1).abs() < Num::new(1) / 16);
+
+        let lag_spike = Elapsed { ticks: 10_000 };
+        assert_eq!(DeltaTime::from_elapsed(lag_spike, 4).frames(), 4.into());
+
+        let velocity: Vector2D<Num<i32, 8>> = (2, -1).into();
+        assert_eq!(
+            move_by(velocity, DeltaTime::from_elapsed(lag_spike, 3)),
+            (6, -3).into()
+        );
+        assert_eq!(move_by(velocity, DeltaTime::ONE_FRAME), velocity);
+    }
+
+    #[test_case]
+    fn timers_can_only_be_reserved_once(gba: &mut crate::Gba) {
+        let timer = gba.timers.try_reserve(TimerId::Timer3).unwrap();
+        assert_eq!(timer.id(), TimerId::Timer3);
+        assert!(gba.timers.is_reserved(TimerId::Timer3));
+        assert!(gba.timers.try_reserve(TimerId::Timer3).is_none());
+
+        drop(timer);
+        assert!(!gba.timers.is_reserved(TimerId::Timer3));
+
+        let mixer = gba.mixer.mixer(crate::sound::mixer::Frequency::Hz10512);
+        assert!(gba.timers.try_reserve(TimerId::Timer0).is_none());
+        assert!(gba.timers.try_reserve(TimerId::Timer1).is_none());
+
+        drop(mixer);
+        assert!(gba.timers.try_reserve(TimerId::Timer0).is_some());
+    }
+
+    #[test_case]
+    fn spare_timers_leave_the_sound_timers_alone(gba: &mut crate::Gba) {
+        let timer3 = Timer::try_reserve_spare().unwrap();
+        let timer2 = Timer::try_reserve_spare().unwrap();
+        assert_eq!(timer3.id(), TimerId::Timer3);
+        assert_eq!(timer2.id(), TimerId::Timer2);
+
+        assert!(Timer::try_reserve_spare().is_none());
+        assert!(!gba.timers.is_reserved(TimerId::Timer0));
+        assert!(!gba.timers.is_reserved(TimerId::Timer1));
+    }
+
+    #[test_case]
+    fn stopwatch_measures_frames(gba: &mut crate::Gba) {
+        let vblank = crate::interrupt::VBlank::get();
+        let mut stopwatch = Stopwatch::new(gba.timers.timers().timer2);
+
+        vblank.wait_for_vblank();
+        stopwatch.restart();
+        for _ in 0..3 {
+            vblank.wait_for_vblank();
+        }
+
+        let frames = stopwatch.lap().frames();
+        assert!(frames > Num::new(2) + Num::new(3) / 4 && frames < Num::new(3) + Num::new(1) / 4);
+        assert!(stopwatch.elapsed() < Elapsed { ticks: 100 });
+
+        assert_eq!(Elapsed { ticks: 16384 }.milliseconds(), 1000.into());
+        assert_eq!(Elapsed { ticks: 16384 }.frames().floor(), 59);
+
+        let _ = stopwatch.into_timer();
+    }
+
+    #[test_case]
+    fn overflow_handlers_are_called(gba: &mut crate::Gba) {
+        let count = Mutex::new(Cell::new(0));
+
+        let mut timer = gba.timers.timers().timer2;
+        timer
+            .set_divider(Divider::Divider1)
+            .set_overflow_amount(1000)
+            .set_enabled(true);
+
+        let handler = timer.add_overflow_handler(|cs| {
+            let count = count.borrow(cs);
+            count.set(count.get() + 1);
+        });
+
+        // the timer overflows every 1000 cycles, so hundreds of times in a
+        // whole frame
+        let vblank = crate::interrupt::VBlank::get();
+        vblank.wait_for_vblank();
+        vblank.wait_for_vblank();
+
+        assert!(free(|cs| count.borrow(cs).get()) >= 3);
+
+        drop(handler);
+        timer.set_enabled(false);
+    }
 }
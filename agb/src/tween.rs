@@ -0,0 +1,292 @@
+#![deny(missing_docs)]
+//! Easing curves and tweens, for moving something smoothly from one value to
+//! another over a number of frames.
+//!
+//! An [`Easing`] curve changes how the movement speeds up and slows down, and a
+//! [`Tween`] keeps track of how far through the movement it is. Tweens work
+//! with anything which implements [`Lerp`], which includes fixed point
+//! numbers, integers and vectors of either.
+//!
+//! ```rust,no_run
+//! # #![no_std]
+//! # #![no_main]
+//! use agb::fixnum::Vector2D;
+//! use agb::tween::{Easing, Tween};
+//!
+//! # fn foo() {
+//! let vblank = agb::interrupt::VBlank::get();
+//!
+//! // slide a menu in from the left of the screen over half a second
+//! let mut menu_position: Tween<Vector2D<i32>> =
+//!     Tween::new((-100, 20).into(), (20, 20).into(), 30, Easing::CubicOut);
+//!
+//! while !menu_position.is_finished() {
+//!     let position = menu_position.update();
+//!     // draw the menu at position
+//!     vblank.wait_for_vblank();
+//! }
+//! # }
+//! ```
+
+use crate::fixnum::{num, Num, Vector2D};
+
+/// How far through a tween something is, from 0 at the start to 1 at the end.
+pub type Progress = Num<i32, 8>;
+
+/// Something which can be interpolated between two values.
+pub trait Lerp: Copy {
+    /// The value `t` of the way from `self` to `to`, where `t` of 0 gives
+    /// `self` and `t` of 1 gives `to`. Easing curves which overshoot give
+    /// values of `t` outside of 0 to 1.
+    #[must_use]
+    fn lerp(self, to: Self, t: Progress) -> Self;
+}
+
+impl<const N: usize> Lerp for Num<i32, N> {
+    fn lerp(self, to: Self, t: Progress) -> Self {
+        self + Num::from_raw(
+            ((i64::from((to - self).to_raw()) * i64::from(t.to_raw())) >> 8) as i32,
+        )
+    }
+}
+
+impl Lerp for i32 {
+    fn lerp(self, to: Self, t: Progress) -> Self {
+        self + (((i64::from(to - self) * i64::from(t.to_raw())) >> 8) as i32)
+    }
+}
+
+impl<T: Lerp + crate::fixnum::Number> Lerp for Vector2D<T> {
+    fn lerp(self, to: Self, t: Progress) -> Self {
+        Vector2D::new(self.x.lerp(to.x, t), self.y.lerp(to.y, t))
+    }
+}
+
+/// A curve which changes how a tween speeds up and slows down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    /// Moves at the same speed all the way.
+    Linear,
+    /// Starts slowly and speeds up.
+    QuadIn,
+    /// Starts quickly and slows down.
+    QuadOut,
+    /// Starts slowly, speeds up, then slows down at the end.
+    QuadInOut,
+    /// Like [`QuadIn`](Easing::QuadIn), but starts more slowly.
+    CubicIn,
+    /// Like [`QuadOut`](Easing::QuadOut), but ends more slowly.
+    CubicOut,
+    /// Like [`QuadInOut`](Easing::QuadInOut), but more pronounced.
+    CubicInOut,
+    /// Bounces away from the start a few times before leaving it.
+    BounceIn,
+    /// Bounces off the end a few times before settling there, like a dropped
+    /// ball.
+    BounceOut,
+}
+
+impl Easing {
+    /// How far through the movement something is when `t` of the time has
+    /// passed, where both start at 0 and end at 1. Values of `t` outside of 0
+    /// to 1 are clamped.
+    #[must_use]
+    pub fn apply(self, t: Progress) -> Progress {
+        let one: Progress = 1.into();
+        let t = t.clamp(0.into(), one);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => one - (one - t) * (one - t),
+            Easing::QuadInOut => {
+                if t < num!(0.5) {
+                    t * t * 2
+                } else {
+                    let u = one - t;
+                    one - u * u * 2
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => {
+                let u = one - t;
+                one - u * u * u
+            }
+            Easing::CubicInOut => {
+                if t < num!(0.5) {
+                    t * t * t * 4
+                } else {
+                    let u = one - t;
+                    one - u * u * u * 4
+                }
+            }
+            Easing::BounceIn => one - bounce_out(one - t),
+            Easing::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+/// The bounce curve from Robert Penner's easing functions, made of 4
+/// parabolas each a quarter of the height of the last.
+fn bounce_out(t: Progress) -> Progress {
+    let steepness: Progress = num!(7.5625);
+    let width: Progress = num!(2.75);
+
+    let (offset, height): (Progress, Progress) = if t < Progress::new(1) / width {
+        (0.into(), 0.into())
+    } else if t < Progress::new(2) / width {
+        (num!(1.5) / width, num!(0.75))
+    } else if t < num!(2.5) / width {
+        (num!(2.25) / width, num!(0.9375))
+    } else {
+        (num!(2.625) / width, num!(0.984375))
+    };
+
+    let t = t - offset;
+    steepness * t * t + height
+}
+
+/// A movement from one value to another over a fixed number of frames, which
+/// is advanced once per frame by [`update`](Tween::update).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    easing: Easing,
+    frame: u16,
+    frames: u16,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Creates a tween which moves from `from` to `to` in the given number of
+    /// frames. A tween lasting 0 frames is immediately at the end.
+    #[must_use]
+    pub fn new(from: T, to: T, frames: u16, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            easing,
+            frame: 0,
+            frames,
+        }
+    }
+
+    /// Moves on by a frame, and returns the new value. Once the tween has
+    /// finished this keeps returning the end value.
+    pub fn update(&mut self) -> T {
+        if self.frame < self.frames {
+            self.frame += 1;
+        }
+
+        self.value()
+    }
+
+    /// The value at the current frame.
+    #[must_use]
+    pub fn value(&self) -> T {
+        self.from.lerp(self.to, self.easing.apply(self.progress()))
+    }
+
+    /// How much of the time has passed, from 0 to 1. This ignores the easing
+    /// curve.
+    #[must_use]
+    pub fn progress(&self) -> Progress {
+        if self.frames == 0 {
+            return 1.into();
+        }
+
+        Progress::new(self.frame.into()) / i32::from(self.frames)
+    }
+
+    /// Whether the tween has reached the end value.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.frames
+    }
+
+    /// Goes back to the start value.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+    }
+
+    /// Starts a new tween from the current value to `to`, for when the target
+    /// changes part way through, such as a camera following the player.
+    pub fn retarget(&mut self, to: T, frames: u16) {
+        self.from = self.value();
+        self.to = to;
+        self.frame = 0;
+        self.frames = frames;
+    }
+
+    /// The value the tween ends at.
+    #[must_use]
+    pub fn target(&self) -> T {
+        self.to
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 9] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+    ];
+
+    #[test_case]
+    fn easing_curves_start_at_0_and_end_at_1(_gba: &mut crate::Gba) {
+        for easing in ALL {
+            let start = easing.apply(0.into());
+            let end = easing.apply(1.into());
+
+            assert!(
+                start.abs() <= num!(0.02),
+                "{:?} starts at {}",
+                easing,
+                start
+            );
+            assert!(
+                (end - 1).abs() <= num!(0.02),
+                "{:?} ends at {}",
+                easing,
+                end
+            );
+        }
+
+        assert_eq!(Easing::QuadIn.apply(num!(0.5)), num!(0.25));
+        assert_eq!(Easing::QuadOut.apply(num!(0.5)), num!(0.75));
+        assert_eq!(Easing::CubicInOut.apply(num!(0.5)), num!(0.5));
+    }
+
+    #[test_case]
+    fn tweens_reach_their_target(_gba: &mut crate::Gba) {
+        let mut tween = Tween::new(10, 20, 4, Easing::Linear);
+        assert_eq!(tween.value(), 10);
+
+        assert_eq!(tween.update(), 12);
+        assert_eq!(tween.update(), 15);
+        assert_eq!(tween.update(), 17);
+        assert!(!tween.is_finished());
+        assert_eq!(tween.update(), 20);
+        assert!(tween.is_finished());
+        assert_eq!(tween.update(), 20);
+
+        tween.retarget(0, 2);
+        assert_eq!(tween.update(), 10);
+        assert_eq!(tween.update(), 0);
+
+        let mut vector: Tween<Vector2D<Num<i32, 8>>> =
+            Tween::new((0, 0).into(), (8, -8).into(), 2, Easing::Linear);
+        assert_eq!(vector.update(), (4, -4).into());
+
+        assert!(Tween::new(1, 2, 0, Easing::BounceOut).is_finished());
+    }
+}
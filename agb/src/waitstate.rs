@@ -0,0 +1,123 @@
+#![deny(missing_docs)]
+//! Configuring how long the CPU waits when accessing the cartridge and
+//! external working ram.
+//!
+//! Reading from the cartridge's ROM takes a number of extra cycles, called
+//! wait states, which are set in the waitstate control register. agb sets
+//! this to [`WaitStates::STANDARD`] at startup, which is the setting used by
+//! almost all commercial games, and turns on the prefetch buffer which reads
+//! ahead while the CPU is busy. Together these make code running from ROM
+//! much faster than the console's default setting.
+//!
+//! Games running on flash carts can try [`WaitStates::FAST_ROM`], and
+//! [`set_ewram_overclock`] makes external working ram faster on the consoles
+//! which support it.
+
+use crate::memory_mapped::MemoryMapped;
+
+pub(crate) const WAITSTATE_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_0204) };
+const INTERNAL_MEMORY_CONTROL: MemoryMapped<u32> = unsafe { MemoryMapped::new(0x0400_0800) };
+
+const PREFETCH: u16 = 1 << 14;
+
+/// A setting for the waitstate control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WaitStates {
+    bits: u16,
+}
+
+impl WaitStates {
+    /// 3 cycles for the first access to ROM and 1 for each access after it,
+    /// 8 cycles for SRAM, and the prefetch buffer turned on. This works with
+    /// every cartridge and is what agb uses by default.
+    pub const STANDARD: Self = Self::from_bits(0x4317);
+
+    /// 2 cycles for the first access to ROM and 1 for each access after it,
+    /// with the prefetch buffer turned on. This is the fastest setting, which
+    /// most flash carts support but many real cartridges don't.
+    pub const FAST_ROM: Self = Self::from_bits(0x431b);
+
+    /// The slowest setting, with 4 cycles for the first access to ROM and 2 for
+    /// each access after it and no prefetch, which is what the BIOS leaves the
+    /// register as.
+    pub const BIOS_DEFAULT: Self = Self::from_bits(0);
+
+    /// A raw value for the
+    /// [waitstate control register](https://mgba-emu.github.io/gbatek/#4000204h---waitcnt---waitstate-control-rw).
+    #[must_use]
+    pub const fn from_bits(bits: u16) -> Self {
+        Self { bits }
+    }
+
+    /// The raw value of the waitstate control register.
+    #[must_use]
+    pub const fn bits(self) -> u16 {
+        self.bits
+    }
+
+    /// The current setting.
+    #[must_use]
+    pub fn current() -> Self {
+        Self::from_bits(WAITSTATE_CONTROL.get())
+    }
+
+    /// Whether the prefetch buffer is turned on.
+    #[must_use]
+    pub const fn prefetch(self) -> bool {
+        self.bits & PREFETCH != 0
+    }
+
+    /// The same setting with the prefetch buffer turned on or off.
+    #[must_use]
+    pub const fn with_prefetch(self, prefetch: bool) -> Self {
+        if prefetch {
+            Self::from_bits(self.bits | PREFETCH)
+        } else {
+            Self::from_bits(self.bits & !PREFETCH)
+        }
+    }
+
+    /// Writes the setting to the waitstate control register.
+    pub fn apply(self) {
+        WAITSTATE_CONTROL.set(self.bits);
+    }
+}
+
+impl Default for WaitStates {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Sets external working ram to take 2 cycles for each 16-bit access rather
+/// than 3, which makes it noticeably faster.
+///
+/// This works on the Game Boy Advance and Game Boy Advance SP, but the
+/// Nintendo DS crashes with it turned on, so it is best offered as an
+/// option rather than always being turned on.
+pub fn set_ewram_overclock(enabled: bool) {
+    let wait_bits = 0x0f00_0000;
+    let wait = if enabled { 0x0e00_0000 } else { 0x0d00_0000 };
+
+    INTERNAL_MEMORY_CONTROL.set((INTERNAL_MEMORY_CONTROL.get() & !wait_bits) | wait);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn standard_wait_states_are_set_at_startup(_gba: &mut crate::Gba) {
+        assert_eq!(WaitStates::current(), WaitStates::STANDARD);
+        assert!(WaitStates::STANDARD.prefetch());
+        assert_eq!(
+            WaitStates::BIOS_DEFAULT.with_prefetch(true).bits(),
+            PREFETCH
+        );
+
+        WaitStates::STANDARD.with_prefetch(false).apply();
+        assert!(!WaitStates::current().prefetch());
+
+        WaitStates::STANDARD.apply();
+    }
+}
@@ -29,7 +29,7 @@ const BALL: &Tag = GRAPHICS.tags().get("Ball");
 // The main function must take 0 arguments and never return. The agb::entry decorator
 // ensures that everything is in order. `agb` will call this after setting up the stack
 // and interrupt handlers correctly.
-#[agb::entry]
+#[agb::entry(title = "PONG", game_code = "PONG", maker_code = "GC")]
 fn main(mut gba: agb::Gba) -> ! {
     // Get the OAM manager
     let object = gba.display.object.get();
@@ -226,7 +226,7 @@ fn main(mut gba: agb::Gba) -> ! {
     }
 }
 
-#[agb::entry]
+#[agb::entry(title = "HYPERSPACE", game_code = "HYPE", maker_code = "GC")]
 fn entry(mut gba: agb::Gba) -> ! {
     main(gba)
 }
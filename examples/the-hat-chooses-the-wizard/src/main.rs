@@ -779,7 +779,7 @@ impl<'a, 'b> PlayingLevel<'a, 'b> {
     }
 }
 
-#[agb::entry]
+#[agb::entry(title = "HATWIZARD", game_code = "HATW", maker_code = "GC")]
 fn agb_main(mut gba: agb::Gba) -> ! {
     main(gba);
 }
@@ -1,15 +1,17 @@
-use agb::sound::mixer::{Mixer, SoundChannel};
+use agb::sound::mixer::{Mixer, SoundChannel, SoundData};
 
 mod music_data {
+    use super::SoundData;
+
     // From the open game art page:
     //
     // USING THE LOOPED VERSION:
     // 1. Play the intro.
     // 2. When the intro reaches approximately 11.080 seconds, trigger the main loop and let the intro finish underneath it.
     // 3. Re-trigger the main loop every time it reaches 1 minute 26.080 seconds, and let the old instance finish underneath the new one.
-    pub const INTRO_MUSIC: &[u8] =
+    pub const INTRO_MUSIC: SoundData =
         agb::include_wav!("sfx/Otto Halmén - Sylvan Waltz (loop intro).wav");
-    pub const LOOP: &[u8] = agb::include_wav!("sfx/Otto Halmén - Sylvan Waltz (loop main).wav");
+    pub const LOOP: SoundData = agb::include_wav!("sfx/Otto Halmén - Sylvan Waltz (loop main).wav");
 
     // These are based on the instructions above and a frame rate of 59.73Hz
     pub const TRIGGER_MUSIC_POINT: i32 = 662;
@@ -17,24 +19,26 @@ mod music_data {
 }
 
 mod effects {
-    const WOOSH1: &[u8] = agb::include_wav!("sfx/woosh1.wav");
-    const WOOSH2: &[u8] = agb::include_wav!("sfx/woosh2.wav");
-    const WOOSH3: &[u8] = agb::include_wav!("sfx/woosh3.wav");
+    use super::SoundData;
+
+    const WOOSH1: SoundData = agb::include_wav!("sfx/woosh1.wav");
+    const WOOSH2: SoundData = agb::include_wav!("sfx/woosh2.wav");
+    const WOOSH3: SoundData = agb::include_wav!("sfx/woosh3.wav");
 
-    pub const WHOOSHES: &[&[u8]] = &[WOOSH1, WOOSH2, WOOSH3];
+    pub const WHOOSHES: &[SoundData] = &[WOOSH1, WOOSH2, WOOSH3];
 
-    pub const CATCH: &[u8] = agb::include_wav!("sfx/catch.wav");
+    pub const CATCH: SoundData = agb::include_wav!("sfx/catch.wav");
 
-    pub const JUMP: &[u8] = agb::include_wav!("sfx/jump.wav");
-    pub const LAND: &[u8] = agb::include_wav!("sfx/land.wav");
+    pub const JUMP: SoundData = agb::include_wav!("sfx/jump.wav");
+    pub const LAND: SoundData = agb::include_wav!("sfx/land.wav");
 
-    pub const SLIME_JUMP: &[u8] = agb::include_wav!("sfx/slime-jump.wav");
-    pub const SLIME_DEATH: &[u8] = agb::include_wav!("sfx/slime-death.wav");
+    pub const SLIME_JUMP: SoundData = agb::include_wav!("sfx/slime-jump.wav");
+    pub const SLIME_DEATH: SoundData = agb::include_wav!("sfx/slime-death.wav");
 
-    pub const SNAIL_EMERGE: &[u8] = agb::include_wav!("sfx/snail-emerge.wav");
-    pub const SNAIL_RETREAT: &[u8] = agb::include_wav!("sfx/snail-retreat.wav");
-    pub const SNAIL_HAT_BOUNCE: &[u8] = agb::include_wav!("sfx/snail-hat-bounce.wav");
-    pub const SNAIL_DEATH: &[u8] = agb::include_wav!("sfx/snail-death.wav");
+    pub const SNAIL_EMERGE: SoundData = agb::include_wav!("sfx/snail-emerge.wav");
+    pub const SNAIL_RETREAT: SoundData = agb::include_wav!("sfx/snail-retreat.wav");
+    pub const SNAIL_HAT_BOUNCE: SoundData = agb::include_wav!("sfx/snail-hat-bounce.wav");
+    pub const SNAIL_DEATH: SoundData = agb::include_wav!("sfx/snail-death.wav");
 }
 
 pub struct MusicBox {
@@ -118,7 +122,7 @@ impl<'a> SfxPlayer<'a> {
         self.mixer.play_sound(SoundChannel::new(effects::LAND));
     }
 
-    fn play_random(&mut self, effect: &[&'static [u8]]) {
+    fn play_random(&mut self, effect: &[SoundData]) {
         self.mixer.play_sound(SoundChannel::new(
             effect[(self.frame as usize) % effect.len()],
         ));
@@ -2306,7 +2306,7 @@ mod tilemap {
     include!(concat!(env!("OUT_DIR"), "/tilemap.rs"));
 }
 
-#[agb::entry]
+#[agb::entry(title = "PURPLENIGHT", game_code = "PURP", maker_code = "GC")]
 fn main(mut gba: agb::Gba) -> ! {
     loop {
         game_with_level(&mut gba);
@@ -134,8 +134,31 @@ fn rgba_to_gba_to_rgba(c: [u8; 4]) -> [u8; 4] {
     n
 }
 
+/// Set to save the screen as the expected image rather than comparing them
+const UPDATE_SCREENSHOTS_VARIABLE: &str = "AGB_UPDATE_SCREENSHOTS";
+
 fn check_image_match(image_path: &str, video_buffer: &VideoBuffer) -> Result<(), Error> {
-    let expected_image = Reader::open(image_path)?.decode()?;
+    if std::env::var_os(UPDATE_SCREENSHOTS_VARIABLE).is_some() {
+        if let Some(folder) = Path::new(image_path).parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+
+        video_buffer_to_image(video_buffer)
+            .save_with_format(image_path, image::ImageFormat::Png)?;
+        println!("[screenshot saved to {}]", image_path);
+        return Ok(());
+    }
+
+    let expected_image = Reader::open(image_path)
+        .map_err(|e| {
+            anyhow!(
+                "could not open {}, run with {} set to create it: {}",
+                image_path,
+                UPDATE_SCREENSHOTS_VARIABLE,
+                e
+            )
+        })?
+        .decode()?;
     let expected = expected_image.to_rgba8();
 
     let (buf_dim_x, buf_dim_y) = video_buffer.get_size();
@@ -144,6 +167,9 @@ fn check_image_match(image_path: &str, video_buffer: &VideoBuffer) -> Result<(),
         return Err(anyhow!("image sizes do not match"));
     }
 
+    let mut first_difference = None;
+    let mut differences = 0;
+
     for y in 0..buf_dim_y {
         for x in 0..buf_dim_x {
             let video_pixel = video_buffer.get_pixel(x, y);
@@ -151,20 +177,28 @@ fn check_image_match(image_path: &str, video_buffer: &VideoBuffer) -> Result<(),
             let video_pixel = gba_colour_to_rgba(video_pixel);
             let image_pixel = rgba_to_gba_to_rgba(image_pixel.0);
             if image_pixel != video_pixel {
-                let output_file = write_video_buffer(video_buffer);
-
-                return Err(anyhow!(
-                    "images do not match, actual output written to {}",
-                    output_file
-                ));
+                first_difference.get_or_insert((x, y));
+                differences += 1;
             }
         }
     }
 
+    if let Some((x, y)) = first_difference {
+        let output_file = write_video_buffer(video_buffer, image_path);
+
+        return Err(anyhow!(
+            "images do not match in {} pixels starting at ({}, {}), actual output written to {}",
+            differences,
+            x,
+            y,
+            output_file
+        ));
+    }
+
     Ok(())
 }
 
-fn write_video_buffer(video_buffer: &VideoBuffer) -> String {
+fn video_buffer_to_image(video_buffer: &VideoBuffer) -> image::DynamicImage {
     let (width, height) = video_buffer.get_size();
     let mut output_image = image::DynamicImage::new_rgba8(width, height);
 
@@ -177,8 +211,19 @@ fn write_video_buffer(video_buffer: &VideoBuffer) -> String {
         }
     }
 
+    output_image
+}
+
+fn write_video_buffer(video_buffer: &VideoBuffer, expected_path: &str) -> String {
+    let output_image = video_buffer_to_image(video_buffer);
+
+    // named after the expected image so that failures in different tests don't overwrite each other
+    let name = Path::new(expected_path)
+        .file_stem()
+        .map_or("output".into(), |stem| stem.to_string_lossy());
+
     let output_folder = std::env::temp_dir();
-    let output_file = "mgba-test-runner-output.png"; // TODO make this random
+    let output_file = format!("mgba-test-runner-{}.png", name);
 
     let output_file = output_folder.join(output_file);
     let _ = output_image.save_with_format(&output_file, image::ImageFormat::Png);
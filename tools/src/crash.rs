@@ -0,0 +1,257 @@
+use clap::{value_parser, Arg, ArgMatches};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// these must match agb/src/backtrace.rs
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const BUILD_ID_CHARACTERS: usize = 4;
+const BUILD_ID_STRIDE: usize = 256;
+const BUILD_ID_SKIP: usize = 0x100;
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+const IWRAM_START: u32 = 0x0300_0000;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidCode,
+    ReadingElf,
+    InvalidElf,
+}
+
+pub fn command() -> clap::Command {
+    clap::Command::new("crash")
+        .about(
+            "Turns the crash code shown when an agb game panics back into function names and lines",
+        )
+        .arg(
+            Arg::new("code")
+                .required(true)
+                .help("The crash code, such as NF6Y-PNMK-J17"),
+        )
+        .arg(
+            Arg::new("elf")
+                .long("elf")
+                .required(true)
+                .value_parser(value_parser!(PathBuf))
+                .help("The elf file of the build of the game which crashed"),
+        )
+        .arg(
+            Arg::new("addr2line")
+                .long("addr2line")
+                .default_value("arm-none-eabi-addr2line")
+                .help("The addr2line program used to find the function and line of each address"),
+        )
+}
+
+pub fn crash(matches: &ArgMatches) -> Result<(), Error> {
+    let code = matches.get_one::<String>("code").expect("defined by clap");
+    let elf_path = matches.get_one::<PathBuf>("elf").expect("defined by clap");
+    let addr2line = matches
+        .get_one::<String>("addr2line")
+        .expect("defined by clap");
+
+    let crash = CrashCode::decode(code).ok_or(Error::InvalidCode)?;
+    let elf = fs::read(elf_path).map_err(|_| Error::ReadingElf)?;
+    let (text_address, text) = text_section(&elf).ok_or(Error::InvalidElf)?;
+
+    let build_id = build_id(text);
+    if build_id != crash.build_id {
+        println!(
+            "Warning: the crash happened in a different build of the game (build id {:05x}, but the elf file is {build_id:05x})",
+            crash.build_id
+        );
+    }
+
+    for frame in crash.frames {
+        let return_address = match frame {
+            Frame::Text(offset) => text_address + offset,
+            Frame::Iwram(offset) => IWRAM_START + offset,
+        };
+
+        // point at the call rather than the instruction after it
+        let call = if return_address & 1 == 1 {
+            (return_address & !1) - 2
+        } else {
+            return_address - 4
+        };
+
+        match symbolise(addr2line, elf_path, call) {
+            Some(location) => println!("{call:#010x} {location}"),
+            None => println!("{call:#010x}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Frame {
+    Text(u32),
+    Iwram(u32),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct CrashCode {
+    build_id: u32,
+    frames: Vec<Frame>,
+}
+
+impl CrashCode {
+    fn decode(code: &str) -> Option<Self> {
+        let mut digits = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| match c.to_ascii_uppercase() {
+                'O' => Some(0),
+                'I' | 'L' => Some(1),
+                c => ALPHABET
+                    .iter()
+                    .position(|&digit| char::from(digit) == c)
+                    .map(|digit| digit as u32),
+            });
+
+        let mut build_id = 0;
+        for _ in 0..BUILD_ID_CHARACTERS {
+            build_id = build_id << 5 | digits.next()??;
+        }
+
+        let mut frames = vec![];
+        while let Some(digit) = digits.next() {
+            let mut digit = digit?;
+            let mut value = 0;
+            let mut shift = 0;
+
+            loop {
+                value |= (digit & 15).checked_shl(shift)?;
+                shift += 4;
+
+                if digit & 16 == 0 {
+                    break;
+                }
+                digit = digits.next()??;
+            }
+
+            frames.push(if value & 1 == 0 {
+                Frame::Text(value >> 1)
+            } else {
+                Frame::Iwram(value >> 1)
+            });
+        }
+
+        Some(CrashCode { build_id, frames })
+    }
+}
+
+/// The address and contents of the `.text` section of a 32 bit little endian
+/// elf file.
+fn text_section(elf: &[u8]) -> Option<(u32, &[u8])> {
+    let u16_at = |offset: usize| {
+        Some(u16::from_le_bytes(
+            elf.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_at = |offset: usize| {
+        Some(u32::from_le_bytes(
+            elf.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    if elf.get(..6)? != b"\x7fELF\x01\x01" {
+        return None;
+    }
+
+    let section_headers = u32_at(0x20)? as usize;
+    let section_header_size = u16_at(0x2e)? as usize;
+    let section_count = u16_at(0x30)? as usize;
+    let names_index = u16_at(0x32)? as usize;
+
+    let header = |index: usize| section_headers + index * section_header_size;
+    let names = u32_at(header(names_index) + 0x10)? as usize;
+
+    (0..section_count).find_map(|index| {
+        let header = header(index);
+        let name = names + u32_at(header)? as usize;
+        if elf.get(name..name + 6)? != b".text\0" {
+            return None;
+        }
+
+        let address = u32_at(header + 0xc)?;
+        let offset = u32_at(header + 0x10)? as usize;
+        let size = u32_at(header + 0x14)? as usize;
+
+        Some((address, elf.get(offset..offset + size)?))
+    })
+}
+
+fn build_id(text: &[u8]) -> u32 {
+    let mut hash = fnv1a(FNV_OFFSET_BASIS, text.len() as u32);
+    for word in text
+        .get(BUILD_ID_SKIP..)
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .step_by(BUILD_ID_STRIDE / 4)
+    {
+        hash = fnv1a(hash, u32::from_le_bytes(word.try_into().unwrap()));
+    }
+
+    (hash ^ (hash >> 20)) & 0xf_ffff
+}
+
+fn fnv1a(hash: u32, word: u32) -> u32 {
+    word.to_le_bytes().iter().fold(hash, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn symbolise(addr2line: &str, elf: &Path, address: u32) -> Option<String> {
+    let output = Command::new(addr2line)
+        .args([
+            "--functions",
+            "--demangle",
+            "--inlines",
+            "--pretty-print",
+            "--exe",
+        ])
+        .arg(elf)
+        .arg(format!("{address:#x}"))
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let location = String::from_utf8_lossy(&output.stdout);
+    Some(location.trim_end().replace('\n', "\n           "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crash_codes_are_decoded() {
+        assert_eq!(
+            CrashCode::decode("nf6y-pnmk-j17"),
+            Some(CrashCode {
+                build_id: 0xa_bcde,
+                frames: vec![Frame::Text(0x9_1a2b), Frame::Iwram(3)],
+            })
+        );
+
+        assert_eq!(CrashCode::decode("NF6Y-PNMK-J1U"), None);
+        assert_eq!(CrashCode::decode("NF6Y-P"), None);
+    }
+
+    #[test]
+    fn build_ids_sample_the_code() {
+        let mut text = vec![0; 0x300];
+        let original = build_id(&text);
+
+        text[0x10] = 1;
+        assert_eq!(build_id(&text), original);
+
+        text[0x200] = 1;
+        assert_ne!(build_id(&text), original);
+    }
+}
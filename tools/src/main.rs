@@ -1,12 +1,14 @@
 #![deny(clippy::all)]
 use clap::Command;
 
+mod crash;
 mod publish;
 mod release;
 mod utils;
 
 #[derive(Debug)]
 pub enum Error {
+    CrashError(crash::Error),
     PublishError(publish::Error),
     ReleaseError(release::Error),
 }
@@ -15,6 +17,7 @@ fn cli() -> Command {
     Command::new("Agb tools")
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .subcommand(crash::command())
         .subcommand(publish::command())
         .subcommand(release::command())
 }
@@ -23,6 +26,8 @@ fn main() {
     let matches = cli().get_matches();
 
     let result = match matches.subcommand() {
+        Some(("crash", arg_matches)) => crash::crash(arg_matches).map_err(Error::CrashError),
+
         Some(("publish", arg_matches)) => {
             publish::publish(arg_matches).map_err(Error::PublishError)
         }